@@ -0,0 +1,187 @@
+//! `boreth check-rpc`: RPC-level parity check for receipts and logs.
+//!
+//! Walks a block range on both a reference bor-geth endpoint and a boreth
+//! endpoint, comparing `eth_getBlockReceipts` per block and `eth_getLogs` per
+//! chunk of blocks. Both responses are [normalized](super::diff::normalize)
+//! first, so only differences a client could observe in the decoded values are
+//! reported. The command fails if any mismatch is found, which makes it usable
+//! as a release gate.
+
+use super::diff::{diff_values, normalize, Divergence};
+use super::rpc::{parse_quantity, RpcClient};
+use serde_json::Value;
+use url::Url;
+
+/// Arguments of `boreth check-rpc`.
+#[derive(Debug, clap::Args)]
+pub struct CheckRpcArgs {
+    /// JSON-RPC endpoint of the reference bor-geth node.
+    #[arg(long, value_name = "URL")]
+    reference: Url,
+
+    /// JSON-RPC endpoint of the boreth node under test.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    target: Url,
+
+    /// First block to check.
+    #[arg(long)]
+    from: u64,
+
+    /// Last block to check (inclusive).
+    #[arg(long)]
+    to: u64,
+
+    /// Number of blocks per `eth_getLogs` request.
+    #[arg(long, default_value_t = 100)]
+    logs_chunk: u64,
+
+    /// Maximum number of mismatching fields printed per block or chunk.
+    #[arg(long, default_value_t = 20)]
+    max_report: usize,
+}
+
+/// Compare two `eth_getBlockReceipts` results after normalization.
+pub fn diff_block_receipts(remote: &[Value], local: &[Value]) -> Vec<Divergence> {
+    let mut out = Vec::new();
+    diff_values(
+        "receipts",
+        &normalize(&Value::Array(remote.to_vec())),
+        &normalize(&Value::Array(local.to_vec())),
+        &mut out,
+    );
+    out
+}
+
+/// Compare two `eth_getLogs` results after normalization.
+///
+/// Logs are ordered by block number and log index first, since the order of
+/// `eth_getLogs` results is not part of the API contract.
+pub fn diff_logs(remote: &[Value], local: &[Value]) -> Vec<Divergence> {
+    let mut out = Vec::new();
+    diff_values("logs", &sorted_logs(remote), &sorted_logs(local), &mut out);
+    out
+}
+
+fn sorted_logs(logs: &[Value]) -> Value {
+    let mut logs: Vec<Value> = logs.iter().map(normalize).collect();
+    logs.sort_by_key(|log| (parse_quantity(&log["blockNumber"]), parse_quantity(&log["logIndex"])));
+    Value::Array(logs)
+}
+
+impl CheckRpcArgs {
+    /// Run the check.
+    pub async fn execute(self) -> eyre::Result<()> {
+        eyre::ensure!(self.from <= self.to, "--from must not be greater than --to");
+        eyre::ensure!(self.logs_chunk > 0, "--logs-chunk must be greater than zero");
+
+        let reference = RpcClient::new(self.reference.clone());
+        let target = RpcClient::new(self.target.clone());
+        let mut mismatched_blocks = 0u64;
+        let mut mismatched_chunks = 0u64;
+
+        for number in self.from..=self.to {
+            let (remote, local) =
+                tokio::try_join!(reference.block_receipts(number), target.block_receipts(number))?;
+            let remote = remote.ok_or_else(|| eyre::eyre!("reference node has no receipts for block {number}"))?;
+            let local = local.ok_or_else(|| eyre::eyre!("target node has no receipts for block {number}"))?;
+
+            let divergences = diff_block_receipts(&remote, &local);
+            if !divergences.is_empty() {
+                mismatched_blocks += 1;
+                self.report(&format!("eth_getBlockReceipts block {number}"), &divergences);
+            }
+        }
+
+        let mut start = self.from;
+        loop {
+            let end = start.saturating_add(self.logs_chunk - 1).min(self.to);
+            let (remote, local) = tokio::try_join!(reference.logs(start, end), target.logs(start, end))?;
+
+            let divergences = diff_logs(&remote, &local);
+            if !divergences.is_empty() {
+                mismatched_chunks += 1;
+                self.report(&format!("eth_getLogs blocks {start}..={end}"), &divergences);
+            }
+            if end == self.to {
+                break;
+            }
+            start = end + 1;
+        }
+
+        eyre::ensure!(
+            mismatched_blocks == 0 && mismatched_chunks == 0,
+            "{mismatched_blocks} blocks with mismatching receipts, {mismatched_chunks} eth_getLogs chunks with \
+             mismatching logs between {} and {}",
+            self.reference,
+            self.target
+        );
+        println!("receipts and logs of blocks {}..={} match {}", self.from, self.to, self.reference);
+        Ok(())
+    }
+
+    fn report(&self, what: &str, divergences: &[Divergence]) {
+        println!("{what}: {} mismatching fields", divergences.len());
+        for d in divergences.iter().take(self.max_report) {
+            println!("  {d}");
+        }
+        if divergences.len() > self.max_report {
+            println!("  ... {} more", divergences.len() - self.max_report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn log(block: &str, index: &str, data: &str) -> Value {
+        json!({
+            "address": "0x0000000000000000000000000000000000001010",
+            "topics": ["0x4dfe1bbbcf077ddc3e01291eea2d5c70c2b422b415d95645b9adcfd678cb1d63"],
+            "data": data,
+            "blockNumber": block,
+            "logIndex": index,
+            "removed": false,
+        })
+    }
+
+    #[test]
+    fn test_receipts_match_despite_representation() {
+        let geth = vec![json!({
+            "status": "0x1",
+            "gasUsed": "0x5208",
+            "contractAddress": null,
+            "to": "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+            "logs": [log("0x10", "0x0", "0x01")],
+        })];
+        let reth = vec![json!({
+            "status": "0x1",
+            "gasUsed": "0x5208",
+            "blobGasUsed": "0x0",
+            "to": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+            "logs": [log("0x10", "0x0", "0x01")],
+        })];
+        assert!(diff_block_receipts(&geth, &reth).is_empty());
+    }
+
+    #[test]
+    fn test_receipt_status_mismatch() {
+        let remote = vec![json!({ "status": "0x1" })];
+        let local = vec![json!({ "status": "0x0" })];
+        let diff = diff_block_receipts(&remote, &local);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].field, "receipts[0].status");
+    }
+
+    #[test]
+    fn test_logs_compared_in_canonical_order() {
+        let remote = vec![log("0x10", "0x0", "0x01"), log("0x11", "0x0", "0x02")];
+        let local = vec![log("0x11", "0x0", "0x02"), log("0x10", "0x0", "0x01")];
+        assert!(diff_logs(&remote, &local).is_empty());
+
+        let local = vec![log("0x10", "0x0", "0x01")];
+        let diff = diff_logs(&remote, &local);
+        assert_eq!(diff[0].field, "logs.len");
+    }
+}
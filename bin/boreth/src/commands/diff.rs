@@ -0,0 +1,165 @@
+//! Field-level comparison of JSON-RPC responses.
+
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A single mismatching field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Path of the field, e.g. `receipts[3].logs[0].data`.
+    pub field: String,
+    /// Value reported by the reference node.
+    pub remote: String,
+    /// Value reported by the local node.
+    pub local: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: remote={} local={}", self.field, self.remote, self.local)
+    }
+}
+
+/// Keys holding JSON-RPC quantities, compared by numeric value.
+const QUANTITY_KEYS: &[&str] = &[
+    "blockNumber",
+    "cumulativeGasUsed",
+    "effectiveGasPrice",
+    "gasUsed",
+    "logIndex",
+    "status",
+    "transactionIndex",
+    "type",
+];
+
+/// Keys one client emits and the other does not, with no consensus meaning.
+///
+/// - `blockTimestamp` on logs is a newer geth/reth addition.
+/// - `blobGasUsed` / `blobGasPrice` are never meaningful on Bor, which has no blobs.
+/// - `removed` is always `false` for logs of canonical blocks.
+const IGNORED_KEYS: &[&str] = &["blobGasPrice", "blobGasUsed", "blockTimestamp", "removed"];
+
+/// Rewrite `value` into a canonical form so representation differences between
+/// bor-geth and boreth do not show up as mismatches.
+///
+/// Hex strings are lowercased (checksummed addresses), quantities lose leading
+/// zeros, and `null` or [`IGNORED_KEYS`] fields are dropped.
+pub fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut out = Map::new();
+            for (key, v) in map {
+                if v.is_null() || IGNORED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let v = if QUANTITY_KEYS.contains(&key.as_str()) {
+                    normalize_quantity(v)
+                } else {
+                    normalize(v)
+                };
+                out.insert(key.clone(), v);
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        Value::String(s) if s.starts_with("0x") || s.starts_with("0X") => {
+            Value::String(s.to_ascii_lowercase())
+        }
+        other => other.clone(),
+    }
+}
+
+fn normalize_quantity(value: &Value) -> Value {
+    let parsed = match value {
+        Value::String(s) => s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .and_then(|hex| u128::from_str_radix(hex, 16).ok()),
+        Value::Number(n) => n.as_u64().map(u128::from),
+        _ => None,
+    };
+    match parsed {
+        Some(n) => Value::String(format!("{n:#x}")),
+        None => normalize(value),
+    }
+}
+
+/// Recursively compare two JSON values, reporting every differing leaf under `path`.
+pub fn diff_values(path: &str, remote: &Value, local: &Value, out: &mut Vec<Divergence>) {
+    match (remote, local) {
+        (Value::Object(r), Value::Object(l)) => {
+            let mut keys: Vec<&String> = r.keys().chain(l.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                diff_values(
+                    &child,
+                    r.get(key).unwrap_or(&Value::Null),
+                    l.get(key).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        (Value::Array(r), Value::Array(l)) => {
+            if r.len() != l.len() {
+                out.push(Divergence {
+                    field: format!("{path}.len"),
+                    remote: r.len().to_string(),
+                    local: l.len().to_string(),
+                });
+            }
+            for (i, (rv, lv)) in r.iter().zip(l).enumerate() {
+                diff_values(&format!("{path}[{i}]"), rv, lv, out);
+            }
+        }
+        (r, l) if r != l => out.push(Divergence {
+            field: path.to_string(),
+            remote: r.to_string(),
+            local: l.to_string(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_representation_differences() {
+        let geth = json!({
+            "address": "0x0000000000000000000000000000000000001001",
+            "transactionIndex": "0x01",
+            "blockNumber": 16,
+            "removed": false,
+            "contractAddress": null,
+            "data": "0x00AB",
+        });
+        let reth = json!({
+            "address": "0x0000000000000000000000000000000000001001",
+            "transactionIndex": "0x1",
+            "blockNumber": "0x10",
+            "blockTimestamp": "0x5f",
+            "data": "0x00ab",
+        });
+        assert_eq!(normalize(&geth), normalize(&reth));
+    }
+
+    #[test]
+    fn test_normalize_keeps_data_leading_zeros() {
+        assert_eq!(normalize(&json!({"data": "0x0001"})), json!({"data": "0x0001"}));
+        assert_eq!(normalize(&json!({"status": "0x01"})), json!({"status": "0x1"}));
+    }
+
+    #[test]
+    fn test_diff_values_paths() {
+        let remote = json!({"logs": [{"data": "0x01"}, {"data": "0x02"}], "status": "0x1"});
+        let local = json!({"logs": [{"data": "0x01"}], "status": "0x0"});
+        let mut out = Vec::new();
+        diff_values("receipts[0]", &remote, &local, &mut out);
+        let fields: Vec<&str> = out.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["receipts[0].logs.len", "receipts[0].status"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! `boreth debug diff-state`: account and storage diff of one block against a
+//! reference node.
+//!
+//! `replay` finds the first block whose state root diverges; this command says
+//! why. The accounts and storage slots block N touched are taken from the
+//! `prestateTracer` diff of both nodes, then the balance, nonce, code and every
+//! touched slot of each account are read from both nodes at block N. Only the
+//! values that differ are printed, and optionally written as JSON for a bug
+//! report. The command fails if any value differs.
+
+use super::diff::Divergence;
+use super::rpc::RpcClient;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use url::Url;
+
+/// Arguments of `boreth debug diff-state`.
+#[derive(Debug, clap::Args)]
+pub struct DiffStateArgs {
+    /// Block whose post-state is compared.
+    #[arg(long)]
+    block: u64,
+
+    /// JSON-RPC endpoint of the reference node, with the `debug` namespace enabled.
+    #[arg(long, value_name = "URL")]
+    other: Url,
+
+    /// JSON-RPC endpoint of the local boreth node, with the `debug` namespace enabled.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    local: Url,
+
+    /// Write the diff as JSON to this file.
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Accounts touched by a block, each with the storage slots touched.
+pub type Touched = BTreeMap<String, BTreeSet<String>>;
+
+/// Collect the accounts and slots of a `debug_traceBlockByNumber` result traced
+/// with `prestateTracer` in diff mode into `touched`.
+///
+/// Addresses and slots are lowercased, since the two nodes may checksum them.
+pub fn collect_touched(traces: &[Value], touched: &mut Touched) {
+    for trace in traces {
+        for side in ["pre", "post"] {
+            let Some(accounts) = trace["result"][side].as_object() else { continue };
+            for (address, account) in accounts {
+                let slots = touched.entry(address.to_ascii_lowercase()).or_default();
+                if let Some(storage) = account["storage"].as_object() {
+                    slots.extend(storage.keys().map(|slot| slot.to_ascii_lowercase()));
+                }
+            }
+        }
+    }
+}
+
+/// Canonical form of a quantity or storage word: lowercase hex without leading zeros.
+fn word(value: &Value) -> Value {
+    let Some(hex) = value.as_str().and_then(|s| s.strip_prefix("0x")) else {
+        return value.clone();
+    };
+    let digits = hex.trim_start_matches('0').to_ascii_lowercase();
+    Value::String(if digits.is_empty() { "0x0".to_string() } else { format!("0x{digits}") })
+}
+
+/// Compare the state of `address` as read from two nodes, each an object with
+/// `balance`, `nonce`, `code` and `storage` keyed by slot.
+pub fn diff_account(address: &str, remote: &Value, local: &Value) -> Vec<Divergence> {
+    let mut out = Vec::new();
+    let mut push = |field: String, remote: &Value, local: &Value| {
+        if remote != local {
+            out.push(Divergence { field, remote: remote.to_string(), local: local.to_string() });
+        }
+    };
+    for field in ["balance", "nonce"] {
+        push(format!("{address}.{field}"), &word(&remote[field]), &word(&local[field]));
+    }
+    push(format!("{address}.code"), &remote["code"], &local["code"]);
+
+    let empty = Map::new();
+    let remote_storage = remote["storage"].as_object().unwrap_or(&empty);
+    let local_storage = local["storage"].as_object().unwrap_or(&empty);
+    let slots: BTreeSet<&String> = remote_storage.keys().chain(local_storage.keys()).collect();
+    for slot in slots {
+        let remote = word(remote_storage.get(slot).unwrap_or(&Value::Null));
+        let local = word(local_storage.get(slot).unwrap_or(&Value::Null));
+        push(format!("{address}.storage[{slot}]"), &remote, &local);
+    }
+    out
+}
+
+/// The minimized diff written with `--output`.
+pub fn diff_json(block: u64, divergences: &[Divergence]) -> Value {
+    let fields: Vec<Value> = divergences
+        .iter()
+        .map(|d| json!({ "field": d.field, "other": d.remote, "local": d.local }))
+        .collect();
+    json!({ "block": block, "divergences": fields })
+}
+
+async fn account_state(
+    client: &RpcClient,
+    address: &str,
+    slots: &BTreeSet<String>,
+    block: u64,
+) -> eyre::Result<Value> {
+    let (balance, nonce, code) = tokio::try_join!(
+        client.balance(address, block),
+        client.nonce(address, block),
+        client.code(address, block)
+    )?;
+    let mut storage = Map::new();
+    for slot in slots {
+        storage.insert(slot.clone(), client.storage_at(address, slot, block).await?);
+    }
+    Ok(json!({ "balance": balance, "nonce": nonce, "code": code, "storage": storage }))
+}
+
+impl DiffStateArgs {
+    /// Run the comparison.
+    pub async fn execute(self) -> eyre::Result<()> {
+        let other = RpcClient::new(self.other.clone());
+        let local = RpcClient::new(self.local.clone());
+        let block = self.block;
+
+        // Either node may have executed the block differently, so touched state is
+        // the union of both.
+        let (other_traces, local_traces) =
+            tokio::try_join!(other.trace_block_prestate(block), local.trace_block_prestate(block))?;
+        let mut touched = Touched::new();
+        collect_touched(&other_traces, &mut touched);
+        collect_touched(&local_traces, &mut touched);
+
+        let mut divergences = Vec::new();
+        for (address, slots) in &touched {
+            let (remote_state, local_state) = tokio::try_join!(
+                account_state(&other, address, slots, block),
+                account_state(&local, address, slots, block)
+            )?;
+            divergences.extend(diff_account(address, &remote_state, &local_state));
+        }
+
+        if let Some(path) = &self.output {
+            std::fs::write(path, serde_json::to_vec_pretty(&diff_json(block, &divergences))?)?;
+        }
+        if divergences.is_empty() {
+            println!("block {block}: {} touched accounts match {}", touched.len(), self.other);
+            return Ok(());
+        }
+        println!("block {block}: state differs from {} (remote = other node):", self.other);
+        for d in &divergences {
+            println!("  {d}");
+        }
+        eyre::bail!("{} values of block {block} differ from {}", divergences.len(), self.other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touched_is_union_of_pre_and_post() {
+        let traces = vec![
+            json!({ "txHash": "0x01", "result": {
+                "pre": { "0xAbC1": { "balance": "0x1", "storage": { "0x01": "0x00" } } },
+                "post": { "0xabc1": { "storage": { "0x02": "0x07" } }, "0x1001": {} },
+            }}),
+            json!({ "txHash": "0x02", "result": { "pre": {}, "post": {} } }),
+        ];
+        let mut touched = Touched::new();
+        collect_touched(&traces, &mut touched);
+        assert_eq!(touched.len(), 2);
+        assert_eq!(touched["0xabc1"], BTreeSet::from(["0x01".to_string(), "0x02".to_string()]));
+        assert!(touched["0x1001"].is_empty());
+    }
+
+    #[test]
+    fn test_account_diff_is_minimal() {
+        let state = |balance: &str, slot: String| {
+            json!({
+                "balance": balance,
+                "nonce": "0x1",
+                "code": "0x",
+                "storage": { "0x01": slot, "0x02": "0x00" },
+            })
+        };
+        // geth returns storage words padded to 32 bytes.
+        let remote = state("0x0a", format!("0x{:064x}", 7));
+        let local = state("0xa", "0x8".to_string());
+        assert!(diff_account("0x1001", &remote, &remote).is_empty());
+
+        let diff = diff_account("0x1001", &remote, &local);
+        assert_eq!(
+            diff,
+            vec![Divergence {
+                field: "0x1001.storage[0x01]".to_string(),
+                remote: "\"0x7\"".to_string(),
+                local: "\"0x8\"".to_string(),
+            }]
+        );
+        assert_eq!(diff_json(16, &diff)["divergences"][0]["other"], "\"0x7\"");
+    }
+}
@@ -0,0 +1,156 @@
+//! `boreth export-blocks`: dump a block range as RLP for bor-geth's `import`.
+//!
+//! Blocks are read with `debug_getRawBlock` from any node serving it and written
+//! back to back, the format `bor import` and reth's `import` both read, so chain
+//! data can be moved from boreth to bor-geth and, with `boreth import`, back.
+//!
+//! State sync receipts are not part of a block's RLP. With `--bor-receipts` they
+//! are written next to the dump, one JSON line per block that has one, keyed by
+//! block number and hash.
+
+use super::rpc::{quantity, RpcClient};
+use alloy_consensus::Header;
+use alloy_primitives::{Bytes, B256};
+use alloy_rlp::Decodable;
+use bor_storage::receipt_key::derived_bor_tx_hash;
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Arguments of `boreth export-blocks`.
+#[derive(Debug, clap::Args)]
+pub struct ExportBlocksArgs {
+    /// JSON-RPC endpoint to read blocks from.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    rpc: Url,
+
+    /// First block to export.
+    #[arg(long)]
+    from: u64,
+
+    /// Last block to export (inclusive).
+    #[arg(long)]
+    to: u64,
+
+    /// File to write the RLP blocks to.
+    #[arg(long, value_name = "FILE")]
+    out: PathBuf,
+
+    /// Also write the state sync receipts of the blocks to `<FILE>.bor-receipts.jsonl`.
+    #[arg(long)]
+    bor_receipts: bool,
+}
+
+/// Path of the state sync receipts written next to the dump `out`.
+pub fn sidecar_path(out: &Path) -> PathBuf {
+    let mut path = out.as_os_str().to_owned();
+    path.push(".bor-receipts.jsonl");
+    path.into()
+}
+
+/// Check that `raw` is the RLP of block `number` and return the block hash.
+pub fn check_block(raw: &[u8], number: u64) -> eyre::Result<B256> {
+    let mut buf = raw;
+    let list = alloy_rlp::Header::decode(&mut buf)?;
+    eyre::ensure!(list.list, "block {number} is not an RLP list");
+    eyre::ensure!(list.payload_length == buf.len(), "block {number} does not match its RLP length");
+    let header = Header::decode(&mut buf)?;
+    eyre::ensure!(header.number == number, "requested block {number}, got {}", header.number);
+    Ok(header.hash_slow())
+}
+
+impl ExportBlocksArgs {
+    /// Export the blocks.
+    pub async fn execute(self) -> eyre::Result<()> {
+        eyre::ensure!(self.from <= self.to, "--from must not be greater than --to");
+
+        let client = RpcClient::new(self.rpc.clone());
+        let mut blocks = BufWriter::new(File::create(&self.out)?);
+        let mut receipts = if self.bor_receipts {
+            Some(BufWriter::new(File::create(sidecar_path(&self.out))?))
+        } else {
+            None
+        };
+        let (mut bytes, mut state_syncs) = (0usize, 0u64);
+
+        for number in self.from..=self.to {
+            let raw: Option<Bytes> =
+                client.call("debug_getRawBlock", json!([quantity(number)])).await?;
+            let raw = raw.filter(|raw| !raw.is_empty());
+            let raw = raw.ok_or_else(|| eyre::eyre!("block {number} not found"))?;
+            let hash = check_block(&raw, number)?;
+            blocks.write_all(&raw)?;
+            bytes += raw.len();
+
+            if let Some(out) = &mut receipts {
+                let served = client
+                    .block_receipts(number)
+                    .await?
+                    .ok_or_else(|| eyre::eyre!("no receipts for block {number}"))?;
+                let bor_tx_hash = json!(derived_bor_tx_hash(number, &hash));
+                let bor_receipt = served.into_iter().find(|r| r["transactionHash"] == bor_tx_hash);
+                if let Some(receipt) = bor_receipt {
+                    let line =
+                        json!({ "blockNumber": number, "blockHash": hash, "receipt": receipt });
+                    writeln!(out, "{line}")?;
+                    state_syncs += 1;
+                }
+            }
+
+            if number % 1000 == 0 {
+                println!("block {number}: exported");
+            }
+        }
+
+        blocks.flush()?;
+        if let Some(out) = &mut receipts {
+            out.flush()?;
+        }
+        println!(
+            "exported blocks {}..={} ({bytes} bytes) to {}",
+            self.from,
+            self.to,
+            self.out.display()
+        );
+        if self.bor_receipts {
+            println!("{state_syncs} state sync receipts in {}", sidecar_path(&self.out).display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_rlp::Encodable;
+
+    fn raw_block(number: u64) -> Vec<u8> {
+        let block = reth_ethereum_primitives::Block {
+            header: Header { number, ..Default::default() },
+            body: Default::default(),
+        };
+        let mut raw = Vec::new();
+        block.encode(&mut raw);
+        raw
+    }
+
+    #[test]
+    fn test_check_block() {
+        let raw = raw_block(42);
+        let hash = Header { number: 42, ..Default::default() }.hash_slow();
+        assert_eq!(check_block(&raw, 42).unwrap(), hash);
+        assert!(check_block(&raw, 43).is_err());
+        assert!(check_block(&raw[..raw.len() - 1], 42).is_err());
+        assert!(check_block(&[0x80], 42).is_err());
+    }
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("/tmp/blocks.rlp")),
+            PathBuf::from("/tmp/blocks.rlp.bor-receipts.jsonl")
+        );
+    }
+}
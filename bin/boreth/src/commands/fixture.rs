@@ -0,0 +1,153 @@
+//! `boreth fixture`: capture blocks to execute without their state.
+//!
+//! Each block is written to `<DIR>/<number>.json` as a [`WitnessBlock`]: its RLP and
+//! execution witness, read with `debug_getRawBlock` and `debug_executionWitness` from an
+//! archive Bor node, and the span and state sync events it commits, fetched from
+//! Heimdall. The events are those between the state receiver's `lastStateId` at the
+//! parent and at the block, so they are exactly the ones the block committed.
+//!
+//! Without block numbers, the blocks `<DIR>/corpus.toml` lists that have no fixture yet
+//! are captured; these are the golden blocks of `crates/bor-evm/tests/golden_blocks.rs`.
+
+use super::export_blocks::check_block;
+use super::replay::bor_schedule;
+use super::rpc::{quantity, RpcClient};
+use alloy_consensus::Header;
+use alloy_primitives::{Bytes, U256};
+use alloy_rlp::Decodable;
+use bor_chainspec::{BorHardforks, STATE_RECEIVER_ADDRESS};
+use bor_evm::WitnessBlock;
+use heimdall_client::{HeimdallClient, HttpHeimdallClient, StateSyncEvent};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Number of state sync events asked from Heimdall at once.
+const STATE_SYNC_PAGE: usize = 50;
+
+/// Arguments of `boreth fixture`.
+#[derive(Debug, clap::Args)]
+pub struct FixtureArgs {
+    /// JSON-RPC endpoint of an archive Bor node serving `debug_executionWitness`.
+    #[arg(long, value_name = "URL")]
+    rpc: Url,
+
+    /// Heimdall REST endpoint the spans and state sync events are fetched from.
+    #[arg(long = "bor.heimdall", value_name = "URL")]
+    heimdall: Url,
+
+    /// Directory the fixtures are written to.
+    #[arg(long, value_name = "DIR", default_value = "fixtures/mainnet")]
+    out: PathBuf,
+
+    /// Blocks to capture; the missing ones of `<DIR>/corpus.toml` if none.
+    blocks: Vec<u64>,
+}
+
+/// The blocks of a `corpus.toml`.
+#[derive(Debug, Deserialize)]
+struct Corpus {
+    block: Vec<CorpusBlock>,
+}
+
+/// A block of the corpus.
+#[derive(Debug, Deserialize)]
+struct CorpusBlock {
+    number: u64,
+    label: String,
+}
+
+/// Path of the fixture of block `number` in `dir`.
+pub fn fixture_path(dir: &Path, number: u64) -> PathBuf {
+    dir.join(format!("{number}.json"))
+}
+
+impl FixtureArgs {
+    /// Capture the blocks.
+    pub async fn execute(self) -> eyre::Result<()> {
+        let blocks = if self.blocks.is_empty() {
+            self.missing_corpus_blocks()?
+        } else {
+            self.blocks.clone()
+        };
+        let rpc = RpcClient::new(self.rpc.clone());
+        let heimdall = HttpHeimdallClient::new(self.heimdall.as_str());
+        let schedule = bor_schedule(rpc.chain_id().await?)?;
+        std::fs::create_dir_all(&self.out)?;
+
+        for number in blocks {
+            let block = capture_witness_block(&rpc, &heimdall, &schedule, number).await?;
+            let path = fixture_path(&self.out, number);
+            std::fs::write(&path, serde_json::to_vec_pretty(&block)?)?;
+            println!(
+                "block {number}: {} witness nodes, span {}, {} state syncs -> {}",
+                block.witness.state.len(),
+                block.span.as_ref().map_or("none".to_string(), |span| span.id.to_string()),
+                block.state_syncs.len(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn missing_corpus_blocks(&self) -> eyre::Result<Vec<u64>> {
+        let corpus = std::fs::read_to_string(self.out.join("corpus.toml"))?;
+        let corpus: Corpus = toml::from_str(&corpus)?;
+        Ok(corpus
+            .block
+            .into_iter()
+            .filter(|block| !fixture_path(&self.out, block.number).exists())
+            .inspect(|block| println!("capturing block {} ({})", block.number, block.label))
+            .map(|block| block.number)
+            .collect())
+    }
+}
+
+/// Capture block `number` from `rpc` and the Heimdall data of its system calls from
+/// `heimdall`.
+pub async fn capture_witness_block(
+    rpc: &RpcClient,
+    heimdall: &HttpHeimdallClient,
+    schedule: &impl BorHardforks,
+    number: u64,
+) -> eyre::Result<WitnessBlock> {
+    eyre::ensure!(number > 0, "the genesis block has no parent to execute on");
+    let raw: Option<Bytes> = rpc.call("debug_getRawBlock", json!([quantity(number)])).await?;
+    let block = raw.ok_or_else(|| eyre::eyre!("{} has no block {number}", rpc.url()))?;
+    check_block(&block, number)?;
+    let mut buf = block.as_ref();
+    alloy_rlp::Header::decode(&mut buf)?;
+    let header = Header::decode(&mut buf)?;
+    let witness = rpc.call("debug_executionWitness", json!([quantity(number)])).await?;
+
+    let span_id = schedule.bor_span_id(number);
+    let span = if schedule.bor_span_start(span_id) == number {
+        Some(heimdall.fetch_span(span_id).await?)
+    } else {
+        None
+    };
+
+    let (from, to) = (last_state_id(rpc, number - 1).await?, last_state_id(rpc, number).await?);
+    eyre::ensure!(from <= to, "lastStateId went back from {from} to {to} in block {number}");
+    let mut state_syncs: Vec<StateSyncEvent> = Vec::new();
+    while (state_syncs.len() as u64) < to - from {
+        let next = from + 1 + state_syncs.len() as u64;
+        let page = heimdall.fetch_state_sync_events(next, header.timestamp, STATE_SYNC_PAGE).await?;
+        let before = state_syncs.len();
+        state_syncs.extend(page.into_iter().filter(|event| (next..=to).contains(&event.id)));
+        eyre::ensure!(state_syncs.len() > before, "Heimdall serves no state sync event {next}");
+    }
+    for (event, id) in state_syncs.iter().zip(from + 1..) {
+        eyre::ensure!(event.id == id, "Heimdall skipped state sync event {id}");
+    }
+
+    Ok(WitnessBlock { block, witness, span, state_syncs })
+}
+
+/// The `lastStateId` of the state receiver after block `number`.
+async fn last_state_id(rpc: &RpcClient, number: u64) -> eyre::Result<u64> {
+    // `lastStateId` is the first storage slot of the state receiver.
+    let slot = rpc.storage_at(&STATE_RECEIVER_ADDRESS.to_string(), "0x0", number).await?;
+    Ok(serde_json::from_value::<U256>(slot)?.saturating_to())
+}
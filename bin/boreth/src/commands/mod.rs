@@ -0,0 +1,128 @@
+//! Bor-specific `boreth` subcommands.
+//!
+//! reth's CLI owns the command line, so these are dispatched before it: if the
+//! first argument names one of the subcommands below, the arguments are parsed
+//! and run here and reth is never started. `debug` is shared with reth: only the
+//! `debug` subcommands below are run here, the others fall through to reth.
+
+use clap::{CommandFactory, Parser, Subcommand};
+use std::ffi::OsString;
+
+pub mod check_rpc;
+pub mod diff;
+pub mod diff_state;
+pub mod export_blocks;
+pub mod fixture;
+pub mod replay;
+pub mod rewind;
+pub mod root_hash;
+pub mod rpc;
+pub mod verify_receipts;
+
+/// Bor tooling commands.
+#[derive(Debug, Parser)]
+#[command(name = "boreth")]
+struct BorCli {
+    #[command(subcommand)]
+    command: BorCommand,
+}
+
+/// The Bor subcommands.
+#[derive(Debug, Subcommand)]
+enum BorCommand {
+    /// Execute the blocks of a reference Bor node locally and compare their roots.
+    Replay(replay::ReplayArgs),
+    /// Compare receipts and logs served by boreth and a reference bor-geth node.
+    CheckRpc(check_rpc::CheckRpcArgs),
+    /// Recompute receipts roots, logs blooms and log indices of historical blocks.
+    VerifyReceipts(verify_receipts::VerifyReceiptsArgs),
+    /// Recompute the checkpoint root hash of a block range.
+    RootHash(root_hash::RootHashArgs),
+    /// Export a block range as RLP for bor-geth's `import`.
+    ExportBlocks(export_blocks::ExportBlocksArgs),
+    /// Capture blocks with their execution witness and Heimdall data as test fixtures.
+    Fixture(fixture::FixtureArgs),
+    /// Divergence triage tooling.
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommand,
+    },
+    /// Maintenance of a stopped node's chain.
+    Bor {
+        #[command(subcommand)]
+        command: ChainCommand,
+    },
+}
+
+/// The Bor `debug` subcommands.
+#[derive(Debug, Subcommand)]
+enum DebugCommand {
+    /// Compare the accounts and storage a block touched with a reference node.
+    DiffState(diff_state::DiffStateArgs),
+}
+
+/// The Bor chain maintenance subcommands.
+#[derive(Debug, Subcommand)]
+enum ChainCommand {
+    /// Unwind the chain to the latest milestone, or a given block.
+    RewindToMilestone(rewind::RewindArgs),
+}
+
+/// Returns `true` if `name`, followed by `sub` for commands with subcommands, is one
+/// of the Bor subcommands.
+fn is_bor_command(name: &str, sub: Option<&str>) -> bool {
+    let cli = BorCli::command();
+    let Some(command) = cli.find_subcommand(name) else { return false };
+    !command.has_subcommands() || sub.is_some_and(|sub| command.find_subcommand(sub).is_some())
+}
+
+/// Run a Bor subcommand if `args` names one; returns `None` to fall through to reth.
+pub fn try_run(args: Vec<OsString>) -> Option<eyre::Result<()>> {
+    let name = args.get(1)?.to_str()?;
+    if !is_bor_command(name, args.get(2).and_then(|sub| sub.to_str())) {
+        return None;
+    }
+
+    let cli = BorCli::parse_from(args);
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => return Some(Err(err.into())),
+    };
+    Some(runtime.block_on(async move {
+        match cli.command {
+            BorCommand::Replay(args) => args.execute().await,
+            BorCommand::CheckRpc(args) => args.execute().await,
+            BorCommand::VerifyReceipts(args) => args.execute().await,
+            BorCommand::RootHash(args) => args.execute().await,
+            BorCommand::ExportBlocks(args) => args.execute().await,
+            BorCommand::Fixture(args) => args.execute().await,
+            BorCommand::Debug { command: DebugCommand::DiffState(args) } => args.execute().await,
+            BorCommand::Bor { command: ChainCommand::RewindToMilestone(args) } => {
+                args.execute().await
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_bor_commands_are_intercepted() {
+        assert!(is_bor_command("replay", None));
+        assert!(is_bor_command("check-rpc", None));
+        assert!(is_bor_command("verify-receipts", None));
+        assert!(is_bor_command("root-hash", None));
+        assert!(is_bor_command("export-blocks", None));
+        assert!(is_bor_command("fixture", None));
+        assert!(is_bor_command("debug", Some("diff-state")));
+        assert!(!is_bor_command("debug", Some("execution")));
+        assert!(!is_bor_command("debug", None));
+        assert!(is_bor_command("bor", Some("rewind-to-milestone")));
+        assert!(!is_bor_command("node", None));
+        assert!(!is_bor_command("stage", None));
+        assert!(try_run(vec!["boreth".into(), "node".into()]).is_none());
+        assert!(try_run(vec!["boreth".into()]).is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! `boreth replay`: shadow-fork comparison against a reference Bor node.
+//!
+//! `replay` walks the requested range and executes every block locally, on the
+//! execution witness the reference node serves for it and the span and state sync
+//! events Heimdall served for it, as `boreth fixture` captures them. The state root,
+//! receipts root and gas used execution gives must match those the block was sealed
+//! with. The first divergence is printed together with the receipts that differ from
+//! the reference node's and the Heimdall span and sprint context of the block, and the
+//! command fails.
+
+use super::diff::Divergence;
+use super::fixture::capture_witness_block;
+use super::rpc::{quantity, RpcClient};
+use alloy_consensus::TxReceipt;
+use bor_chainspec::{
+    bor_amoy_genesis, bor_mainnet_genesis, BorChainSpec, BorConfig, BorHardforks,
+    AMOY_CHAIN_ID, MAINNET_CHAIN_ID,
+};
+use bor_evm::{
+    execute_witness_block, BorEvmConfig, BorPostExecution, BorSystemCaller, WitnessExecution,
+};
+use heimdall_client::{HeimdallClient, HttpHeimdallClient};
+use reth_chainspec::EthChainSpec;
+use reth_ethereum_primitives::Receipt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use url::Url;
+
+/// Receipt fields that must match exactly.
+const RECEIPT_FIELDS: &[&str] = &["status", "gasUsed", "cumulativeGasUsed", "logsBloom"];
+
+/// Log fields that must match exactly.
+const LOG_FIELDS: &[&str] = &["address", "topics", "data", "logIndex"];
+
+/// Arguments of `boreth replay`.
+#[derive(Debug, clap::Args)]
+pub struct ReplayArgs {
+    /// JSON-RPC endpoint of an archive reference Bor node serving `debug_executionWitness`.
+    #[arg(long, value_name = "URL")]
+    rpc: Url,
+
+    /// First block to execute.
+    #[arg(long)]
+    from: u64,
+
+    /// Last block to execute (inclusive).
+    #[arg(long)]
+    to: u64,
+
+    /// Heimdall REST endpoint the spans and state sync events are fetched from.
+    #[arg(long = "bor.heimdall", value_name = "URL")]
+    heimdall: Url,
+}
+
+fn compare_fields(prefix: &str, fields: &[&str], remote: &Value, local: &Value, out: &mut Vec<Divergence>) {
+    for field in fields {
+        let (r, l) = (&remote[*field], &local[*field]);
+        if r != l {
+            out.push(Divergence {
+                field: format!("{prefix}{field}"),
+                remote: r.to_string(),
+                local: l.to_string(),
+            });
+        }
+    }
+}
+
+/// Compare the roots and gas used of a block's header with those its execution gave.
+pub fn diff_execution(executed: &WitnessExecution) -> Vec<Divergence> {
+    let header = &executed.header;
+    let mut out = Vec::new();
+    let mut compare = |field: &str, remote: String, local: String| {
+        if remote != local {
+            out.push(Divergence { field: field.to_string(), remote, local });
+        }
+    };
+    compare("stateRoot", header.state_root.to_string(), executed.state_root.to_string());
+    compare("receiptsRoot", header.receipts_root.to_string(), executed.receipts_root.to_string());
+    compare("gasUsed", header.gas_used.to_string(), executed.gas_used.to_string());
+    out
+}
+
+/// Receipts in the shape of `eth_getBlockReceipts`, with the fields compared.
+pub fn receipts_json(receipts: &[Receipt]) -> Vec<Value> {
+    let mut previous_gas = 0;
+    let mut log_index = 0;
+    receipts
+        .iter()
+        .map(|receipt| {
+            let logs: Vec<Value> = receipt
+                .logs
+                .iter()
+                .map(|log| {
+                    log_index += 1;
+                    json!({
+                        "address": log.address,
+                        "topics": log.topics(),
+                        "data": log.data.data,
+                        "logIndex": quantity(log_index - 1),
+                    })
+                })
+                .collect();
+            let gas_used = receipt.cumulative_gas_used - previous_gas;
+            previous_gas = receipt.cumulative_gas_used;
+            json!({
+                "status": quantity(u64::from(receipt.success)),
+                "gasUsed": quantity(gas_used),
+                "cumulativeGasUsed": quantity(receipt.cumulative_gas_used),
+                "logsBloom": receipt.bloom(),
+                "logs": logs,
+            })
+        })
+        .collect()
+}
+
+/// Compare two `eth_getBlockReceipts` results receipt by receipt and log by log.
+pub fn diff_receipts(remote: &[Value], local: &[Value]) -> Vec<Divergence> {
+    let mut out = Vec::new();
+    if remote.len() != local.len() {
+        out.push(Divergence {
+            field: "receipts.len".to_string(),
+            remote: remote.len().to_string(),
+            local: local.len().to_string(),
+        });
+    }
+
+    for (i, (r, l)) in remote.iter().zip(local).enumerate() {
+        compare_fields(&format!("receipts[{i}]."), RECEIPT_FIELDS, r, l, &mut out);
+
+        let empty = Vec::new();
+        let r_logs = r["logs"].as_array().unwrap_or(&empty);
+        let l_logs = l["logs"].as_array().unwrap_or(&empty);
+        if r_logs.len() != l_logs.len() {
+            out.push(Divergence {
+                field: format!("receipts[{i}].logs.len"),
+                remote: r_logs.len().to_string(),
+                local: l_logs.len().to_string(),
+            });
+        }
+        for (j, (rl, ll)) in r_logs.iter().zip(l_logs).enumerate() {
+            compare_fields(&format!("receipts[{i}].logs[{j}]."), LOG_FIELDS, rl, ll, &mut out);
+        }
+    }
+    out
+}
+
+impl ReplayArgs {
+    /// Run the comparison.
+    pub async fn execute(self) -> eyre::Result<()> {
+        eyre::ensure!(self.from <= self.to, "--from must not be greater than --to");
+
+        let remote = RpcClient::new(self.rpc.clone());
+        let heimdall = HttpHeimdallClient::new(self.heimdall.as_str());
+        let chain_id = remote.chain_id().await?;
+        let schedule = bor_schedule(chain_id)?;
+        let evm_config = bor_evm_config(chain_id)?;
+
+        for number in self.from..=self.to {
+            let block = capture_witness_block(&remote, &heimdall, &schedule, number).await?;
+            let executed = execute_witness_block(&evm_config, &block)?;
+            let mut divergences = diff_execution(&executed);
+            if divergences.is_empty() {
+                if number % 1000 == 0 {
+                    println!("block {number}: ok");
+                }
+                continue;
+            }
+
+            // Only fetch receipts when the commitments differ, to locate the cause.
+            let remote_receipts = remote.block_receipts(number).await?.unwrap_or_default();
+            divergences.extend(diff_receipts(&remote_receipts, &receipts_json(&executed.receipts)));
+            println!("first divergence at block {number}:");
+            for d in &divergences {
+                println!("  {d}");
+            }
+            print_context(&schedule, number, &heimdall).await;
+            eyre::bail!("block {number} diverges from {}", self.rpc);
+        }
+
+        println!("blocks {}..={} match {}", self.from, self.to, self.rpc);
+        Ok(())
+    }
+}
+
+/// The EVM configuration blocks of the chain are imported with.
+fn bor_evm_config(chain_id: u64) -> eyre::Result<BorEvmConfig> {
+    let chain_spec = Arc::new(bor_schedule(chain_id)?.into_inner());
+    let bor_config = BorConfig::for_chain(chain_id, chain_spec.genesis())?;
+    Ok(BorEvmConfig::new(chain_spec)
+        .with_system_caller(BorSystemCaller::from_config(&bor_config))
+        .with_post_execution(BorPostExecution::from_config(&bor_config)?))
+}
+
+/// Returns the Bor schedule of the chain.
+pub fn bor_schedule(chain_id: u64) -> eyre::Result<BorChainSpec> {
+    match chain_id {
+        MAINNET_CHAIN_ID => Ok(bor_mainnet_genesis()),
+        AMOY_CHAIN_ID => Ok(bor_amoy_genesis()),
+        _ => eyre::bail!("unsupported chain id {chain_id}"),
+    }
+}
+
+async fn print_context(schedule: &BorChainSpec, number: u64, heimdall: &HttpHeimdallClient) {
+    let span_id = schedule.bor_span_id(number);
+    println!(
+        "  context: sprint_start={} span={span_id} span_start={}",
+        schedule.is_bor_sprint_start(number).unwrap_or(false),
+        schedule.bor_span_start(span_id) == number
+    );
+
+    match heimdall.fetch_span(span_id).await {
+        Ok(span) => println!(
+            "  heimdall span {}: blocks {}..={}, {} validators, {} producers",
+            span.id,
+            span.start_block,
+            span.end_block,
+            span.validator_set.validators.len(),
+            span.selected_producers.len()
+        ),
+        Err(err) => println!("  heimdall span {span_id}: unavailable ({err})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::Header;
+    use alloy_primitives::{Address, Bytes, Log, B256};
+
+    fn executed(state_root: B256) -> WitnessExecution {
+        let header = Header {
+            number: 16,
+            state_root: B256::with_last_byte(1),
+            gas_used: 21_000,
+            ..Default::default()
+        };
+        WitnessExecution {
+            receipts_root: header.receipts_root,
+            header,
+            state_root,
+            receipts: Vec::new(),
+            gas_used: 21_000,
+        }
+    }
+
+    fn receipt(status: &str, data: &str) -> Value {
+        json!({
+            "transactionHash": "0x01",
+            "status": status,
+            "gasUsed": "0x5208",
+            "cumulativeGasUsed": "0x5208",
+            "logsBloom": "0x00",
+            "contractAddress": null,
+            "logs": [{ "address": "0x1001", "topics": ["0x02"], "data": data, "logIndex": "0x0" }],
+        })
+    }
+
+    #[test]
+    fn test_matching_execution() {
+        assert!(diff_execution(&executed(B256::with_last_byte(1))).is_empty());
+    }
+
+    #[test]
+    fn test_state_root_divergence() {
+        let diff = diff_execution(&executed(B256::with_last_byte(2)));
+        assert_eq!(
+            diff,
+            vec![Divergence {
+                field: "stateRoot".to_string(),
+                remote: B256::with_last_byte(1).to_string(),
+                local: B256::with_last_byte(2).to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_receipt_and_log_divergence() {
+        let remote = vec![receipt("0x1", "0xdead")];
+        let local = vec![receipt("0x0", "0xbeef")];
+        let fields: Vec<String> = diff_receipts(&remote, &local).into_iter().map(|d| d.field).collect();
+        assert_eq!(fields, vec!["receipts[0].status", "receipts[0].logs[0].data"]);
+    }
+
+    #[test]
+    fn test_missing_receipt() {
+        let remote = vec![receipt("0x1", "0x"), receipt("0x1", "0x")];
+        let local = vec![receipt("0x1", "0x")];
+        let diff = diff_receipts(&remote, &local);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].field, "receipts.len");
+    }
+
+    #[test]
+    fn test_executed_receipts_in_rpc_shape() {
+        let log = |byte| {
+            Log::new_unchecked(
+                Address::with_last_byte(byte),
+                vec![B256::with_last_byte(byte)],
+                Bytes::from(vec![byte]),
+            )
+        };
+        let receipt = |success, cumulative_gas_used, logs| Receipt {
+            success,
+            cumulative_gas_used,
+            logs,
+            ..Default::default()
+        };
+        let receipts = vec![receipt(true, 21_000, vec![log(1)]), receipt(false, 50_000, vec![log(2)])];
+        let json = receipts_json(&receipts);
+        assert_eq!(json[1]["status"], "0x0");
+        assert_eq!(json[1]["gasUsed"], "0x7148");
+        assert_eq!(json[1]["cumulativeGasUsed"], "0xc350");
+        assert_eq!(json[1]["logs"][0]["logIndex"], "0x1");
+        assert_eq!(json[1]["logs"][0]["data"], "0x02");
+        assert_eq!(json[1]["logs"][0]["topics"][0], B256::with_last_byte(2).to_string());
+        assert_eq!(diff_receipts(&json, &json), Vec::new());
+    }
+}
@@ -0,0 +1,124 @@
+//! `boreth bor rewind-to-milestone`: unwind a stopped node to its last milestone.
+//!
+//! A node that followed a bad fork past the last milestone keeps building on it
+//! until the fork is unwound. Blocks up to a milestone are final, so that is where
+//! this command goes back to: the latest milestone the node cached (see
+//! [`HeimdallCacheFile`]), the latest one on Heimdall with `--bor.heimdall`, or any
+//! block given with `--block`.
+//!
+//! The chain is unwound by reth's `stage unwind`, run on the same executable, which
+//! fails if the node is still running. The Bor data kept in the data directory is
+//! rewound with it: a cached milestone or checkpoint ending above the target is
+//! dropped, so the restarted node does not finalize blocks it no longer has, and so
+//! are total difficulty checkpoints above it.
+
+use bor_storage::{TotalDifficultyIndex, DEFAULT_TD_CHECKPOINT_INTERVAL, TD_INDEX_FILE};
+use heimdall_client::{
+    HeimdallCacheFile, HeimdallClient, HttpHeimdallClient, HEIMDALL_CACHE_FILE,
+};
+use std::path::PathBuf;
+use std::process::Command;
+use url::Url;
+
+/// Arguments of `boreth bor rewind-to-milestone`.
+#[derive(Debug, clap::Args)]
+pub struct RewindArgs {
+    /// Data directory of the stopped node.
+    #[arg(long, value_name = "PATH")]
+    datadir: PathBuf,
+
+    /// Chain of the node, passed on to `stage unwind`.
+    #[arg(long, value_name = "CHAIN_OR_PATH")]
+    chain: Option<String>,
+
+    /// Rewind to this block instead of the end of the latest milestone.
+    #[arg(long, conflicts_with = "heimdall")]
+    block: Option<u64>,
+
+    /// Heimdall REST endpoint to read the latest milestone from, instead of the one the
+    /// node last cached.
+    #[arg(long = "bor.heimdall", value_name = "URL")]
+    heimdall: Option<Url>,
+}
+
+impl RewindArgs {
+    /// Run the rewind.
+    pub async fn execute(self) -> eyre::Result<()> {
+        let cache = HeimdallCacheFile::new(self.datadir.join(HEIMDALL_CACHE_FILE));
+        let target = self.target(&cache).await?;
+        println!("rewinding {} to block {target}", self.datadir.display());
+
+        let mut unwind = Command::new(std::env::current_exe()?);
+        unwind.args(["stage", "unwind", "--datadir"]).arg(&self.datadir);
+        if let Some(chain) = &self.chain {
+            unwind.args(["--chain", chain]);
+        }
+        let status = unwind.args(["to-block", &target.to_string()]).status()?;
+        eyre::ensure!(status.success(), "stage unwind to block {target} failed: {status}");
+
+        if let Some(mut cached) = cache.load() {
+            if cached.rewind_to(target) {
+                cache.save(&cached)?;
+                println!("dropped cached Heimdall milestone or checkpoint above block {target}");
+            }
+        }
+        let td_path = self.datadir.join(TD_INDEX_FILE);
+        let td_index = TotalDifficultyIndex::load(&td_path, DEFAULT_TD_CHECKPOINT_INTERVAL);
+        if let Some(mut index) = td_index {
+            index.truncate_above(target);
+            index.save(&td_path)?;
+        }
+        println!("rewound to block {target}");
+        Ok(())
+    }
+
+    /// The block to rewind to.
+    async fn target(&self, cache: &HeimdallCacheFile) -> eyre::Result<u64> {
+        if let Some(block) = self.block {
+            return Ok(block);
+        }
+        if let Some(url) = &self.heimdall {
+            let milestone = HttpHeimdallClient::new(url.as_str()).fetch_milestone_latest().await?;
+            println!("latest milestone on Heimdall ends at block {}", milestone.end_block);
+            return Ok(milestone.end_block);
+        }
+        cached_milestone_end(cache).ok_or_else(|| {
+            eyre::eyre!(
+                "no milestone cached in {}, pass --block or --bor.heimdall",
+                cache.path().display()
+            )
+        })
+    }
+}
+
+/// End block of the milestone cached in `cache`, if any.
+pub fn cached_milestone_end(cache: &HeimdallCacheFile) -> Option<u64> {
+    cache.load()?.milestone.map(|milestone| milestone.end_block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256};
+    use heimdall_client::{HeimdallCacheSnapshot, Milestone};
+
+    #[test]
+    fn test_target_is_cached_milestone() {
+        let dir = std::env::temp_dir().join(format!("boreth-rewind-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache = HeimdallCacheFile::new(dir.join(HEIMDALL_CACHE_FILE));
+        assert_eq!(cached_milestone_end(&cache), None);
+
+        let milestone = Milestone {
+            milestone_id: "m-7".to_string(),
+            start_block: 1_000,
+            end_block: 1_015,
+            hash: B256::with_last_byte(7),
+            proposer: Address::with_last_byte(1),
+        };
+        let cached = HeimdallCacheSnapshot { milestone: Some(milestone), ..Default::default() };
+        cache.save(&cached).unwrap();
+        assert_eq!(cached_milestone_end(&cache), Some(1_015));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
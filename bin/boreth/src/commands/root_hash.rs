@@ -0,0 +1,101 @@
+//! `boreth root-hash`: recompute the checkpoint root of a block range.
+//!
+//! Validators use it to cross-check the root a proposer submitted to Heimdall
+//! against the headers of any node they trust, without running `bor_getRootHash`
+//! on their own node. Headers are read one by one and only their leaves kept, so
+//! the longest checkpoint costs no more memory than its leaves.
+
+use super::rpc::{parse_quantity, RpcClient};
+use alloy_primitives::B256;
+use bor_rpc::{validate_checkpoint_range, RootHashBuilder};
+use serde_json::Value;
+use url::Url;
+
+/// Arguments of `boreth root-hash`.
+#[derive(Debug, clap::Args)]
+pub struct RootHashArgs {
+    /// JSON-RPC endpoint to read headers from.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    rpc: Url,
+
+    /// First block of the checkpoint.
+    #[arg(long)]
+    from: u64,
+
+    /// Last block of the checkpoint (inclusive).
+    #[arg(long)]
+    to: u64,
+
+    /// Root hash to compare against, e.g. the one of a proposed checkpoint.
+    #[arg(long, value_name = "HASH")]
+    expected: Option<B256>,
+}
+
+/// Add the header of `block`, as served by `eth_getBlockByNumber`, to `builder`.
+pub fn push_block(builder: &mut RootHashBuilder, block: &Value) -> eyre::Result<()> {
+    let quantity = |name: &str| {
+        parse_quantity(&block[name]).ok_or_else(|| eyre::eyre!("invalid {name}: {}", block[name]))
+    };
+    let hash = |name: &str| -> eyre::Result<B256> {
+        serde_json::from_value(block[name].clone()).map_err(|e| eyre::eyre!("invalid {name}: {e}"))
+    };
+    builder.push(
+        quantity("number")?,
+        quantity("timestamp")?,
+        hash("transactionsRoot")?,
+        hash("receiptsRoot")?,
+    )?;
+    Ok(())
+}
+
+impl RootHashArgs {
+    /// Compute the root and compare it against `--expected`, if given.
+    pub async fn execute(self) -> eyre::Result<()> {
+        let client = RpcClient::new(self.rpc.clone());
+        validate_checkpoint_range(self.from, self.to, client.block_number().await?)?;
+
+        let mut builder = RootHashBuilder::new(self.from);
+        for number in self.from..=self.to {
+            let block =
+                client.block(number).await?.ok_or_else(|| eyre::eyre!("block {number} not found"))?;
+            push_block(&mut builder, &block)?;
+            if number % 1000 == 0 {
+                println!("block {number}: read");
+            }
+        }
+
+        let root = builder.root();
+        println!("root hash of blocks {}..={}: {root}", self.from, self.to);
+        if let Some(expected) = self.expected {
+            eyre::ensure!(root == expected, "expected root hash {expected}, computed {root}");
+            println!("matches the expected root hash");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_rpc::checkpoint_leaf;
+    use serde_json::json;
+
+    #[test]
+    fn test_served_header_fields() {
+        let tx_root = B256::with_last_byte(1);
+        let receipts_root = B256::with_last_byte(2);
+        let block = json!({
+            "number": "0x10",
+            "timestamp": "0x5f5e100",
+            "transactionsRoot": tx_root,
+            "receiptsRoot": receipts_root,
+        });
+
+        let mut builder = RootHashBuilder::new(16);
+        push_block(&mut builder, &block).unwrap();
+        assert_eq!(builder.root(), checkpoint_leaf(16, 100_000_000, tx_root, receipts_root));
+
+        // Served out of order, e.g. by a node that skipped a block.
+        assert!(push_block(&mut builder, &block).is_err());
+    }
+}
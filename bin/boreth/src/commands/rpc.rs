@@ -0,0 +1,125 @@
+//! Minimal JSON-RPC client used by the tooling subcommands.
+//!
+//! Responses are kept as [`serde_json::Value`] so that bor-geth and boreth
+//! output can be compared field by field without committing to a schema.
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use url::Url;
+
+/// A JSON-RPC endpoint.
+#[derive(Debug)]
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: Url,
+    next_id: AtomicU64,
+}
+
+impl RpcClient {
+    /// Create a client for `url`.
+    pub fn new(url: Url) -> Self {
+        Self { http: reqwest::Client::new(), url, next_id: AtomicU64::new(1) }
+    }
+
+    /// The endpoint URL.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Call `method` and deserialize its `result`.
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> eyre::Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let mut response: Value =
+            self.http.post(self.url.clone()).json(&request).send().await?.error_for_status()?.json().await?;
+
+        if let Some(error) = response.get("error") {
+            eyre::bail!("{method} failed on {}: {error}", self.url);
+        }
+        let result = response.get_mut("result").map(Value::take).unwrap_or(Value::Null);
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// `eth_blockNumber`.
+    pub async fn block_number(&self) -> eyre::Result<u64> {
+        let number: Value = self.call("eth_blockNumber", json!([])).await?;
+        parse_quantity(&number).ok_or_else(|| eyre::eyre!("invalid block number {number}"))
+    }
+
+    /// `eth_chainId`.
+    pub async fn chain_id(&self) -> eyre::Result<u64> {
+        let id: Value = self.call("eth_chainId", json!([])).await?;
+        parse_quantity(&id).ok_or_else(|| eyre::eyre!("invalid chain id {id}"))
+    }
+
+    /// `eth_getBlockByNumber` with transaction hashes only.
+    pub async fn block(&self, number: u64) -> eyre::Result<Option<Value>> {
+        self.call("eth_getBlockByNumber", json!([quantity(number), false])).await
+    }
+
+    /// `eth_getBlockReceipts`.
+    pub async fn block_receipts(&self, number: u64) -> eyre::Result<Option<Vec<Value>>> {
+        self.call("eth_getBlockReceipts", json!([quantity(number)])).await
+    }
+
+    /// `eth_getLogs` over the inclusive block range `from..=to`, without filters.
+    pub async fn logs(&self, from: u64, to: u64) -> eyre::Result<Vec<Value>> {
+        self.call("eth_getLogs", json!([{ "fromBlock": quantity(from), "toBlock": quantity(to) }])).await
+    }
+
+    /// `debug_traceBlockByNumber` with the `prestateTracer` in diff mode.
+    pub async fn trace_block_prestate(&self, number: u64) -> eyre::Result<Vec<Value>> {
+        let config = json!({ "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } });
+        self.call("debug_traceBlockByNumber", json!([quantity(number), config])).await
+    }
+
+    /// `eth_getBalance` of `address` at block `number`.
+    pub async fn balance(&self, address: &str, number: u64) -> eyre::Result<Value> {
+        self.call("eth_getBalance", json!([address, quantity(number)])).await
+    }
+
+    /// `eth_getTransactionCount` of `address` at block `number`.
+    pub async fn nonce(&self, address: &str, number: u64) -> eyre::Result<Value> {
+        self.call("eth_getTransactionCount", json!([address, quantity(number)])).await
+    }
+
+    /// `eth_getCode` of `address` at block `number`.
+    pub async fn code(&self, address: &str, number: u64) -> eyre::Result<Value> {
+        self.call("eth_getCode", json!([address, quantity(number)])).await
+    }
+
+    /// `eth_getStorageAt` of `slot` of `address` at block `number`.
+    pub async fn storage_at(&self, address: &str, slot: &str, number: u64) -> eyre::Result<Value> {
+        self.call("eth_getStorageAt", json!([address, slot, quantity(number)])).await
+    }
+}
+
+/// Encode `n` as a JSON-RPC quantity.
+pub fn quantity(n: u64) -> String {
+    format!("{n:#x}")
+}
+
+/// Parse a JSON-RPC quantity (`"0x1a"`) or plain JSON number.
+pub fn parse_quantity(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => u64::from_str_radix(s.strip_prefix("0x")?, 16).ok(),
+        Value::Number(n) => n.as_u64(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantity_roundtrip() {
+        assert_eq!(quantity(0), "0x0");
+        assert_eq!(quantity(26), "0x1a");
+        assert_eq!(parse_quantity(&json!("0x1a")), Some(26));
+        assert_eq!(parse_quantity(&json!(26)), Some(26));
+        assert_eq!(parse_quantity(&json!("26")), None);
+        assert_eq!(parse_quantity(&Value::Null), None);
+    }
+}
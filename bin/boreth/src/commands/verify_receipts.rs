@@ -0,0 +1,193 @@
+//! `boreth verify-receipts`: header commitment and log index parity for
+//! historical blocks.
+//!
+//! For each block in the range, the served receipts are re-encoded and the
+//! receipts root and logs bloom recomputed under Bor's rules: before Madhugiri
+//! the state sync receipt is excluded from both, afterwards it is included.
+//! The served `logIndex` values must number the state sync receipt's logs after
+//! the regular ones. Any difference means the node indexes or commits receipts
+//! differently from Bor.
+
+use super::rpc::{parse_quantity, RpcClient};
+use alloy_primitives::{Bloom, Log, B256};
+use bor_chainspec::{BorHardfork, AMOY_CHAIN_ID, MAINNET_CHAIN_ID};
+use bor_storage::{
+    receipt_key::derived_bor_tx_hash, verify_receipt_parity, BlockReceipts, ParityReceipt,
+};
+use serde_json::Value;
+use url::Url;
+
+/// Arguments of `boreth verify-receipts`.
+#[derive(Debug, clap::Args)]
+pub struct VerifyReceiptsArgs {
+    /// JSON-RPC endpoint of the node to verify.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    rpc: Url,
+
+    /// First block to verify.
+    #[arg(long)]
+    from: u64,
+
+    /// Last block to verify (inclusive).
+    #[arg(long)]
+    to: u64,
+}
+
+/// Returns the Madhugiri activation block of the chain.
+fn madhugiri_block(chain_id: u64) -> eyre::Result<u64> {
+    match chain_id {
+        MAINNET_CHAIN_ID => Ok(BorHardfork::Madhugiri.mainnet_block()),
+        AMOY_CHAIN_ID => Ok(BorHardfork::Madhugiri.amoy_block()),
+        _ => eyre::bail!("unsupported chain id {chain_id}"),
+    }
+}
+
+fn field<T: serde::de::DeserializeOwned>(value: &Value, name: &str) -> eyre::Result<T> {
+    serde_json::from_value(value[name].clone()).map_err(|e| eyre::eyre!("invalid {name}: {e}"))
+}
+
+fn quantity_field(value: &Value, name: &str) -> eyre::Result<u64> {
+    parse_quantity(&value[name]).ok_or_else(|| eyre::eyre!("invalid {name}: {}", value[name]))
+}
+
+/// Decode an `eth_getBlockReceipts` entry, returning the receipt and its served log indices.
+pub fn parse_receipt(receipt: &Value) -> eyre::Result<(ParityReceipt, Vec<u64>)> {
+    let empty = Vec::new();
+    let logs = receipt["logs"].as_array().unwrap_or(&empty);
+
+    let mut parsed = Vec::with_capacity(logs.len());
+    let mut indices = Vec::with_capacity(logs.len());
+    for log in logs {
+        parsed.push(Log::new_unchecked(field(log, "address")?, field(log, "topics")?, field(log, "data")?));
+        indices.push(quantity_field(log, "logIndex")?);
+    }
+
+    let receipt = ParityReceipt {
+        tx_type: parse_quantity(&receipt["type"]).unwrap_or(0) as u8,
+        success: quantity_field(receipt, "status")? == 1,
+        cumulative_gas_used: quantity_field(receipt, "cumulativeGasUsed")?,
+        logs: parsed,
+    };
+    Ok((receipt, indices))
+}
+
+/// Split a block's served receipts into regular receipts and the state sync receipt.
+///
+/// The state sync receipt is recognized by its derived transaction hash.
+pub fn parse_block_receipts(
+    number: u64,
+    block_hash: B256,
+    unified: bool,
+    receipts: &[Value],
+) -> eyre::Result<(BlockReceipts, Vec<Vec<u64>>)> {
+    let bor_tx_hash = derived_bor_tx_hash(number, &block_hash);
+    let mut block = BlockReceipts { unified, ..Default::default() };
+    let mut indices = Vec::with_capacity(receipts.len());
+    let mut bor_indices = None;
+
+    for value in receipts {
+        let (receipt, served) = parse_receipt(value)?;
+        if field::<B256>(value, "transactionHash")? == bor_tx_hash {
+            block.bor_receipt = Some(receipt);
+            bor_indices = Some(served);
+        } else {
+            block.receipts.push(receipt);
+            indices.push(served);
+        }
+    }
+    indices.extend(bor_indices);
+    Ok((block, indices))
+}
+
+impl VerifyReceiptsArgs {
+    /// Run the verification.
+    pub async fn execute(self) -> eyre::Result<()> {
+        eyre::ensure!(self.from <= self.to, "--from must not be greater than --to");
+
+        let client = RpcClient::new(self.rpc.clone());
+        let madhugiri = madhugiri_block(client.chain_id().await?)?;
+        let mut failed = 0u64;
+
+        for number in self.from..=self.to {
+            let block = client.block(number).await?.ok_or_else(|| eyre::eyre!("block {number} not found"))?;
+            let receipts = client
+                .block_receipts(number)
+                .await?
+                .ok_or_else(|| eyre::eyre!("no receipts for block {number}"))?;
+
+            let hash: B256 = field(&block, "hash")?;
+            let (parsed, served) = parse_block_receipts(number, hash, number >= madhugiri, &receipts)?;
+            let mismatches = verify_receipt_parity(
+                &parsed,
+                field(&block, "receiptsRoot")?,
+                field::<Bloom>(&block, "logsBloom")?,
+                &served,
+            );
+
+            if mismatches.is_empty() {
+                if number % 1000 == 0 {
+                    println!("block {number}: ok");
+                }
+                continue;
+            }
+            failed += 1;
+            println!("block {number}: {} mismatches", mismatches.len());
+            for mismatch in &mismatches {
+                println!("  {mismatch}");
+            }
+        }
+
+        eyre::ensure!(failed == 0, "{failed} blocks fail receipt parity on {}", self.rpc);
+        println!("receipts of blocks {}..={} match their headers", self.from, self.to);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn receipt(tx_hash: B256, log_indices: &[u64]) -> Value {
+        let logs: Vec<Value> = log_indices
+            .iter()
+            .map(|i| {
+                json!({
+                    "address": "0x0000000000000000000000000000000000001010",
+                    "topics": ["0x4dfe1bbbcf077ddc3e01291eea2d5c70c2b422b415d95645b9adcfd678cb1d63"],
+                    "data": "0x01",
+                    "logIndex": format!("{i:#x}"),
+                })
+            })
+            .collect();
+        json!({
+            "transactionHash": tx_hash,
+            "type": "0x2",
+            "status": "0x1",
+            "cumulativeGasUsed": "0x5208",
+            "logs": logs,
+        })
+    }
+
+    #[test]
+    fn test_bor_receipt_is_recognized_and_ordered_last() {
+        let hash = B256::new([0xab; 32]);
+        let bor_hash = derived_bor_tx_hash(16, &hash);
+        let served =
+            vec![receipt(B256::new([1; 32]), &[0]), receipt(bor_hash, &[2]), receipt(B256::new([2; 32]), &[1])];
+
+        let (block, indices) = parse_block_receipts(16, hash, false, &served).unwrap();
+        assert_eq!(block.receipts.len(), 2);
+        assert!(block.bor_receipt.is_some());
+        assert_eq!(block.receipts[0].tx_type, 2);
+        assert_eq!(indices, vec![vec![0], vec![1], vec![2]]);
+        assert!(verify_receipt_parity(&block, block.receipts_root(), block.logs_bloom(), &indices).is_empty());
+    }
+
+    #[test]
+    fn test_madhugiri_block_per_chain() {
+        assert_eq!(madhugiri_block(MAINNET_CHAIN_ID).unwrap(), 80_084_800);
+        assert_eq!(madhugiri_block(AMOY_CHAIN_ID).unwrap(), 28_899_616);
+        assert!(madhugiri_block(1).is_err());
+    }
+}
@@ -1,102 +1,65 @@
 //! Boreth — Polygon Bor execution client built on Reth.
 
-use bor_chainspec::BorChainSpecParser;
-use bor_consensus::BorConsensus;
-use bor_evm::BorEvmConfig;
-use bor_node::handshake::BorRlpxHandshake;
+use alloy_eips::BlockId;
+use alloy_primitives::B256;
+use bor_chainspec::{bor_genesis_chainspec, BorChainSpecParser};
+use bor_consensus::{
+    BlockSealer, DeferredChecks, DoubleSignGuard, LastValidatorMismatch, SharedDeferredChecks,
+    SharedDoubleSignGuard, SharedLastValidatorMismatch, VerificationSourceSelector,
+};
+use bor_evm::{
+    BorEvmConfig, CachedSprintContext, ExecutionDiffRecorder, HistoricalValidatorReader,
+    PendingStateOverlay,
+};
+use bor_node::{
+    export_canon_metrics, proposal_candidates,
+    tasks::{
+        assert_roots, complete_sprint_markers, index_state_syncs, index_total_difficulty,
+        overlay_pending_state_syncs, persist_heimdall_cache, prefer_milestone_peers,
+        prefetch_state_syncs, rebuild_head_snapshot, report_sprint_recovery,
+    },
+    AnnouncedHead, BorArgs, BorBlockImport, BorCanonNotifications, BorConsensusBuilder, BorDataDir,
+    BorEngineValidatorBuilder, BorError, BorExecutorBuilder, BorNetworkBuilder, BorNode,
+    BorNodeConfig, BorNodeTypes, BorParams, BorPayloadBuilderBuilder, BorPoolBuilder, BorResync,
+    CommittedSpans, EngineForkchoiceSink, EnginePayloadTrigger,
+    ForkchoiceDriver, ForkchoiceMode, HeimdallPush, MilestonePeers, MilestoneService,
+    NetworkHead, PendingStateChanges, ProducerHistory, ProducerMonitor, ProducerScheduler,
+    ProductionHalt, ProposalSimulator, ProviderProduction, ProviderProposalExecutor,
+    PushListener, SprintPresimulation, SyncTuning, TxJournal, JOURNAL_REPLAY_INTERVAL,
+};
+use bor_rpc::{
+    bor_admin_module, bor_block_module, bor_call_module, bor_debug_module, bor_fee_module,
+    bor_milestone_module, bor_monitor_module, bor_proposal_module, bor_resync_module,
+    bor_root_hash_module, bor_state_sync_module, bor_transaction_module, bor_validators_module,
+    bor_vote_module, rpc_error, DebugContext,
+};
+use bor_storage::TD_INDEX_FILE;
 use clap::Parser;
-use reth_chainspec::{EthereumHardforks, Hardforks};
+use heimdall_client::HttpHeimdallClient;
+use reth_chainspec::EthChainSpec;
 use reth_ethereum_cli::interface::Cli;
-use reth_evm::eth::spec::EthExecutorSpec;
-use reth_network::{primitives::BasicNetworkPrimitives, NetworkHandle, NetworkManager, PeersInfo};
-use reth_node_api::{PrimitivesTy, TxTy};
 use reth_node_builder::{
-    components::{ConsensusBuilder, ExecutorBuilder, NetworkBuilder},
-    BuilderContext,
-    node::{FullNodeTypes, NodeTypes},
+    components::{BasicPayloadServiceBuilder, ComponentsBuilder},
+    rpc::{BasicEngineApiBuilder, BasicEngineValidatorBuilder, RpcAddOns},
 };
-use reth_node_ethereum::{EthereumAddOns, EthereumNode};
-use reth_tracing::tracing::info;
+use reth_node_ethereum::{EthereumAddOns, EthereumEthApiBuilder};
+use reth_rpc_eth_api::EthApiServer;
+use reth_provider::{CanonStateSubscriptions, ChainSpecProvider};
+use reth_tracing::tracing::{info, warn};
 use reth_transaction_pool::{PoolTransaction, TransactionPool};
 use std::sync::Arc;
 
-/// Bor PoA consensus builder that replaces Ethereum's Beacon consensus.
-#[derive(Debug, Default, Clone, Copy)]
-#[non_exhaustive]
-pub struct BorConsensusBuilder;
-
-impl<Node> ConsensusBuilder<Node> for BorConsensusBuilder
-where
-    Node: FullNodeTypes<
-        Types: reth_node_builder::node::NodeTypes<
-            ChainSpec: reth_chainspec::EthChainSpec + reth_chainspec::EthereumHardforks,
-            Primitives = reth_ethereum_primitives::EthPrimitives,
-        >,
-    >,
-{
-    type Consensus = Arc<BorConsensus<<Node::Types as reth_node_builder::node::NodeTypes>::ChainSpec>>;
-
-    async fn build_consensus(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Consensus> {
-        Ok(Arc::new(BorConsensus::new(ctx.chain_spec())))
-    }
-}
+mod commands;
 
-/// Bor network builder with custom eth/69 handshake.
-///
-/// Go-Bor's eth/69 Status message includes a TD field that standard eth/69
-/// omits. This builder wires in [`BorRlpxHandshake`] to handle both formats.
-#[derive(Debug, Default, Clone, Copy)]
-#[non_exhaustive]
-pub struct BorNetworkBuilder;
-
-impl<Node, Pool> NetworkBuilder<Node, Pool> for BorNetworkBuilder
-where
-    Node: FullNodeTypes<Types: NodeTypes<ChainSpec: Hardforks>>,
-    Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TxTy<Node::Types>>>
-        + Unpin
-        + 'static,
-{
-    type Network =
-        NetworkHandle<BasicNetworkPrimitives<PrimitivesTy<Node::Types>, reth_transaction_pool::PoolPooledTx<Pool>>>;
-
-    async fn build_network(
-        self,
-        ctx: &BuilderContext<Node>,
-        pool: Pool,
-    ) -> eyre::Result<Self::Network> {
-        let network_config_builder = ctx
-            .network_config_builder()?
-            .eth_rlpx_handshake(Arc::new(BorRlpxHandshake::default()));
-
-        let network_config = ctx.build_network_config(network_config_builder);
-        let network = NetworkManager::builder(network_config).await?;
-        let handle = ctx.start_network(network, pool);
-        info!(target: "boreth", enode=%handle.local_node_record(), "P2P networking initialized with Bor handshake");
-        Ok(handle)
-    }
-}
-
-/// Bor EVM executor builder that wires in the custom [`BorEvmConfig`].
-#[derive(Debug, Default, Clone, Copy)]
-#[non_exhaustive]
-pub struct BorExecutorBuilder;
-
-impl<Types, Node> ExecutorBuilder<Node> for BorExecutorBuilder
-where
-    Types: reth_node_builder::node::NodeTypes<
-        ChainSpec: EthExecutorSpec + EthereumHardforks + Clone,
-        Primitives = reth_ethereum_primitives::EthPrimitives,
-    >,
-    Node: FullNodeTypes<Types = Types>,
-{
-    type EVM = BorEvmConfig<Types::ChainSpec>;
-
-    async fn build_evm(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::EVM> {
-        Ok(BorEvmConfig::new(ctx.chain_spec()))
+fn main() {
+    if let Some(result) = commands::try_run(std::env::args_os().collect()) {
+        if let Err(err) = result {
+            eprintln!("Error: {err:?}");
+            std::process::exit(1);
+        }
+        return;
     }
-}
 
-fn main() {
     reth_cli_util::sigsegv_handler::install();
 
     if std::env::var_os("RUST_BACKTRACE").is_none() {
@@ -104,20 +67,533 @@ fn main() {
     }
 
     if let Err(err) =
-        Cli::<BorChainSpecParser>::parse().run(async move |builder, _| {
+        Cli::<BorChainSpecParser, BorArgs>::parse().run(async move |mut builder, mut bor_args| {
             info!(target: "boreth", "Launching Boreth node with Bor PoA consensus");
+            bor_args.apply_config_file()?;
+            let mut txpool = bor_args.txpool_config(builder.config().datadir().data_dir());
+            txpool.apply_to(&mut builder.config_mut().txpool);
+            // The journal is reth's local transactions backup, wherever reth's flags put it.
+            let pool_args = &builder.config().txpool;
+            txpool.journal = (!pool_args.disable_transactions_backup)
+                .then(|| pool_args.transactions_backup_path.clone())
+                .flatten();
+            // The payload builder steps toward the target within the 1/1024 bound that
+            // `BorConsensus` checks against the parent. Without one, `BorEvmConfig` targets
+            // the chain's block gas limit at each block.
+            builder.config_mut().builder.gas_limit = bor_args.miner_gas_limit;
+            // reth's download batches are sized for 12-second blocks; a fresh data directory
+            // starts with batches sized for Polygon's instead.
+            if builder.config().config.is_none() {
+                SyncTuning::default().write_reth_toml(&builder.config().datadir().config())?;
+            }
+            let chain_id = builder.config().chain.chain().id();
+            // Refuse to sync a chain no peer of the selected network shares.
+            let genesis_hash = builder.config().chain.genesis_hash();
+            bor_chainspec::validate_genesis_hash(chain_id, genesis_hash)?;
+            if bor_chainspec::known_genesis_hash(chain_id).is_some_and(|hash| hash != genesis_hash)
+            {
+                warn!(
+                    target: "boreth",
+                    chain_id,
+                    %genesis_hash,
+                    "built-in preset lacks the genesis alloc, pass the genesis file to --chain"
+                );
+            }
+            // Refuse a `bor` config the chain could not be run from.
+            bor_chainspec::validate_bor_config(&builder.config().chain.genesis)?;
+            let sealer = bor_args
+                .key_file
+                .as_deref()
+                .map(|path| -> eyre::Result<_> {
+                    let last_signed =
+                        builder.config().datadir().data_dir().join("bor-last-signed.json");
+                    let guard: SharedDoubleSignGuard =
+                        Arc::new(DoubleSignGuard::open(last_signed)?);
+                    Ok(Arc::new(BlockSealer::from_file(path)?.with_double_sign_guard(guard)))
+                })
+                .transpose()?;
+            // The key decides the signer; `--bor.signer` alone only names it.
+            let signer = match (&sealer, bor_args.signer) {
+                (Some(sealer), Some(signer)) if signer != sealer.address() => {
+                    eyre::bail!("--bor.signer {signer} is not the address of --bor.keyfile")
+                }
+                (Some(sealer), _) => Some(sealer.address()),
+                (None, signer) => signer,
+            };
+            let heimdall_url = bor_args.heimdall_url_for(chain_id);
+            let params = heimdall_url
+                .as_ref()
+                .map(|url| -> eyre::Result<_> {
+                    let heimdall = HttpHeimdallClient::new(url.as_str())
+                        .with_limits(bor_args.heimdall_limits())
+                        .with_config(bor_args.heimdall_config()?)?;
+                    Ok(BorParams::new(heimdall, signer))
+                })
+                .transpose()?;
+            let admin_module = params.clone().map(bor_admin_module::<_, BorError>).transpose()?;
+            let BorDataDir {
+                span_cache,
+                heimdall_cache,
+                tracker,
+                journal,
+                sprint_wal,
+                state_syncs,
+                total_difficulty,
+                bad_blocks,
+                snapshots,
+            } = BorDataDir::open(
+                builder.config().datadir().data_dir(),
+                bor_args.clear_heimdall_cache,
+            )?;
+            let pending_state = PendingStateOverlay::new();
+            let rpc_state_syncs = state_syncs.clone();
+            let state_sync_module = bor_state_sync_module(state_syncs.clone())?;
+            let rpc_total_difficulty = total_difficulty.clone();
+            let announce_total_difficulty = total_difficulty.clone();
+            let production_halt = ProductionHalt::new();
+            let cross_check = bor_args
+                .heimdall_cross_check
+                .as_ref()
+                .map(|url| -> eyre::Result<_> {
+                    Ok(HttpHeimdallClient::new(url.as_str())
+                        .with_limits(bor_args.heimdall_limits())
+                        .with_config(bor_args.heimdall_config()?)?)
+                })
+                .transpose()?;
+            let resync = params.as_ref().map(|params| {
+                let resync = BorResync::new(
+                    params.heimdall(),
+                    chain_id,
+                    span_cache.clone(),
+                    pending_state.clone(),
+                )
+                .with_config(params.heimdall_config().clone())
+                .with_journal(journal.clone());
+                match cross_check {
+                    Some(secondary) => resync.with_cross_check(secondary, production_halt.clone()),
+                    None => resync,
+                }
+            });
+            let resync_module = resync.clone().map(bor_resync_module::<_, BorError>).transpose()?;
+            let debug_bad_blocks = bad_blocks.clone();
+            let assert_bad_blocks = bad_blocks.clone();
+            let bor_node = BorNode::with_chain_spec(
+                BorNodeConfig::for_chain(chain_id),
+                Arc::new(bor_genesis_chainspec(builder.config().chain.genesis().clone())?),
+            )?
+            .with_snapshot_store(snapshots.clone());
+            let debug_snapshots = snapshots;
+            let execution_diffs = bor_args.assert_roots.then(ExecutionDiffRecorder::new);
+            let debug_spans = span_cache.clone();
+            let debug_state_syncs = state_syncs.clone();
+            let validator_mismatch: SharedLastValidatorMismatch =
+                Arc::new(LastValidatorMismatch::new());
+            let debug_validator_mismatch = validator_mismatch.clone();
+            let miner_gas_limit = bor_args.miner_gas_limit;
+            let tx_ordering = bor_args.tx_ordering();
+            let proposal_inputs = params
+                .clone()
+                .map(|params| (params, span_cache.clone(), pending_state.clone()));
+            let rpc_pending_state = pending_state.clone();
+
+            let deferred_checks: SharedDeferredChecks = Arc::new(DeferredChecks::new());
+            let mut consensus = BorConsensusBuilder::default()
+                .with_deferred_checks(deferred_checks.clone())
+                .with_bad_block_store(bad_blocks)
+                .with_state_sync_store(state_syncs.clone())
+                .with_span_cache(span_cache.clone())
+                .with_heimdall_journal(journal)
+                .with_last_validator_mismatch(validator_mismatch)
+                .with_root_assertion(bor_args.assert_roots);
+            let selector = VerificationSourceSelector {
+                contract_state_distance: bor_args.contract_state_distance,
+            };
+            consensus = consensus.with_contract_state_verification(tracker.clone(), selector);
+
+            let mut executor = BorExecutorBuilder::new()
+                .with_sprint_presimulation(bor_args.presimulate_sprint)
+                .with_sprint_wal(sprint_wal.clone())
+                .with_state_sync_store(state_syncs.clone())
+                .with_state_sync_profiling(bor_args.profile_state_syncs)
+                .with_execution_diffs(execution_diffs.clone())
+                .with_deferred_checks(deferred_checks)
+                .with_gas_limit_target(miner_gas_limit);
+            // Without Heimdall, blocks execute without their system calls.
+            if params.is_some() {
+                executor = executor.with_sprint_context(span_cache.clone(), pending_state.clone());
+            }
+
+            let vote_tracker = tracker.clone();
+            let producer_history = ProducerHistory::default();
+            let history = producer_history.clone();
+            let performance =
+                move |start, end| history.performance(start, end).map_err(BorError::from);
+            let monitor_module = bor_monitor_module(performance)?;
+            let network_head = NetworkHead::default();
+            let (block_import, announced_imports) = BorBlockImport::new(network_head.clone());
+            let network = BorNetworkBuilder::default().with_block_import(block_import);
+            #[cfg(feature = "milestone-gossip")]
+            let gossip = bor_node::gossip::MilestoneGossip::new(tracker.clone());
+            #[cfg(feature = "milestone-gossip")]
+            let milestone_hints = gossip.hints();
+            #[cfg(feature = "milestone-gossip")]
+            let network = network.with_sub_protocol(gossip);
+
+            // Ethereum's add-ons, validating the Bor payload attributes builds are asked with.
+            let add_ons: EthereumAddOns<_, _, _, _, _> = EthereumAddOns::new(RpcAddOns::new(
+                EthereumEthApiBuilder::default(),
+                BorEngineValidatorBuilder::default(),
+                BasicEngineApiBuilder::default(),
+                BasicEngineValidatorBuilder::new(BorEngineValidatorBuilder::default()),
+                Default::default(),
+            ));
             let handle = builder
-                .with_types::<EthereumNode>()
+                .with_types::<BorNodeTypes>()
                 .with_components(
-                    EthereumNode::components()
-                        .consensus(BorConsensusBuilder)
-                        .executor(BorExecutorBuilder)
-                        .network(BorNetworkBuilder),
+                    ComponentsBuilder::default()
+                        .node_types()
+                        .pool(BorPoolBuilder { config: txpool.clone() })
+                        .executor(executor)
+                        .payload(BasicPayloadServiceBuilder::new(BorPayloadBuilderBuilder {
+                            spans: span_cache.clone(),
+                        }))
+                        .network(network)
+                        .consensus(consensus),
                 )
-                .with_add_ons(EthereumAddOns::default())
+                .with_add_ons(add_ons)
+                .extend_rpc_modules(move |ctx| {
+                    // Admin methods change node parameters: local IPC only.
+                    if let Some(module) = admin_module {
+                        ctx.modules.merge_ipc(module)?;
+                    }
+                    if let Some(module) = resync_module {
+                        ctx.modules.merge_ipc(module)?;
+                    }
+                    // Dry runs expose the signer's schedule, so keep them local too.
+                    if let Some((params, spans, pending)) = proposal_inputs {
+                        let source = ProviderProduction {
+                            provider: ctx.provider().clone(),
+                            chain_spec: ctx.provider().chain_spec(),
+                            spans: spans.clone(),
+                            params,
+                        };
+                        let chain_spec = ctx.provider().chain_spec();
+                        let sprint_context = CachedSprintContext::new(
+                            chain_spec.clone(),
+                            spans.clone(),
+                            pending.clone(),
+                        );
+                        let executor = ProviderProposalExecutor {
+                            provider: ctx.provider().clone(),
+                            evm_config: BorEvmConfig::new(chain_spec)
+                                .with_gas_limit_target(miner_gas_limit)
+                                .with_sprint_context(Arc::new(sprint_context)),
+                        };
+                        let simulator = ProposalSimulator::new(source, executor, spans, pending)
+                            .with_gas_limit_target(miner_gas_limit);
+                        let pool = ctx.pool().clone();
+                        let simulate = move |number| {
+                            simulator.simulate(number, proposal_candidates(&pool, tx_ordering))
+                        };
+                        let module = bor_proposal_module(ctx.provider().clone(), simulate)?;
+                        ctx.modules.merge_ipc(module)?;
+                    }
+                    let reader = HistoricalValidatorReader::new(
+                        ctx.provider().clone(),
+                        BorEvmConfig::new(ctx.provider().chain_spec()),
+                    );
+                    ctx.modules.merge_configured(bor_validators_module::<_, BorError>(reader)?)?;
+                    let root_hash = bor_root_hash_module::<_, BorError>(ctx.provider().clone())?;
+                    ctx.modules.merge_configured(root_hash)?;
+                    ctx.modules.merge_configured(monitor_module)?;
+                    ctx.modules.merge_configured(state_sync_module)?;
+                    let milestones = bor_milestone_module::<BorError>(vote_tracker.clone())?;
+                    ctx.modules.merge_configured(milestones)?;
+                    ctx.modules.merge_configured(bor_vote_module::<_, BorError>(
+                        ctx.provider().clone(),
+                        vote_tracker.clone(),
+                    )?)?;
+                    // Next to reth's `debug_getBadBlocks`, which keeps serving reth's records.
+                    ctx.modules.merge_configured(bor_debug_module::<_, BorError>(DebugContext {
+                        provider: ctx.provider().clone(),
+                        bad_blocks: debug_bad_blocks,
+                        spans: debug_spans,
+                        state_syncs: debug_state_syncs,
+                        snapshots: debug_snapshots,
+                        validator_mismatch: debug_validator_mismatch,
+                    })?)?;
+                    // reth's fee history assumes Ethereum's base fee change denominator,
+                    // and its tip suggestion Ethereum's fee market.
+                    let fees = bor_fee_module::<_, BorError>(ctx.provider().clone())?;
+                    ctx.modules.replace_configured(fees)?;
+                    // reth assumes a merged chain and reports no total difficulty.
+                    let eth = ctx.registry.eth_api().clone();
+                    let blocks = move |block: BlockId, full: bool| {
+                        let eth = eth.clone();
+                        async move {
+                            let block = match block {
+                                BlockId::Hash(hash) => {
+                                    EthApiServer::block_by_hash(&eth, hash.block_hash, full).await?
+                                }
+                                BlockId::Number(number) => {
+                                    EthApiServer::block_by_number(&eth, number, full).await?
+                                }
+                            };
+                            let block = block.map(serde_json::to_value).transpose();
+                            block.map_err(rpc_error::<BorError>)
+                        }
+                    };
+                    ctx.modules.replace_configured(bor_block_module::<_, _, _, BorError>(
+                        ctx.provider().clone(),
+                        rpc_total_difficulty,
+                        vote_tracker,
+                        blocks,
+                    )?)?;
+                    // reth's pending block does not commit the events of a sprint start.
+                    let eth = ctx.registry.eth_api().clone();
+                    let call = move |request: serde_json::Value, block, state, block_state| {
+                        let eth = eth.clone();
+                        async move {
+                            let request =
+                                serde_json::from_value(request).map_err(rpc_error::<BorError>)?;
+                            EthApiServer::call(&eth, request, block, state, block_state).await
+                        }
+                    };
+                    let changes = PendingStateChanges {
+                        provider: ctx.provider().clone(),
+                        evm_config: BorEvmConfig::new(ctx.provider().chain_spec()),
+                        pending: rpc_pending_state,
+                    };
+                    let pending = move || changes.overrides();
+                    ctx.modules.replace_configured(bor_call_module(pending, call)?)?;
+                    // reth knows nothing of the transactions state syncs are reported under.
+                    let eth = ctx.registry.eth_api().clone();
+                    let lookup = move |hash: B256| {
+                        let eth = eth.clone();
+                        async move {
+                            let tx = EthApiServer::transaction_by_hash(&eth, hash).await?;
+                            tx.map(serde_json::to_value).transpose().map_err(rpc_error::<BorError>)
+                        }
+                    };
+                    ctx.modules.replace_configured(bor_transaction_module::<_, _, _, BorError>(
+                        ctx.provider().clone(),
+                        rpc_state_syncs,
+                        lookup,
+                    )?)?;
+                    Ok(())
+                })
                 .launch_with_debug_capabilities()
                 .await?;
 
+            let rolled_back = report_sprint_recovery(&sprint_wal, &handle.node.provider);
+            let (provider, spans) = (handle.node.provider.clone(), span_cache.clone());
+            tokio::task::spawn_blocking(move || {
+                if let Err(err) = rebuild_head_snapshot(&bor_node, &provider, &spans) {
+                    warn!(target: "boreth", %err, "failed to rebuild the head snapshot");
+                }
+            });
+            if let Some(params) = &params {
+                let reader = HistoricalValidatorReader::new(
+                    handle.node.provider.clone(),
+                    BorEvmConfig::new(handle.node.provider.chain_spec()),
+                );
+                params.heimdall().set_committed_spans(Arc::new(CommittedSpans::new(reader)));
+            }
+            let notifications = handle.node.provider.subscribe_to_canonical_state();
+            let bor_canon = BorCanonNotifications::new();
+            handle.node.task_executor.spawn(export_canon_metrics(bor_canon.subscribe()));
+            let reorgs = bor_canon.subscribe();
+            handle.node.task_executor.spawn(network_head.clone().follow_reorgs(reorgs));
+            handle.node.task_executor.spawn_critical(
+                "bor announced block import",
+                announced_imports.run(handle.node.add_ons_handle.beacon_engine_handle.clone()),
+            );
+            handle
+                .node
+                .task_executor
+                .spawn(index_state_syncs(
+                    handle.node.provider.chain_spec(),
+                    state_syncs,
+                    bor_canon.clone(),
+                    notifications,
+                ));
+            if let Some(diffs) = execution_diffs {
+                let events = handle.node.add_ons_handle.engine_events.new_listener();
+                let dir = handle.node.config.datadir().data_dir().join("bor-execution-diffs");
+                handle.node.task_executor.spawn_critical(
+                    "bor root assertion",
+                    assert_roots(events, diffs, assert_bad_blocks, dir),
+                );
+                info!(target: "boreth", "asserting the roots of executed blocks");
+            }
+            let provider = handle.node.provider.clone();
+            handle.node.task_executor.spawn_critical_with_graceful_shutdown_signal(
+                "bor sprint markers",
+                |shutdown| complete_sprint_markers(sprint_wal, provider, shutdown),
+            );
+            let provider = handle.node.provider.clone();
+            let td_path = handle.node.config.datadir().data_dir().join(TD_INDEX_FILE);
+            handle.node.task_executor.spawn_critical_with_graceful_shutdown_signal(
+                "bor total difficulty",
+                |shutdown| index_total_difficulty(provider, total_difficulty, td_path, shutdown),
+            );
+            let (cache_spans, cache_tracker) = (span_cache.clone(), tracker.clone());
+            handle.node.task_executor.spawn_critical_with_graceful_shutdown_signal(
+                "bor heimdall cache",
+                |shutdown| {
+                    persist_heimdall_cache(heimdall_cache, cache_spans, cache_tracker, shutdown)
+                },
+            );
+
+            if let Some(params) = params.clone() {
+                let source = ProviderProduction {
+                    provider: handle.node.provider.clone(),
+                    chain_spec: handle.node.provider.chain_spec(),
+                    spans: span_cache.clone(),
+                    params: params.clone(),
+                };
+                let presimulation = bor_args.presimulate_sprint.then(|| SprintPresimulation {
+                    provider: handle.node.provider.clone(),
+                    evm_config: handle.node.evm_config.clone(),
+                    pending: pending_state.clone(),
+                    gas_limit_target: miner_gas_limit,
+                });
+                if let Some(sealer) = sealer {
+                    let trigger = EnginePayloadTrigger {
+                        engine: handle.node.add_ons_handle.beacon_engine_handle.clone(),
+                        payloads: handle.node.payload_builder_handle.clone(),
+                        sealer,
+                        network: handle.node.network.clone(),
+                        provider: handle.node.provider.clone(),
+                        total_difficulty: announce_total_difficulty,
+                        chain_spec: handle.node.provider.chain_spec(),
+                        presimulation,
+                    };
+                    let scheduler =
+                        ProducerScheduler::new(source, trigger).with_halt(production_halt.clone());
+                    handle
+                        .node
+                        .task_executor
+                        .spawn_critical("bor producer scheduler", scheduler.run());
+                } else {
+                    info!(target: "boreth", "no --bor.keyfile, not producing blocks");
+                }
+
+                let source = ProviderProduction {
+                    provider: handle.node.provider.clone(),
+                    chain_spec: handle.node.provider.chain_spec(),
+                    spans: span_cache.clone(),
+                    params,
+                };
+                let monitor = ProducerMonitor::new(source, producer_history);
+                handle.node.task_executor.spawn(monitor.run());
+            }
+
+            if let Some(resync) = resync {
+                let refetch = resync.clone();
+                handle.node.task_executor.spawn(async move {
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    if let Err(err) = refetch.prefetch_rolled_back(&rolled_back, now).await {
+                        warn!(target: "boreth", %err, "failed to refetch rolled back state syncs");
+                    }
+                });
+                let notifications = handle.node.provider.subscribe_to_canonical_state();
+                handle.node.task_executor.spawn(prefetch_state_syncs(
+                    handle.node.provider.clone(),
+                    resync.clone(),
+                    notifications,
+                ));
+                handle.node.task_executor.spawn(resync.run_journal_replay(JOURNAL_REPLAY_INTERVAL));
+                let changes = PendingStateChanges {
+                    provider: handle.node.provider.clone(),
+                    evm_config: handle.node.evm_config.clone(),
+                    pending: pending_state.clone(),
+                };
+                handle
+                    .node
+                    .task_executor
+                    .spawn(overlay_pending_state_syncs(handle.node.pool.clone(), changes));
+            }
+
+            let push = match bor_args.heimdall_push {
+                Some(addr) => {
+                    let push = HeimdallPush::new();
+                    let listener = PushListener::bind(addr, push.clone()).await?;
+                    handle
+                        .node
+                        .task_executor
+                        .spawn_critical("bor heimdall push listener", listener.run());
+                    if let Some(params) = &params {
+                        let spans = bor_node::prefetch_pushed_spans(
+                            push.clone(),
+                            params.heimdall(),
+                            span_cache.clone(),
+                        );
+                        handle.node.task_executor.spawn(spans);
+                    }
+                    Some(push)
+                }
+                None => None,
+            };
+
+            if let Some(path) = txpool.journal {
+                let pool = handle.node.pool.clone();
+                let journal = TxJournal::new(path, txpool.rejournal);
+                let local_transactions = move || {
+                    pool.get_local_transactions()
+                        .into_iter()
+                        .map(|tx| tx.transaction.clone_into_consensus().into_inner())
+                        .collect::<Vec<_>>()
+                };
+                handle
+                    .node
+                    .task_executor
+                    .spawn_critical("bor txpool journal", journal.run(local_transactions));
+            }
+
+            // Milestones and checkpoints back the finality the RPC reports in every mode.
+            if let Some(params) = &params {
+                let mut milestones = MilestoneService::new(params.heimdall(), tracker.clone());
+                // Peers hinting at a newer milestone wake the service like Heimdall would.
+                #[cfg(feature = "milestone-gossip")]
+                let push = {
+                    let push = push.unwrap_or_default();
+                    handle.node.task_executor.spawn(bor_node::gossip::poll_on_milestone_hints(
+                        milestone_hints,
+                        tracker.clone(),
+                        push.clone(),
+                    ));
+                    Some(push)
+                };
+                if let Some(push) = push {
+                    milestones = milestones.with_push(push);
+                }
+                handle.node.task_executor.spawn_critical("bor milestone service", milestones.run());
+                handle.node.task_executor.spawn(prefer_milestone_peers(
+                    handle.node.network.clone(),
+                    tracker.clone(),
+                    Arc::new(MilestonePeers::default()),
+                ));
+            }
+
+            if bor_args.forkchoice == ForkchoiceMode::Internal {
+                let (Some(heimdall_url), Some(_)) = (heimdall_url, &params) else {
+                    eyre::bail!("no Heimdall endpoint known for chain {chain_id}, set --bor.heimdall");
+                };
+                let head =
+                    AnnouncedHead { provider: handle.node.provider.clone(), network: network_head };
+                let driver = ForkchoiceDriver::new(
+                    head,
+                    EngineForkchoiceSink(handle.node.add_ons_handle.beacon_engine_handle.clone()),
+                    tracker,
+                );
+                handle.node.task_executor.spawn_critical("bor forkchoice driver", driver.run());
+                info!(target: "boreth", %heimdall_url, "internal forkchoice driver enabled");
+            }
+
             handle.wait_for_node_exit().await
         })
     {
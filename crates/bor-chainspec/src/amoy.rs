@@ -1,13 +1,15 @@
 //! Amoy testnet (chain 80002) genesis configuration.
 
-use std::collections::BTreeMap;
-
 use alloy_chains::Chain;
 use alloy_genesis::Genesis;
 use reth_chainspec::ChainSpecBuilder;
 use reth_ethereum_forks::{ChainHardforks, EthereumHardfork, ForkCondition};
 
-use crate::{BorHardfork, chainspec::BorChainSpec, constants::AMOY_CHAIN_ID};
+use crate::{
+    AmoyBorHardforks,
+    chainspec::{BorChainSpec, schedule},
+    constants::AMOY_CHAIN_ID,
+};
 
 // Amoy Ethereum fork activation blocks (from Go-Bor AmoyChainConfig).
 const AMOY_LONDON_BLOCK: u64 = 73_100;
@@ -54,12 +56,7 @@ pub fn bor_amoy_genesis() -> BorChainSpec {
         .with_forks(hardforks)
         .build();
 
-    let mut bor_hardforks = BTreeMap::new();
-    for fork in BorHardfork::all() {
-        bor_hardforks.insert(*fork, ForkCondition::Block(fork.amoy_block()));
-    }
-
-    BorChainSpec::new(inner, bor_hardforks)
+    BorChainSpec::new(inner, schedule(AmoyBorHardforks))
 }
 
 #[cfg(test)]
@@ -67,6 +64,7 @@ mod tests {
     use reth_chainspec::EthChainSpec;
 
     use super::*;
+    use crate::BorHardfork;
 
     #[test]
     fn test_amoy_chain_id() {
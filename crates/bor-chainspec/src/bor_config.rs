@@ -0,0 +1,422 @@
+//! The `bor` section of a bor-geth genesis config.
+//!
+//! [`BorConfig`] reads the section as bor-geth writes it, so a genesis file written for
+//! bor-geth can be passed to boreth as is. Keys boreth does not use, such as
+//! `parallelUniverseBlock`, are ignored. [`BorConfig::validate`] rejects sections
+//! a node could not run a chain from, and [`BorConfig::json_schema`] describes the
+//! keys that are read; `res/bor_config.schema.json` is generated from it.
+//!
+//! [`BorConfig::for_chain`] is the config a node runs a chain with: the genesis
+//! section if there is one, else the known config of mainnet or Amoy.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use alloy_genesis::Genesis;
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{AMOY_CHAIN_ID, MAINNET_CHAIN_ID},
+    ContractUpgrade, ForkValue, ScheduleError,
+};
+
+/// The `bor` section of bor-geth's mainnet genesis, without its `blockAlloc`.
+static MAINNET: LazyLock<BorConfig> = LazyLock::new(|| {
+    let value = serde_json::from_str(include_str!("../res/mainnet_bor_config.json"))
+        .expect("valid embedded mainnet bor config JSON");
+    BorConfig::from_value(&value).expect("valid embedded mainnet bor config")
+});
+
+/// The `bor` section of bor-geth's Amoy genesis, without its `blockAlloc`.
+static AMOY: LazyLock<BorConfig> = LazyLock::new(|| {
+    let value = serde_json::from_str(include_str!("../res/amoy_bor_config.json"))
+        .expect("valid embedded Amoy bor config JSON");
+    BorConfig::from_value(&value).expect("valid embedded Amoy bor config")
+});
+
+/// The `bor` section of a genesis config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BorConfig {
+    /// Minimum seconds between blocks.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub period: ForkValue<u64>,
+    /// Delay of the first block of a sprint, in seconds.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub producer_delay: ForkValue<u64>,
+    /// Blocks per sprint.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub sprint: ForkValue<u64>,
+    /// Extra delay per position a backup producer is behind the proposer, in seconds.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub backup_multiplier: ForkValue<u64>,
+    /// Address of the ValidatorSet contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_contract: Option<Address>,
+    /// Address of the StateReceiver contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_receiver_contract: Option<Address>,
+    /// Number of state sync events committed at given sprint starts, where it differs
+    /// from the events Heimdall serves.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub override_state_sync_records: BTreeMap<u64, i64>,
+    /// Seconds an event must be recorded on Heimdall before the block committing it.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub state_sync_confirmation_delay: ForkValue<u64>,
+    /// Contract the base fee is credited to.
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub burnt_contract: ForkValue<Address>,
+    /// Accounts set at given blocks, read by the executor.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub block_alloc: BTreeMap<u64, serde_json::Value>,
+    /// Upgrades of the system contracts, see [`ContractUpgrade`].
+    #[serde(default, skip_serializing_if = "ForkValue::is_empty")]
+    pub contract_upgrades: ForkValue<ContractUpgrade>,
+    /// Jaipur activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jaipur_block: Option<u64>,
+    /// Delhi activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delhi_block: Option<u64>,
+    /// Indore activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indore_block: Option<u64>,
+    /// Agra activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agra_block: Option<u64>,
+    /// Napoli activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub napoli_block: Option<u64>,
+    /// Ahmedabad activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ahmedabad_block: Option<u64>,
+    /// Bhilai activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bhilai_block: Option<u64>,
+    /// Rio activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rio_block: Option<u64>,
+    /// Madhugiri activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub madhugiri_block: Option<u64>,
+    /// Dandeli activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dandeli_block: Option<u64>,
+    /// Lisovo activation block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lisovo_block: Option<u64>,
+}
+
+/// JSON shape of a [`BorConfig`] key.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// A block number.
+    Block,
+    /// An address.
+    Address,
+    /// A map from block to unsigned number.
+    Numbers,
+    /// A map from block to signed number.
+    SignedNumbers,
+    /// A map from block to address.
+    Addresses,
+    /// A map from block to object.
+    Objects,
+}
+
+/// Every key [`BorConfig`] reads, in field order.
+const KEYS: &[(&str, Kind)] = &[
+    ("period", Kind::Numbers),
+    ("producerDelay", Kind::Numbers),
+    ("sprint", Kind::Numbers),
+    ("backupMultiplier", Kind::Numbers),
+    ("validatorContract", Kind::Address),
+    ("stateReceiverContract", Kind::Address),
+    ("overrideStateSyncRecords", Kind::SignedNumbers),
+    ("stateSyncConfirmationDelay", Kind::Numbers),
+    ("burntContract", Kind::Addresses),
+    ("blockAlloc", Kind::Objects),
+    ("contractUpgrades", Kind::Objects),
+    ("jaipurBlock", Kind::Block),
+    ("delhiBlock", Kind::Block),
+    ("indoreBlock", Kind::Block),
+    ("agraBlock", Kind::Block),
+    ("napoliBlock", Kind::Block),
+    ("ahmedabadBlock", Kind::Block),
+    ("bhilaiBlock", Kind::Block),
+    ("rioBlock", Kind::Block),
+    ("madhugiriBlock", Kind::Block),
+    ("dandeliBlock", Kind::Block),
+    ("lisovoBlock", Kind::Block),
+];
+
+impl BorConfig {
+    /// The known config of Polygon PoS mainnet.
+    ///
+    /// It has no `blockAlloc`: the contract code bor-geth sets at those blocks is only in
+    /// mainnet's genesis file.
+    pub fn mainnet() -> &'static Self {
+        &MAINNET
+    }
+
+    /// The known config of the Amoy testnet.
+    pub fn amoy() -> &'static Self {
+        &AMOY
+    }
+
+    /// The known config of the chain with `chain_id`, if it is mainnet or Amoy.
+    pub fn known(chain_id: u64) -> Option<&'static Self> {
+        match chain_id {
+            MAINNET_CHAIN_ID => Some(Self::mainnet()),
+            AMOY_CHAIN_ID => Some(Self::amoy()),
+            _ => None,
+        }
+    }
+
+    /// The config of the chain with `chain_id` and `genesis`.
+    ///
+    /// That is the `bor` section of `genesis`; without one, the [known](Self::known) config
+    /// of mainnet or Amoy, or an empty config for other chains.
+    pub fn for_chain(chain_id: u64, genesis: &Genesis) -> Result<Self, ScheduleError> {
+        match Self::from_genesis(genesis)? {
+            Some(config) => Ok(config),
+            None => Ok(Self::known(chain_id).cloned().unwrap_or_default()),
+        }
+    }
+
+    /// The block-keyed number `key` of the config, e.g. `sprint`, if it is set.
+    ///
+    /// `key` is one of `period`, `producerDelay`, `sprint`, `backupMultiplier` and
+    /// `stateSyncConfirmationDelay`, as written in the genesis.
+    pub fn block_keyed(&self, key: &str) -> Option<&ForkValue<u64>> {
+        let value = match key {
+            "period" => &self.period,
+            "producerDelay" => &self.producer_delay,
+            "sprint" => &self.sprint,
+            "backupMultiplier" => &self.backup_multiplier,
+            "stateSyncConfirmationDelay" => &self.state_sync_confirmation_delay,
+            _ => return None,
+        };
+        (!value.is_empty()).then_some(value)
+    }
+
+    /// Read the `bor` section of `genesis`, if it has one.
+    pub fn from_genesis(genesis: &Genesis) -> Result<Option<Self>, ScheduleError> {
+        genesis.config.extra_fields.get("bor").map(Self::from_value).transpose()
+    }
+
+    /// Read a `bor` section.
+    ///
+    /// A malformed section is reported under the first key that fails to read on its own.
+    pub fn from_value(bor: &serde_json::Value) -> Result<Self, ScheduleError> {
+        serde_json::from_value(bor.clone()).map_err(|e| {
+            let name = KEYS
+                .iter()
+                .map(|(key, _)| *key)
+                .find(|key| {
+                    bor.get(key).is_some_and(|value| {
+                        let alone = serde_json::Map::from_iter([(key.to_string(), value.clone())]);
+                        serde_json::from_value::<Self>(alone.into()).is_err()
+                    })
+                })
+                .unwrap_or("bor");
+            ScheduleError::Invalid { name, reason: e.to_string() }
+        })
+    }
+
+    /// Activation blocks of the Bor forks, by key, in fork order.
+    pub fn fork_blocks(&self) -> [(&'static str, Option<u64>); 11] {
+        [
+            ("jaipurBlock", self.jaipur_block),
+            ("delhiBlock", self.delhi_block),
+            ("indoreBlock", self.indore_block),
+            ("agraBlock", self.agra_block),
+            ("napoliBlock", self.napoli_block),
+            ("ahmedabadBlock", self.ahmedabad_block),
+            ("bhilaiBlock", self.bhilai_block),
+            ("rioBlock", self.rio_block),
+            ("madhugiriBlock", self.madhugiri_block),
+            ("dandeliBlock", self.dandeli_block),
+            ("lisovoBlock", self.lisovo_block),
+        ]
+    }
+
+    /// Check that a node can run the chain.
+    ///
+    /// Of the block-keyed parameters that are given, `sprint` and `period` must have a
+    /// non-zero value from block 0 on, and `producerDelay` and `backupMultiplier` a
+    /// value from block 0 on. No contract may be the zero address, and the forks that
+    /// are given must activate in order.
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        for (name, schedule) in [("sprint", &self.sprint), ("period", &self.period)] {
+            if !schedule.is_empty() {
+                schedule.validate_length(name)?;
+            }
+        }
+        for (name, schedule) in
+            [("producerDelay", &self.producer_delay), ("backupMultiplier", &self.backup_multiplier)]
+        {
+            if !schedule.is_empty() {
+                schedule.validate_from_genesis(name)?;
+            }
+        }
+
+        let contracts = [
+            ("validatorContract", self.validator_contract.map(|address| (0, address))),
+            ("stateReceiverContract", self.state_receiver_contract.map(|address| (0, address))),
+        ];
+        let upgrades = self.contract_upgrades.iter().flat_map(|(block, upgrade)| {
+            [upgrade.validator_contract, upgrade.state_receiver_contract]
+                .map(|address| ("contractUpgrades", address.map(|address| (block, address))))
+        });
+        let burnt = self.burnt_contract.iter().map(|(block, address)| {
+            ("burntContract", Some((block, *address)))
+        });
+        for (name, contract) in contracts.into_iter().chain(upgrades).chain(burnt) {
+            if let Some((block, Address::ZERO)) = contract {
+                return Err(ScheduleError::Zero { name, block });
+            }
+        }
+
+        let mut previous: Option<(&'static str, u64)> = None;
+        for (name, block) in self.fork_blocks() {
+            let Some(block) = block else { continue };
+            if let Some((previous, previous_block)) = previous {
+                if block < previous_block {
+                    return Err(ScheduleError::OutOfOrder {
+                        name,
+                        block,
+                        previous,
+                        previous_block,
+                    });
+                }
+            }
+            previous = Some((name, block));
+        }
+        Ok(())
+    }
+
+    /// JSON schema of the keys [`BorConfig`] reads.
+    pub fn json_schema() -> serde_json::Value {
+        let block = serde_json::json!({ "type": "integer", "minimum": 0 });
+        let address = serde_json::json!({ "$ref": "#/$defs/address" });
+        let by_block = |value: serde_json::Value| {
+            serde_json::json!({
+                "type": "object",
+                "propertyNames": { "pattern": "^[0-9]+$" },
+                "additionalProperties": value
+            })
+        };
+        let properties: serde_json::Map<String, serde_json::Value> = KEYS
+            .iter()
+            .map(|(key, kind)| {
+                let schema = match kind {
+                    Kind::Block => block.clone(),
+                    Kind::Address => address.clone(),
+                    Kind::Numbers => by_block(block.clone()),
+                    Kind::SignedNumbers => by_block(serde_json::json!({ "type": "integer" })),
+                    Kind::Addresses => by_block(address.clone()),
+                    Kind::Objects => by_block(serde_json::json!({ "type": "object" })),
+                };
+                (key.to_string(), schema)
+            })
+            .collect();
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "BorConfig",
+            "description": "The `bor` section of a Bor genesis config.",
+            "type": "object",
+            "properties": properties,
+            "$defs": {
+                "address": { "type": "string", "pattern": "^0x[0-9a-fA-F]{40}$" }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bor: serde_json::Value) -> BorConfig {
+        BorConfig::from_value(&bor).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = config(serde_json::json!({
+            "sprint": { "0": 64, "32": 16 },
+            "period": { "0": 2 },
+            "burntContract": { "0": "0x000000000000000000000000000000000000dead" },
+            "jaipurBlock": 0,
+            "delhiBlock": 32,
+            "rioBlock": 32
+        }));
+        assert_eq!(valid.validate(), Ok(()));
+        assert_eq!(BorConfig::default().validate(), Ok(()));
+
+        let stalled = config(serde_json::json!({ "period": { "0": 2, "64": 0 } }));
+        assert_eq!(stalled.validate(), Err(ScheduleError::Zero { name: "period", block: 64 }));
+
+        let receiver = config(serde_json::json!({
+            "stateReceiverContract": "0x0000000000000000000000000000000000000000"
+        }));
+        assert_eq!(
+            receiver.validate(),
+            Err(ScheduleError::Zero { name: "stateReceiverContract", block: 0 })
+        );
+        let upgrade = config(serde_json::json!({
+            "contractUpgrades": {
+                "64": { "validatorContract": "0x0000000000000000000000000000000000000000" }
+            }
+        }));
+        assert_eq!(
+            upgrade.validate(),
+            Err(ScheduleError::Zero { name: "contractUpgrades", block: 64 })
+        );
+
+        let reordered = config(serde_json::json!({ "delhiBlock": 64, "indoreBlock": 32 }));
+        assert_eq!(
+            reordered.validate(),
+            Err(ScheduleError::OutOfOrder {
+                name: "indoreBlock",
+                block: 32,
+                previous: "delhiBlock",
+                previous_block: 64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_for_chain() {
+        let empty = Genesis::default();
+        assert_eq!(BorConfig::for_chain(MAINNET_CHAIN_ID, &empty).unwrap(), *BorConfig::mainnet());
+        assert_eq!(BorConfig::for_chain(AMOY_CHAIN_ID, &empty).unwrap(), *BorConfig::amoy());
+        assert_eq!(BorConfig::for_chain(1337, &empty).unwrap(), BorConfig::default());
+
+        // A genesis section wins over the known config.
+        let genesis: Genesis = serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 137, "bor": { "sprint": { "0": 4 } } },
+            "alloc": {}
+        }))
+        .unwrap();
+        let config = BorConfig::for_chain(MAINNET_CHAIN_ID, &genesis).unwrap();
+        assert_eq!(config.sprint, ForkValue::constant(4));
+        assert!(config.burnt_contract.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_key_is_named() {
+        let err = BorConfig::from_value(&serde_json::json!({
+            "sprint": { "0": 16 },
+            "burntContract": { "0": "0xdead" }
+        }))
+        .unwrap_err();
+        assert!(matches!(err, ScheduleError::Invalid { name: "burntContract", .. }), "{err}");
+
+        // Keys bor-geth writes that boreth does not read are ignored.
+        let ignored = config(serde_json::json!({ "parallelUniverseBlock": 0 }));
+        assert_eq!(ignored, BorConfig::default());
+    }
+}
@@ -12,7 +12,10 @@ use reth_ethereum_forks::{
     ForkId, Hardfork, Hardforks, Head,
 };
 
-use crate::BorHardfork;
+use crate::{
+    AmoyBorHardforks, BorConfig, BorHardfork, BorHardforks, ForkValue, MainnetBorHardforks,
+    ScheduleError, AMOY_CHAIN_ID, MAINNET_CHAIN_ID,
+};
 
 /// Polygon Bor chain specification.
 ///
@@ -24,14 +27,19 @@ pub struct BorChainSpec {
     inner: ChainSpec,
     /// Polygon-specific hardfork activation conditions, ordered by hardfork.
     bor_hardforks: BTreeMap<BorHardfork, ForkCondition>,
+    /// The chain's `bor` config, see [`BorConfig::for_chain`].
+    bor_config: BorConfig,
 }
 
 impl BorChainSpec {
     /// Create a new `BorChainSpec` from an inner [`ChainSpec`] and Bor hardfork map.
     ///
+    /// The chain's parameters come from its [`BorConfig::for_chain`].
+    ///
     /// # Panics
     ///
-    /// Panics if the hardforks are not in ascending activation order.
+    /// Panics if the hardforks are not in ascending activation order, or if the genesis
+    /// `bor` config cannot be read; [`bor_genesis_chainspec`] reports the latter instead.
     pub fn new(inner: ChainSpec, bor_hardforks: BTreeMap<BorHardfork, ForkCondition>) -> Self {
         // Validate hardfork ordering — block-activated forks must be in ascending order.
         let mut prev_block: Option<u64> = None;
@@ -47,7 +55,9 @@ impl BorChainSpec {
             }
         }
 
-        Self { inner, bor_hardforks }
+        let bor_config = BorConfig::for_chain(inner.chain.id(), &inner.genesis)
+            .expect("readable genesis bor config");
+        Self { inner, bor_hardforks, bor_config }
     }
 
     /// Returns a reference to the inner Ethereum [`ChainSpec`].
@@ -65,6 +75,11 @@ impl BorChainSpec {
         &self.bor_hardforks
     }
 
+    /// Returns the chain's `bor` config.
+    pub fn bor_config(&self) -> &BorConfig {
+        &self.bor_config
+    }
+
     /// Returns all unique, non-zero fork block numbers from Ethereum hardforks only,
     /// sorted in ascending order. Used for fork ID and fork filter computation.
     ///
@@ -110,14 +125,69 @@ impl BorChainSpec {
 
     /// Check if a specific Bor hardfork is active at the given block number.
     pub fn is_bor_fork_active_at_block(&self, fork: BorHardfork, block: u64) -> bool {
-        self.bor_hardforks
-            .get(&fork)
-            .map(|condition| match condition {
-                ForkCondition::Block(activation_block) => block >= *activation_block,
-                ForkCondition::Never => false,
-                _ => false,
-            })
-            .unwrap_or(false)
+        BorHardforks::is_bor_fork_active_at_block(self, fork, block)
+    }
+}
+
+impl BorHardforks for BorChainSpec {
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition {
+        self.bor_hardforks.bor_fork_activation(fork)
+    }
+
+    fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+        self.bor_config.block_keyed(key).cloned()
+    }
+}
+
+/// The node runs on reth's [`ChainSpec`], which has no Bor forks of its own: mainnet and Amoy
+/// use their known schedules, other chains the `<fork>Block` entries of the genesis `bor`
+/// config, as in a bor-geth genesis file. Parameters such as `sprint` are read from the
+/// genesis `bor` config, or the known config of mainnet and Amoy without one.
+impl BorHardforks for ChainSpec {
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition {
+        match self.chain.id() {
+            MAINNET_CHAIN_ID => MainnetBorHardforks.bor_fork_activation(fork),
+            AMOY_CHAIN_ID => AmoyBorHardforks.bor_fork_activation(fork),
+            _ => genesis_fork_activation(&self.genesis, fork),
+        }
+    }
+
+    fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+        match self.genesis.config.extra_fields.get("bor") {
+            // Checked by `validate_bor_config` when the node starts.
+            Some(bor) => bor
+                .get(key)
+                .and_then(|value| serde_json::from_value(value.clone()).ok())
+                .filter(|value: &ForkValue<u64>| !value.is_empty()),
+            None => BorConfig::known(self.chain.id())?.block_keyed(key).cloned(),
+        }
+    }
+}
+
+/// Read the activation block of `fork` from the genesis `bor` config.
+fn genesis_fork_activation(genesis: &Genesis, fork: BorHardfork) -> ForkCondition {
+    let name = fork.name();
+    let key = format!("{}{}Block", name[..1].to_ascii_lowercase(), &name[1..]);
+    genesis
+        .config
+        .extra_fields
+        .get("bor")
+        .and_then(|bor| bor.get(&key))
+        .and_then(|block| block.as_u64())
+        .map_or(ForkCondition::Never, ForkCondition::Block)
+}
+
+/// Check the genesis `bor` config, if any, with [`BorConfig::validate`].
+///
+/// Each of `sprint`, `period`, `producerDelay` and `backupMultiplier` that is given must
+/// have a value from block 0 on, and the sprint length and period must never be zero, so
+/// that no block finds its parameters missing once the node runs.
+///
+/// [`BorConfig::validate`]: crate::BorConfig::validate
+pub fn validate_bor_config(genesis: &Genesis) -> Result<(), ScheduleError> {
+    match crate::BorConfig::from_genesis(genesis)? {
+        Some(config) => config.validate(),
+        None => Ok(()),
     }
 }
 
@@ -237,20 +307,28 @@ impl EthereumHardforks for BorChainSpec {
 
 /// Build a mainnet `BorChainSpec` with Polygon PoS mainnet hardforks.
 pub fn bor_mainnet_chainspec(inner: ChainSpec) -> BorChainSpec {
-    let mut bor_hardforks = BTreeMap::new();
-    for fork in BorHardfork::all() {
-        bor_hardforks.insert(*fork, ForkCondition::Block(fork.mainnet_block()));
-    }
-    BorChainSpec::new(inner, bor_hardforks)
+    BorChainSpec::new(inner, schedule(MainnetBorHardforks))
 }
 
 /// Build an Amoy testnet `BorChainSpec` with Polygon Amoy hardforks.
 pub fn bor_amoy_chainspec(inner: ChainSpec) -> BorChainSpec {
-    let mut bor_hardforks = BTreeMap::new();
-    for fork in BorHardfork::all() {
-        bor_hardforks.insert(*fork, ForkCondition::Block(fork.amoy_block()));
-    }
-    BorChainSpec::new(inner, bor_hardforks)
+    BorChainSpec::new(inner, schedule(AmoyBorHardforks))
+}
+
+/// Build the `BorChainSpec` of a chain from its genesis, e.g. a devnet's.
+///
+/// The Bor forks are those [`ChainSpec`] reads from the genesis (the known schedules
+/// for mainnet and Amoy), and the `bor` config is checked with [`validate_bor_config`].
+pub fn bor_genesis_chainspec(genesis: Genesis) -> Result<BorChainSpec, ScheduleError> {
+    validate_bor_config(&genesis)?;
+    let inner = ChainSpec::from(genesis);
+    let forks = schedule(inner.clone());
+    Ok(BorChainSpec::new(inner, forks))
+}
+
+/// Collect a schedule into the hardfork map held by [`BorChainSpec`].
+pub(crate) fn schedule(forks: impl BorHardforks) -> BTreeMap<BorHardfork, ForkCondition> {
+    BorHardfork::all().iter().map(|fork| (*fork, forks.bor_fork_activation(*fork))).collect()
 }
 
 #[cfg(test)]
@@ -329,6 +407,111 @@ mod tests {
         assert_eq!(bor_id.hash, inner_id.hash);
     }
 
+    #[test]
+    fn test_reth_chainspec_schedule() {
+        let mainnet = mainnet_spec().into_inner();
+        assert!(mainnet.is_jaipur_active_at_block(23_850_000));
+        assert_eq!(mainnet.bor_sprint_size(38_189_056), 16);
+
+        let genesis: Genesis = serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 1337, "bor": { "jaipurBlock": 0, "delhiBlock": 32 } },
+            "alloc": {}
+        }))
+        .unwrap();
+        let devnet = ChainSpecBuilder::default()
+            .chain(Chain::from_id(1337))
+            .genesis(genesis)
+            .london_activated()
+            .build();
+        assert_eq!(devnet.bor_fork_activation(BorHardfork::Delhi), ForkCondition::Block(32));
+        assert_eq!(devnet.bor_fork_activation(BorHardfork::Rio), ForkCondition::Never);
+        assert_eq!(devnet.bor_sprint_size(31), 64);
+        assert_eq!(devnet.bor_sprint_size(32), 16);
+
+        // Parameters the genesis gives win over the fork schedule's.
+        let genesis: Genesis = serde_json::from_value(serde_json::json!({
+            "config": {
+                "chainId": 1337,
+                "bor": { "delhiBlock": 0, "sprint": { "0": 4 }, "period": { "0": 1 } }
+            },
+            "alloc": {}
+        }))
+        .unwrap();
+        let devnet = ChainSpecBuilder::default()
+            .chain(Chain::from_id(1337))
+            .genesis(genesis)
+            .london_activated()
+            .build();
+        assert_eq!(devnet.bor_sprint_size(100), 4);
+        assert_eq!(devnet.bor_period(100), 1);
+        assert_eq!(devnet.bor_producer_delay(100), 4);
+        assert_eq!(BorChainSpec::new(devnet.clone(), schedule(devnet)).bor_sprint_size(100), 4);
+    }
+
+    #[test]
+    fn test_genesis_chainspec() {
+        let genesis: Genesis = serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 1337, "bor": { "delhiBlock": 32, "sprint": { "0": 4 } } },
+            "alloc": {}
+        }))
+        .unwrap();
+        let devnet = bor_genesis_chainspec(genesis).unwrap();
+        assert_eq!(devnet.chain().id(), 1337);
+        assert!(devnet.is_bor_fork_active_at_block(BorHardfork::Delhi, 32));
+        assert!(!devnet.is_bor_fork_active_at_block(BorHardfork::Rio, u64::MAX));
+
+        let gap: Genesis = serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 1337, "bor": { "sprint": { "64": 16 } } },
+            "alloc": {}
+        }))
+        .unwrap();
+        assert_eq!(
+            bor_genesis_chainspec(gap).unwrap_err(),
+            ScheduleError::Missing { name: "sprint", block: 0 }
+        );
+    }
+
+    #[test]
+    fn test_bor_config_validation() {
+        let genesis = |bor: serde_json::Value| -> Genesis {
+            serde_json::from_value(serde_json::json!({
+                "config": { "chainId": 1337, "bor": bor },
+                "alloc": {}
+            }))
+            .unwrap()
+        };
+        let valid = genesis(serde_json::json!({
+            "sprint": { "0": 64, "32": 16 },
+            "period": { "0": 2 },
+            "backupMultiplier": { "0": 0 }
+        }));
+        assert_eq!(validate_bor_config(&valid), Ok(()));
+
+        let late = genesis(serde_json::json!({ "sprint": { "32": 16 } }));
+        assert_eq!(
+            validate_bor_config(&late),
+            Err(ScheduleError::Missing { name: "sprint", block: 0 })
+        );
+
+        let zero = genesis(serde_json::json!({ "sprint": { "0": 0 } }));
+        assert_eq!(
+            validate_bor_config(&zero),
+            Err(ScheduleError::Zero { name: "sprint", block: 0 })
+        );
+
+        let malformed = genesis(serde_json::json!({ "period": [2] }));
+        assert!(matches!(
+            validate_bor_config(&malformed),
+            Err(ScheduleError::Invalid { name: "period", .. })
+        ));
+
+        let upgrade = genesis(serde_json::json!({ "contractUpgrades": { "64": "0x1002" } }));
+        assert!(matches!(
+            validate_bor_config(&upgrade),
+            Err(ScheduleError::Invalid { name: "contractUpgrades", .. })
+        ));
+    }
+
     #[test]
     fn test_fork_filter_at_genesis() {
         let spec = mainnet_spec();
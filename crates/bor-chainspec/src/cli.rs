@@ -12,8 +12,14 @@ use reth_cli::chainspec::ChainSpecParser;
 ///
 /// Supported chains:
 /// - `"amoy"` — Polygon Amoy testnet (chain ID 80002)
-/// - `"polygon"` / `"mainnet"` — Polygon PoS mainnet (chain ID 137)
+/// - `"polygon"` / `"polygon-mainnet"` / `"mainnet"` — Polygon PoS mainnet (chain ID 137)
 /// - Any file path or inline JSON genesis — parsed via Reth's standard genesis parser
+///
+/// A genesis whose `bor` config leaves blocks without a sprint length is rejected here,
+/// before the node starts; see [`validate_bor_config`](crate::validate_bor_config).
+///
+/// The node checks the genesis hash of mainnet and Amoy at startup; see
+/// [`validate_genesis_hash`](crate::validate_genesis_hash).
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 pub struct BorChainSpecParser;
@@ -26,10 +32,11 @@ impl ChainSpecParser for BorChainSpecParser {
     fn parse(s: &str) -> eyre::Result<Arc<ChainSpec>> {
         match s {
             "amoy" => Ok(Arc::new(crate::bor_amoy_genesis().into_inner())),
-            "polygon" | "mainnet" => Ok(Arc::new(crate::bor_mainnet_genesis().into_inner())),
+            "polygon" | "polygon-mainnet" | "mainnet" => Ok(Arc::new(crate::bor_mainnet_genesis().into_inner())),
             _ => {
                 // Fall back to parsing as a genesis JSON file path or inline JSON
                 let genesis = reth_cli::chainspec::parse_genesis(s)?;
+                crate::validate_bor_config(&genesis)?;
                 Ok(Arc::new(genesis.into()))
             }
         }
@@ -59,6 +66,19 @@ mod tests {
         assert_eq!(spec.chain_id(), 137);
     }
 
+    #[test]
+    fn test_parse_polygon_mainnet_alias() {
+        let spec = BorChainSpecParser::parse("polygon-mainnet").unwrap();
+        assert_eq!(spec.chain_id(), 137);
+    }
+
+    #[test]
+    fn test_parse_rejects_sprint_gap() {
+        let genesis = r#"{"config":{"chainId":1337,"bor":{"sprint":{"64":16}}},"alloc":{}}"#;
+        let err = BorChainSpecParser::parse(genesis).unwrap_err();
+        assert!(err.to_string().contains("sprint has no value at block 0"), "{err}");
+    }
+
     #[test]
     fn test_parse_unknown_fails() {
         assert!(BorChainSpecParser::parse("nonexistent-chain").is_err());
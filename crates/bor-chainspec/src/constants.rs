@@ -1,6 +1,6 @@
 //! Bor chain constants and well-known addresses.
 
-use alloy_primitives::{Address, address};
+use alloy_primitives::{Address, B256, address, b256};
 
 /// System address used for system transactions (2^160 - 2).
 pub const SYSTEM_ADDRESS: Address = address!("fffffffffffffffffffffffffffffffffffffffe");
@@ -17,17 +17,75 @@ pub const MAINNET_CHAIN_ID: u64 = 137;
 /// Polygon Amoy testnet chain ID.
 pub const AMOY_CHAIN_ID: u64 = 80002;
 
+/// Genesis block hash of Polygon PoS mainnet.
+pub const MAINNET_GENESIS_HASH: B256 =
+    b256!("a9c28ce2141b56c474f1dc504bee9b01eb1bd7d1a507580d5519d4437a97de1b");
+
+/// Genesis block hash of the Polygon Amoy testnet.
+pub const AMOY_GENESIS_HASH: B256 =
+    b256!("7202b2b53c5a0836e773e319d18922cc756dd67432f9a1f65352b61f4406c697");
+
 /// Default sprint size (number of blocks per sprint).
 pub const SPRINT_SIZE: u64 = 16;
 
+/// Sprint size before Delhi.
+pub const PRE_DELHI_SPRINT_SIZE: u64 = 64;
+
+/// Span size before Rio.
+pub const SPAN_SIZE: u64 = 6400;
+
+/// Span size from Rio.
+pub const RIO_SPAN_SIZE: u64 = 1600;
+
 /// Default block period in seconds.
 pub const BLOCK_PERIOD: u64 = 2;
 
-/// Length of the vanity portion of extra data (bytes).
-pub const EXTRADATA_VANITY_LEN: usize = 32;
+/// Delay of the first block of a sprint before Delhi, in seconds.
+pub const PRE_DELHI_PRODUCER_DELAY: u64 = 6;
+
+/// Delay of the first block of a sprint from Delhi, in seconds.
+pub const PRODUCER_DELAY: u64 = 4;
+
+/// Extra delay per position a backup producer is behind the proposer, in seconds.
+pub const BACKUP_MULTIPLIER: u64 = 2;
+
+/// Seconds a block's timestamp may be ahead of the local clock.
+pub const ALLOWED_FUTURE_BLOCK_TIME: u64 = 15;
+
+/// Difficulty of a block whose signer is furthest from being in turn.
+///
+/// The in-turn signer's difficulty is the size of the validator set, and each position
+/// further out of turn lowers it by one, down to this floor.
+pub const DIFF_NO_TURN: u64 = 1;
+
+/// Divisor of the parent gas limit bounding the change of a child's.
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 1024;
+
+/// Lowest gas limit a block may have.
+pub const MIN_GAS_LIMIT: u64 = 5000;
 
-/// Length of the seal (signature) portion of extra data (bytes).
-pub const EXTRADATA_SEAL_LEN: usize = 65;
+/// Highest gas limit a block may have (2^63 - 1).
+pub const MAX_GAS_LIMIT: u64 = 0x7fffffffffffffff;
+
+/// Block gas limit before Bhilai.
+pub const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// Block gas limit from Bhilai.
+pub const BHILAI_BLOCK_GAS_LIMIT: u64 = 45_000_000;
+
+/// Base fee change denominator before Delhi.
+pub const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Base fee change denominator from Delhi.
+pub const DELHI_BASE_FEE_CHANGE_DENOMINATOR: u64 = 16;
+
+/// Base fee change denominator from Bhilai.
+pub const BHILAI_BASE_FEE_CHANGE_DENOMINATOR: u64 = 64;
+
+/// Largest contract code size, in bytes (EIP-170).
+pub const MAX_CODE_SIZE: usize = 24_576;
+
+pub use bor_primitives::{EXTRADATA_SEAL_LEN, EXTRADATA_VANITY_LEN};
 
 /// State sync delay in seconds (post-Indore hard fork).
 pub const STATE_SYNC_DELAY: u64 = 128;
@@ -66,4 +124,9 @@ mod tests {
         assert_eq!(MAINNET_CHAIN_ID, 137);
         assert_eq!(AMOY_CHAIN_ID, 80002);
     }
+
+    #[test]
+    fn test_max_gas_limit() {
+        assert_eq!(MAX_GAS_LIMIT, i64::MAX as u64);
+    }
 }
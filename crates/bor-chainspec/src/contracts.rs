@@ -0,0 +1,107 @@
+//! Upgrades of the Bor system contracts.
+//!
+//! The ValidatorSet and StateReceiver contracts live at fixed genesis addresses
+//! and are called through fixed selectors. A chain that moves either contract,
+//! or changes the function the consensus engine calls, lists the change under
+//! `contractUpgrades` in the genesis `bor` config, keyed by the block it takes
+//! effect at:
+//!
+//! ```json
+//! "contractUpgrades": {
+//!     "41000000": { "stateReceiverContract": "0x0000000000000000000000000000000000001002" }
+//! }
+//! ```
+//!
+//! Upgrades accumulate: a field left out keeps the value of the last upgrade
+//! that set it, or the genesis value.
+
+use alloy_genesis::Genesis;
+use alloy_primitives::{Address, FixedBytes};
+use serde::{Deserialize, Serialize};
+
+use crate::{ForkValue, ScheduleError};
+
+/// Changes to the system contracts from a block on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ContractUpgrade {
+    /// New address of the ValidatorSet contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_contract: Option<Address>,
+    /// New address of the StateReceiver contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_receiver_contract: Option<Address>,
+    /// New selector of the function called instead of `commitSpan(uint256,bytes)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_span_selector: Option<FixedBytes<4>>,
+    /// New selector of the function called instead of `onStateReceive(uint256,bytes)`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_state_receive_selector: Option<FixedBytes<4>>,
+}
+
+/// Read the `contractUpgrades` of the genesis `bor` config.
+///
+/// A chain without any has an empty schedule.
+pub fn genesis_contract_upgrades(
+    genesis: &Genesis,
+) -> Result<ForkValue<ContractUpgrade>, ScheduleError> {
+    let upgrades = genesis
+        .config
+        .extra_fields
+        .get("bor")
+        .and_then(|bor| bor.get("contractUpgrades"));
+    match upgrades {
+        Some(upgrades) => serde_json::from_value(upgrades.clone()).map_err(|e| {
+            ScheduleError::Invalid { name: "contractUpgrades", reason: e.to_string() }
+        }),
+        None => Ok(ForkValue::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    fn genesis(bor: serde_json::Value) -> Genesis {
+        serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 1337, "bor": bor },
+            "alloc": {}
+        }))
+        .unwrap()
+    }
+
+    const RECEIVER: &str = "0x0000000000000000000000000000000000001002";
+
+    #[test]
+    fn test_genesis_contract_upgrades() {
+        let upgrades = genesis_contract_upgrades(&genesis(serde_json::json!({
+            "contractUpgrades": {
+                "64": { "stateReceiverContract": RECEIVER },
+                "128": { "commitSpanSelector": "0x12345678" }
+            }
+        })))
+        .unwrap();
+
+        let at_64 = upgrades.value_at(64).unwrap();
+        let receiver = address!("0000000000000000000000000000000000001002");
+        assert_eq!(at_64.state_receiver_contract, Some(receiver));
+        assert_eq!(at_64.validator_contract, None);
+        let at_200 = upgrades.value_at(200).unwrap();
+        assert_eq!(at_200.commit_span_selector, Some(FixedBytes([0x12, 0x34, 0x56, 0x78])));
+        assert_eq!(upgrades.value_at(63), None);
+    }
+
+    #[test]
+    fn test_missing_and_malformed_upgrades() {
+        assert!(genesis_contract_upgrades(&genesis(serde_json::json!({}))).unwrap().is_empty());
+
+        let typo = genesis(serde_json::json!({
+            "contractUpgrades": { "64": { "stateReceiver": RECEIVER } }
+        }));
+        assert!(matches!(
+            genesis_contract_upgrades(&typo),
+            Err(ScheduleError::Invalid { name: "contractUpgrades", .. })
+        ));
+    }
+}
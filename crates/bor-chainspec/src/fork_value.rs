@@ -0,0 +1,256 @@
+//! Block-keyed chain parameters.
+//!
+//! Bor's chain config gives several parameters (`period`, `producerDelay`,
+//! `sprint`, `backupMultiplier`, `stateSyncConfirmationDelay`,
+//! `burntContract`) as a map from activation block to value, where a block
+//! uses the entry with the greatest key not above it. [`ForkValue`] is that map,
+//! and reads and writes the same JSON shape: `{"0": 64, "38189056": 16}`.
+//!
+//! A map from a malformed genesis may not cover block 0, or may hold a zero
+//! sprint length that a block number is later divided by. Such maps are caught
+//! when the chain config is loaded, see [`ForkValue::validate_from_genesis`],
+//! and lookups that can miss return a [`ScheduleError`] rather than panic.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Error in a block-keyed parameter.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScheduleError {
+    /// The parameter has no value at a block.
+    #[error("{name} has no value at block {block}")]
+    Missing {
+        /// Name of the parameter.
+        name: &'static str,
+        /// The block.
+        block: u64,
+    },
+    /// A length parameter is zero from some block on.
+    #[error("{name} is zero from block {block}")]
+    Zero {
+        /// Name of the parameter.
+        name: &'static str,
+        /// The block the zero takes effect at.
+        block: u64,
+    },
+    /// A fork activates before a fork that precedes it.
+    #[error("{name} {block} is before {previous} {previous_block}")]
+    OutOfOrder {
+        /// Name of the fork block parameter.
+        name: &'static str,
+        /// Its activation block.
+        block: u64,
+        /// Name of the preceding fork block parameter.
+        previous: &'static str,
+        /// Its activation block.
+        previous_block: u64,
+    },
+    /// The parameter is not a valid map from block to value.
+    #[error("invalid {name}: {reason}")]
+    Invalid {
+        /// Name of the parameter.
+        name: &'static str,
+        /// Why it could not be read.
+        reason: String,
+    },
+}
+
+/// A parameter that changes at given blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ForkValue<T>(BTreeMap<u64, T>);
+
+impl<T> Default for ForkValue<T> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<T> ForkValue<T> {
+    /// A parameter with `value` from genesis on.
+    pub fn constant(value: T) -> Self {
+        Self(BTreeMap::from([(0, value)]))
+    }
+
+    /// Use `value` from `block` on.
+    pub fn with_value(mut self, block: u64, value: T) -> Self {
+        self.0.insert(block, value);
+        self
+    }
+
+    /// The value in force at `block`, or `None` before the first entry.
+    pub fn value_at(&self, block: u64) -> Option<&T> {
+        self.0.range(..=block).next_back().map(|(_, value)| value)
+    }
+
+    /// The value of parameter `name` in force at `block`.
+    pub fn try_value_at(&self, name: &'static str, block: u64) -> Result<&T, ScheduleError> {
+        self.value_at(block).ok_or(ScheduleError::Missing { name, block })
+    }
+
+    /// Check that parameter `name` has a value from genesis on.
+    pub fn validate_from_genesis(&self, name: &'static str) -> Result<(), ScheduleError> {
+        self.try_value_at(name, 0).map(|_| ())
+    }
+
+    /// The block the value in force at `block` took effect at.
+    pub fn activation_at(&self, block: u64) -> Option<u64> {
+        self.0.range(..=block).next_back().map(|(&activation, _)| activation)
+    }
+
+    /// Activation blocks and values, in block order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &T)> {
+        self.0.iter().map(|(&block, value)| (block, value))
+    }
+
+    /// Returns `true` if no value is set at any block.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: Copy> ForkValue<T> {
+    /// The value in force at `block`, or `default` before the first entry.
+    pub fn value_at_or(&self, block: u64, default: T) -> T {
+        self.value_at(block).copied().unwrap_or(default)
+    }
+}
+
+impl ForkValue<u64> {
+    /// Check that length parameter `name` has a value from genesis on and is never zero.
+    pub fn validate_length(&self, name: &'static str) -> Result<(), ScheduleError> {
+        self.validate_from_genesis(name)?;
+        match self.iter().find(|(_, value)| **value == 0) {
+            Some((block, _)) => Err(ScheduleError::Zero { name, block }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `true` if `block` starts a new period of length parameter `name`, e.g. a sprint.
+    ///
+    /// Periods are counted from block 0 like Bor does, not from the activation block.
+    pub fn is_period_start(&self, name: &'static str, block: u64) -> Result<bool, ScheduleError> {
+        let length = *self.try_value_at(name, block)?;
+        block
+            .checked_rem(length)
+            .map(|rem| rem == 0)
+            .ok_or(ScheduleError::Zero { name, block: self.activation_at(block).unwrap_or(block) })
+    }
+}
+
+impl<T> FromIterator<(u64, T)> for ForkValue<T> {
+    fn from_iter<I: IntoIterator<Item = (u64, T)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BorHardfork, BorHardforks, MainnetBorHardforks};
+    use alloy_primitives::{address, Address};
+
+    #[test]
+    fn test_greatest_key_not_above_block() {
+        let sprint: ForkValue<u64> = [(0, 64), (38_189_056, 16)].into_iter().collect();
+        assert_eq!(sprint.value_at(0), Some(&64));
+        assert_eq!(sprint.value_at(38_189_055), Some(&64));
+        assert_eq!(sprint.value_at(38_189_056), Some(&16));
+        assert_eq!(sprint.value_at(u64::MAX), Some(&16));
+        assert_eq!(sprint.activation_at(40_000_000), Some(38_189_056));
+    }
+
+    #[test]
+    fn test_before_first_entry() {
+        // Contracts only set from a later block have no value before it, rather than panicking.
+        let burnt = ForkValue::default().with_value(23_850_000, Address::ZERO);
+        assert_eq!(burnt.value_at(23_849_999), None);
+        assert_eq!(burnt.value_at_or(1, Address::with_last_byte(1)), Address::with_last_byte(1));
+        assert!(ForkValue::<u64>::default().is_empty());
+    }
+
+    #[test]
+    fn test_matches_mainnet_params() {
+        let delhi = BorHardfork::Delhi.mainnet_block();
+        let sprint = ForkValue::constant(64).with_value(delhi, 16);
+        let producer_delay = ForkValue::constant(6).with_value(delhi, 4);
+        for block in [0, delhi - 1, delhi, delhi + 1, 80_000_000] {
+            assert_eq!(sprint.value_at_or(block, 0), MainnetBorHardforks.bor_sprint_size(block));
+            let delay = MainnetBorHardforks.bor_producer_delay(block);
+            assert_eq!(producer_delay.value_at_or(block, 0), delay);
+        }
+    }
+
+    #[test]
+    fn test_sprint_validation() {
+        let sprint: ForkValue<u64> = [(0, 64), (100, 16)].into_iter().collect();
+        assert_eq!(sprint.validate_length("sprint"), Ok(()));
+        assert_eq!(sprint.is_period_start("sprint", 128), Ok(true));
+        assert_eq!(sprint.is_period_start("sprint", 120), Ok(false));
+
+        let late: ForkValue<u64> = ForkValue::default().with_value(10, 16);
+        assert_eq!(
+            late.validate_length("sprint"),
+            Err(ScheduleError::Missing { name: "sprint", block: 0 })
+        );
+        assert!(late.is_period_start("sprint", 3).is_err());
+
+        let zero = ForkValue::constant(16).with_value(64, 0);
+        assert_eq!(
+            zero.validate_length("sprint"),
+            Err(ScheduleError::Zero { name: "sprint", block: 64 })
+        );
+        assert_eq!(
+            zero.is_period_start("sprint", 70),
+            Err(ScheduleError::Zero { name: "sprint", block: 64 })
+        );
+    }
+
+    /// Arbitrary maps never make a lookup panic, and validated maps never make one fail.
+    #[test]
+    fn test_arbitrary_schedules() {
+        // xorshift64, so the maps are the same on every run.
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..1000 {
+            let entries = next() % 6;
+            let schedule: ForkValue<u64> = (0..entries)
+                .map(|_| {
+                    let block = if next() % 3 == 0 { 0 } else { next() % 1000 };
+                    (block, next() % 4 * 16)
+                })
+                .collect();
+            let valid = schedule.validate_length("sprint").is_ok();
+            for block in (0..20).map(|_| next() % 1200).chain([u64::MAX]) {
+                let start = schedule.is_period_start("sprint", block);
+                if valid {
+                    assert!(start.is_ok(), "{schedule:?} at {block}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bor_config_json() {
+        let json = r#"{
+            "0": "0x0000000000000000000000000000000000000000",
+            "22370000": "0x70bca57f4579f58670ab2d18ef16e02c17553c38"
+        }"#;
+        let burnt: ForkValue<Address> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            burnt.value_at(30_000_000),
+            Some(&address!("70bca57f4579f58670ab2d18ef16e02c17553c38"))
+        );
+        let encoded = serde_json::to_string(&burnt).unwrap();
+        assert_eq!(serde_json::from_str::<ForkValue<Address>>(&encoded).unwrap(), burnt);
+
+        let period: ForkValue<u64> = serde_json::from_str(r#"{"0": 2}"#).unwrap();
+        assert_eq!(period, ForkValue::constant(2));
+    }
+}
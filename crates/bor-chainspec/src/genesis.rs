@@ -12,6 +12,11 @@ const BERLIN_BLOCK: u64 = 29_231_616;
 const LONDON_BLOCK: u64 = 29_231_616;
 
 /// Build the Polygon PoS mainnet genesis configuration (chain 137).
+///
+/// The mainnet genesis alloc is not embedded yet, so this spec's genesis hash is not
+/// [`MAINNET_GENESIS_HASH`](crate::MAINNET_GENESIS_HASH). The node starts on it, and warns
+/// that peers will not share its genesis; pass mainnet's genesis file to `--chain` to
+/// sync from genesis.
 pub fn bor_mainnet_genesis() -> BorChainSpec {
     let hardforks = ChainHardforks::new(vec![
         (Box::new(EthereumHardfork::Frontier) as Box<dyn reth_ethereum_forks::Hardfork>, ForkCondition::Block(0)),
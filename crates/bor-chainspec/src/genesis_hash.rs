@@ -0,0 +1,83 @@
+//! Genesis hash checks for the known Polygon networks.
+//!
+//! A genesis file with the chain ID of mainnet or Amoy but different contents
+//! yields a chain no peer shares, and the node would only notice once every
+//! handshake fails. Checking the computed genesis hash against the network's
+//! known one at startup catches it right away.
+//!
+//! The built-in presets pass as they are: the mainnet preset has no genesis alloc
+//! embedded, so its hash is its own rather than mainnet's, see
+//! [`bor_mainnet_genesis`](crate::bor_mainnet_genesis).
+
+use alloy_primitives::B256;
+use reth_chainspec::EthChainSpec;
+
+use crate::constants::{AMOY_CHAIN_ID, AMOY_GENESIS_HASH, MAINNET_CHAIN_ID, MAINNET_GENESIS_HASH};
+
+/// The genesis hash of the known Polygon network with `chain_id`, if any.
+pub fn known_genesis_hash(chain_id: u64) -> Option<B256> {
+    match chain_id {
+        MAINNET_CHAIN_ID => Some(MAINNET_GENESIS_HASH),
+        AMOY_CHAIN_ID => Some(AMOY_GENESIS_HASH),
+        _ => None,
+    }
+}
+
+/// The genesis hash of the built-in preset of the chain with `chain_id`, if any.
+pub fn preset_genesis_hash(chain_id: u64) -> Option<B256> {
+    match chain_id {
+        MAINNET_CHAIN_ID => Some(crate::bor_mainnet_genesis().genesis_hash()),
+        AMOY_CHAIN_ID => Some(crate::bor_amoy_genesis().genesis_hash()),
+        _ => None,
+    }
+}
+
+/// Check `genesis_hash` against the known genesis of `chain_id`.
+///
+/// Chains other than mainnet and Amoy have no known genesis and always pass, as does
+/// the genesis of the chain's built-in preset.
+pub fn validate_genesis_hash(chain_id: u64, genesis_hash: B256) -> eyre::Result<()> {
+    match known_genesis_hash(chain_id) {
+        Some(expected)
+            if expected != genesis_hash && preset_genesis_hash(chain_id) != Some(genesis_hash) =>
+        {
+            eyre::bail!(
+                "genesis hash {genesis_hash} does not match chain {chain_id}'s genesis {expected}"
+            )
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_chain_passes() {
+        assert_eq!(known_genesis_hash(1), None);
+        assert!(validate_genesis_hash(1, B256::ZERO).is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_is_rejected() {
+        assert!(validate_genesis_hash(MAINNET_CHAIN_ID, MAINNET_GENESIS_HASH).is_ok());
+        assert!(validate_genesis_hash(MAINNET_CHAIN_ID, AMOY_GENESIS_HASH).is_err());
+        assert!(validate_genesis_hash(AMOY_CHAIN_ID, B256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_presets_pass() {
+        let mainnet = crate::bor_mainnet_genesis().genesis_hash();
+        assert_ne!(mainnet, MAINNET_GENESIS_HASH);
+        assert!(validate_genesis_hash(MAINNET_CHAIN_ID, mainnet).is_ok());
+        // Another chain's preset is no excuse.
+        assert!(validate_genesis_hash(AMOY_CHAIN_ID, mainnet).is_err());
+    }
+
+    #[test]
+    fn test_embedded_amoy_genesis_matches() {
+        let spec = crate::bor_amoy_genesis();
+        assert_eq!(spec.genesis_hash(), AMOY_GENESIS_HASH);
+    }
+}
@@ -2,12 +2,23 @@
 
 use core::fmt;
 use core::str::FromStr;
+use std::collections::BTreeMap;
 
-use reth_ethereum_forks::Hardfork;
+use reth_ethereum_forks::{ForkCondition, Hardfork};
+
+use crate::constants::{
+    BACKUP_MULTIPLIER, BASE_FEE_CHANGE_DENOMINATOR, BHILAI_BASE_FEE_CHANGE_DENOMINATOR,
+    BHILAI_BLOCK_GAS_LIMIT, BLOCK_GAS_LIMIT, BLOCK_PERIOD, DELHI_BASE_FEE_CHANGE_DENOMINATOR,
+    PRE_DELHI_PRODUCER_DELAY, PRE_DELHI_SPRINT_SIZE, PRODUCER_DELAY, RIO_SPAN_SIZE, SPAN_SIZE,
+    SPRINT_SIZE, STATE_SYNC_DELAY,
+};
+use crate::{BorConfig, ForkValue, ScheduleError};
 
 /// All Polygon Bor hardforks.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BorHardfork {
+    /// Jaipur hardfork.
+    Jaipur,
     /// Delhi hardfork.
     Delhi,
     /// Indore hardfork.
@@ -34,6 +45,7 @@ impl BorHardfork {
     /// Returns the mainnet (chain 137) activation block number for this hardfork.
     pub const fn mainnet_block(&self) -> u64 {
         match self {
+            Self::Jaipur => 23_850_000,
             Self::Delhi => 38_189_056,
             Self::Indore => 44_934_656,
             Self::Agra => 50_523_000,
@@ -51,6 +63,7 @@ impl BorHardfork {
     /// Values from Go-Bor's `AmoyChainConfig`.
     pub const fn amoy_block(&self) -> u64 {
         match self {
+            Self::Jaipur => 73_100,
             Self::Delhi => 73_100,
             Self::Indore => 73_100,
             Self::Agra => 73_100,
@@ -67,6 +80,7 @@ impl BorHardfork {
     /// Returns all hardfork variants in activation order.
     pub const fn all() -> &'static [Self] {
         &[
+            Self::Jaipur,
             Self::Delhi,
             Self::Indore,
             Self::Agra,
@@ -81,9 +95,235 @@ impl BorHardfork {
     }
 }
 
+/// Activation schedule of the Bor hardforks, and the chain parameters that follow from it.
+///
+/// Parameters are keyed on block number; callers pass the block whose rules apply (for the
+/// base fee, the parent, as in Bor). Those the `bor` config gives, such as `sprint`, come
+/// from [`bor_config_value`](Self::bor_config_value); a chain without them gets Bor's
+/// mainnet values at its own fork blocks.
+pub trait BorHardforks {
+    /// The activation condition of `fork`.
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition;
+
+    /// The block-keyed number `key` of the chain's `bor` config, if it gives one, see
+    /// [`BorConfig::block_keyed`].
+    fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+        let _ = key;
+        None
+    }
+
+    /// Returns `true` if `fork` is active at `block`.
+    fn is_bor_fork_active_at_block(&self, fork: BorHardfork, block: u64) -> bool {
+        self.bor_fork_activation(fork).active_at_block(block)
+    }
+
+    /// Returns `true` if Jaipur is active at `block`.
+    fn is_jaipur_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Jaipur, block)
+    }
+
+    /// Returns `true` if Delhi is active at `block`.
+    fn is_delhi_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Delhi, block)
+    }
+
+    /// Returns `true` if Indore is active at `block`.
+    fn is_indore_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Indore, block)
+    }
+
+    /// Returns `true` if Agra is active at `block`.
+    fn is_agra_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Agra, block)
+    }
+
+    /// Returns `true` if Napoli is active at `block`.
+    fn is_napoli_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Napoli, block)
+    }
+
+    /// Returns `true` if Bhilai is active at `block`.
+    fn is_bhilai_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Bhilai, block)
+    }
+
+    /// Returns `true` if Rio is active at `block`.
+    fn is_rio_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Rio, block)
+    }
+
+    /// Returns `true` if Madhugiri is active at `block`.
+    fn is_madhugiri_active_at_block(&self, block: u64) -> bool {
+        self.is_bor_fork_active_at_block(BorHardfork::Madhugiri, block)
+    }
+
+    /// Sprint size: the config's `sprint`, else 64 blocks, 16 from Delhi.
+    ///
+    /// A config without a usable sprint at `block` gives the fork schedule's; consensus
+    /// and execution use [`try_bor_sprint_size`](Self::try_bor_sprint_size) instead.
+    fn bor_sprint_size(&self, block: u64) -> u64 {
+        let fallback =
+            if self.is_delhi_active_at_block(block) { SPRINT_SIZE } else { PRE_DELHI_SPRINT_SIZE };
+        config_value_or(self, "sprint", block, fallback)
+    }
+
+    /// Sprint size at `block`, or why the config's `sprint` has none there.
+    fn try_bor_sprint_size(&self, block: u64) -> Result<u64, ScheduleError> {
+        let Some(sprint) = self.bor_config_value("sprint") else {
+            return Ok(self.bor_sprint_size(block));
+        };
+        match *sprint.try_value_at("sprint", block)? {
+            0 => Err(ScheduleError::Zero {
+                name: "sprint",
+                block: sprint.activation_at(block).unwrap_or(block),
+            }),
+            size => Ok(size),
+        }
+    }
+
+    /// Returns `true` if `block` is the first block of a sprint, counting from block 0.
+    ///
+    /// Genesis starts a sprint too; it has no system calls as it is not executed.
+    fn is_bor_sprint_start(&self, block: u64) -> Result<bool, ScheduleError> {
+        match self.bor_config_value("sprint") {
+            Some(sprint) => sprint.is_period_start("sprint", block),
+            None => Ok(block % self.bor_sprint_size(block) == 0),
+        }
+    }
+
+    /// Returns `true` if `block` is the last block of a sprint.
+    fn is_bor_sprint_end(&self, block: u64) -> Result<bool, ScheduleError> {
+        match block.checked_add(1) {
+            Some(next) => self.is_bor_sprint_start(next),
+            None => Ok(false),
+        }
+    }
+
+    /// Span size: 6400 blocks, 1600 from Rio.
+    fn bor_span_size(&self, block: u64) -> u64 {
+        if self.is_rio_active_at_block(block) { RIO_SPAN_SIZE } else { SPAN_SIZE }
+    }
+
+    /// The Heimdall span covering `block`: spans after the genesis span are 6400 blocks
+    /// long, 1600 from the first span starting at or after Rio.
+    fn bor_span_id(&self, block: u64) -> u64 {
+        bor_primitives::span_id_for_block_at(block, self.bor_rio_block())
+    }
+
+    /// The first block of span `span_id`, the inverse of [`bor_span_id`](Self::bor_span_id).
+    fn bor_span_start(&self, span_id: u64) -> u64 {
+        bor_primitives::span_start_block_at(span_id, self.bor_rio_block())
+    }
+
+    /// The block Rio activates at, if it activates by block.
+    fn bor_rio_block(&self) -> Option<u64> {
+        match self.bor_fork_activation(BorHardfork::Rio) {
+            ForkCondition::Block(block) => Some(block),
+            _ => None,
+        }
+    }
+
+    /// Block gas limit: 30M, 45M from Bhilai.
+    fn bor_block_gas_limit(&self, block: u64) -> u64 {
+        if self.is_bhilai_active_at_block(block) { BHILAI_BLOCK_GAS_LIMIT } else { BLOCK_GAS_LIMIT }
+    }
+
+    /// Base fee change denominator: 8, 16 from Delhi, 64 from Bhilai.
+    fn bor_base_fee_change_denominator(&self, block: u64) -> u64 {
+        if self.is_bhilai_active_at_block(block) {
+            BHILAI_BASE_FEE_CHANGE_DENOMINATOR
+        } else if self.is_delhi_active_at_block(block) {
+            DELHI_BASE_FEE_CHANGE_DENOMINATOR
+        } else {
+            BASE_FEE_CHANGE_DENOMINATOR
+        }
+    }
+
+    /// Delay of the first block of a sprint, in seconds: the config's `producerDelay`,
+    /// else 6, 4 from Delhi.
+    fn bor_producer_delay(&self, block: u64) -> u64 {
+        let fallback = if self.is_delhi_active_at_block(block) {
+            PRODUCER_DELAY
+        } else {
+            PRE_DELHI_PRODUCER_DELAY
+        };
+        config_value_or(self, "producerDelay", block, fallback)
+    }
+
+    /// Minimum seconds between blocks: the config's `period`, else 2.
+    fn bor_period(&self, block: u64) -> u64 {
+        config_value_or(self, "period", block, BLOCK_PERIOD)
+    }
+
+    /// Extra delay per position a backup producer is behind the proposer, in seconds:
+    /// the config's `backupMultiplier`, else 2.
+    fn bor_backup_multiplier(&self, block: u64) -> u64 {
+        config_value_or(self, "backupMultiplier", block, BACKUP_MULTIPLIER)
+    }
+
+    /// Seconds a state sync event must be on Heimdall before the block committing it:
+    /// the config's `stateSyncConfirmationDelay`, else 128 from Indore and none before.
+    fn bor_state_sync_confirmation_delay(&self, block: u64) -> u64 {
+        let fallback = if self.is_indore_active_at_block(block) { STATE_SYNC_DELAY } else { 0 };
+        config_value_or(self, "stateSyncConfirmationDelay", block, fallback)
+    }
+}
+
+/// The value of `key` in `forks`' config at `block`, or `fallback` where it has none.
+///
+/// A zero sprint or period never comes out of a validated config; one from an unchecked
+/// config is replaced by `fallback` rather than divided by.
+fn config_value_or<F: BorHardforks + ?Sized>(
+    forks: &F,
+    key: &'static str,
+    block: u64,
+    fallback: u64,
+) -> u64 {
+    forks
+        .bor_config_value(key)
+        .and_then(|value| value.value_at(block).copied())
+        .filter(|value| *value != 0 || !matches!(key, "sprint" | "period"))
+        .unwrap_or(fallback)
+}
+
+/// The Polygon PoS mainnet hardfork schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MainnetBorHardforks;
+
+impl BorHardforks for MainnetBorHardforks {
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition {
+        ForkCondition::Block(fork.mainnet_block())
+    }
+
+    fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+        BorConfig::mainnet().block_keyed(key).cloned()
+    }
+}
+
+/// The Amoy testnet hardfork schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AmoyBorHardforks;
+
+impl BorHardforks for AmoyBorHardforks {
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition {
+        ForkCondition::Block(fork.amoy_block())
+    }
+
+    fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+        BorConfig::amoy().block_keyed(key).cloned()
+    }
+}
+
+impl BorHardforks for BTreeMap<BorHardfork, ForkCondition> {
+    fn bor_fork_activation(&self, fork: BorHardfork) -> ForkCondition {
+        self.get(&fork).copied().unwrap_or(ForkCondition::Never)
+    }
+}
+
 impl Hardfork for BorHardfork {
     fn name(&self) -> &'static str {
         match self {
+            Self::Jaipur => "Jaipur",
             Self::Delhi => "Delhi",
             Self::Indore => "Indore",
             Self::Agra => "Agra",
@@ -109,6 +349,7 @@ impl FromStr for BorHardfork {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
+            "jaipur" => Ok(Self::Jaipur),
             "delhi" => Ok(Self::Delhi),
             "indore" => Ok(Self::Indore),
             "agra" => Ok(Self::Agra),
@@ -143,6 +384,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_jaipur_block() {
+        assert_eq!(BorHardfork::Jaipur.mainnet_block(), 23_850_000);
+        assert_eq!("jaipur".parse::<BorHardfork>().unwrap(), BorHardfork::Jaipur);
+    }
+
+    #[test]
+    fn test_schedule_parameters() {
+        let mainnet = MainnetBorHardforks;
+        assert!(!mainnet.is_jaipur_active_at_block(23_849_999));
+        assert!(mainnet.is_jaipur_active_at_block(23_850_000));
+        assert_eq!(mainnet.bor_sprint_size(38_189_055), 64);
+        assert_eq!(mainnet.bor_sprint_size(38_189_056), 16);
+        assert_eq!(mainnet.bor_base_fee_change_denominator(76_000_000), 64);
+
+        // Amoy runs Delhi rules almost from genesis.
+        assert_eq!(AmoyBorHardforks.bor_sprint_size(73_100), 16);
+        assert_eq!(AmoyBorHardforks.bor_span_size(26_272_256), 1600);
+        assert_eq!(mainnet.bor_span_id(77_414_656 + 1600), 12_098);
+        assert_eq!(mainnet.bor_span_start(12_097), 77_414_656);
+        let amoy_rio_span = AmoyBorHardforks.bor_span_id(26_272_256);
+        assert_eq!(AmoyBorHardforks.bor_span_start(amoy_rio_span), 26_272_256);
+
+        // Forks missing from a schedule never activate.
+        let only_delhi = BTreeMap::from([(BorHardfork::Delhi, ForkCondition::Block(10))]);
+        assert_eq!(only_delhi.bor_sprint_size(10), 16);
+        assert!(!only_delhi.is_bhilai_active_at_block(u64::MAX));
+        assert_eq!(only_delhi.bor_period(10), 2);
+        assert_eq!(only_delhi.bor_state_sync_confirmation_delay(10), 0);
+    }
+
+    #[test]
+    fn test_config_parameters() {
+        let mainnet = MainnetBorHardforks;
+        let sprint = BorConfig::mainnet().block_keyed("sprint").cloned();
+        assert_eq!(mainnet.bor_config_value("sprint"), sprint);
+        assert_eq!(mainnet.bor_producer_delay(38_189_055), 6);
+        assert_eq!(mainnet.bor_producer_delay(38_189_056), 4);
+        assert_eq!(mainnet.bor_backup_multiplier(0), 2);
+        assert_eq!(mainnet.bor_state_sync_confirmation_delay(44_934_656), 128);
+        assert_eq!(AmoyBorHardforks.bor_sprint_size(0), 16);
+        assert_eq!(mainnet.bor_config_value("burntContract"), None);
+    }
+
+    #[test]
+    fn test_fallible_sprint_lookups() {
+        assert_eq!(MainnetBorHardforks.try_bor_sprint_size(38_189_056), Ok(16));
+        assert_eq!(MainnetBorHardforks.is_bor_sprint_start(38_189_056), Ok(true));
+        assert_eq!(MainnetBorHardforks.is_bor_sprint_end(38_189_055), Ok(true));
+        assert_eq!(MainnetBorHardforks.is_bor_sprint_end(u64::MAX), Ok(false));
+
+        // Schedules without a `sprint` use the fork schedule's.
+        let only_delhi = BTreeMap::from([(BorHardfork::Delhi, ForkCondition::Block(64))]);
+        assert_eq!(only_delhi.is_bor_sprint_start(48), Ok(false));
+        assert_eq!(only_delhi.is_bor_sprint_start(80), Ok(true));
+
+        /// A chain whose `sprint` starts after genesis.
+        struct Late;
+        impl BorHardforks for Late {
+            fn bor_fork_activation(&self, _: BorHardfork) -> ForkCondition {
+                ForkCondition::Never
+            }
+            fn bor_config_value(&self, key: &'static str) -> Option<ForkValue<u64>> {
+                (key == "sprint").then(|| ForkValue::default().with_value(10, 16))
+            }
+        }
+        let missing = ScheduleError::Missing { name: "sprint", block: 3 };
+        assert_eq!(Late.try_bor_sprint_size(3), Err(missing.clone()));
+        assert_eq!(Late.is_bor_sprint_start(3), Err(missing));
+        assert_eq!(Late.bor_sprint_size(3), 64);
+    }
+
     #[test]
     fn test_delhi_block() {
         assert_eq!(BorHardfork::Delhi.mainnet_block(), 38_189_056);
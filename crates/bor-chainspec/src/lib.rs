@@ -4,12 +4,22 @@ pub mod constants;
 pub use constants::*;
 
 mod hardfork;
-pub use hardfork::BorHardfork;
+pub use hardfork::{AmoyBorHardforks, BorHardfork, BorHardforks, MainnetBorHardforks};
 
-pub mod params;
+pub mod fork_value;
+pub use fork_value::{ForkValue, ScheduleError};
+
+pub mod bor_config;
+pub use bor_config::BorConfig;
+
+pub mod contracts;
+pub use contracts::{ContractUpgrade, genesis_contract_upgrades};
 
 mod chainspec;
-pub use chainspec::{BorChainSpec, bor_amoy_chainspec, bor_mainnet_chainspec};
+pub use chainspec::{
+    BorChainSpec, bor_amoy_chainspec, bor_genesis_chainspec, bor_mainnet_chainspec,
+    validate_bor_config,
+};
 
 mod amoy;
 pub use amoy::bor_amoy_genesis;
@@ -19,5 +29,8 @@ pub use genesis::bor_mainnet_genesis;
 
 pub mod bootnodes;
 
+pub mod genesis_hash;
+pub use genesis_hash::{known_genesis_hash, preset_genesis_hash, validate_genesis_hash};
+
 pub mod cli;
 pub use cli::BorChainSpecParser;
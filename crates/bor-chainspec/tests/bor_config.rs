@@ -0,0 +1,105 @@
+//! The `bor` sections of the mainnet and Amoy genesis files, read as [`BorConfig`].
+//!
+//! The fixtures in `res/` are the sections of bor-geth's genesis files without their
+//! `blockAlloc` entries, whose contract code [`BorConfig`] does not look into.
+
+use alloy_primitives::{address, Address};
+use bor_chainspec::{
+    AmoyBorHardforks, BorConfig, BorHardfork, BorHardforks, ForkValue, MainnetBorHardforks,
+    BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS,
+};
+
+const MAINNET: &str = include_str!("../res/mainnet_bor_config.json");
+const AMOY: &str = include_str!("../res/amoy_bor_config.json");
+const SCHEMA: &str = include_str!("../res/bor_config.schema.json");
+
+fn read(json: &str) -> (serde_json::Value, BorConfig) {
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    let config = BorConfig::from_value(&value).unwrap();
+    (value, config)
+}
+
+/// Serializing gives back every key that was read, with the same value up to the case
+/// of addresses.
+fn assert_round_trip(value: &serde_json::Value, config: &BorConfig) {
+    let written = serde_json::to_value(config).unwrap();
+    let lowercase = |entry: &serde_json::Value| entry.to_string().to_lowercase();
+    for (key, entry) in written.as_object().unwrap() {
+        assert_eq!(value.get(key).map(lowercase), Some(lowercase(entry)), "{key}");
+    }
+    let ignored: Vec<_> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .filter(|key| written.get(key.as_str()).is_none())
+        .collect();
+    assert_eq!(ignored, ["parallelUniverseBlock"]);
+    assert_eq!(&serde_json::from_value::<BorConfig>(written).unwrap(), config);
+}
+
+fn assert_forks(config: &BorConfig, block: impl Fn(BorHardfork) -> u64) {
+    let blocks: Vec<_> = config.fork_blocks().into_iter().map(|(_, block)| block).collect();
+    let expected: Vec<_> = BorHardfork::all().iter().map(|fork| Some(block(*fork))).collect();
+    assert_eq!(blocks, expected);
+}
+
+#[test]
+fn mainnet_bor_config() {
+    let (value, config) = read(MAINNET);
+    assert_round_trip(&value, &config);
+    assert_eq!(config.validate(), Ok(()));
+    assert_forks(&config, |fork| fork.mainnet_block());
+
+    for block in [0, 38_189_055, 38_189_056, 80_000_000] {
+        assert_eq!(config.sprint.value_at_or(block, 0), MainnetBorHardforks.bor_sprint_size(block));
+        assert_eq!(
+            config.producer_delay.value_at_or(block, 0),
+            MainnetBorHardforks.bor_producer_delay(block)
+        );
+    }
+    assert_eq!(config.period, ForkValue::constant(2));
+    assert_eq!(config.validator_contract, Some(BOR_VALIDATOR_SET_ADDRESS));
+    assert_eq!(config.state_receiver_contract, Some(STATE_RECEIVER_ADDRESS));
+    assert_eq!(config.override_state_sync_records.get(&14_949_120), Some(&8));
+    assert_eq!(
+        config.burnt_contract.value_at(23_850_000),
+        Some(&address!("70bca57f4579f58670ab2d18ef16e02c17553c38"))
+    );
+    assert_eq!(config.state_sync_confirmation_delay.value_at(44_934_656), Some(&128));
+}
+
+#[test]
+fn amoy_bor_config() {
+    let (value, config) = read(AMOY);
+    assert_round_trip(&value, &config);
+    assert_eq!(config.validate(), Ok(()));
+    assert_forks(&config, |fork| fork.amoy_block());
+
+    let delhi = BorHardfork::Delhi.amoy_block();
+    assert_eq!(config.sprint.value_at_or(delhi, 0), AmoyBorHardforks.bor_sprint_size(delhi));
+    assert_eq!(config.period, ForkValue::constant(2));
+    assert!(config.override_state_sync_records.is_empty());
+    assert_eq!(
+        config.burnt_contract.value_at(0),
+        Some(&address!("000000000000000000000000000000000000dead"))
+    );
+    assert_ne!(config.burnt_contract.value_at(0), Some(&Address::ZERO));
+}
+
+/// Changing the keys [`BorConfig`] reads must regenerate `res/bor_config.schema.json`.
+#[test]
+fn schema_is_up_to_date() {
+    let schema: serde_json::Value = serde_json::from_str(SCHEMA).unwrap();
+    assert_eq!(
+        schema,
+        BorConfig::json_schema(),
+        "res/bor_config.schema.json is stale, write BorConfig::json_schema() to it"
+    );
+
+    // Every key of the fixtures that is read is described.
+    let properties = schema["properties"].as_object().unwrap();
+    let (_, config) = read(MAINNET);
+    for key in serde_json::to_value(config).unwrap().as_object().unwrap().keys() {
+        assert!(properties.contains_key(key), "{key}");
+    }
+}
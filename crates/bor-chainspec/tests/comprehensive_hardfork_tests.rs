@@ -4,16 +4,40 @@
 use std::str::FromStr;
 
 use bor_chainspec::{
-    bor_amoy_genesis, bor_mainnet_genesis,
-    params::{
-        base_fee_change_denominator, block_gas_limit, is_span_start, is_sprint_start,
-        max_code_size, span_size, sprint_size,
-    },
-    BorHardfork,
+    bor_amoy_genesis, bor_mainnet_genesis, BorHardfork, BorHardforks, MainnetBorHardforks,
+    MAX_CODE_SIZE,
 };
-use reth_chainspec::EthChainSpec;
+use reth_chainspec::{EthChainSpec, EthereumHardforks};
 use reth_ethereum_forks::Hardfork;
 
+const MAINNET: MainnetBorHardforks = MainnetBorHardforks;
+
+fn sprint_size(block: u64) -> u64 {
+    MAINNET.bor_sprint_size(block)
+}
+
+fn span_size(block: u64) -> u64 {
+    MAINNET.bor_span_size(block)
+}
+
+fn block_gas_limit(block: u64) -> u64 {
+    MAINNET.bor_block_gas_limit(block)
+}
+
+fn base_fee_change_denominator(block: u64) -> u64 {
+    MAINNET.bor_base_fee_change_denominator(block)
+}
+
+fn is_sprint_start(block: u64) -> bool {
+    MAINNET.is_bor_sprint_start(block).unwrap()
+}
+
+/// Whether a mainnet span starts at `block`: the genesis span at 0, then one at 256 and
+/// every 6400 blocks after it, every 1600 from the first span at or after Rio.
+fn is_span_start(block: u64) -> bool {
+    MAINNET.bor_span_start(MAINNET.bor_span_id(block)) == block
+}
+
 // ---------------------------------------------------------------------------
 // 3.1 Mid-sprint hardfork activation: Agra (50_523_000 % 16 == 8)
 // ---------------------------------------------------------------------------
@@ -140,34 +164,34 @@ fn span_size_post_rio() {
 
 #[test]
 fn first_post_rio_span_boundary() {
-    // The first span boundary at or after Rio with the new span size.
+    // The first span starting at or after Rio, and every one after it, is 1600 blocks long.
     let rio = 77_414_656_u64;
-    let new_span = 1600_u64;
-    // Find the first multiple of 1600 >= rio
-    let first_boundary = rio.div_ceil(new_span) * new_span;
-    assert_eq!(first_boundary % new_span, 0);
+    let mut span_id = MAINNET.bor_span_id(rio);
+    if MAINNET.bor_span_start(span_id) < rio {
+        span_id += 1;
+    }
+    let first_boundary = MAINNET.bor_span_start(span_id);
     assert!(first_boundary >= rio);
-    assert!(is_span_start(first_boundary, new_span));
+    assert!(is_span_start(first_boundary));
+    assert_eq!(MAINNET.bor_span_start(span_id + 1), first_boundary + 1600);
 }
 
 #[test]
 fn span_id_calculation_pre_rio() {
-    // With 6400-block spans, span_id = block / 6400
+    // After the 256-block genesis span, spans are 6400 blocks long.
     let block = 77_414_655_u64;
-    let span = span_size(block);
-    assert_eq!(span, 6400);
-    let span_id = block / span;
-    assert_eq!(span_id, 12_096);
+    assert_eq!(span_size(block), 6400);
+    assert_eq!(MAINNET.bor_span_id(block), (block - 256) / 6400 + 1);
+    assert_eq!(MAINNET.bor_span_id(block), 12_096);
 }
 
 #[test]
 fn is_span_start_various() {
-    assert!(is_span_start(0, 6400));
-    assert!(is_span_start(6400, 6400));
-    assert!(!is_span_start(6401, 6400));
-    assert!(is_span_start(0, 1600));
-    assert!(is_span_start(1600, 1600));
-    assert!(!is_span_start(1601, 1600));
+    assert!(is_span_start(0));
+    assert!(is_span_start(256));
+    assert!(is_span_start(6656));
+    assert!(!is_span_start(6400));
+    assert!(!is_span_start(6657));
 }
 
 // ---------------------------------------------------------------------------
@@ -229,12 +253,12 @@ fn base_fee_denom_post_bhilai() {
 }
 
 // ---------------------------------------------------------------------------
-// 3.8 All 10 hardforks integration
+// 3.8 All 11 hardforks integration
 // ---------------------------------------------------------------------------
 
 #[test]
-fn all_hardforks_returns_ten() {
-    assert_eq!(BorHardfork::all().len(), 10);
+fn all_hardforks_returns_eleven() {
+    assert_eq!(BorHardfork::all().len(), 11);
 }
 
 #[test]
@@ -272,6 +296,7 @@ fn no_two_hardforks_share_same_block() {
 fn all_params_correct_at_each_boundary() {
     let expected: &[(BorHardfork, u64, u64, u64, u64, u64)] = &[
         // (fork, block, sprint, span, gas_limit, base_fee_denom)
+        (BorHardfork::Jaipur, 23_850_000, 64, 6400, 30_000_000, 8),
         (BorHardfork::Delhi, 38_189_056, 16, 6400, 30_000_000, 16),
         (BorHardfork::Indore, 44_934_656, 16, 6400, 30_000_000, 16),
         (BorHardfork::Agra, 50_523_000, 16, 6400, 30_000_000, 16),
@@ -357,10 +382,10 @@ fn amoy_all_forks_active_at_latest() {
 // ---------------------------------------------------------------------------
 
 #[test]
-fn mainnet_chainspec_contains_all_10_hardforks() {
+fn mainnet_chainspec_contains_all_11_hardforks() {
     let spec = bor_mainnet_genesis();
     let bor_forks = spec.bor_hardforks();
-    assert_eq!(bor_forks.len(), 10);
+    assert_eq!(bor_forks.len(), 11);
     for fork in BorHardfork::all() {
         assert!(bor_forks.contains_key(fork), "missing hardfork: {fork}");
     }
@@ -369,6 +394,7 @@ fn mainnet_chainspec_contains_all_10_hardforks() {
 #[test]
 fn mainnet_chainspec_block_numbers_correct() {
     let expected: &[(BorHardfork, u64)] = &[
+        (BorHardfork::Jaipur, 23_850_000),
         (BorHardfork::Delhi, 38_189_056),
         (BorHardfork::Indore, 44_934_656),
         (BorHardfork::Agra, 50_523_000),
@@ -448,6 +474,7 @@ fn hardfork_parse_empty_returns_err() {
 #[test]
 fn hardfork_parse_all_variants_lowercase() {
     let names = [
+        "jaipur",
         "delhi",
         "indore",
         "agra",
@@ -513,41 +540,36 @@ fn is_sprint_start_post_delhi_boundaries() {
 }
 
 // ---------------------------------------------------------------------------
-// is_span_start with various span sizes
+// is_span_start before and after Rio
 // ---------------------------------------------------------------------------
 
 #[test]
 fn is_span_start_with_6400() {
-    assert!(is_span_start(0, 6400));
-    assert!(is_span_start(6400, 6400));
-    assert!(is_span_start(12800, 6400));
-    assert!(!is_span_start(1, 6400));
-    assert!(!is_span_start(6399, 6400));
+    assert!(is_span_start(256 + 6400));
+    assert!(is_span_start(256 + 2 * 6400));
+    assert!(!is_span_start(1));
+    assert!(!is_span_start(255));
+    assert!(!is_span_start(12_800));
 }
 
 #[test]
 fn is_span_start_with_1600() {
-    assert!(is_span_start(0, 1600));
-    assert!(is_span_start(1600, 1600));
-    assert!(is_span_start(3200, 1600));
-    assert!(!is_span_start(1, 1600));
-    assert!(!is_span_start(1599, 1600));
-    assert!(!is_span_start(1601, 1600));
+    let rio = MAINNET.bor_span_start(MAINNET.bor_span_id(77_414_656) + 1);
+    assert!(is_span_start(rio));
+    assert!(is_span_start(rio + 1600));
+    assert!(is_span_start(rio + 3200));
+    assert!(!is_span_start(rio + 1));
+    assert!(!is_span_start(rio + 1599));
+    assert!(!is_span_start(rio + 1601));
 }
 
 // ---------------------------------------------------------------------------
-// max_code_size always returns 24_576
+// Maximum code size is the EIP-170 limit
 // ---------------------------------------------------------------------------
 
 #[test]
-fn max_code_size_always_24576() {
-    assert_eq!(max_code_size(0), 24_576);
-    assert_eq!(max_code_size(1), 24_576);
-    assert_eq!(max_code_size(38_189_056), 24_576);
-    assert_eq!(max_code_size(76_000_000), 24_576);
-    assert_eq!(max_code_size(83_756_500), 24_576);
-    assert_eq!(max_code_size(100_000_000), 24_576);
-    assert_eq!(max_code_size(u64::MAX), 24_576);
+fn max_code_size_is_eip170() {
+    assert_eq!(MAX_CODE_SIZE, 24_576);
 }
 
 // ---------------------------------------------------------------------------
@@ -566,6 +588,20 @@ fn amoy_genesis_chain_id_80002() {
     assert_eq!(spec.chain_id(), 80002);
 }
 
+// ---------------------------------------------------------------------------
+// Receipt encoding from genesis
+// ---------------------------------------------------------------------------
+
+/// Polygon launched with Byzantium active, so no block has a receipt carrying an
+/// intermediate state root: every receipt encodes a status code, and the plain
+/// Ethereum receipt encoding reproduces historical receipt roots.
+#[test]
+fn status_code_receipts_from_genesis() {
+    for spec in [bor_mainnet_genesis(), bor_amoy_genesis()] {
+        assert!(spec.is_byzantium_active_at_block(0), "chain {}", spec.chain_id());
+    }
+}
+
 // ---------------------------------------------------------------------------
 // All forks active at their block, not active one block before
 // ---------------------------------------------------------------------------
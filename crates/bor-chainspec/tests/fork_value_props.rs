@@ -0,0 +1,112 @@
+//! Property tests of block-keyed parameter lookups and sprint boundaries.
+//!
+//! A lookup that resolves the wrong entry at an activation block, or a sprint
+//! boundary off by one, makes two nodes disagree on a block's rules. These check
+//! the lookups against a plain scan of the map over arbitrary maps and blocks,
+//! including the malformed maps a genesis file can hold.
+
+use bor_chainspec::{
+    BorHardfork, BorHardforks, ForkValue, MainnetBorHardforks, ScheduleError,
+    PRE_DELHI_SPRINT_SIZE, SPRINT_SIZE,
+};
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+
+/// Maps with activation blocks clustered at small numbers, so blocks drawn from the
+/// same range hit them, their neighbours and the gaps before the first entry.
+fn schedule(values: impl Strategy<Value = u64>) -> impl Strategy<Value = BTreeMap<u64, u64>> {
+    prop::collection::btree_map(prop_oneof![0..64u64, any::<u64>()], values, 0..8)
+}
+
+fn block() -> impl Strategy<Value = u64> {
+    prop_oneof![0..128u64, any::<u64>()]
+}
+
+/// The entry with the greatest key not above `block`, by scanning every entry.
+fn scan(map: &BTreeMap<u64, u64>, block: u64) -> Option<(u64, u64)> {
+    map.iter().filter(|(&key, _)| key <= block).map(|(&k, &v)| (k, v)).max_by_key(|(k, _)| *k)
+}
+
+proptest! {
+    #[test]
+    fn value_at_is_greatest_key_not_above(map in schedule(any::<u64>()), block in block()) {
+        let value: ForkValue<u64> = map.clone().into_iter().collect();
+        let expected = scan(&map, block);
+        prop_assert_eq!(value.value_at(block).copied(), expected.map(|(_, v)| v));
+        prop_assert_eq!(value.activation_at(block), expected.map(|(k, _)| k));
+        prop_assert_eq!(value.value_at_or(block, 7), expected.map_or(7, |(_, v)| v));
+        match value.try_value_at("sprint", block) {
+            Ok(&v) => prop_assert_eq!(Some(v), expected.map(|(_, v)| v)),
+            Err(err) => {
+                prop_assert!(expected.is_none());
+                prop_assert_eq!(err, ScheduleError::Missing { name: "sprint", block });
+            }
+        }
+    }
+
+    #[test]
+    fn activation_is_monotonic(map in schedule(any::<u64>()), a in block(), b in block()) {
+        let value: ForkValue<u64> = map.into_iter().collect();
+        let (low, high) = (a.min(b), a.max(b));
+        if let Some(activation) = value.activation_at(low) {
+            prop_assert!(activation <= low);
+            // Once a value is in force, a later block has one activated no earlier.
+            prop_assert!(value.activation_at(high).is_some_and(|later| later >= activation));
+        }
+    }
+
+    #[test]
+    fn period_start_never_panics(map in schedule(0..20u64), block in block()) {
+        let value: ForkValue<u64> = map.clone().into_iter().collect();
+        match (value.is_period_start("sprint", block), scan(&map, block)) {
+            (Ok(start), Some((_, length))) => {
+                prop_assert!(length > 0);
+                prop_assert_eq!(start, block % length == 0);
+            }
+            (Err(ScheduleError::Zero { block: at, .. }), Some((activation, 0))) => {
+                prop_assert_eq!(at, activation);
+            }
+            (Err(ScheduleError::Missing { .. }), None) => {}
+            (result, entry) => prop_assert!(false, "{result:?} for entry {entry:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_lengths_give_a_period_start_at_every_block(
+        map in schedule(1..20u64),
+        first in 1..20u64,
+        block in block(),
+    ) {
+        let mut map = map;
+        map.entry(0).or_insert(first);
+        let value: ForkValue<u64> = map.into_iter().collect();
+        prop_assert!(value.validate_length("sprint").is_ok());
+        prop_assert!(value.is_period_start("sprint", block).is_ok());
+    }
+
+    #[test]
+    fn sprint_end_precedes_sprint_start(
+        map in schedule(1..20u64),
+        first in 1..20u64,
+        block in 0..4096u64,
+    ) {
+        let mut map = map;
+        map.entry(0).or_insert(first);
+        let value: ForkValue<u64> = map.into_iter().collect();
+        let length = *value.value_at(block).unwrap();
+        // Within one sprint length, the block after a sprint's last block starts the next.
+        if value.value_at(block + 1) == Some(&length) {
+            let sprint_end = (block + 1) % length == 0;
+            prop_assert_eq!(value.is_period_start("sprint", block + 1), Ok(sprint_end));
+        }
+    }
+
+    #[test]
+    fn mainnet_sprint_starts(block in prop_oneof![38_180_000..38_200_000u64, any::<u64>()]) {
+        let size = MainnetBorHardforks.bor_sprint_size(block);
+        prop_assert!(size == PRE_DELHI_SPRINT_SIZE || size == SPRINT_SIZE);
+        prop_assert_eq!(MainnetBorHardforks.is_bor_sprint_start(block), Ok(block % size == 0));
+        let delhi = BorHardfork::Delhi.mainnet_block();
+        prop_assert_eq!(size == SPRINT_SIZE, block >= delhi);
+    }
+}
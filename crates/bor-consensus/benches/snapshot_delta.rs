@@ -0,0 +1,81 @@
+//! Snapshots of a batch of headers, kept per header as a verifier caching them by
+//! hash would.
+//!
+//! `naive` clones the parent [`BorSnapshot`] and applies each header to the clone,
+//! `delta` derives a [`SnapshotView`] from the parent's. Both run over [`HEADERS`]
+//! headers of a set of [`VALIDATORS`], with a new validator set every sprint.
+
+use alloy_primitives::{Address, B256};
+use bor_consensus::{BorSnapshot, SnapshotDelta, SnapshotView};
+use bor_primitives::{Validator, ValidatorSet};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Size of the validator set, about that of mainnet.
+const VALIDATORS: u8 = 100;
+/// Headers in a batch.
+const HEADERS: u64 = 1024;
+const SPRINT_SIZE: u64 = 16;
+
+fn validator_set(priority: i64) -> ValidatorSet {
+    let validators: Vec<_> = (1..=VALIDATORS)
+        .map(|i| Validator {
+            id: i.into(),
+            address: Address::with_last_byte(i),
+            voting_power: 100,
+            signer: Address::with_last_byte(i),
+            proposer_priority: priority + i64::from(i),
+        })
+        .collect();
+    ValidatorSet { proposer: validators.first().cloned(), validators }
+}
+
+fn deltas() -> Vec<SnapshotDelta> {
+    (1..=HEADERS)
+        .map(|number| {
+            let signer = Address::with_last_byte((number % u64::from(VALIDATORS)) as u8 + 1);
+            let delta = SnapshotDelta::new(number, B256::with_last_byte(number as u8), signer);
+            if (number + 1) % SPRINT_SIZE == 0 {
+                delta.with_validator_set(validator_set(number as i64))
+            } else {
+                delta
+            }
+        })
+        .collect()
+}
+
+fn naive(genesis: &BorSnapshot, deltas: &[SnapshotDelta]) -> Vec<BorSnapshot> {
+    let mut snapshots: Vec<BorSnapshot> = Vec::with_capacity(deltas.len());
+    for delta in deltas {
+        let mut snapshot = snapshots.last().unwrap_or(genesis).clone();
+        snapshot.apply(delta.number, delta.signer);
+        snapshot.hash = delta.hash;
+        if let Some(validator_set) = &delta.validator_set {
+            snapshot.validator_set = validator_set.clone();
+        }
+        snapshots.push(snapshot);
+    }
+    snapshots
+}
+
+fn delta(genesis: &SnapshotView, deltas: &[SnapshotDelta]) -> Vec<SnapshotView> {
+    let mut views: Vec<SnapshotView> = Vec::with_capacity(deltas.len());
+    for delta in deltas {
+        let view = views.last().unwrap_or(genesis).child(delta.clone());
+        views.push(view);
+    }
+    views
+}
+
+fn header_batch(c: &mut Criterion) {
+    let genesis = BorSnapshot::new(0, B256::ZERO, validator_set(0));
+    let genesis_view = SnapshotView::from(genesis.clone());
+    let deltas = deltas();
+
+    let mut group = c.benchmark_group("snapshot_per_header");
+    group.bench_function("naive", |b| b.iter(|| black_box(naive(&genesis, &deltas))));
+    group.bench_function("delta", |b| b.iter(|| black_box(delta(&genesis_view, &deltas))));
+    group.finish();
+}
+
+criterion_group!(benches, header_batch);
+criterion_main!(benches);
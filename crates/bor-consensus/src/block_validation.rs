@@ -33,7 +33,8 @@ pub fn validate_block_pre_execution(
     }
 
     // 3. At span start: extra data must contain validator addresses
-    let is_span_start = block_number > 0 && block_number % span_size == 0;
+    // A zero span size marks no span start instead of dividing by zero.
+    let is_span_start = block_number > 0 && block_number.checked_rem(span_size) == Some(0);
 
     if is_span_start {
         let parsed = ExtraData::parse(extra_data)
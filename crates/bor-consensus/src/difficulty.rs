@@ -6,6 +6,7 @@
 //! - Minimum difficulty is always 1.
 
 use alloy_primitives::{Address, U256};
+use bor_chainspec::constants::DIFF_NO_TURN;
 
 /// Difficulty assigned to the in-turn (primary) proposer.
 /// Equal to the length of the validator set.
@@ -16,12 +17,8 @@ pub fn diff_inturn(validator_count: usize) -> U256 {
 /// Difficulty assigned to an out-of-turn (backup) proposer at the given distance.
 /// Equal to `validator_count - distance`, clamped to a minimum of 1.
 pub fn diff_noturn(validator_count: usize, distance: usize) -> U256 {
-    let diff = if distance >= validator_count {
-        1
-    } else {
-        validator_count - distance
-    };
-    U256::from(diff.max(1))
+    let diff = validator_count.saturating_sub(distance) as u64;
+    U256::from(diff.max(DIFF_NO_TURN))
 }
 
 /// Returns `true` if the given signer is the in-turn proposer for the block.
@@ -45,7 +42,7 @@ pub fn calculate_difficulty(
     block_number: u64,
 ) -> U256 {
     if validators.is_empty() {
-        return U256::from(1);
+        return U256::from(DIFF_NO_TURN);
     }
 
     let count = validators.len();
@@ -66,7 +63,7 @@ pub fn calculate_difficulty(
         }
     } else {
         // Signer not in validator set — minimum difficulty
-        U256::from(1)
+        U256::from(DIFF_NO_TURN)
     }
 }
 
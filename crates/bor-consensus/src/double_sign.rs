@@ -0,0 +1,181 @@
+//! Double-sign protection for the local validator.
+//!
+//! A validator that signs two different blocks at the same height equivocates,
+//! which Polygon penalizes. This typically happens when a key is rotated or a
+//! backup node takes over while the primary is still running. [`DoubleSignGuard`]
+//! remembers the last block this node signed and refuses to sign a different
+//! block at that height or below. The record is written to disk before the
+//! signature is released, so it survives restarts.
+//!
+//! The guard only stops this node from signing. Blocks other nodes sign with the
+//! same key are imported like any other: refusing them would fork this node off the
+//! chain the rest of the network follows.
+
+use alloy_primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// The last block signed by this node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedBlock {
+    /// Address the block was signed with.
+    pub signer: Address,
+    /// Block number.
+    pub number: u64,
+    /// Seal hash of the block (its hash without the seal).
+    pub seal_hash: B256,
+}
+
+/// Reasons the guard refuses to sign.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum DoubleSignError {
+    #[error("already signed block {signed} at height {number}, refusing to sign {requested}")]
+    DoubleSign { number: u64, signed: B256, requested: B256 },
+    #[error("refusing to sign block {number} below last signed block {last}")]
+    BelowLastSigned { number: u64, last: u64 },
+    #[error("failed to persist last signed block: {0}")]
+    Persist(String),
+}
+
+/// A [`DoubleSignGuard`] shared with the sealer.
+pub type SharedDoubleSignGuard = Arc<DoubleSignGuard>;
+
+/// Tracks the last block signed by this node, optionally persisted to a file.
+#[derive(Debug, Default)]
+pub struct DoubleSignGuard {
+    last: Mutex<Option<SignedBlock>>,
+    path: Option<PathBuf>,
+}
+
+impl DoubleSignGuard {
+    /// Create a guard that only remembers signatures while the node runs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a guard persisted at `path`, loading the record left by a previous run.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, DoubleSignError> {
+        let path = path.into();
+        let last = match std::fs::read(&path) {
+            Ok(bytes) => Some(serde_json::from_slice(&bytes).map_err(|e| {
+                DoubleSignError::Persist(format!("invalid record in {}: {e}", path.display()))
+            })?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(DoubleSignError::Persist(format!("{}: {e}", path.display()))),
+        };
+        if let Some(last) = &last {
+            info!(
+                target: "bor::double_sign",
+                number = last.number,
+                signer = %last.signer,
+                "loaded last signed block"
+            );
+        }
+        Ok(Self { last: Mutex::new(last), path: Some(path) })
+    }
+
+    /// The last block signed by this node.
+    pub fn last_signed(&self) -> Option<SignedBlock> {
+        *self.last.lock().expect("double sign lock poisoned")
+    }
+
+    /// Record that this node is about to sign `seal_hash` at `number` with `signer`.
+    ///
+    /// Must be called before the signature leaves the node. Re-signing the block already
+    /// recorded is allowed. The record is persisted before returning, whatever key signs:
+    /// a rotated key still speaks for the same validator.
+    pub fn authorize(
+        &self,
+        signer: Address,
+        number: u64,
+        seal_hash: B256,
+    ) -> Result<(), DoubleSignError> {
+        let mut last = self.last.lock().expect("double sign lock poisoned");
+        if let Some(last) = *last {
+            if number == last.number && seal_hash == last.seal_hash {
+                return Ok(());
+            }
+            if number == last.number {
+                return Err(DoubleSignError::DoubleSign {
+                    number,
+                    signed: last.seal_hash,
+                    requested: seal_hash,
+                });
+            }
+            if number < last.number {
+                return Err(DoubleSignError::BelowLastSigned { number, last: last.number });
+            }
+        }
+
+        let record = SignedBlock { signer, number, seal_hash };
+        if let Some(path) = &self.path {
+            persist(path, &record)?;
+        }
+        *last = Some(record);
+        Ok(())
+    }
+}
+
+/// Write `record` to `path` atomically: to a temporary file first, then renamed over it.
+fn persist(path: &Path, record: &SignedBlock) -> Result<(), DoubleSignError> {
+    let tmp = path.with_extension("tmp");
+    let json = serde_json::to_vec(record).map_err(|e| DoubleSignError::Persist(e.to_string()))?;
+    std::fs::write(&tmp, json)
+        .and_then(|()| std::fs::rename(&tmp, path))
+        .map_err(|e| DoubleSignError::Persist(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNER: Address = Address::new([0xaa; 20]);
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bor-double-sign-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("last-signed.json")
+    }
+
+    #[test]
+    fn test_refuses_competing_block_at_same_height() {
+        let guard = DoubleSignGuard::new();
+        guard.authorize(SIGNER, 10, B256::with_last_byte(1)).unwrap();
+        guard.authorize(SIGNER, 10, B256::with_last_byte(1)).unwrap();
+
+        assert_eq!(
+            guard.authorize(SIGNER, 10, B256::with_last_byte(2)),
+            Err(DoubleSignError::DoubleSign {
+                number: 10,
+                signed: B256::with_last_byte(1),
+                requested: B256::with_last_byte(2),
+            })
+        );
+        // A rotated key does not lift the protection.
+        assert!(guard.authorize(Address::new([0xbb; 20]), 10, B256::with_last_byte(2)).is_err());
+        assert!(matches!(
+            guard.authorize(SIGNER, 9, B256::with_last_byte(3)),
+            Err(DoubleSignError::BelowLastSigned { number: 9, last: 10 })
+        ));
+        guard.authorize(SIGNER, 11, B256::with_last_byte(4)).unwrap();
+    }
+
+    #[test]
+    fn test_record_survives_restart() {
+        let path = temp_path("restart");
+        let _ = std::fs::remove_file(&path);
+
+        let guard = DoubleSignGuard::open(&path).unwrap();
+        assert_eq!(guard.last_signed(), None);
+        guard.authorize(SIGNER, 42, B256::with_last_byte(1)).unwrap();
+        drop(guard);
+
+        let reopened = DoubleSignGuard::open(&path).unwrap();
+        assert_eq!(reopened.last_signed().map(|b| b.number), Some(42));
+        assert!(reopened.authorize(SIGNER, 42, B256::with_last_byte(2)).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
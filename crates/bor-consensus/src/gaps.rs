@@ -0,0 +1,174 @@
+//! Bor consensus checks boreth does not perform yet.
+//!
+//! [`VALIDATION_GAPS`] lists them. Some are skipped only when what they need is
+//! missing, e.g. the validators of a block whose span has not been fetched; under
+//! [`STRICT_CONSENSUS`] such blocks are not accepted unchecked but put in
+//! [`DeferredChecks`], whose executor fails them with a retryable error. Some are
+//! performed only when what they need is at hand, which it is not for most blocks
+//! behind the tip, so they are skipped even then. Some are performed only when the
+//! node is told to. Any not implemented at all are listed too, so they are not
+//! forgotten.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use alloy_primitives::B256;
+
+/// Whether blocks reaching a check boreth cannot perform are held back until it can.
+///
+/// On in release builds. Debug builds, tests included, accept such blocks unchecked
+/// unless built with the `strict-consensus` feature.
+pub const STRICT_CONSENSUS: bool = !cfg!(debug_assertions) || cfg!(feature = "strict-consensus");
+
+/// A consensus check boreth skips or lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationGap {
+    /// The validators of the block are unknown: its span is not cached and contract
+    /// state is unavailable, so its signer cannot be authorized.
+    UnknownValidators,
+    /// The header's difficulty against the signer's succession number, when the span
+    /// of the block is not cached.
+    Difficulty,
+    /// The header's timestamp against the signer's producer delay after the parent, when
+    /// the span of the block is not cached.
+    ProducerDelay,
+    /// The validator bytes in the extra data of sprint-end headers against the
+    /// next span, when it is not cached.
+    SprintEndValidators,
+    /// The receipts root and logs bloom against the executed receipts, unless the node
+    /// asserts roots.
+    ReceiptsRoot,
+}
+
+/// How boreth treats a [`ValidationGap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapHandling {
+    /// Skipped when what the check needs is missing; deferred under
+    /// [`STRICT_CONSENSUS`].
+    FailsClosed,
+    /// Checked when what the check needs is at hand, skipped otherwise even under
+    /// [`STRICT_CONSENSUS`].
+    BestEffort,
+    /// Checked only when the node is told to, e.g. with `--bor.assert-roots`; every block
+    /// passes otherwise.
+    OptIn,
+    /// Not implemented: every block passes.
+    Missing,
+}
+
+/// Every gap, with how it is handled.
+pub const VALIDATION_GAPS: &[(ValidationGap, GapHandling)] = &[
+    (ValidationGap::UnknownValidators, GapHandling::FailsClosed),
+    (ValidationGap::Difficulty, GapHandling::BestEffort),
+    (ValidationGap::ProducerDelay, GapHandling::BestEffort),
+    (ValidationGap::SprintEndValidators, GapHandling::BestEffort),
+    (ValidationGap::ReceiptsRoot, GapHandling::OptIn),
+];
+
+impl ValidationGap {
+    /// Short name of the check, as recorded with bad blocks.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::UnknownValidators => "unknown_validators",
+            Self::Difficulty => "difficulty",
+            Self::ProducerDelay => "producer_delay",
+            Self::SprintEndValidators => "sprint_end_validators",
+            Self::ReceiptsRoot => "receipts_root",
+        }
+    }
+
+    /// How boreth treats the gap.
+    pub fn handling(self) -> GapHandling {
+        VALIDATION_GAPS
+            .iter()
+            .find(|(gap, _)| *gap == self)
+            .map(|(_, handling)| *handling)
+            .expect("every gap is listed")
+    }
+
+    /// Why block `number`, which needs the check, is not imported yet.
+    pub fn deferred(self, number: u64) -> String {
+        format!("block {number} deferred: {} cannot be checked yet, retry later", self.name())
+    }
+}
+
+/// A [`DeferredChecks`] shared between consensus and the executor.
+pub type SharedDeferredChecks = Arc<DeferredChecks>;
+
+/// Blocks consensus let through under [`STRICT_CONSENSUS`] without a check it could not
+/// perform, by number and parent hash.
+///
+/// Rejecting such a block in consensus would mark it invalid for good, although it is
+/// only unchecked until Heimdall serves what the check needs. Instead the executor
+/// given the same set fails it before its transactions with an internal error, which
+/// reth reports as a failed import rather than an invalid block, so the block is
+/// validated again when it is next offered.
+#[derive(Debug, Default)]
+pub struct DeferredChecks {
+    blocks: Mutex<BTreeMap<(u64, B256), ValidationGap>>,
+}
+
+impl DeferredChecks {
+    /// An empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold back block `number` on `parent_hash`, which needs `gap` checked.
+    pub fn defer(&self, number: u64, parent_hash: B256, gap: ValidationGap) {
+        let mut blocks = self.blocks.lock().expect("deferred checks lock poisoned");
+        blocks.insert((number, parent_hash), gap);
+    }
+
+    /// The check block `number` on `parent_hash` was held back for, if any, forgetting it.
+    pub fn take(&self, number: u64, parent_hash: B256) -> Option<ValidationGap> {
+        self.blocks.lock().expect("deferred checks lock poisoned").remove(&(number, parent_hash))
+    }
+
+    /// Returns `true` if no block is held back.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.lock().expect("deferred checks lock poisoned").is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Closing a gap, or finding a new one, must update this table.
+    #[test]
+    fn test_remaining_gaps() {
+        let gaps: Vec<_> =
+            VALIDATION_GAPS.iter().map(|(gap, handling)| (gap.name(), *handling)).collect();
+        assert_eq!(
+            gaps,
+            [
+                ("unknown_validators", GapHandling::FailsClosed),
+                ("difficulty", GapHandling::BestEffort),
+                ("producer_delay", GapHandling::BestEffort),
+                ("sprint_end_validators", GapHandling::BestEffort),
+                ("receipts_root", GapHandling::OptIn),
+            ]
+        );
+        // Checked with the succession whenever the span is cached.
+        assert_eq!(ValidationGap::Difficulty.handling(), GapHandling::BestEffort);
+        assert_eq!(ValidationGap::ReceiptsRoot.handling(), GapHandling::OptIn);
+    }
+
+    #[test]
+    fn test_deferred_names_the_check() {
+        let err = ValidationGap::UnknownValidators.deferred(64);
+        assert!(err.contains("block 64 deferred: unknown_validators"), "{err}");
+    }
+
+    #[test]
+    fn test_deferred_block_is_taken_once() {
+        let deferred = DeferredChecks::new();
+        let parent = B256::with_last_byte(1);
+        deferred.defer(64, parent, ValidationGap::UnknownValidators);
+        assert_eq!(deferred.take(64, B256::ZERO), None);
+        assert_eq!(deferred.take(64, parent), Some(ValidationGap::UnknownValidators));
+        assert_eq!(deferred.take(64, parent), None);
+        assert!(deferred.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Gas limit movement between consecutive Bor blocks.
+//!
+//! Bor kept Ethereum's pre-London rule: a block's gas limit differs from its
+//! parent's by less than `parent / 1024` and never drops below 5000. Producers
+//! move toward their configured target in steps that stay within that bound.
+
+pub use bor_chainspec::constants::{GAS_LIMIT_BOUND_DIVISOR, MAX_GAS_LIMIT, MIN_GAS_LIMIT};
+use bor_chainspec::BorHardforks;
+use reth_consensus::ConsensusError;
+
+/// Check that `gas_limit` may follow a parent with `parent_gas_limit`.
+pub fn validate_gas_limit(parent_gas_limit: u64, gas_limit: u64) -> Result<(), ConsensusError> {
+    let bound = parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR;
+    if gas_limit > parent_gas_limit && gas_limit - parent_gas_limit >= bound {
+        return Err(ConsensusError::GasLimitInvalidIncrease {
+            parent_gas_limit,
+            child_gas_limit: gas_limit,
+        });
+    }
+    if gas_limit < parent_gas_limit && parent_gas_limit - gas_limit >= bound {
+        return Err(ConsensusError::GasLimitInvalidDecrease {
+            parent_gas_limit,
+            child_gas_limit: gas_limit,
+        });
+    }
+    if gas_limit < MIN_GAS_LIMIT {
+        return Err(ConsensusError::GasLimitInvalidMinimum { child_gas_limit: gas_limit });
+    }
+    Ok(())
+}
+
+/// Gas limit block `number` moves toward: `configured` (`--miner.gaslimit`) if set, the
+/// chain's block gas limit at `number` otherwise.
+pub fn gas_limit_target<F: BorHardforks + ?Sized>(
+    forks: &F,
+    number: u64,
+    configured: Option<u64>,
+) -> u64 {
+    configured.unwrap_or_else(|| forks.bor_block_gas_limit(number))
+}
+
+/// Gas limit of a block built on a parent with `parent_gas_limit`, moving toward `target`
+/// by at most `parent / 1024 - 1`, one less than [`validate_gas_limit`] allows.
+///
+/// Targets outside [`MIN_GAS_LIMIT`]..=[`MAX_GAS_LIMIT`] are clamped to that range.
+pub fn next_gas_limit(parent_gas_limit: u64, target: u64) -> u64 {
+    let target = target.clamp(MIN_GAS_LIMIT, MAX_GAS_LIMIT);
+    let delta = (parent_gas_limit / GAS_LIMIT_BOUND_DIVISOR).saturating_sub(1);
+    if parent_gas_limit < target {
+        parent_gas_limit.saturating_add(delta).min(target)
+    } else {
+        parent_gas_limit.saturating_sub(delta).max(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::{BorHardfork, MainnetBorHardforks};
+
+    #[test]
+    fn test_change_must_stay_below_bound() {
+        let parent = 30_000_000;
+        let bound = parent / GAS_LIMIT_BOUND_DIVISOR;
+        assert!(validate_gas_limit(parent, parent).is_ok());
+        assert!(validate_gas_limit(parent, parent + bound - 1).is_ok());
+        assert!(validate_gas_limit(parent, parent - bound + 1).is_ok());
+        assert!(matches!(
+            validate_gas_limit(parent, parent + bound),
+            Err(ConsensusError::GasLimitInvalidIncrease { .. })
+        ));
+        assert!(matches!(
+            validate_gas_limit(parent, parent - bound),
+            Err(ConsensusError::GasLimitInvalidDecrease { .. })
+        ));
+    }
+
+    #[test]
+    fn test_minimum() {
+        // Within the bound of the parent, but below the floor.
+        assert!(matches!(
+            validate_gas_limit(MIN_GAS_LIMIT, MIN_GAS_LIMIT - 1),
+            Err(ConsensusError::GasLimitInvalidMinimum { child_gas_limit: 4_999 })
+        ));
+        assert!(validate_gas_limit(MIN_GAS_LIMIT, MIN_GAS_LIMIT).is_ok());
+    }
+
+    #[test]
+    fn test_target_follows_chain_unless_configured() {
+        let bhilai = BorHardfork::Bhilai.mainnet_block();
+        assert_eq!(gas_limit_target(&MainnetBorHardforks, bhilai - 1, None), 30_000_000);
+        assert_eq!(gas_limit_target(&MainnetBorHardforks, bhilai, None), 45_000_000);
+        assert_eq!(gas_limit_target(&MainnetBorHardforks, bhilai, Some(60_000_000)), 60_000_000);
+    }
+
+    #[test]
+    fn test_at_target_stays() {
+        assert_eq!(next_gas_limit(30_000_000, 30_000_000), 30_000_000);
+    }
+
+    #[test]
+    fn test_moves_within_bound() {
+        let parent = 30_000_000;
+        let step = parent / GAS_LIMIT_BOUND_DIVISOR - 1;
+        assert_eq!(next_gas_limit(parent, 45_000_000), parent + step);
+        assert_eq!(next_gas_limit(parent, 20_000_000), parent - step);
+        assert!(validate_gas_limit(parent, parent + step).is_ok());
+        assert!(validate_gas_limit(parent, parent - step).is_ok());
+    }
+
+    #[test]
+    fn test_does_not_overshoot() {
+        assert_eq!(next_gas_limit(30_000_000, 30_000_100), 30_000_100);
+        assert_eq!(next_gas_limit(30_000_000, 29_999_900), 29_999_900);
+    }
+
+    #[test]
+    fn test_target_floor() {
+        assert_eq!(next_gas_limit(MIN_GAS_LIMIT + 1, 0), MIN_GAS_LIMIT);
+    }
+
+    #[test]
+    fn test_target_ceiling() {
+        assert_eq!(next_gas_limit(MAX_GAS_LIMIT, u64::MAX), MAX_GAS_LIMIT);
+    }
+}
@@ -0,0 +1,89 @@
+//! Fee-model rules from Jaipur.
+//!
+//! Jaipur brought London's EIP-1559 fee market to Bor, on mainnet and Amoy at the same
+//! block as London itself. From then on every header carries a base fee, and every
+//! transaction must be willing to pay it: its fee cap (the gas price of legacy and
+//! access-list transactions) is at least the base fee, and a dynamic-fee transaction
+//! does not offer a tip above its fee cap. Before Jaipur none of this is checked.
+//!
+//! The rules are keyed on Bor's own Jaipur block rather than reth's London activation,
+//! so a chain whose genesis gives one but not the other follows Bor.
+
+use alloy_consensus::Transaction;
+use reth_consensus::ConsensusError;
+
+/// Check that a header carries a base fee if Jaipur is active at it.
+pub fn validate_jaipur_header(
+    jaipur_active: bool,
+    base_fee: Option<u64>,
+) -> Result<(), ConsensusError> {
+    if jaipur_active && base_fee.is_none() {
+        return Err(ConsensusError::BaseFeeMissing);
+    }
+    Ok(())
+}
+
+/// Check the fee caps of the transactions of block `number` against its `base_fee`.
+pub fn validate_jaipur_transactions<'a, T: Transaction + 'a>(
+    number: u64,
+    jaipur_active: bool,
+    base_fee: Option<u64>,
+    transactions: impl IntoIterator<Item = &'a T>,
+) -> Result<(), ConsensusError> {
+    if !jaipur_active {
+        return Ok(());
+    }
+    let base_fee = u128::from(base_fee.ok_or(ConsensusError::BaseFeeMissing)?);
+    for (index, tx) in transactions.into_iter().enumerate() {
+        let fee_cap = tx.max_fee_per_gas();
+        if fee_cap < base_fee {
+            return Err(ConsensusError::Other(format!(
+                "block {number}: transaction {index} has fee cap {fee_cap} below base fee \
+                 {base_fee}"
+            )));
+        }
+        if let Some(tip) = tx.max_priority_fee_per_gas().filter(|tip| *tip > fee_cap) {
+            return Err(ConsensusError::Other(format!(
+                "block {number}: transaction {index} has tip {tip} above fee cap {fee_cap}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{TxEip1559, TxLegacy};
+
+    fn dynamic(max_fee_per_gas: u128, max_priority_fee_per_gas: u128) -> TxEip1559 {
+        TxEip1559 { max_fee_per_gas, max_priority_fee_per_gas, ..Default::default() }
+    }
+
+    #[test]
+    fn test_header_needs_base_fee_from_jaipur() {
+        assert!(validate_jaipur_header(false, None).is_ok());
+        assert!(validate_jaipur_header(true, Some(7)).is_ok());
+        assert!(matches!(
+            validate_jaipur_header(true, None),
+            Err(ConsensusError::BaseFeeMissing)
+        ));
+    }
+
+    #[test]
+    fn test_fee_caps_cover_base_fee_from_jaipur() {
+        let low = [dynamic(6, 1)];
+        assert!(validate_jaipur_transactions(9, false, None, &low).is_ok());
+        let err = validate_jaipur_transactions(9, true, Some(7), &low).unwrap_err();
+        assert!(err.to_string().contains("transaction 0 has fee cap 6 below base fee 7"), "{err}");
+
+        let txs = [dynamic(7, 0), dynamic(10, 10)];
+        assert!(validate_jaipur_transactions(9, true, Some(7), &txs).is_ok());
+        let greedy = [dynamic(7, 0), dynamic(10, 11)];
+        let err = validate_jaipur_transactions(9, true, Some(7), &greedy).unwrap_err();
+        assert!(err.to_string().contains("transaction 1 has tip 11 above fee cap 10"), "{err}");
+
+        let legacy = [TxLegacy { gas_price: 6, ..Default::default() }];
+        assert!(validate_jaipur_transactions(9, true, Some(7), &legacy).is_err());
+    }
+}
@@ -1,10 +1,31 @@
 //! Bor consensus engine implementation.
 
+pub mod double_sign;
+pub use double_sign::{DoubleSignError, DoubleSignGuard, SharedDoubleSignGuard, SignedBlock};
+
 pub mod difficulty;
 pub use difficulty::{calculate_difficulty, is_inturn};
 
-pub mod extra_data;
-pub use extra_data::ExtraData;
+pub use bor_primitives::extra_data;
+pub use extra_data::{BlockExtraData, ExtraData, ExtraDataBuilder, ExtraDataError};
+
+pub mod gaps;
+pub use gaps::{
+    DeferredChecks, GapHandling, SharedDeferredChecks, ValidationGap, STRICT_CONSENSUS,
+    VALIDATION_GAPS,
+};
+
+pub mod gas_limit;
+pub use gas_limit::{
+    gas_limit_target, next_gas_limit, validate_gas_limit, GAS_LIMIT_BOUND_DIVISOR, MAX_GAS_LIMIT,
+    MIN_GAS_LIMIT,
+};
+
+pub mod jaipur;
+pub use jaipur::{validate_jaipur_header, validate_jaipur_transactions};
+
+pub mod milestone;
+pub use milestone::{LockedMilestone, MilestoneTracker, MilestoneVoteError};
 
 pub mod proposer;
 
@@ -14,9 +35,29 @@ pub use recents::Recents;
 pub mod snapshot;
 pub use snapshot::BorSnapshot;
 
+pub mod snapshot_delta;
+pub use snapshot_delta::{SnapshotDelta, SnapshotView};
+
+pub mod snapshot_rebuild;
+pub use snapshot_rebuild::{
+    RebuildProgress, SnapshotRebuildError, SnapshotRebuilder, DEFAULT_REBUILD_CHUNK_SIZE,
+};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+pub mod succession;
+pub use succession::{
+    producer_delay, sprint_validator_set, succession_difficulty, succession_number,
+    validate_succession,
+};
+
 pub mod seal;
 pub use seal::{compute_seal_hash, ecrecover_seal, SealError};
 
+pub mod sealer;
+pub use sealer::{BlockSealer, SealerError};
+
 pub mod block_validation;
 pub use block_validation::{validate_block_pre_execution, validate_block_post_execution};
 
@@ -26,5 +67,16 @@ pub use validation::{
     validate_header, validate_header_against_parent,
 };
 
+pub mod validator_mismatch;
+pub use validator_mismatch::{
+    ExpectedValidator, LastValidatorMismatch, SharedLastValidatorMismatch, ValidatorMismatch,
+};
+
+pub mod verification_source;
+pub use verification_source::{
+    ContractValidatorSource, VerificationSource, VerificationSourceSelector,
+    DEFAULT_CONTRACT_STATE_DISTANCE,
+};
+
 pub mod reth_consensus;
-pub use reth_consensus::BorConsensus;
+pub use reth_consensus::{validate_ommers, BorConsensus, SPAN_CACHE_SIZE};
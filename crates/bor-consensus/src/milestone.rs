@@ -0,0 +1,347 @@
+//! Milestone finality tracking.
+//!
+//! Heimdall milestones finalize ranges of Bor blocks long before the matching checkpoint
+//! lands on L1. [`MilestoneTracker`] keeps the latest milestone together with a bounded
+//! history keyed by milestone ID, so RPC handlers can answer finality queries without
+//! round-tripping to Heimdall. It also records the latest checkpoint, which backs the
+//! `safe` block tag.
+//!
+//! Before Heimdall agrees on a milestone, it asks the validators to vote on its end
+//! block (`bor_getVoteOnHash`). A validator voting for a milestone *locks* it, as Bor
+//! does, and refuses to vote for one ending before it until a milestone at or past the
+//! locked block arrives; see [`MilestoneTracker::vote`].
+
+use alloy_primitives::B256;
+use heimdall_client::{Checkpoint, Milestone};
+use std::collections::{HashMap, VecDeque};
+use std::sync::RwLock;
+
+/// Maximum number of milestones retained for lookups by ID.
+pub const MAX_TRACKED_MILESTONES: usize = 256;
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    latest: Option<Milestone>,
+    by_id: HashMap<String, Milestone>,
+    /// Insertion order of `by_id`, oldest first.
+    order: VecDeque<String>,
+    latest_checkpoint: Option<Checkpoint>,
+    locked: Option<LockedMilestone>,
+}
+
+/// The milestone end block the node voted for last, not yet finalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedMilestone {
+    /// End block voted for.
+    pub end_block: u64,
+    /// Local hash of the end block.
+    pub hash: B256,
+    /// IDs of the milestone proposals voted for with this end block.
+    pub milestone_ids: Vec<String>,
+}
+
+/// Reasons not to vote for a milestone proposal.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MilestoneVoteError {
+    /// A milestone at or past the end block is already final.
+    #[error("end block {end_block} is not past finalized block {finalized}")]
+    Finalized {
+        /// End block of the proposal.
+        end_block: u64,
+        /// End block of the latest milestone.
+        finalized: u64,
+    },
+    /// The node voted for a later end block that is not final yet.
+    #[error("end block {end_block} is before locked block {locked}")]
+    BehindLock {
+        /// End block of the proposal.
+        end_block: u64,
+        /// End block voted for last.
+        locked: u64,
+    },
+    /// The proposal's end block is not the local one.
+    #[error("hash mismatch: local {local}, milestone {milestone}")]
+    HashMismatch {
+        /// Local hash of the end block.
+        local: B256,
+        /// Hash in the proposal.
+        milestone: B256,
+    },
+}
+
+/// Thread-safe store of the milestones observed from Heimdall.
+#[derive(Debug, Default)]
+pub struct MilestoneTracker {
+    state: RwLock<TrackerState>,
+}
+
+impl MilestoneTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a milestone.
+    ///
+    /// Returns `true` if the milestone advanced the finalized height. Milestones that do not
+    /// move past the current latest end block are still indexed by ID but do not replace it.
+    pub fn update(&self, milestone: Milestone) -> bool {
+        let mut state = self.state.write().unwrap();
+
+        if !milestone.milestone_id.is_empty() && !state.by_id.contains_key(&milestone.milestone_id)
+        {
+            if state.order.len() == MAX_TRACKED_MILESTONES {
+                let evicted = state.order.pop_front().expect("history is full");
+                state.by_id.remove(&evicted);
+            }
+            state.order.push_back(milestone.milestone_id.clone());
+            state.by_id.insert(milestone.milestone_id.clone(), milestone.clone());
+        }
+
+        let advances = state
+            .latest
+            .as_ref()
+            .is_none_or(|latest| milestone.end_block > latest.end_block);
+        if advances {
+            if state.locked.as_ref().is_some_and(|locked| locked.end_block <= milestone.end_block) {
+                state.locked = None;
+            }
+            state.latest = Some(milestone);
+        }
+        advances
+    }
+
+    /// Vote for the proposal `milestone_id` ending at `end_block` with `hash`, whose local
+    /// hash is `local_hash`, and lock its end block.
+    ///
+    /// The vote is refused if the end block is already final, if the node locked a later
+    /// one, or if the hashes differ. Voting again for the locked block adds the proposal
+    /// to the lock; a later block replaces it.
+    pub fn vote(
+        &self,
+        milestone_id: &str,
+        end_block: u64,
+        hash: B256,
+        local_hash: B256,
+    ) -> Result<(), MilestoneVoteError> {
+        let mut state = self.state.write().unwrap();
+        if let Some(finalized) = state.latest.as_ref().map(|m| m.end_block) {
+            if end_block <= finalized {
+                return Err(MilestoneVoteError::Finalized { end_block, finalized });
+            }
+        }
+        if let Some(locked) = state.locked.as_ref().map(|locked| locked.end_block) {
+            if end_block < locked {
+                return Err(MilestoneVoteError::BehindLock { end_block, locked });
+            }
+        }
+        if hash != local_hash {
+            return Err(MilestoneVoteError::HashMismatch { local: local_hash, milestone: hash });
+        }
+
+        match &mut state.locked {
+            Some(locked) if locked.end_block == end_block && locked.hash == hash => {
+                if !locked.milestone_ids.iter().any(|id| id == milestone_id) {
+                    locked.milestone_ids.push(milestone_id.to_string());
+                }
+            }
+            locked => {
+                *locked = Some(LockedMilestone {
+                    end_block,
+                    hash,
+                    milestone_ids: vec![milestone_id.to_string()],
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the milestone end block the node is locked on, if any.
+    pub fn locked(&self) -> Option<LockedMilestone> {
+        self.state.read().unwrap().locked.clone()
+    }
+
+    /// Returns the latest milestone seen, if any.
+    pub fn latest(&self) -> Option<Milestone> {
+        self.state.read().unwrap().latest.clone()
+    }
+
+    /// Returns the ID of the latest milestone seen, if any.
+    pub fn latest_id(&self) -> Option<String> {
+        self.state.read().unwrap().latest.as_ref().map(|m| m.milestone_id.clone())
+    }
+
+    /// Look up a recently observed milestone by its Heimdall ID.
+    pub fn by_id(&self, milestone_id: &str) -> Option<Milestone> {
+        self.state.read().unwrap().by_id.get(milestone_id).cloned()
+    }
+
+    /// Returns the highest block number finalized by a milestone.
+    pub fn finalized_block(&self) -> Option<u64> {
+        self.state.read().unwrap().latest.as_ref().map(|m| m.end_block)
+    }
+
+    /// Returns `true` if `block_number` is covered by the latest milestone.
+    pub fn is_finalized(&self, block_number: u64) -> bool {
+        self.finalized_block().is_some_and(|end| block_number <= end)
+    }
+
+    /// Record a checkpoint. Returns `true` if it advanced the checkpointed height.
+    pub fn update_checkpoint(&self, checkpoint: Checkpoint) -> bool {
+        let mut state = self.state.write().unwrap();
+        let advances = state
+            .latest_checkpoint
+            .as_ref()
+            .is_none_or(|latest| checkpoint.end_block > latest.end_block);
+        if advances {
+            state.latest_checkpoint = Some(checkpoint);
+        }
+        advances
+    }
+
+    /// Returns the latest checkpoint seen, if any.
+    pub fn latest_checkpoint(&self) -> Option<Checkpoint> {
+        self.state.read().unwrap().latest_checkpoint.clone()
+    }
+
+    /// Returns the block number the `finalized` tag resolves to.
+    ///
+    /// Bor treats the latest milestone as finalized. Until the first milestone is seen the
+    /// latest checkpoint is used instead, since checkpointed blocks are final as well.
+    pub fn finalized_tag_block(&self) -> Option<u64> {
+        let state = self.state.read().unwrap();
+        state
+            .latest
+            .as_ref()
+            .map(|m| m.end_block)
+            .or_else(|| state.latest_checkpoint.as_ref().map(|c| c.end_block))
+    }
+
+    /// Returns the block number the `safe` tag resolves to: the end of the latest checkpoint.
+    pub fn safe_tag_block(&self) -> Option<u64> {
+        self.state.read().unwrap().latest_checkpoint.as_ref().map(|c| c.end_block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256};
+
+    fn milestone(id: &str, start: u64, end: u64) -> Milestone {
+        Milestone {
+            milestone_id: id.to_string(),
+            start_block: start,
+            end_block: end,
+            hash: B256::from([end as u8; 32]),
+            proposer: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_empty_tracker() {
+        let tracker = MilestoneTracker::new();
+        assert!(tracker.latest().is_none());
+        assert!(tracker.finalized_block().is_none());
+        assert!(!tracker.is_finalized(0));
+    }
+
+    #[test]
+    fn test_update_advances_latest() {
+        let tracker = MilestoneTracker::new();
+        assert!(tracker.update(milestone("a", 0, 100)));
+        assert!(tracker.update(milestone("b", 101, 200)));
+
+        assert_eq!(tracker.latest_id().as_deref(), Some("b"));
+        assert_eq!(tracker.finalized_block(), Some(200));
+        assert!(tracker.is_finalized(200));
+        assert!(!tracker.is_finalized(201));
+    }
+
+    #[test]
+    fn test_vote_locks_end_block() {
+        let tracker = MilestoneTracker::new();
+        let hash = B256::repeat_byte(0x64);
+        tracker.vote("a", 64, hash, hash).unwrap();
+        tracker.vote("b", 64, hash, hash).unwrap();
+        let locked = tracker.locked().unwrap();
+        assert_eq!((locked.end_block, locked.milestone_ids.len()), (64, 2));
+
+        assert_eq!(
+            tracker.vote("c", 63, hash, hash),
+            Err(MilestoneVoteError::BehindLock { end_block: 63, locked: 64 })
+        );
+        assert!(matches!(
+            tracker.vote("c", 80, hash, B256::ZERO),
+            Err(MilestoneVoteError::HashMismatch { .. })
+        ));
+        assert_eq!(tracker.locked().unwrap().end_block, 64);
+
+        // The milestone reaching the locked block releases it.
+        tracker.update(milestone("a", 0, 64));
+        assert_eq!(tracker.locked(), None);
+        assert_eq!(
+            tracker.vote("d", 64, hash, hash),
+            Err(MilestoneVoteError::Finalized { end_block: 64, finalized: 64 })
+        );
+    }
+
+    #[test]
+    fn test_stale_milestone_does_not_regress() {
+        let tracker = MilestoneTracker::new();
+        tracker.update(milestone("b", 101, 200));
+        assert!(!tracker.update(milestone("a", 0, 100)));
+
+        assert_eq!(tracker.finalized_block(), Some(200));
+        // Still reachable by ID.
+        assert_eq!(tracker.by_id("a").unwrap().end_block, 100);
+    }
+
+    fn checkpoint(end: u64) -> Checkpoint {
+        Checkpoint {
+            start_block: end.saturating_sub(255),
+            end_block: end,
+            root_hash: B256::ZERO,
+            proposer: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_tags_without_data() {
+        let tracker = MilestoneTracker::new();
+        assert_eq!(tracker.finalized_tag_block(), None);
+        assert_eq!(tracker.safe_tag_block(), None);
+    }
+
+    #[test]
+    fn test_finalized_tag_falls_back_to_checkpoint() {
+        let tracker = MilestoneTracker::new();
+        tracker.update_checkpoint(checkpoint(1_000));
+        assert_eq!(tracker.finalized_tag_block(), Some(1_000));
+        assert_eq!(tracker.safe_tag_block(), Some(1_000));
+
+        tracker.update(milestone("a", 1_001, 1_200));
+        assert_eq!(tracker.finalized_tag_block(), Some(1_200));
+        assert_eq!(tracker.safe_tag_block(), Some(1_000));
+    }
+
+    #[test]
+    fn test_stale_checkpoint_ignored() {
+        let tracker = MilestoneTracker::new();
+        assert!(tracker.update_checkpoint(checkpoint(2_000)));
+        assert!(!tracker.update_checkpoint(checkpoint(1_000)));
+        assert_eq!(tracker.latest_checkpoint().unwrap().end_block, 2_000);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let tracker = MilestoneTracker::new();
+        for i in 0..(MAX_TRACKED_MILESTONES as u64 + 10) {
+            tracker.update(milestone(&format!("ms-{i}"), i * 10, i * 10 + 9));
+        }
+
+        assert!(tracker.by_id("ms-0").is_none());
+        assert!(tracker.by_id("ms-9").is_none());
+        assert!(tracker.by_id("ms-10").is_some());
+    }
+}
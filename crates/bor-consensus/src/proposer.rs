@@ -1,47 +1,205 @@
 //! CometBFT/Tendermint weighted round-robin proposer selection algorithm.
+//!
+//! This is a port of `IncrementProposerPriority` from Bor's `valset` package
+//! (itself taken from Tendermint). Every step uses the same `i64` semantics as
+//! Go, including clipping adds/subs, truncating division when rescaling and
+//! floor division when centering, so proposer order is identical even for
+//! extreme voting powers.
 
 use alloy_primitives::Address;
-use bor_primitives::ValidatorSet;
+use bor_primitives::{Validator, ValidatorSet};
 
-/// Select the next proposer using CometBFT weighted round-robin.
+/// Priorities are rescaled so that `max - min` stays within this many times
+/// the total voting power.
+pub const PRIORITY_WINDOW_SIZE_FACTOR: i64 = 2;
+
+/// Upper bound on the total voting power of a validator set.
 ///
-/// The algorithm:
-/// 1. Increment every validator's `proposer_priority` by their `voting_power`.
-/// 2. The validator with the highest `proposer_priority` is selected as proposer.
-/// 3. Subtract the total voting power from the selected proposer's priority.
-/// 4. Update the proposer field on the validator set and return the address.
-pub fn select_proposer(validator_set: &mut ValidatorSet) -> Address {
-    let total_voting_power: i64 = validator_set
+/// Chosen so that `PRIORITY_WINDOW_SIZE_FACTOR * total` and priority updates
+/// can never overflow.
+pub const MAX_TOTAL_VOTING_POWER: i64 = i64::MAX / 8;
+
+/// Errors raised by the checked proposer priority API.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ProposerError {
+    #[error("empty validator set")]
+    EmptyValidatorSet,
+    #[error("validator {0} has negative voting power")]
+    NegativeVotingPower(Address),
+    #[error("total voting power exceeds {MAX_TOTAL_VOTING_POWER}")]
+    TotalVotingPowerOverflow,
+}
+
+/// `a + b`, clipped to the `i64` range.
+fn safe_add_clip(a: i64, b: i64) -> i64 {
+    a.saturating_add(b)
+}
+
+/// `a - b`, clipped to the `i64` range.
+fn safe_sub_clip(a: i64, b: i64) -> i64 {
+    a.saturating_sub(b)
+}
+
+/// Total voting power, clipped like Go's `updateTotalVotingPower` but without
+/// the bound check.
+fn total_voting_power(validator_set: &ValidatorSet) -> i64 {
+    validator_set
         .validators
         .iter()
-        .map(|v| v.voting_power)
-        .sum();
+        .fold(0, |sum, v| safe_add_clip(sum, v.voting_power))
+}
 
-    // Step 1: increment all priorities by voting_power
-    for v in validator_set.validators.iter_mut() {
-        v.proposer_priority += v.voting_power;
+/// Checks that every voting power is non-negative and that the total does not
+/// exceed [`MAX_TOTAL_VOTING_POWER`]. Returns the total.
+pub fn check_total_voting_power(validator_set: &ValidatorSet) -> Result<i64, ProposerError> {
+    let mut sum: i64 = 0;
+    for v in &validator_set.validators {
+        if v.voting_power < 0 {
+            return Err(ProposerError::NegativeVotingPower(v.signer));
+        }
+        sum = safe_add_clip(sum, v.voting_power);
+        if sum > MAX_TOTAL_VOTING_POWER {
+            return Err(ProposerError::TotalVotingPowerOverflow);
+        }
+    }
+    Ok(sum)
+}
+
+/// Absolute spread between the highest and lowest priority, with Go's
+/// wrapping arithmetic.
+fn max_min_priority_diff(validator_set: &ValidatorSet) -> i64 {
+    let mut max = i64::MIN;
+    let mut min = i64::MAX;
+    for v in &validator_set.validators {
+        min = min.min(v.proposer_priority);
+        max = max.max(v.proposer_priority);
     }
+    let diff = max.wrapping_sub(min);
+    diff.wrapping_abs()
+}
+
+/// Divides all priorities down so their spread is at most `diff_max`.
+fn rescale_priorities(validator_set: &mut ValidatorSet, diff_max: i64) {
+    if diff_max <= 0 {
+        return;
+    }
+    let diff = max_min_priority_diff(validator_set);
+    if diff > diff_max {
+        let ratio = diff.wrapping_add(diff_max).wrapping_sub(1) / diff_max;
+        for v in validator_set.validators.iter_mut() {
+            v.proposer_priority /= ratio;
+        }
+    }
+}
 
-    // Step 2: find validator with highest proposer_priority
-    let selected_idx = validator_set
+/// Average priority, rounded towards negative infinity like Go's `big.Int.Div`.
+fn average_priority(validator_set: &ValidatorSet) -> i64 {
+    let n = validator_set.validators.len() as i128;
+    let sum: i128 = validator_set
         .validators
         .iter()
-        .enumerate()
-        .max_by_key(|(_, v)| v.proposer_priority)
-        .map(|(i, _)| i)
-        .expect("validator set must not be empty");
+        .map(|v| v.proposer_priority as i128)
+        .sum();
+    sum.div_euclid(n) as i64
+}
+
+/// Centers priorities around zero.
+fn shift_by_average_priority(validator_set: &mut ValidatorSet) {
+    let avg = average_priority(validator_set);
+    for v in validator_set.validators.iter_mut() {
+        v.proposer_priority = safe_sub_clip(v.proposer_priority, avg);
+    }
+}
+
+/// Index of the validator with the highest priority; ties go to the lower address.
+fn most_priority_index(validators: &[Validator]) -> usize {
+    let mut best = 0;
+    for (i, v) in validators.iter().enumerate().skip(1) {
+        let current = &validators[best];
+        if v.proposer_priority > current.proposer_priority
+            || (v.proposer_priority == current.proposer_priority && v.signer < current.signer)
+        {
+            best = i;
+        }
+    }
+    best
+}
+
+fn increment_unchecked(validator_set: &mut ValidatorSet, times: usize, total: i64) -> Address {
+    assert!(!validator_set.validators.is_empty(), "validator set must not be empty");
+
+    let diff_max = PRIORITY_WINDOW_SIZE_FACTOR.wrapping_mul(total);
+    rescale_priorities(validator_set, diff_max);
+    shift_by_average_priority(validator_set);
 
-    // Step 3: subtract total voting power from selected proposer
-    validator_set.validators[selected_idx].proposer_priority -= total_voting_power;
+    let mut selected = 0;
+    for _ in 0..times {
+        for v in validator_set.validators.iter_mut() {
+            v.proposer_priority = safe_add_clip(v.proposer_priority, v.voting_power);
+        }
+        selected = most_priority_index(&validator_set.validators);
+        let proposer = &mut validator_set.validators[selected];
+        proposer.proposer_priority = safe_sub_clip(proposer.proposer_priority, total);
+    }
 
-    // Step 4: set proposer and return address
-    let proposer = validator_set.validators[selected_idx].clone();
+    let proposer = validator_set.validators[selected].clone();
     let address = proposer.signer;
     validator_set.proposer = Some(proposer);
-
     address
 }
 
+/// Advance proposer priorities `times` rounds and return the resulting proposer.
+///
+/// Mirrors Go's `IncrementProposerPriority`: priorities are first rescaled into
+/// a window of `PRIORITY_WINDOW_SIZE_FACTOR * total` and centered on zero, then
+/// each round adds every validator's voting power and charges the winner the
+/// total. Unlike [`select_proposer`], the voting powers are checked first.
+/// A `times` of zero is treated as one.
+pub fn increment_proposer_priority(
+    validator_set: &mut ValidatorSet,
+    times: usize,
+) -> Result<Address, ProposerError> {
+    if validator_set.validators.is_empty() {
+        return Err(ProposerError::EmptyValidatorSet);
+    }
+    let total = check_total_voting_power(validator_set)?;
+    Ok(increment_unchecked(validator_set, times.max(1), total))
+}
+
+/// Select the next proposer using CometBFT weighted round-robin.
+///
+/// Equivalent to one round of [`increment_proposer_priority`] without the
+/// voting power checks: totals above [`MAX_TOTAL_VOTING_POWER`] are clipped
+/// rather than rejected.
+///
+/// # Panics
+///
+/// Panics if the validator set is empty.
+pub fn select_proposer(validator_set: &mut ValidatorSet) -> Address {
+    let total = total_voting_power(validator_set);
+    increment_unchecked(validator_set, 1, total)
+}
+
+/// Returns the current proposer of the validator set without advancing it.
+///
+/// Uses the set's recorded proposer when present. Otherwise falls back to the
+/// validator with the highest `proposer_priority`, breaking ties by the lower
+/// address as CometBFT does. Returns `None` for an empty set.
+pub fn current_proposer(validator_set: &ValidatorSet) -> Option<Address> {
+    if let Some(proposer) = &validator_set.proposer {
+        return Some(proposer.signer);
+    }
+    validator_set
+        .validators
+        .iter()
+        .max_by(|a, b| {
+            a.proposer_priority
+                .cmp(&b.proposer_priority)
+                .then_with(|| b.signer.cmp(&a.signer))
+        })
+        .map(|v| v.signer)
+}
+
 /// Get the block producer for a specific sprint within a span.
 ///
 /// Runs `select_proposer` for the given sprint, advancing the validator set state.
@@ -134,6 +292,135 @@ mod tests {
         assert_eq!(count_b, 100);
     }
 
+    fn with_priorities(powers_and_priorities: &[(i64, i64)]) -> ValidatorSet {
+        make_validator_set(
+            powers_and_priorities
+                .iter()
+                .enumerate()
+                .map(|(i, &(power, priority))| {
+                    let mut v = make_validator(i as u64 + 1, i as u8 + 1, power);
+                    v.proposer_priority = priority;
+                    v
+                })
+                .collect(),
+        )
+    }
+
+    fn priorities(vs: &ValidatorSet) -> Vec<i64> {
+        vs.validators.iter().map(|v| v.proposer_priority).collect()
+    }
+
+    #[test]
+    fn test_check_total_voting_power() {
+        let vs = with_priorities(&[(MAX_TOTAL_VOTING_POWER - 1, 0), (1, 0)]);
+        assert_eq!(check_total_voting_power(&vs), Ok(MAX_TOTAL_VOTING_POWER));
+
+        let vs = with_priorities(&[(MAX_TOTAL_VOTING_POWER, 0), (1, 0)]);
+        assert_eq!(check_total_voting_power(&vs), Err(ProposerError::TotalVotingPowerOverflow));
+
+        let vs = with_priorities(&[(i64::MAX, 0), (i64::MAX, 0)]);
+        assert_eq!(check_total_voting_power(&vs), Err(ProposerError::TotalVotingPowerOverflow));
+
+        let vs = with_priorities(&[(10, 0), (-1, 0)]);
+        assert_eq!(
+            check_total_voting_power(&vs),
+            Err(ProposerError::NegativeVotingPower(Address::new([2; 20])))
+        );
+    }
+
+    #[test]
+    fn test_increment_rejects_invalid_sets() {
+        let mut empty = make_validator_set(vec![]);
+        assert_eq!(
+            increment_proposer_priority(&mut empty, 1),
+            Err(ProposerError::EmptyValidatorSet)
+        );
+
+        let mut vs = with_priorities(&[(i64::MAX / 2, 0), (i64::MAX / 3, 0), (1, 0)]);
+        assert_eq!(
+            increment_proposer_priority(&mut vs, 1),
+            Err(ProposerError::TotalVotingPowerOverflow)
+        );
+        // Rejected sets are left untouched.
+        assert_eq!(priorities(&vs), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_extreme_powers_within_bound() {
+        let mut vs = with_priorities(&[(1, 0), (MAX_TOTAL_VOTING_POWER - 1, 0)]);
+        for _ in 0..4 {
+            assert_eq!(increment_proposer_priority(&mut vs, 1), Ok(Address::new([2; 20])));
+        }
+        assert_eq!(priorities(&vs), vec![4, -4]);
+    }
+
+    #[test]
+    fn test_rescale_truncates_towards_zero() {
+        // Spread 2000 > 2 * total 3: ratio = ceil(2000 / 6) = 334, and -1000 / 334
+        // truncates to -2 as in Go.
+        let mut vs = with_priorities(&[(1, 1000), (1, -1000), (1, 0)]);
+        assert_eq!(select_proposer(&mut vs), Address::new([1; 20]));
+        assert_eq!(priorities(&vs), vec![0, -1, 1]);
+    }
+
+    #[test]
+    fn test_average_rounds_down() {
+        // Average of -3 and 0 is -2 under floor division (not -1).
+        let mut vs = with_priorities(&[(1, -3), (1, 0)]);
+        assert_eq!(select_proposer(&mut vs), Address::new([2; 20]));
+        assert_eq!(priorities(&vs), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_spread_wraps_like_go() {
+        // MAX - MIN wraps to -1 in Go, so no rescale happens; later adds clip.
+        let mut vs = with_priorities(&[(10, i64::MAX), (20, i64::MIN), (30, 0)]);
+        assert_eq!(select_proposer(&mut vs), Address::new([1; 20]));
+        assert_eq!(priorities(&vs), vec![i64::MAX - 60, i64::MIN + 21, 31]);
+    }
+
+    #[test]
+    fn test_increment_multiple_times() {
+        let mut vs = with_priorities(&[(100, 0), (200, 0), (300, 0)]);
+        assert_eq!(increment_proposer_priority(&mut vs, 3), Ok(Address::new([1; 20])));
+        assert_eq!(priorities(&vs), vec![-300, 0, 300]);
+        assert_eq!(vs.proposer.as_ref().unwrap().signer, Address::new([1; 20]));
+    }
+
+    #[test]
+    fn test_equal_priority_tie_breaks_on_lower_address() {
+        let mut vs = make_validator_set(vec![
+            make_validator(1, 0xcc, 100),
+            make_validator(2, 0xaa, 100),
+            make_validator(3, 0xbb, 100),
+        ]);
+        let order: Vec<_> = (0..3).map(|_| select_proposer(&mut vs)).collect();
+        assert_eq!(
+            order,
+            vec![Address::new([0xaa; 20]), Address::new([0xbb; 20]), Address::new([0xcc; 20])]
+        );
+    }
+
+    #[test]
+    fn test_current_proposer() {
+        let mut vs = make_validator_set(vec![
+            make_validator(1, 0xaa, 100),
+            make_validator(2, 0xbb, 100),
+        ]);
+        assert!(current_proposer(&make_validator_set(vec![])).is_none());
+
+        // Equal priorities: lower address wins.
+        assert_eq!(current_proposer(&vs), Some(Address::new([0xaa; 20])));
+
+        vs.validators[1].proposer_priority = 10;
+        assert_eq!(current_proposer(&vs), Some(Address::new([0xbb; 20])));
+
+        // A recorded proposer takes precedence and is not advanced.
+        let selected = select_proposer(&mut vs);
+        assert_eq!(current_proposer(&vs), Some(selected));
+        assert_eq!(current_proposer(&vs), Some(selected));
+    }
+
     #[test]
     fn test_proposer_deterministic() {
         let make_set = || {
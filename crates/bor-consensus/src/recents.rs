@@ -37,6 +37,11 @@ impl Recents {
         self.signers.insert(block, signer);
     }
 
+    /// Returns the tracked `(block, signer)` pairs in block order.
+    pub fn signers(&self) -> impl Iterator<Item = (u64, Address)> + '_ {
+        self.signers.iter().map(|(block, signer)| (*block, *signer))
+    }
+
     /// Prune entries older than the window
     pub fn prune(&mut self, current_block: u64, validator_count: usize) {
         let window = validator_count / 2 + 1;
@@ -6,7 +6,10 @@
 //! # Architecture
 //!
 //! Header-only validation (`validate_header`) performs structural checks that don't
-//! require external state (nonce, ommers, extra data format, etc.).
+//! require external state (nonce, ommers, extra data format, etc.). Validation against the
+//! parent adds the succession rules when the span of the block is cached: the signer must
+//! wait the chain's delay for its position behind the sprint proposer, and the header must
+//! carry the difficulty of that position.
 //!
 //! Block-level validation (`validate_block_pre_execution`) performs full seal verification:
 //! - Recovers the block signer via ecrecover from the seal
@@ -14,12 +17,38 @@
 //! - Checks the anti-double-sign window
 //!
 //! The span cache must be populated eagerly before blocks are validated. This is typically
-//! done by a separate component that pre-fetches spans from Heimdall.
+//! done by a separate component that pre-fetches spans from Heimdall. Blocks far behind the
+//! tip can instead be checked against the ValidatorSet contract, see
+//! [`with_contract_state_verification`](BorConsensus::with_contract_state_verification).
+//!
+//! When a bad block store is attached, every block rejected by the block-level checks is
+//! recorded there together with the span and recent signers it was judged against. Blocks
+//! rejected after execution also carry the IDs of the state sync events they applied, read
+//! from the state sync store the executor records them in, when one is attached.
+//! When a Heimdall journal is attached, every span missing from the cache when a block
+//! needed it is recorded there, so the skipped signer checks are not forgotten. Under
+//! [`STRICT_CONSENSUS`](crate::gaps::STRICT_CONSENSUS) such blocks are held back in the
+//! [`DeferredChecks`] shared with the executor instead, which fails their import with an
+//! error reth retries rather than one marking them invalid.
+//!
+//! The validator bytes of sprint-end blocks are checked against the next span when it
+//! is cached. A mismatch is rejected with the [`ValidatorMismatch`] spelling out both
+//! lists, which is also kept for `debug_borLastValidatorMismatch` when a record is
+//! attached.
+//!
+//! After execution the gas used is compared with the header. With
+//! [`with_root_assertion`](BorConsensus::with_root_assertion), the receipts root and logs
+//! bloom are compared too, for `--bor.assert-roots`.
 
-use alloy_consensus::EMPTY_OMMER_ROOT_HASH;
-use alloy_primitives::Address;
+use alloy_consensus::{
+    proofs::calculate_receipt_root, TxReceipt, Typed2718, EMPTY_OMMER_ROOT_HASH,
+};
+use alloy_primitives::{Address, Bloom, B256};
+use bor_chainspec::constants::{EXTRADATA_SEAL_LEN, EXTRADATA_VANITY_LEN};
+use bor_chainspec::{BorHardforks, ScheduleError};
 use bor_primitives::Span;
-use heimdall_client::SpanCache;
+use bor_storage::{BadBlockRecord, SharedBadBlockStore, SharedStateSyncStore, SnapshotSummary};
+use heimdall_client::{SharedHeimdallJournal, SharedSpanCache, SpanCache};
 use reth_chainspec::{EthChainSpec, EthereumHardforks};
 use reth_consensus::{Consensus, ConsensusError, FullConsensus, HeaderValidator, ReceiptRootBloom};
 use reth_execution_types::BlockExecutionResult;
@@ -32,8 +61,20 @@ use std::sync::{Arc, Mutex};
 use tracing::{debug, warn};
 
 use crate::extra_data::ExtraData;
+use crate::gaps::{DeferredChecks, SharedDeferredChecks, ValidationGap, STRICT_CONSENSUS};
+use crate::gas_limit::{validate_gas_limit, MAX_GAS_LIMIT};
+use crate::jaipur::{validate_jaipur_header, validate_jaipur_transactions};
+use crate::milestone::MilestoneTracker;
 use crate::recents::Recents;
 use crate::seal::{compute_seal_hash, ecrecover_seal};
+use crate::succession::{sprint_validator_set, validate_succession};
+use crate::validator_mismatch::{SharedLastValidatorMismatch, ValidatorMismatch};
+use crate::verification_source::{
+    ContractValidatorSource, VerificationSource, VerificationSourceSelector,
+};
+
+/// Number of spans [`BorConsensus`] keeps cached by default.
+pub const SPAN_CACHE_SIZE: usize = 64;
 
 /// Bor consensus engine for Reth.
 ///
@@ -44,7 +85,8 @@ use crate::seal::{compute_seal_hash, ecrecover_seal};
 /// - No withdrawals (Polygon does not use Ethereum withdrawals)
 /// - Difficulty is non-zero (PoA in-turn / not-in-turn)
 /// - Extra data contains vanity + optional validators + seal
-/// - Gas limit and base fee validated per Ethereum rules
+/// - Gas limit moves within `parent / 1024` of the parent's; base fee per Ethereum rules
+/// - From Jaipur, a base fee is set and every transaction's fee cap covers it
 /// - Nonce must be zero
 /// - Seal is verified against the authorized validator set
 #[derive(Debug)]
@@ -52,9 +94,32 @@ pub struct BorConsensus<ChainSpec> {
     /// Chain specification.
     chain_spec: Arc<ChainSpec>,
     /// Cached Heimdall spans for validator set lookups.
-    span_cache: Mutex<SpanCache>,
+    span_cache: SharedSpanCache,
     /// Recent block signers for anti-double-sign enforcement.
     recents: Mutex<Recents>,
+    /// Where rejected blocks are recorded, if anywhere.
+    bad_blocks: Option<SharedBadBlockStore>,
+    /// Where the executor records the state sync events of blocks, if anywhere.
+    state_syncs: Option<SharedStateSyncStore>,
+    /// Validators read from contract state for blocks far behind the tip, if enabled.
+    contract_verification: Option<ContractVerification>,
+    /// Where spans missing at validation are recorded, if anywhere.
+    journal: Option<SharedHeimdallJournal>,
+    /// Where the last sprint-end validator mismatch is kept, if anywhere.
+    validator_mismatch: Option<SharedLastValidatorMismatch>,
+    /// Blocks held back under [`STRICT_CONSENSUS`], for the executor to fail.
+    deferred: SharedDeferredChecks,
+    /// Whether the receipts root and logs bloom are checked after execution.
+    assert_roots: bool,
+}
+
+/// Contract-state verification of blocks far behind the tip.
+#[derive(Debug)]
+struct ContractVerification {
+    source: Arc<dyn ContractValidatorSource>,
+    /// The latest milestone marks the tip.
+    milestones: Arc<MilestoneTracker>,
+    selector: VerificationSourceSelector,
 }
 
 impl<ChainSpec> BorConsensus<ChainSpec> {
@@ -62,28 +127,129 @@ impl<ChainSpec> BorConsensus<ChainSpec> {
     pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
         Self {
             chain_spec,
-            span_cache: Mutex::new(SpanCache::new(64)),
+            span_cache: Arc::new(Mutex::new(SpanCache::new(SPAN_CACHE_SIZE))),
             recents: Mutex::new(Recents::new()),
+            bad_blocks: None,
+            state_syncs: None,
+            contract_verification: None,
+            journal: None,
+            validator_mismatch: None,
+            deferred: Arc::new(DeferredChecks::new()),
+            assert_roots: false,
         }
     }
 
+    /// Record rejected blocks in `store`.
+    pub fn with_bad_block_store(mut self, store: SharedBadBlockStore) -> Self {
+        self.bad_blocks = Some(store);
+        self
+    }
+
+    /// Read the state sync events executed blocks applied from `store`, for their bad
+    /// block records.
+    pub fn with_state_sync_store(mut self, store: SharedStateSyncStore) -> Self {
+        self.state_syncs = Some(store);
+        self
+    }
+
+    /// Record spans that are not cached when a block needs them in `journal`.
+    pub fn with_heimdall_journal(mut self, journal: SharedHeimdallJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Keep the last sprint-end validator mismatch in `last`.
+    pub fn with_last_validator_mismatch(mut self, last: SharedLastValidatorMismatch) -> Self {
+        self.validator_mismatch = Some(last);
+        self
+    }
+
+    /// Check the receipts root and logs bloom of executed blocks against their header.
+    pub fn with_root_assertion(mut self) -> Self {
+        self.assert_roots = true;
+        self
+    }
+
+    /// Hold back blocks in `deferred`, shared with the executor that fails them.
+    pub fn with_deferred_checks(mut self, deferred: SharedDeferredChecks) -> Self {
+        self.deferred = deferred;
+        self
+    }
+
+    /// The blocks held back under [`STRICT_CONSENSUS`].
+    pub fn deferred_checks(&self) -> &SharedDeferredChecks {
+        &self.deferred
+    }
+
+    /// Look up spans in `cache`, shared with whatever keeps it up to date.
+    pub fn with_span_cache(mut self, cache: SharedSpanCache) -> Self {
+        self.span_cache = cache;
+        self
+    }
+
+    /// Check blocks the `selector` places far behind the tip against the validators of
+    /// `source`, with the end of the latest milestone in `milestones` as the tip.
+    ///
+    /// Blocks whose parent state `source` cannot read fall back to the span cache.
+    pub fn with_contract_state_verification(
+        mut self,
+        source: Arc<dyn ContractValidatorSource>,
+        milestones: Arc<MilestoneTracker>,
+        selector: VerificationSourceSelector,
+    ) -> Self {
+        self.contract_verification = Some(ContractVerification { source, milestones, selector });
+        self
+    }
+
+    /// The chain specification.
+    pub fn chain_spec(&self) -> &Arc<ChainSpec> {
+        &self.chain_spec
+    }
+
+    /// The span cache used for validator set lookups.
+    pub fn span_cache(&self) -> &SharedSpanCache {
+        &self.span_cache
+    }
+
     /// Insert a span into the cache. Call this to eagerly populate spans
     /// before block validation reaches them.
+    ///
+    /// A span the journal recorded as missing is resolved.
     pub fn insert_span(&self, span: Span) {
+        if let Some(journal) = &self.journal {
+            journal.resolve_span(span.id);
+        }
         self.span_cache.lock().expect("span cache lock poisoned").insert(span);
     }
 
-    /// Look up the span that covers the given block number.
-    /// Returns `None` if the span is not in the cache.
-    fn get_span_for_block(&self, block_number: u64, span_size: u64) -> Option<Span> {
-        let span_id = bor_primitives::span_id_at(block_number, span_size);
+    /// Look up the cached span whose blocks include the given block number.
+    /// Returns `None` if no such span is in the cache.
+    fn get_span_for_block(&self, block_number: u64) -> Option<Span> {
         self.span_cache
             .lock()
             .expect("span cache lock poisoned")
-            .get(span_id)
+            .span_for_block(block_number)
             .cloned()
     }
 
+    /// Signers authorized at `block_number`, from the source selected for it.
+    fn signers_for_block(&self, block_number: u64) -> Option<Vec<Address>> {
+        if let Some(contract) = &self.contract_verification {
+            let tip = contract.milestones.finalized_block();
+            if contract.selector.select(block_number, tip) == VerificationSource::ContractState {
+                match contract.source.signers(block_number) {
+                    Some(signers) => return Some(signers),
+                    None => debug!(
+                        target: "bor::consensus",
+                        block = block_number,
+                        "parent state unavailable, checking signer against span"
+                    ),
+                }
+            }
+        }
+        self.get_span_for_block(block_number).map(|span| Self::authorized_signers(&span))
+    }
+
     /// Get the list of authorized signer addresses from a span's validator set.
     fn authorized_signers(span: &Span) -> Vec<Address> {
         span.validator_set
@@ -94,10 +260,175 @@ impl<ChainSpec> BorConsensus<ChainSpec> {
     }
 }
 
+impl<ChainSpec: BorHardforks> BorConsensus<ChainSpec> {
+    /// Record a rejected block in the bad block store, if one is attached.
+    ///
+    /// `check` names the failing check. Callers that executed the block pass the IDs of the
+    /// state sync events it applied.
+    pub fn record_bad_block<H: BlockHeader>(
+        &self,
+        header: &SealedHeader<H>,
+        signer: Option<Address>,
+        check: &str,
+        error: &ConsensusError,
+        state_sync_ids: Vec<u64>,
+    ) {
+        let Some(store) = &self.bad_blocks else { return };
+        let number = header.number();
+        let snapshot = self.get_span_for_block(number).map(|span| SnapshotSummary {
+            validators: Self::authorized_signers(&span),
+            proposer: span.validator_set.proposer.as_ref().map(|v| v.signer),
+            recents: self.recents.lock().expect("recents lock poisoned").signers().collect(),
+        });
+
+        warn!(target: "bor::consensus", block = number, hash = %header.hash(), check, %error, "bad block");
+        store.write().expect("bad block store lock poisoned").put_bad_block(BadBlockRecord {
+            number,
+            hash: header.hash(),
+            parent_hash: header.parent_hash(),
+            signer,
+            check: check.to_string(),
+            error: error.to_string(),
+            snapshot,
+            span_id: Some(self.chain_spec.bor_span_id(number)),
+            state_sync_ids,
+        });
+    }
+
+    /// IDs of the state sync events executed block `header` applied, as recorded by the
+    /// executor in the state sync store.
+    fn executed_state_sync_ids<H: BlockHeader>(&self, header: &SealedHeader<H>) -> Vec<u64> {
+        let Some(store) = &self.state_syncs else { return Vec::new() };
+        let store = store.read().expect("state sync store lock poisoned");
+        let events = store.executed(header.number(), header.parent_hash());
+        events.into_iter().map(|event| event.id).collect()
+    }
+
+    /// Handle a block whose validators are unknown: record the span miss, then accept
+    /// the block unchecked, or hold it back under [`STRICT_CONSENSUS`].
+    fn unknown_validators(&self, block_number: u64, parent_hash: B256) {
+        if let Some(journal) = &self.journal {
+            let span_id = self.chain_spec.bor_span_id(block_number);
+            journal.record_span_miss(span_id, block_number);
+        }
+        if STRICT_CONSENSUS {
+            debug!(
+                target: "bor::consensus",
+                block = block_number,
+                "validators unknown, deferring block until its span is fetched"
+            );
+            self.deferred.defer(block_number, parent_hash, ValidationGap::UnknownValidators);
+            return;
+        }
+        warn!(
+            target: "bor::consensus",
+            block = block_number,
+            "validators unknown, skipping signer authorization check"
+        );
+    }
+
+    /// Check that the signer of `header` waited its turn behind the sprint proposer, and that
+    /// the header carries the difficulty of its position.
+    ///
+    /// Skipped when the span of the block is not cached; block validation then handles the
+    /// signer, see [`unknown_validators`](Self::unknown_validators).
+    fn check_succession<H: BlockHeader>(
+        &self,
+        header: &H,
+        parent_timestamp: u64,
+    ) -> Result<(), ConsensusError> {
+        let number = header.number();
+        let Some(span) = self.get_span_for_block(number) else { return Ok(()) };
+        let schedule = |e: ScheduleError| ConsensusError::Other(format!("block {number}: {e}"));
+        let Some(validator_set) =
+            sprint_validator_set(&*self.chain_spec, &span, number).map_err(schedule)?
+        else {
+            return Ok(());
+        };
+        let extra = ExtraData::parse(header.extra_data())
+            .map_err(|e| ConsensusError::Other(format!("invalid extra data: {e}")))?;
+        let signer = ecrecover_seal(&compute_seal_hash(header), &extra.seal)
+            .map_err(|e| ConsensusError::Other(format!("seal recovery failed: {e}")))?;
+        validate_succession(
+            &*self.chain_spec,
+            number,
+            header.timestamp(),
+            header.difficulty(),
+            &signer,
+            parent_timestamp,
+            &validator_set,
+        )
+        .map_err(|e| ConsensusError::Other(format!("block {number}: {e}")))?;
+        Ok(())
+    }
+
+    /// Check the validator bytes of block `block_number`, if it ends a sprint, against
+    /// the span of the block after it.
+    ///
+    /// Skipped when that span is not cached, see [`ValidationGap::SprintEndValidators`].
+    fn sprint_end_validators(
+        &self,
+        block_number: u64,
+        extra: &ExtraData,
+    ) -> Result<(), ConsensusError> {
+        let sprint_end = self
+            .chain_spec
+            .is_bor_sprint_end(block_number)
+            .map_err(|e| ConsensusError::Other(format!("block {block_number}: {e}")))?;
+        if !sprint_end {
+            return Ok(());
+        }
+        let Some(span) = self.get_span_for_block(block_number + 1) else {
+            debug!(
+                target: "bor::consensus",
+                block = block_number,
+                "next span not cached, skipping sprint-end validator check"
+            );
+            return Ok(());
+        };
+        let got = extra.validators();
+        let Some(mismatch) =
+            ValidatorMismatch::check(block_number, span.id, &span.validator_set.validators, &got)
+        else {
+            return Ok(());
+        };
+        if let Some(last) = &self.validator_mismatch {
+            last.record(mismatch.clone());
+        }
+        Err(ConsensusError::Other(mismatch.to_string()))
+    }
+}
+
+/// Check that `body` has no ommers and that its ommers hash is `ommers_hash`, the one
+/// of its header.
+///
+/// This is the rule at every height: bor rejects uncles from genesis on and checks the
+/// uncle hash of every body against its header, so no Polygon block carries ommers and
+/// all of them encode an empty list. A body without an ommers list at all counts as
+/// an empty one.
+pub fn validate_ommers<Body: BlockBody>(
+    body: &Body,
+    ommers_hash: B256,
+) -> Result<(), ConsensusError> {
+    let body_hash = body.calculate_ommers_root().unwrap_or(EMPTY_OMMER_ROOT_HASH);
+    if body_hash != ommers_hash {
+        return Err(ConsensusError::BodyOmmersHashDiff(GotExpectedBoxed::from(
+            GotExpected::new(body_hash, ommers_hash),
+        )));
+    }
+    if body_hash != EMPTY_OMMER_ROOT_HASH {
+        return Err(ConsensusError::BodyOmmersHashDiff(GotExpectedBoxed::from(
+            GotExpected::new(body_hash, EMPTY_OMMER_ROOT_HASH),
+        )));
+    }
+    Ok(())
+}
+
 impl<H, ChainSpec> HeaderValidator<H> for BorConsensus<ChainSpec>
 where
     H: BlockHeader,
-    ChainSpec: EthChainSpec<Header = H> + EthereumHardforks + Debug + Send + Sync,
+    ChainSpec:
+        EthChainSpec<Header = H> + EthereumHardforks + BorHardforks + Debug + Send + Sync,
 {
     fn validate_header(&self, header: &SealedHeader<H>) -> Result<(), ConsensusError> {
         let header = header.header();
@@ -120,13 +451,21 @@ where
             });
         }
 
-        // Bor: extra data must be at least vanity (32) + seal (65) = 97 bytes
-        if header.extra_data().len() < 97 {
+        if header.gas_limit() > MAX_GAS_LIMIT {
+            return Err(ConsensusError::HeaderGasLimitExceedsMax { gas_limit: header.gas_limit() });
+        }
+
+        // Bor: extra data must hold at least the vanity and the seal
+        if header.extra_data().len() < EXTRADATA_VANITY_LEN + EXTRADATA_SEAL_LEN {
             return Err(ConsensusError::ExtraDataExceedsMax {
                 len: header.extra_data().len(),
             });
         }
 
+        // Bor: base fee from Jaipur
+        let jaipur = self.chain_spec.is_jaipur_active_at_block(header.number());
+        validate_jaipur_header(jaipur, header.base_fee_per_gas())?;
+
         // No withdrawals root on Bor
         if header.withdrawals_root().is_some() {
             return Err(ConsensusError::WithdrawalsRootUnexpected);
@@ -181,6 +520,12 @@ where
             });
         }
 
+        // Gas limit moves by less than 1/1024 of the parent's per block
+        validate_gas_limit(parent.gas_limit(), header.gas_limit())?;
+
+        // Bor: backups wait their turn and carry the difficulty of their position
+        self.check_succession(header.header(), parent.timestamp())?;
+
         Ok(())
     }
 }
@@ -188,7 +533,8 @@ where
 impl<B, ChainSpec> Consensus<B> for BorConsensus<ChainSpec>
 where
     B: Block,
-    ChainSpec: EthChainSpec<Header = B::Header> + EthereumHardforks + Debug + Send + Sync,
+    ChainSpec:
+        EthChainSpec<Header = B::Header> + EthereumHardforks + BorHardforks + Debug + Send + Sync,
 {
     fn validate_body_against_header(
         &self,
@@ -196,20 +542,26 @@ where
         header: &SealedHeader<B::Header>,
     ) -> Result<(), ConsensusError> {
         // Bor: no ommers allowed
-        if body.ommers().is_some_and(|o| !o.is_empty()) {
-            return Err(ConsensusError::BodyOmmersHashDiff(
-                GotExpectedBoxed::from(GotExpected::new(
-                    alloy_primitives::B256::ZERO,
-                    EMPTY_OMMER_ROOT_HASH,
-                )),
-            ));
-        }
+        validate_ommers(body, header.ommers_hash())?;
 
         // Bor: no withdrawals
         if body.withdrawals().is_some() {
             return Err(ConsensusError::WithdrawalsRootUnexpected);
         }
 
+        // Bor: Napoli adopted Cancun without blob transactions
+        if body.transactions().iter().any(|tx| tx.is_eip4844()) {
+            return Err(ConsensusError::Other("blob transactions are not supported on Bor".into()));
+        }
+
+        // Bor: from Jaipur, every transaction's fee cap covers the base fee
+        validate_jaipur_transactions(
+            header.number(),
+            self.chain_spec.is_jaipur_active_at_block(header.number()),
+            header.base_fee_per_gas(),
+            body.transactions(),
+        )?;
+
         // Validate transaction root
         let tx_root =
             reth_primitives_traits::proofs::calculate_transaction_root(body.transactions());
@@ -225,14 +577,7 @@ where
 
     fn validate_block_pre_execution(&self, block: &SealedBlock<B>) -> Result<(), ConsensusError> {
         // Ommers must be empty
-        if block.body().ommers().is_some_and(|o| !o.is_empty()) {
-            return Err(ConsensusError::BodyOmmersHashDiff(
-                GotExpectedBoxed::from(GotExpected::new(
-                    alloy_primitives::B256::ZERO,
-                    EMPTY_OMMER_ROOT_HASH,
-                )),
-            ));
-        }
+        validate_ommers(block.body(), block.header().ommers_hash())?;
 
         // No withdrawals
         if block.body().withdrawals().is_some() {
@@ -242,9 +587,14 @@ where
         let header = block.header();
         let block_number = header.number();
 
+        let reject = |signer: Option<Address>, check: &str, error: ConsensusError| {
+            self.record_bad_block(block.sealed_header(), signer, check, &error, Vec::new());
+            error
+        };
+
         // Parse extra data to get seal
         let extra = ExtraData::parse(header.extra_data()).map_err(|e| {
-            ConsensusError::Other(format!("invalid extra data: {e}").into())
+            reject(None, "extra_data", ConsensusError::Other(format!("invalid extra data: {e}").into()))
         })?;
 
         // Compute seal hash (header RLP with seal stripped from extra data)
@@ -252,71 +602,106 @@ where
 
         // Recover signer from seal
         let signer = ecrecover_seal(&seal_hash, &extra.seal).map_err(|e| {
-            ConsensusError::Other(format!("seal recovery failed: {e}").into())
+            reject(None, "seal", ConsensusError::Other(format!("seal recovery failed: {e}").into()))
         })?;
 
         debug!(target: "bor::consensus", block = block_number, ?signer, "recovered block signer");
 
-        // Look up the validator set from the span cache.
-        // Use the chain-appropriate span size. For now, use a heuristic:
-        // if all Bor forks are at block 0 (Amoy), Rio is active from genesis → span_size = 1600.
-        // Otherwise determine from chain spec.
-        // TODO: Get span_size from chain spec properly based on block number.
-        let span_size = 6400u64; // Default pre-Rio span size
-        if let Some(span) = self.get_span_for_block(block_number, span_size) {
-            let signers = Self::authorized_signers(&span);
+        // Look up the validator set from the span cache or contract state.
+        if let Some(signers) = self.signers_for_block(block_number) {
 
             // Verify signer is authorized
             if !signers.contains(&signer) {
-                return Err(ConsensusError::Other(
-                    format!("unauthorized signer {signer} at block {block_number}").into(),
+                return Err(reject(
+                    Some(signer),
+                    "unauthorized_signer",
+                    ConsensusError::Other(
+                        format!("unauthorized signer {signer} at block {block_number}").into(),
+                    ),
                 ));
             }
 
             // Anti-double-sign check
             let recents = self.recents.lock().expect("recents lock poisoned");
-            if recents.is_recently_signed(&signer, block_number, signers.len()) {
-                return Err(ConsensusError::Other(
-                    format!("signer {signer} signed too recently at block {block_number}").into(),
+            let recently_signed = recents.is_recently_signed(&signer, block_number, signers.len());
+            drop(recents);
+            if recently_signed {
+                return Err(reject(
+                    Some(signer),
+                    "recently_signed",
+                    ConsensusError::Other(
+                        format!("signer {signer} signed too recently at block {block_number}")
+                            .into(),
+                    ),
                 ));
             }
-            drop(recents);
 
             // Record this signer in recents
             let mut recents = self.recents.lock().expect("recents lock poisoned");
             recents.add_signer(block_number, signer);
             recents.prune(block_number, signers.len());
         } else {
-            warn!(
-                target: "bor::consensus",
-                block = block_number,
-                "span not cached, skipping signer authorization check"
-            );
+            self.unknown_validators(block_number, header.parent_hash());
         }
 
+        // Sprint-end validator bytes must list the next span's validators
+        self.sprint_end_validators(block_number, &extra).map_err(|error| {
+            reject(Some(signer), ValidationGap::SprintEndValidators.name(), error)
+        })?;
+
         Ok(())
     }
 }
 
 impl<ChainSpec, N> FullConsensus<N> for BorConsensus<ChainSpec>
 where
-    ChainSpec: Send + Sync + EthChainSpec<Header = N::BlockHeader> + EthereumHardforks + Debug,
+    ChainSpec: Send
+        + Sync
+        + EthChainSpec<Header = N::BlockHeader>
+        + EthereumHardforks
+        + BorHardforks
+        + Debug,
     N: NodePrimitives,
 {
     fn validate_block_post_execution(
         &self,
         block: &RecoveredBlock<N::Block>,
         result: &BlockExecutionResult<N::Receipt>,
-        _receipt_root_bloom: Option<ReceiptRootBloom>,
+        receipt_root_bloom: Option<ReceiptRootBloom>,
     ) -> Result<(), ConsensusError> {
         // Validate gas used matches
         let header_gas = block.header().gas_used();
         let exec_gas = result.gas_used;
         if header_gas != exec_gas {
-            return Err(ConsensusError::BlockGasUsed {
+            let error = ConsensusError::BlockGasUsed {
                 gas: GotExpected::new(exec_gas, header_gas),
                 gas_spent_by_tx: Vec::new(),
+            };
+            let state_sync_ids = self.executed_state_sync_ids(block.sealed_header());
+            self.record_bad_block(block.sealed_header(), None, "gas_used", &error, state_sync_ids);
+            return Err(error);
+        }
+
+        if self.assert_roots {
+            let (receipts_root, logs_bloom) = receipt_root_bloom.unwrap_or_else(|| {
+                let receipts: Vec<_> =
+                    result.receipts.iter().map(TxReceipt::with_bloom_ref).collect();
+                let bloom = receipts.iter().fold(Bloom::ZERO, |bloom, r| bloom | r.bloom_ref());
+                (calculate_receipt_root(&receipts), bloom)
             });
+            let header = block.header();
+            let (check, error) = if receipts_root != header.receipts_root() {
+                let diff = GotExpected::new(receipts_root, header.receipts_root());
+                ("receipts_root", ConsensusError::BodyReceiptRootDiff(diff.into()))
+            } else if logs_bloom != header.logs_bloom() {
+                let diff = GotExpected::new(logs_bloom, header.logs_bloom());
+                ("logs_bloom", ConsensusError::BodyBloomLogDiff(diff.into()))
+            } else {
+                return Ok(());
+            };
+            let state_sync_ids = self.executed_state_sync_ids(block.sealed_header());
+            self.record_bad_block(block.sealed_header(), None, check, &error, state_sync_ids);
+            return Err(error);
         }
 
         Ok(())
@@ -326,8 +711,9 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy_consensus::Header;
-    use alloy_primitives::{B256, B64};
+    use alloy_consensus::{Header, TxEnvelope};
+    use alloy_primitives::{hex, B64};
+    use alloy_rlp::Decodable;
     use reth_chainspec::ChainSpec;
 
     fn bor_consensus() -> BorConsensus<ChainSpec> {
@@ -367,6 +753,60 @@ mod tests {
         assert!(consensus.validate_header(&sealed).is_ok());
     }
 
+    #[test]
+    fn test_header_against_parent_checks_succession() {
+        use crate::test_utils::{seal, test_signer, test_span};
+
+        let consensus = bor_consensus();
+        let signers: Vec<_> = (0..3).map(test_signer).collect();
+        let addresses: Vec<Address> = signers.iter().map(|(_, address)| *address).collect();
+        let mut span = test_span(1, 256, &addresses);
+        span.validator_set.proposer = span.validator_set.validators.first().cloned();
+        consensus.insert_span(span);
+
+        let parent = Header {
+            number: 256,
+            timestamp: 1_000,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        let parent = SealedHeader::seal_slow(parent);
+        // The first backup waits a period and a backup multiplier, and carries difficulty 2.
+        let block = |timestamp, difficulty: u64| {
+            let mut header = Header {
+                number: 257,
+                parent_hash: parent.hash(),
+                timestamp,
+                difficulty: alloy_primitives::U256::from(difficulty),
+                gas_limit: 30_000_000,
+                extra_data: alloy_primitives::Bytes::from(vec![0u8; 97]),
+                ..Default::default()
+            };
+            seal(&mut header, &signers[1].0);
+            SealedHeader::seal_slow(header)
+        };
+        assert!(consensus.validate_header_against_parent(&block(1_004, 2), &parent).is_ok());
+        let too_soon = consensus.validate_header_against_parent(&block(1_002, 2), &parent);
+        assert!(too_soon.unwrap_err().to_string().contains("too soon"));
+        let in_turn = consensus.validate_header_against_parent(&block(1_004, 3), &parent);
+        assert!(in_turn.unwrap_err().to_string().contains("wrong difficulty"));
+    }
+
+    #[test]
+    fn test_bor_consensus_rejects_gas_limit_above_max() {
+        let consensus = bor_consensus();
+        let header = Header {
+            nonce: B64::ZERO,
+            ommers_hash: EMPTY_OMMER_ROOT_HASH,
+            extra_data: alloy_primitives::Bytes::from(vec![0u8; 97]),
+            gas_limit: MAX_GAS_LIMIT + 1,
+            ..Default::default()
+        };
+        let sealed = SealedHeader::seal_slow(header);
+        let err = consensus.validate_header(&sealed).unwrap_err();
+        assert!(matches!(err, ConsensusError::HeaderGasLimitExceedsMax { .. }));
+    }
+
     #[test]
     fn test_bor_consensus_rejects_withdrawals_root() {
         let consensus = bor_consensus();
@@ -382,4 +822,274 @@ mod tests {
         let err = consensus.validate_header(&sealed).unwrap_err();
         assert!(matches!(err, ConsensusError::WithdrawalsRootUnexpected));
     }
+
+    #[test]
+    fn test_bor_consensus_requires_base_fee_from_jaipur() {
+        let consensus = bor_consensus();
+        let jaipur = consensus.chain_spec.is_jaipur_active_at_block(73_100);
+        assert!(jaipur && !consensus.chain_spec.is_jaipur_active_at_block(73_099));
+        let header = Header {
+            number: 73_100,
+            nonce: B64::ZERO,
+            ommers_hash: EMPTY_OMMER_ROOT_HASH,
+            extra_data: alloy_primitives::Bytes::from(vec![0u8; 97]),
+            gas_limit: 30_000_000,
+            ..Default::default()
+        };
+        let sealed = SealedHeader::seal_slow(header.clone());
+        let err = consensus.validate_header(&sealed).unwrap_err();
+        assert!(matches!(err, ConsensusError::BaseFeeMissing));
+
+        let sealed = SealedHeader::seal_slow(Header { base_fee_per_gas: Some(7), ..header });
+        assert!(consensus.validate_header(&sealed).is_ok());
+    }
+
+    #[test]
+    fn test_empty_ommers_list_is_accepted() {
+        // Body of an early mainnet block without transactions: `[[], []]`.
+        let body =
+            alloy_consensus::BlockBody::<TxEnvelope>::decode(&mut &hex!("c2c0c0")[..]).unwrap();
+        assert_eq!(body.ommers, Vec::new());
+        assert!(validate_ommers(&body, EMPTY_OMMER_ROOT_HASH).is_ok());
+        assert!(matches!(
+            validate_ommers(&body, B256::ZERO),
+            Err(ConsensusError::BodyOmmersHashDiff(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_ommer_root_is_hash_of_empty_list() {
+        // The hash of the RLP empty list `0xc0`, not of empty input.
+        let empty_list = alloy_primitives::keccak256(alloy_rlp::EMPTY_LIST_CODE.to_be_bytes());
+        assert_eq!(EMPTY_OMMER_ROOT_HASH, empty_list);
+        assert_ne!(EMPTY_OMMER_ROOT_HASH, alloy_primitives::keccak256([]));
+        let body = alloy_consensus::BlockBody::<TxEnvelope>::default();
+        assert!(validate_ommers(&body, empty_list).is_ok());
+        let err = validate_ommers(&body, alloy_primitives::keccak256([])).unwrap_err();
+        assert!(matches!(err, ConsensusError::BodyOmmersHashDiff(_)));
+    }
+
+    #[test]
+    fn test_ommers_are_rejected_at_any_height() {
+        let body = alloy_consensus::BlockBody::<TxEnvelope> {
+            transactions: Vec::new(),
+            ommers: vec![Header { number: 1, ..Default::default() }],
+            withdrawals: None,
+        };
+        let ommers_hash = body.calculate_ommers_root().unwrap();
+        for header_hash in [ommers_hash, EMPTY_OMMER_ROOT_HASH] {
+            let err = validate_ommers(&body, header_hash).unwrap_err();
+            assert!(matches!(err, ConsensusError::BodyOmmersHashDiff(_)));
+        }
+    }
+
+    #[derive(Debug)]
+    struct FixedValidators(Option<Vec<Address>>);
+
+    impl ContractValidatorSource for FixedValidators {
+        fn signers(&self, _number: u64) -> Option<Vec<Address>> {
+            self.0.clone()
+        }
+    }
+
+    fn milestones_up_to(end_block: u64) -> Arc<MilestoneTracker> {
+        let milestones = Arc::new(MilestoneTracker::new());
+        milestones.update(heimdall_client::Milestone {
+            milestone_id: "1".into(),
+            start_block: end_block - 10,
+            end_block,
+            hash: B256::ZERO,
+            proposer: Address::ZERO,
+        });
+        milestones
+    }
+
+    #[test]
+    fn test_far_behind_blocks_use_contract_state() {
+        let signer = Address::with_last_byte(1);
+        let consensus = bor_consensus().with_contract_state_verification(
+            Arc::new(FixedValidators(Some(vec![signer]))),
+            milestones_up_to(100_000),
+            VerificationSourceSelector { contract_state_distance: 1_000 },
+        );
+        assert_eq!(consensus.signers_for_block(50_000), Some(vec![signer]));
+        // Near the tip the span cache is used, and it holds no span here.
+        assert_eq!(consensus.signers_for_block(99_500), None);
+    }
+
+    #[test]
+    fn test_unknown_validators_deferred_when_strict() {
+        let consensus = bor_consensus();
+        assert_eq!(consensus.signers_for_block(64), None);
+        consensus.unknown_validators(64, B256::ZERO);
+        let deferred = consensus.deferred_checks().take(64, B256::ZERO);
+        assert_eq!(deferred.is_some(), STRICT_CONSENSUS);
+    }
+
+    #[test]
+    fn test_unavailable_contract_state_falls_back_to_span() {
+        let consensus = bor_consensus().with_contract_state_verification(
+            Arc::new(FixedValidators(None)),
+            milestones_up_to(100_000),
+            VerificationSourceSelector { contract_state_distance: 1_000 },
+        );
+        let validator = bor_primitives::Validator {
+            id: 1,
+            address: Address::with_last_byte(2),
+            voting_power: 100,
+            signer: Address::with_last_byte(2),
+            proposer_priority: 0,
+        };
+        let id = bor_primitives::span_id_for_block(50_000);
+        consensus.insert_span(Span {
+            id,
+            start_block: bor_primitives::span_start_block(id),
+            end_block: bor_primitives::span_start_block(id + 1) - 1,
+            validator_set: bor_primitives::ValidatorSet {
+                validators: vec![validator.clone()],
+                proposer: Some(validator.clone()),
+            },
+            selected_producers: vec![validator],
+            bor_chain_id: "137".to_string(),
+        });
+        assert_eq!(consensus.signers_for_block(50_000), Some(vec![Address::with_last_byte(2)]));
+    }
+
+    #[test]
+    fn test_sprint_end_validator_mismatch_is_kept() {
+        use crate::validator_mismatch::LastValidatorMismatch;
+        use crate::ExtraDataBuilder;
+
+        let last = Arc::new(LastValidatorMismatch::new());
+        let consensus = bor_consensus().with_last_validator_mismatch(last.clone());
+        let validator = |byte: u8| bor_primitives::Validator {
+            id: byte.into(),
+            address: Address::repeat_byte(byte),
+            voting_power: 100,
+            signer: Address::repeat_byte(byte),
+            proposer_priority: 0,
+        };
+        let validators = vec![validator(0xaa), validator(0xbb)];
+        consensus.insert_span(Span {
+            id: 1,
+            start_block: 256,
+            end_block: 6655,
+            validator_set: bor_primitives::ValidatorSet {
+                validators: validators.clone(),
+                proposer: Some(validators[0].clone()),
+            },
+            selected_producers: validators.clone(),
+            bor_chain_id: "80002".to_string(),
+        });
+        let extra = |validators: &[bor_primitives::Validator]| {
+            let bytes = ExtraDataBuilder::new(6463, 64).with_validators(validators).build();
+            ExtraData::parse(&bytes.unwrap()).unwrap()
+        };
+
+        // 6463 ends a 64-block sprint; 6462 does not and carries no validators.
+        consensus.sprint_end_validators(6463, &extra(&validators)).unwrap();
+        consensus.sprint_end_validators(6462, &extra(&[])).unwrap();
+        assert_eq!(last.get(), None);
+
+        let err = consensus.sprint_end_validators(6463, &extra(&validators[..1])).unwrap_err();
+        assert!(err.to_string().contains("(power 100)"), "{err}");
+        let mismatch = last.get().unwrap();
+        assert_eq!(mismatch.block, 6463);
+        assert_eq!(mismatch.missing_power, 100);
+        assert_eq!(mismatch.missing[0].address, Address::repeat_byte(0xbb));
+    }
+
+    #[test]
+    fn test_record_bad_block_with_span_context() {
+        use bor_primitives::{Validator, ValidatorSet};
+        use bor_storage::{BadBlockStore, InMemoryBadBlockStore};
+        use std::sync::RwLock;
+
+        let store = Arc::new(RwLock::new(InMemoryBadBlockStore::default()));
+        let consensus = bor_consensus().with_bad_block_store(store.clone());
+        let validator = Validator {
+            id: 1,
+            address: Address::new([0xaa; 20]),
+            voting_power: 100,
+            signer: Address::new([0xaa; 20]),
+            proposer_priority: 0,
+        };
+        consensus.insert_span(Span {
+            id: 1,
+            start_block: 6400,
+            end_block: 12799,
+            validator_set: ValidatorSet {
+                validators: vec![validator.clone()],
+                proposer: Some(validator.clone()),
+            },
+            selected_producers: vec![validator],
+            bor_chain_id: "80002".to_string(),
+        });
+
+        let header = SealedHeader::seal_slow(Header {
+            number: 6500,
+            parent_hash: B256::with_last_byte(1),
+            ..Default::default()
+        });
+        let error = ConsensusError::Other("unauthorized signer".into());
+        consensus.record_bad_block(&header, Some(Address::new([0xbb; 20])), "unauthorized_signer", &error, vec![3]);
+
+        let records = store.read().unwrap().bad_blocks();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.hash, header.hash());
+        assert_eq!(record.check, "unauthorized_signer");
+        assert_eq!(record.span_id, Some(1));
+        assert_eq!(record.state_sync_ids, vec![3]);
+        assert_eq!(record.snapshot.as_ref().unwrap().validators, vec![Address::new([0xaa; 20])]);
+    }
+
+    #[test]
+    fn test_post_execution_bad_block_records_state_syncs() {
+        use bor_storage::{
+            BadBlockStore, CommittedStateSync, InMemoryBadBlockStore, InMemoryStateSyncStore,
+            StateSyncStore,
+        };
+        use reth_ethereum_primitives::{Block, EthPrimitives, Receipt};
+        use std::sync::RwLock;
+
+        let bad_blocks = Arc::new(RwLock::new(InMemoryBadBlockStore::default()));
+        let state_syncs = Arc::new(RwLock::new(InMemoryStateSyncStore::default()));
+        let consensus = bor_consensus()
+            .with_bad_block_store(bad_blocks.clone())
+            .with_state_sync_store(state_syncs.clone());
+        let header = Header {
+            number: 16,
+            parent_hash: B256::with_last_byte(15),
+            gas_used: 21_000,
+            ..Default::default()
+        };
+        let event = |id| CommittedStateSync { id, contract: None, data: Default::default() };
+        let events = vec![event(4), event(5)];
+        state_syncs.write().unwrap().record_executed(16, header.parent_hash, events);
+
+        let block =
+            RecoveredBlock::new_unhashed(Block { header, body: Default::default() }, Vec::new());
+        let result = BlockExecutionResult::<Receipt> {
+            receipts: Vec::new(),
+            requests: Default::default(),
+            gas_used: 0,
+            blob_gas_used: 0,
+        };
+        let err = FullConsensus::<EthPrimitives>::validate_block_post_execution(
+            &consensus, &block, &result, None,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ConsensusError::BlockGasUsed { .. }), "{err}");
+        let records = bad_blocks.read().unwrap().bad_blocks();
+        assert_eq!(records[0].check, "gas_used");
+        assert_eq!(records[0].state_sync_ids, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_record_bad_block_without_store_is_noop() {
+        let consensus = bor_consensus();
+        let header = SealedHeader::seal_slow(Header::default());
+        consensus.record_bad_block(&header, None, "seal", &ConsensusError::Other("x".into()), Vec::new());
+    }
 }
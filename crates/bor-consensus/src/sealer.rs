@@ -0,0 +1,176 @@
+//! Sealing the blocks this node produces.
+//!
+//! The payload builder leaves the last 65 bytes of a block's extra data zeroed.
+//! [`BlockSealer`] holds the validator key and writes its signature over the seal
+//! hash there, the signature [`ecrecover_seal`](crate::ecrecover_seal) recovers the
+//! signer from. With a [`DoubleSignGuard`](crate::DoubleSignGuard) attached, a block is
+//! only signed once the guard has recorded it.
+
+use crate::double_sign::{DoubleSignError, SharedDoubleSignGuard};
+use crate::seal::compute_seal_hash;
+use alloy_consensus::Header;
+use alloy_primitives::{Address, Bytes, B256};
+use bor_chainspec::constants::EXTRADATA_SEAL_LEN;
+use k256::ecdsa::SigningKey;
+use std::path::Path;
+
+/// Reasons a block cannot be sealed.
+#[derive(Debug, thiserror::Error)]
+pub enum SealerError {
+    /// The key file could not be read.
+    #[error("failed to read key file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The key file does not hold a valid secp256k1 secret key.
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+    /// The extra data has no room for a seal.
+    #[error("extra data of block {number} is {len} bytes, too short for a seal")]
+    NoSeal {
+        /// Number of the block.
+        number: u64,
+        /// Length of its extra data.
+        len: usize,
+    },
+    /// The block competes with one already signed.
+    #[error(transparent)]
+    DoubleSign(#[from] DoubleSignError),
+    /// Signing failed.
+    #[error("failed to sign block {number}: {reason}")]
+    Sign {
+        /// Number of the block.
+        number: u64,
+        /// Why signing failed.
+        reason: String,
+    },
+}
+
+/// Signs the blocks this node produces with the validator key.
+pub struct BlockSealer {
+    key: SigningKey,
+    address: Address,
+    /// Record of the blocks signed, if any.
+    double_sign: Option<SharedDoubleSignGuard>,
+}
+
+impl std::fmt::Debug for BlockSealer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockSealer").field("address", &self.address).finish_non_exhaustive()
+    }
+}
+
+impl BlockSealer {
+    /// Seal with `key`.
+    pub fn new(key: SigningKey) -> Self {
+        let address = Address::from_raw_public_key(
+            &key.verifying_key().to_encoded_point(false).as_bytes()[1..],
+        );
+        Self { key, address, double_sign: None }
+    }
+
+    /// Seal with the hex-encoded secret key in the file at `path`.
+    pub fn from_file(path: &Path) -> Result<Self, SealerError> {
+        let secret: B256 = std::fs::read_to_string(path)?
+            .trim()
+            .parse()
+            .map_err(|err| SealerError::InvalidKey(format!("{err}")))?;
+        let key = SigningKey::from_bytes((&secret.0).into())
+            .map_err(|err| SealerError::InvalidKey(err.to_string()))?;
+        Ok(Self::new(key))
+    }
+
+    /// Refuse to sign a block competing with one `guard` recorded, and record every
+    /// block signed in it.
+    pub fn with_double_sign_guard(mut self, guard: SharedDoubleSignGuard) -> Self {
+        self.double_sign = Some(guard);
+        self
+    }
+
+    /// The address blocks are sealed for.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Write the seal of the key over `header` into its extra data.
+    pub fn seal(&self, header: &mut Header) -> Result<(), SealerError> {
+        let number = header.number;
+        let len = header.extra_data.len();
+        if len < EXTRADATA_SEAL_LEN {
+            return Err(SealerError::NoSeal { number, len });
+        }
+        let seal_hash = compute_seal_hash(header);
+        if let Some(guard) = &self.double_sign {
+            guard.authorize(self.address, number, seal_hash)?;
+        }
+        let (signature, recid) = self
+            .key
+            .sign_prehash_recoverable(seal_hash.as_ref())
+            .map_err(|err| SealerError::Sign { number, reason: err.to_string() })?;
+        let mut extra_data = header.extra_data.to_vec();
+        let seal_start = len - EXTRADATA_SEAL_LEN;
+        extra_data[seal_start..seal_start + 64].copy_from_slice(&signature.to_bytes());
+        extra_data[seal_start + 64] = recid.to_byte();
+        header.extra_data = Bytes::from(extra_data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::double_sign::DoubleSignGuard;
+    use crate::seal::ecrecover_seal;
+    use crate::test_utils::test_signer;
+    use bor_primitives::ExtraDataBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sealed_header_recovers_to_the_key() {
+        let (key, address) = test_signer(0);
+        let sealer = BlockSealer::new(key);
+        assert_eq!(sealer.address(), address);
+
+        let mut header = Header {
+            number: 7,
+            extra_data: ExtraDataBuilder::new(7, 16).build().unwrap(),
+            ..Default::default()
+        };
+        sealer.seal(&mut header).unwrap();
+        let signature = &header.extra_data[header.extra_data.len() - EXTRADATA_SEAL_LEN..];
+        assert_eq!(ecrecover_seal(&compute_seal_hash(&header), signature).unwrap(), address);
+
+        let mut short = Header { number: 8, ..Default::default() };
+        assert!(matches!(sealer.seal(&mut short), Err(SealerError::NoSeal { number: 8, len: 0 })));
+    }
+
+    #[test]
+    fn test_guard_refuses_a_second_block_at_a_height() {
+        let sealer = BlockSealer::new(test_signer(0).0)
+            .with_double_sign_guard(Arc::new(DoubleSignGuard::new()));
+        let header = |timestamp| Header {
+            number: 7,
+            timestamp,
+            extra_data: ExtraDataBuilder::new(7, 16).build().unwrap(),
+            ..Default::default()
+        };
+        sealer.seal(&mut header(1)).unwrap();
+        // Sealing the same block again is harmless.
+        sealer.seal(&mut header(1)).unwrap();
+        assert!(matches!(
+            sealer.seal(&mut header(2)),
+            Err(SealerError::DoubleSign(DoubleSignError::DoubleSign { number: 7, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_key_file() {
+        let path = std::env::temp_dir().join(format!("bor-sealer-key-{}", std::process::id()));
+        let (key, address) = test_signer(1);
+        std::fs::write(&path, format!("0x{}\n", alloy_primitives::hex::encode(key.to_bytes())))
+            .unwrap();
+        assert_eq!(BlockSealer::from_file(&path).unwrap().address(), address);
+
+        std::fs::write(&path, "not a key").unwrap();
+        assert!(matches!(BlockSealer::from_file(&path), Err(SealerError::InvalidKey(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
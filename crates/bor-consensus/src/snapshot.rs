@@ -1,6 +1,8 @@
 //! Bor consensus snapshot: tracks validator set and recent signers at a block.
 
-use alloy_primitives::{Address, B256};
+use crate::succession;
+use crate::validation::ValidationError;
+use alloy_primitives::{Address, B256, U256};
 use bor_primitives::ValidatorSet;
 use std::collections::BTreeMap;
 
@@ -50,6 +52,16 @@ impl BorSnapshot {
             .any(|v| &v.signer == addr)
     }
 
+    /// Succession number of `signer` behind the current proposer (0 = proposer).
+    pub fn succession(&self, signer: &Address) -> Result<usize, ValidationError> {
+        succession::succession_number(&self.validator_set, signer)
+    }
+
+    /// Difficulty a block sealed by `signer` must carry at this snapshot.
+    pub fn difficulty(&self, signer: &Address) -> Result<U256, ValidationError> {
+        succession::succession_difficulty(&self.validator_set, signer)
+    }
+
     /// Encode snapshot to JSON bytes for storage.
     pub fn encode(&self) -> Vec<u8> {
         serde_json::to_vec(self).expect("snapshot serialization should not fail")
@@ -118,6 +130,20 @@ mod tests {
         assert!(!snap.is_authorized(&invalid));
     }
 
+    #[test]
+    fn test_snapshot_succession_and_difficulty() {
+        let mut vs = test_validator_set();
+        vs.proposer = Some(vs.validators[1].clone());
+        let snap = BorSnapshot::new(100, B256::ZERO, vs);
+
+        let v1 = address!("0000000000000000000000000000000000000001");
+        let v2 = address!("0000000000000000000000000000000000000002");
+        assert_eq!(snap.succession(&v2).unwrap(), 0);
+        assert_eq!(snap.succession(&v1).unwrap(), 2);
+        assert_eq!(snap.difficulty(&v2).unwrap(), U256::from(3));
+        assert_eq!(snap.difficulty(&v1).unwrap(), U256::from(1));
+    }
+
     #[test]
     fn test_snapshot_encode_decode_roundtrip() {
         let vs = test_validator_set();
@@ -0,0 +1,239 @@
+//! Copy-on-write snapshots for runs of headers.
+//!
+//! A header changes little of the snapshot of its parent: it adds its signer to the
+//! recents and drops those that left the signing window, and only at a sprint end
+//! does the validator set, with its proposer priorities, change. Yet
+//! [`BorSnapshot::apply`] rebuilds the map of recents on every block, and keeping the
+//! snapshot of each header of a batch clones the validator set as many times.
+//!
+//! A [`SnapshotView`] shares its validator set with the view it was derived from
+//! until a [`SnapshotDelta`] replaces it, and keeps the recents in a ring buffer no
+//! longer than the window, so deriving the view of a child header is a reference
+//! count increment and a copy of at most `validators / 2 + 2` entries.
+
+use crate::snapshot::BorSnapshot;
+use alloy_primitives::{Address, B256};
+use bor_primitives::ValidatorSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// What a header changes in the snapshot of its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDelta {
+    /// Number of the header.
+    pub number: u64,
+    /// Hash of the header.
+    pub hash: B256,
+    /// Signer recovered from its seal.
+    pub signer: Address,
+    /// Validator set taking over after the header, if it ends a sprint.
+    pub validator_set: Option<ValidatorSet>,
+}
+
+impl SnapshotDelta {
+    /// The delta of header `number` with `hash`, sealed by `signer`.
+    pub const fn new(number: u64, hash: B256, signer: Address) -> Self {
+        Self { number, hash, signer, validator_set: None }
+    }
+
+    /// Replace the validator set after the header with `validator_set`.
+    pub fn with_validator_set(mut self, validator_set: ValidatorSet) -> Self {
+        self.validator_set = Some(validator_set);
+        self
+    }
+}
+
+/// A snapshot whose validator set is shared with the views derived from it.
+///
+/// Converts from and to [`BorSnapshot`]; applying the same headers to either gives
+/// the same snapshot.
+#[derive(Debug, Clone)]
+pub struct SnapshotView {
+    number: u64,
+    hash: B256,
+    validator_set: Arc<ValidatorSet>,
+    /// `(block, signer)`, in block order.
+    recents: VecDeque<(u64, Address)>,
+}
+
+impl SnapshotView {
+    /// Block the view is at.
+    pub const fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Hash of that block.
+    pub const fn hash(&self) -> B256 {
+        self.hash
+    }
+
+    /// The current validator set.
+    pub fn validator_set(&self) -> &ValidatorSet {
+        &self.validator_set
+    }
+
+    /// Recent `(block, signer)` pairs, in block order.
+    pub fn recents(&self) -> impl Iterator<Item = (u64, Address)> + '_ {
+        self.recents.iter().copied()
+    }
+
+    /// Whether `other` uses the same validator set without having copied it.
+    pub fn shares_validator_set(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.validator_set, &other.validator_set)
+    }
+
+    /// Check if an address is an authorized validator/signer.
+    pub fn is_authorized(&self, addr: &Address) -> bool {
+        self.validator_set.validators.iter().any(|v| &v.signer == addr)
+    }
+
+    /// Apply a header to the view, like [`BorSnapshot::apply`] followed by setting
+    /// the hash and, at a sprint end, the validator set.
+    pub fn apply(&mut self, delta: SnapshotDelta) {
+        let SnapshotDelta { number, hash, signer, validator_set } = delta;
+        self.number = number;
+        self.hash = hash;
+        match self.recents.back() {
+            Some(&(last, _)) if last >= number => {
+                let at = self.recents.partition_point(|(block, _)| *block < number);
+                match self.recents.get_mut(at) {
+                    Some(entry) if entry.0 == number => entry.1 = signer,
+                    _ => self.recents.insert(at, (number, signer)),
+                }
+            }
+            _ => self.recents.push_back((number, signer)),
+        }
+
+        let validator_count = self.validator_set.validators.len();
+        if validator_count > 0 {
+            let window = (validator_count / 2 + 1) as u64;
+            let cutoff = number.saturating_sub(window);
+            while self.recents.front().is_some_and(|(block, _)| *block < cutoff) {
+                self.recents.pop_front();
+            }
+        }
+
+        if let Some(validator_set) = validator_set {
+            self.replace_validator_set(validator_set);
+        }
+    }
+
+    /// Replace the validator set, leaving the views sharing the current one as they are.
+    pub fn replace_validator_set(&mut self, validator_set: ValidatorSet) {
+        self.validator_set = Arc::new(validator_set);
+    }
+
+    /// The view of the header `delta` describes, leaving this one as it is.
+    pub fn child(&self, delta: SnapshotDelta) -> Self {
+        let mut child = self.clone();
+        child.apply(delta);
+        child
+    }
+
+    /// The snapshot the view stands for.
+    pub fn to_snapshot(&self) -> BorSnapshot {
+        BorSnapshot {
+            number: self.number,
+            hash: self.hash,
+            validator_set: ValidatorSet::clone(&self.validator_set),
+            recents: self.recents.iter().copied().collect(),
+        }
+    }
+
+    /// The snapshot the view stands for, copying the validator set only if another
+    /// view still shares it.
+    pub fn into_snapshot(self) -> BorSnapshot {
+        BorSnapshot {
+            number: self.number,
+            hash: self.hash,
+            validator_set: Arc::unwrap_or_clone(self.validator_set),
+            recents: self.recents.into_iter().collect(),
+        }
+    }
+}
+
+impl From<BorSnapshot> for SnapshotView {
+    fn from(snapshot: BorSnapshot) -> Self {
+        Self {
+            number: snapshot.number,
+            hash: snapshot.hash,
+            validator_set: Arc::new(snapshot.validator_set),
+            recents: snapshot.recents.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_primitives::Validator;
+
+    fn validator_set(count: u8) -> ValidatorSet {
+        let validators: Vec<_> = (1..=count)
+            .map(|i| Validator {
+                id: i.into(),
+                address: Address::with_last_byte(i),
+                voting_power: 100,
+                signer: Address::with_last_byte(i),
+                proposer_priority: 0,
+            })
+            .collect();
+        ValidatorSet { proposer: validators.first().cloned(), validators }
+    }
+
+    fn assert_same(view: &SnapshotView, snapshot: &BorSnapshot) {
+        let materialized = view.to_snapshot();
+        assert_eq!(materialized.number, snapshot.number);
+        assert_eq!(materialized.hash, snapshot.hash);
+        assert_eq!(materialized.validator_set, snapshot.validator_set);
+        assert_eq!(materialized.recents, snapshot.recents);
+    }
+
+    #[test]
+    fn test_matches_full_snapshot() {
+        let mut snapshot = BorSnapshot::new(100, B256::ZERO, validator_set(5));
+        snapshot.recents.insert(99, Address::with_last_byte(4));
+        let mut view = SnapshotView::from(snapshot.clone());
+
+        for number in 101..=140 {
+            let signer = Address::with_last_byte((number % 5) as u8 + 1);
+            let hash = B256::with_last_byte(number as u8);
+            let mut delta = SnapshotDelta::new(number, hash, signer);
+
+            snapshot.apply(number, signer);
+            snapshot.hash = hash;
+            if number % 16 == 15 {
+                let next = validator_set((number / 16) as u8 + 3);
+                snapshot.validator_set = next.clone();
+                delta = delta.with_validator_set(next);
+            }
+            view.apply(delta);
+            assert_same(&view, &snapshot);
+        }
+
+        // Re-applying a block replaces its signer, as in the map of recents.
+        snapshot.apply(140, Address::with_last_byte(9));
+        view.apply(SnapshotDelta::new(140, snapshot.hash, Address::with_last_byte(9)));
+        assert_same(&view, &snapshot);
+        assert_same(&SnapshotView::from(view.clone().into_snapshot()), &snapshot);
+    }
+
+    #[test]
+    fn test_child_shares_validator_set() {
+        let parent = SnapshotView::from(BorSnapshot::new(10, B256::ZERO, validator_set(3)));
+        let signer = Address::with_last_byte(1);
+        let child = parent.child(SnapshotDelta::new(11, B256::with_last_byte(11), signer));
+        assert!(child.shares_validator_set(&parent));
+        assert_eq!(parent.number(), 10);
+        assert_eq!(parent.recents().count(), 0);
+        assert_eq!(child.recents().collect::<Vec<_>>(), vec![(11, signer)]);
+
+        let sprint_end = SnapshotDelta::new(12, B256::with_last_byte(12), signer)
+            .with_validator_set(validator_set(4));
+        let grandchild = child.child(sprint_end);
+        assert!(!grandchild.shares_validator_set(&child));
+        assert_eq!(grandchild.validator_set().validators.len(), 4);
+        assert!(grandchild.is_authorized(&Address::with_last_byte(4)));
+        assert!(!child.is_authorized(&Address::with_last_byte(4)));
+    }
+}
@@ -4,6 +4,7 @@
 //! Heimdall spans before block validation needs them.
 
 use crate::BorConsensus;
+use bor_chainspec::BorHardforks;
 use heimdall_client::HeimdallClient;
 use reth_chainspec::{EthChainSpec, EthereumHardforks};
 use std::fmt::Debug;
@@ -11,9 +12,6 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
-/// Default span size (pre-Rio). TODO: make configurable per chain spec.
-const DEFAULT_SPAN_SIZE: u64 = 6400;
-
 /// How many spans ahead of the current block to pre-fetch.
 const PREFETCH_AHEAD: u64 = 2;
 
@@ -31,14 +29,12 @@ pub struct SpanPrefetcher<C, ChainSpec> {
     consensus: Arc<BorConsensus<ChainSpec>>,
     /// The highest span ID that has been successfully fetched and cached.
     last_fetched_span: Option<u64>,
-    /// Span size in blocks (depends on chain fork).
-    span_size: u64,
 }
 
 impl<C, ChainSpec> SpanPrefetcher<C, ChainSpec>
 where
     C: HeimdallClient,
-    ChainSpec: EthChainSpec + EthereumHardforks + Debug + Send + Sync,
+    ChainSpec: EthChainSpec + EthereumHardforks + BorHardforks + Debug + Send + Sync,
 {
     /// Create a new span prefetcher.
     pub fn new(
@@ -49,16 +45,9 @@ where
             client,
             consensus,
             last_fetched_span: None,
-            span_size: DEFAULT_SPAN_SIZE,
         }
     }
 
-    /// Create a new span prefetcher with a custom span size.
-    pub fn with_span_size(mut self, span_size: u64) -> Self {
-        self.span_size = span_size;
-        self
-    }
-
     /// Fetch a single span by ID and insert it into the consensus span cache.
     /// Returns `true` if the span was fetched successfully.
     async fn fetch_and_cache_span(&self, span_id: u64) -> bool {
@@ -89,7 +78,7 @@ where
 
     /// Ensure spans are cached for the given block number and ahead.
     async fn ensure_spans_for_block(&mut self, block_number: u64) {
-        let current_span_id = bor_primitives::span_id_at(block_number, self.span_size);
+        let current_span_id = self.consensus.chain_spec().bor_span_id(block_number);
 
         // Fetch current span + PREFETCH_AHEAD spans
         for offset in 0..=PREFETCH_AHEAD {
@@ -117,7 +106,7 @@ where
     where
         F: Fn() -> Option<u64> + Send,
     {
-        info!(target: "bor::prefetch", span_size = self.span_size, "span prefetcher started");
+        info!(target: "bor::prefetch", "span prefetcher started");
 
         // Fetch span 0 (genesis span) immediately
         self.fetch_and_cache_span(0).await;
@@ -188,8 +177,8 @@ mod tests {
         prefetcher.prefetch_for_block(6400).await; // span 1
 
         // Should have cached span 1, 2, and 3 (but 3 doesn't exist)
-        assert!(consensus.span_cache.lock().unwrap().contains(1));
-        assert!(consensus.span_cache.lock().unwrap().contains(2));
+        assert!(consensus.span_cache().lock().unwrap().contains(1));
+        assert!(consensus.span_cache().lock().unwrap().contains(2));
     }
 
     #[tokio::test]
@@ -201,7 +190,7 @@ mod tests {
         let mut prefetcher = SpanPrefetcher::new(mock, consensus.clone());
         prefetcher.prefetch_for_block(0).await;
 
-        assert!(consensus.span_cache.lock().unwrap().contains(0));
+        assert!(consensus.span_cache().lock().unwrap().contains(0));
     }
 
     #[tokio::test]
@@ -215,7 +204,7 @@ mod tests {
 
         // First fetch
         prefetcher.prefetch_for_block(0).await;
-        assert!(consensus.span_cache.lock().unwrap().contains(0));
+        assert!(consensus.span_cache().lock().unwrap().contains(0));
 
         // Second fetch at same block should not re-fetch
         prefetcher.prefetch_for_block(0).await;
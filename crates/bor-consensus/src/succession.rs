@@ -0,0 +1,288 @@
+//! Backup producer succession.
+//!
+//! Every sprint has one designated proposer. If it misses its slot, the
+//! validators that follow it in the set may produce the block instead, in
+//! order. A validator's *succession number* is its circular distance from the
+//! proposer: the proposer is 0, the next validator 1, and so on.
+//!
+//! Backups pay for taking over twice:
+//! - **Delay**: the block may not be sealed before
+//!   `parent.timestamp + producer_delay(number, succession)`, so each backup
+//!   waits `backup_multiplier` seconds longer than the one before it.
+//! - **Difficulty**: the block carries `validator_count - succession`, so the
+//!   proposer's chain always outweighs a backup's when both exist.
+
+use alloy_primitives::{Address, U256};
+use bor_chainspec::{constants::DIFF_NO_TURN, BorHardforks, ScheduleError};
+use bor_primitives::{Span, ValidatorSet};
+
+use crate::proposer::{current_proposer, increment_proposer_priority};
+use crate::validation::ValidationError;
+
+fn index_of(validator_set: &ValidatorSet, address: &Address) -> Option<usize> {
+    validator_set
+        .validators
+        .iter()
+        .position(|v| &v.signer == address)
+}
+
+/// Returns the succession number of `signer` relative to the current proposer.
+///
+/// Fails with [`ValidationError::UnauthorizedProposer`] if the proposer is not
+/// part of the set, and [`ValidationError::UnauthorizedSigner`] if the signer
+/// is not.
+pub fn succession_number(
+    validator_set: &ValidatorSet,
+    signer: &Address,
+) -> Result<usize, ValidationError> {
+    let proposer = current_proposer(validator_set)
+        .ok_or(ValidationError::UnauthorizedSigner(*signer))?;
+    let proposer_idx = index_of(validator_set, &proposer)
+        .ok_or(ValidationError::UnauthorizedProposer(proposer))?;
+    let signer_idx =
+        index_of(validator_set, signer).ok_or(ValidationError::UnauthorizedSigner(*signer))?;
+
+    let total = validator_set.validators.len();
+    Ok((signer_idx + total - proposer_idx) % total)
+}
+
+/// Returns the difficulty a block sealed by `signer` must carry.
+///
+/// This is `validator_count - succession`, so the proposer gets the full
+/// validator count and each backup one less than the validator before it. An
+/// empty set yields 1.
+pub fn succession_difficulty(
+    validator_set: &ValidatorSet,
+    signer: &Address,
+) -> Result<U256, ValidationError> {
+    let total = validator_set.validators.len();
+    if total == 0 {
+        return Ok(U256::from(DIFF_NO_TURN));
+    }
+    let succession = succession_number(validator_set, signer)?;
+    Ok(U256::from(total - succession))
+}
+
+/// Validator set of the sprint containing `number`, derived from the span covering it.
+///
+/// The span's validator set names the proposer of its first sprint; every later sprint
+/// advances proposer priorities by one round, as Bor's snapshot does at sprint ends.
+/// Returns `None` if the span does not cover `number`.
+pub fn sprint_validator_set<F: BorHardforks + ?Sized>(
+    forks: &F,
+    span: &Span,
+    number: u64,
+) -> Result<Option<ValidatorSet>, ScheduleError> {
+    if number < span.start_block || number > span.end_block {
+        return Ok(None);
+    }
+    let mut set = span.validator_set.clone();
+    let sprints = (number - span.start_block) / forks.try_bor_sprint_size(number)?;
+    if sprints > 0 {
+        let Ok(proposer) = increment_proposer_priority(&mut set, sprints as usize) else {
+            return Ok(None);
+        };
+        set.proposer = set.validators.iter().find(|v| v.signer == proposer).cloned();
+    }
+    Ok(Some(set))
+}
+
+/// Returns the minimum number of seconds between the parent and a block at
+/// `number` sealed by a producer with the given succession number.
+///
+/// The base delay is the chain's block period, or its producer delay for the
+/// first block of a sprint. Each step of succession adds the backup multiplier.
+pub fn producer_delay<F: BorHardforks + ?Sized>(
+    forks: &F,
+    number: u64,
+    succession: usize,
+) -> Result<u64, ScheduleError> {
+    let base = if forks.is_bor_sprint_start(number)? {
+        forks.bor_producer_delay(number)
+    } else {
+        forks.bor_period(number)
+    };
+    Ok(base + succession as u64 * forks.bor_backup_multiplier(number))
+}
+
+/// Returns the earliest timestamp a producer with the given succession number
+/// may use for block `number`.
+pub fn earliest_block_time<F: BorHardforks + ?Sized>(
+    forks: &F,
+    parent_timestamp: u64,
+    number: u64,
+    succession: usize,
+) -> Result<u64, ScheduleError> {
+    Ok(parent_timestamp + producer_delay(forks, number, succession)?)
+}
+
+/// Validates the succession rules for a block sealed by `signer`.
+///
+/// Checks that the signer waited long enough for its position behind the
+/// proposer and that the header difficulty matches that position. Returns
+/// the signer's succession number.
+pub fn validate_succession<F: BorHardforks + ?Sized>(
+    forks: &F,
+    number: u64,
+    timestamp: u64,
+    difficulty: U256,
+    signer: &Address,
+    parent_timestamp: u64,
+    validator_set: &ValidatorSet,
+) -> Result<usize, ValidationError> {
+    let succession = succession_number(validator_set, signer)?;
+
+    let earliest = earliest_block_time(forks, parent_timestamp, number, succession)?;
+    if timestamp < earliest {
+        return Err(ValidationError::BlockTooSoon {
+            number,
+            succession,
+            block_time: timestamp,
+            earliest,
+        });
+    }
+
+    let expected = succession_difficulty(validator_set, signer)?;
+    if difficulty != expected {
+        return Err(ValidationError::WrongDifficulty {
+            expected,
+            got: difficulty,
+        });
+    }
+
+    Ok(succession)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::MainnetBorHardforks;
+    use bor_primitives::{span_start_block, Validator};
+
+    fn addr(b: u8) -> Address {
+        Address::new([b; 20])
+    }
+
+    fn validator_set(count: u8, proposer: u8) -> ValidatorSet {
+        let validators: Vec<Validator> = (1..=count)
+            .map(|b| Validator {
+                id: b as u64,
+                address: addr(b),
+                voting_power: 100,
+                signer: addr(b),
+                proposer_priority: 0,
+            })
+            .collect();
+        let proposer = validators.iter().find(|v| v.signer == addr(proposer)).cloned();
+        ValidatorSet {
+            validators,
+            proposer,
+        }
+    }
+
+    #[test]
+    fn test_succession_wraps_around_set() {
+        let vs = validator_set(4, 3);
+        assert_eq!(succession_number(&vs, &addr(3)).unwrap(), 0);
+        assert_eq!(succession_number(&vs, &addr(4)).unwrap(), 1);
+        assert_eq!(succession_number(&vs, &addr(1)).unwrap(), 2);
+        assert_eq!(succession_number(&vs, &addr(2)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_succession_difficulty() {
+        let vs = validator_set(4, 3);
+        assert_eq!(succession_difficulty(&vs, &addr(3)).unwrap(), U256::from(4));
+        assert_eq!(succession_difficulty(&vs, &addr(4)).unwrap(), U256::from(3));
+        assert_eq!(succession_difficulty(&vs, &addr(2)).unwrap(), U256::from(1));
+        assert_eq!(
+            succession_difficulty(&ValidatorSet { validators: vec![], proposer: None }, &addr(1))
+                .unwrap(),
+            U256::from(1)
+        );
+    }
+
+    #[test]
+    fn test_unknown_signer_and_proposer() {
+        let vs = validator_set(3, 1);
+        assert!(matches!(
+            succession_number(&vs, &addr(9)),
+            Err(ValidationError::UnauthorizedSigner(a)) if a == addr(9)
+        ));
+
+        let mut vs = validator_set(3, 1);
+        vs.proposer.as_mut().unwrap().signer = addr(8);
+        assert!(matches!(
+            succession_number(&vs, &addr(1)),
+            Err(ValidationError::UnauthorizedProposer(a)) if a == addr(8)
+        ));
+    }
+
+    #[test]
+    fn test_producer_delay() {
+        // Mid-sprint: block period plus backup multiplier per step.
+        let forks = MainnetBorHardforks;
+        assert_eq!(producer_delay(&forks, 65, 0), Ok(2));
+        assert_eq!(producer_delay(&forks, 65, 1), Ok(4));
+        assert_eq!(producer_delay(&forks, 65, 3), Ok(8));
+
+        // Sprint start, pre-Delhi: producer delay of 6.
+        assert_eq!(producer_delay(&forks, 64, 0), Ok(6));
+        assert_eq!(producer_delay(&forks, 64, 2), Ok(10));
+
+        // Sprint start, post-Delhi: producer delay of 4.
+        assert_eq!(producer_delay(&forks, 38_189_056, 0), Ok(4));
+        assert_eq!(producer_delay(&forks, 38_189_056, 1), Ok(6));
+    }
+
+    #[test]
+    fn test_validate_succession_backup_accepted() {
+        let vs = validator_set(4, 1);
+        // Second backup, mid-sprint: waits 2 + 2*2 = 6s, difficulty 4 - 2 = 2.
+        let succession =
+            validate_succession(&MainnetBorHardforks, 65, 1006, U256::from(2), &addr(3), 1000, &vs)
+                .unwrap();
+        assert_eq!(succession, 2);
+    }
+
+    #[test]
+    fn test_validate_succession_too_soon() {
+        let vs = validator_set(4, 1);
+        let forks = MainnetBorHardforks;
+        let err =
+            validate_succession(&forks, 65, 1005, U256::from(2), &addr(3), 1000, &vs).unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::BlockTooSoon { succession: 2, earliest: 1006, .. }
+        ));
+    }
+
+    #[test]
+    fn test_validate_succession_wrong_difficulty() {
+        let vs = validator_set(4, 1);
+        // A backup claiming the proposer's difficulty is rejected.
+        let forks = MainnetBorHardforks;
+        let err =
+            validate_succession(&forks, 65, 1010, U256::from(4), &addr(3), 1000, &vs).unwrap_err();
+        assert!(matches!(err, ValidationError::WrongDifficulty { .. }));
+    }
+
+    #[test]
+    fn test_sprint_validator_set_rotates_proposer() {
+        let set = validator_set(3, 1);
+        let span = Span {
+            id: 1,
+            start_block: span_start_block(1),
+            end_block: span_start_block(2) - 1,
+            validator_set: set.clone(),
+            selected_producers: set.validators.clone(),
+            bor_chain_id: "137".to_string(),
+        };
+        let forks = MainnetBorHardforks;
+        let first = sprint_validator_set(&forks, &span, 256).unwrap().unwrap();
+        assert_eq!(first.proposer.unwrap().signer, addr(1));
+        let later = sprint_validator_set(&forks, &span, 256 + 64).unwrap().unwrap();
+        assert!(later.proposer.is_some());
+        assert_eq!(sprint_validator_set(&forks, &span, 6656), Ok(None));
+    }
+}
@@ -0,0 +1,397 @@
+//! Synthetic Bor chains for tests.
+//!
+//! [`TestChain`] generates sealed headers for any sprint and span length, validator
+//! count and hardfork schedule, so snapshot, difficulty and timestamp logic can be
+//! tested without mainnet fixtures. Validators sign with deterministic keys from
+//! [`test_signer`]; each span gives them different voting powers, so the proposer
+//! rotates every sprint and the order changes at every span boundary. The chain keeps
+//! the [`BorSnapshot`] after every block, built the way an importing node builds it.
+//!
+//! Blocks are sealed by the proposer unless a backup is named with
+//! [`TestChain::push_block_by`], and are timestamped at the earliest time the
+//! producer may seal them under the chain's own delays.
+
+use std::collections::BTreeMap;
+
+use alloy_consensus::Header;
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use bor_chainspec::constants::EXTRADATA_SEAL_LEN;
+use bor_chainspec::{BorHardfork, BorHardforks, BACKUP_MULTIPLIER, BLOCK_PERIOD};
+use bor_primitives::{Span, Validator, ValidatorSet};
+use k256::ecdsa::SigningKey;
+use reth_ethereum_forks::ForkCondition;
+
+use crate::extra_data::ExtraDataBuilder;
+use crate::proposer::select_proposer;
+use crate::seal::compute_seal_hash;
+use crate::BorSnapshot;
+
+/// Vanity written into every test header.
+const TEST_VANITY: &[u8] = b"boreth-test-chain";
+
+/// Gas limit of every test block.
+const TEST_GAS_LIMIT: u64 = 30_000_000;
+
+/// Chain ID written into the spans of every test chain.
+pub const TEST_CHAIN_ID: u64 = 15_001;
+
+/// Shape of a [`TestChain`].
+#[derive(Debug, Clone)]
+pub struct TestChainConfig {
+    /// Number of validators.
+    pub validators: usize,
+    /// Blocks per sprint.
+    pub sprint_size: u64,
+    /// Blocks per span; must be a multiple of the sprint size.
+    pub span_size: u64,
+    /// Activation of the Bor hardforks; forks not listed never activate.
+    pub forks: BTreeMap<BorHardfork, ForkCondition>,
+    /// Seconds between blocks within a sprint.
+    pub period: u64,
+    /// Extra seconds per position a backup producer is behind the proposer.
+    pub backup_multiplier: u64,
+    /// Timestamp of the genesis header.
+    pub genesis_timestamp: u64,
+}
+
+impl Default for TestChainConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            sprint_size: 4,
+            span_size: 16,
+            forks: BTreeMap::new(),
+            period: BLOCK_PERIOD,
+            backup_multiplier: BACKUP_MULTIPLIER,
+            genesis_timestamp: 1_700_000_000,
+        }
+    }
+}
+
+impl TestChainConfig {
+    /// Set the number of validators.
+    pub fn with_validators(mut self, validators: usize) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Set the sprint size.
+    pub fn with_sprint_size(mut self, sprint_size: u64) -> Self {
+        self.sprint_size = sprint_size;
+        self
+    }
+
+    /// Set the span size.
+    pub fn with_span_size(mut self, span_size: u64) -> Self {
+        self.span_size = span_size;
+        self
+    }
+
+    /// Activate `fork` at `block`.
+    pub fn with_fork(mut self, fork: BorHardfork, block: u64) -> Self {
+        self.forks.insert(fork, ForkCondition::Block(block));
+        self
+    }
+
+    /// Seconds a block at `number` sealed by a producer `succession` positions behind the
+    /// proposer waits after its parent: the producer delay of the schedule at sprint
+    /// start, the period otherwise, plus the backup multiplier per position.
+    pub fn producer_delay(&self, number: u64, succession: usize) -> u64 {
+        let base = if number % self.sprint_size == 0 {
+            self.forks.bor_producer_delay(number)
+        } else {
+            self.period
+        };
+        base + succession as u64 * self.backup_multiplier
+    }
+}
+
+/// Deterministic signing key and address of test validator `index`.
+pub fn test_signer(index: usize) -> (SigningKey, Address) {
+    let secret = keccak256(format!("boreth-test-validator-{index}"));
+    let key = SigningKey::from_bytes((&secret.0).into()).expect("keccak output is a valid key");
+    let address = Address::from_raw_public_key(
+        &key.verifying_key().to_encoded_point(false).as_bytes()[1..],
+    );
+    (key, address)
+}
+
+/// Span `id` over `signers`, with voting powers that change from span to span.
+pub fn test_span(id: u64, span_size: u64, signers: &[Address]) -> Span {
+    let validators: Vec<Validator> = signers
+        .iter()
+        .enumerate()
+        .map(|(i, signer)| Validator {
+            id: i as u64 + 1,
+            address: *signer,
+            voting_power: 100 * (1 + ((i as u64 + id) % signers.len() as u64) as i64),
+            signer: *signer,
+            proposer_priority: 0,
+        })
+        .collect();
+    Span {
+        id,
+        start_block: id * span_size,
+        end_block: (id + 1) * span_size - 1,
+        validator_set: ValidatorSet { validators: validators.clone(), proposer: None },
+        selected_producers: validators,
+        bor_chain_id: TEST_CHAIN_ID.to_string(),
+    }
+}
+
+/// A generated chain of sealed headers with the snapshot after each of them.
+#[derive(Debug, Clone)]
+pub struct TestChain {
+    config: TestChainConfig,
+    signers: Vec<(SigningKey, Address)>,
+    headers: Vec<Header>,
+    snapshots: Vec<BorSnapshot>,
+}
+
+impl TestChain {
+    /// Start a chain at genesis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no validators, or if the span size is not a positive
+    /// multiple of the sprint size.
+    pub fn new(config: TestChainConfig) -> Self {
+        assert!(config.validators > 0, "test chain needs at least one validator");
+        assert!(
+            config.sprint_size > 0 && config.span_size % config.sprint_size == 0,
+            "span size must be a positive multiple of the sprint size"
+        );
+        let signers: Vec<_> = (0..config.validators).map(test_signer).collect();
+        let genesis = Header {
+            difficulty: U256::from(1),
+            gas_limit: TEST_GAS_LIMIT,
+            timestamp: config.genesis_timestamp,
+            extra_data: ExtraDataBuilder::new(0, config.sprint_size)
+                .build()
+                .expect("genesis extra data is valid"),
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        let mut chain = Self { config, signers, headers: Vec::new(), snapshots: Vec::new() };
+        let mut validator_set = chain.span(0).validator_set;
+        select_proposer(&mut validator_set);
+        chain.snapshots.push(BorSnapshot::new(0, genesis.hash_slow(), validator_set));
+        chain.headers.push(genesis);
+        chain
+    }
+
+    /// Generate a chain of `blocks` blocks after genesis, each sealed by its proposer.
+    pub fn generate(config: TestChainConfig, blocks: u64) -> Self {
+        let mut chain = Self::new(config);
+        chain.extend(blocks);
+        chain
+    }
+
+    /// Append `blocks` blocks, each sealed by its proposer.
+    pub fn extend(&mut self, blocks: u64) {
+        for _ in 0..blocks {
+            self.push_block_by(self.proposer_index());
+        }
+    }
+
+    /// Append a block sealed by validator `index`, a backup unless it is the proposer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not a validator.
+    pub fn push_block_by(&mut self, index: usize) -> &Header {
+        let (key, signer) = self.signers[index].clone();
+        let parent = self.head();
+        let snapshot = self.snapshot();
+        let number = parent.number + 1;
+        let succession = snapshot.succession(&signer).expect("signer is a validator");
+
+        let mut extra = ExtraDataBuilder::new(number, self.config.sprint_size)
+            .with_vanity(TEST_VANITY);
+        let next = self.is_sprint_end(number).then(|| self.next_validator_set(number));
+        if let Some(next) = &next {
+            extra = extra.with_validators(&next.validators);
+        }
+        let mut header = Header {
+            parent_hash: parent.hash_slow(),
+            beneficiary: signer,
+            difficulty: snapshot.difficulty(&signer).expect("signer is a validator"),
+            number,
+            gas_limit: TEST_GAS_LIMIT,
+            timestamp: parent.timestamp + self.config.producer_delay(number, succession),
+            extra_data: extra.build().expect("test extra data is valid"),
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        seal(&mut header, &key);
+
+        let mut snapshot = snapshot.clone();
+        snapshot.apply(number, signer);
+        snapshot.hash = header.hash_slow();
+        if let Some(mut next) = next {
+            select_proposer(&mut next);
+            snapshot.validator_set = next;
+        }
+        self.snapshots.push(snapshot);
+        self.headers.push(header);
+        self.head()
+    }
+
+    /// The chain configuration.
+    pub fn config(&self) -> &TestChainConfig {
+        &self.config
+    }
+
+    /// Validator addresses, in validator order.
+    pub fn signers(&self) -> Vec<Address> {
+        self.signers.iter().map(|(_, address)| *address).collect()
+    }
+
+    /// Signing key of validator `index`, e.g. to seal a crafted header.
+    pub fn signing_key(&self, index: usize) -> &SigningKey {
+        &self.signers[index].0
+    }
+
+    /// Span `id` of the chain.
+    pub fn span(&self, id: u64) -> Span {
+        test_span(id, self.config.span_size, &self.signers())
+    }
+
+    /// All headers, starting at genesis.
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// The header at `number`, if generated.
+    pub fn header(&self, number: u64) -> Option<&Header> {
+        self.headers.get(number as usize)
+    }
+
+    /// The last header.
+    pub fn head(&self) -> &Header {
+        self.headers.last().expect("genesis is always present")
+    }
+
+    /// The snapshot after block `number`, if generated.
+    pub fn snapshot_at(&self, number: u64) -> Option<&BorSnapshot> {
+        self.snapshots.get(number as usize)
+    }
+
+    /// The snapshot after the last block.
+    pub fn snapshot(&self) -> &BorSnapshot {
+        self.snapshots.last().expect("genesis is always present")
+    }
+
+    /// Index of the validator proposing the next block.
+    pub fn proposer_index(&self) -> usize {
+        self.signers
+            .iter()
+            .position(|(_, address)| self.snapshot().succession(address).ok() == Some(0))
+            .expect("the proposer is a validator")
+    }
+
+    fn is_sprint_end(&self, number: u64) -> bool {
+        (number + 1) % self.config.sprint_size == 0
+    }
+
+    /// Validator set that takes over after the sprint ending at `number`.
+    fn next_validator_set(&self, number: u64) -> ValidatorSet {
+        if (number + 1) % self.config.span_size == 0 {
+            self.span((number + 1) / self.config.span_size).validator_set
+        } else {
+            self.snapshot().validator_set.clone()
+        }
+    }
+}
+
+/// Write the seal of `key` over `header` into its extra data.
+pub fn seal(header: &mut Header, key: &SigningKey) {
+    let (sig, recid) = key
+        .sign_prehash_recoverable(compute_seal_hash(header).as_ref())
+        .expect("seal hash is a valid prehash");
+    let mut extra_data = header.extra_data.to_vec();
+    let seal_start = extra_data.len() - EXTRADATA_SEAL_LEN;
+    extra_data[seal_start..seal_start + 64].copy_from_slice(&sig.to_bytes());
+    extra_data[seal_start + 64] = recid.to_byte();
+    header.extra_data = Bytes::from(extra_data);
+}
+
+/// Hashes of `headers`, in order.
+pub fn hashes(headers: &[Header]) -> Vec<B256> {
+    headers.iter().map(Header::hash_slow).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extra_data::ExtraData;
+    use crate::seal::ecrecover_seal;
+    use crate::succession::succession_difficulty;
+
+    fn recover(header: &Header) -> Address {
+        let extra = ExtraData::parse(&header.extra_data).unwrap();
+        ecrecover_seal(&compute_seal_hash(header), &extra.seal).unwrap()
+    }
+
+    #[test]
+    fn test_generated_chain_is_linked_and_sealed_by_proposers() {
+        let config = TestChainConfig::default().with_validators(5).with_sprint_size(3);
+        let chain = TestChain::generate(config.with_span_size(9), 20);
+        assert_eq!(chain.head().number, 20);
+
+        let hashes = hashes(chain.headers());
+        for header in &chain.headers()[1..] {
+            let number = header.number;
+            assert_eq!(header.parent_hash, hashes[number as usize - 1]);
+            let parent = chain.snapshot_at(number - 1).unwrap();
+            let signer = recover(header);
+            assert_eq!(parent.succession(&signer).ok(), Some(0), "block {number}");
+            assert_eq!(header.difficulty, U256::from(5));
+            assert_eq!(chain.snapshot_at(number).unwrap().hash, hashes[number as usize]);
+
+            let extra = ExtraData::parse(&header.extra_data).unwrap();
+            assert_eq!(extra.validator_bytes.is_empty(), (number + 1) % 3 != 0, "block {number}");
+        }
+    }
+
+    #[test]
+    fn test_backup_difficulty_and_delay() {
+        let mut chain = TestChain::generate(TestChainConfig::default(), 2);
+        let backup = (chain.proposer_index() + 1) % 4;
+        let parent = chain.head().clone();
+        let snapshot = chain.snapshot().clone();
+
+        let header = chain.push_block_by(backup).clone();
+        let signer = recover(&header);
+        assert_eq!(signer, chain.signers()[backup]);
+        let expected = succession_difficulty(&snapshot.validator_set, &signer).unwrap();
+        assert_eq!(header.difficulty, expected);
+        assert_eq!(expected, U256::from(3));
+        assert_eq!(header.timestamp, parent.timestamp + BLOCK_PERIOD + BACKUP_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_fork_schedule_sets_sprint_start_delay() {
+        let config = TestChainConfig::default().with_fork(BorHardfork::Delhi, 8);
+        let chain = TestChain::generate(config, 9);
+        let delay = |number: u64| {
+            chain.header(number).unwrap().timestamp - chain.header(number - 1).unwrap().timestamp
+        };
+        // Sprint starts at 4 and 8: before and at Delhi.
+        assert_eq!(delay(4), 6);
+        assert_eq!(delay(8), 4);
+        assert_eq!(delay(5), BLOCK_PERIOD);
+    }
+
+    #[test]
+    fn test_validator_set_rotates_at_span_end() {
+        let chain = TestChain::generate(TestChainConfig::default(), 16);
+        let first = &chain.snapshot_at(15).unwrap().validator_set;
+        let second = &chain.snapshot_at(16).unwrap().validator_set;
+        assert_eq!(first.validators.len(), second.validators.len());
+        let power = |set: &ValidatorSet| set.validators[0].voting_power;
+        assert_eq!(power(first), chain.span(1).validator_set.validators[0].voting_power);
+        assert_eq!(power(second), power(first));
+        assert_ne!(power(&chain.span(0).validator_set), power(first));
+    }
+}
@@ -14,9 +14,8 @@ use alloy_primitives::{Address, B256, U256};
 use crate::difficulty::calculate_difficulty;
 use crate::extra_data::ExtraData;
 use crate::seal::ecrecover_seal;
-
-/// Maximum allowed clock drift for block timestamps (15 seconds).
-const MAX_FUTURE_BLOCK_TIME: u64 = 15;
+use bor_chainspec::constants::ALLOWED_FUTURE_BLOCK_TIME;
+use bor_chainspec::ScheduleError;
 
 /// Errors during consensus validation.
 #[derive(Debug, thiserror::Error)]
@@ -43,6 +42,10 @@ pub enum ValidationError {
     InvalidGasLimit { expected: u64, got: u64 },
     #[error("invalid base fee: expected {expected}, got {got}")]
     InvalidBaseFee { expected: U256, got: U256 },
+    #[error("block {number} (succession {succession}) too soon: timestamp {block_time}, earliest {earliest}")]
+    BlockTooSoon { number: u64, succession: usize, block_time: u64, earliest: u64 },
+    #[error("proposer {0} is not in the validator set")]
+    UnauthorizedProposer(Address),
     #[error("block timestamp must be greater than parent: block={block_time}, parent={parent_time}")]
     TimestampNotIncreasing { block_time: u64, parent_time: u64 },
     #[error("non-empty withdrawals")]
@@ -55,6 +58,8 @@ pub enum ValidationError {
     ReceiptRootMismatch { expected: B256, got: B256 },
     #[error("gas used mismatch: expected {expected}, got {got}")]
     GasUsedMismatch { expected: u64, got: u64 },
+    #[error(transparent)]
+    Schedule(#[from] ScheduleError),
 }
 
 /// Parameters for validating a Bor header.
@@ -112,7 +117,7 @@ pub fn validate_header(
         .map_err(|e| ValidationError::InvalidExtraData(e.to_string()))?;
 
     // 5. Block timestamp must not be too far in the future
-    if params.timestamp > current_time + MAX_FUTURE_BLOCK_TIME {
+    if params.timestamp > current_time + ALLOWED_FUTURE_BLOCK_TIME {
         return Err(ValidationError::FutureBlock {
             block_time: params.timestamp,
             now: current_time,
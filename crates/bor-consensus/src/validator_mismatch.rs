@@ -0,0 +1,199 @@
+//! Diagnostics for sprint-end validator bytes that differ from the next span.
+//!
+//! The last block of a sprint lists, in its extra data, the validators of the
+//! block after it, in the order of its span. When that list is not the one the span
+//! holds, [`ValidatorMismatch`] records both lists and what separates them:
+//! validators missing from the header with the voting power they carry, and
+//! addresses the span does not know. [`LastValidatorMismatch`] keeps the most
+//! recent one for `debug_borLastValidatorMismatch`.
+
+use alloy_primitives::Address;
+use bor_primitives::Validator;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A validator the header was expected to list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedValidator {
+    /// Signer address.
+    pub address: Address,
+    /// Voting power in the span.
+    pub voting_power: i64,
+}
+
+/// Sprint-end validator bytes that differ from the validators of the next span.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorMismatch {
+    /// The sprint-end block.
+    pub block: u64,
+    /// The span the validators were expected from.
+    pub span_id: u64,
+    /// Validators of the span, in span order.
+    pub expected: Vec<ExpectedValidator>,
+    /// Validators listed in the header's extra data, in header order.
+    pub got: Vec<Address>,
+    /// Expected validators the header does not list.
+    pub missing: Vec<ExpectedValidator>,
+    /// Listed validators the span does not hold.
+    pub unexpected: Vec<Address>,
+    /// Total voting power of the missing validators.
+    pub missing_power: i64,
+}
+
+impl ValidatorMismatch {
+    /// Compare the validators `got` from the extra data of sprint-end block `block`
+    /// against `expected`, the validators of span `span_id`.
+    ///
+    /// Returns `None` if the header lists exactly the span's validators, in order.
+    pub fn check(
+        block: u64,
+        span_id: u64,
+        expected: &[Validator],
+        got: &[Address],
+    ) -> Option<Self> {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|v| ExpectedValidator { address: v.signer, voting_power: v.voting_power })
+            .collect();
+
+        if expected.iter().map(|v| v.address).eq(got.iter().copied()) {
+            return None;
+        }
+
+        let missing: Vec<_> =
+            expected.iter().filter(|v| !got.contains(&v.address)).copied().collect();
+        let unexpected: Vec<_> = got
+            .iter()
+            .filter(|address| !expected.iter().any(|v| v.address == **address))
+            .copied()
+            .collect();
+        let missing_power = missing.iter().map(|v| v.voting_power).sum();
+
+        let got = got.to_vec();
+        Some(Self { block, span_id, expected, got, missing, unexpected, missing_power })
+    }
+
+    /// Whether the header lists the expected validators, but in another order.
+    pub fn is_reordered(&self) -> bool {
+        self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.expected.len() == self.got.len()
+    }
+}
+
+impl fmt::Display for ValidatorMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid validators at sprint end block {}: expected {} validators of span {}, got {}",
+            self.block,
+            self.expected.len(),
+            self.span_id,
+            self.got.len()
+        )?;
+        if self.is_reordered() {
+            return write!(f, "; same validators in another order");
+        }
+        if !self.missing.is_empty() {
+            write!(f, "; missing")?;
+            for v in &self.missing {
+                write!(f, " {} (power {})", v.address, v.voting_power)?;
+            }
+            write!(f, ", {} voting power in total", self.missing_power)?;
+        }
+        if !self.unexpected.is_empty() {
+            write!(f, "; unexpected")?;
+            for address in &self.unexpected {
+                write!(f, " {address}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`LastValidatorMismatch`] shared between consensus and RPC.
+pub type SharedLastValidatorMismatch = Arc<LastValidatorMismatch>;
+
+/// The most recent [`ValidatorMismatch`] seen by consensus.
+#[derive(Debug, Default)]
+pub struct LastValidatorMismatch {
+    last: Mutex<Option<ValidatorMismatch>>,
+}
+
+impl LastValidatorMismatch {
+    /// Create an empty record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the recorded mismatch with `mismatch`.
+    pub fn record(&self, mismatch: ValidatorMismatch) {
+        *self.last.lock().expect("validator mismatch lock poisoned") = Some(mismatch);
+    }
+
+    /// The most recent mismatch, if any was seen since the node started.
+    pub fn get(&self) -> Option<ValidatorMismatch> {
+        self.last.lock().expect("validator mismatch lock poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(byte: u8, voting_power: i64) -> Validator {
+        Validator {
+            id: byte.into(),
+            address: Address::repeat_byte(byte),
+            voting_power,
+            signer: Address::repeat_byte(byte),
+            proposer_priority: 0,
+        }
+    }
+
+    #[test]
+    fn test_matching_validators() {
+        let expected = [validator(0xbb, 10), validator(0xaa, 20)];
+        let got = [Address::repeat_byte(0xbb), Address::repeat_byte(0xaa)];
+        assert_eq!(ValidatorMismatch::check(15, 1, &expected, &got), None);
+    }
+
+    #[test]
+    fn test_mismatch_lists_missing_power_and_unexpected() {
+        let expected = [validator(0xaa, 20), validator(0xbb, 10), validator(0xcc, 5)];
+        let got = [Address::repeat_byte(0xaa), Address::repeat_byte(0xdd)];
+        let mismatch = ValidatorMismatch::check(15, 1, &expected, &got).unwrap();
+
+        assert_eq!(mismatch.missing.len(), 2);
+        assert_eq!(mismatch.missing_power, 15);
+        assert_eq!(mismatch.unexpected, vec![Address::repeat_byte(0xdd)]);
+        assert!(!mismatch.is_reordered());
+        let message = mismatch.to_string();
+        assert!(message.contains("expected 3 validators of span 1, got 2"), "{message}");
+        assert!(message.contains(&format!("{} (power 10)", Address::repeat_byte(0xbb))), "{message}");
+        assert!(message.contains(&format!("unexpected {}", Address::repeat_byte(0xdd))), "{message}");
+    }
+
+    #[test]
+    fn test_reordered_validators() {
+        let expected = [validator(0xaa, 20), validator(0xbb, 10)];
+        let got = [Address::repeat_byte(0xbb), Address::repeat_byte(0xaa)];
+        let mismatch = ValidatorMismatch::check(15, 1, &expected, &got).unwrap();
+        assert!(mismatch.is_reordered());
+        assert!(mismatch.to_string().contains("in another order"));
+    }
+
+    #[test]
+    fn test_last_mismatch_is_replaced() {
+        let last = LastValidatorMismatch::new();
+        assert_eq!(last.get(), None);
+        let expected = [validator(0xaa, 20)];
+        for block in [15, 31] {
+            last.record(ValidatorMismatch::check(block, 1, &expected, &[]).unwrap());
+        }
+        assert_eq!(last.get().unwrap().block, 31);
+    }
+}
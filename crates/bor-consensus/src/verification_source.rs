@@ -0,0 +1,78 @@
+//! Where the validators a block is checked against come from.
+//!
+//! Near the tip, signers are checked against the span Heimdall reports. Far
+//! behind it, every span the node meets has long been committed on-chain, so
+//! the ValidatorSet contract at the parent's state answers the same question
+//! without a Heimdall round trip per span. [`VerificationSourceSelector`] picks
+//! between the two by the block's distance from the tip, taken as the end of
+//! the latest milestone.
+
+use alloy_primitives::Address;
+use std::fmt::Debug;
+
+/// Default distance from the tip beyond which validators are read from contract state.
+pub const DEFAULT_CONTRACT_STATE_DISTANCE: u64 = 6_400;
+
+/// Source of the validators a block's signer is checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationSource {
+    /// The span fetched from Heimdall.
+    Heimdall,
+    /// The ValidatorSet contract at the parent block's state.
+    ContractState,
+}
+
+/// Validators read from the ValidatorSet contract.
+pub trait ContractValidatorSource: Debug + Send + Sync {
+    /// Signers of the validators for block `number`, read from the state of its parent.
+    ///
+    /// Returns `None` if that state is not available, e.g. not executed yet.
+    fn signers(&self, number: u64) -> Option<Vec<Address>>;
+}
+
+/// Picks the [`VerificationSource`] of a block by its distance from the tip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationSourceSelector {
+    /// Blocks more than this far behind the tip are checked against contract state.
+    pub contract_state_distance: u64,
+}
+
+impl Default for VerificationSourceSelector {
+    fn default() -> Self {
+        Self { contract_state_distance: DEFAULT_CONTRACT_STATE_DISTANCE }
+    }
+}
+
+impl VerificationSourceSelector {
+    /// The source for block `number` with the tip at `tip`, if known.
+    ///
+    /// Without a known tip the node cannot tell it is behind, so Heimdall is used.
+    pub fn select(&self, number: u64, tip: Option<u64>) -> VerificationSource {
+        match tip {
+            Some(tip) if tip.saturating_sub(number) > self.contract_state_distance => {
+                VerificationSource::ContractState
+            }
+            _ => VerificationSource::Heimdall,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_by_distance() {
+        let selector = VerificationSourceSelector { contract_state_distance: 100 };
+        assert_eq!(selector.select(1_000, Some(1_100)), VerificationSource::Heimdall);
+        assert_eq!(selector.select(1_000, Some(1_101)), VerificationSource::ContractState);
+        // Blocks past the last milestone are near the tip.
+        assert_eq!(selector.select(2_000, Some(1_101)), VerificationSource::Heimdall);
+    }
+
+    #[test]
+    fn test_unknown_tip_uses_heimdall() {
+        let selector = VerificationSourceSelector::default();
+        assert_eq!(selector.select(0, None), VerificationSource::Heimdall);
+    }
+}
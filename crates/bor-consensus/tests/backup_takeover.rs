@@ -0,0 +1,315 @@
+//! Multi-validator backup producer takeover simulations.
+//!
+//! A small chain is produced block by block from a [`BorSnapshot`]. For every
+//! block the first online validator in succession order seals it at the
+//! earliest time its position allows, and the resulting header fields are run
+//! back through [`validate_succession`]. The proposer rotates at every sprint
+//! end, as in Bor.
+//!
+//! Recent-signer limits are left out on purpose: they are exercised by the
+//! header validation tests and would otherwise mask the succession behaviour.
+
+use std::collections::HashSet;
+
+use alloy_primitives::{Address, B256, U256};
+use bor_chainspec::{BorHardforks, MainnetBorHardforks};
+use bor_consensus::proposer::select_proposer;
+use bor_consensus::succession::earliest_block_time;
+use bor_consensus::{validate_succession, BorSnapshot, ValidationError};
+use bor_primitives::{Validator, ValidatorSet};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn addr(b: u8) -> Address {
+    Address::new([b; 20])
+}
+
+/// Equal-power validator set `1..=count` with its first proposer already selected.
+fn make_validator_set(count: u8) -> ValidatorSet {
+    let mut vs = ValidatorSet {
+        validators: (1..=count)
+            .map(|b| Validator {
+                id: b as u64,
+                address: addr(b),
+                voting_power: 100,
+                signer: addr(b),
+                proposer_priority: 0,
+            })
+            .collect(),
+        proposer: None,
+    };
+    select_proposer(&mut vs);
+    vs
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SimBlock {
+    number: u64,
+    timestamp: u64,
+    difficulty: U256,
+    signer: Address,
+    succession: usize,
+}
+
+struct Simulation {
+    snap: BorSnapshot,
+    offline: HashSet<Address>,
+    parent_time: u64,
+}
+
+impl Simulation {
+    /// Starts a simulation whose first produced block is the sprint start `first`.
+    fn new(validators: u8, first: u64) -> Self {
+        assert!(MainnetBorHardforks.is_bor_sprint_start(first).unwrap());
+        Self {
+            snap: BorSnapshot::new(first - 1, B256::ZERO, make_validator_set(validators)),
+            offline: HashSet::new(),
+            parent_time: 1_000,
+        }
+    }
+
+    fn proposer(&self) -> Address {
+        self.snap.validator_set.proposer.as_ref().unwrap().signer
+    }
+
+    fn set_offline(&mut self, signer: Address) {
+        self.offline.insert(signer);
+    }
+
+    fn set_online(&mut self, signer: Address) {
+        self.offline.remove(&signer);
+    }
+
+    /// Produces and validates the next block, or `None` if every validator is offline.
+    fn produce(&mut self) -> Option<SimBlock> {
+        let number = self.snap.number + 1;
+        let validators = &self.snap.validator_set.validators;
+        let signer = validators
+            .iter()
+            .map(|v| v.signer)
+            .filter(|s| !self.offline.contains(s))
+            .min_by_key(|s| self.snap.succession(s).unwrap())?;
+
+        let succession = self.snap.succession(&signer).unwrap();
+        let forks = MainnetBorHardforks;
+        let block = SimBlock {
+            number,
+            timestamp: earliest_block_time(&forks, self.parent_time, number, succession).unwrap(),
+            difficulty: self.snap.difficulty(&signer).unwrap(),
+            signer,
+            succession,
+        };
+
+        let validated = validate_succession(
+            &forks,
+            block.number,
+            block.timestamp,
+            block.difficulty,
+            &block.signer,
+            self.parent_time,
+            &self.snap.validator_set,
+        )
+        .expect("simulated block must validate");
+        assert_eq!(validated, succession);
+
+        self.snap.apply(number, signer);
+        self.parent_time = block.timestamp;
+        if (number + 1) % MainnetBorHardforks.bor_sprint_size(number) == 0 {
+            select_proposer(&mut self.snap.validator_set);
+        }
+        Some(block)
+    }
+
+    fn produce_n(&mut self, n: usize) -> Vec<SimBlock> {
+        (0..n).map(|_| self.produce().expect("a validator is online")).collect()
+    }
+}
+
+fn total_difficulty(blocks: &[SimBlock]) -> U256 {
+    blocks.iter().fold(U256::ZERO, |acc, b| acc + b.difficulty)
+}
+
+// ---------------------------------------------------------------------------
+// Single sprint
+// ---------------------------------------------------------------------------
+
+#[test]
+fn proposer_online_produces_whole_sprint() {
+    let mut sim = Simulation::new(4, 64);
+    let proposer = sim.proposer();
+    let blocks = sim.produce_n(64);
+
+    assert!(blocks.iter().all(|b| b.signer == proposer && b.succession == 0));
+    assert!(blocks.iter().all(|b| b.difficulty == U256::from(4)));
+    // Sprint start waits the producer delay, later blocks the period.
+    assert_eq!(blocks[0].timestamp, 1_006);
+    assert_eq!(blocks[1].timestamp, 1_008);
+}
+
+#[test]
+fn first_backup_takes_over_missed_sprint() {
+    let mut sim = Simulation::new(4, 64);
+    let proposer = sim.proposer();
+    sim.set_offline(proposer);
+
+    let blocks = sim.produce_n(64);
+    let backup = blocks[0].signer;
+    assert_ne!(backup, proposer);
+    assert!(blocks.iter().all(|b| b.signer == backup && b.succession == 1));
+    assert!(blocks.iter().all(|b| b.difficulty == U256::from(3)));
+    assert_eq!(blocks[0].timestamp, 1_000 + 6 + 2);
+    assert_eq!(blocks[1].timestamp - blocks[0].timestamp, 2 + 2);
+}
+
+#[test]
+fn takeover_cascades_through_offline_backups() {
+    let mut sim = Simulation::new(5, 64);
+    let order: Vec<Address> = {
+        let mut v: Vec<_> = sim.snap.validator_set.validators.iter().map(|v| v.signer).collect();
+        v.sort_by_key(|s| sim.snap.succession(s).unwrap());
+        v
+    };
+    for signer in &order[..3] {
+        sim.set_offline(*signer);
+    }
+
+    let block = sim.produce().unwrap();
+    assert_eq!(block.signer, order[3]);
+    assert_eq!(block.succession, 3);
+    assert_eq!(block.difficulty, U256::from(2));
+    assert_eq!(block.timestamp, 1_000 + 6 + 3 * 2);
+
+    // Only the last validator left: lowest difficulty, longest delay.
+    sim.set_offline(order[3]);
+    let block = sim.produce().unwrap();
+    assert_eq!(block.signer, order[4]);
+    assert_eq!(block.difficulty, U256::from(1));
+    assert_eq!(block.timestamp - 1_012, 2 + 4 * 2);
+
+    sim.set_offline(order[4]);
+    assert!(sim.produce().is_none());
+}
+
+#[test]
+fn proposer_resumes_mid_sprint() {
+    let mut sim = Simulation::new(3, 64);
+    let proposer = sim.proposer();
+    sim.set_offline(proposer);
+    let missed = sim.produce_n(10);
+    assert!(missed.iter().all(|b| b.succession == 1));
+
+    sim.set_online(proposer);
+    let resumed = sim.produce_n(5);
+    assert!(resumed.iter().all(|b| b.signer == proposer && b.succession == 0));
+    assert_eq!(resumed[0].timestamp - missed[9].timestamp, 2);
+}
+
+// ---------------------------------------------------------------------------
+// Across sprints
+// ---------------------------------------------------------------------------
+
+#[test]
+fn offline_validator_only_costs_its_own_sprints() {
+    let mut sim = Simulation::new(4, 64);
+    let offline = addr(2);
+    sim.set_offline(offline);
+
+    let mut sprints_as_proposer = 0;
+    for _ in 0..8 {
+        let proposer = sim.proposer();
+        let blocks = sim.produce_n(64);
+        let expected = if proposer == offline {
+            sprints_as_proposer += 1;
+            1
+        } else {
+            0
+        };
+        assert!(
+            blocks.iter().all(|b| b.succession == expected),
+            "sprint proposed by {proposer} used the wrong succession"
+        );
+        assert!(blocks.iter().all(|b| b.signer != offline));
+    }
+    // Equal powers rotate through every validator twice in eight sprints.
+    assert_eq!(sprints_as_proposer, 2);
+}
+
+#[test]
+fn post_delhi_sprints_use_shorter_delay() {
+    let delhi = 38_189_056;
+    let mut sim = Simulation::new(4, delhi);
+    sim.set_offline(sim.proposer());
+
+    let blocks = sim.produce_n(17);
+    assert_eq!(blocks[0].timestamp, 1_000 + 4 + 2);
+    // Block 16 opens the next sprint with a new proposer.
+    assert_eq!(blocks[16].succession, 0);
+    assert_eq!(blocks[16].timestamp - blocks[15].timestamp, 4);
+}
+
+// ---------------------------------------------------------------------------
+// Fork choice and rejection
+// ---------------------------------------------------------------------------
+
+#[test]
+fn proposer_chain_outweighs_backup_chain() {
+    let mut honest = Simulation::new(4, 64);
+    let mut partitioned = Simulation::new(4, 64);
+    partitioned.set_offline(partitioned.proposer());
+
+    let honest_blocks = honest.produce_n(64);
+    let backup_blocks = partitioned.produce_n(64);
+
+    assert!(total_difficulty(&honest_blocks) > total_difficulty(&backup_blocks));
+    assert!(honest_blocks.last().unwrap().timestamp < backup_blocks.last().unwrap().timestamp);
+}
+
+#[test]
+fn backup_block_before_its_slot_is_rejected() {
+    let sim = Simulation::new(4, 64);
+    let vs = &sim.snap.validator_set;
+    let backup = vs
+        .validators
+        .iter()
+        .map(|v| v.signer)
+        .find(|s| sim.snap.succession(s).unwrap() == 2)
+        .unwrap();
+
+    // Sealed at the proposer's time.
+    let forks = MainnetBorHardforks;
+    let err =
+        validate_succession(&forks, 64, 1_006, U256::from(2), &backup, 1_000, vs).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::BlockTooSoon { number: 64, succession: 2, earliest: 1_010, .. }
+    ));
+
+    // On time but claiming in-turn difficulty.
+    let err =
+        validate_succession(&forks, 64, 1_010, U256::from(4), &backup, 1_000, vs).unwrap_err();
+    assert!(matches!(err, ValidationError::WrongDifficulty { .. }));
+
+    // On time with the right difficulty.
+    assert_eq!(
+        validate_succession(&forks, 64, 1_010, U256::from(2), &backup, 1_000, vs).unwrap(),
+        2
+    );
+}
+
+#[test]
+fn unknown_signer_cannot_take_over() {
+    let sim = Simulation::new(3, 64);
+    let err = validate_succession(
+        &MainnetBorHardforks,
+        64,
+        2_000,
+        U256::from(1),
+        &addr(0x99),
+        1_000,
+        &sim.snap.validator_set,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ValidationError::UnauthorizedSigner(a) if a == addr(0x99)));
+}
@@ -416,7 +416,7 @@ fn test_4_6_timestamp_parent_plus_1_accepted() {
 
 #[test]
 fn test_4_6_future_block_at_exact_boundary() {
-    // MAX_FUTURE_BLOCK_TIME = 15
+    // ALLOWED_FUTURE_BLOCK_TIME = 15
     let now = 1000u64;
 
     // Exactly at boundary: now + 15 => should pass
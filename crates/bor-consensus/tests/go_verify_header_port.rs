@@ -8,7 +8,7 @@
 //! - "nil header number → errUnknownBlock": Our API takes `u64` block number,
 //!   so nil is not representable.
 //! - "Rio/Bhilai-specific future block modes": We have a single FutureBlock
-//!   check with MAX_FUTURE_BLOCK_TIME=15s; no mode-based variants.
+//!   check with ALLOWED_FUTURE_BLOCK_TIME=15s; no mode-based variants.
 //! - "VerifyHeaders batch verification": Our API validates one header at a time.
 //! - "Signer caching / LRU snapshot tests": Not part of our stateless validation.
 //! - "Gas limit exceeds maximum": Our `validate_header` does not enforce gas
@@ -151,7 +151,7 @@ fn test_future_block_boundary_allowed() {
 }
 
 // Note: Go tests multiple "future block" modes for Rio and Bhilai forks.
-// Our implementation has a single MAX_FUTURE_BLOCK_TIME=15s check with no
+// Our implementation has a single ALLOWED_FUTURE_BLOCK_TIME=15s check with no
 // fork-specific modes, so those sub-cases are not portable.
 
 // ===========================================================================
@@ -0,0 +1,144 @@
+//! Latency of the system calls at a sprint start, with and without cache warming.
+//!
+//! The contracts are the recording stubs of the executor tests, over a database that
+//! takes [`READ_LATENCY`] per read to stand in for disk. `cold` runs the calls on a
+//! fresh block cache, `warmed` after [`BorSystemCaller::warm`] loaded it, which the
+//! executor does before the block's transactions.
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use bor_chainspec::constants::{BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS};
+use bor_evm::{BorExecutionCtx, BorSystemCaller, PendingCommitSpan, SystemCallWarmer};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use reth_evm::{EthEvmFactory, EvmEnv, EvmFactory};
+use revm::{
+    context::CfgEnv,
+    database::{CacheDB, EmptyDB, State, WrapDatabaseRef},
+    primitives::hardfork::SpecId,
+    state::{AccountInfo, Bytecode},
+    DatabaseRef,
+};
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+
+/// Time each read of the backing database takes.
+const READ_LATENCY: Duration = Duration::from_micros(20);
+
+const RECORDER: Address = Address::new([0x42; 20]);
+const SPRINT_START: u64 = 6400;
+
+// slot[0] += 1; slot[slot[0]] = CALLER
+const RECORDER_CODE: &[u8] = &[
+    0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x33, 0x90, 0x55, 0x00,
+];
+
+/// In-memory database that waits [`READ_LATENCY`] on every read.
+#[derive(Debug)]
+struct SlowDb(CacheDB<EmptyDB>);
+
+fn wait() {
+    let started = Instant::now();
+    while started.elapsed() < READ_LATENCY {
+        std::hint::spin_loop();
+    }
+}
+
+impl DatabaseRef for SlowDb {
+    type Error = Infallible;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        wait();
+        self.0.basic_ref(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        wait();
+        self.0.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        wait();
+        self.0.storage_ref(address, index)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.0.block_hash_ref(number)
+    }
+}
+
+type SlowState = State<WrapDatabaseRef<SlowDb>>;
+
+/// Stub that calls the recorder with no arguments and stops.
+fn reporting_stub() -> Bytecode {
+    let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+    code.extend_from_slice(RECORDER.as_slice());
+    code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+    Bytecode::new_raw(code.into())
+}
+
+fn slow_state() -> SlowState {
+    let mut db = CacheDB::new(EmptyDB::default());
+    db.insert_account_info(
+        RECORDER,
+        AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(RECORDER_CODE))),
+    );
+    for contract in [BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS] {
+        db.insert_account_info(contract, AccountInfo::from_bytecode(reporting_stub()));
+    }
+    State::builder().with_database(WrapDatabaseRef(SlowDb(db))).with_bundle_update().build()
+}
+
+fn env() -> EvmEnv {
+    let mut env = EvmEnv::default();
+    env.cfg_env = CfgEnv::new().with_chain_id(137).with_spec_and_mainnet_gas_params(SpecId::LONDON);
+    env.block_env.number = U256::from(SPRINT_START);
+    env.block_env.gas_limit = 30_000_000;
+    env
+}
+
+fn sprint_ctx() -> BorExecutionCtx {
+    BorExecutionCtx {
+        pending_commit_span: Some(PendingCommitSpan {
+            span_id: U256::from(1),
+            validator_bytes: Bytes::from_static(&[0xc0]),
+        }),
+        pending_state_syncs: (1..=16u64).map(|id| (U256::from(id), Bytes::new())).collect(),
+        sprint_start: true,
+        ..Default::default()
+    }
+}
+
+fn system_calls(caller: &BorSystemCaller, ctx: &BorExecutionCtx, state: &mut SlowState) {
+    let mut evm = EthEvmFactory::default().create_evm(state, env());
+    caller.apply_sprint_boundary(&mut evm, ctx.sprint_context()).unwrap();
+}
+
+fn sprint_start(c: &mut Criterion) {
+    let caller = BorSystemCaller::new().with_warmer(SystemCallWarmer::new());
+    let ctx = sprint_ctx();
+    // Let the warmer record what the calls touch, as the previous sprint start would.
+    system_calls(&caller, &ctx, &mut slow_state());
+
+    let mut group = c.benchmark_group("sprint_start_system_calls");
+    group.bench_function("cold", |b| {
+        b.iter_batched(
+            slow_state,
+            |mut state| system_calls(&caller, &ctx, &mut state),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("warmed", |b| {
+        b.iter_batched(
+            || {
+                let mut state = slow_state();
+                caller.warm(&mut state, SPRINT_START);
+                state
+            },
+            |mut state| system_calls(&caller, &ctx, &mut state),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sprint_start);
+criterion_main!(benches);
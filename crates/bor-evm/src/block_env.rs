@@ -0,0 +1,167 @@
+//! The [`BlockEnv`] of Bor blocks.
+//!
+//! Bor never went through the merge: its headers keep the producer's difficulty
+//! (the in-turn bonus) and a zero mix hash, and opcode `0x44` returns that
+//! difficulty even under the Shanghai and later rules Polygon adopted. From the
+//! Merge spec on, revm answers `0x44` from `prevrandao` instead, so Bor sets it to
+//! the difficulty. The header's coinbase is zero as well; fees go to the signer
+//! (see [`block_fee_recipient`](crate::block_fee_recipient)).
+//!
+//! `COINBASE` (`0x41`) returns the same signer, never the zero header field: Bor's
+//! EVM context takes its coinbase from the consensus engine's author, at every fork.
+//! Execution, tracing and `eth_call` at a block all derive their environment from the
+//! sealed header, so they agree. Calls against the pending block have no signer yet
+//! and see whatever fee recipient the pending attributes suggest.
+//!
+//! Imported blocks ([`header_block_env`]) and blocks built locally
+//! ([`next_block_env`]) go through the same [`bor_block_env`].
+
+use alloy_consensus::Header;
+use alloy_primitives::{Address, B256, U256};
+use reth_evm::NextBlockEnvAttributes;
+use revm::{context::BlockEnv, primitives::hardfork::SpecId};
+
+/// Block-level values a Bor [`BlockEnv`] is derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorBlockEnvInput {
+    /// Block number.
+    pub number: u64,
+    /// Block timestamp in seconds.
+    pub timestamp: u64,
+    /// Address fees are paid to, i.e. the block's signer.
+    pub fee_recipient: Address,
+    /// Header difficulty: the validator set size minus the producer's succession.
+    pub difficulty: U256,
+    /// Block gas limit.
+    pub gas_limit: u64,
+    /// Base fee per gas.
+    pub base_fee: u64,
+}
+
+/// The [`BlockEnv`] of a block executed under `spec`.
+pub fn bor_block_env(input: BorBlockEnvInput, spec: SpecId) -> BlockEnv {
+    BlockEnv {
+        number: U256::from(input.number),
+        beneficiary: input.fee_recipient,
+        timestamp: U256::from(input.timestamp),
+        difficulty: input.difficulty,
+        prevrandao: (spec >= SpecId::MERGE).then(|| difficulty_word(input.difficulty)),
+        gas_limit: input.gas_limit,
+        basefee: input.base_fee,
+        // Polygon never enabled blobs.
+        blob_excess_gas_and_price: None,
+    }
+}
+
+/// The [`BlockEnv`] of an imported block whose fees go to `fee_recipient`.
+pub fn header_block_env(header: &Header, fee_recipient: Address, spec: SpecId) -> BlockEnv {
+    bor_block_env(
+        BorBlockEnvInput {
+            number: header.number,
+            timestamp: header.timestamp,
+            fee_recipient,
+            difficulty: header.difficulty,
+            gas_limit: header.gas_limit,
+            base_fee: header.base_fee_per_gas.unwrap_or_default(),
+        },
+        spec,
+    )
+}
+
+/// The [`BlockEnv`] of the block built on `parent` with `attributes`.
+///
+/// Bor payload attributes carry the producer's difficulty in `prev_randao`, as a big-endian
+/// word; see [`difficulty_word`]. The fee recipient is the local signer.
+pub fn next_block_env(
+    parent: &Header,
+    attributes: &NextBlockEnvAttributes,
+    base_fee: u64,
+    spec: SpecId,
+) -> BlockEnv {
+    bor_block_env(
+        BorBlockEnvInput {
+            number: parent.number.saturating_add(1),
+            timestamp: attributes.timestamp,
+            fee_recipient: attributes.suggested_fee_recipient,
+            difficulty: attributes.prev_randao.into(),
+            gas_limit: attributes.gas_limit,
+            base_fee,
+        },
+        spec,
+    )
+}
+
+/// `difficulty` as the 32-byte word opcode `0x44` returns.
+pub fn difficulty_word(difficulty: U256) -> B256 {
+    difficulty.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(difficulty: u64) -> BorBlockEnvInput {
+        BorBlockEnvInput {
+            number: 80_084_800,
+            timestamp: 1_750_000_000,
+            fee_recipient: Address::new([0xaa; 20]),
+            difficulty: U256::from(difficulty),
+            gas_limit: 45_000_000,
+            base_fee: 30_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_prevrandao_is_difficulty_from_merge_rules() {
+        let env = bor_block_env(input(7), SpecId::CANCUN);
+        assert_eq!(env.difficulty, U256::from(7));
+        assert_eq!(env.prevrandao, Some(B256::with_last_byte(7)));
+
+        let env = bor_block_env(input(7), SpecId::LONDON);
+        assert_eq!(env.prevrandao, None);
+    }
+
+    #[test]
+    fn test_next_block_matches_imported_block() {
+        let parent = Header { number: 99, ..Default::default() };
+        let header = Header {
+            number: 100,
+            timestamp: 1_234,
+            difficulty: U256::from(3),
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(25),
+            ..Default::default()
+        };
+        let fee_recipient = Address::new([0xbb; 20]);
+        let attributes = NextBlockEnvAttributes {
+            timestamp: 1_234,
+            suggested_fee_recipient: fee_recipient,
+            prev_randao: difficulty_word(U256::from(3)),
+            gas_limit: 30_000_000,
+            parent_beacon_block_root: None,
+            withdrawals: None,
+            extra_data: Default::default(),
+        };
+
+        assert_eq!(
+            next_block_env(&parent, &attributes, 25, SpecId::PRAGUE),
+            header_block_env(&header, fee_recipient, SpecId::PRAGUE)
+        );
+    }
+
+    #[test]
+    fn test_number_saturates() {
+        let parent = Header { number: u64::MAX, ..Default::default() };
+        let attributes = NextBlockEnvAttributes {
+            timestamp: 0,
+            suggested_fee_recipient: Address::ZERO,
+            prev_randao: B256::ZERO,
+            gas_limit: 0,
+            parent_beacon_block_root: None,
+            withdrawals: None,
+            extra_data: Default::default(),
+        };
+        let env = next_block_env(&parent, &attributes, 0, SpecId::LONDON);
+        assert_eq!(env.number, U256::from(u64::MAX));
+    }
+}
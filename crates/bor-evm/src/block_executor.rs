@@ -13,13 +13,57 @@
 //!    state sync events from Heimdall L1 are relayed to the StateReceiver
 //!    contract at `0x1001`.
 //!
+//! The base fee of each transaction is credited to the block's burnt contract as the
+//! transaction is committed; after the system calls, the block's `blockAlloc` accounts
+//! are set (see [`BorPostExecution`]).
+//!
 //! Both calls are executed as system calls from `SYSTEM_ADDRESS`
-//! (`0xffffFFFfFFffffffffffffffFfFFFfffFFFfFFfE`).
+//! (`0xffffFFFfFFffffffffffffffFfFFFfffFFFfFFfE`), in that order, by
+//! [`BorSystemCaller`]. The caller only knows the contracts it calls, with their
+//! upgrades at fork blocks, and the chain's `overrideStateSyncRecords`; the calls due
+//! in a block come in as a [`SprintContext`],
+//! so the factory keeps a single caller that every executor, including those of
+//! parallel payload builds, borrows. It never lets a call change the nonce of
+//! `SYSTEM_ADDRESS` or charge its balance, and debug builds check the account again
+//! once a block's calls are applied.
+//!
+//! Before the transactions of a block with system calls, a caller given a
+//! [`SystemCallWarmer`] loads the state the calls of the last sprint start touched.
+//! An executor given a [`SprintPresimulator`] commits the calls' state from a
+//! simulation run ahead of the block instead of running them, when it still holds.
+//! Finishing the first block of a sprint logs a [`SprintSummary`]; a caller given a
+//! [`StateSyncProfiler`] also records the cost of each `onStateReceive` call. One
+//! given a [`StateSyncFilter`] relays only the state sync events it lets through.
+//!
+//! An executor given a [`SprintWal`] marks the block there before its system calls run
+//! and once they are applied, so a node stopped in between can tell on restart which
+//! sprint-start blocks were rolled back. One given a [`StateSyncStore`] records the
+//! state sync events each block committed, and one given an [`ExecutionDiffRecorder`]
+//! the accounts each block touched and the gas of each of its transactions. One given
+//! the [`DeferredChecks`] of consensus fails the blocks held back there before their
+//! transactions, with an internal error reth retries.
 
-use crate::system_call::{CommitSpanCall, StateReceiveCall};
+use crate::{
+    execution_diff::{ExecutionDiff, ExecutionDiffRecorder},
+    post_execution::BorPostExecution,
+    presim::SprintPresimulator,
+    profile::{StateSyncProfile, StateSyncProfiler},
+    sprint_summary::{BlockProducer, SprintSummary, SystemCallTimings},
+    state_sync_filter::{SharedStateSyncFilter, StateSyncFilter},
+    system_call::{
+        check_system_account, pin_system_account, CommitSpanCall, StateReceiveCall,
+        COMMIT_SPAN_SELECTOR, ON_STATE_RECEIVE_SELECTOR,
+    },
+    warm::SystemCallWarmer,
+};
 use alloy_consensus::{Transaction, TransactionEnvelope, TxReceipt};
+use bor_consensus::{DeferredChecks, SharedDeferredChecks};
+use bor_storage::{
+    sprint_wal::{SharedSprintWal, SprintMarker, SprintStage, SprintWal},
+    state_syncs::{CommittedStateSync, SharedStateSyncStore, StateSyncStore},
+};
 use alloy_eips::Encodable2718;
-use alloy_primitives::{Bytes, Log, U256};
+use alloy_primitives::{Address, Bytes, Log, B256, U256};
 use reth_evm::{
     block::{
         BlockExecutionError, BlockExecutionResult, BlockExecutor, BlockExecutorFactory,
@@ -31,12 +75,24 @@ use reth_evm::{
     },
     Database, Evm, EvmFactory, FromRecoveredTx, FromTxWithEncoded,
 };
+use bor_chainspec::{
+    constants::{BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, SYSTEM_ADDRESS},
+    BorConfig, ContractUpgrade, ForkValue,
+};
 use core::fmt::Debug;
-use revm::{database::State, DatabaseCommit, Inspector};
-use tracing::debug;
+use revm::{
+    database::State,
+    state::{AccountInfo, EvmState},
+    Database as _, DatabaseCommit, Inspector,
+};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::Instant;
+use tracing::{debug, info, warn};
 
 /// Pending span commitment data for system call execution.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PendingCommitSpan {
     /// The span ID to commit.
     pub span_id: U256,
@@ -55,6 +111,50 @@ pub struct BorExecutionCtx {
     /// State sync events to relay via `onStateReceive` during finalization.
     /// Each entry is `(state_id, data)`.
     pub pending_state_syncs: Vec<(U256, Bytes)>,
+    /// Whether the block starts a sprint, and so gets a [`SprintSummary`] logged.
+    ///
+    /// Blocks with system calls are logged either way.
+    pub sprint_start: bool,
+    /// The producer of the block, if whoever built this context knows it.
+    pub producer: Option<BlockProducer>,
+    /// L1 contract that emitted each pending state sync event, by state ID, where known.
+    ///
+    /// Only passed on to the [`StateSyncStore`], which indexes events by contract.
+    pub state_sync_contracts: BTreeMap<u64, Address>,
+}
+
+impl BorExecutionCtx {
+    /// Returns `true` if the block gets a Bor receipt.
+    ///
+    /// Bor only writes the state sync receipt, and from Madhugiri the state sync transaction,
+    /// when the block commits at least one event. A sprint start without events, or a block
+    /// that only commits a span, has neither.
+    pub fn has_bor_receipt(&self) -> bool {
+        !self.pending_state_syncs.is_empty()
+    }
+
+    /// The system calls of this block, as [`BorSystemCaller`] takes them.
+    pub fn sprint_context(&self) -> SprintContext<'_> {
+        SprintContext {
+            commit_span: self.pending_commit_span.as_ref(),
+            state_syncs: &self.pending_state_syncs,
+        }
+    }
+}
+
+/// The Bor system calls due in one block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SprintContext<'a> {
+    /// Span to commit through `commitSpan`, if one is due.
+    pub commit_span: Option<&'a PendingCommitSpan>,
+    /// State sync events to relay through `onStateReceive`, as `(state_id, data)`.
+    pub state_syncs: &'a [(U256, Bytes)],
+}
+
+impl<'a> From<&'a BorExecutionCtx> for SprintContext<'a> {
+    fn from(ctx: &'a BorExecutionCtx) -> Self {
+        ctx.sprint_context()
+    }
 }
 
 /// Combined execution context for Bor block execution.
@@ -80,12 +180,37 @@ pub struct BorBlockExecutor<'a, E, Spec, R: ReceiptBuilder> {
     pub inner: EthBlockExecutor<'a, E, Spec, R>,
     /// Bor-specific execution context.
     pub bor_ctx: BorExecutionCtx,
+    /// Issues the system calls of `bor_ctx`, usually shared with the factory.
+    pub system_caller: &'a BorSystemCaller,
+    /// Post-execution changes of the chain, usually shared with the factory.
+    pub post_execution: &'a BorPostExecution,
+    /// Simulations of the system calls to reuse, if any.
+    pub presimulator: Option<&'a SprintPresimulator>,
+    /// Write-ahead markers of sprint-start blocks, if kept.
+    pub sprint_wal: Option<&'a SprintWal>,
+    /// Where the state sync events committed are recorded, if anywhere.
+    pub state_syncs: Option<&'a RwLock<dyn StateSyncStore>>,
+    /// Where the execution diff of the block is recorded, if anywhere.
+    pub execution_diffs: Option<&'a ExecutionDiffRecorder>,
+    /// Blocks consensus held back, which are failed rather than executed, if shared.
+    pub deferred_checks: Option<&'a DeferredChecks>,
+    /// Hash of the block's parent, which simulations are keyed by.
+    parent_hash: B256,
+    /// When execution of the block started.
+    started: Instant,
 }
 
 impl<E: Debug, Spec: Debug, R: ReceiptBuilder> Debug for BorBlockExecutor<'_, E, Spec, R> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("BorBlockExecutor")
             .field("bor_ctx", &self.bor_ctx)
+            .field("system_caller", self.system_caller)
+            .field("post_execution", self.post_execution)
+            .field("presimulator", &self.presimulator)
+            .field("sprint_wal", &self.sprint_wal)
+            .field("state_syncs", &self.state_syncs)
+            .field("execution_diffs", &self.execution_diffs)
+            .field("deferred_checks", &self.deferred_checks)
             .finish_non_exhaustive()
     }
 }
@@ -96,6 +221,10 @@ where
     R: ReceiptBuilder,
 {
     /// Create a new Bor block executor wrapping an Ethereum executor.
+    ///
+    /// System calls go to the canonical contracts and no post-execution changes are
+    /// made; see [`with_system_caller`](Self::with_system_caller) and
+    /// [`with_post_execution`](Self::with_post_execution) to change that.
     pub fn new(
         evm: E,
         eth_ctx: EthBlockExecutionCtx<'a>,
@@ -104,86 +233,507 @@ where
         receipt_builder: R,
     ) -> Self {
         Self {
+            parent_hash: eth_ctx.parent_hash,
             inner: EthBlockExecutor::new(evm, eth_ctx, spec, receipt_builder),
             bor_ctx,
+            system_caller: &DEFAULT_SYSTEM_CALLER,
+            post_execution: &DEFAULT_POST_EXECUTION,
+            presimulator: None,
+            sprint_wal: None,
+            state_syncs: None,
+            execution_diffs: None,
+            deferred_checks: None,
+            started: Instant::now(),
         }
     }
+
+    /// Issue the system calls through `system_caller`.
+    pub fn with_system_caller(mut self, system_caller: &'a BorSystemCaller) -> Self {
+        self.system_caller = system_caller;
+        self
+    }
+
+    /// Make the post-execution changes of `post_execution`.
+    pub fn with_post_execution(mut self, post_execution: &'a BorPostExecution) -> Self {
+        self.post_execution = post_execution;
+        self
+    }
+
+    /// Reuse the simulations of `presimulator` where they still hold.
+    pub fn with_presimulator(mut self, presimulator: &'a SprintPresimulator) -> Self {
+        self.presimulator = Some(presimulator);
+        self
+    }
+
+    /// Mark sprint-start blocks in `sprint_wal` while their system calls run.
+    pub fn with_sprint_wal(mut self, sprint_wal: &'a SprintWal) -> Self {
+        self.sprint_wal = Some(sprint_wal);
+        self
+    }
+
+    /// Record the state sync events committed by the block in `state_syncs`.
+    pub fn with_state_sync_store(mut self, state_syncs: &'a RwLock<dyn StateSyncStore>) -> Self {
+        self.state_syncs = Some(state_syncs);
+        self
+    }
+
+    /// Record the execution diff of the block in `execution_diffs`.
+    pub fn with_execution_diffs(mut self, execution_diffs: &'a ExecutionDiffRecorder) -> Self {
+        self.execution_diffs = Some(execution_diffs);
+        self
+    }
+
+    /// Fail the block if consensus held it back in `deferred_checks`.
+    pub fn with_deferred_checks(mut self, deferred_checks: &'a DeferredChecks) -> Self {
+        self.deferred_checks = Some(deferred_checks);
+        self
+    }
 }
 
-impl<'db, DB, E, Spec, R> BorBlockExecutor<'_, E, Spec, R>
-where
-    DB: Database + 'db,
-    E: Evm<
-        DB = &'db mut State<DB>,
-        Tx: FromRecoveredTx<R::Transaction> + FromTxWithEncoded<R::Transaction>,
-    >,
-    Spec: EthExecutorSpec,
-    R: ReceiptBuilder<Transaction: Transaction + Encodable2718>,
-{
-    /// Execute Bor system calls using the inner EVM.
+/// The caller of the canonical contracts, for executors not given one.
+static DEFAULT_SYSTEM_CALLER: BorSystemCaller = BorSystemCaller::new();
+
+/// No post-execution changes, for executors not given any.
+static DEFAULT_POST_EXECUTION: LazyLock<BorPostExecution> = LazyLock::new(Default::default);
+
+/// Number of state sync events to commit at given sprint starts, as in Bor's
+/// `overrideStateSyncRecords`.
+///
+/// A sprint start listed here commits at most that many of its events. A negative count
+/// skips the override, and the block commits all its events.
+pub type StateSyncRecordsOverride = BTreeMap<u64, i64>;
+
+/// The system contracts a block calls, and the functions it calls on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SystemContracts {
+    /// Address of the ValidatorSet contract.
+    pub validator_set: Address,
+    /// Address of the StateReceiver contract.
+    pub state_receiver: Address,
+    /// Selector of `commitSpan(uint256,bytes)` or its replacement.
+    pub commit_span_selector: [u8; 4],
+    /// Selector of `onStateReceive(uint256,bytes)` or its replacement.
+    pub on_state_receive_selector: [u8; 4],
+}
+
+impl SystemContracts {
+    /// The contracts of `self` with `upgrade` applied.
+    pub fn upgraded(self, upgrade: &ContractUpgrade) -> Self {
+        Self {
+            validator_set: upgrade.validator_contract.unwrap_or(self.validator_set),
+            state_receiver: upgrade.state_receiver_contract.unwrap_or(self.state_receiver),
+            commit_span_selector: upgrade
+                .commit_span_selector
+                .map_or(self.commit_span_selector, |selector| selector.0),
+            on_state_receive_selector: upgrade
+                .on_state_receive_selector
+                .map_or(self.on_state_receive_selector, |selector| selector.0),
+        }
+    }
+}
+
+/// Issues Bor's system calls.
+///
+/// Holds only the contracts involved, their upgrades and the chain's state sync
+/// overrides, never per-block state, so its methods take `&self` and one caller serves
+/// any number of executors at once. The [`SystemCallWarmer`], if any, is shared by them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorSystemCaller {
+    caller: Address,
+    contracts: SystemContracts,
+    upgrades: BTreeMap<u64, ContractUpgrade>,
+    state_sync_records: StateSyncRecordsOverride,
+    warmer: Option<SystemCallWarmer>,
+    profiler: Option<StateSyncProfiler>,
+    filter: Option<SharedStateSyncFilter>,
+}
+
+impl Default for BorSystemCaller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BorSystemCaller {
+    /// A caller of the canonical ValidatorSet and StateReceiver contracts.
+    pub const fn new() -> Self {
+        Self {
+            caller: SYSTEM_ADDRESS,
+            contracts: SystemContracts {
+                validator_set: BOR_VALIDATOR_SET_ADDRESS,
+                state_receiver: STATE_RECEIVER_ADDRESS,
+                commit_span_selector: COMMIT_SPAN_SELECTOR,
+                on_state_receive_selector: ON_STATE_RECEIVE_SELECTOR,
+            },
+            upgrades: BTreeMap::new(),
+            state_sync_records: BTreeMap::new(),
+            warmer: None,
+            profiler: None,
+            filter: None,
+        }
+    }
+
+    /// A caller of the contracts of a chain with `config`, with their `contractUpgrades`
+    /// and the chain's `overrideStateSyncRecords`.
     ///
-    /// Called during `finish()` before delegating to the Ethereum executor's
-    /// finalization logic. This ensures Bor state changes are committed to the
-    /// DB before balance increments and state root computation.
-    fn execute_bor_system_calls(&mut self) -> Result<(), BlockExecutionError> {
-        // 1. commitSpan — update validator set at span boundaries
-        if let Some(ref commit) = self.bor_ctx.pending_commit_span {
-            let call = CommitSpanCall {
-                span_id: commit.span_id,
-                validator_bytes: commit.validator_bytes.clone(),
-            };
+    /// Contracts `config` does not give are the canonical ones.
+    pub fn from_config(config: &BorConfig) -> Self {
+        let mut caller = Self::new()
+            .with_contract_upgrades(config.contract_upgrades.clone())
+            .with_state_sync_records_override(config.override_state_sync_records.clone());
+        if let Some(address) = config.validator_contract {
+            caller = caller.with_validator_set(address);
+        }
+        if let Some(address) = config.state_receiver_contract {
+            caller = caller.with_state_receiver(address);
+        }
+        caller
+    }
 
-            debug!(
-                target: "bor::executor",
-                span_id = %commit.span_id,
-                "executing commitSpan system call"
-            );
+    /// Send `commitSpan` to the ValidatorSet contract at `address`.
+    pub const fn with_validator_set(mut self, address: Address) -> Self {
+        self.contracts.validator_set = address;
+        self
+    }
 
-            let res = self
-                .inner
-                .evm
-                .transact_system_call(
-                    CommitSpanCall::caller(),
-                    CommitSpanCall::to_address(),
-                    call.call_data(),
-                )
-                .map_err(|e| BlockExecutionError::msg(format!("commitSpan failed: {e}")))?;
-
-            self.inner.evm.db_mut().commit(res.state);
+    /// Send `onStateReceive` to the StateReceiver contract at `address`.
+    pub const fn with_state_receiver(mut self, address: Address) -> Self {
+        self.contracts.state_receiver = address;
+        self
+    }
+
+    /// Apply each of `upgrades` to the contracts from its block on, as in the
+    /// `contractUpgrades` of a [`BorConfig`].
+    pub fn with_contract_upgrades(mut self, upgrades: ForkValue<ContractUpgrade>) -> Self {
+        self.upgrades = upgrades.iter().map(|(block, upgrade)| (block, *upgrade)).collect();
+        self
+    }
+
+    /// Record the state the system calls touch in `warmer`, and warm it before the next
+    /// block with system calls, see [`warm`](Self::warm).
+    pub fn with_warmer(mut self, warmer: SystemCallWarmer) -> Self {
+        self.warmer = Some(warmer);
+        self
+    }
+
+    /// The warmer the caller records to, if any.
+    pub const fn warmer(&self) -> Option<&SystemCallWarmer> {
+        self.warmer.as_ref()
+    }
+
+    /// Record the gas and storage writes of each `onStateReceive` call in `profiler`.
+    pub fn with_profiler(mut self, profiler: StateSyncProfiler) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// The profiler the caller records to, if any.
+    pub const fn profiler(&self) -> Option<&StateSyncProfiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Relay only the state sync events `filter` lets through, with the data it gives.
+    pub fn with_state_sync_filter(mut self, filter: impl StateSyncFilter + 'static) -> Self {
+        self.filter = Some(SharedStateSyncFilter::new(filter));
+        self
+    }
+
+    /// Load the state of the system calls of block `number` into `db`'s cache.
+    ///
+    /// Loads the contracts the block calls and whatever the calls of the last sprint
+    /// start touched. Does nothing without a warmer; a failed read is only logged, as
+    /// the calls themselves will read the state again.
+    pub fn warm<DB: Database>(&self, db: &mut State<DB>, number: u64) {
+        let Some(warmer) = &self.warmer else { return };
+        let contracts = self.contracts_at(number);
+        let accounts = [self.caller, contracts.validator_set, contracts.state_receiver];
+        match warmer.warm(db, &accounts) {
+            Ok(slots) => debug!(target: "bor::executor", number, slots, "warmed system call state"),
+            Err(err) => debug!(target: "bor::executor", number, %err, "failed to warm state"),
         }
+    }
 
-        // 2. onStateReceive — relay state sync events at sprint boundaries
-        for (state_id, data) in &self.bor_ctx.pending_state_syncs {
-            let call = StateReceiveCall {
-                state_id: *state_id,
-                data: data.clone(),
-            };
+    /// The contracts block `number` calls, with every upgrade up to it applied.
+    pub fn contracts_at(&self, number: u64) -> SystemContracts {
+        self.upgrades
+            .range(..=number)
+            .fold(self.contracts, |contracts, (_, upgrade)| contracts.upgraded(upgrade))
+    }
+
+    /// Commit only the number of state sync events `overrides` gives at its sprint starts.
+    pub fn with_state_sync_records_override(
+        mut self,
+        overrides: StateSyncRecordsOverride,
+    ) -> Self {
+        self.state_sync_records = overrides;
+        self
+    }
+
+    /// The state sync events that block `number` commits out of `events`.
+    ///
+    /// Without an override for the block these are all of `events`.
+    pub fn state_syncs_at<'e>(
+        &self,
+        number: u64,
+        events: &'e [(U256, Bytes)],
+    ) -> &'e [(U256, Bytes)] {
+        match self.state_sync_records.get(&number).and_then(|&n| usize::try_from(n).ok()) {
+            Some(n) => &events[..n.min(events.len())],
+            None => events,
+        }
+    }
+
+    /// The state sync events that block `number` relays out of `events`: those of
+    /// [`state_syncs_at`](Self::state_syncs_at), as the filter, if any, leaves them.
+    pub fn relayed_state_syncs<'e>(
+        &self,
+        number: u64,
+        events: &'e [(U256, Bytes)],
+    ) -> Cow<'e, [(U256, Bytes)]> {
+        let events = self.state_syncs_at(number, events);
+        match &self.filter {
+            Some(filter) => Cow::Owned(filter.apply(number, events)),
+            None => Cow::Borrowed(events),
+        }
+    }
+
+    /// Address the system calls are made from.
+    pub const fn caller(&self) -> Address {
+        self.caller
+    }
+
+    /// Address of the ValidatorSet contract at genesis.
+    pub const fn validator_set(&self) -> Address {
+        self.contracts.validator_set
+    }
+
+    /// Address of the StateReceiver contract at genesis.
+    pub const fn state_receiver(&self) -> Address {
+        self.contracts.state_receiver
+    }
 
-            debug!(
+    /// Execute the system calls of `ctx` on `evm` and commit their state.
+    ///
+    /// This is the only place the calls are issued, so their order is fixed here and
+    /// matches Bor's `Finalize`: a due `commitSpan` first, so that the new span's
+    /// validators are in the ValidatorSet contract, then one `onStateReceive` per state
+    /// sync event in ID order. Blocks without pending calls leave the state untouched.
+    /// Where the chain overrides the number of state sync records, only that many of
+    /// the events are committed, and a filter may drop or rewrite those, see
+    /// [`relayed_state_syncs`](Self::relayed_state_syncs).
+    ///
+    /// Returns the time spent in each kind of call.
+    pub fn apply_sprint_boundary<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        ctx: SprintContext<'_>,
+    ) -> Result<SystemCallTimings, BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let mut timings = SystemCallTimings::default();
+        if let Some(commit) = ctx.commit_span {
+            let started = Instant::now();
+            self.commit_span(evm, commit)?;
+            timings.commit_span = started.elapsed();
+        }
+        let number = evm.block().number.saturating_to::<u64>();
+        let state_syncs = self.relayed_state_syncs(number, ctx.state_syncs);
+        if state_syncs.len() < ctx.state_syncs.len() {
+            info!(
                 target: "bor::executor",
-                state_id = %state_id,
-                "executing onStateReceive system call"
+                number,
+                committed = state_syncs.len(),
+                fetched = ctx.state_syncs.len(),
+                "state sync records overridden or filtered"
             );
+        }
+        let started = Instant::now();
+        for (state_id, data) in state_syncs.iter() {
+            self.on_state_receive(evm, *state_id, data)?;
+        }
+        timings.state_syncs = started.elapsed();
+        Ok(timings)
+    }
 
-            let res = self
-                .inner
-                .evm
-                .transact_system_call(
-                    StateReceiveCall::caller(),
-                    StateReceiveCall::to_address(),
-                    call.call_data(),
-                )
-                .map_err(|e| {
-                    BlockExecutionError::msg(format!(
-                        "onStateReceive failed for state_id {state_id}: {e}"
-                    ))
-                })?;
-
-            self.inner.evm.db_mut().commit(res.state);
+    /// Execute the system calls of `ctx` on `evm` and return the state each call left.
+    ///
+    /// The calls run as in [`apply_sprint_boundary`](Self::apply_sprint_boundary), each
+    /// committed before the next, so committing the returned states in order to another
+    /// database over the same state reproduces the block's system calls. That is how a
+    /// [`SprintPresimulator`] runs them ahead of the block.
+    pub fn simulate_sprint_boundary<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        ctx: SprintContext<'_>,
+    ) -> Result<Vec<EvmState>, BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let mut states = Vec::new();
+        if let Some(commit) = ctx.commit_span {
+            states.push(self.commit_span_state(evm, commit)?);
+        }
+        let number = evm.block().number.saturating_to::<u64>();
+        for (state_id, data) in self.relayed_state_syncs(number, ctx.state_syncs).iter() {
+            states.push(self.on_state_receive_state(evm, *state_id, data)?);
+        }
+        for state in &states {
+            evm.db_mut().commit(state.clone());
         }
+        Ok(states)
+    }
 
+    /// Commit the validators of `commit` to the ValidatorSet contract.
+    pub fn commit_span<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        commit: &PendingCommitSpan,
+    ) -> Result<(), BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let state = self.commit_span_state(evm, commit)?;
+        evm.db_mut().commit(state);
         Ok(())
     }
+
+    /// Run `commitSpan` for `commit` and return its state, uncommitted.
+    fn commit_span_state<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        commit: &PendingCommitSpan,
+    ) -> Result<EvmState, BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let call = CommitSpanCall {
+            span_id: commit.span_id,
+            validator_bytes: commit.validator_bytes.clone(),
+        };
+        let number = evm.block().number.saturating_to();
+        let contracts = self.contracts_at(number);
+
+        debug!(
+            target: "bor::executor",
+            span_id = %commit.span_id,
+            "executing commitSpan system call"
+        );
+
+        let mut res = evm
+            .transact_system_call(
+                self.caller,
+                contracts.validator_set,
+                call.call_data_with_selector(contracts.commit_span_selector),
+            )
+            .map_err(|e| BlockExecutionError::msg(format!("commitSpan failed: {e}")))?;
+        self.pin_caller(evm.db_mut(), &mut res.state)?;
+
+        if let Some(warmer) = &self.warmer {
+            warmer.record(number, &res.state);
+        }
+        Ok(res.state)
+    }
+
+    /// Relay state sync event `state_id` to the StateReceiver contract.
+    pub fn on_state_receive<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        state_id: U256,
+        data: &Bytes,
+    ) -> Result<(), BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let state = self.on_state_receive_state(evm, state_id, data)?;
+        evm.db_mut().commit(state);
+        Ok(())
+    }
+
+    /// Run `onStateReceive` for event `state_id` and return its state, uncommitted.
+    fn on_state_receive_state<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        state_id: U256,
+        data: &Bytes,
+    ) -> Result<EvmState, BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let call = StateReceiveCall { state_id, data: data.clone() };
+        let number = evm.block().number.saturating_to();
+        let contracts = self.contracts_at(number);
+
+        debug!(
+            target: "bor::executor",
+            state_id = %state_id,
+            "executing onStateReceive system call"
+        );
+
+        let started = Instant::now();
+        let mut res = evm
+            .transact_system_call(
+                self.caller,
+                contracts.state_receiver,
+                call.call_data_with_selector(contracts.on_state_receive_selector),
+            )
+            .map_err(|e| {
+                BlockExecutionError::msg(format!(
+                    "onStateReceive failed for state_id {state_id}: {e}"
+                ))
+            })?;
+        self.pin_caller(evm.db_mut(), &mut res.state)?;
+
+        if let Some(profiler) = &self.profiler {
+            profiler.record(StateSyncProfile::new(
+                number,
+                state_id.saturating_to(),
+                &res.result,
+                &res.state,
+                started.elapsed(),
+            ));
+        }
+        if let Some(warmer) = &self.warmer {
+            warmer.record(number, &res.state);
+        }
+        Ok(res.state)
+    }
+
+    /// Undo any change a system call made in `state` to the caller's nonce, and any
+    /// charge to its balance, see [`pin_system_account`].
+    fn pin_caller<DB: Database>(
+        &self,
+        db: &mut State<DB>,
+        state: &mut EvmState,
+    ) -> Result<(), BlockExecutionError> {
+        let Some(account) = state.get_mut(&self.caller) else { return Ok(()) };
+        // The call's state is not committed yet, so this is the account before it.
+        let before = system_account_at(db, self.caller)?;
+        if pin_system_account(&mut account.info, before.as_ref()) {
+            warn!(target: "bor::executor", caller = %self.caller, "undid system caller change");
+        }
+        Ok(())
+    }
+}
+
+/// Execute the Bor system calls of `ctx` on `evm` against the canonical contracts.
+///
+/// Shorthand for [`BorSystemCaller::apply_sprint_boundary`] with the default caller.
+pub fn apply_sprint_boundary<'db, DB, E>(
+    evm: &mut E,
+    ctx: &BorExecutionCtx,
+) -> Result<SystemCallTimings, BlockExecutionError>
+where
+    DB: Database + 'db,
+    E: Evm<DB = &'db mut State<DB>>,
+{
+    DEFAULT_SYSTEM_CALLER.apply_sprint_boundary(evm, ctx.sprint_context())
 }
 
 impl<'db, DB, E, Spec, R> BlockExecutor for BorBlockExecutor<'_, E, Spec, R>
@@ -206,7 +756,19 @@ where
         EthTxResult<E::HaltReason, <R::Transaction as TransactionEnvelope>::TxType>;
 
     fn apply_pre_execution_changes(&mut self) -> Result<(), BlockExecutionError> {
-        self.inner.apply_pre_execution_changes()
+        if let Some(deferred) = self.deferred_checks {
+            let number = self.inner.evm.block().number.saturating_to();
+            if let Some(gap) = deferred.take(number, self.parent_hash) {
+                return Err(BlockExecutionError::msg(gap.deferred(number)));
+            }
+        }
+        self.inner.apply_pre_execution_changes()?;
+        let ctx = self.bor_ctx.sprint_context();
+        if ctx.commit_span.is_some() || !ctx.state_syncs.is_empty() {
+            let number = self.inner.evm.block().number.saturating_to();
+            self.system_caller.warm(self.inner.evm.db_mut(), number);
+        }
+        Ok(())
     }
 
     fn execute_transaction_without_commit(
@@ -217,7 +779,9 @@ where
     }
 
     fn commit_transaction(&mut self, output: Self::Result) -> Result<u64, BlockExecutionError> {
-        self.inner.commit_transaction(output)
+        let gas_used = self.inner.commit_transaction(output)?;
+        self.post_execution.credit_base_fee(&mut self.inner.evm, gas_used)?;
+        Ok(gas_used)
     }
 
     fn finish(
@@ -225,14 +789,105 @@ where
     ) -> Result<(Self::Evm, BlockExecutionResult<R::Receipt>), BlockExecutionError> {
         // Execute Bor system calls BEFORE Ethereum's finish() handles
         // balance increments. This matches Go Bor's Finalize ordering:
-        // user txs → commitSpan → onStateReceive → balance increments
-        self.execute_bor_system_calls()?;
+        // user txs → commitSpan → onStateReceive → blockAlloc → balance increments
+        let ctx = self.bor_ctx.sprint_context();
+        let has_calls = ctx.commit_span.is_some() || !ctx.state_syncs.is_empty();
+        let id = |id: &U256| id.saturating_to::<u64>();
+        let number = self.inner.evm.block().number.saturating_to();
+        let applied = self.system_caller.relayed_state_syncs(number, ctx.state_syncs);
+        let state_syncs = applied
+            .first()
+            .zip(applied.last())
+            .map(|((first, _), (last, _))| id(first)..=id(last));
+        let span_id = ctx.commit_span.map(|commit| id(&commit.span_id));
+
+        let wal = self.sprint_wal.filter(|_| has_calls);
+        if let Some(wal) = wal {
+            let marker = SprintMarker {
+                number,
+                parent_hash: self.parent_hash,
+                span_id,
+                first_state_id: state_syncs.as_ref().map(|ids| *ids.start()),
+                last_state_id: state_syncs.as_ref().map(|ids| *ids.end()),
+                stage: SprintStage::Executing,
+            };
+            log_wal_error(number, wal.begin(marker));
+        }
+        let caller = self.system_caller.caller();
+        let system_account = if cfg!(debug_assertions) && has_calls {
+            Some(system_account_at(self.inner.evm.db_mut(), caller)?)
+        } else {
+            None
+        };
+        let presimulated = self
+            .presimulator
+            .filter(|_| has_calls)
+            .and_then(|presim| presim.apply(&mut self.inner.evm, self.parent_hash, ctx));
+        let timings = match presimulated {
+            Some(timings) => Ok(timings),
+            None => self.system_caller.apply_sprint_boundary(&mut self.inner.evm, ctx),
+        };
+        if let Some(wal) = wal {
+            let written = match &timings {
+                Ok(_) => wal.executed(number, self.parent_hash),
+                Err(_) => wal.abort(number, self.parent_hash),
+            };
+            log_wal_error(number, written);
+        }
+        let timings = timings?;
+        if let Some(before) = system_account {
+            let after = system_account_at(self.inner.evm.db_mut(), caller)?;
+            check_system_account(before.as_ref(), after.as_ref())
+                .map_err(|e| BlockExecutionError::msg(format!("block {number}: {e}")))?;
+        }
+        // Only blocks with a Bor receipt get a state sync transaction to look up. A filter
+        // may still drop every event, which the store skips as well.
+        if let Some(store) = self.state_syncs.filter(|_| self.bor_ctx.has_bor_receipt()) {
+            let events = applied
+                .iter()
+                .map(|(id, data)| {
+                    let id = id.saturating_to();
+                    let contract = self.bor_ctx.state_sync_contracts.get(&id).copied();
+                    CommittedStateSync { id, contract, data: data.clone() }
+                })
+                .collect();
+            store.write().expect("state sync store lock poisoned").record_executed(
+                number,
+                self.parent_hash,
+                events,
+            );
+        }
+        self.post_execution.apply_block_alloc(&mut self.inner.evm)?;
+
+        if self.bor_ctx.sprint_start || has_calls {
+            SprintSummary {
+                number,
+                producer: self.bor_ctx.producer,
+                state_syncs,
+                span_id,
+                system_calls: timings,
+                execution: self.started.elapsed(),
+            }
+            .log();
+        }
 
         // Delegate to Ethereum's finish for:
         // - Prague requests (no-op on Bor)
         // - Balance increments (no-op on Bor: no ommers, no withdrawals)
         // - DAO fork (no-op on Bor)
-        self.inner.finish()
+        let (execution_diffs, parent_hash) = (self.execution_diffs, self.parent_hash);
+        let (mut evm, result) = self.inner.finish()?;
+        if let Some(recorder) = execution_diffs {
+            // The block's changes stay in the transition state until reth merges them.
+            let transitions = evm.db_mut().transition_state.as_ref().map(|t| &t.transitions);
+            recorder.record(ExecutionDiff::new(
+                number,
+                parent_hash,
+                &result.receipts,
+                transitions.into_iter().flatten(),
+            ));
+        }
+        Ok((evm, result))
     }
 
     fn set_state_hook(&mut self, hook: Option<Box<dyn OnStateHook>>) {
@@ -252,14 +907,47 @@ where
     }
 }
 
+/// The account of the system caller `address` in `db`, with the block's changes so far.
+fn system_account_at<DB: Database>(
+    db: &mut State<DB>,
+    address: Address,
+) -> Result<Option<AccountInfo>, BlockExecutionError> {
+    db.basic(address)
+        .map_err(|e| BlockExecutionError::msg(format!("system caller read failed: {e}")))
+}
+
+/// Warn about a failed write of the sprint markers.
+///
+/// The markers only report on what a restart rolled back, so a failed write must not
+/// fail the block.
+fn log_wal_error(number: u64, written: std::io::Result<()>) {
+    if let Err(err) = written {
+        warn!(target: "bor::executor", number, %err, "failed to write sprint marker");
+    }
+}
+
 /// Factory for creating [`BorBlockExecutor`] instances.
 ///
 /// Wraps [`EthBlockExecutorFactory`] and constructs executors with Bor-specific
-/// execution context.
+/// execution context. Every executor borrows the factory's [`BorSystemCaller`].
 #[derive(Debug)]
 pub struct BorBlockExecutorFactory<R, Spec, EvmFactory> {
     /// Inner Ethereum factory.
     inner: EthBlockExecutorFactory<R, Spec, EvmFactory>,
+    /// System caller shared by all executors.
+    system_caller: BorSystemCaller,
+    /// Post-execution changes shared by all executors.
+    post_execution: BorPostExecution,
+    /// Simulations of the system calls shared by all executors, if enabled.
+    presimulator: Option<SprintPresimulator>,
+    /// Write-ahead markers of sprint-start blocks, if kept.
+    sprint_wal: Option<SharedSprintWal>,
+    /// Where executors record committed state sync events, if anywhere.
+    state_syncs: Option<SharedStateSyncStore>,
+    /// Where executors record the execution diff of each block, if anywhere.
+    execution_diffs: Option<ExecutionDiffRecorder>,
+    /// Blocks consensus held back, if shared with it.
+    deferred_checks: Option<SharedDeferredChecks>,
 }
 
 impl<R: Clone, Spec: Clone, EvmF: Clone> Clone for BorBlockExecutorFactory<R, Spec, EvmF> {
@@ -270,14 +958,97 @@ impl<R: Clone, Spec: Clone, EvmF: Clone> Clone for BorBlockExecutorFactory<R, Sp
                 self.inner.spec().clone(),
                 self.inner.evm_factory().clone(),
             ),
+            system_caller: self.system_caller.clone(),
+            post_execution: self.post_execution.clone(),
+            presimulator: self.presimulator.clone(),
+            sprint_wal: self.sprint_wal.clone(),
+            state_syncs: self.state_syncs.clone(),
+            execution_diffs: self.execution_diffs.clone(),
+            deferred_checks: self.deferred_checks.clone(),
         }
     }
 }
 
 impl<R, Spec, EvmFactory> BorBlockExecutorFactory<R, Spec, EvmFactory> {
     /// Create a new Bor block executor factory.
-    pub const fn new(inner: EthBlockExecutorFactory<R, Spec, EvmFactory>) -> Self {
-        Self { inner }
+    pub fn new(inner: EthBlockExecutorFactory<R, Spec, EvmFactory>) -> Self {
+        Self {
+            inner,
+            system_caller: BorSystemCaller::new(),
+            post_execution: Default::default(),
+            presimulator: None,
+            sprint_wal: None,
+            state_syncs: None,
+            execution_diffs: None,
+            deferred_checks: None,
+        }
+    }
+
+    /// Issue system calls through `system_caller`.
+    pub fn with_system_caller(mut self, system_caller: BorSystemCaller) -> Self {
+        self.system_caller = system_caller;
+        self
+    }
+
+    /// Make the post-execution changes of `post_execution` in every block.
+    pub fn with_post_execution(mut self, post_execution: BorPostExecution) -> Self {
+        self.post_execution = post_execution;
+        self
+    }
+
+    /// Let executors reuse the system call simulations of `presimulator`.
+    pub fn with_presimulator(mut self, presimulator: SprintPresimulator) -> Self {
+        self.presimulator = Some(presimulator);
+        self
+    }
+
+    /// Let executors mark sprint-start blocks in `sprint_wal`.
+    pub fn with_sprint_wal(mut self, sprint_wal: SharedSprintWal) -> Self {
+        self.sprint_wal = Some(sprint_wal);
+        self
+    }
+
+    /// Let executors record the state sync events of each block in `state_syncs`.
+    pub fn with_state_sync_store(mut self, state_syncs: SharedStateSyncStore) -> Self {
+        self.state_syncs = Some(state_syncs);
+        self
+    }
+
+    /// Let executors record the execution diff of each block in `execution_diffs`.
+    pub fn with_execution_diffs(mut self, execution_diffs: ExecutionDiffRecorder) -> Self {
+        self.execution_diffs = Some(execution_diffs);
+        self
+    }
+
+    /// Let executors fail the blocks consensus held back in `deferred_checks`.
+    pub fn with_deferred_checks(mut self, deferred_checks: SharedDeferredChecks) -> Self {
+        self.deferred_checks = Some(deferred_checks);
+        self
+    }
+
+    /// Returns the recorder of execution diffs shared by the executors, if any.
+    pub const fn execution_diffs(&self) -> Option<&ExecutionDiffRecorder> {
+        self.execution_diffs.as_ref()
+    }
+
+    /// Returns the sprint markers shared by the executors, if any.
+    pub fn sprint_wal(&self) -> Option<&SprintWal> {
+        self.sprint_wal.as_deref()
+    }
+
+    /// Returns the presimulator shared by the executors, if any.
+    pub const fn presimulator(&self) -> Option<&SprintPresimulator> {
+        self.presimulator.as_ref()
+    }
+
+    /// Returns the post-execution changes shared by the executors.
+    pub const fn post_execution(&self) -> &BorPostExecution {
+        &self.post_execution
+    }
+
+    /// Returns the system caller shared by the executors.
+    pub const fn system_caller(&self) -> &BorSystemCaller {
+        &self.system_caller
     }
 
     /// Returns the inner Ethereum factory.
@@ -314,12 +1085,34 @@ where
         DB: Database + 'a,
         I: Inspector<EvmF::Context<&'a mut State<DB>>> + 'a,
     {
-        BorBlockExecutor::new(
+        let executor = BorBlockExecutor::new(
             evm,
             ctx.eth,
             ctx.bor,
             self.inner.spec(),
             self.inner.receipt_builder(),
         )
+        .with_system_caller(&self.system_caller)
+        .with_post_execution(&self.post_execution);
+        let executor = match &self.presimulator {
+            Some(presimulator) => executor.with_presimulator(presimulator),
+            None => executor,
+        };
+        let executor = match &self.sprint_wal {
+            Some(sprint_wal) => executor.with_sprint_wal(sprint_wal),
+            None => executor,
+        };
+        let executor = match &self.state_syncs {
+            Some(state_syncs) => executor.with_state_sync_store(&**state_syncs),
+            None => executor,
+        };
+        let executor = match &self.execution_diffs {
+            Some(execution_diffs) => executor.with_execution_diffs(execution_diffs),
+            None => executor,
+        };
+        match &self.deferred_checks {
+            Some(deferred_checks) => executor.with_deferred_checks(deferred_checks),
+            None => executor,
+        }
     }
 }
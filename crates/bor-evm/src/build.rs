@@ -10,6 +10,7 @@ use alloy_consensus::{
     Block, BlockBody, Header, TxReceipt, EMPTY_OMMER_ROOT_HASH,
 };
 use alloy_eips::merge::BEACON_NONCE;
+use alloy_primitives::{Address, B256};
 use reth_chainspec::{EthChainSpec, EthereumHardforks};
 use reth_evm::{
     block::{BlockExecutionResult, BlockExecutorFactory},
@@ -83,21 +84,23 @@ where
         let header = Header {
             parent_hash: ctx.parent_hash,
             ommers_hash: EMPTY_OMMER_ROOT_HASH,
-            beneficiary: evm_env.block_env.beneficiary(),
+            // Bor pays fees to the signer but leaves the beneficiary empty.
+            beneficiary: Address::ZERO,
             state_root,
             transactions_root,
             receipts_root,
             withdrawals_root,
             logs_bloom,
             timestamp,
-            mix_hash: evm_env.block_env.prevrandao().unwrap_or_default(),
+            // Bor keeps a zero mix hash; `prevrandao` holds the difficulty.
+            mix_hash: B256::ZERO,
             nonce: BEACON_NONCE.into(),
             base_fee_per_gas: Some(evm_env.block_env.basefee()),
             number: evm_env.block_env.number().saturating_to(),
             gas_limit: evm_env.block_env.gas_limit(),
             difficulty: evm_env.block_env.difficulty(),
             gas_used: *gas_used,
-            extra_data: Default::default(),
+            extra_data: ctx.extra_data.clone(),
             parent_beacon_block_root: None,
             blob_gas_used,
             excess_blob_gas,
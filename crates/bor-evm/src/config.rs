@@ -1,7 +1,8 @@
 //! Bor EVM configuration with fork-aware precompile sets.
 
 use alloy_primitives::{Address, address};
-use bor_chainspec::BorHardfork;
+use bor_chainspec::{BorHardforks, MainnetBorHardforks};
+use revm::primitives::hardfork::SpecId;
 
 /// P256VERIFY precompile address, added at the Napoli hardfork.
 pub const P256_VERIFY_ADDRESS: Address = address!("0000000000000000000000000000000000000100");
@@ -13,12 +14,36 @@ const KZG_ADDRESS: Address = address!("000000000000000000000000000000000000000a"
 /// BorEvmConfig holds chain spec for fork-aware EVM configuration.
 pub struct BorEvmConfig;
 
+/// Returns the revm spec that applies at `block`.
+///
+/// Polygon enables the Ethereum forks by block number through its own hardforks: Agra
+/// brings the Shanghai rules, Napoli the Cancun rules (transient storage, `MCOPY`, the
+/// EIP-6780 `SELFDESTRUCT` change, but no blobs) and Bhilai the Prague rules. `eth_spec`
+/// is the spec implied by the chain's Ethereum fork schedule; the later of the two wins.
+pub fn bor_spec_id(forks: &impl BorHardforks, block: u64, eth_spec: SpecId) -> SpecId {
+    let bor_spec = if forks.is_bhilai_active_at_block(block) {
+        SpecId::PRAGUE
+    } else if forks.is_napoli_active_at_block(block) {
+        SpecId::CANCUN
+    } else if forks.is_agra_active_at_block(block) {
+        SpecId::SHANGHAI
+    } else {
+        return eth_spec;
+    };
+    if eth_spec > bor_spec { eth_spec } else { bor_spec }
+}
+
 /// Returns the set of active precompile addresses at a given mainnet block number.
+pub fn bor_precompile_addresses(block: u64) -> Vec<Address> {
+    bor_precompile_addresses_for(&MainnetBorHardforks, block)
+}
+
+/// Returns the set of active precompile addresses at `block` under the given fork schedule.
 ///
 /// Standard precompiles (0x01-0x09) are always present.
 /// Post-Napoli: adds P256VERIFY at 0x100.
 /// KZG (0x0a) is never included on Bor.
-pub fn bor_precompile_addresses(block: u64) -> Vec<Address> {
+pub fn bor_precompile_addresses_for(forks: &impl BorHardforks, block: u64) -> Vec<Address> {
     let mut addrs: Vec<Address> = (1u64..=9)
         .map(|i| {
             let mut bytes = [0u8; 20];
@@ -27,7 +52,7 @@ pub fn bor_precompile_addresses(block: u64) -> Vec<Address> {
         })
         .collect();
 
-    if block >= BorHardfork::Napoli.mainnet_block() {
+    if forks.is_napoli_active_at_block(block) {
         addrs.push(P256_VERIFY_ADDRESS);
     }
 
@@ -37,6 +62,7 @@ pub fn bor_precompile_addresses(block: u64) -> Vec<Address> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bor_chainspec::{AmoyBorHardforks, BorHardfork};
 
     #[test]
     fn test_precompiles_pre_napoli() {
@@ -72,4 +98,28 @@ mod tests {
         let far_future = bor_precompile_addresses(u64::MAX);
         assert!(!far_future.contains(&KZG_ADDRESS));
     }
+
+    #[test]
+    fn test_spec_id_follows_bor_forks() {
+        let forks = MainnetBorHardforks;
+        let agra = BorHardfork::Agra.mainnet_block();
+        let napoli = BorHardfork::Napoli.mainnet_block();
+        let bhilai = BorHardfork::Bhilai.mainnet_block();
+
+        assert_eq!(bor_spec_id(&forks, agra - 1, SpecId::LONDON), SpecId::LONDON);
+        assert_eq!(bor_spec_id(&forks, agra, SpecId::LONDON), SpecId::SHANGHAI);
+        assert_eq!(bor_spec_id(&forks, napoli - 1, SpecId::LONDON), SpecId::SHANGHAI);
+        assert_eq!(bor_spec_id(&forks, napoli, SpecId::LONDON), SpecId::CANCUN);
+        assert_eq!(bor_spec_id(&forks, bhilai, SpecId::LONDON), SpecId::PRAGUE);
+
+        // A later spec from the Ethereum schedule is kept.
+        assert_eq!(bor_spec_id(&forks, agra, SpecId::CANCUN), SpecId::CANCUN);
+    }
+
+    #[test]
+    fn test_precompiles_follow_schedule() {
+        let napoli = BorHardfork::Napoli.amoy_block();
+        assert_eq!(bor_precompile_addresses_for(&AmoyBorHardforks, napoli - 1).len(), 9);
+        assert!(bor_precompile_addresses_for(&AmoyBorHardforks, napoli).contains(&P256_VERIFY_ADDRESS));
+    }
 }
@@ -2,14 +2,44 @@
 //!
 //! This wires the custom [`BorBlockExecutorFactory`] into Reth's execution
 //! pipeline, enabling Bor-specific system calls during block finalization.
+//!
+//! The revm spec of a block follows the Bor hardfork schedule (see
+//! [`bor_spec_id`]), and blob transactions are disabled at every fork.
+//!
+//! The `commitSpan` and `onStateReceive` calls of a block are read from the
+//! [`SprintContextSource`](crate::SprintContextSource) given to
+//! [`BorEvmConfig::with_sprint_context`]; without one, blocks execute without system calls.
+//!
+//! Blocks built on a parent, by the payload builder or for the pending block, move their
+//! gas limit toward `--miner.gaslimit` if set and the chain's block gas limit otherwise,
+//! whatever limit reth's builder config asked for.
+//!
+//! EVMs are created by [`EthEvmFactory`] unless another [`BorEvmFactory`] is given to
+//! [`BorEvmConfig::new_with_custom_factory`], e.g. one adding precompiles or opcodes
+//! for a private chain. Executors, system calls and payload builds all use it.
 
-use crate::block_executor::{BorBlockExecutionCtx, BorBlockExecutorFactory, BorExecutionCtx};
+use crate::block_executor::{
+    BorBlockExecutionCtx, BorBlockExecutorFactory, BorExecutionCtx, BorSystemCaller,
+};
+use crate::block_env::{
+    bor_block_env, header_block_env, next_block_env, BorBlockEnvInput,
+};
 use crate::build::BorBlockAssembler;
+use crate::execution_diff::ExecutionDiffRecorder;
+use crate::config::bor_spec_id;
+use crate::post_execution::BorPostExecution;
+use crate::presim::SprintPresimulator;
+use crate::sprint_context::{SharedSprintContextSource, SprintContextError};
 use alloy_consensus::Header;
 use alloy_eips::Decodable2718;
-use alloy_primitives::{Bytes, U256};
+use alloy_primitives::{Address, Bytes, U256};
 use alloy_rpc_types_engine::ExecutionData;
-use core::{convert::Infallible, fmt::Debug};
+use bor_chainspec::{constants::EXTRADATA_SEAL_LEN, BorHardforks};
+use bor_consensus::{
+    compute_seal_hash, ecrecover_seal, gas_limit_target, next_gas_limit, SharedDeferredChecks,
+};
+use bor_storage::{sprint_wal::SharedSprintWal, state_syncs::SharedStateSyncStore};
+use core::fmt::Debug;
 use reth_chainspec::{EthChainSpec, EthereumHardforks};
 use reth_ethereum_primitives::{EthPrimitives, TransactionSigned};
 use reth_evm::{
@@ -27,10 +57,71 @@ use reth_evm_ethereum::{revm_spec_by_timestamp_and_block_number, RethReceiptBuil
 use reth_primitives_traits::{constants::MAX_TX_GAS_LIMIT_OSAKA, SealedBlock, SealedHeader, SignedTransaction, TxTy};
 use reth_storage_errors::any::AnyError;
 use revm::context::{BlockEnv, CfgEnv};
-use revm::context_interface::block::BlobExcessGasAndPrice;
 use revm::primitives::hardfork::SpecId;
 use std::borrow::Cow;
 use std::sync::Arc;
+use tracing::debug;
+
+/// An [`EvmFactory`] [`BorEvmConfig`] can create the EVMs of Bor blocks with.
+///
+/// Implemented for every factory of Ethereum transactions over revm's mainnet spec and
+/// block environment, so a custom factory only needs to wrap [`EthEvmFactory`] and
+/// extend its [`PrecompilesMap`] or instructions.
+pub trait BorEvmFactory:
+    EvmFactory<
+        Tx: TransactionEnv
+                + FromRecoveredTx<TransactionSigned>
+                + FromTxWithEncoded<TransactionSigned>,
+        Spec = SpecId,
+        BlockEnv = BlockEnv,
+        Precompiles = PrecompilesMap,
+    > + Clone
+    + Debug
+    + Send
+    + Sync
+    + Unpin
+    + 'static
+{
+}
+
+impl<F> BorEvmFactory for F where
+    F: EvmFactory<
+            Tx: TransactionEnv
+                    + FromRecoveredTx<TransactionSigned>
+                    + FromTxWithEncoded<TransactionSigned>,
+            Spec = SpecId,
+            BlockEnv = BlockEnv,
+            Precompiles = PrecompilesMap,
+        > + Clone
+        + Debug
+        + Send
+        + Sync
+        + Unpin
+        + 'static
+{
+}
+
+/// The address transaction fees of `header` are paid to: the block's signer.
+///
+/// Bor leaves the header's beneficiary at zero and credits fees to the author recovered
+/// from the seal, as `COINBASE` does. Headers without a valid seal, such as genesis, fall
+/// back to the beneficiary.
+pub fn block_fee_recipient(header: &Header) -> Address {
+    let extra = &header.extra_data;
+    let seal = &extra[extra.len().saturating_sub(EXTRADATA_SEAL_LEN)..];
+    match ecrecover_seal(&compute_seal_hash(header), seal) {
+        Ok(signer) => signer,
+        Err(err) => {
+            debug!(
+                target: "bor::evm",
+                number = header.number,
+                %err,
+                "no block signer, paying fees to beneficiary"
+            );
+            header.beneficiary
+        }
+    }
+}
 
 /// Bor EVM configuration for Reth.
 ///
@@ -45,18 +136,24 @@ pub struct BorEvmConfig<C = reth_chainspec::ChainSpec, EvmF = EthEvmFactory> {
     pub block_assembler: BorBlockAssembler<C>,
     /// Chain spec.
     chain_spec: Arc<C>,
+    /// Where the system calls of each block are read from, if anywhere.
+    sprint_context: Option<SharedSprintContextSource>,
+    /// Gas limit built blocks move toward, the chain's block gas limit if unset.
+    gas_limit_target: Option<u64>,
 }
 
 impl<C> BorEvmConfig<C> {
     /// Create a new Bor EVM configuration with the given chain spec.
     pub fn new(chain_spec: Arc<C>) -> Self {
-        Self::new_with_evm_factory(chain_spec, EthEvmFactory::default())
+        Self::new_with_custom_factory(chain_spec, EthEvmFactory::default())
     }
 }
 
 impl<C, EvmF> BorEvmConfig<C, EvmF> {
-    /// Create a new Bor EVM configuration with a custom EVM factory.
-    pub fn new_with_evm_factory(chain_spec: Arc<C>, evm_factory: EvmF) -> Self {
+    /// Create a new Bor EVM configuration creating EVMs with `evm_factory` instead of
+    /// [`EthEvmFactory`], e.g. to add precompiles. To be usable, the factory must be a
+    /// [`BorEvmFactory`].
+    pub fn new_with_custom_factory(chain_spec: Arc<C>, evm_factory: EvmF) -> Self {
         let eth_factory = EthBlockExecutorFactory::new(
             RethReceiptBuilder::default(),
             chain_spec.clone(),
@@ -66,34 +163,130 @@ impl<C, EvmF> BorEvmConfig<C, EvmF> {
             block_assembler: BorBlockAssembler::new(chain_spec.clone()),
             executor_factory: BorBlockExecutorFactory::new(eth_factory),
             chain_spec,
+            sprint_context: None,
+            gas_limit_target: None,
         }
     }
 
+    /// Issue the system calls of every block through `system_caller`.
+    pub fn with_system_caller(mut self, system_caller: BorSystemCaller) -> Self {
+        self.executor_factory = self.executor_factory.with_system_caller(system_caller);
+        self
+    }
+
+    /// Make the post-execution changes of `post_execution` in every block.
+    pub fn with_post_execution(mut self, post_execution: BorPostExecution) -> Self {
+        self.executor_factory = self.executor_factory.with_post_execution(post_execution);
+        self
+    }
+
+    /// Let block executors reuse the sprint-start simulations of `presimulator`.
+    pub fn with_presimulator(mut self, presimulator: SprintPresimulator) -> Self {
+        self.executor_factory = self.executor_factory.with_presimulator(presimulator);
+        self
+    }
+
+    /// Mark sprint-start blocks in `sprint_wal` while their system calls run.
+    pub fn with_sprint_wal(mut self, sprint_wal: SharedSprintWal) -> Self {
+        self.executor_factory = self.executor_factory.with_sprint_wal(sprint_wal);
+        self
+    }
+
+    /// Record the state sync events committed by every block in `state_syncs`.
+    pub fn with_state_sync_store(mut self, state_syncs: SharedStateSyncStore) -> Self {
+        self.executor_factory = self.executor_factory.with_state_sync_store(state_syncs);
+        self
+    }
+
+    /// Record the execution diff of every block in `execution_diffs`.
+    pub fn with_execution_diffs(mut self, execution_diffs: ExecutionDiffRecorder) -> Self {
+        self.executor_factory = self.executor_factory.with_execution_diffs(execution_diffs);
+        self
+    }
+
+    /// Fail the blocks consensus held back in `deferred_checks` instead of executing them.
+    pub fn with_deferred_checks(mut self, deferred_checks: SharedDeferredChecks) -> Self {
+        self.executor_factory = self.executor_factory.with_deferred_checks(deferred_checks);
+        self
+    }
+
+    /// Read the span commits and state sync events of every block from `sprint_context`.
+    pub fn with_sprint_context(mut self, sprint_context: SharedSprintContextSource) -> Self {
+        self.sprint_context = Some(sprint_context);
+        self
+    }
+
+    /// Move the gas limit of built blocks toward `target` (`--miner.gaslimit`) instead of
+    /// the chain's block gas limit.
+    pub fn with_gas_limit_target(mut self, target: Option<u64>) -> Self {
+        self.gas_limit_target = target;
+        self
+    }
+
     /// Returns the chain spec.
     pub fn chain_spec(&self) -> &Arc<C> {
         &self.chain_spec
     }
 }
 
+impl<C, EvmF> BorEvmConfig<C, EvmF>
+where
+    C: EthExecutorSpec + EthChainSpec<Header = Header> + EthereumHardforks + BorHardforks,
+{
+    /// The [`CfgEnv`] of the block at `block_number`.
+    ///
+    /// Polygon adopted Cancun without EIP-4844, so no transaction may carry blobs.
+    pub fn bor_cfg_env(&self, timestamp: u64, block_number: u64) -> CfgEnv {
+        let eth_spec =
+            revm_spec_by_timestamp_and_block_number(self.chain_spec(), timestamp, block_number);
+        let spec = bor_spec_id(&*self.chain_spec, block_number, eth_spec);
+
+        let mut cfg_env = CfgEnv::new()
+            .with_chain_id(self.chain_spec.chain().id())
+            .with_spec_and_mainnet_gas_params(spec);
+        cfg_env.set_max_blobs_per_tx(0);
+        if self.chain_spec.is_osaka_active_at_timestamp(timestamp) {
+            cfg_env.tx_gas_limit_cap = Some(MAX_TX_GAS_LIMIT_OSAKA);
+        }
+        cfg_env
+    }
+
+    /// The Bor context of block `block_number` with timestamp `timestamp`, with the system
+    /// calls the sprint context source has for it.
+    fn bor_execution_ctx(
+        &self,
+        block_number: u64,
+        timestamp: u64,
+    ) -> Result<BorExecutionCtx, SprintContextError> {
+        let sprint_start = block_number > 0 && self.chain_spec.is_bor_sprint_start(block_number)?;
+        let mut ctx = BorExecutionCtx { sprint_start, ..Default::default() };
+        if let Some(source) = &self.sprint_context {
+            ctx.pending_commit_span = source.commit_span(block_number)?;
+            if sprint_start {
+                for event in source.state_syncs(block_number, timestamp)? {
+                    ctx.state_sync_contracts.insert(event.id, event.contract);
+                    ctx.pending_state_syncs.push((U256::from(event.id), event.data));
+                }
+            }
+        }
+        Ok(ctx)
+    }
+}
+
 impl<C, EvmF> ConfigureEvm for BorEvmConfig<C, EvmF>
 where
-    C: EthExecutorSpec + EthChainSpec<Header = Header> + reth_chainspec::EthereumHardforks + Clone + 'static,
-    EvmF: EvmFactory<
-            Tx: TransactionEnv
-                    + FromRecoveredTx<TransactionSigned>
-                    + FromTxWithEncoded<TransactionSigned>,
-            Spec = SpecId,
-            BlockEnv = BlockEnv,
-            Precompiles = PrecompilesMap,
-        > + Clone
-        + Debug
-        + Send
-        + Sync
-        + Unpin
+    C: EthExecutorSpec
+        + EthChainSpec<Header = Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
         + 'static,
+    EvmF: BorEvmFactory,
 {
     type Primitives = EthPrimitives;
-    type Error = Infallible;
+    /// A block whose sprint the chain's `bor` config cannot tell, or whose system calls
+    /// have not been fetched.
+    type Error = SprintContextError;
     type NextBlockEnvCtx = NextBlockEnvAttributes;
     type BlockExecutorFactory = BorBlockExecutorFactory<RethReceiptBuilder, Arc<C>, EvmF>;
     type BlockAssembler = BorBlockAssembler<C>;
@@ -107,12 +300,9 @@ where
     }
 
     fn evm_env(&self, header: &Header) -> Result<EvmEnv<SpecId>, Self::Error> {
-        Ok(EvmEnv::for_eth_block(
-            header,
-            &*self.chain_spec,
-            self.chain_spec.chain().id(),
-            self.chain_spec.blob_params_at_timestamp(header.timestamp),
-        ))
+        let cfg_env = self.bor_cfg_env(header.timestamp, header.number);
+        let block_env = header_block_env(header, block_fee_recipient(header), cfg_env.spec);
+        Ok(EvmEnv { cfg_env, block_env })
     }
 
     fn next_evm_env(
@@ -120,22 +310,17 @@ where
         parent: &Header,
         attributes: &NextBlockEnvAttributes,
     ) -> Result<EvmEnv, Self::Error> {
-        use reth_evm::eth::NextEvmEnvAttributes;
-        Ok(EvmEnv::for_eth_next_block(
-            parent,
-            NextEvmEnvAttributes {
-                timestamp: attributes.timestamp,
-                suggested_fee_recipient: attributes.suggested_fee_recipient,
-                prev_randao: attributes.prev_randao,
-                gas_limit: attributes.gas_limit,
-            },
-            self.chain_spec
-                .next_block_base_fee(parent, attributes.timestamp)
-                .unwrap_or_default(),
-            &*self.chain_spec,
-            self.chain_spec.chain().id(),
-            self.chain_spec.blob_params_at_timestamp(attributes.timestamp),
-        ))
+        let number = parent.number.saturating_add(1);
+        let cfg_env = self.bor_cfg_env(attributes.timestamp, number);
+        let base_fee =
+            self.chain_spec.next_block_base_fee(parent, attributes.timestamp).unwrap_or_default();
+        let target = gas_limit_target(&*self.chain_spec, number, self.gas_limit_target);
+        let attributes = NextBlockEnvAttributes {
+            gas_limit: next_gas_limit(parent.gas_limit, target),
+            ..attributes.clone()
+        };
+        let block_env = next_block_env(parent, &attributes, base_fee, cfg_env.spec);
+        Ok(EvmEnv { cfg_env, block_env })
     }
 
     fn context_for_block<'a>(
@@ -151,9 +336,7 @@ where
                 withdrawals: block.body().withdrawals.as_ref().map(Cow::Borrowed),
                 extra_data: block.header().extra_data.clone(),
             },
-            // System call data will be populated by the pipeline/node
-            // before execution. For now, default to no-op.
-            bor: BorExecutionCtx::default(),
+            bor: self.bor_execution_ctx(block.header().number, block.header().timestamp)?,
         })
     }
 
@@ -169,69 +352,46 @@ where
                 parent_beacon_block_root: attributes.parent_beacon_block_root,
                 ommers: &[],
                 withdrawals: attributes.withdrawals.map(Cow::Owned),
-                extra_data: Default::default(),
+                // Vanity, sprint-end validators and the seal reserved for the sealer.
+                extra_data: attributes.extra_data,
             },
-            bor: BorExecutionCtx::default(),
+            bor: self.bor_execution_ctx(parent.number.saturating_add(1), attributes.timestamp)?,
         })
     }
 }
 
 impl<C, EvmF> ConfigureEngineEvm<ExecutionData> for BorEvmConfig<C, EvmF>
 where
-    C: EthExecutorSpec + EthChainSpec<Header = Header> + EthereumHardforks + Clone + 'static,
-    EvmF: EvmFactory<
-            Tx: TransactionEnv
-                    + FromRecoveredTx<TransactionSigned>
-                    + FromTxWithEncoded<TransactionSigned>,
-            Spec = SpecId,
-            BlockEnv = BlockEnv,
-            Precompiles = PrecompilesMap,
-        > + Clone
-        + Debug
-        + Send
-        + Sync
-        + Unpin
+    C: EthExecutorSpec
+        + EthChainSpec<Header = Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
         + 'static,
+    EvmF: BorEvmFactory,
 {
     fn evm_env_for_payload(&self, payload: &ExecutionData) -> Result<EvmEnvFor<Self>, Self::Error> {
         let timestamp = payload.payload.timestamp();
         let block_number = payload.payload.block_number();
 
-        let blob_params = self.chain_spec().blob_params_at_timestamp(timestamp);
-        let spec =
-            revm_spec_by_timestamp_and_block_number(self.chain_spec(), timestamp, block_number);
-
-        let mut cfg_env = CfgEnv::new()
-            .with_chain_id(self.chain_spec().chain().id())
-            .with_spec_and_mainnet_gas_params(spec);
-
-        if let Some(blob_params) = &blob_params {
-            cfg_env.set_max_blobs_per_tx(blob_params.max_blobs_per_tx);
-        }
-
-        if self.chain_spec().is_osaka_active_at_timestamp(timestamp) {
-            cfg_env.tx_gas_limit_cap = Some(MAX_TX_GAS_LIMIT_OSAKA);
-        }
+        let cfg_env = self.bor_cfg_env(timestamp, block_number);
 
-        let blob_excess_gas_and_price =
-            payload.payload.excess_blob_gas().zip(blob_params).map(|(excess_blob_gas, params)| {
-                let blob_gasprice = params.calc_blob_fee(excess_blob_gas);
-                BlobExcessGasAndPrice { excess_blob_gas, blob_gasprice }
-            });
-
-        let block_env = BlockEnv {
-            number: U256::from(block_number),
-            beneficiary: payload.payload.fee_recipient(),
-            timestamp: U256::from(timestamp),
-            difficulty: if spec >= SpecId::MERGE {
-                U256::ZERO
-            } else {
-                payload.payload.as_v1().prev_randao.into()
-            },
-            prevrandao: (spec >= SpecId::MERGE).then(|| payload.payload.as_v1().prev_randao),
-            gas_limit: payload.payload.gas_limit(),
-            basefee: payload.payload.saturated_base_fee_per_gas(),
-            blob_excess_gas_and_price,
+        // The fee recipient is the signer, which needs the whole header to recover.
+        let block_env = match payload.clone().try_into_block::<TransactionSigned>() {
+            Ok(block) => {
+                header_block_env(&block.header, block_fee_recipient(&block.header), cfg_env.spec)
+            }
+            Err(_) => bor_block_env(
+                BorBlockEnvInput {
+                    number: block_number,
+                    timestamp,
+                    fee_recipient: payload.payload.fee_recipient(),
+                    difficulty: U256::ZERO,
+                    gas_limit: payload.payload.gas_limit(),
+                    base_fee: payload.payload.saturated_base_fee_per_gas(),
+                },
+                cfg_env.spec,
+            ),
         };
 
         Ok(EvmEnv { cfg_env, block_env })
@@ -250,7 +410,10 @@ where
                 withdrawals: payload.payload.withdrawals().map(|w| Cow::Owned(w.clone().into())),
                 extra_data: payload.payload.as_v1().extra_data.clone(),
             },
-            bor: BorExecutionCtx::default(),
+            bor: self.bor_execution_ctx(
+                payload.payload.block_number(),
+                payload.payload.timestamp(),
+            )?,
         })
     }
 
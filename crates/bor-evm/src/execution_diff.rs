@@ -0,0 +1,302 @@
+//! Execution diffs of imported blocks, for the `--bor.assert-roots` mode.
+//!
+//! A follower whose execution differs from the chain's by one storage slot only
+//! learns that a root did not match. With an [`ExecutionDiffRecorder`], executors
+//! keep, for each of the last blocks they executed, the gas each transaction used and
+//! every account the block touched with its balance, nonce, code and storage slots
+//! before and after. When a block is rejected, its diff is written out with
+//! [`ExecutionDiff::dump`] and compared against a trace of the block on bor-geth.
+
+use alloy_consensus::TxReceipt;
+use alloy_primitives::{Address, B256, U256};
+use revm::database::TransitionAccount;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Number of blocks an [`ExecutionDiffRecorder`] keeps the diff of.
+pub const DEFAULT_EXECUTION_DIFF_HISTORY: usize = 64;
+
+/// Gas used by one transaction of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxGas {
+    /// Index of the transaction in the block.
+    pub index: u64,
+    /// Gas the transaction used.
+    pub gas_used: u64,
+    /// Gas used by the block up to and including the transaction.
+    pub cumulative_gas_used: u64,
+    /// Whether the transaction succeeded.
+    pub success: bool,
+}
+
+/// A storage slot a block changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlotChange {
+    /// Slot key.
+    pub slot: U256,
+    /// Value before the block.
+    pub before: U256,
+    /// Value after the block.
+    pub after: U256,
+}
+
+/// An account a block touched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TouchedAccount {
+    /// Account address.
+    pub address: Address,
+    /// Balance before the block, if the account existed.
+    pub balance_before: Option<U256>,
+    /// Balance after the block, if the account exists.
+    pub balance_after: Option<U256>,
+    /// Nonce before the block, if the account existed.
+    pub nonce_before: Option<u64>,
+    /// Nonce after the block, if the account exists.
+    pub nonce_after: Option<u64>,
+    /// Whether the account's code hash changed.
+    pub code_changed: bool,
+    /// Whether the account's storage was wiped.
+    pub storage_destroyed: bool,
+    /// Storage slots the block changed, by key.
+    pub storage: Vec<SlotChange>,
+}
+
+impl TouchedAccount {
+    /// The change `transition` made to the account at `address`.
+    pub fn new(address: Address, transition: &TransitionAccount) -> Self {
+        let before = transition.previous_info.as_ref();
+        let after = transition.info.as_ref();
+        let mut storage: Vec<_> = transition
+            .storage
+            .iter()
+            .filter(|(_, slot)| slot.is_changed())
+            .map(|(key, slot)| SlotChange {
+                slot: *key,
+                before: slot.previous_or_original_value,
+                after: slot.present_value,
+            })
+            .collect();
+        storage.sort_by_key(|change| change.slot);
+        Self {
+            address,
+            balance_before: before.map(|info| info.balance),
+            balance_after: after.map(|info| info.balance),
+            nonce_before: before.map(|info| info.nonce),
+            nonce_after: after.map(|info| info.nonce),
+            code_changed: before.map(|info| info.code_hash) != after.map(|info| info.code_hash),
+            storage_destroyed: transition.storage_was_destroyed,
+            storage,
+        }
+    }
+}
+
+/// What executing a block did, as far as its roots are concerned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionDiff {
+    /// Block number.
+    pub number: u64,
+    /// Hash of the block's parent; the block's own hash is not known when it executes.
+    pub parent_hash: B256,
+    /// Gas used by the block.
+    pub gas_used: u64,
+    /// Gas used by each transaction, in order.
+    pub transactions: Vec<TxGas>,
+    /// Accounts the block touched, by address, with system calls included.
+    pub touched: Vec<TouchedAccount>,
+}
+
+impl ExecutionDiff {
+    /// The diff of block `number` on `parent_hash` that produced `receipts` and left
+    /// `transitions`.
+    pub fn new<'a, R: TxReceipt>(
+        number: u64,
+        parent_hash: B256,
+        receipts: &[R],
+        transitions: impl IntoIterator<Item = (&'a Address, &'a TransitionAccount)>,
+    ) -> Self {
+        let mut previous = 0;
+        let transactions = receipts
+            .iter()
+            .enumerate()
+            .map(|(index, receipt)| {
+                let cumulative = receipt.cumulative_gas_used();
+                let gas = TxGas {
+                    index: index as u64,
+                    gas_used: cumulative.saturating_sub(previous),
+                    cumulative_gas_used: cumulative,
+                    success: receipt.status(),
+                };
+                previous = cumulative;
+                gas
+            })
+            .collect();
+        let mut touched: Vec<_> = transitions
+            .into_iter()
+            .map(|(address, transition)| TouchedAccount::new(*address, transition))
+            .collect();
+        touched.sort_by_key(|account| account.address);
+        Self { number, parent_hash, gas_used: previous, transactions, touched }
+    }
+
+    /// Write the diff as JSON to `dir`, named after the block, and return the file's path.
+    pub fn dump(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let name = format!("bor-execution-diff-{}-{}.json", self.number, self.parent_hash);
+        let path = dir.join(name);
+        std::fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug)]
+struct Diffs {
+    recent: VecDeque<ExecutionDiff>,
+    capacity: usize,
+}
+
+/// Keeps the execution diffs of the last blocks executed.
+///
+/// Clones share the same record, and compare equal only to each other.
+#[derive(Debug, Clone)]
+pub struct ExecutionDiffRecorder {
+    diffs: Arc<Mutex<Diffs>>,
+}
+
+impl PartialEq for ExecutionDiffRecorder {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.diffs, &other.diffs)
+    }
+}
+
+impl Eq for ExecutionDiffRecorder {}
+
+impl Default for ExecutionDiffRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionDiffRecorder {
+    /// Create a recorder keeping [`DEFAULT_EXECUTION_DIFF_HISTORY`] blocks.
+    pub fn new() -> Self {
+        Self::with_history(DEFAULT_EXECUTION_DIFF_HISTORY)
+    }
+
+    /// Create a recorder keeping the diffs of the last `capacity` blocks.
+    pub fn with_history(capacity: usize) -> Self {
+        let diffs = Diffs { recent: VecDeque::with_capacity(capacity), capacity };
+        Self { diffs: Arc::new(Mutex::new(diffs)) }
+    }
+
+    /// Record `diff`, replacing an earlier one of the same block on the same parent.
+    pub fn record(&self, diff: ExecutionDiff) {
+        let mut diffs = self.diffs.lock().expect("execution diff lock poisoned");
+        if diffs.capacity == 0 {
+            return;
+        }
+        diffs.recent.retain(|d| (d.number, d.parent_hash) != (diff.number, diff.parent_hash));
+        if diffs.recent.len() == diffs.capacity {
+            diffs.recent.pop_front();
+        }
+        diffs.recent.push_back(diff);
+    }
+
+    /// The diff of block `number` on `parent_hash`, if it is still kept.
+    pub fn get(&self, number: u64, parent_hash: B256) -> Option<ExecutionDiff> {
+        let diffs = self.diffs.lock().expect("execution diff lock poisoned");
+        diffs.recent.iter().find(|d| d.number == number && d.parent_hash == parent_hash).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{Eip658Value, Receipt};
+    use revm::database::{AccountStatus, StorageSlot, StorageWithOriginalValues};
+    use revm::state::AccountInfo;
+
+    fn receipt(cumulative_gas_used: u64, success: bool) -> Receipt {
+        Receipt { status: Eip658Value::Eip658(success), cumulative_gas_used, logs: Vec::new() }
+    }
+
+    fn transition(balance: (u64, u64), slot: Option<(u64, u64)>) -> TransitionAccount {
+        let info =
+            |balance: u64| AccountInfo { balance: U256::from(balance), ..Default::default() };
+        let mut storage = StorageWithOriginalValues::default();
+        if let Some((before, after)) = slot {
+            let slot = StorageSlot::new_changed(U256::from(before), U256::from(after));
+            storage.insert(U256::from(1), slot);
+        }
+        TransitionAccount {
+            info: Some(info(balance.1)),
+            status: AccountStatus::Changed,
+            previous_info: Some(info(balance.0)),
+            previous_status: AccountStatus::Loaded,
+            storage,
+            storage_was_destroyed: false,
+        }
+    }
+
+    #[test]
+    fn test_diff_of_block() {
+        let receipts = [receipt(21_000, true), receipt(71_000, false)];
+        let (a, b) = (Address::with_last_byte(2), Address::with_last_byte(1));
+        let transitions =
+            [(a, transition((100, 50), Some((0, 7)))), (b, transition((0, 1), None))];
+        let diff = ExecutionDiff::new(
+            64,
+            B256::with_last_byte(63),
+            &receipts,
+            transitions.iter().map(|(address, transition)| (address, transition)),
+        );
+
+        assert_eq!(diff.gas_used, 71_000);
+        assert_eq!(diff.transactions[1].gas_used, 50_000);
+        assert!(!diff.transactions[1].success);
+        assert_eq!(diff.touched[0].address, b);
+        let touched = &diff.touched[1];
+        assert_eq!(touched.balance_before, Some(U256::from(100)));
+        assert_eq!(touched.balance_after, Some(U256::from(50)));
+        assert!(!touched.code_changed);
+        assert_eq!(
+            touched.storage,
+            vec![SlotChange { slot: U256::from(1), before: U256::ZERO, after: U256::from(7) }]
+        );
+
+        let dir = std::env::temp_dir().join(format!("bor-execution-diff-{}", std::process::id()));
+        let path = diff.dump(&dir).unwrap();
+        let dumped: ExecutionDiff =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(dumped, diff);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_keeps_last_blocks() {
+        let recorder = ExecutionDiffRecorder::with_history(2);
+        let diff = |number: u64, parent: u8| ExecutionDiff {
+            number,
+            parent_hash: B256::with_last_byte(parent),
+            gas_used: 0,
+            transactions: Vec::new(),
+            touched: Vec::new(),
+        };
+        recorder.record(diff(1, 0));
+        recorder.record(diff(2, 1));
+        recorder.record(diff(2, 1));
+        assert!(recorder.get(1, B256::with_last_byte(0)).is_some());
+
+        recorder.record(diff(2, 9));
+        assert!(recorder.get(1, B256::with_last_byte(0)).is_none());
+        assert!(recorder.get(2, B256::with_last_byte(1)).is_some());
+        assert!(recorder.get(2, B256::with_last_byte(9)).is_some());
+        assert_eq!(recorder.clone(), recorder);
+        assert_ne!(ExecutionDiffRecorder::new(), recorder);
+    }
+}
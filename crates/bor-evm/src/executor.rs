@@ -41,8 +41,9 @@ pub fn plan_system_txs(
     has_pending_span: bool,
     pending_state_sync_events: &[(U256, Bytes)],
 ) -> SystemTxPlan {
-    let is_sprint_boundary = block_number > 0 && block_number % sprint_size == 0;
-    let is_span_boundary = block_number > 0 && block_number % span_size == 0;
+    // A zero size marks no boundary instead of dividing by zero.
+    let is_sprint_boundary = block_number > 0 && block_number.checked_rem(sprint_size) == Some(0);
+    let is_span_boundary = block_number > 0 && block_number.checked_rem(span_size) == Some(0);
 
     SystemTxPlan {
         execute_commit_span: is_span_boundary && has_pending_span,
@@ -139,6 +140,14 @@ mod tests {
         assert_eq!(plan.state_sync_events.len(), 1);
     }
 
+    #[test]
+    fn test_zero_sizes_do_not_panic() {
+        let events = vec![(U256::from(1), Bytes::from(vec![0x01]))];
+        let plan = plan_system_txs(16, 0, 0, true, &events);
+        assert!(!plan.execute_commit_span);
+        assert!(plan.state_sync_events.is_empty());
+    }
+
     #[test]
     fn test_empty_block_valid() {
         // Block 7 (not a boundary), no events, no pending span
@@ -197,6 +206,20 @@ mod tests {
         assert_eq!(result.state_sync_count, 0);
     }
 
+    #[test]
+    fn test_bor_receipt_only_with_state_syncs() {
+        // commitSpan alone does not produce a Bor receipt.
+        let plan = plan_system_txs(6400, 16, 6400, true, &[]);
+        let validator_bytes = Some(Bytes::from_static(&[0xaa; 20]));
+        let result = execute_system_tx_plan(&plan, Some(U256::from(1)), validator_bytes);
+        assert!(result.commit_span_executed);
+        assert_eq!(result.state_sync_count, 0);
+
+        let events = vec![(U256::from(1), Bytes::from_static(b"sync"))];
+        let plan = plan_system_txs(16, 16, 6400, false, &events);
+        assert_eq!(execute_system_tx_plan(&plan, None, None).state_sync_count, 1);
+    }
+
     #[test]
     fn test_span_boundary_no_pending_span() {
         // Span boundary but no pending span data → no commitSpan
@@ -2,21 +2,82 @@
 
 pub mod block_executor;
 pub use block_executor::{
-    BorBlockExecutionCtx, BorBlockExecutor, BorBlockExecutorFactory, BorExecutionCtx,
-    PendingCommitSpan,
+    apply_sprint_boundary, BorBlockExecutionCtx, BorBlockExecutor, BorBlockExecutorFactory,
+    BorExecutionCtx, BorSystemCaller, PendingCommitSpan, SprintContext, StateSyncRecordsOverride,
+    SystemContracts,
+};
+
+pub mod block_env;
+pub use block_env::{
+    bor_block_env, difficulty_word, header_block_env, next_block_env, BorBlockEnvInput,
 };
 
 pub mod build;
 pub use build::BorBlockAssembler;
 
 pub mod config;
-pub use config::{BorEvmConfig as BorEvmConfigPrecompiles, P256_VERIFY_ADDRESS, bor_precompile_addresses};
+pub use config::{
+    BorEvmConfig as BorEvmConfigPrecompiles, P256_VERIFY_ADDRESS, bor_precompile_addresses,
+    bor_precompile_addresses_for, bor_spec_id,
+};
 
 pub mod evm_config;
-pub use evm_config::BorEvmConfig;
+pub use evm_config::{block_fee_recipient, BorEvmConfig, BorEvmFactory};
+
+pub mod execution_diff;
+pub use execution_diff::{
+    ExecutionDiff, ExecutionDiffRecorder, SlotChange, TouchedAccount, TxGas,
+    DEFAULT_EXECUTION_DIFF_HISTORY,
+};
 
 pub mod executor;
 pub use executor::{SystemTxPlan, SystemTxResult, SystemCallRecord, plan_system_txs, execute_system_tx_plan};
 
+pub mod pending_state;
+pub use pending_state::{
+    PendingStateOverlay, PendingStateSyncs, StateSyncCrossCheckError, StateSyncRejection,
+    apply_pending_state_syncs, fetch_cross_checked_state_syncs, fetch_pending_state_syncs,
+    next_sprint_start, pending_state_sync_changes, select_state_sync_events, state_sync_to_time,
+    validate_state_sync_event,
+};
+
+pub mod validator_contract;
+pub use validator_contract::{
+    decode_bor_validators, get_bor_validators_call_data, read_bor_validators,
+    HistoricalValidatorReader, ValidatorContractError,
+};
+
+pub mod post_execution;
+pub use post_execution::{AllocAccount, BlockAlloc, BorPostExecution};
+
+pub mod presim;
+pub use presim::SprintPresimulator;
+
+pub mod profile;
+pub use profile::{StateSyncProfile, StateSyncProfiler, DEFAULT_PROFILE_HISTORY};
+
+pub mod sprint_context;
+pub use sprint_context::{
+    CachedSprintContext, SharedSprintContextSource, SprintContextError, SprintContextSource,
+};
+
+pub mod sprint_summary;
+pub use sprint_summary::{BlockProducer, SprintSummary, SystemCallTimings};
+
+pub mod state_sync_filter;
+pub use state_sync_filter::{
+    PassThrough, SharedStateSyncFilter, StateSyncAction, StateSyncFilter,
+};
+
 pub mod system_call;
-pub use system_call::{CommitSpanCall, StateReceiveCall, prepare_state_sync_calls};
+pub use system_call::{
+    check_system_account, pin_system_account, CommitSpanCall, StateReceiveCall,
+    SystemAccountViolation, COMMIT_SPAN_SELECTOR, ON_STATE_RECEIVE_SELECTOR,
+    prepare_state_sync_calls,
+};
+
+pub mod warm;
+pub use warm::SystemCallWarmer;
+
+pub mod witness;
+pub use witness::{execute_witness_block, WitnessBlock, WitnessError, WitnessExecution};
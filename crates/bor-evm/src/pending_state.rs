@@ -0,0 +1,581 @@
+//! Pending state overlay for state sync events that are not applied yet.
+//!
+//! State sync events recorded on Heimdall are only committed to the chain at
+//! the next sprint start, through `onStateReceive` system calls. Until then a
+//! bridge deposit is invisible to `eth_call`. [`PendingStateOverlay`] holds the
+//! events expected at the next sprint start so that `eth_call` on the pending
+//! block and the transaction pool can execute them on top of the latest state
+//! with [`apply_pending_state_syncs`]; [`pending_state_sync_changes`] returns the
+//! accounts they change there.
+
+use crate::evm_config::BorEvmFactory;
+use crate::system_call::StateReceiveCall;
+use alloy_primitives::{Bytes, U256};
+use bor_chainspec::BorHardforks;
+use heimdall_client::{HeimdallClient, HeimdallConfig, HeimdallError, StateSyncEvent};
+use reth_evm::{block::BlockExecutionError, Database, Evm, EvmEnv, EvmFactory};
+use reth_revm::database::StateProviderDatabase;
+use reth_storage_api::StateProviderFactory;
+use revm::{
+    database::{states::bundle_state::BundleRetention, BundleState, State},
+    DatabaseCommit,
+};
+use std::sync::{Arc, RwLock};
+
+pub use bor_primitives::next_sprint_start;
+
+/// State sync events expected to be applied at an upcoming sprint start.
+///
+/// Holds every event fetched for the sprint start with the time Heimdall recorded it at;
+/// which of them the block commits depends on its timestamp, see
+/// [`events_before`](Self::events_before).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingStateSyncs {
+    /// The sprint start block that will apply the events.
+    pub block_number: u64,
+    /// The events, in ascending ID order.
+    pub records: Vec<StateSyncEvent>,
+}
+
+impl PendingStateSyncs {
+    /// ID of the last pending event, if any.
+    pub fn last_state_id(&self) -> Option<U256> {
+        self.records.last().map(|record| U256::from(record.id))
+    }
+
+    /// Returns `true` if no events are pending.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Every pending event, as `onStateReceive` takes them.
+    pub fn events(&self) -> Vec<(U256, Bytes)> {
+        self.records.iter().map(|record| (U256::from(record.id), record.data.clone())).collect()
+    }
+
+    /// The pending events recorded before `to_time`, see [`state_sync_to_time`].
+    pub fn events_before(&self, to_time: u64) -> Vec<StateSyncEvent> {
+        self.records.iter().take_while(|record| record.time < to_time).cloned().collect()
+    }
+}
+
+/// Why an event ends the run of state sync events committed at a sprint start.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StateSyncRejection {
+    #[error("expected state sync event {expected}, got {got}")]
+    OutOfSequence { expected: u64, got: u64 },
+    #[error("state sync event {id} targets chain {chain_id}")]
+    WrongChain { id: u64, chain_id: String },
+    #[error("state sync event {id} recorded at {time}, not before {to_time}")]
+    TooRecent { id: u64, time: u64, to_time: u64 },
+}
+
+/// Exclusive upper bound on the record time of events committed at sprint start `number`.
+///
+/// From Indore, events must be recorded the chain's state sync confirmation delay, 128
+/// seconds on mainnet, before the block's own timestamp. Before Indore the bound is the
+/// timestamp of the previous sprint start, `number - sprint_size`.
+pub fn state_sync_to_time(
+    forks: &impl BorHardforks,
+    number: u64,
+    block_time: u64,
+    previous_sprint_start_time: u64,
+) -> u64 {
+    if forks.is_indore_active_at_block(number) {
+        block_time.saturating_sub(forks.bor_state_sync_confirmation_delay(number))
+    } else {
+        previous_sprint_start_time
+    }
+}
+
+/// Check that `event` may follow `last_state_id`, as Bor's `validateEventRecord` does.
+///
+/// The ID must be exactly `last_state_id + 1`, the event must target `chain_id` when one is
+/// given, and it must be recorded strictly before `to_time` under every fork. Record times
+/// are whole seconds, truncated like Go's `time.Unix`, so an event recorded at `to_time`
+/// itself is excluded and one recorded a fraction of a second before it is included.
+pub fn validate_state_sync_event(
+    event: &StateSyncEvent,
+    last_state_id: u64,
+    to_time: u64,
+    chain_id: Option<&str>,
+) -> Result<(), StateSyncRejection> {
+    if event.id != last_state_id + 1 {
+        let expected = last_state_id + 1;
+        return Err(StateSyncRejection::OutOfSequence { expected, got: event.id });
+    }
+    if chain_id.is_some_and(|chain_id| chain_id != event.bor_chain_id) {
+        return Err(StateSyncRejection::WrongChain {
+            id: event.id,
+            chain_id: event.bor_chain_id.clone(),
+        });
+    }
+    if event.time >= to_time {
+        return Err(StateSyncRejection::TooRecent { id: event.id, time: event.time, to_time });
+    }
+    Ok(())
+}
+
+/// Order and filter Heimdall's events for commit after `last_state_id`, like Bor's
+/// `CommitStates`.
+///
+/// Events are sorted by ID, keeping Heimdall's order among equal IDs. IDs already committed
+/// are skipped; the first event failing [`validate_state_sync_event`] ends the selection,
+/// together with everything after it.
+pub fn select_state_sync_events(
+    mut events: Vec<StateSyncEvent>,
+    last_state_id: u64,
+    to_time: u64,
+    chain_id: Option<&str>,
+) -> Vec<StateSyncEvent> {
+    events.sort_by_key(|event| event.id);
+    let mut last = last_state_id;
+    let mut selected = Vec::new();
+    for event in events {
+        if event.id <= last {
+            continue;
+        }
+        if validate_state_sync_event(&event, last, to_time, chain_id).is_err() {
+            break;
+        }
+        last = event.id;
+        selected.push(event);
+    }
+    selected
+}
+
+/// Fetch the events for chain `chain_id` after `last_state_id` that Heimdall recorded
+/// before `to_time`.
+///
+/// Events are paged as set by `config`, up to its page limit, and selected as by
+/// [`select_state_sync_events`]. Fetching stops at the first event that is not selected,
+/// since the state receiver only accepts consecutive IDs.
+pub async fn fetch_pending_state_syncs<C: HeimdallClient>(
+    client: &C,
+    config: &HeimdallConfig,
+    block_number: u64,
+    last_state_id: u64,
+    to_time: u64,
+    chain_id: &str,
+) -> Result<PendingStateSyncs, HeimdallError> {
+    let page_size = config.state_sync_page_size;
+    let mut records: Vec<StateSyncEvent> = Vec::new();
+    let mut pages = 0;
+
+    loop {
+        let last = records.last().map_or(last_state_id, |record| record.id);
+        let page = client.fetch_state_sync_events(last + 1, to_time, page_size).await?;
+        pages += 1;
+        let full_page = page.len() == page_size;
+        let fresh = page.iter().filter(|event| event.id > last).count();
+
+        let selected = select_state_sync_events(page, last, to_time, Some(chain_id));
+        let complete = selected.len() == fresh;
+        records.extend(selected);
+        if !complete
+            || !full_page
+            || config.max_state_sync_pages.is_some_and(|max| pages >= max)
+        {
+            return Ok(PendingStateSyncs { block_number, records });
+        }
+    }
+}
+
+/// Why state sync events fetched from two Heimdall endpoints are not applied.
+#[derive(Debug, thiserror::Error)]
+pub enum StateSyncCrossCheckError {
+    /// One of the endpoints failed to serve the events.
+    #[error("{endpoint} Heimdall: {source}")]
+    Heimdall {
+        /// `"primary"` or `"secondary"`.
+        endpoint: &'static str,
+        /// The error of that endpoint.
+        source: HeimdallError,
+    },
+    /// The endpoints serve different events.
+    #[error(
+        "Heimdall endpoints disagree on state sync event {state_id} for block {block_number}: \
+         primary serves {primary} events, secondary {secondary}"
+    )]
+    Mismatch {
+        /// The sprint start the events are for.
+        block_number: u64,
+        /// First event ID whose data, or presence, differs.
+        state_id: U256,
+        /// Events served by the primary endpoint.
+        primary: usize,
+        /// Events served by the secondary endpoint.
+        secondary: usize,
+    },
+}
+
+/// Fetch the pending events from `primary` and `secondary`, as by
+/// [`fetch_pending_state_syncs`], and return them only if both serve the same records.
+///
+/// A bridge deposit applied from a compromised or buggy Heimdall cannot be undone, so
+/// callers are expected to stop producing blocks on [`StateSyncCrossCheckError::Mismatch`]
+/// rather than fall back to either endpoint.
+pub async fn fetch_cross_checked_state_syncs<C: HeimdallClient>(
+    primary: &C,
+    secondary: &C,
+    config: &HeimdallConfig,
+    block_number: u64,
+    last_state_id: u64,
+    to_time: u64,
+    chain_id: &str,
+) -> Result<PendingStateSyncs, StateSyncCrossCheckError> {
+    let ours = fetch_pending_state_syncs(
+        primary,
+        config,
+        block_number,
+        last_state_id,
+        to_time,
+        chain_id,
+    )
+    .await
+    .map_err(|source| StateSyncCrossCheckError::Heimdall { endpoint: "primary", source })?;
+    let theirs = fetch_pending_state_syncs(
+        secondary,
+        config,
+        block_number,
+        last_state_id,
+        to_time,
+        chain_id,
+    )
+    .await
+    .map_err(|source| StateSyncCrossCheckError::Heimdall { endpoint: "secondary", source })?;
+    if ours == theirs {
+        return Ok(ours);
+    }
+
+    let state_id = ours
+        .records
+        .iter()
+        .zip(&theirs.records)
+        .find(|(a, b)| a != b)
+        .map(|(a, _)| U256::from(a.id))
+        .unwrap_or_else(|| {
+            let shorter = ours.records.len().min(theirs.records.len());
+            U256::from(last_state_id + 1 + shorter as u64)
+        });
+    Err(StateSyncCrossCheckError::Mismatch {
+        block_number,
+        state_id,
+        primary: ours.records.len(),
+        secondary: theirs.records.len(),
+    })
+}
+
+/// Shared, periodically refreshed view of the pending state sync events.
+///
+/// Clones share the same view.
+#[derive(Debug, Clone, Default)]
+pub struct PendingStateOverlay {
+    pending: Arc<RwLock<Option<PendingStateSyncs>>>,
+}
+
+impl PendingStateOverlay {
+    /// Create an empty overlay.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the pending events.
+    pub fn update(&self, pending: PendingStateSyncs) {
+        *self.pending.write().expect("pending state lock poisoned") = Some(pending);
+    }
+
+    /// Drop the pending events, e.g. once their sprint start is canonical.
+    pub fn clear(&self) {
+        *self.pending.write().expect("pending state lock poisoned") = None;
+    }
+
+    /// The pending events, if any are known.
+    pub fn pending(&self) -> Option<PendingStateSyncs> {
+        self.pending.read().expect("pending state lock poisoned").clone()
+    }
+
+    /// The events to overlay for calls against the state after `head`.
+    ///
+    /// Events are only returned while their sprint start is still ahead of `head`; once the
+    /// chain has reached it they are part of the real state.
+    pub fn events_after(&self, head: u64) -> Vec<(U256, Bytes)> {
+        match self.pending() {
+            Some(pending) if pending.block_number > head => pending.events(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Execute `onStateReceive` for each event on top of the EVM's state and commit the results.
+///
+/// Used to build the pending state for `eth_call` and pool validation. Returns the number of
+/// events applied.
+pub fn apply_pending_state_syncs<E>(
+    evm: &mut E,
+    events: &[(U256, Bytes)],
+) -> Result<usize, BlockExecutionError>
+where
+    E: Evm<DB: DatabaseCommit>,
+{
+    for (state_id, data) in events {
+        let call = StateReceiveCall { state_id: *state_id, data: data.clone() };
+        let res = evm
+            .transact_system_call(
+                StateReceiveCall::caller(),
+                StateReceiveCall::to_address(),
+                call.call_data(),
+            )
+            .map_err(|e| {
+                BlockExecutionError::msg(format!(
+                    "pending onStateReceive failed for state_id {state_id}: {e}"
+                ))
+            })?;
+        evm.db_mut().commit(res.state);
+    }
+    Ok(events.len())
+}
+
+/// The accounts the pending `events` change when applied, under `env` in an EVM of
+/// `evm_factory`, on top of the latest state of `provider`.
+///
+/// The bundle holds the state each changed account is left in, which `eth_call` on the
+/// pending block and the transaction pool overlay on the latest state.
+pub fn pending_state_sync_changes<P: StateProviderFactory, F: BorEvmFactory>(
+    provider: &P,
+    evm_factory: &F,
+    env: EvmEnv,
+    events: &[(U256, Bytes)],
+) -> Result<BundleState, BlockExecutionError> {
+    let state = provider.latest().map_err(BlockExecutionError::other)?;
+    state_sync_changes_on(StateProviderDatabase::new(state), evm_factory, env, events)
+}
+
+/// [`pending_state_sync_changes`] on top of `db`.
+fn state_sync_changes_on<DB: Database, F: BorEvmFactory>(
+    db: DB,
+    evm_factory: &F,
+    env: EvmEnv,
+    events: &[(U256, Bytes)],
+) -> Result<BundleState, BlockExecutionError> {
+    let mut db = State::builder().with_database(db).with_bundle_update().build();
+    apply_pending_state_syncs(&mut evm_factory.create_evm(&mut db, env), events)?;
+    db.merge_transitions(BundleRetention::PlainState);
+    Ok(db.take_bundle())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256};
+    use bor_chainspec::constants::{STATE_RECEIVER_ADDRESS, STATE_SYNC_DELAY};
+    use heimdall_client::MockHeimdallClient;
+    use reth_evm::{eth::EthEvmFactory, EvmEnv, EvmFactory};
+    use revm::{
+        database::{CacheDB, EmptyDB},
+        state::{AccountInfo, Bytecode},
+        Database,
+    };
+
+    fn event(id: u64, time: u64) -> StateSyncEvent {
+        StateSyncEvent {
+            id,
+            contract: Address::ZERO,
+            data: Bytes::from(id.to_be_bytes().to_vec()),
+            tx_hash: B256::ZERO,
+            log_index: 0,
+            bor_chain_id: "137".to_string(),
+            time,
+        }
+    }
+
+    #[test]
+    fn test_next_sprint_start() {
+        assert_eq!(next_sprint_start(0, 16), Some(16));
+        assert_eq!(next_sprint_start(15, 16), Some(16));
+        assert_eq!(next_sprint_start(16, 16), Some(32));
+        assert_eq!(next_sprint_start(16, 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pages_and_stops_at_time_and_gaps() {
+        let events: Vec<_> = (1..=120).map(|id| event(id, 1_000 + id)).collect();
+        let client = MockHeimdallClient::new().with_events(events);
+
+        // Paged across three requests, bounded by time.
+        let config = HeimdallConfig::default();
+        let pending =
+            fetch_pending_state_syncs(&client, &config, 32, 0, 1_111, "137").await.unwrap();
+        assert_eq!(pending.block_number, 32);
+        assert_eq!(pending.records.len(), 110);
+        assert_eq!(pending.last_state_id(), Some(U256::from(110)));
+
+        // Starts after the last applied ID.
+        let pending =
+            fetch_pending_state_syncs(&client, &config, 32, 100, u64::MAX, "137").await.unwrap();
+        assert_eq!(pending.records.first().unwrap().id, 101);
+
+        let gap =
+            MockHeimdallClient::new().with_events(vec![event(1, 1), event(2, 2), event(4, 4)]);
+        let pending =
+            fetch_pending_state_syncs(&gap, &config, 32, 0, u64::MAX, "137").await.unwrap();
+        assert_eq!(pending.records.len(), 2);
+
+        // Smaller pages, capped at two of them.
+        let capped =
+            HeimdallConfig::default().with_state_sync_page_size(20).with_max_state_sync_pages(2);
+        let pending =
+            fetch_pending_state_syncs(&client, &capped, 32, 0, u64::MAX, "137").await.unwrap();
+        assert_eq!(pending.records.len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stops_at_other_chains() {
+        let mut events: Vec<_> = (1..=3).map(|id| event(id, 1)).collect();
+        events[1].bor_chain_id = "80002".to_string();
+        let client = MockHeimdallClient::new().with_events(events);
+
+        let config = HeimdallConfig::default();
+        let pending =
+            fetch_pending_state_syncs(&client, &config, 32, 0, u64::MAX, "137").await.unwrap();
+        // An event for another chain ends the run, like a gap.
+        assert_eq!(pending.last_state_id(), Some(U256::from(1)));
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_rejects_disagreeing_endpoints() {
+        let events: Vec<_> = (1..=5).map(|id| event(id, 1_000 + id)).collect();
+        let primary = MockHeimdallClient::new().with_events(events.clone());
+        let config = HeimdallConfig::default();
+
+        let agreeing = MockHeimdallClient::new().with_events(events.clone());
+        let pending =
+            fetch_cross_checked_state_syncs(&primary, &agreeing, &config, 32, 0, u64::MAX, "137")
+                .await
+                .unwrap();
+        assert_eq!(pending.records.len(), 5);
+
+        let mut forged = events.clone();
+        forged[2].data = Bytes::from_static(b"mint");
+        let forged = MockHeimdallClient::new().with_events(forged);
+        let err =
+            fetch_cross_checked_state_syncs(&primary, &forged, &config, 32, 0, u64::MAX, "137")
+                .await
+                .unwrap_err();
+        assert!(
+            matches!(err, StateSyncCrossCheckError::Mismatch { state_id, primary: 5, .. }
+                if state_id == U256::from(3)),
+            "{err}"
+        );
+
+        // An endpoint withholding the last event disagrees from that event on.
+        let behind = MockHeimdallClient::new().with_events(events[..4].to_vec());
+        let err =
+            fetch_cross_checked_state_syncs(&primary, &behind, &config, 32, 0, u64::MAX, "137")
+                .await
+                .unwrap_err();
+        assert!(
+            matches!(err, StateSyncCrossCheckError::Mismatch { state_id, secondary: 4, .. }
+                if state_id == U256::from(5)),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_record_time_must_be_strictly_before_to_time() {
+        // Go: `!eventRecord.Time.Before(to)` rejects an event recorded exactly at `to`.
+        assert!(validate_state_sync_event(&event(1, 999), 0, 1_000, None).is_ok());
+        assert_eq!(
+            validate_state_sync_event(&event(1, 1_000), 0, 1_000, None),
+            Err(StateSyncRejection::TooRecent { id: 1, time: 1_000, to_time: 1_000 })
+        );
+    }
+
+    #[test]
+    fn test_validate_sequence_and_chain() {
+        assert_eq!(
+            validate_state_sync_event(&event(3, 1), 1, 1_000, None),
+            Err(StateSyncRejection::OutOfSequence { expected: 2, got: 3 })
+        );
+        assert!(validate_state_sync_event(&event(2, 1), 1, 1_000, Some("137")).is_ok());
+        assert!(matches!(
+            validate_state_sync_event(&event(2, 1), 1, 1_000, Some("80002")),
+            Err(StateSyncRejection::WrongChain { id: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_sorts_skips_and_stops_like_bor() {
+        // Heimdall pages may be out of order and repeat committed IDs.
+        let events = vec![event(6, 10), event(4, 10), event(3, 10), event(5, 1_000), event(7, 10)];
+        let ids: Vec<u64> = select_state_sync_events(events, 3, 1_000, Some("137"))
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        // 3 is already committed; 5 is too recent, which also drops 6 and 7.
+        assert_eq!(ids, vec![4]);
+
+        let gap = vec![event(1, 1), event(2, 1), event(4, 1)];
+        assert_eq!(select_state_sync_events(gap, 0, 1_000, None).len(), 2);
+    }
+
+    #[test]
+    fn test_to_time_per_fork() {
+        use bor_chainspec::{BorHardfork, MainnetBorHardforks};
+        let indore = BorHardfork::Indore.mainnet_block();
+
+        // Pre-Indore: the previous sprint start's timestamp.
+        assert_eq!(state_sync_to_time(&MainnetBorHardforks, indore - 64, 5_000, 4_800), 4_800);
+        // From Indore: the block's timestamp minus the state sync delay.
+        assert_eq!(
+            state_sync_to_time(&MainnetBorHardforks, indore, 5_000, 4_800),
+            5_000 - STATE_SYNC_DELAY
+        );
+    }
+
+    #[test]
+    fn test_events_before_record_time() {
+        let pending = PendingStateSyncs {
+            block_number: 32,
+            records: vec![event(1, 10), event(2, 20), event(3, 30)],
+        };
+        assert_eq!(pending.events_before(20).len(), 1);
+        assert_eq!(pending.events_before(31).len(), 3);
+        assert_eq!(pending.events()[1], (U256::from(2), Bytes::from(2u64.to_be_bytes().to_vec())));
+    }
+
+    #[test]
+    fn test_overlay_only_ahead_of_head() {
+        let overlay = PendingStateOverlay::new();
+        assert!(overlay.events_after(10).is_empty());
+
+        overlay.clone().update(PendingStateSyncs { block_number: 16, records: vec![event(1, 1)] });
+        assert_eq!(overlay.events_after(15).len(), 1);
+        assert!(overlay.events_after(16).is_empty());
+
+        overlay.clear();
+        assert!(overlay.pending().is_none());
+    }
+
+    #[test]
+    fn test_apply_pending_state_syncs() {
+        // PUSH1 4 CALLDATALOAD PUSH1 0 SSTORE STOP: stores the state ID in slot 0.
+        let code =
+            Bytecode::new_raw(Bytes::from_static(&[0x60, 0x04, 0x35, 0x60, 0x00, 0x55, 0x00]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(STATE_RECEIVER_ADDRESS, AccountInfo::from_bytecode(code));
+        let mut evm = EthEvmFactory::default().create_evm(db.clone(), EvmEnv::default());
+
+        let events = vec![(U256::from(7), Bytes::new()), (U256::from(8), Bytes::new())];
+        assert_eq!(apply_pending_state_syncs(&mut evm, &events).unwrap(), 2);
+        let slot = evm.db_mut().storage(STATE_RECEIVER_ADDRESS, U256::ZERO).unwrap();
+        assert_eq!(slot, U256::from(8));
+
+        // The changes leave the state receiver with the last ID.
+        let changes =
+            state_sync_changes_on(db, &EthEvmFactory::default(), EvmEnv::default(), &events)
+                .unwrap();
+        let receiver = &changes.state[&STATE_RECEIVER_ADDRESS];
+        assert_eq!(receiver.storage[&U256::ZERO].present_value, U256::from(8));
+    }
+}
@@ -0,0 +1,193 @@
+//! State changes Bor makes after a block's transactions.
+//!
+//! Besides the system calls, two things differ from Ethereum once a block's
+//! transactions ran:
+//!
+//! - **Base fee.** Bor does not burn the base fee; from London on it credits
+//!   `gas_used * base_fee` of each transaction to the block's `burntContract` as the
+//!   transaction is committed, so later transactions of the block see the balance.
+//! - **Block alloc.** The chain config's `blockAlloc` replaces contract code at
+//!   given blocks, after the system calls, as Bor's `changeContractCodeIfNeeded`
+//!   does: the code is always set, the balance only if the account has none.
+
+use alloy_primitives::{Address, Bytes, U256};
+use bor_chainspec::{BorConfig, ForkValue, ScheduleError};
+use reth_evm::{block::BlockExecutionError, Database, Evm};
+use revm::{
+    database::State,
+    state::{Account, Bytecode},
+    Database as _, DatabaseCommit,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tracing::{info, trace};
+
+/// Account set by a `blockAlloc` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AllocAccount {
+    /// Code the account gets.
+    #[serde(default)]
+    pub code: Bytes,
+    /// Balance the account gets if it has none.
+    #[serde(default)]
+    pub balance: U256,
+}
+
+/// Accounts to set, by the block at whose end they are set.
+pub type BlockAlloc = BTreeMap<u64, BTreeMap<Address, AllocAccount>>;
+
+/// Bor's post-execution changes of a chain.
+///
+/// Like [`BorSystemCaller`](crate::BorSystemCaller) it holds only chain config,
+/// so the factory keeps one and every executor borrows it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BorPostExecution {
+    burnt_contract: ForkValue<Address>,
+    block_alloc: BlockAlloc,
+}
+
+impl BorPostExecution {
+    /// The post-execution changes of a chain with `config`, from its `burntContract` and
+    /// `blockAlloc`.
+    ///
+    /// Without a `burntContract` the base fee is burnt as on Ethereum.
+    pub fn from_config(config: &BorConfig) -> Result<Self, ScheduleError> {
+        let block_alloc = config
+            .block_alloc
+            .iter()
+            .map(|(&block, accounts)| {
+                let accounts = serde_json::from_value(accounts.clone()).map_err(|e| {
+                    ScheduleError::Invalid { name: "blockAlloc", reason: e.to_string() }
+                })?;
+                Ok((block, accounts))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::default()
+            .with_burnt_contract(config.burnt_contract.clone())
+            .with_block_alloc(block_alloc))
+    }
+
+    /// Credit the base fee to `burnt_contract`, by block.
+    pub fn with_burnt_contract(mut self, burnt_contract: ForkValue<Address>) -> Self {
+        self.burnt_contract = burnt_contract;
+        self
+    }
+
+    /// Set the accounts of `block_alloc` at the end of their blocks.
+    pub fn with_block_alloc(mut self, block_alloc: BlockAlloc) -> Self {
+        self.block_alloc = block_alloc;
+        self
+    }
+
+    /// The contract the base fee of block `number` is credited to, if any.
+    pub fn burnt_contract(&self, number: u64) -> Option<Address> {
+        self.burnt_contract.value_at(number).copied()
+    }
+
+    /// Credit the base fee of a transaction of the current block that used `gas_used`
+    /// gas to the block's burnt contract.
+    pub fn credit_base_fee<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        gas_used: u64,
+    ) -> Result<(), BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let number = evm.block().number.saturating_to::<u64>();
+        let Some(contract) = self.burnt_contract(number) else { return Ok(()) };
+        let amount = u128::from(gas_used) * u128::from(evm.block().basefee);
+        if amount == 0 {
+            return Ok(());
+        }
+
+        trace!(target: "bor::executor", number, %contract, amount, "crediting base fee");
+        evm.db_mut()
+            .increment_balances([(contract, amount)])
+            .map_err(|e| BlockExecutionError::msg(format!("base fee credit failed: {e}")))
+    }
+
+    /// Set the `blockAlloc` accounts of the current block, if it has any.
+    pub fn apply_block_alloc<'db, DB, E>(&self, evm: &mut E) -> Result<(), BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let number = evm.block().number.saturating_to::<u64>();
+        let Some(accounts) = self.block_alloc.get(&number) else { return Ok(()) };
+
+        let state = evm.db_mut();
+        let mut changes = Vec::with_capacity(accounts.len());
+        for (&address, alloc) in accounts {
+            info!(target: "bor::executor", number, %address, "changing contract code");
+            let mut info = state
+                .basic(address)
+                .map_err(|e| BlockExecutionError::msg(format!("block alloc failed: {e}")))?
+                .unwrap_or_default();
+            let code = Bytecode::new_raw(alloc.code.clone());
+            info.code_hash = code.hash_slow();
+            info.code = Some(code);
+            if info.balance.is_zero() {
+                info.balance = alloc.balance;
+            }
+            let mut account = Account::from(info);
+            account.mark_touch();
+            changes.push((address, account));
+        }
+        state.commit(changes.into_iter().collect());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_burnt_contract_by_chain() {
+        let mainnet = BorPostExecution::from_config(BorConfig::mainnet()).unwrap();
+        assert_eq!(mainnet.burnt_contract(23_849_999), None);
+        assert_eq!(
+            mainnet.burnt_contract(50_522_999),
+            Some(address!("70bca57f4579f58670ab2d18ef16e02c17553c38"))
+        );
+        assert_eq!(
+            mainnet.burnt_contract(50_523_000),
+            Some(address!("7A8ed27F4C30512326878652d20fC85727401854"))
+        );
+
+        let amoy = BorPostExecution::from_config(BorConfig::amoy()).unwrap();
+        let dead = address!("000000000000000000000000000000000000dead");
+        assert_eq!(amoy.burnt_contract(0), Some(dead));
+
+        let custom = BorPostExecution::from_config(&BorConfig::default()).unwrap();
+        assert_eq!(custom.burnt_contract(u64::MAX), None);
+    }
+
+    #[test]
+    fn test_block_alloc_from_config() {
+        let config = BorConfig::from_value(&serde_json::json!({
+            "blockAlloc": {
+                "64": {
+                    "0000000000000000000000000000000000001010": { "balance": "0x5", "code": "0x00" }
+                }
+            }
+        }))
+        .unwrap();
+        let post = BorPostExecution::from_config(&config).unwrap();
+        let account = &post.block_alloc[&64][&address!("0000000000000000000000000000000000001010")];
+        assert_eq!(account.balance, U256::from(5));
+        assert_eq!(account.code, Bytes::from_static(&[0x00]));
+
+        let malformed = BorConfig::from_value(&serde_json::json!({
+            "blockAlloc": { "64": { "0x1010": { "code": "0x00" } } }
+        }))
+        .unwrap();
+        assert!(matches!(
+            BorPostExecution::from_config(&malformed),
+            Err(ScheduleError::Invalid { name: "blockAlloc", .. })
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+//! Pre-simulation of the system calls of a sprint start.
+//!
+//! A producer has two seconds to build the first block of a sprint, and the
+//! `commitSpan` and `onStateReceive` calls at its end take a good part of them when
+//! many state sync events are due. The calls depend on little besides the parent
+//! state, the block's environment and the events, all known before the block
+//! is built, so [`SprintPresimulator::presimulate`] can run them in the background as
+//! soon as the parent is the tip, on a throwaway database over the tip state.
+//!
+//! The executor of the block then commits the recorded states instead of running
+//! the calls again, if they were simulated on the same parent, with the same block
+//! environment and calls, and none of the block's transactions changed an account
+//! the calls read or wrote. Otherwise it runs the calls as usual, so a stale or
+//! conflicting simulation only costs the time spent on it.
+
+use crate::{
+    block_executor::{BorSystemCaller, SprintContext},
+    evm_config::BorEvmFactory,
+    sprint_summary::SystemCallTimings,
+};
+use alloy_primitives::{Address, Bytes, B256, U256};
+use reth_evm::{block::BlockExecutionError, Database, Evm, EvmEnv, EvmFactory};
+use reth_revm::database::StateProviderDatabase;
+use reth_storage_api::StateProviderFactory;
+use revm::{database::State, state::EvmState, DatabaseCommit};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::debug;
+
+/// What the system calls of a block were simulated for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PresimulationKey {
+    parent_hash: B256,
+    number: u64,
+    timestamp: U256,
+    beneficiary: Address,
+    difficulty: U256,
+    prevrandao: Option<B256>,
+    gas_limit: u64,
+    basefee: u64,
+    commit_span: Option<(U256, Bytes)>,
+    state_syncs: Vec<(U256, Bytes)>,
+}
+
+impl PresimulationKey {
+    fn new<E: Evm>(evm: &E, parent_hash: B256, ctx: SprintContext<'_>) -> Self {
+        let block = evm.block();
+        Self {
+            parent_hash,
+            number: block.number.saturating_to(),
+            timestamp: block.timestamp,
+            beneficiary: block.beneficiary,
+            difficulty: block.difficulty,
+            prevrandao: block.prevrandao,
+            gas_limit: block.gas_limit,
+            basefee: block.basefee,
+            commit_span: ctx.commit_span.map(|c| (c.span_id, c.validator_bytes.clone())),
+            state_syncs: ctx.state_syncs.to_vec(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Presimulated {
+    key: PresimulationKey,
+    /// Whether the first state is the one of `commitSpan`.
+    commits_span: bool,
+    /// The state of each call, in order.
+    states: Vec<EvmState>,
+}
+
+/// System calls of the next sprint start, simulated ahead of the block.
+///
+/// Holds the last simulation only. Clones share it, so the task that simulates and
+/// the executors that build blocks each keep one.
+#[derive(Debug, Clone, Default)]
+pub struct SprintPresimulator {
+    last: Arc<Mutex<Option<Presimulated>>>,
+}
+
+impl PartialEq for SprintPresimulator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.last, &other.last)
+    }
+}
+
+impl Eq for SprintPresimulator {}
+
+impl SprintPresimulator {
+    /// Create a presimulator with nothing simulated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Simulate the calls of `ctx` for the block `evm` is set up for, on top of
+    /// `parent_hash`, and keep their state for the block's executor.
+    ///
+    /// `evm` must run on the parent's state; its database is left with the calls
+    /// committed and should be thrown away. Returns the number of calls simulated.
+    pub fn presimulate<'db, DB, E>(
+        &self,
+        caller: &BorSystemCaller,
+        evm: &mut E,
+        parent_hash: B256,
+        ctx: SprintContext<'_>,
+    ) -> Result<usize, BlockExecutionError>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let key = PresimulationKey::new(evm, parent_hash, ctx);
+        let states = caller.simulate_sprint_boundary(evm, ctx)?;
+        let calls = states.len();
+        let commits_span = ctx.commit_span.is_some();
+        *self.lock() = Some(Presimulated { key, commits_span, states });
+        Ok(calls)
+    }
+
+    /// [`presimulate`](Self::presimulate) under `env`, on the state of block
+    /// `parent_hash` as `provider` has it, in an EVM of `evm_factory`: the one the
+    /// block's executor will use, so that custom precompiles are simulated too.
+    pub fn presimulate_from<P: StateProviderFactory, F: BorEvmFactory>(
+        &self,
+        provider: &P,
+        evm_factory: &F,
+        caller: &BorSystemCaller,
+        env: EvmEnv,
+        parent_hash: B256,
+        ctx: SprintContext<'_>,
+    ) -> Result<usize, BlockExecutionError> {
+        let state =
+            provider.history_by_block_hash(parent_hash).map_err(BlockExecutionError::other)?;
+        let mut db = State::builder().with_database(StateProviderDatabase::new(state)).build();
+        let mut evm = evm_factory.create_evm(&mut db, env);
+        self.presimulate(caller, &mut evm, parent_hash, ctx)
+    }
+
+    /// Whether a simulation on top of `parent_hash` is held.
+    pub fn is_presimulated(&self, parent_hash: B256) -> bool {
+        self.lock().as_ref().is_some_and(|last| last.key.parent_hash == parent_hash)
+    }
+
+    /// Drop the held simulation.
+    pub fn clear(&self) {
+        self.lock().take();
+    }
+
+    /// Commit the simulated calls of `ctx` to the block `evm` executes, on top of
+    /// `parent_hash`, if the simulation still holds for it.
+    ///
+    /// Returns `None`, leaving the block untouched, if nothing was simulated for the
+    /// block, or if it was but the block's own changes touched an account the calls
+    /// read or wrote. Those changes are only visible with bundle updates enabled on
+    /// the block's [`State`]; without them nothing is reused.
+    pub fn apply<'db, DB, E>(
+        &self,
+        evm: &mut E,
+        parent_hash: B256,
+        ctx: SprintContext<'_>,
+    ) -> Option<SystemCallTimings>
+    where
+        DB: Database + 'db,
+        E: Evm<DB = &'db mut State<DB>>,
+    {
+        let key = PresimulationKey::new(evm, parent_hash, ctx);
+        let guard = self.lock();
+        let last = guard.as_ref().filter(|last| last.key == key)?;
+        let Some(transitions) = evm.db_mut().transition_state.as_ref() else {
+            return None;
+        };
+        let conflict = last
+            .states
+            .iter()
+            .flat_map(|state| state.keys())
+            .find(|address| transitions.transitions.contains_key(*address));
+        if let Some(address) = conflict {
+            debug!(
+                target: "bor::executor",
+                number = key.number,
+                %address,
+                "block changed state of the pre-simulated system calls"
+            );
+            return None;
+        }
+
+        let mut timings = SystemCallTimings::default();
+        let mut states = last.states.iter();
+        if last.commits_span {
+            let started = Instant::now();
+            if let Some(state) = states.next() {
+                evm.db_mut().commit(state.clone());
+            }
+            timings.commit_span = started.elapsed();
+        }
+        let started = Instant::now();
+        for state in states {
+            evm.db_mut().commit(state.clone());
+        }
+        timings.state_syncs = started.elapsed();
+        debug!(
+            target: "bor::executor",
+            number = key.number,
+            calls = last.states.len(),
+            "committed pre-simulated system calls"
+        );
+        Some(timings)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Presimulated>> {
+        self.last.lock().expect("presimulator lock poisoned")
+    }
+}
@@ -0,0 +1,186 @@
+//! Gas profile of the state sync events relayed at sprint starts.
+//!
+//! Every so often a sprint start takes hundreds of milliseconds longer than the
+//! others, and the cause is one bridge payload whose receiver does far more work than
+//! usual. [`SprintSummary`](crate::SprintSummary) only tells the time spent in all
+//! `onStateReceive` calls together. [`StateSyncProfiler`] records the gas, storage
+//! writes and time of each event from the result of its call, and logs the events
+//! above a gas threshold with what their call touched.
+
+use alloy_primitives::Address;
+use revm::{context::result::ExecutionResult, state::EvmState};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+/// Number of events a [`StateSyncProfiler`] keeps the profile of.
+pub const DEFAULT_PROFILE_HISTORY: usize = 1024;
+
+/// Cost of relaying one state sync event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSyncProfile {
+    /// Block the event was committed in.
+    pub block: u64,
+    /// State sync ID.
+    pub state_id: u64,
+    /// Gas used by the `onStateReceive` call.
+    pub gas_used: u64,
+    /// Storage slots the call changed.
+    pub storage_writes: usize,
+    /// Accounts the call touched.
+    pub accounts: usize,
+    /// The account with the most storage writes, with their number.
+    pub busiest: Option<(Address, usize)>,
+    /// Time spent in the call.
+    pub elapsed: Duration,
+}
+
+impl StateSyncProfile {
+    /// The profile of the call relaying event `state_id` in block `block`, from its
+    /// result and the state it left.
+    pub fn new(
+        block: u64,
+        state_id: u64,
+        result: &ExecutionResult,
+        state: &EvmState,
+        elapsed: Duration,
+    ) -> Self {
+        let writes: Vec<(Address, usize)> = state
+            .iter()
+            .map(|(address, account)| {
+                (*address, account.storage.values().filter(|slot| slot.is_changed()).count())
+            })
+            .collect();
+        let storage_writes = writes.iter().map(|(_, count)| count).sum();
+        let busiest = writes.into_iter().filter(|(_, count)| *count > 0).max_by_key(|w| w.1);
+        Self {
+            block,
+            state_id,
+            gas_used: result.gas_used(),
+            storage_writes,
+            accounts: state.len(),
+            busiest,
+            elapsed,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Profiles {
+    recent: VecDeque<StateSyncProfile>,
+    capacity: usize,
+}
+
+/// Records the cost of each state sync event and logs those above a gas threshold.
+///
+/// Clones share the same record, and compare equal only to each other.
+#[derive(Debug, Clone)]
+pub struct StateSyncProfiler {
+    gas_threshold: u64,
+    profiles: Arc<Mutex<Profiles>>,
+}
+
+impl PartialEq for StateSyncProfiler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.profiles, &other.profiles)
+    }
+}
+
+impl Eq for StateSyncProfiler {}
+
+impl StateSyncProfiler {
+    /// Create a profiler logging events that use more than `gas_threshold` gas.
+    pub fn new(gas_threshold: u64) -> Self {
+        Self::with_history(gas_threshold, DEFAULT_PROFILE_HISTORY)
+    }
+
+    /// Create a profiler keeping the profiles of the last `capacity` events.
+    pub fn with_history(gas_threshold: u64, capacity: usize) -> Self {
+        let profiles = Profiles { recent: VecDeque::with_capacity(capacity), capacity };
+        Self { gas_threshold, profiles: Arc::new(Mutex::new(profiles)) }
+    }
+
+    /// Gas above which an event is logged.
+    pub const fn gas_threshold(&self) -> u64 {
+        self.gas_threshold
+    }
+
+    /// Record `profile`, logging it if it is above the threshold.
+    ///
+    /// Returns whether it was.
+    pub fn record(&self, profile: StateSyncProfile) -> bool {
+        let outlier = profile.gas_used > self.gas_threshold;
+        if outlier {
+            warn!(
+                target: "bor::profile",
+                block = profile.block,
+                state_id = profile.state_id,
+                gas_used = profile.gas_used,
+                storage_writes = profile.storage_writes,
+                accounts = profile.accounts,
+                busiest = ?profile.busiest,
+                elapsed = ?profile.elapsed,
+                threshold = self.gas_threshold,
+                "expensive state sync event"
+            );
+        }
+        let mut profiles = self.profiles.lock().expect("profiler lock poisoned");
+        if profiles.capacity > 0 {
+            if profiles.recent.len() == profiles.capacity {
+                profiles.recent.pop_front();
+            }
+            profiles.recent.push_back(profile);
+        }
+        outlier
+    }
+
+    /// The profiles recorded, oldest first.
+    pub fn profiles(&self) -> Vec<StateSyncProfile> {
+        self.profiles.lock().expect("profiler lock poisoned").recent.iter().cloned().collect()
+    }
+
+    /// The recorded profiles above the threshold, oldest first.
+    pub fn outliers(&self) -> Vec<StateSyncProfile> {
+        self.profiles().into_iter().filter(|p| p.gas_used > self.gas_threshold).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(state_id: u64, gas_used: u64) -> StateSyncProfile {
+        StateSyncProfile {
+            block: 64,
+            state_id,
+            gas_used,
+            storage_writes: 0,
+            accounts: 0,
+            busiest: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_outliers_above_threshold() {
+        let profiler = StateSyncProfiler::new(100_000);
+        assert!(!profiler.record(profile(1, 50_000)));
+        assert!(!profiler.record(profile(2, 100_000)));
+        assert!(profiler.record(profile(3, 900_000)));
+        assert_eq!(profiler.profiles().len(), 3);
+        assert_eq!(profiler.outliers(), vec![profile(3, 900_000)]);
+    }
+
+    #[test]
+    fn test_history_bounded() {
+        let profiler = StateSyncProfiler::with_history(0, 2);
+        for id in 1..=3 {
+            profiler.record(profile(id, 1));
+        }
+        let ids: Vec<_> = profiler.profiles().iter().map(|p| p.state_id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert_eq!(profiler.clone(), profiler);
+        assert_ne!(StateSyncProfiler::new(0), profiler);
+    }
+}
@@ -0,0 +1,202 @@
+//! Where the system calls of a block come from.
+//!
+//! A block that starts a span commits it through `commitSpan`, and a block that starts a
+//! sprint relays the state sync events Heimdall recorded since the last one through
+//! `onStateReceive`. Neither is part of the block itself: every node reads them from
+//! Heimdall. [`BorEvmConfig`](crate::BorEvmConfig) asks a [`SprintContextSource`] for
+//! them when it builds the execution context of a block, for imported blocks and
+//! payloads alike.
+//!
+//! Execution is synchronous, so sources answer from data fetched ahead of time.
+//! [`CachedSprintContext`] reads spans from the span cache and state sync events from the
+//! [`PendingStateOverlay`], of which a sprint start commits those recorded before the
+//! bound its timestamp sets. A block whose data has not been fetched yet fails with
+//! [`SprintContextError`] instead of executing without its system calls, which would
+//! give it a wrong state root.
+
+use crate::block_executor::PendingCommitSpan;
+use crate::pending_state::{state_sync_to_time, PendingStateOverlay};
+use alloy_primitives::U256;
+use bor_chainspec::{BorHardforks, ScheduleError};
+use bor_primitives::encode_validator_bytes;
+use heimdall_client::{SharedSpanCache, StateSyncEvent};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Why the execution context of a block cannot be built.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SprintContextError {
+    /// The chain's `bor` config cannot tell the sprint of the block.
+    #[error(transparent)]
+    Schedule(#[from] ScheduleError),
+    /// The span the block commits has not been fetched.
+    #[error("span {span_id} committed in block {block} is not cached")]
+    SpanUnavailable {
+        /// The block committing the span.
+        block: u64,
+        /// The span it commits.
+        span_id: u64,
+    },
+    /// The state sync events the block relays have not been fetched.
+    #[error("state sync events of sprint start {block} are not fetched")]
+    StateSyncsUnavailable {
+        /// The sprint start relaying the events.
+        block: u64,
+    },
+}
+
+/// The Heimdall data of the system calls of a block.
+pub trait SprintContextSource: Debug + Send + Sync {
+    /// The span block `number` commits, if it starts one.
+    fn commit_span(&self, number: u64) -> Result<Option<PendingCommitSpan>, SprintContextError>;
+
+    /// The state sync events sprint start `number`, with timestamp `timestamp`, relays, in
+    /// ascending ID order.
+    fn state_syncs(
+        &self,
+        number: u64,
+        timestamp: u64,
+    ) -> Result<Vec<StateSyncEvent>, SprintContextError>;
+}
+
+/// A [`SprintContextSource`] shared between clones of the EVM config.
+pub type SharedSprintContextSource = Arc<dyn SprintContextSource>;
+
+/// Reads spans from the span cache and state sync events from the pending overlay.
+#[derive(Debug, Clone)]
+pub struct CachedSprintContext<C> {
+    /// Where spans start.
+    chain_spec: Arc<C>,
+    /// Spans fetched from Heimdall.
+    spans: SharedSpanCache,
+    /// State sync events fetched for the next sprint start.
+    pending: PendingStateOverlay,
+}
+
+impl<C> CachedSprintContext<C> {
+    /// Read the spans of `chain_spec` from `spans` and state sync events from `pending`.
+    pub fn new(chain_spec: Arc<C>, spans: SharedSpanCache, pending: PendingStateOverlay) -> Self {
+        Self { chain_spec, spans, pending }
+    }
+}
+
+impl<C: BorHardforks + Debug + Send + Sync> SprintContextSource for CachedSprintContext<C> {
+    fn commit_span(&self, number: u64) -> Result<Option<PendingCommitSpan>, SprintContextError> {
+        let span_id = self.chain_spec.bor_span_id(number);
+        if number == 0 || self.chain_spec.bor_span_start(span_id) != number {
+            return Ok(None);
+        }
+        let mut spans = self.spans.lock().expect("span cache lock poisoned");
+        let span = spans
+            .get(span_id)
+            .ok_or(SprintContextError::SpanUnavailable { block: number, span_id })?;
+        Ok(Some(PendingCommitSpan {
+            span_id: U256::from(span.id),
+            validator_bytes: encode_validator_bytes(&span.validator_set.validators).into(),
+        }))
+    }
+
+    /// Before Indore the events of a sprint start are bounded by the timestamp of the
+    /// previous one, which is not cached, so only sprint starts from Indore are answered.
+    fn state_syncs(
+        &self,
+        number: u64,
+        timestamp: u64,
+    ) -> Result<Vec<StateSyncEvent>, SprintContextError> {
+        let unavailable = SprintContextError::StateSyncsUnavailable { block: number };
+        if !self.chain_spec.is_indore_active_at_block(number) {
+            return Err(unavailable);
+        }
+        match self.pending.pending() {
+            Some(pending) if pending.block_number == number => {
+                // The previous sprint start's time only bounds events before Indore.
+                let to_time = state_sync_to_time(&*self.chain_spec, number, timestamp, 0);
+                Ok(pending.events_before(to_time))
+            }
+            _ => Err(unavailable),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pending_state::PendingStateSyncs;
+    use alloy_primitives::{Address, Bytes, B256};
+    use bor_chainspec::{BorHardfork, MainnetBorHardforks};
+    use bor_primitives::{span_start_block, Span, Validator, ValidatorSet};
+    use heimdall_client::SpanCache;
+    use std::sync::Mutex;
+
+    fn span(id: u64) -> Span {
+        let validator = Validator {
+            id: 1,
+            address: Address::with_last_byte(1),
+            voting_power: 100,
+            signer: Address::with_last_byte(1),
+            proposer_priority: 0,
+        };
+        Span {
+            id,
+            start_block: span_start_block(id),
+            end_block: span_start_block(id + 1) - 1,
+            validator_set: ValidatorSet {
+                validators: vec![validator.clone()],
+                proposer: Some(validator.clone()),
+            },
+            selected_producers: vec![validator],
+            bor_chain_id: "137".to_string(),
+        }
+    }
+
+    fn source() -> CachedSprintContext<MainnetBorHardforks> {
+        let spans = Arc::new(Mutex::new(SpanCache::new(4)));
+        spans.lock().unwrap().insert(span(1));
+        CachedSprintContext::new(Arc::new(MainnetBorHardforks), spans, PendingStateOverlay::new())
+    }
+
+    #[test]
+    fn test_commit_span_at_span_start() {
+        let source = source();
+        let commit = source.commit_span(256).unwrap().unwrap();
+        assert_eq!(commit.span_id, U256::from(1));
+        let validator_bytes = encode_validator_bytes(&span(1).validator_set.validators);
+        assert_eq!(commit.validator_bytes, Bytes::from(validator_bytes));
+
+        assert_eq!(source.commit_span(0), Ok(None));
+        assert_eq!(source.commit_span(320), Ok(None));
+        assert_eq!(
+            source.commit_span(6656),
+            Err(SprintContextError::SpanUnavailable { block: 6656, span_id: 2 })
+        );
+    }
+
+    #[test]
+    fn test_state_syncs_of_pending_sprint_start() {
+        let record = |id, time| StateSyncEvent {
+            id,
+            contract: Address::with_last_byte(9),
+            data: Bytes::from_static(&[1]),
+            tx_hash: B256::ZERO,
+            log_index: 0,
+            bor_chain_id: "137".to_string(),
+            time,
+        };
+        let source = source();
+        let sprint_start = BorHardfork::Indore.mainnet_block();
+        let unavailable = SprintContextError::StateSyncsUnavailable { block: sprint_start };
+        assert_eq!(source.state_syncs(sprint_start, 10_000), Err(unavailable.clone()));
+
+        let records = vec![record(7, 1_000), record(8, 9_900)];
+        source.pending.update(PendingStateSyncs { block_number: sprint_start, records });
+        // Events recorded within the confirmation delay wait for the next sprint.
+        let events = source.state_syncs(sprint_start, 10_000).unwrap();
+        assert_eq!(events.iter().map(|event| event.id).collect::<Vec<_>>(), vec![7]);
+        let next = SprintContextError::StateSyncsUnavailable { block: sprint_start + 16 };
+        assert_eq!(source.state_syncs(sprint_start + 16, 10_000), Err(next));
+
+        // Before Indore the bound is the previous sprint start's time, which is not cached.
+        let early = SprintContextError::StateSyncsUnavailable { block: 64 };
+        assert_eq!(source.state_syncs(64, 10_000), Err(early));
+    }
+}
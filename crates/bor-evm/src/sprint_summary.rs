@@ -0,0 +1,71 @@
+//! One log line per sprint.
+//!
+//! During an incident operators want to know, sprint by sprint, who produced
+//! the first block, which state sync events and span it committed, and where
+//! the time went. The executor gathers that into a [`SprintSummary`] while it
+//! finishes a sprint's first block and logs it as a single INFO line under the
+//! `bor::sprint` target, so one `grep` gives the whole history.
+
+use alloy_primitives::Address;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use tracing::info;
+
+/// The producer of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockProducer {
+    /// The signer of the block.
+    pub address: Address,
+    /// Its distance from the sprint's proposer: 0 in turn, then one per backup.
+    pub succession: usize,
+}
+
+/// Time spent in the system calls of a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemCallTimings {
+    /// Time spent in `commitSpan`.
+    pub commit_span: Duration,
+    /// Time spent in all `onStateReceive` calls.
+    pub state_syncs: Duration,
+}
+
+/// What happened at the start of a sprint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SprintSummary {
+    /// The first block of the sprint.
+    pub number: u64,
+    /// Its producer, if known.
+    pub producer: Option<BlockProducer>,
+    /// IDs of the state sync events it committed, if any.
+    pub state_syncs: Option<RangeInclusive<u64>>,
+    /// ID of the span it committed, if any.
+    pub span_id: Option<u64>,
+    /// Time spent in its system calls.
+    pub system_calls: SystemCallTimings,
+    /// Time from the start of its execution, whether built or imported, to its end.
+    pub execution: Duration,
+}
+
+impl SprintSummary {
+    /// Log the summary.
+    pub fn log(&self) {
+        let (first_event, last_event) = match &self.state_syncs {
+            Some(ids) => (Some(*ids.start()), Some(*ids.end())),
+            None => (None, None),
+        };
+        info!(
+            target: "bor::sprint",
+            number = self.number,
+            producer = ?self.producer.map(|p| p.address),
+            succession = ?self.producer.map(|p| p.succession),
+            events = self.state_syncs.as_ref().map_or(0, |ids| ids.end() - ids.start() + 1),
+            first_event = ?first_event,
+            last_event = ?last_event,
+            span = ?self.span_id,
+            commit_span_us = self.system_calls.commit_span.as_micros() as u64,
+            state_syncs_us = self.system_calls.state_syncs.as_micros() as u64,
+            execution_ms = self.execution.as_millis() as u64,
+            "sprint"
+        );
+    }
+}
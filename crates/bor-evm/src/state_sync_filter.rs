@@ -0,0 +1,131 @@
+//! Embedder hook over the state sync events a block commits.
+//!
+//! Public Bor chains relay every event Heimdall serves. Chains built on boreth may
+//! want less, e.g. a private chain that only accepts deposits from its own bridge
+//! contracts. A [`StateSyncFilter`] given to the
+//! [`BorSystemCaller`](crate::BorSystemCaller) sees each event after the chain's
+//! record overrides and decides whether it is applied as is, skipped, or applied with
+//! other data. Without one every event passes, as with [`PassThrough`].
+//!
+//! # Determinism
+//!
+//! The filter decides what state a block commits, so every node of the chain must run
+//! the same filter, configured the same way, or their state roots diverge. A filter
+//! must decide from its arguments alone: no clock, randomness, network access or
+//! state carried between calls. The same event is filtered when a block is built,
+//! imported, re-executed and presimulated, and must get the same decision each time.
+//!
+//! The stock StateReceiver contract requires consecutive state sync IDs, so skipping
+//! an event makes every later one revert there. Chains that skip events need a
+//! receiver that tolerates gaps; otherwise replace the data of unwanted events with a
+//! payload their receiver ignores.
+
+use alloy_primitives::{Bytes, U256};
+use std::fmt::Debug;
+use std::sync::Arc;
+use tracing::debug;
+
+/// What to do with a state sync event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateSyncAction {
+    /// Relay the event as served.
+    Apply,
+    /// Do not relay the event.
+    Skip,
+    /// Relay the event with this data instead.
+    Replace(Bytes),
+}
+
+/// Decides, for each state sync event of a block, whether and how it is relayed.
+///
+/// See the [module docs](self) for what implementations must guarantee.
+pub trait StateSyncFilter: Debug + Send + Sync {
+    /// The action for event `state_id` carrying `data`, committed in block `number`.
+    fn filter(&self, number: u64, state_id: U256, data: &Bytes) -> StateSyncAction;
+}
+
+/// Relays every event as served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PassThrough;
+
+impl StateSyncFilter for PassThrough {
+    fn filter(&self, _number: u64, _state_id: U256, _data: &Bytes) -> StateSyncAction {
+        StateSyncAction::Apply
+    }
+}
+
+/// A [`StateSyncFilter`] shared between clones of a caller.
+///
+/// Two handles are equal if they share the same filter.
+#[derive(Debug, Clone)]
+pub struct SharedStateSyncFilter(Arc<dyn StateSyncFilter>);
+
+impl SharedStateSyncFilter {
+    /// Share `filter`.
+    pub fn new(filter: impl StateSyncFilter + 'static) -> Self {
+        Self(Arc::new(filter))
+    }
+
+    /// The events of block `number` to relay out of `events`, in the same order.
+    pub fn apply(&self, number: u64, events: &[(U256, Bytes)]) -> Vec<(U256, Bytes)> {
+        events
+            .iter()
+            .filter_map(|(state_id, data)| match self.0.filter(number, *state_id, data) {
+                StateSyncAction::Apply => Some((*state_id, data.clone())),
+                StateSyncAction::Skip => {
+                    debug!(target: "bor::executor", number, %state_id, "state sync skipped");
+                    None
+                }
+                StateSyncAction::Replace(data) => {
+                    debug!(target: "bor::executor", number, %state_id, "state sync replaced");
+                    Some((*state_id, data))
+                }
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for SharedStateSyncFilter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedStateSyncFilter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Skips odd IDs and empties the data of those above 3.
+    #[derive(Debug)]
+    struct EvenOnly;
+
+    impl StateSyncFilter for EvenOnly {
+        fn filter(&self, _number: u64, state_id: U256, _data: &Bytes) -> StateSyncAction {
+            if state_id.bit(0) {
+                StateSyncAction::Skip
+            } else if state_id > U256::from(3) {
+                StateSyncAction::Replace(Bytes::new())
+            } else {
+                StateSyncAction::Apply
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_skips_and_replaces_in_order() {
+        let events: Vec<_> =
+            (1..=4u64).map(|id| (U256::from(id), Bytes::from(vec![id as u8]))).collect();
+
+        assert_eq!(SharedStateSyncFilter::new(PassThrough).apply(64, &events), events);
+        assert_eq!(
+            SharedStateSyncFilter::new(EvenOnly).apply(64, &events),
+            vec![(U256::from(2), Bytes::from(vec![2])), (U256::from(4), Bytes::new())]
+        );
+
+        let filter = SharedStateSyncFilter::new(PassThrough);
+        assert_eq!(filter.clone(), filter);
+        assert_ne!(SharedStateSyncFilter::new(PassThrough), filter);
+    }
+}
@@ -2,18 +2,25 @@
 //!
 //! These are special EVM calls that are not triggered by transactions but by the
 //! consensus layer itself (e.g., at span boundaries or for state sync events).
+//!
+//! bor-geth runs them as plain message calls from [`SYSTEM_ADDRESS`]: its nonce is not
+//! incremented and no gas is bought from its balance. The account is part of the state
+//! root, so a call that changed either would fork the chain without failing a block
+//! until the roots are compared. [`pin_system_account`] undoes such changes and
+//! [`check_system_account`] detects them.
 
-use alloy_primitives::{Address, Bytes, U256};
+use alloy_primitives::{Address, Bytes, B256, U256};
 use alloy_sol_types::SolValue;
 use bor_chainspec::constants::{BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, SYSTEM_ADDRESS};
+use revm::state::AccountInfo;
 
 /// Function selector for `commitSpan(uint256,bytes)`.
 /// keccak256("commitSpan(uint256,bytes)")[:4]
-const COMMIT_SPAN_SELECTOR: [u8; 4] = [0x60, 0xcc, 0x80, 0xd8];
+pub const COMMIT_SPAN_SELECTOR: [u8; 4] = [0x60, 0xcc, 0x80, 0xd8];
 
 /// Function selector for `onStateReceive(uint256,bytes)`.
 /// keccak256("onStateReceive(uint256,bytes)")[:4]
-const ON_STATE_RECEIVE_SELECTOR: [u8; 4] = [0x26, 0xc5, 0x3b, 0xea];
+pub const ON_STATE_RECEIVE_SELECTOR: [u8; 4] = [0x26, 0xc5, 0x3b, 0xea];
 
 /// `commitSpan` is called at span boundaries to update the validator set.
 /// It calls the BorValidatorSet contract at `0x1000`.
@@ -27,8 +34,13 @@ pub struct CommitSpanCall {
 impl CommitSpanCall {
     /// Build the ABI-encoded call data for `commitSpan(uint256,bytes)`.
     pub fn call_data(&self) -> Bytes {
+        self.call_data_with_selector(COMMIT_SPAN_SELECTOR)
+    }
+
+    /// Build the call data with the same arguments for the function with `selector`.
+    pub fn call_data_with_selector(&self, selector: [u8; 4]) -> Bytes {
         let mut data = Vec::with_capacity(4 + 64);
-        data.extend_from_slice(&COMMIT_SPAN_SELECTOR);
+        data.extend_from_slice(&selector);
         let encoded = (self.span_id, self.validator_bytes.as_ref()).abi_encode_params();
         data.extend_from_slice(&encoded);
         Bytes::from(data)
@@ -57,8 +69,13 @@ pub struct StateReceiveCall {
 impl StateReceiveCall {
     /// Build the ABI-encoded call data for `onStateReceive(uint256,bytes)`.
     pub fn call_data(&self) -> Bytes {
+        self.call_data_with_selector(ON_STATE_RECEIVE_SELECTOR)
+    }
+
+    /// Build the call data with the same arguments for the function with `selector`.
+    pub fn call_data_with_selector(&self, selector: [u8; 4]) -> Bytes {
         let mut encoded_data = Vec::with_capacity(4 + 64);
-        encoded_data.extend_from_slice(&ON_STATE_RECEIVE_SELECTOR);
+        encoded_data.extend_from_slice(&selector);
         let params = (self.state_id, self.data.as_ref()).abi_encode_params();
         encoded_data.extend_from_slice(&params);
         Bytes::from(encoded_data)
@@ -91,6 +108,70 @@ pub fn prepare_state_sync_calls(
         .collect()
 }
 
+/// A change to the account of [`SYSTEM_ADDRESS`] that system calls must never make.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SystemAccountViolation {
+    /// The nonce changed.
+    #[error("system address nonce changed from {before} to {after}")]
+    Nonce {
+        /// Nonce before the system calls.
+        before: u64,
+        /// Nonce after them.
+        after: u64,
+    },
+    /// The balance dropped, as if gas had been charged.
+    #[error("system address balance dropped from {before} to {after}")]
+    Balance {
+        /// Balance before the system calls.
+        before: U256,
+        /// Balance after them.
+        after: U256,
+    },
+    /// The code changed.
+    #[error("system address code hash changed from {before} to {after}")]
+    Code {
+        /// Code hash before the system calls.
+        before: B256,
+        /// Code hash after them.
+        after: B256,
+    },
+}
+
+/// Restore in `after`, the system address's account as a system call left it, the
+/// nonce it had `before` the call, and its balance if the call lowered it.
+///
+/// Returns `true` if anything was restored. Value sent to the system address is kept.
+pub fn pin_system_account(after: &mut AccountInfo, before: Option<&AccountInfo>) -> bool {
+    let before = before.cloned().unwrap_or_default();
+    let changed = after.nonce != before.nonce || after.balance < before.balance;
+    after.nonce = before.nonce;
+    after.balance = after.balance.max(before.balance);
+    changed
+}
+
+/// Check that the system address's account kept its nonce and code, and no less
+/// balance, from `before` to `after` a block's system calls. A missing account is an
+/// empty one.
+pub fn check_system_account(
+    before: Option<&AccountInfo>,
+    after: Option<&AccountInfo>,
+) -> Result<(), SystemAccountViolation> {
+    let before = before.cloned().unwrap_or_default();
+    let after = after.cloned().unwrap_or_default();
+    if after.nonce != before.nonce {
+        return Err(SystemAccountViolation::Nonce { before: before.nonce, after: after.nonce });
+    }
+    if after.balance < before.balance {
+        let (before, after) = (before.balance, after.balance);
+        return Err(SystemAccountViolation::Balance { before, after });
+    }
+    if after.code_hash != before.code_hash {
+        let (before, after) = (before.code_hash, after.code_hash);
+        return Err(SystemAccountViolation::Code { before, after });
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +267,31 @@ mod tests {
         // Just verify it encodes without error and starts with selector
         assert_eq!(&data[..4], &COMMIT_SPAN_SELECTOR);
     }
+
+    #[test]
+    fn test_system_account_nonce_and_balance_are_pinned() {
+        let before = AccountInfo { nonce: 7, balance: U256::from(100), ..Default::default() };
+        let mut after = AccountInfo { nonce: 8, balance: U256::from(90), ..Default::default() };
+        assert_eq!(
+            check_system_account(Some(&before), Some(&after)),
+            Err(SystemAccountViolation::Nonce { before: 7, after: 8 })
+        );
+        assert!(pin_system_account(&mut after, Some(&before)));
+        assert_eq!(check_system_account(Some(&before), Some(&after)), Ok(()));
+        assert_eq!((after.nonce, after.balance), (7, U256::from(100)));
+
+        // Value sent to the system address is not a charge.
+        after.balance = U256::from(150);
+        assert!(!pin_system_account(&mut after, Some(&before)));
+        assert_eq!(after.balance, U256::from(150));
+
+        let mut created = AccountInfo { nonce: 1, ..Default::default() };
+        assert!(pin_system_account(&mut created, None));
+        assert_eq!(check_system_account(None, Some(&created)), Ok(()));
+        let coded = AccountInfo { code_hash: B256::with_last_byte(1), ..Default::default() };
+        assert!(matches!(
+            check_system_account(None, Some(&coded)),
+            Err(SystemAccountViolation::Code { .. })
+        ));
+    }
 }
@@ -0,0 +1,196 @@
+//! Reads of the ValidatorSet contract at historical blocks.
+//!
+//! Bor asks the contract at `0x1000` for the validators of a block with
+//! `getBorValidators(uint256)`, executed against the state of a given block
+//! rather than the state being built. [`read_bor_validators`] runs that call
+//! over any revm database, and [`HistoricalValidatorReader`] opens the state of
+//! an arbitrary block through a [`StateProviderFactory`] to run it there, so
+//! RPC and header verification never touch the live EVM of block execution.
+
+use crate::evm_config::BorEvmConfig;
+use alloy_consensus::Header;
+use alloy_eips::BlockHashOrNumber;
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::{sol, SolCall};
+use bor_chainspec::{
+    constants::{BOR_VALIDATOR_SET_ADDRESS, SYSTEM_ADDRESS},
+    BorHardforks,
+};
+use bor_primitives::Validator;
+use reth_chainspec::{EthChainSpec, EthereumHardforks};
+use reth_evm::{
+    eth::spec::EthExecutorSpec, ConfigureEvm, Database, EthEvmFactory, Evm, EvmEnv, EvmFactory,
+};
+use reth_revm::database::StateProviderDatabase;
+use reth_storage_api::{HeaderProvider, StateProviderFactory};
+use reth_storage_errors::provider::ProviderError;
+use revm::context::result::ExecutionResult;
+
+sol! {
+    /// Validators and voting powers the ValidatorSet contract holds for a block.
+    function getBorValidators(uint256 number) external view returns (address[], uint256[]);
+}
+
+/// Errors reading the ValidatorSet contract.
+#[derive(Debug, thiserror::Error)]
+pub enum ValidatorContractError {
+    #[error("unknown block {0}")]
+    UnknownBlock(BlockHashOrNumber),
+    #[error("provider error: {0}")]
+    Provider(String),
+    #[error("getBorValidators call failed: {0}")]
+    Call(String),
+    #[error("getBorValidators reverted: {0}")]
+    Reverted(Bytes),
+    #[error("invalid getBorValidators output: {0}")]
+    Decode(String),
+}
+
+/// Call data of `getBorValidators(number)`.
+pub fn get_bor_validators_call_data(number: u64) -> Bytes {
+    getBorValidatorsCall { number: U256::from(number) }.abi_encode().into()
+}
+
+/// Decode the output of `getBorValidators` into validators in contract order.
+///
+/// The contract returns no IDs or priorities; validators sign with their own address.
+pub fn decode_bor_validators(output: &[u8]) -> Result<Vec<Validator>, ValidatorContractError> {
+    let decoded = getBorValidatorsCall::abi_decode_returns(output)
+        .map_err(|e| ValidatorContractError::Decode(e.to_string()))?;
+    let (addresses, powers) = (decoded._0, decoded._1);
+    if addresses.len() != powers.len() {
+        return Err(ValidatorContractError::Decode(format!(
+            "{} validators but {} voting powers",
+            addresses.len(),
+            powers.len()
+        )));
+    }
+    addresses
+        .into_iter()
+        .zip(powers)
+        .map(|(address, power)| {
+            let voting_power = i64::try_from(power).map_err(|_| {
+                ValidatorContractError::Decode(format!("voting power of {address} overflows"))
+            })?;
+            Ok(Validator { id: 0, address, voting_power, signer: address, proposer_priority: 0 })
+        })
+        .collect()
+}
+
+/// Call `getBorValidators(number)` on `db` under `env`, without committing anything.
+pub fn read_bor_validators<DB>(
+    db: DB,
+    env: EvmEnv,
+    number: u64,
+) -> Result<Vec<Validator>, ValidatorContractError>
+where
+    DB: Database,
+{
+    let mut evm = EthEvmFactory::default().create_evm(db, env);
+    let result = evm
+        .transact_system_call(
+            SYSTEM_ADDRESS,
+            BOR_VALIDATOR_SET_ADDRESS,
+            get_bor_validators_call_data(number),
+        )
+        .map_err(|e| ValidatorContractError::Call(e.to_string()))?;
+    match result.result {
+        ExecutionResult::Success { output, .. } => decode_bor_validators(output.data()),
+        ExecutionResult::Revert { output, .. } => Err(ValidatorContractError::Reverted(output)),
+        ExecutionResult::Halt { reason, .. } => {
+            Err(ValidatorContractError::Call(format!("halted: {reason:?}")))
+        }
+    }
+}
+
+/// Reads the ValidatorSet contract at any block the provider has state for.
+#[derive(Debug, Clone)]
+pub struct HistoricalValidatorReader<P, C> {
+    provider: P,
+    evm_config: BorEvmConfig<C>,
+}
+
+impl<P, C> HistoricalValidatorReader<P, C>
+where
+    P: StateProviderFactory + HeaderProvider<Header = Header>,
+    C: EthExecutorSpec
+        + EthChainSpec<Header = Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
+        + 'static,
+{
+    /// Create a reader over `provider`, executing with `evm_config`'s rules.
+    pub fn new(provider: P, evm_config: BorEvmConfig<C>) -> Self {
+        Self { provider, evm_config }
+    }
+
+    /// The provider state is read from.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// The validators the contract holds for block `number`, read from the state after `at`.
+    ///
+    /// Bor verifies a block against the contract state of its parent, so `at` is usually
+    /// `number - 1`.
+    pub fn validators_at(
+        &self,
+        at: BlockHashOrNumber,
+        number: u64,
+    ) -> Result<Vec<Validator>, ValidatorContractError> {
+        let provider = |e: ProviderError| ValidatorContractError::Provider(e.to_string());
+        let (hash, header) = match at {
+            BlockHashOrNumber::Hash(hash) => (hash, self.provider.header(hash).map_err(provider)?),
+            BlockHashOrNumber::Number(number) => {
+                match self.provider.sealed_header(number).map_err(provider)? {
+                    Some(header) => (header.hash(), Some(header.into_header())),
+                    None => return Err(ValidatorContractError::UnknownBlock(at)),
+                }
+            }
+        };
+        let header = header.ok_or(ValidatorContractError::UnknownBlock(at))?;
+
+        let state = self.provider.history_by_block_hash(hash).map_err(provider)?;
+        let env = self
+            .evm_config
+            .evm_env(&header)
+            .map_err(|e| ValidatorContractError::Call(e.to_string()))?;
+        read_bor_validators(StateProviderDatabase::new(state), env, number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolValue;
+
+    #[test]
+    fn test_selector() {
+        // keccak256("getBorValidators(uint256)")[..4]
+        assert_eq!(&get_bor_validators_call_data(1)[..4], &[0x0c, 0x35, 0xb1, 0xcb]);
+    }
+
+    #[test]
+    fn test_decode_validators() {
+        let addresses = vec![Address::with_last_byte(1), Address::with_last_byte(2)];
+        let powers = vec![U256::from(100), U256::from(250)];
+        let output = (addresses.clone(), powers).abi_encode_params();
+
+        let validators = decode_bor_validators(&output).unwrap();
+        assert_eq!(validators.len(), 2);
+        assert_eq!(validators[1].address, addresses[1]);
+        assert_eq!(validators[1].signer, addresses[1]);
+        assert_eq!(validators[1].voting_power, 250);
+    }
+
+    #[test]
+    fn test_decode_rejects_mismatched_lengths_and_garbage() {
+        let output = (vec![Address::with_last_byte(1)], Vec::<U256>::new()).abi_encode_params();
+        assert!(matches!(decode_bor_validators(&output), Err(ValidatorContractError::Decode(_))));
+        assert!(decode_bor_validators(&[0xde, 0xad]).is_err());
+
+        let output = (vec![Address::with_last_byte(1)], vec![U256::MAX]).abi_encode_params();
+        assert!(decode_bor_validators(&output).is_err());
+    }
+}
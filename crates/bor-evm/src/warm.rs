@@ -0,0 +1,142 @@
+//! Cache warming for the system calls of a sprint start.
+//!
+//! The first block of a sprint runs `commitSpan` and `onStateReceive` after its
+//! transactions. The calls read the ValidatorSet and StateReceiver contracts, and
+//! the receivers of the events, none of which the block's transactions usually
+//! touch, so every read misses the block's [`State`] cache and goes to the database
+//! while the block is being sealed. That is the latency spike at each sprint start.
+//!
+//! [`SystemCallWarmer`] records the accounts and slots the calls of the last sprint
+//! start touched. Before the transactions of the next one it loads them into the
+//! block's cache, so that the reads happen before transaction selection instead of
+//! after it. Run `cargo bench -p bor-evm --bench sprint_boundary` to measure the
+//! effect on the system calls.
+
+use alloy_primitives::{Address, U256};
+use revm::{database::State, state::EvmState, Database};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct Touched {
+    /// The block the accounts were touched in.
+    block: u64,
+    /// The accounts, with the storage slots read or written.
+    accounts: BTreeMap<Address, BTreeSet<U256>>,
+}
+
+/// The state touched by the system calls of the last sprint start.
+///
+/// Clones share the same record, and compare equal only to each other.
+#[derive(Debug, Clone, Default)]
+pub struct SystemCallWarmer {
+    touched: Arc<Mutex<Touched>>,
+}
+
+impl PartialEq for SystemCallWarmer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.touched, &other.touched)
+    }
+}
+
+impl Eq for SystemCallWarmer {}
+
+impl SystemCallWarmer {
+    /// Create a warmer with nothing recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the accounts and slots of `state`, as left by a system call in block
+    /// `number`.
+    ///
+    /// A later block than the one recorded so far replaces the record, so it only ever
+    /// holds the state of one sprint start.
+    pub fn record(&self, number: u64, state: &EvmState) {
+        let mut touched = self.touched.lock().expect("warmer lock poisoned");
+        if number > touched.block {
+            *touched = Touched { block: number, accounts: BTreeMap::new() };
+        } else if number < touched.block {
+            return;
+        }
+        for (address, account) in state {
+            touched.accounts.entry(*address).or_default().extend(account.storage.keys());
+        }
+    }
+
+    /// The recorded accounts with their slots.
+    pub fn touched(&self) -> BTreeMap<Address, BTreeSet<U256>> {
+        self.touched.lock().expect("warmer lock poisoned").accounts.clone()
+    }
+
+    /// Load `contracts` and the recorded accounts, their code and slots into `db`'s cache.
+    ///
+    /// Returns the number of slots loaded.
+    pub fn warm<DB: Database>(
+        &self,
+        db: &mut State<DB>,
+        contracts: &[Address],
+    ) -> Result<usize, DB::Error> {
+        let mut touched = self.touched();
+        for contract in contracts {
+            touched.entry(*contract).or_default();
+        }
+        let mut slots = 0;
+        for (address, keys) in touched {
+            if let Some(info) = db.basic(address)? {
+                if info.code.is_none() {
+                    db.code_by_hash(info.code_hash)?;
+                }
+            }
+            for key in keys {
+                db.storage(address, key)?;
+                slots += 1;
+            }
+        }
+        Ok(slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::state::Account;
+
+    fn touched(slots: &[(Address, u64)]) -> EvmState {
+        let mut state = EvmState::default();
+        for (address, slot) in slots {
+            state.entry(*address).or_insert_with(Account::default).storage.insert(
+                U256::from(*slot),
+                Default::default(),
+            );
+        }
+        state
+    }
+
+    #[test]
+    fn test_record_keeps_one_block() {
+        let (a, b) = (Address::new([0xaa; 20]), Address::new([0xbb; 20]));
+        let warmer = SystemCallWarmer::new();
+        warmer.record(64, &touched(&[(a, 1)]));
+        warmer.record(64, &touched(&[(a, 2), (b, 7)]));
+        assert_eq!(warmer.touched()[&a].len(), 2);
+        assert_eq!(warmer.touched()[&b].len(), 1);
+
+        // An older block, e.g. a reorged one being re-executed, leaves the record alone.
+        warmer.record(32, &touched(&[(b, 9)]));
+        assert_eq!(warmer.touched()[&b].len(), 1);
+
+        warmer.record(128, &touched(&[(b, 3)]));
+        assert_eq!(warmer.touched().keys().copied().collect::<Vec<_>>(), vec![b]);
+    }
+
+    #[test]
+    fn test_clones_share_the_record() {
+        let warmer = SystemCallWarmer::new();
+        let clone = warmer.clone();
+        clone.record(64, &touched(&[(Address::ZERO, 1)]));
+        assert_eq!(warmer.touched().len(), 1);
+        assert_eq!(warmer, clone);
+        assert_ne!(warmer, SystemCallWarmer::new());
+    }
+}
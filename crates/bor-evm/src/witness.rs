@@ -0,0 +1,312 @@
+//! Executing a block on its execution witness.
+//!
+//! A node that has not synced a block's state can still execute it: the trie nodes,
+//! bytecodes and ancestor headers the block reads, as `debug_executionWitness` returns
+//! them, are enough to run it and to compute the state root it leaves. A Bor block
+//! also needs the Heimdall data of its system calls, so a [`WitnessBlock`] carries the
+//! span it commits and the state sync events it relays next to its witness.
+//!
+//! [`execute_witness_block`] runs the block through the same [`BorEvmConfig`] imported
+//! blocks go through, and returns the roots and gas used execution gives, for the
+//! caller to compare with the block's header.
+
+use crate::block_executor::PendingCommitSpan;
+use crate::evm_config::{BorEvmConfig, BorEvmFactory};
+use crate::pending_state::PendingStateOverlay;
+use crate::sprint_context::{CachedSprintContext, SprintContextError, SprintContextSource};
+use alloy_consensus::{proofs::calculate_receipt_root, Header, TxReceipt};
+use alloy_primitives::{map::B256Map, Address, Bytes, B256, U256};
+use alloy_rlp::Decodable;
+use bor_chainspec::BorHardforks;
+use bor_primitives::Span;
+use heimdall_client::{SpanCache, StateSyncEvent};
+use reth_chainspec::{EthChainSpec, EthExecutorSpec, EthereumHardforks};
+use reth_ethereum_primitives::{Block, Receipt};
+use reth_evm::{execute::Executor, ConfigureEvm};
+use reth_primitives_traits::Block as _;
+use reth_stateless::{ExecutionWitness, StatelessSparseTrie, StatelessTrie};
+use reth_trie_common::{HashedPostState, KeccakKeyHasher};
+use revm::{
+    database_interface::{DBErrorMarker, DatabaseRef, WrapDatabaseRef},
+    state::{AccountInfo, Bytecode},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+/// A block with everything needed to execute it without its state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WitnessBlock {
+    /// RLP of the block.
+    pub block: Bytes,
+    /// Trie nodes, bytecodes and ancestor headers the block reads.
+    pub witness: ExecutionWitness,
+    /// Span the block commits, if it starts one.
+    pub span: Option<Span>,
+    /// State sync events the block relays, in ascending ID order.
+    pub state_syncs: Vec<StateSyncEvent>,
+}
+
+/// What executing a [`WitnessBlock`] gave.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessExecution {
+    /// Header of the executed block, carrying the roots it was sealed with.
+    pub header: Header,
+    /// State root after the block.
+    pub state_root: B256,
+    /// Root of the block's receipts.
+    pub receipts_root: B256,
+    /// Receipts of the block's transactions.
+    pub receipts: Vec<Receipt>,
+    /// Gas the block used.
+    pub gas_used: u64,
+}
+
+/// Why a [`WitnessBlock`] cannot be executed.
+#[derive(Debug, thiserror::Error)]
+pub enum WitnessError {
+    /// The block or an ancestor header does not decode.
+    #[error("invalid RLP: {0}")]
+    Rlp(#[from] alloy_rlp::Error),
+    /// A transaction signer cannot be recovered.
+    #[error("cannot recover the signers of block {0}")]
+    Signers(u64),
+    /// The witness lacks the parent header, whose state root the block starts from.
+    #[error("witness of block {number} has no parent header {parent_hash}")]
+    MissingParent {
+        /// Number of the block.
+        number: u64,
+        /// Hash of its parent.
+        parent_hash: B256,
+    },
+    /// The witness does not hold the trie nodes of the parent state.
+    #[error("invalid witness: {0}")]
+    Witness(String),
+    /// Execution failed.
+    #[error(transparent)]
+    Execution(#[from] reth_evm::block::BlockExecutionError),
+}
+
+/// Execute `block` on its witness with `evm_config`, its system calls read from `block`.
+pub fn execute_witness_block<C, EvmF>(
+    evm_config: &BorEvmConfig<C, EvmF>,
+    block: &WitnessBlock,
+) -> Result<WitnessExecution, WitnessError>
+where
+    C: EthExecutorSpec
+        + EthChainSpec<Header = Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
+        + Debug
+        + 'static,
+    EvmF: BorEvmFactory,
+{
+    let decoded = Block::decode(&mut block.block.as_ref())?;
+    let number = decoded.header.number;
+    let parent_hash = decoded.header.parent_hash;
+    let recovered = decoded.try_into_recovered().map_err(|_| WitnessError::Signers(number))?;
+
+    let mut block_hashes = HashMap::new();
+    let mut parent_state_root = None;
+    for encoded in &block.witness.headers {
+        let header = Header::decode(&mut encoded.as_ref())?;
+        let hash = header.hash_slow();
+        if hash == parent_hash {
+            parent_state_root = Some(header.state_root);
+        }
+        block_hashes.insert(header.number, hash);
+    }
+    let parent_state_root =
+        parent_state_root.ok_or(WitnessError::MissingParent { number, parent_hash })?;
+
+    let (mut trie, bytecodes) = StatelessSparseTrie::new(&block.witness, parent_state_root)
+        .map_err(|err| WitnessError::Witness(err.to_string()))?;
+    let context = WitnessSprintContext::new(evm_config.chain_spec().clone(), block, number);
+    let evm_config = evm_config.clone().with_sprint_context(Arc::new(context));
+    let output = {
+        let db = WitnessDatabase { trie: &trie, bytecodes, block_hashes };
+        evm_config.executor(WrapDatabaseRef(&db)).execute(&recovered)?
+    };
+
+    let receipts_root = calculate_receipt_root(
+        &output.result.receipts.iter().map(TxReceipt::with_bloom_ref).collect::<Vec<_>>(),
+    );
+    let hashed = HashedPostState::from_bundle_state::<KeccakKeyHasher>(&output.state.state);
+    let state_root = trie
+        .calculate_state_root(hashed)
+        .map_err(|err| WitnessError::Witness(err.to_string()))?;
+    Ok(WitnessExecution {
+        header: recovered.header().clone(),
+        state_root,
+        receipts_root,
+        receipts: output.result.receipts,
+        gas_used: output.result.gas_used,
+    })
+}
+
+/// Reads accounts and storage from the witness trie, and ancestor hashes from its headers.
+struct WitnessDatabase<'a> {
+    trie: &'a StatelessSparseTrie,
+    bytecodes: B256Map<Bytecode>,
+    block_hashes: HashMap<u64, B256>,
+}
+
+/// Something the block read is missing from its witness.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+struct WitnessDatabaseError(String);
+
+impl DBErrorMarker for WitnessDatabaseError {}
+
+impl DatabaseRef for WitnessDatabase<'_> {
+    type Error = WitnessDatabaseError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let account =
+            self.trie.account(address).map_err(|err| WitnessDatabaseError(err.to_string()))?;
+        Ok(account.map(|account| AccountInfo {
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            ..Default::default()
+        }))
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.bytecodes
+            .get(&code_hash)
+            .cloned()
+            .ok_or_else(|| WitnessDatabaseError(format!("no bytecode {code_hash} in the witness")))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.trie.storage(address, index).map_err(|err| WitnessDatabaseError(err.to_string()))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.block_hashes
+            .get(&number)
+            .copied()
+            .ok_or_else(|| WitnessDatabaseError(format!("no header {number} in the witness")))
+    }
+}
+
+/// Commits the span of a [`WitnessBlock`] as [`CachedSprintContext`] would, and relays
+/// its state sync events.
+#[derive(Debug)]
+struct WitnessSprintContext<C> {
+    spans: CachedSprintContext<C>,
+    /// Number of the block.
+    number: u64,
+    state_syncs: Vec<StateSyncEvent>,
+}
+
+impl<C> WitnessSprintContext<C> {
+    fn new(chain_spec: Arc<C>, block: &WitnessBlock, number: u64) -> Self {
+        let mut spans = SpanCache::new(1);
+        if let Some(span) = &block.span {
+            spans.insert(span.clone());
+        }
+        let spans = CachedSprintContext::new(
+            chain_spec,
+            Arc::new(Mutex::new(spans)),
+            PendingStateOverlay::new(),
+        );
+        Self { spans, number, state_syncs: block.state_syncs.clone() }
+    }
+}
+
+impl<C: BorHardforks + Debug + Send + Sync> SprintContextSource for WitnessSprintContext<C> {
+    fn commit_span(&self, number: u64) -> Result<Option<PendingCommitSpan>, SprintContextError> {
+        self.spans.commit_span(number)
+    }
+
+    fn state_syncs(
+        &self,
+        number: u64,
+        _timestamp: u64,
+    ) -> Result<Vec<StateSyncEvent>, SprintContextError> {
+        if number != self.number {
+            return Err(SprintContextError::StateSyncsUnavailable { block: number });
+        }
+        Ok(self.state_syncs.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_rlp::Encodable;
+    use reth_chainspec::{Chain, ChainSpecBuilder};
+
+    fn witness_block(headers: Vec<Header>) -> WitnessBlock {
+        let parent = Header { number: 4, ..Default::default() };
+        let block = Block {
+            header: Header { number: 5, parent_hash: parent.hash_slow(), ..Default::default() },
+            body: Default::default(),
+        };
+        let mut encoded = Vec::new();
+        block.encode(&mut encoded);
+        let headers = headers
+            .iter()
+            .map(|header| {
+                let mut encoded = Vec::new();
+                header.encode(&mut encoded);
+                encoded.into()
+            })
+            .collect();
+        WitnessBlock {
+            block: encoded.into(),
+            witness: ExecutionWitness { headers, ..Default::default() },
+            span: None,
+            state_syncs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_block_needs_its_parent_header() {
+        let spec = ChainSpecBuilder::default()
+            .chain(Chain::from_id(137))
+            .genesis(Default::default())
+            .london_activated()
+            .build();
+        let config = BorEvmConfig::new(Arc::new(spec));
+        let stranger = Header { number: 4, gas_limit: 1, ..Default::default() };
+        let block = witness_block(vec![stranger]);
+        assert!(matches!(
+            execute_witness_block(&config, &block),
+            Err(WitnessError::MissingParent { number: 5, .. })
+        ));
+
+        let json = serde_json::to_value(&block).unwrap();
+        assert!(json["stateSyncs"].as_array().unwrap().is_empty());
+        assert_eq!(serde_json::from_value::<WitnessBlock>(json).unwrap(), block);
+    }
+
+    #[test]
+    fn test_state_syncs_only_of_the_block() {
+        let mut block = witness_block(Vec::new());
+        block.state_syncs = vec![StateSyncEvent {
+            id: 7,
+            contract: Address::with_last_byte(9),
+            data: Bytes::from_static(&[1]),
+            tx_hash: B256::ZERO,
+            log_index: 0,
+            bor_chain_id: "137".to_string(),
+            time: 1_000,
+        }];
+        let context =
+            WitnessSprintContext::new(Arc::new(bor_chainspec::MainnetBorHardforks), &block, 5);
+        assert_eq!(context.state_syncs(5, 0).unwrap(), block.state_syncs);
+        assert_eq!(
+            context.state_syncs(6, 0),
+            Err(SprintContextError::StateSyncsUnavailable { block: 6 })
+        );
+        // No span was captured for the block, so none can be committed at a span start.
+        assert!(context.commit_span(256).is_err());
+        assert_eq!(context.commit_span(257), Ok(None));
+    }
+}
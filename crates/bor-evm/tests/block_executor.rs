@@ -0,0 +1,657 @@
+//! `BorBlockExecutor` over an in-memory state.
+//!
+//! Each test executes a block against `State` backed by an empty `CacheDB`.
+//! The ValidatorSet and StateReceiver contracts are replaced by stubs that
+//! report every call to a recorder contract, so the order of the system calls
+//! can be read back from its storage.
+//!
+//! A database panicking on first access to a contract stands in for a node killed
+//! while the system calls of a sprint start run.
+
+use alloy_consensus::{transaction::Recovered, SignableTransaction, TxLegacy};
+use alloy_primitives::{Address, Bytes, Signature, TxKind, B256, U256};
+use bor_chainspec::constants::{BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, SYSTEM_ADDRESS};
+use bor_evm::{
+    apply_sprint_boundary, plan_system_txs, AllocAccount, BlockAlloc, BorBlockExecutor,
+    BorExecutionCtx, BorPostExecution, BorSystemCaller, PendingCommitSpan, SprintPresimulator,
+    StateSyncAction, StateSyncFilter, StateSyncProfiler, SystemCallTimings, SystemCallWarmer,
+};
+use bor_chainspec::{BorConfig, ContractUpgrade, ForkValue};
+use bor_storage::{
+    receipt_key::derived_bor_tx_hash, InMemoryStateSyncStore, SprintOutcome, SprintStage,
+    SprintWal, StateSyncStore,
+};
+use reth_chainspec::{Chain, ChainSpec, ChainSpecBuilder};
+use reth_ethereum_primitives::{Receipt, TransactionSigned};
+use reth_evm::{
+    block::{BlockExecutionError, BlockExecutor, BlockValidationError},
+    eth::EthBlockExecutionCtx,
+    EthEvmFactory, EvmEnv, EvmFactory,
+};
+use reth_evm_ethereum::RethReceiptBuilder;
+use revm::{
+    context::CfgEnv,
+    database::{CacheDB, EmptyDB, State},
+    primitives::hardfork::SpecId,
+    state::{AccountInfo, Bytecode},
+    Database,
+};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, RwLock};
+
+type MemoryState = State<CacheDB<EmptyDB>>;
+
+const RECORDER: Address = Address::new([0x42; 20]);
+const SENDER: Address = Address::new([0x11; 20]);
+const BLOCK_GAS_LIMIT: u64 = 100_000;
+
+// slot[0] += 1; slot[slot[0]] = CALLER
+const RECORDER_CODE: &[u8] = &[
+    0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x33, 0x90, 0x55, 0x00,
+];
+
+/// Stub that calls the recorder with no arguments and stops.
+fn reporting_stub() -> Bytecode {
+    let mut code = vec![0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x73];
+    code.extend_from_slice(RECORDER.as_slice());
+    code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+    Bytecode::new_raw(code.into())
+}
+
+fn memory_db() -> CacheDB<EmptyDB> {
+    let mut db = CacheDB::new(EmptyDB::default());
+    db.insert_account_info(
+        RECORDER,
+        AccountInfo::from_bytecode(Bytecode::new_raw(Bytes::from_static(RECORDER_CODE))),
+    );
+    for contract in [BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS] {
+        db.insert_account_info(contract, AccountInfo::from_bytecode(reporting_stub()));
+    }
+    db
+}
+
+fn memory_state() -> MemoryState {
+    State::builder().with_database(memory_db()).with_bundle_update().build()
+}
+
+/// Memory database of a node killed when execution first loads `killed_at`.
+struct KilledAt {
+    db: CacheDB<EmptyDB>,
+    killed_at: Address,
+}
+
+impl Database for KilledAt {
+    type Error = <CacheDB<EmptyDB> as Database>::Error;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        assert_ne!(address, self.killed_at, "node killed");
+        self.db.basic(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash(code_hash)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.db.storage(address, index)
+    }
+
+    fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
+fn eth_ctx(parent_hash: B256, tx_count: usize) -> EthBlockExecutionCtx<'static> {
+    EthBlockExecutionCtx {
+        tx_count_hint: Some(tx_count),
+        parent_hash,
+        parent_beacon_block_root: None,
+        ommers: &[],
+        withdrawals: None,
+        extra_data: Bytes::new(),
+    }
+}
+
+fn chain_spec() -> Arc<ChainSpec> {
+    Arc::new(
+        ChainSpecBuilder::default()
+            .chain(Chain::from_id(137))
+            .genesis(Default::default())
+            .london_activated()
+            .build(),
+    )
+}
+
+fn env(number: u64) -> EvmEnv {
+    let mut env = EvmEnv::default();
+    env.cfg_env = CfgEnv::new().with_chain_id(137).with_spec_and_mainnet_gas_params(SpecId::LONDON);
+    env.block_env.number = U256::from(number);
+    env.block_env.gas_limit = BLOCK_GAS_LIMIT;
+    env
+}
+
+/// Zero-priced transfer from [`SENDER`].
+fn transfer(nonce: u64, gas_limit: u64) -> Recovered<TransactionSigned> {
+    let tx = TxLegacy {
+        chain_id: Some(137),
+        nonce,
+        gas_price: 0,
+        gas_limit,
+        to: TxKind::Call(Address::new([0x22; 20])),
+        value: U256::ZERO,
+        input: Bytes::new(),
+    };
+    Recovered::new_unchecked(tx.into_signed(Signature::test_signature()).into(), SENDER)
+}
+
+/// Execute block `number` with `txs` and `bor_ctx`, returning the receipts and the state.
+fn execute(
+    number: u64,
+    txs: &[Recovered<TransactionSigned>],
+    bor_ctx: BorExecutionCtx,
+) -> Result<(Vec<Receipt>, MemoryState), BlockExecutionError> {
+    let mut state = memory_state();
+    let receipts = {
+        let evm = EthEvmFactory::default().create_evm(&mut state, env(number));
+        let mut executor = BorBlockExecutor::new(
+            evm,
+            eth_ctx(B256::ZERO, txs.len()),
+            bor_ctx,
+            chain_spec(),
+            RethReceiptBuilder::default(),
+        );
+        executor.apply_pre_execution_changes()?;
+        for tx in txs {
+            executor.execute_transaction(tx.as_recovered_ref())?;
+        }
+        executor.finish()?.1.receipts
+    };
+    Ok((receipts, state))
+}
+
+/// Callers reported to the recorder, in call order.
+fn recorded_callers(state: &mut MemoryState) -> Vec<Address> {
+    let count = state.storage(RECORDER, U256::ZERO).unwrap().to::<u64>();
+    (1..=count)
+        .map(|slot| {
+            let caller = state.storage(RECORDER, U256::from(slot)).unwrap();
+            Address::from_word(caller.into())
+        })
+        .collect()
+}
+
+fn sprint_ctx() -> BorExecutionCtx {
+    BorExecutionCtx {
+        pending_commit_span: Some(PendingCommitSpan {
+            span_id: U256::from(1),
+            validator_bytes: Bytes::from_static(&[0xc0]),
+        }),
+        pending_state_syncs: vec![
+            (U256::from(1), Bytes::from_static(b"first")),
+            (U256::from(2), Bytes::from_static(b"second")),
+        ],
+        sprint_start: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tx_exceeding_remaining_block_gas_is_rejected() {
+    let txs = [transfer(0, 60_000), transfer(1, 60_000)];
+    let err = execute(1, &txs, BorExecutionCtx::default()).unwrap_err();
+    assert!(matches!(
+        err,
+        BlockExecutionError::Validation(
+            BlockValidationError::TransactionGasLimitMoreThanAvailableBlockGas {
+                transaction_gas_limit: 60_000,
+                block_available_gas: 40_000,
+            }
+        )
+    ));
+}
+
+#[test]
+fn tx_within_remaining_block_gas_is_accepted() {
+    let txs = [transfer(0, 60_000), transfer(1, 40_000)];
+    let (receipts, _) = execute(1, &txs, BorExecutionCtx::default()).unwrap();
+    assert_eq!(receipts.len(), 2);
+}
+
+#[test]
+fn receipts_accumulate_gas() {
+    let txs = [transfer(0, 21_000), transfer(1, 30_000), transfer(2, 21_000)];
+    let (receipts, _) = execute(1, &txs, BorExecutionCtx::default()).unwrap();
+
+    let cumulative: Vec<u64> = receipts.iter().map(|r| r.cumulative_gas_used).collect();
+    assert_eq!(cumulative, vec![21_000, 42_000, 63_000]);
+    assert!(receipts.iter().all(|r| r.success));
+}
+
+#[test]
+fn system_calls_do_not_add_receipts() {
+    let (receipts, _) = execute(6400, &[transfer(0, 21_000)], sprint_ctx()).unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].cumulative_gas_used, 21_000);
+}
+
+#[test]
+fn commit_span_runs_before_state_syncs() {
+    let (_, mut state) = execute(6400, &[transfer(0, 21_000)], sprint_ctx()).unwrap();
+    assert_eq!(
+        recorded_callers(&mut state),
+        vec![BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, STATE_RECEIVER_ADDRESS]
+    );
+}
+
+#[test]
+fn apply_sprint_boundary_commits_span_first() {
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    apply_sprint_boundary(&mut evm, &sprint_ctx()).unwrap();
+    drop(evm);
+
+    assert_eq!(
+        recorded_callers(&mut state),
+        vec![BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, STATE_RECEIVER_ADDRESS]
+    );
+}
+
+#[test]
+fn apply_sprint_boundary_without_calls_is_noop() {
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6401));
+    let timings = apply_sprint_boundary(&mut evm, &BorExecutionCtx::default()).unwrap();
+    drop(evm);
+
+    assert_eq!(timings, SystemCallTimings::default());
+
+    assert!(recorded_callers(&mut state).is_empty());
+}
+
+#[test]
+fn shared_system_caller_serves_several_blocks() {
+    let caller = BorSystemCaller::new().with_state_receiver(BOR_VALIDATOR_SET_ADDRESS);
+    let ctx = sprint_ctx();
+    let mut states = [memory_state(), memory_state()];
+    for (state, number) in states.iter_mut().zip([64, 128]) {
+        let mut evm = EthEvmFactory::default().create_evm(state, env(number));
+        caller.apply_sprint_boundary(&mut evm, ctx.sprint_context()).unwrap();
+    }
+
+    for state in &mut states {
+        assert_eq!(recorded_callers(state), vec![BOR_VALIDATOR_SET_ADDRESS; 3]);
+    }
+}
+
+/// Relay ten events at mainnet block `number` through the mainnet caller.
+fn mainnet_state_syncs_at(number: u64) -> usize {
+    let caller = BorSystemCaller::from_config(BorConfig::mainnet());
+    let events: Vec<_> = (1..=10u64).map(|id| (U256::from(id), Bytes::new())).collect();
+    let ctx = BorExecutionCtx { pending_state_syncs: events, ..Default::default() };
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(number));
+    caller.apply_sprint_boundary(&mut evm, ctx.sprint_context()).unwrap();
+    drop(evm);
+    recorded_callers(&mut state).len()
+}
+
+#[test]
+fn mainnet_state_sync_records_are_overridden() {
+    assert_eq!(mainnet_state_syncs_at(14_949_120), 8);
+    assert_eq!(mainnet_state_syncs_at(14_949_184), 0);
+    assert_eq!(mainnet_state_syncs_at(14_953_536), 5);
+    assert_eq!(mainnet_state_syncs_at(14_953_856), 0);
+    assert_eq!(mainnet_state_syncs_at(14_953_920), 10);
+    assert_eq!(mainnet_state_syncs_at(14_949_056), 10);
+}
+
+#[test]
+fn contract_upgrade_applies_from_its_block() {
+    let upgrade = ContractUpgrade {
+        state_receiver_contract: Some(BOR_VALIDATOR_SET_ADDRESS),
+        ..Default::default()
+    };
+    let upgrades = ForkValue::default().with_value(128, upgrade);
+    let caller = BorSystemCaller::new().with_contract_upgrades(upgrades);
+    let ctx = BorExecutionCtx { pending_commit_span: None, ..sprint_ctx() };
+    for (number, expected) in [(64, STATE_RECEIVER_ADDRESS), (128, BOR_VALIDATOR_SET_ADDRESS)] {
+        let mut state = memory_state();
+        let mut evm = EthEvmFactory::default().create_evm(&mut state, env(number));
+        caller.apply_sprint_boundary(&mut evm, ctx.sprint_context()).unwrap();
+        drop(evm);
+        assert_eq!(recorded_callers(&mut state), vec![expected; 2]);
+    }
+    assert_eq!(caller.contracts_at(127).state_receiver, STATE_RECEIVER_ADDRESS);
+    assert_eq!(caller.contracts_at(u64::MAX).validator_set, BOR_VALIDATOR_SET_ADDRESS);
+}
+
+#[test]
+fn warmer_records_system_call_state() {
+    let warmer = SystemCallWarmer::new();
+    let caller = BorSystemCaller::new().with_warmer(warmer.clone());
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    caller.apply_sprint_boundary(&mut evm, sprint_ctx().sprint_context()).unwrap();
+    drop(evm);
+
+    let touched = warmer.touched();
+    assert!(touched.contains_key(&BOR_VALIDATOR_SET_ADDRESS));
+    assert!(touched.contains_key(&STATE_RECEIVER_ADDRESS));
+    // The recorder's counter and one slot per call.
+    assert_eq!(touched[&RECORDER].len(), 4);
+
+    let mut fresh = memory_state();
+    caller.warm(&mut fresh, 6464);
+    assert!(fresh.cache.accounts.contains_key(&RECORDER));
+}
+
+#[test]
+fn profiler_records_each_state_sync() {
+    let profiler = StateSyncProfiler::new(u64::MAX);
+    let caller = BorSystemCaller::new().with_profiler(profiler.clone());
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    caller.apply_sprint_boundary(&mut evm, sprint_ctx().sprint_context()).unwrap();
+    drop(evm);
+
+    let profiles = profiler.profiles();
+    let ids: Vec<_> = profiles.iter().map(|p| p.state_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    for profile in &profiles {
+        assert_eq!(profile.block, 6400);
+        assert!(profile.gas_used > 0);
+        // The recorder's counter and the slot of the call.
+        assert_eq!(profile.storage_writes, 2);
+        assert_eq!(profile.busiest, Some((RECORDER, 2)));
+    }
+    assert!(profiler.outliers().is_empty());
+}
+
+/// Drops the first event of a block and rewrites the others.
+#[derive(Debug)]
+struct DropFirst;
+
+impl StateSyncFilter for DropFirst {
+    fn filter(&self, _number: u64, state_id: U256, _data: &Bytes) -> StateSyncAction {
+        match state_id.to::<u64>() {
+            1 => StateSyncAction::Skip,
+            _ => StateSyncAction::Replace(Bytes::from_static(b"rewritten")),
+        }
+    }
+}
+
+#[test]
+fn state_sync_filter_drops_and_rewrites_events() {
+    let caller = BorSystemCaller::new().with_state_sync_filter(DropFirst);
+    let ctx = sprint_ctx();
+    assert_eq!(
+        caller.relayed_state_syncs(6400, &ctx.pending_state_syncs).into_owned(),
+        vec![(U256::from(2), Bytes::from_static(b"rewritten"))]
+    );
+
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    caller.apply_sprint_boundary(&mut evm, ctx.sprint_context()).unwrap();
+    drop(evm);
+    let callers = recorded_callers(&mut state);
+    assert_eq!(callers, vec![BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS]);
+}
+
+#[test]
+fn presimulated_system_calls_are_reused() {
+    let parent = B256::repeat_byte(0x01);
+    let caller = BorSystemCaller::new();
+    let presim = SprintPresimulator::new();
+    let ctx = sprint_ctx();
+    let mut tip = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut tip, env(6400));
+    assert_eq!(presim.presimulate(&caller, &mut evm, parent, ctx.sprint_context()).unwrap(), 3);
+    drop(evm);
+    assert!(presim.is_presimulated(parent));
+
+    // Another parent, or other calls, do not match the simulation.
+    let mut state = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    assert!(presim.apply(&mut evm, B256::ZERO, ctx.sprint_context()).is_none());
+    let mut fewer = sprint_ctx();
+    fewer.pending_state_syncs.pop();
+    assert!(presim.apply(&mut evm, parent, fewer.sprint_context()).is_none());
+
+    assert!(presim.apply(&mut evm, parent, ctx.sprint_context()).is_some());
+    drop(evm);
+    assert_eq!(
+        recorded_callers(&mut state),
+        vec![BOR_VALIDATOR_SET_ADDRESS, STATE_RECEIVER_ADDRESS, STATE_RECEIVER_ADDRESS]
+    );
+}
+
+#[test]
+fn presimulation_is_not_reused_after_conflicting_change() {
+    let parent = B256::repeat_byte(0x01);
+    let presim = SprintPresimulator::new();
+    let ctx = sprint_ctx();
+    let mut tip = memory_state();
+    let mut evm = EthEvmFactory::default().create_evm(&mut tip, env(6400));
+    presim.presimulate(&BorSystemCaller::new(), &mut evm, parent, ctx.sprint_context()).unwrap();
+    drop(evm);
+
+    // The block's transactions changed the recorder the calls write to.
+    let mut state = memory_state();
+    state.increment_balances([(RECORDER, 1)]).unwrap();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    assert!(presim.apply(&mut evm, parent, ctx.sprint_context()).is_none());
+}
+
+#[test]
+fn negative_state_sync_override_is_skipped() {
+    let caller = BorSystemCaller::new()
+        .with_state_sync_records_override([(64, -1), (128, 1), (192, 5)].into_iter().collect());
+    let events = sprint_ctx().pending_state_syncs;
+    assert_eq!(caller.state_syncs_at(64, &events).len(), 2);
+    assert_eq!(caller.state_syncs_at(128, &events).len(), 1);
+    assert_eq!(caller.state_syncs_at(192, &events).len(), 2);
+    assert_eq!(caller.state_syncs_at(256, &events).len(), 2);
+}
+
+#[test]
+fn base_fee_is_credited_to_burnt_contract() {
+    let burnt = Address::new([0xbb; 20]);
+    let post = BorPostExecution::default()
+        .with_burnt_contract(ForkValue::default().with_value(10, burnt));
+    let mut state = memory_state();
+    for number in [9, 10] {
+        let mut env = env(number);
+        env.block_env.basefee = 7;
+        let mut evm = EthEvmFactory::default().create_evm(&mut state, env);
+        post.credit_base_fee(&mut evm, 21_000).unwrap();
+    }
+
+    // Block 9 is before the contract is set, so only block 10 credits it.
+    assert_eq!(state.basic(burnt).unwrap().unwrap().balance, U256::from(7 * 21_000));
+}
+
+#[test]
+fn block_alloc_replaces_code_and_keeps_balance() {
+    let code = Bytes::from_static(&[0x00]);
+    let alloc = |balance| AllocAccount { code: code.clone(), balance: U256::from(balance) };
+    let block_alloc: BlockAlloc = [(
+        100,
+        [(STATE_RECEIVER_ADDRESS, alloc(5)), (RECORDER, alloc(5))].into_iter().collect(),
+    )]
+    .into_iter()
+    .collect();
+    let post = BorPostExecution::default().with_block_alloc(block_alloc);
+
+    let mut state = memory_state();
+    state.increment_balances([(RECORDER, 1)]).unwrap();
+    for number in [99, 100] {
+        let mut evm = EthEvmFactory::default().create_evm(&mut state, env(number));
+        post.apply_block_alloc(&mut evm).unwrap();
+    }
+
+    let receiver = state.basic(STATE_RECEIVER_ADDRESS).unwrap().unwrap();
+    assert_eq!(receiver.code.unwrap().original_bytes(), code);
+    assert_eq!(receiver.balance, U256::from(5));
+    // Accounts with a balance keep it.
+    assert_eq!(state.basic(RECORDER).unwrap().unwrap().balance, U256::from(1));
+}
+
+#[test]
+fn sprint_start_without_span_only_commits_state() {
+    let ctx = BorExecutionCtx { pending_commit_span: None, ..sprint_ctx() };
+    let (_, mut state) = execute(64, &[], ctx).unwrap();
+    assert_eq!(recorded_callers(&mut state), vec![STATE_RECEIVER_ADDRESS; 2]);
+}
+
+#[test]
+fn non_sprint_block_makes_no_system_calls() {
+    // Events fetched for the upcoming sprint must not leak into the blocks before it.
+    let fetched = sprint_ctx().pending_state_syncs;
+    let plan = plan_system_txs(65, 64, 6400, false, &fetched);
+    let ctx = BorExecutionCtx {
+        pending_commit_span: None,
+        pending_state_syncs: plan.state_sync_events,
+        ..Default::default()
+    };
+    assert!(!ctx.has_bor_receipt());
+
+    let (receipts, mut state) = execute(65, &[transfer(0, 21_000)], ctx).unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert!(recorded_callers(&mut state).is_empty());
+}
+
+#[test]
+fn system_calls_leave_system_address_untouched() {
+    let funded = AccountInfo { nonce: 7, balance: U256::from(1_000), ..Default::default() };
+    let mut db = memory_db();
+    db.insert_account_info(SYSTEM_ADDRESS, funded.clone());
+    let mut state = State::builder().with_database(db).with_bundle_update().build();
+    let mut evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    apply_sprint_boundary(&mut evm, &sprint_ctx()).unwrap();
+    drop(evm);
+
+    assert_eq!(recorded_callers(&mut state).len(), 3);
+    let after = state.basic(SYSTEM_ADDRESS).unwrap().unwrap();
+    assert_eq!((after.nonce, after.balance), (funded.nonce, funded.balance));
+    assert_eq!(after.code_hash, funded.code_hash);
+}
+
+#[test]
+fn sprint_start_is_marked_executed() {
+    let parent = B256::repeat_byte(0x07);
+    let wal = SprintWal::new();
+    let mut state = memory_state();
+    let evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    let mut executor = BorBlockExecutor::new(
+        evm,
+        eth_ctx(parent, 0),
+        sprint_ctx(),
+        chain_spec(),
+        RethReceiptBuilder::default(),
+    )
+    .with_sprint_wal(&wal);
+    executor.apply_pre_execution_changes().unwrap();
+    executor.finish().unwrap();
+
+    let markers = wal.markers();
+    assert_eq!(markers.len(), 1);
+    assert_eq!((markers[0].number, markers[0].stage), (6400, SprintStage::Executed));
+    assert_eq!(markers[0].span_id, Some(1));
+    assert!(wal.wait_idle(std::time::Duration::ZERO));
+    // Persisting the block drops its marker.
+    assert_eq!(wal.complete_through(6400).unwrap(), 1);
+}
+
+#[test]
+fn committed_state_syncs_are_recorded() {
+    let parent = B256::repeat_byte(0x07);
+    let store = RwLock::new(InMemoryStateSyncStore::default());
+    let mut state = memory_state();
+    let evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    let mut executor = BorBlockExecutor::new(
+        evm,
+        eth_ctx(parent, 0),
+        sprint_ctx(),
+        chain_spec(),
+        RethReceiptBuilder::default(),
+    )
+    .with_state_sync_store(&store);
+    executor.apply_pre_execution_changes().unwrap();
+    executor.finish().unwrap();
+
+    let mut store = store.into_inner().unwrap();
+    // Recorded under the parent until the block's hash is known.
+    assert!(store.block(6400).is_none());
+    let hash = B256::repeat_byte(0x64);
+    assert!(store.canonicalize(6400, parent, hash));
+    let block = store.block_by_bor_tx_hash(&derived_bor_tx_hash(6400, &hash)).unwrap();
+    let ids: Vec<_> = block.events.iter().map(|event| event.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    assert_eq!(block.events[1].data, Bytes::from_static(b"second"));
+}
+
+#[test]
+fn span_commit_without_state_syncs_has_no_bor_tx() {
+    let parent = B256::repeat_byte(0x07);
+    let store = RwLock::new(InMemoryStateSyncStore::default());
+    let ctx = BorExecutionCtx { pending_state_syncs: Vec::new(), ..sprint_ctx() };
+    assert!(!ctx.has_bor_receipt());
+    let mut state = memory_state();
+    let evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+    let mut executor = BorBlockExecutor::new(
+        evm,
+        eth_ctx(parent, 0),
+        ctx,
+        chain_spec(),
+        RethReceiptBuilder::default(),
+    )
+    .with_state_sync_store(&store);
+    executor.apply_pre_execution_changes().unwrap();
+    executor.finish().unwrap();
+
+    let mut store = store.into_inner().unwrap();
+    let hash = B256::repeat_byte(0x64);
+    assert!(!store.canonicalize(6400, parent, hash));
+    assert!(store.block_by_bor_tx_hash(&derived_bor_tx_hash(6400, &hash)).is_none());
+}
+
+#[test]
+fn kill_during_sprint_rolls_back() {
+    let path = std::env::temp_dir()
+        .join(format!("bor-kill-during-sprint-{}.json", std::process::id()));
+    let parent = B256::repeat_byte(0x07);
+    let wal = SprintWal::open(&path).unwrap();
+    // Killed once `commitSpan` ran, as `onStateReceive` starts.
+    let db = KilledAt { db: memory_db(), killed_at: STATE_RECEIVER_ADDRESS };
+    let mut state = State::builder().with_database(db).with_bundle_update().build();
+    let killed = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let evm = EthEvmFactory::default().create_evm(&mut state, env(6400));
+        let mut executor = BorBlockExecutor::new(
+            evm,
+            eth_ctx(parent, 0),
+            sprint_ctx(),
+            chain_spec(),
+            RethReceiptBuilder::default(),
+        )
+        .with_sprint_wal(&wal);
+        executor.apply_pre_execution_changes().unwrap();
+        executor.finish().map(|_| ())
+    }));
+    assert!(killed.is_err());
+    drop(wal);
+
+    // The restarted node finds the block in flight and, not in its database, rolled back.
+    let restarted = SprintWal::open(&path).unwrap();
+    let recovered = restarted.recover(|_| None);
+    assert_eq!(recovered.len(), 1);
+    let (marker, outcome) = &recovered[0];
+    assert_eq!((marker.number, marker.parent_hash), (6400, parent));
+    assert_eq!(marker.stage, SprintStage::Executing);
+    assert_eq!((marker.first_state_id, marker.last_state_id), (Some(1), Some(2)));
+    assert_eq!(*outcome, SprintOutcome::RolledBack);
+
+    // `commitSpan` only reached the in-memory state, never the database under it.
+    assert!(state.bundle_state.state.is_empty());
+    assert_eq!(state.database.db.storage(RECORDER, U256::ZERO).unwrap(), U256::ZERO);
+    std::fs::remove_file(&path).unwrap();
+}
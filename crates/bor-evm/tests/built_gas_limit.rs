@@ -0,0 +1,53 @@
+//! Built blocks move their gas limit toward the chain's block gas limit, or toward
+//! `--miner.gaslimit` when set, whatever limit the payload attributes ask for.
+
+use alloy_consensus::Header;
+use bor_chainspec::BorHardfork;
+use bor_consensus::validate_gas_limit;
+use bor_evm::BorEvmConfig;
+use reth_chainspec::{Chain, ChainSpecBuilder};
+use reth_evm::{ConfigureEvm, NextBlockEnvAttributes};
+use std::sync::Arc;
+
+fn mainnet_config() -> BorEvmConfig {
+    let spec = ChainSpecBuilder::default()
+        .chain(Chain::from_id(137))
+        .genesis(Default::default())
+        .london_activated()
+        .build();
+    BorEvmConfig::new(Arc::new(spec))
+}
+
+/// Gas limit `config` gives the block after a parent at `parent_number` with a 30M limit.
+fn built_gas_limit(config: &BorEvmConfig, parent_number: u64) -> u64 {
+    let parent = Header { number: parent_number, gas_limit: 30_000_000, ..Default::default() };
+    // reth's builder config asks for its own default; the chain's limit wins.
+    let attributes = NextBlockEnvAttributes {
+        timestamp: 0,
+        suggested_fee_recipient: Default::default(),
+        prev_randao: Default::default(),
+        gas_limit: 60_000_000,
+        parent_beacon_block_root: None,
+        withdrawals: None,
+        extra_data: Default::default(),
+    };
+    let env = config.next_evm_env(&parent, &attributes).unwrap();
+    let gas_limit = env.block_env.gas_limit;
+    assert!(validate_gas_limit(30_000_000, gas_limit).is_ok());
+    gas_limit
+}
+
+#[test]
+fn default_target_is_the_chains_block_gas_limit() {
+    let config = mainnet_config();
+    let bhilai = BorHardfork::Bhilai.mainnet_block();
+    assert_eq!(built_gas_limit(&config, bhilai - 2), 30_000_000);
+    assert!(built_gas_limit(&config, bhilai - 1) > 30_000_000);
+}
+
+#[test]
+fn configured_target_overrides_the_chain() {
+    let config = mainnet_config().with_gas_limit_target(Some(20_000_000));
+    let bhilai = BorHardfork::Bhilai.mainnet_block();
+    assert!(built_gas_limit(&config, bhilai) < 30_000_000);
+}
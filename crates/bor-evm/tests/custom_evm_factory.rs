@@ -0,0 +1,103 @@
+//! `BorEvmConfig` over an EVM factory adding a precompile, as a private chain would.
+
+use alloy_primitives::{address, Address, Bytes, TxKind, U256};
+use bor_evm::{BorEvmConfig, BorEvmFactory};
+use reth_chainspec::{Chain, ChainSpec, ChainSpecBuilder};
+use reth_evm::{
+    eth::{EthEvm, EthEvmContext},
+    precompiles::{DynPrecompile, PrecompileInput, PrecompilesMap},
+    ConfigureEvm, Database, EthEvmFactory, Evm, EvmEnv, EvmFactory,
+};
+use revm::{
+    context::{BlockEnv, TxEnv},
+    context_interface::result::{EVMError, HaltReason},
+    database::{CacheDB, EmptyDB},
+    inspector::NoOpInspector,
+    precompile::{PrecompileOutput, PrecompileResult},
+    primitives::hardfork::SpecId,
+    state::AccountInfo,
+    Inspector,
+};
+use std::sync::Arc;
+
+const ECHO: Address = address!("0000000000000000000000000000000000000b07");
+const CALLER: Address = Address::new([0x11; 20]);
+const ECHO_GAS: u64 = 15;
+
+type Config<F> = BorEvmConfig<ChainSpec, F>;
+type EchoEvm<DB, I> = EthEvm<DB, I, PrecompilesMap>;
+
+/// Ethereum's factory with a precompile at [`ECHO`] returning its input.
+#[derive(Debug, Clone, Copy, Default)]
+struct EchoEvmFactory;
+
+fn echo(input: PrecompileInput<'_>) -> PrecompileResult {
+    Ok(PrecompileOutput::new(ECHO_GAS, Bytes::copy_from_slice(input.data)))
+}
+
+fn with_echo<DB: Database, I: Inspector<EthEvmContext<DB>>>(
+    mut evm: EchoEvm<DB, I>,
+) -> EchoEvm<DB, I> {
+    evm.precompiles_mut().apply_precompile(&ECHO, |_| Some(DynPrecompile::from(echo)));
+    evm
+}
+
+impl EvmFactory for EchoEvmFactory {
+    type Evm<DB: Database, I: Inspector<EthEvmContext<DB>>> = EchoEvm<DB, I>;
+    type Context<DB: Database> = EthEvmContext<DB>;
+    type Tx = TxEnv;
+    type Error<DBError: core::error::Error + Send + Sync + 'static> = EVMError<DBError>;
+    type HaltReason = HaltReason;
+    type Spec = SpecId;
+    type BlockEnv = BlockEnv;
+    type Precompiles = PrecompilesMap;
+
+    fn create_evm<DB: Database>(&self, db: DB, env: EvmEnv) -> Self::Evm<DB, NoOpInspector> {
+        with_echo(EthEvmFactory::default().create_evm(db, env))
+    }
+
+    fn create_evm_with_inspector<DB: Database, I: Inspector<Self::Context<DB>>>(
+        &self,
+        db: DB,
+        env: EvmEnv,
+        inspector: I,
+    ) -> Self::Evm<DB, I> {
+        with_echo(EthEvmFactory::default().create_evm_with_inspector(db, env, inspector))
+    }
+}
+
+fn config<F: BorEvmFactory>(evm_factory: F) -> Config<F> {
+    let spec = ChainSpecBuilder::default()
+        .chain(Chain::from_id(137))
+        .genesis(Default::default())
+        .london_activated()
+        .build();
+    BorEvmConfig::new_with_custom_factory(Arc::new(spec), evm_factory)
+}
+
+/// Output of a call to [`ECHO`] in an EVM of `config`, if it succeeded.
+fn call_echo<F: BorEvmFactory>(config: &Config<F>) -> Option<Bytes> {
+    let mut db = CacheDB::new(EmptyDB::default());
+    db.insert_account_info(CALLER, AccountInfo { balance: U256::from(1), ..Default::default() });
+    let mut evm = config.evm_with_env(db, EvmEnv::default());
+    let tx = TxEnv {
+        caller: CALLER,
+        kind: TxKind::Call(ECHO),
+        data: Bytes::from_static(b"bor"),
+        gas_limit: 100_000,
+        ..Default::default()
+    };
+    let res = evm.transact(tx).unwrap();
+    res.result.is_success().then(|| res.result.output().cloned()).flatten()
+}
+
+#[test]
+fn custom_factory_precompile_is_callable() {
+    assert_eq!(call_echo(&config(EchoEvmFactory)), Some(Bytes::from_static(b"bor")));
+}
+
+#[test]
+fn default_factory_has_no_custom_precompile() {
+    // An empty account: the call succeeds without output.
+    assert_eq!(call_echo(&config(EthEvmFactory::default())), Some(Bytes::new()));
+}
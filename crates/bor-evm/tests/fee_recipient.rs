@@ -0,0 +1,145 @@
+//! Transaction fees are paid to the block's signer, not its (empty) beneficiary.
+//!
+//! Contracts read the same address from `COINBASE`, at every fork, whether the block is
+//! built, imported, traced or called into over RPC.
+
+use alloy_consensus::Header;
+use alloy_primitives::{keccak256, Address, Bytes, TxKind, U256};
+use bor_chainspec::BorHardfork;
+use bor_consensus::compute_seal_hash;
+use bor_evm::{block_fee_recipient, BorEvmConfig};
+use k256::ecdsa::SigningKey;
+use reth_chainspec::{Chain, ChainSpecBuilder};
+use reth_evm::{ConfigureEvm, Evm, EvmEnv, NextBlockEnvAttributes};
+use revm::{
+    context::TxEnv,
+    database::{CacheDB, EmptyDB},
+    state::{AccountInfo, Bytecode},
+};
+use std::sync::Arc;
+
+const CONTRACT: Address = Address::new([0x42; 20]);
+const CALLER: Address = Address::new([0x11; 20]);
+
+// COINBASE PUSH1 0 SSTORE STOP
+const STORE_COINBASE: &[u8] = &[0x41, 0x60, 0x00, 0x55, 0x00];
+
+fn mainnet_config() -> BorEvmConfig {
+    let spec = ChainSpecBuilder::default()
+        .chain(Chain::from_id(137))
+        .genesis(Default::default())
+        .london_activated()
+        .build();
+    BorEvmConfig::new(Arc::new(spec))
+}
+
+fn signing_key() -> SigningKey {
+    SigningKey::from_bytes((&keccak256(b"bor fee recipient").0).into()).unwrap()
+}
+
+fn key_address(key: &SigningKey) -> Address {
+    let point = key.verifying_key().to_encoded_point(false);
+    Address::from_slice(&keccak256(&point.as_bytes()[1..])[12..])
+}
+
+/// Header at `number` sealed by `key`, with a zero beneficiary as on Polygon.
+fn sealed_header(number: u64, key: &SigningKey) -> Header {
+    let mut header = Header {
+        number,
+        gas_limit: 30_000_000,
+        difficulty: U256::from(1),
+        base_fee_per_gas: Some(7),
+        extra_data: Bytes::from(vec![0u8; 32 + 65]),
+        ..Default::default()
+    };
+    let (sig, recid) = key.sign_prehash_recoverable(compute_seal_hash(&header).as_ref()).unwrap();
+    let mut extra = vec![0u8; 32];
+    extra.extend_from_slice(&sig.to_bytes());
+    extra.push(recid.to_byte());
+    header.extra_data = extra.into();
+    header
+}
+
+#[test]
+fn fees_go_to_seal_signer() {
+    let key = signing_key();
+    let header = sealed_header(100, &key);
+    assert_eq!(header.beneficiary, Address::ZERO);
+    assert_eq!(block_fee_recipient(&header), key_address(&key));
+}
+
+#[test]
+fn unsealed_header_falls_back_to_beneficiary() {
+    let header = Header { beneficiary: Address::new([0x33; 20]), ..Default::default() };
+    assert_eq!(block_fee_recipient(&header), Address::new([0x33; 20]));
+}
+
+#[test]
+fn evm_env_uses_signer_as_coinbase() {
+    let config = mainnet_config();
+    let key = signing_key();
+
+    let env = config.evm_env(&sealed_header(100, &key)).unwrap();
+    assert_eq!(env.block_env.beneficiary, key_address(&key));
+}
+
+/// What `COINBASE` returns to a transaction run in `env`, as `eth_call` and the tracers
+/// run it.
+fn coinbase_seen_by_contract(config: &BorEvmConfig, env: EvmEnv) -> Address {
+    let mut db = CacheDB::new(EmptyDB::default());
+    let code = Bytecode::new_raw(Bytes::from_static(STORE_COINBASE));
+    db.insert_account_info(CONTRACT, AccountInfo::from_bytecode(code));
+    db.insert_account_info(
+        CALLER,
+        AccountInfo { balance: U256::from(10).pow(U256::from(18)), ..Default::default() },
+    );
+
+    let mut evm = config.evm_with_env(db, env);
+    let tx = TxEnv {
+        caller: CALLER,
+        kind: TxKind::Call(CONTRACT),
+        gas_limit: 100_000,
+        gas_price: 7,
+        chain_id: Some(137),
+        ..Default::default()
+    };
+    let res = evm.transact(tx).unwrap();
+    assert!(res.result.is_success());
+    let word = res.state[&CONTRACT].storage[&U256::ZERO].present_value;
+    Address::from_word(word.into())
+}
+
+#[test]
+fn coinbase_opcode_returns_signer_at_every_fork() {
+    let config = mainnet_config();
+    let key = signing_key();
+
+    let blocks = BorHardfork::all().iter().map(|fork| fork.mainnet_block());
+    for number in std::iter::once(1).chain(blocks) {
+        let env = config.evm_env(&sealed_header(number, &key)).unwrap();
+        assert_eq!(coinbase_seen_by_contract(&config, env), key_address(&key), "{number}");
+    }
+}
+
+#[test]
+fn built_block_sees_the_coinbase_it_is_imported_with() {
+    let config = mainnet_config();
+    let key = signing_key();
+    let number = BorHardfork::Madhugiri.mainnet_block();
+    let parent = Header { number: number - 1, gas_limit: 30_000_000, ..Default::default() };
+
+    // The producer suggests its own signer as fee recipient, see `BorBlockEnvInput`.
+    let attributes = NextBlockEnvAttributes {
+        timestamp: 0,
+        suggested_fee_recipient: key_address(&key),
+        prev_randao: Default::default(),
+        gas_limit: 30_000_000,
+        parent_beacon_block_root: None,
+        withdrawals: None,
+        extra_data: Default::default(),
+    };
+    let built = config.next_evm_env(&parent, &attributes).unwrap();
+    let imported = config.evm_env(&sealed_header(number, &key)).unwrap();
+    assert_eq!(built.block_env.beneficiary, imported.block_env.beneficiary);
+    assert_eq!(coinbase_seen_by_contract(&config, imported), key_address(&key));
+}
@@ -0,0 +1,73 @@
+//! Mainnet blocks executed against the roots mainnet sealed them with.
+//!
+//! `fixtures/mainnet/corpus.toml` lists sprint starts, span starts and the blocks around
+//! the Bor forks. Each is captured into `fixtures/mainnet/<number>.json` by
+//! `boreth fixture`: the block, its execution witness and the span and state sync events
+//! Heimdall served for it. Executed on its witness, with the system calls of the mainnet
+//! `bor` config, every block must give the state root, receipts root and gas used of its
+//! header.
+//!
+//! Blocks without a captured fixture are skipped with a note on how to capture them:
+//! `boreth fixture --rpc <bor node> --bor.heimdall <heimdall>` captures every missing one.
+
+use bor_chainspec::{bor_mainnet_genesis, BorConfig, MAINNET_CHAIN_ID};
+use bor_evm::{
+    execute_witness_block, BorEvmConfig, BorPostExecution, BorSystemCaller, WitnessBlock,
+};
+use reth_chainspec::EthChainSpec;
+use serde::Deserialize;
+use std::sync::Arc;
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../fixtures/mainnet");
+
+#[derive(Debug, Deserialize)]
+struct Corpus {
+    block: Vec<CorpusBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorpusBlock {
+    number: u64,
+    label: String,
+}
+
+fn mainnet_config() -> BorEvmConfig {
+    let chain_spec = Arc::new(bor_mainnet_genesis().into_inner());
+    let bor_config = BorConfig::for_chain(MAINNET_CHAIN_ID, chain_spec.genesis()).unwrap();
+    BorEvmConfig::new(chain_spec)
+        .with_system_caller(BorSystemCaller::from_config(&bor_config))
+        .with_post_execution(BorPostExecution::from_config(&bor_config).unwrap())
+}
+
+#[test]
+fn mainnet_blocks_match_their_roots() {
+    let corpus = std::fs::read_to_string(format!("{CORPUS}/corpus.toml")).unwrap();
+    let corpus: Corpus = toml::from_str(&corpus).unwrap();
+    assert!(corpus.block.len() >= 20);
+    let config = mainnet_config();
+
+    for CorpusBlock { number, label } in corpus.block {
+        let path = format!("{CORPUS}/{number}.json");
+        let Ok(fixture) = std::fs::read(&path) else {
+            eprintln!(
+                "skipping block {number} ({label}): no {path}, capture it with \
+                 `boreth fixture --rpc <bor node> --bor.heimdall <heimdall> {number}`"
+            );
+            continue;
+        };
+        let block: WitnessBlock = serde_json::from_slice(&fixture).unwrap();
+        let executed = execute_witness_block(&config, &block)
+            .unwrap_or_else(|err| panic!("block {number} ({label}): {err}"));
+        let header = &executed.header;
+        assert_eq!(header.number, number, "{path} holds another block");
+        assert_eq!(executed.gas_used, header.gas_used, "gas used of block {number} ({label})");
+        assert_eq!(
+            executed.receipts_root, header.receipts_root,
+            "receipts root of block {number} ({label})"
+        );
+        assert_eq!(
+            executed.state_root, header.state_root,
+            "state root of block {number} ({label})"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! EVM behavior on either side of the Napoli boundary.
+//!
+//! Napoli brings Cancun's opcodes by block number. The block before it must
+//! treat `TSTORE`, `TLOAD` and `MCOPY` as invalid; the Napoli block itself
+//! must execute them. Blobs stay disabled throughout.
+
+use alloy_primitives::{Address, Bytes, U256};
+use bor_chainspec::{AmoyBorHardforks, BorHardfork, BorHardforks, MainnetBorHardforks};
+use bor_evm::{bor_spec_id, BorEvmConfig};
+use reth_chainspec::{Chain, ChainSpecBuilder};
+use reth_evm::{eth::EthEvmFactory, Evm, EvmEnv, EvmFactory};
+use revm::{
+    context::CfgEnv,
+    database::{CacheDB, EmptyDB},
+    primitives::hardfork::SpecId,
+    state::{AccountInfo, Bytecode},
+};
+use std::sync::Arc;
+
+const CONTRACT: Address = Address::new([0x42; 20]);
+
+/// Run `code` at `block` under `forks`, returning whether the call succeeded.
+fn call_succeeds(forks: &impl BorHardforks, block: u64, code: &'static [u8]) -> bool {
+    let mut db = CacheDB::new(EmptyDB::default());
+    let code = Bytecode::new_raw(Bytes::from_static(code));
+    db.insert_account_info(CONTRACT, AccountInfo::from_bytecode(code));
+
+    let spec = bor_spec_id(forks, block, SpecId::LONDON);
+    let mut env = EvmEnv::default();
+    env.cfg_env = CfgEnv::new().with_chain_id(137).with_spec_and_mainnet_gas_params(spec);
+    env.block_env.number = U256::from(block);
+    env.block_env.prevrandao = Some(Default::default());
+
+    let mut evm = EthEvmFactory::default().create_evm(db, env);
+    let res = evm.transact_system_call(Address::ZERO, CONTRACT, Bytes::new()).unwrap();
+    res.result.is_success()
+}
+
+// PUSH1 1 PUSH1 0 TSTORE PUSH1 0 TLOAD STOP
+const TRANSIENT_STORAGE: &[u8] = &[0x60, 0x01, 0x60, 0x00, 0x5d, 0x60, 0x00, 0x5c, 0x00];
+// PUSH1 32 PUSH1 0 PUSH1 32 MCOPY STOP
+const MCOPY: &[u8] = &[0x60, 0x20, 0x60, 0x00, 0x60, 0x20, 0x5e, 0x00];
+// PUSH0 STOP (Shanghai, enabled at Agra)
+const PUSH0: &[u8] = &[0x5f, 0x00];
+
+#[test]
+fn transient_storage_activates_at_napoli() {
+    let napoli = BorHardfork::Napoli.mainnet_block();
+    assert!(!call_succeeds(&MainnetBorHardforks, napoli - 1, TRANSIENT_STORAGE));
+    assert!(call_succeeds(&MainnetBorHardforks, napoli, TRANSIENT_STORAGE));
+}
+
+#[test]
+fn mcopy_activates_at_napoli() {
+    let napoli = BorHardfork::Napoli.mainnet_block();
+    assert!(!call_succeeds(&MainnetBorHardforks, napoli - 1, MCOPY));
+    assert!(call_succeeds(&MainnetBorHardforks, napoli, MCOPY));
+}
+
+#[test]
+fn push0_activates_at_agra() {
+    let agra = BorHardfork::Agra.mainnet_block();
+    assert!(!call_succeeds(&MainnetBorHardforks, agra - 1, PUSH0));
+    assert!(call_succeeds(&MainnetBorHardforks, agra, PUSH0));
+}
+
+#[test]
+fn amoy_boundary_uses_amoy_schedule() {
+    let napoli = BorHardfork::Napoli.amoy_block();
+    assert!(!call_succeeds(&AmoyBorHardforks, napoli - 1, TRANSIENT_STORAGE));
+    assert!(call_succeeds(&AmoyBorHardforks, napoli, MCOPY));
+}
+
+#[test]
+fn node_config_maps_napoli_to_cancun_without_blobs() {
+    let spec = ChainSpecBuilder::default()
+        .chain(Chain::from_id(137))
+        .genesis(Default::default())
+        .london_activated()
+        .build();
+    let config = BorEvmConfig::new(Arc::new(spec));
+    let napoli = BorHardfork::Napoli.mainnet_block();
+
+    assert_eq!(config.bor_cfg_env(0, napoli - 1).spec, SpecId::SHANGHAI);
+    let cfg = config.bor_cfg_env(0, napoli);
+    assert_eq!(cfg.spec, SpecId::CANCUN);
+    assert_eq!(cfg.max_blobs_per_tx, Some(0));
+}
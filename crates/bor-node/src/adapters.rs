@@ -0,0 +1,523 @@
+//! The node's source, sink and trigger traits over reth's provider, engine and network.
+//!
+//! The forkchoice driver, producer scheduler, producer monitor and proposal simulator are
+//! written against small traits so they can be tested without a node; these implement
+//! them for a running one.
+
+use crate::error::BorError;
+use crate::forkchoice::{ForkchoiceSink, HeadSource, NetworkHead};
+use crate::monitor::{MonitorSource, MonitoredBlock};
+use crate::params::BorParams;
+use crate::payload::BorEngineTypes;
+use crate::producer::{ParentBlock, PayloadTrigger, ProductionSource, Slot};
+use crate::proposal::{simulated_tx, ExecutedProposal, ProposalBlock, ProposalExecutor};
+use alloy_consensus::Transaction;
+use alloy_eips::eip2718::{Decodable2718, Encodable2718};
+use alloy_primitives::{Address, B256, U128, U256};
+use alloy_rpc_types_engine::ForkchoiceState;
+use alloy_rpc_types_eth::state::{AccountOverride, StateOverride};
+use bor_chainspec::BorHardforks;
+use bor_consensus::{compute_seal_hash, sprint_validator_set, BlockSealer};
+use bor_evm::{
+    bor_block_env, difficulty_word, pending_state_sync_changes, BorBlockEnvInput, BorEvmConfig,
+    PendingStateOverlay, SprintContext,
+};
+use bor_payload::{
+    order_deterministically, BorPayloadBuilderAttributes, BuildBudget, PayloadTx, PoolTx,
+    TxOrdering, DEFAULT_BUILD_MARGIN,
+};
+use bor_primitives::ValidatorSet;
+use bor_rpc::{get_author, BorRpcError};
+use bor_storage::SharedTotalDifficultyIndex;
+use heimdall_client::SharedSpanCache;
+use reth_chainspec::{ChainSpec, EthChainSpec};
+use reth_eth_wire::NewBlock;
+use reth_ethereum_primitives::TransactionSigned;
+use reth_evm::{
+    block::{BlockExecutionError, BlockExecutorFactory as _, BlockValidationError},
+    execute::BlockBuilder,
+    ConfigureEvm, EvmEnv, NextBlockEnvAttributes,
+};
+use reth_network::{primitives::NetworkPrimitives, NetworkHandle};
+use reth_node_api::{ConsensusEngineHandle, EngineApiMessageVersion, PayloadTypes};
+use reth_payload_builder::PayloadBuilderHandle;
+use reth_payload_primitives::{BuiltPayload, PayloadKind};
+use reth_primitives_traits::{SealedBlock, SealedHeader, SignedTransaction};
+use reth_provider::{BlockHashReader, BlockNumReader, HeaderProvider, StateProviderFactory};
+use reth_revm::{
+    database::StateProviderDatabase,
+    db::{BundleState, State},
+};
+use reth_transaction_pool::{PoolTransaction, TransactionPool};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Exposes the head peers announce to the [`ForkchoiceDriver`](crate::ForkchoiceDriver),
+/// and the node's canonical chain the finality of milestones is checked against.
+#[derive(Debug, Clone)]
+pub struct AnnouncedHead<P> {
+    /// Canonical chain of the node.
+    pub provider: P,
+    /// Heaviest block peers announced.
+    pub network: NetworkHead,
+}
+
+impl<P> HeadSource for AnnouncedHead<P>
+where
+    P: BlockNumReader + BlockHashReader + Send + Sync,
+{
+    /// The announced head while it is ahead of the canonical one, which only the
+    /// forkchoice updates sent for it move.
+    fn best_block(&self) -> Option<(u64, B256)> {
+        let number = self.provider.best_block_number().ok()?;
+        let local = self.provider.block_hash(number).ok()?.map(|hash| (number, hash));
+        match self.network.best_block() {
+            Some(announced) if local.is_none_or(|(number, _)| announced.0 > number) => {
+                Some(announced)
+            }
+            _ => local,
+        }
+    }
+
+    fn canonical_hash(&self, number: u64) -> Option<B256> {
+        self.provider.block_hash(number).ok().flatten()
+    }
+}
+
+/// Forwards forkchoice updates from the [`ForkchoiceDriver`](crate::ForkchoiceDriver) to
+/// the engine tree.
+#[derive(Debug, Clone)]
+pub struct EngineForkchoiceSink<T: PayloadTypes>(pub ConsensusEngineHandle<T>);
+
+impl<T: PayloadTypes> ForkchoiceSink for EngineForkchoiceSink<T> {
+    async fn update_forkchoice(&self, state: ForkchoiceState) -> eyre::Result<()> {
+        self.0
+            .fork_choice_updated(state, None, EngineApiMessageVersion::default())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Feeds the [`ProducerScheduler`](crate::ProducerScheduler) the canonical head, cached
+/// spans and the current signer.
+#[derive(Debug, Clone)]
+pub struct ProviderProduction<P> {
+    /// Canonical chain the parent of each slot is read from.
+    pub provider: P,
+    /// Fork schedule of the chain.
+    pub chain_spec: Arc<ChainSpec>,
+    /// Spans the producers of each slot are read from.
+    pub spans: SharedSpanCache,
+    /// Holder of the current signer.
+    pub params: BorParams,
+}
+
+impl<P> ProductionSource for ProviderProduction<P>
+where
+    P: BlockNumReader + HeaderProvider<Header = alloy_consensus::Header> + Send + Sync,
+{
+    fn parent(&self) -> Option<ParentBlock> {
+        let number = self.provider.best_block_number().ok()?;
+        let header = self.provider.sealed_header(number).ok()??;
+        Some(ParentBlock {
+            number,
+            hash: header.hash(),
+            timestamp: header.timestamp,
+            gas_limit: header.gas_limit,
+        })
+    }
+
+    fn validator_set(&self, number: u64) -> Option<ValidatorSet> {
+        let mut spans = self.spans.lock().expect("span cache lock poisoned");
+        sprint_validator_set(&*self.chain_spec, spans.span_for_block(number)?, number).ok()?
+    }
+
+    fn signer(&self) -> Option<Address> {
+        self.params.signer()
+    }
+
+    fn chain_spec(&self) -> &dyn BorHardforks {
+        &*self.chain_spec
+    }
+}
+
+impl<P> MonitorSource for ProviderProduction<P>
+where
+    P: BlockNumReader + HeaderProvider<Header = alloy_consensus::Header> + Send + Sync,
+{
+    fn block(&self, number: u64) -> Option<MonitoredBlock> {
+        let header = self.provider.sealed_header(number).ok()??;
+        let signer = get_author(&compute_seal_hash(header.header()), &header.extra_data).ok()?;
+        Some(MonitoredBlock { hash: header.hash(), timestamp: header.timestamp, signer })
+    }
+}
+
+/// Runs the system calls of a sprint-start slot on its parent's state ahead of the build.
+#[derive(Clone)]
+pub struct SprintPresimulation<P> {
+    /// State the calls run on.
+    pub provider: P,
+    /// EVM config blocks are built with.
+    pub evm_config: BorEvmConfig,
+    /// State sync events pending for the next sprint start.
+    pub pending: PendingStateOverlay,
+    /// Gas limit built blocks move toward (`--miner.gaslimit`).
+    pub gas_limit_target: Option<u64>,
+}
+
+impl<P> SprintPresimulation<P>
+where
+    P: StateProviderFactory + HeaderProvider<Header = alloy_consensus::Header>,
+{
+    /// Simulate the state sync events pending for `slot`, in the environment the payload
+    /// builder will give the block, and return the number of calls simulated.
+    ///
+    /// Span commits are not simulated; a block committing one runs its calls as usual.
+    pub fn run(&self, slot: &Slot) -> eyre::Result<usize> {
+        let factory = self.evm_config.block_executor_factory();
+        let Some(presimulator) = factory.presimulator() else { return Ok(0) };
+        let Some(pending) = self.pending.pending().filter(|p| p.block_number == slot.number)
+        else {
+            return Ok(0);
+        };
+        let Some(parent) = self.provider.header(slot.parent_hash)? else { return Ok(0) };
+
+        let chain_spec = self.evm_config.chain_spec();
+        let target =
+            bor_payload::gas_limit_target(&**chain_spec, slot.number, self.gas_limit_target);
+        let cfg_env = self.evm_config.bor_cfg_env(slot.timestamp, slot.number);
+        let input = BorBlockEnvInput {
+            number: slot.number,
+            timestamp: slot.timestamp,
+            fee_recipient: slot.signer,
+            difficulty: slot.difficulty,
+            gas_limit: bor_payload::next_gas_limit(parent.gas_limit, target),
+            base_fee: chain_spec.next_block_base_fee(&parent, slot.timestamp).unwrap_or_default(),
+        };
+        let block_env = bor_block_env(input, cfg_env.spec);
+        let state_syncs = pending.events();
+        let ctx = SprintContext { commit_span: None, state_syncs: &state_syncs };
+        Ok(presimulator.presimulate_from(
+            &self.provider,
+            factory.evm_factory(),
+            factory.system_caller(),
+            EvmEnv { cfg_env, block_env },
+            slot.parent_hash,
+            ctx,
+        )?)
+    }
+}
+
+/// Executes the blocks of `bor_simulateProposal` on the state of their head, with the EVM
+/// config blocks are built with.
+#[derive(Debug, Clone)]
+pub struct ProviderProposalExecutor<P> {
+    /// State the blocks are executed on.
+    pub provider: P,
+    /// EVM config blocks are built with.
+    pub evm_config: BorEvmConfig,
+}
+
+impl<P> ProposalExecutor for ProviderProposalExecutor<P>
+where
+    P: StateProviderFactory + HeaderProvider<Header = alloy_consensus::Header> + Send + Sync,
+{
+    fn execute(
+        &self,
+        block: &ProposalBlock,
+        transactions: Vec<PayloadTx>,
+    ) -> Result<ExecutedProposal, BorError> {
+        let unknown = || BorRpcError::InvalidParams(format!("unknown head {}", block.head));
+        let head = self.provider.header(block.head)?.ok_or_else(unknown)?;
+        let parent = alloy_consensus::Header {
+            number: block.number - 1,
+            timestamp: block.parent_timestamp,
+            gas_limit: block.parent_gas_limit,
+            ..head
+        };
+        let parent = SealedHeader::new(parent, block.head);
+        let state = self.provider.state_by_block_hash(block.head)?;
+        let mut db = State::builder()
+            .with_database(StateProviderDatabase::new(&state))
+            .with_bundle_update()
+            .build();
+        let attributes = NextBlockEnvAttributes {
+            timestamp: block.timestamp,
+            suggested_fee_recipient: block.producer,
+            prev_randao: difficulty_word(block.difficulty),
+            gas_limit: block.gas_limit,
+            parent_beacon_block_root: None,
+            withdrawals: None,
+            extra_data: block.extra_data.clone(),
+        };
+        let mut builder = self
+            .evm_config
+            .builder_for_next_block(&mut db, &parent, attributes)
+            .map_err(BlockExecutionError::other)?;
+        builder.apply_pre_execution_changes()?;
+
+        let mut gas_used = 0;
+        let mut transaction_count = 0;
+        for tx in transactions {
+            if gas_used + tx.gas_used > block.gas_limit {
+                continue;
+            }
+            let tx = TransactionSigned::decode_2718(&mut tx.data.as_ref())
+                .map_err(|e| BorRpcError::InvalidParams(e.to_string()))?;
+            let Ok(tx) = tx.try_into_recovered() else { continue };
+            match builder.execute_transaction(tx) {
+                Ok(used) => {
+                    gas_used += used;
+                    transaction_count += 1;
+                }
+                // Left out, as the payload builder leaves out what the block cannot take.
+                Err(BlockExecutionError::Validation(BlockValidationError::InvalidTx {
+                    ..
+                })) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let outcome = builder.finish(&state)?;
+        let header = outcome.block.header();
+        Ok(ExecutedProposal {
+            gas_used: header.gas_used,
+            state_root: header.state_root,
+            transaction_count,
+        })
+    }
+}
+
+/// The user transactions `bor_simulateProposal` builds a block from, as the payload
+/// builder would take them from `pool`.
+///
+/// With deterministic ordering they come from a snapshot of the pending transactions, so
+/// the same pool contents always give the same block.
+pub fn proposal_candidates<Pool>(pool: &Pool, ordering: TxOrdering) -> Vec<PayloadTx>
+where
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TransactionSigned>>,
+{
+    let payload_tx = |tx: &Pool::Transaction| {
+        let encoded = tx.clone_into_consensus().into_inner().encoded_2718();
+        simulated_tx(encoded.into(), tx.gas_limit(), tx.priority_fee_or_price())
+    };
+    match ordering {
+        TxOrdering::Pool => {
+            pool.best_transactions().map(|tx| payload_tx(&tx.transaction)).collect()
+        }
+        TxOrdering::Deterministic => {
+            let snapshot = pool.pending_transactions().into_iter().map(|tx| PoolTx {
+                sender: tx.sender(),
+                nonce: tx.nonce(),
+                hash: *tx.hash(),
+                tx: payload_tx(&tx.transaction),
+            });
+            order_deterministically(snapshot)
+        }
+    }
+}
+
+/// The accounts the state sync events pending for the next sprint start change on top
+/// of the latest state: what `eth_call` on the pending block and the transaction pool see
+/// of a bridge deposit before the sprint start commits it.
+#[derive(Debug, Clone)]
+pub struct PendingStateChanges<P> {
+    /// Latest state the events are applied on.
+    pub provider: P,
+    /// EVM config the events are executed with.
+    pub evm_config: BorEvmConfig,
+    /// State sync events pending for the next sprint start.
+    pub pending: PendingStateOverlay,
+}
+
+impl<P> PendingStateChanges<P>
+where
+    P: StateProviderFactory + BlockNumReader + HeaderProvider<Header = alloy_consensus::Header>,
+{
+    /// The changed accounts and the head they apply on, if any events are pending.
+    pub fn changes(&self) -> Result<Option<(u64, BundleState)>, BorError> {
+        let number = self.provider.best_block_number()?;
+        let events = self.pending.events_after(number);
+        if events.is_empty() {
+            return Ok(None);
+        }
+        let Some(head) = self.provider.header_by_number(number)? else { return Ok(None) };
+        let env = self.evm_config.evm_env(&head).map_err(BlockExecutionError::other)?;
+        let factory = self.evm_config.block_executor_factory();
+        let evm_factory = factory.evm_factory();
+        let changes = pending_state_sync_changes(&self.provider, evm_factory, env, &events)?;
+        Ok(Some((number, changes)))
+    }
+
+    /// The changed accounts as `eth_call` state overrides, if any events are pending.
+    pub fn overrides(&self) -> Result<Option<StateOverride>, BorError> {
+        let Some((_, changes)) = self.changes()? else { return Ok(None) };
+        let overrides = changes
+            .state
+            .into_iter()
+            .map(|(address, account)| {
+                let account_override = AccountOverride {
+                    balance: account.info.as_ref().map(|info| info.balance),
+                    nonce: account.info.as_ref().map(|info| info.nonce),
+                    state_diff: Some(
+                        account
+                            .storage
+                            .iter()
+                            .map(|(slot, value)| {
+                                (B256::from(*slot), B256::from(value.present_value))
+                            })
+                            .collect(),
+                    ),
+                    ..Default::default()
+                };
+                (address, account_override)
+            })
+            .collect();
+        Ok(Some(overrides))
+    }
+}
+
+/// Produces the block of a slot when the [`ProducerScheduler`](crate::ProducerScheduler)
+/// says so: starts a payload build job in the engine tree, takes the payload when its
+/// [`BuildBudget`] runs out, seals it, imports it and announces it to peers.
+pub struct EnginePayloadTrigger<P, N: NetworkPrimitives> {
+    /// Engine tree the built blocks are imported into.
+    pub engine: ConsensusEngineHandle<BorEngineTypes>,
+    /// Payload build jobs the built blocks are taken from.
+    pub payloads: PayloadBuilderHandle<BorEngineTypes>,
+    /// Signs the built blocks.
+    pub sealer: Arc<BlockSealer>,
+    /// Peers the sealed blocks are announced to.
+    pub network: NetworkHandle<N>,
+    /// Headers the total difficulty of announced blocks is summed from.
+    pub provider: P,
+    /// Total difficulties announced blocks are sent with.
+    pub total_difficulty: SharedTotalDifficultyIndex,
+    /// Fork schedule the Bor fields of each build are resolved from.
+    pub chain_spec: Arc<ChainSpec>,
+    /// Simulates sprint-start system calls while the slot is not due, if enabled.
+    pub presimulation: Option<SprintPresimulation<P>>,
+}
+
+impl<P, N> EnginePayloadTrigger<P, N>
+where
+    P: HeaderProvider<Header = alloy_consensus::Header>,
+    N: NetworkPrimitives<NewBlockPayload = NewBlock<reth_ethereum_primitives::Block>>,
+{
+    /// Total difficulty of a block of `difficulty` on top of block `parent`, if indexed.
+    fn total_difficulty(&self, parent: u64, difficulty: U256) -> eyre::Result<Option<U256>> {
+        let index = self.total_difficulty.read().expect("total difficulty lock poisoned");
+        let parent_total = index.total_difficulty(parent, |range| -> eyre::Result<_> {
+            let expected = range.end() - range.start() + 1;
+            let headers = self.provider.headers_range(range)?;
+            eyre::ensure!(headers.len() as u64 == expected, "headers below {parent} missing");
+            Ok(headers.iter().map(|header| header.difficulty).collect())
+        })?;
+        Ok(parent_total.map(|total| total + difficulty))
+    }
+
+    /// Announce `block` to peers, which need its total difficulty to judge it.
+    fn announce(&self, block: SealedBlock<reth_ethereum_primitives::Block>) -> eyre::Result<()> {
+        let (number, hash) = (block.number, block.hash());
+        let Some(td) = self.total_difficulty(number - 1, block.difficulty)? else {
+            debug!(target: "boreth", number, "total difficulty not indexed, block not announced");
+            return Ok(());
+        };
+        let td = U128::from(td.saturating_to::<u128>());
+        self.network.announce_block(NewBlock { block: block.into_block(), td }, hash);
+        Ok(())
+    }
+}
+
+impl<P, N> PayloadTrigger for EnginePayloadTrigger<P, N>
+where
+    P: StateProviderFactory
+        + HeaderProvider<Header = alloy_consensus::Header>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    N: NetworkPrimitives<NewBlockPayload = NewBlock<reth_ethereum_primitives::Block>>,
+{
+    async fn prepare(&self, slot: Slot) -> eyre::Result<()> {
+        let Some(presimulation) = self.presimulation.clone() else { return Ok(()) };
+        // Runs for as long as the calls take; the slot is built whether or not it is done.
+        tokio::task::spawn_blocking(move || match presimulation.run(&slot) {
+            Ok(0) => {}
+            Ok(calls) => {
+                debug!(target: "boreth", number = slot.number, calls, "pre-simulated sprint start")
+            }
+            Err(err) => {
+                debug!(target: "boreth", number = slot.number, %err, "sprint pre-simulation failed")
+            }
+        });
+        Ok(())
+    }
+
+    async fn build_payload(&self, slot: Slot) -> eyre::Result<()> {
+        // The signer can be rotated at runtime, the key cannot.
+        eyre::ensure!(
+            slot.signer == self.sealer.address(),
+            "slot of {} but the key is {}'s",
+            slot.signer,
+            self.sealer.address()
+        );
+        let state = ForkchoiceState { head_block_hash: slot.parent_hash, ..Default::default() };
+        let attributes = BorPayloadBuilderAttributes::new(
+            &*self.chain_spec,
+            slot.parent_hash,
+            slot.number,
+            slot.timestamp,
+            slot.signer,
+            slot.difficulty,
+        );
+        let updated = self
+            .engine
+            .fork_choice_updated(
+                state,
+                Some(attributes.engine_attributes()),
+                EngineApiMessageVersion::default(),
+            )
+            .await?;
+        let Some(payload_id) = updated.payload_id else {
+            eyre::bail!("no build started for block {}: {:?}", slot.number, updated.payload_status)
+        };
+        info!(
+            target: "boreth",
+            number = slot.number,
+            succession = slot.succession,
+            span_id = attributes.span_id,
+            sprint_start = attributes.sprint_start,
+            %payload_id,
+            "started block production"
+        );
+
+        // The block must be announced by its timestamp. The build stops taking
+        // transactions when the same budget runs out, so the payload resolves promptly.
+        let budget = BuildBudget::for_block_time(slot.timestamp, DEFAULT_BUILD_MARGIN);
+        tokio::time::sleep_until(budget.deadline().into()).await;
+        let payload = self
+            .payloads
+            .resolve_kind(payload_id, PayloadKind::WaitForPending)
+            .await
+            .ok_or_else(|| eyre::eyre!("build {payload_id} of block {} is gone", slot.number))??;
+        let mut block = payload.block().as_ref().clone().into_block();
+        self.sealer.seal(&mut block.header)?;
+        let block = SealedBlock::seal_slow(block);
+        let hash = block.hash();
+
+        let payload = BorEngineTypes::block_to_payload(block.clone());
+        let status = self.engine.new_payload(payload).await?;
+        eyre::ensure!(status.is_valid(), "sealed block {} rejected: {status:?}", slot.number);
+        let state = ForkchoiceState { head_block_hash: hash, ..Default::default() };
+        self.engine.fork_choice_updated(state, None, EngineApiMessageVersion::default()).await?;
+        info!(
+            target: "boreth",
+            number = slot.number,
+            %hash,
+            txs = block.body().transactions.len(),
+            gas_used = block.gas_used,
+            "sealed block"
+        );
+        self.announce(block)
+    }
+}
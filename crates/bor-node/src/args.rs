@@ -0,0 +1,676 @@
+//! Bor-specific command line arguments, layered on top of reth's `node` command.
+
+use crate::config::{BorNetwork, ForkchoiceMode};
+use crate::config_file::{BorConfigFile, ConfigFileError};
+use crate::txpool::{
+    BorTxPoolConfig, DEFAULT_JOURNAL_FILE, DEFAULT_MAX_ACCOUNT_SLOTS, DEFAULT_MAX_REORG_DEPTH,
+    DEFAULT_PENDING_MAX_COUNT, DEFAULT_QUEUED_LIFETIME, DEFAULT_QUEUED_MAX_COUNT,
+    DEFAULT_REJOURNAL_INTERVAL,
+};
+use alloy_primitives::Address;
+use bor_consensus::DEFAULT_CONTRACT_STATE_DISTANCE;
+use bor_payload::TxOrdering;
+use heimdall_client::{
+    config::{DEFAULT_STATE_SYNC_PAGE_SIZE, DEFAULT_TIMEOUT},
+    limit::DEFAULT_MAX_IN_FLIGHT,
+    HeimdallAuth, HeimdallConfig, RequestLimits, RetryPolicy, SpanTrust,
+};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
+
+/// Extra `node` arguments for Bor.
+#[derive(Debug, Clone, Default, clap::Args)]
+#[command(next_help_heading = "Bor")]
+pub struct BorArgs {
+    /// TOML file of Bor settings, keyed by flag name. Flags given on the command line
+    /// take precedence.
+    #[arg(long = "bor.config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Heimdall REST API endpoint. Defaults to the public endpoint of the selected chain.
+    #[arg(long = "bor.heimdall", value_name = "URL")]
+    pub heimdall_url: Option<Url>,
+
+    /// Who drives forkchoice updates: the node itself (from announced blocks and Heimdall
+    /// milestones), or, in hybrid setups, an external client via `engine_forkchoiceUpdated`.
+    #[arg(long = "bor.forkchoice", value_enum, default_value_t = ForkchoiceMode::Internal)]
+    pub forkchoice: ForkchoiceMode,
+
+    /// Maximum number of concurrent requests to Heimdall.
+    #[arg(long = "bor.heimdall-max-inflight", value_name = "N", default_value_t = DEFAULT_MAX_IN_FLIGHT)]
+    pub heimdall_max_in_flight: usize,
+
+    /// Maximum number of Heimdall requests per second. `0` disables the rate limit.
+    #[arg(long = "bor.heimdall-rps", value_name = "N", default_value_t = 0)]
+    pub heimdall_requests_per_second: u32,
+
+    /// Timeout of a single Heimdall request, in seconds. `0` disables the timeout.
+    #[arg(long = "bor.heimdall-timeout", value_name = "SECONDS", default_value_t = DEFAULT_TIMEOUT.as_secs())]
+    pub heimdall_timeout: u64,
+
+    /// Number of state sync events requested from Heimdall per page.
+    #[arg(long = "bor.heimdall-page-size", value_name = "N", default_value_t = DEFAULT_STATE_SYNC_PAGE_SIZE)]
+    pub heimdall_page_size: usize,
+
+    /// Maximum number of state sync pages fetched per sprint. `0` disables the limit.
+    #[arg(long = "bor.heimdall-max-pages", value_name = "N", default_value_t = 0)]
+    pub heimdall_max_pages: usize,
+
+    /// Number of attempts per Heimdall request, including the first.
+    #[arg(long = "bor.heimdall-retries", value_name = "N", default_value_t = RetryPolicy::default().max_attempts)]
+    pub heimdall_retries: u32,
+
+    /// Delay before the first retry of a Heimdall request, in milliseconds. Doubles per retry.
+    #[arg(long = "bor.heimdall-retry-delay", value_name = "MS", default_value_t = 500)]
+    pub heimdall_retry_delay_ms: u64,
+
+    /// Basic authentication for Heimdall, as `USER:PASSWORD`.
+    #[arg(long = "bor.heimdall-auth", value_name = "USER:PASSWORD")]
+    pub heimdall_auth: Option<HeimdallAuth>,
+
+    /// Bearer token sent to Heimdall.
+    #[arg(long = "bor.heimdall-token", value_name = "TOKEN", conflicts_with = "heimdall_auth")]
+    pub heimdall_token: Option<String>,
+
+    /// Extra header sent with every Heimdall request, as `NAME: VALUE`. Can be repeated.
+    #[arg(long = "bor.heimdall-header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    pub heimdall_headers: Vec<(String, String)>,
+
+    /// PEM file of a root certificate to trust for Heimdall, e.g. of a private CA. Can be
+    /// repeated.
+    #[arg(long = "bor.heimdall-ca", value_name = "PATH")]
+    pub heimdall_ca: Vec<PathBuf>,
+
+    /// Address to accept Heimdall push notifications on, e.g. `127.0.0.1:8555`. New spans,
+    /// milestones and checkpoints are then fetched as soon as announced rather than at the
+    /// next poll.
+    #[arg(long = "bor.heimdall-push", value_name = "ADDR")]
+    pub heimdall_push: Option<SocketAddr>,
+
+    /// How far spans served by Heimdall are trusted: `trusted` accepts them as served,
+    /// `warn` and `verify` check the span before each against the ValidatorSet contract
+    /// and log, or reject, a disagreement.
+    #[arg(long = "bor.heimdall-span-trust", value_name = "MODE", default_value = "trusted")]
+    pub heimdall_span_trust: SpanTrust,
+
+    /// Second Heimdall endpoint to fetch state sync events from. Events are only applied
+    /// if both endpoints serve the same ones; on a mismatch block production halts.
+    #[arg(long = "bor.heimdall-cross-check", value_name = "URL")]
+    pub heimdall_cross_check: Option<Url>,
+
+    /// Delete the spans, milestone and checkpoint cached from Heimdall in the data
+    /// directory before starting, so they are all fetched again. Command line only.
+    #[arg(long = "bor.clear-heimdall-cache")]
+    pub clear_heimdall_cache: bool,
+
+    /// Blocks more than this far behind the latest milestone have their signer checked
+    /// against the ValidatorSet contract rather than the Heimdall span.
+    #[arg(long = "bor.contract-state-distance", value_name = "BLOCKS", default_value_t = DEFAULT_CONTRACT_STATE_DISTANCE)]
+    pub contract_state_distance: u64,
+
+    /// Address to produce blocks as. Can be rotated at runtime with `bor_setSigner`.
+    #[arg(long = "bor.signer", value_name = "ADDRESS")]
+    pub signer: Option<Address>,
+
+    /// File holding the hex-encoded secret key produced blocks are sealed with. Blocks
+    /// are only produced with a key; its address is the signer unless one is given.
+    #[arg(long = "bor.keyfile", value_name = "PATH")]
+    pub key_file: Option<PathBuf>,
+
+    /// Gas limit produced blocks move toward, within 1/1024 of the parent's per block.
+    /// Defaults to the chain's block gas limit at each block: 30M, 45M from Bhilai.
+    #[arg(long = "miner.gaslimit", value_name = "GAS")]
+    pub miner_gas_limit: Option<u64>,
+
+    /// Order the user transactions of simulated blocks by effective tip, nonce and hash
+    /// instead of pool arrival, so a block can be rebuilt from a snapshot of the pool.
+    #[arg(long = "miner.deterministic-ordering")]
+    pub deterministic_ordering: bool,
+
+    /// Run the system calls of sprint-start blocks this node produces on the parent
+    /// state while waiting for the slot, and reuse them if the parent is still the tip.
+    #[arg(long = "bor.presimulate-sprint")]
+    pub presimulate_sprint: bool,
+
+    /// Record the gas and storage writes of each state sync event executed, and warn
+    /// about those using more than `GAS`.
+    #[arg(long = "bor.profile-state-syncs", value_name = "GAS")]
+    pub profile_state_syncs: Option<u64>,
+
+    /// Check the receipts root and logs bloom of each imported block after executing
+    /// it. When an executed block is rejected, on any root, write the accounts it touched
+    /// and the gas of each transaction to `bor-execution-diffs` in the data directory,
+    /// then stop the node.
+    #[arg(long = "bor.assert-roots")]
+    pub assert_roots: bool,
+
+    /// Maximum number of executable transactions in the pool.
+    #[arg(long = "bor.txpool.pending", value_name = "N", default_value_t = DEFAULT_PENDING_MAX_COUNT)]
+    pub txpool_pending: usize,
+
+    /// Maximum number of non-executable transactions in the pool.
+    #[arg(long = "bor.txpool.queued", value_name = "N", default_value_t = DEFAULT_QUEUED_MAX_COUNT)]
+    pub txpool_queued: usize,
+
+    /// Maximum number of executable transactions per account.
+    #[arg(long = "bor.txpool.account-slots", value_name = "N", default_value_t = DEFAULT_MAX_ACCOUNT_SLOTS)]
+    pub txpool_account_slots: usize,
+
+    /// How long a non-executable transaction stays in the pool, in seconds.
+    #[arg(long = "bor.txpool.lifetime", value_name = "SECONDS", default_value_t = DEFAULT_QUEUED_LIFETIME.as_secs())]
+    pub txpool_lifetime: u64,
+
+    /// Journal of local transactions. Defaults to `bor-transactions.rlp` in the data directory.
+    #[arg(long = "bor.txpool.journal", value_name = "PATH")]
+    pub txpool_journal: Option<PathBuf>,
+
+    /// Keep local transactions in memory only.
+    #[arg(long = "bor.txpool.nojournal", conflicts_with = "txpool_journal")]
+    pub txpool_no_journal: bool,
+
+    /// Interval between journal rewrites, in seconds.
+    #[arg(long = "bor.txpool.rejournal", value_name = "SECONDS", default_value_t = DEFAULT_REJOURNAL_INTERVAL.as_secs())]
+    pub txpool_rejournal: u64,
+
+    /// Reload the accounts changed by reorgs up to this many blocks deep into the pool at
+    /// once, rather than a few per block as reth does past 64 blocks.
+    #[arg(long = "bor.txpool.reorg-depth", value_name = "BLOCKS", default_value_t = DEFAULT_MAX_REORG_DEPTH)]
+    pub txpool_reorg_depth: u64,
+}
+
+impl BorArgs {
+    /// Fill the flags left at their defaults from the `--bor.config` file, if one is set.
+    pub fn apply_config_file(&mut self) -> Result<(), ConfigFileError> {
+        match self.config.clone() {
+            Some(path) => self.merge_config_file(&BorConfigFile::load(&path)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Fill the flags left at their defaults from `file`.
+    ///
+    /// A flag is taken as given on the command line when it differs from its default, so a
+    /// flag explicitly set to its default value can still be overridden by the file.
+    pub fn merge_config_file(&mut self, file: &BorConfigFile) -> Result<(), ConfigFileError> {
+        let defaults = Self::cli_defaults();
+        for entry in file.entries() {
+            match entry.key {
+                "bor.heimdall" => {
+                    fill(&mut self.heimdall_url, &defaults.heimdall_url, Some(entry.parse()?))
+                }
+                "bor.forkchoice" => {
+                    let mode = entry.get::<String>()?;
+                    let mode = clap::ValueEnum::from_str(&mode, true)
+                        .map_err(|_| entry.invalid("expected `internal` or `external`"))?;
+                    fill(&mut self.forkchoice, &defaults.forkchoice, mode)
+                }
+                "bor.heimdall-max-inflight" => fill(
+                    &mut self.heimdall_max_in_flight,
+                    &defaults.heimdall_max_in_flight,
+                    entry.get()?,
+                ),
+                "bor.heimdall-rps" => fill(
+                    &mut self.heimdall_requests_per_second,
+                    &defaults.heimdall_requests_per_second,
+                    entry.get()?,
+                ),
+                "bor.heimdall-timeout" => {
+                    fill(&mut self.heimdall_timeout, &defaults.heimdall_timeout, entry.get()?)
+                }
+                "bor.heimdall-page-size" => {
+                    let size = entry.get::<usize>()?;
+                    if size == 0 {
+                        return Err(entry.invalid("must be at least 1"));
+                    }
+                    fill(&mut self.heimdall_page_size, &defaults.heimdall_page_size, size)
+                }
+                "bor.heimdall-max-pages" => {
+                    fill(&mut self.heimdall_max_pages, &defaults.heimdall_max_pages, entry.get()?)
+                }
+                "bor.heimdall-retries" => {
+                    let retries = entry.get::<u32>()?;
+                    if retries == 0 {
+                        return Err(entry.invalid("must be at least 1"));
+                    }
+                    fill(&mut self.heimdall_retries, &defaults.heimdall_retries, retries)
+                }
+                "bor.heimdall-retry-delay" => fill(
+                    &mut self.heimdall_retry_delay_ms,
+                    &defaults.heimdall_retry_delay_ms,
+                    entry.get()?,
+                ),
+                "bor.heimdall-auth" => {
+                    fill(&mut self.heimdall_auth, &defaults.heimdall_auth, Some(entry.parse()?))
+                }
+                "bor.heimdall-token" => {
+                    fill(&mut self.heimdall_token, &defaults.heimdall_token, Some(entry.get()?))
+                }
+                "bor.heimdall-header" => fill(
+                    &mut self.heimdall_headers,
+                    &defaults.heimdall_headers,
+                    entry.parse_each(parse_header)?,
+                ),
+                "bor.heimdall-ca" => fill(
+                    &mut self.heimdall_ca,
+                    &defaults.heimdall_ca,
+                    entry.parse_each(PathBuf::from_str)?,
+                ),
+                "bor.heimdall-push" => {
+                    fill(&mut self.heimdall_push, &defaults.heimdall_push, Some(entry.parse()?))
+                }
+                "bor.heimdall-span-trust" => fill(
+                    &mut self.heimdall_span_trust,
+                    &defaults.heimdall_span_trust,
+                    entry.parse()?,
+                ),
+                "bor.heimdall-cross-check" => fill(
+                    &mut self.heimdall_cross_check,
+                    &defaults.heimdall_cross_check,
+                    Some(entry.parse()?),
+                ),
+                "bor.contract-state-distance" => fill(
+                    &mut self.contract_state_distance,
+                    &defaults.contract_state_distance,
+                    entry.get()?,
+                ),
+                "bor.signer" => fill(&mut self.signer, &defaults.signer, Some(entry.parse()?)),
+                "bor.keyfile" => fill(
+                    &mut self.key_file,
+                    &defaults.key_file,
+                    Some(entry.get::<String>()?.into()),
+                ),
+                "miner.gaslimit" => {
+                    fill(&mut self.miner_gas_limit, &defaults.miner_gas_limit, Some(entry.get()?))
+                }
+                "miner.deterministic-ordering" => fill(
+                    &mut self.deterministic_ordering,
+                    &defaults.deterministic_ordering,
+                    entry.get()?,
+                ),
+                "bor.presimulate-sprint" => fill(
+                    &mut self.presimulate_sprint,
+                    &defaults.presimulate_sprint,
+                    entry.get()?,
+                ),
+                "bor.profile-state-syncs" => fill(
+                    &mut self.profile_state_syncs,
+                    &defaults.profile_state_syncs,
+                    Some(entry.get()?),
+                ),
+                "bor.assert-roots" => {
+                    fill(&mut self.assert_roots, &defaults.assert_roots, entry.get()?)
+                }
+                "bor.txpool.pending" => {
+                    fill(&mut self.txpool_pending, &defaults.txpool_pending, entry.get()?)
+                }
+                "bor.txpool.queued" => {
+                    fill(&mut self.txpool_queued, &defaults.txpool_queued, entry.get()?)
+                }
+                "bor.txpool.account-slots" => fill(
+                    &mut self.txpool_account_slots,
+                    &defaults.txpool_account_slots,
+                    entry.get()?,
+                ),
+                "bor.txpool.lifetime" => {
+                    fill(&mut self.txpool_lifetime, &defaults.txpool_lifetime, entry.get()?)
+                }
+                "bor.txpool.journal" => fill(
+                    &mut self.txpool_journal,
+                    &defaults.txpool_journal,
+                    Some(entry.get::<String>()?.into()),
+                ),
+                "bor.txpool.nojournal" => {
+                    fill(&mut self.txpool_no_journal, &defaults.txpool_no_journal, entry.get()?)
+                }
+                "bor.txpool.rejournal" => {
+                    fill(&mut self.txpool_rejournal, &defaults.txpool_rejournal, entry.get()?)
+                }
+                "bor.txpool.reorg-depth" => {
+                    fill(&mut self.txpool_reorg_depth, &defaults.txpool_reorg_depth, entry.get()?)
+                }
+                // bor-geth settings boreth has no counterpart for: only their defaults are
+                // accepted, so a bor-geth config does not silently change meaning.
+                "bor.devfakeauthor" | "bor.parallel-evm" => {
+                    if entry.get::<bool>()? {
+                        return Err(ConfigFileError::Unsupported(entry.key.to_string()));
+                    }
+                }
+                "bor.prefetch-depth" => {
+                    entry.get::<u64>()?;
+                    return Err(ConfigFileError::Unsupported(entry.key.to_string()));
+                }
+                key => return Err(ConfigFileError::UnknownKey(key.to_string())),
+            }
+        }
+        if self.heimdall_auth.is_some() && self.heimdall_token.is_some() {
+            return Err(ConfigFileError::Invalid {
+                key: "bor.heimdall-token".to_string(),
+                reason: "conflicts with bor.heimdall-auth".to_string(),
+            });
+        }
+        if self.txpool_no_journal && self.txpool_journal.is_some() {
+            return Err(ConfigFileError::Invalid {
+                key: "bor.txpool.nojournal".to_string(),
+                reason: "conflicts with bor.txpool.journal".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The flags as parsed from an empty command line.
+    fn cli_defaults() -> Self {
+        use clap::{Args, FromArgMatches};
+        let command = Self::augment_args(clap::Command::new("boreth"));
+        Self::from_arg_matches(&command.get_matches_from(["boreth"]))
+            .expect("defaults of the Bor flags parse")
+    }
+
+    /// Returns the Heimdall URL to use for `chain_id`: the explicit flag if set, otherwise the
+    /// public endpoint of a known network.
+    pub fn heimdall_url_for(&self, chain_id: u64) -> Option<Url> {
+        self.heimdall_url
+            .clone()
+            .or_else(|| BorNetwork::from_chain_id(chain_id).map(BorNetwork::default_heimdall_url))
+    }
+
+    /// Returns the request limits for the Heimdall client.
+    pub fn heimdall_limits(&self) -> RequestLimits {
+        RequestLimits::default()
+            .with_max_in_flight(self.heimdall_max_in_flight)
+            .with_requests_per_second(self.heimdall_requests_per_second)
+    }
+
+    /// Returns the request settings for the Heimdall client, reading the `--bor.heimdall-ca`
+    /// certificates.
+    pub fn heimdall_config(&self) -> eyre::Result<HeimdallConfig> {
+        let mut config = HeimdallConfig::default()
+            .with_timeout(Duration::from_secs(self.heimdall_timeout))
+            .with_state_sync_page_size(self.heimdall_page_size)
+            .with_max_state_sync_pages(self.heimdall_max_pages)
+            .with_retry(RetryPolicy {
+                max_attempts: self.heimdall_retries,
+                base_delay: Duration::from_millis(self.heimdall_retry_delay_ms),
+            })
+            .with_span_trust(self.heimdall_span_trust);
+        if let Some(auth) = &self.heimdall_auth {
+            config = config.with_auth(auth.clone());
+        }
+        if let Some(token) = &self.heimdall_token {
+            config = config.with_auth(HeimdallAuth::Bearer(token.clone()));
+        }
+        for (name, value) in &self.heimdall_headers {
+            config = config.with_header(name, value);
+        }
+        for path in &self.heimdall_ca {
+            let pem = std::fs::read(path).map_err(|e| {
+                eyre::eyre!("failed to read Heimdall CA certificate {}: {e}", path.display())
+            })?;
+            config = config.with_root_certificate(pem);
+        }
+        Ok(config)
+    }
+
+    /// Returns how user transactions of simulated blocks are ordered.
+    pub fn tx_ordering(&self) -> TxOrdering {
+        if self.deterministic_ordering {
+            TxOrdering::Deterministic
+        } else {
+            TxOrdering::Pool
+        }
+    }
+
+    /// Returns the pool sizes and journal settings, with the journal under `data_dir` unless
+    /// set explicitly.
+    pub fn txpool_config(&self, data_dir: &Path) -> BorTxPoolConfig {
+        let journal = (!self.txpool_no_journal).then(|| {
+            self.txpool_journal.clone().unwrap_or_else(|| data_dir.join(DEFAULT_JOURNAL_FILE))
+        });
+        BorTxPoolConfig {
+            pending_max_count: self.txpool_pending,
+            queued_max_count: self.txpool_queued,
+            max_account_slots: self.txpool_account_slots,
+            queued_lifetime: Duration::from_secs(self.txpool_lifetime),
+            journal,
+            // A zero interval would make the journal task spin.
+            rejournal: Duration::from_secs(self.txpool_rejournal.max(1)),
+            max_reorg_depth: self.txpool_reorg_depth,
+        }
+    }
+}
+
+/// Set `arg` to `value` unless it was changed from `default` on the command line.
+fn fill<T: PartialEq>(arg: &mut T, default: &T, value: T) {
+    if arg == default {
+        *arg = value;
+    }
+}
+
+/// Parse a `NAME: VALUE` header.
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s.split_once(':').ok_or_else(|| format!("expected NAME: VALUE, got {s}"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing header name in {s}"));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        bor: BorArgs,
+    }
+
+    #[test]
+    fn test_defaults() {
+        let args = TestCli::parse_from(["boreth"]).bor;
+        assert_eq!(args.forkchoice, ForkchoiceMode::Internal);
+        assert!(args.heimdall_url.is_none());
+        assert_eq!(
+            args.heimdall_url_for(137).unwrap().as_str(),
+            "https://heimdall-api.polygon.technology/"
+        );
+        assert!(args.heimdall_url_for(1).is_none());
+        assert_eq!(args.heimdall_limits(), RequestLimits::default());
+        assert_eq!(args.heimdall_config().unwrap(), HeimdallConfig::default());
+        assert!(args.signer.is_none());
+        assert!(args.miner_gas_limit.is_none());
+        assert!(!args.presimulate_sprint);
+        assert!(args.profile_state_syncs.is_none());
+        assert!(!args.assert_roots);
+        assert_eq!(args.tx_ordering(), TxOrdering::Pool);
+        assert!(args.heimdall_push.is_none());
+        assert!(args.heimdall_cross_check.is_none());
+        assert!(!args.clear_heimdall_cache);
+        assert_eq!(args.contract_state_distance, DEFAULT_CONTRACT_STATE_DISTANCE);
+        assert_eq!(
+            args.txpool_config(Path::new("/data")),
+            BorTxPoolConfig {
+                journal: Some(PathBuf::from("/data/bor-transactions.rlp")),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_txpool_flags() {
+        let args = TestCli::parse_from([
+            "boreth",
+            "--bor.txpool.pending",
+            "65536",
+            "--bor.txpool.account-slots",
+            "32",
+            "--bor.txpool.lifetime",
+            "600",
+            "--bor.txpool.rejournal",
+            "0",
+            "--bor.txpool.reorg-depth",
+            "128",
+        ])
+        .bor;
+        let config = args.txpool_config(Path::new("/data"));
+        assert_eq!(config.pending_max_count, 65_536);
+        assert_eq!(config.queued_max_count, DEFAULT_QUEUED_MAX_COUNT);
+        assert_eq!(config.max_account_slots, 32);
+        assert_eq!(config.queued_lifetime, Duration::from_secs(600));
+        assert_eq!(config.rejournal, Duration::from_secs(1));
+        assert_eq!(config.max_reorg_depth, 128);
+
+        let args = TestCli::parse_from(["boreth", "--bor.txpool.nojournal"]).bor;
+        assert_eq!(args.txpool_config(Path::new("/data")).journal, None);
+        let conflicting = ["boreth", "--bor.txpool.nojournal", "--bor.txpool.journal", "x"];
+        assert!(TestCli::try_parse_from(conflicting).is_err());
+    }
+
+    #[test]
+    fn test_explicit_flags() {
+        let args = TestCli::parse_from([
+            "boreth",
+            "--bor.heimdall",
+            "http://localhost:1317",
+            "--bor.forkchoice",
+            "external",
+            "--bor.heimdall-max-inflight",
+            "4",
+            "--bor.heimdall-rps",
+            "20",
+            "--bor.heimdall-timeout",
+            "10",
+            "--bor.heimdall-page-size",
+            "100",
+            "--bor.heimdall-max-pages",
+            "8",
+            "--bor.heimdall-retries",
+            "5",
+            "--bor.signer",
+            "0x00000000000000000000000000000000000000aa",
+            "--bor.keyfile",
+            "validator.key",
+            "--miner.gaslimit",
+            "45000000",
+            "--miner.deterministic-ordering",
+            "--bor.presimulate-sprint",
+            "--bor.profile-state-syncs",
+            "1000000",
+            "--bor.assert-roots",
+            "--bor.heimdall-push",
+            "127.0.0.1:8555",
+            "--bor.heimdall-span-trust",
+            "verify",
+            "--bor.heimdall-cross-check",
+            "http://backup:1317",
+            "--bor.clear-heimdall-cache",
+        ])
+        .bor;
+        assert_eq!(
+            args.heimdall_limits(),
+            RequestLimits { max_in_flight: 4, requests_per_second: Some(20) }
+        );
+        let config = args.heimdall_config().unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(10));
+        assert_eq!(config.state_sync_page_size, 100);
+        assert_eq!(config.max_state_sync_pages, Some(8));
+        assert_eq!(config.retry.max_attempts, 5);
+        assert_eq!(config.retry.base_delay, Duration::from_millis(500));
+        assert_eq!(config.span_trust, SpanTrust::Verify);
+        assert_eq!(args.forkchoice, ForkchoiceMode::External);
+        assert_eq!(args.signer, Some(Address::with_last_byte(0xaa)));
+        assert_eq!(args.key_file, Some(PathBuf::from("validator.key")));
+        assert_eq!(args.miner_gas_limit, Some(45_000_000));
+        assert!(args.presimulate_sprint);
+        assert_eq!(args.profile_state_syncs, Some(1_000_000));
+        assert!(args.assert_roots);
+        assert_eq!(args.tx_ordering(), TxOrdering::Deterministic);
+        assert_eq!(args.heimdall_push, Some("127.0.0.1:8555".parse().unwrap()));
+        assert_eq!(args.heimdall_url_for(137).unwrap().as_str(), "http://localhost:1317/");
+        assert_eq!(args.heimdall_cross_check.unwrap().as_str(), "http://backup:1317/");
+        assert!(args.clear_heimdall_cache);
+    }
+
+    #[test]
+    fn test_heimdall_auth_flags() {
+        let args = TestCli::parse_from([
+            "boreth",
+            "--bor.heimdall-token",
+            "t0ken",
+            "--bor.heimdall-header",
+            "X-Validator: v1",
+            "--bor.heimdall-header",
+            "X-Region:eu",
+        ])
+        .bor;
+        let config = args.heimdall_config().unwrap();
+        assert_eq!(config.auth, Some(HeimdallAuth::Bearer("t0ken".into())));
+        assert_eq!(
+            config.headers,
+            vec![("X-Validator".into(), "v1".into()), ("X-Region".into(), "eu".into())]
+        );
+
+        let args = TestCli::parse_from(["boreth", "--bor.heimdall-auth", "bor:pw"]).bor;
+        assert!(matches!(args.heimdall_config().unwrap().auth, Some(HeimdallAuth::Basic { .. })));
+
+        let both = ["boreth", "--bor.heimdall-auth", "bor:pw", "--bor.heimdall-token", "t"];
+        assert!(TestCli::try_parse_from(both).is_err());
+        assert!(TestCli::try_parse_from(["boreth", "--bor.heimdall-header", "novalue"]).is_err());
+
+        let missing = ["boreth", "--bor.heimdall-ca", "/nonexistent/ca.pem"];
+        assert!(TestCli::parse_from(missing).bor.heimdall_config().is_err());
+    }
+
+    #[test]
+    fn test_config_file_below_flags() {
+        let file: BorConfigFile = r#"
+            [bor]
+            heimdall = "http://heimdall.local:1317"
+            heimdall-retries = 7
+            signer = "0x00000000000000000000000000000000000000aa"
+            forkchoice = "external"
+            assert-roots = true
+            devfakeauthor = false
+
+            [bor.txpool]
+            pending = 65536
+
+            [miner]
+            gaslimit = 45000000
+        "#
+        .parse()
+        .unwrap();
+        let mut args = TestCli::parse_from(["boreth", "--bor.heimdall-retries", "2"]).bor;
+        args.merge_config_file(&file).unwrap();
+
+        assert_eq!(args.heimdall_url_for(137).unwrap().as_str(), "http://heimdall.local:1317/");
+        assert_eq!(args.heimdall_config().unwrap().retry.max_attempts, 2);
+        assert_eq!(args.signer, Some(Address::with_last_byte(0xaa)));
+        assert_eq!(args.forkchoice, ForkchoiceMode::External);
+        assert!(args.assert_roots);
+        assert_eq!(args.txpool_pending, 65_536);
+        assert_eq!(args.miner_gas_limit, Some(45_000_000));
+    }
+
+    #[test]
+    fn test_config_file_errors_name_the_key() {
+        let merge = |toml: &str| {
+            let mut args = TestCli::parse_from(["boreth"]).bor;
+            args.merge_config_file(&toml.parse().unwrap()).unwrap_err().to_string()
+        };
+        assert!(merge("bor.heimdall-retries = 0").starts_with("`bor.heimdall-retries`"));
+        assert!(merge("bor.heimdall = \"not a url\"").starts_with("`bor.heimdall`"));
+        assert!(merge("bor.forkchoice = \"both\"").starts_with("`bor.forkchoice`"));
+        assert!(merge("bor.heimdall-span-trust = \"no\"").starts_with("`bor.heimdall-span-trust`"));
+        assert!(merge("bor.heimdall-cross-check = 1").starts_with("`bor.heimdall-cross-check`"));
+        assert!(merge("miner.gaslimit = \"lots\"").starts_with("`miner.gaslimit`"));
+        assert_eq!(merge("bor.heimdal = \"x\""), "unknown key `bor.heimdal`");
+        let unsupported = "`bor.parallel-evm` is not supported by boreth";
+        assert_eq!(merge("bor.parallel-evm = true"), unsupported);
+        assert!(merge("[bor]\nheimdall-auth = \"u:p\"\nheimdall-token = \"t\"")
+            .starts_with("`bor.heimdall-token`"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Canonical chain updates with their Bor metadata.
+//!
+//! reth's canonical state notifications carry blocks and their execution outcome,
+//! but not what Bor components ask of every new block: who produced it, which span it
+//! belongs to and which state sync events it committed. The node task following the
+//! canonical chain derives them once per block and publishes a [`BorCanonUpdate`] on
+//! [`BorCanonNotifications`]; the milestone voter, ExExes and metrics subscribe to it
+//! instead of recovering seals and querying the state sync index themselves.
+
+use alloy_primitives::{Address, B256};
+use bor_chainspec::BorHardforks;
+use bor_storage::CommittedStateSync;
+use reth_metrics::{
+    metrics::{Counter, Gauge},
+    Metrics,
+};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// Updates [`BorCanonNotifications`] buffers for slow subscribers.
+pub const BOR_CANON_CHANNEL_SIZE: usize = 256;
+
+/// Bor metadata of a canonical block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorBlockMeta {
+    /// Block number.
+    pub number: u64,
+    /// Block hash.
+    pub hash: B256,
+    /// Heimdall span the block belongs to.
+    pub span_id: u64,
+    /// Validator that signed the block, if its seal could be recovered.
+    pub producer: Option<Address>,
+    /// IDs of the state sync events the block committed, if any.
+    pub state_sync_ids: Option<RangeInclusive<u64>>,
+}
+
+impl BorBlockMeta {
+    /// Metadata of block `number` with hash `hash`, signed by `producer`, that
+    /// committed `events`, in the spans `hardforks` sets.
+    pub fn new(
+        hardforks: &impl BorHardforks,
+        number: u64,
+        hash: B256,
+        producer: Option<Address>,
+        events: &[CommittedStateSync],
+    ) -> Self {
+        let state_sync_ids = match (events.first(), events.last()) {
+            (Some(first), Some(last)) => Some(first.id..=last.id),
+            _ => None,
+        };
+        Self {
+            number,
+            hash,
+            span_id: hardforks.bor_span_id(number),
+            producer,
+            state_sync_ids,
+        }
+    }
+}
+
+/// A change of the canonical chain.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BorCanonUpdate {
+    /// First block number reorged out, if the update is a reorg.
+    pub reverted_from: Option<u64>,
+    /// Blocks that became canonical, in order.
+    pub committed: Vec<BorBlockMeta>,
+}
+
+impl BorCanonUpdate {
+    /// The last block that became canonical.
+    pub fn tip(&self) -> Option<&BorBlockMeta> {
+        self.committed.last()
+    }
+}
+
+/// Broadcast of [`BorCanonUpdate`]s. Clones publish to the same subscribers.
+#[derive(Debug, Clone)]
+pub struct BorCanonNotifications {
+    sender: broadcast::Sender<Arc<BorCanonUpdate>>,
+}
+
+impl Default for BorCanonNotifications {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BorCanonNotifications {
+    /// Create a broadcast buffering [`BOR_CANON_CHANNEL_SIZE`] updates.
+    pub fn new() -> Self {
+        Self { sender: broadcast::channel(BOR_CANON_CHANNEL_SIZE).0 }
+    }
+
+    /// Receive the updates published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<BorCanonUpdate>> {
+        self.sender.subscribe()
+    }
+
+    /// Publish `update` to the current subscribers. Without subscribers it is dropped.
+    pub fn publish(&self, update: BorCanonUpdate) {
+        let _ = self.sender.send(Arc::new(update));
+    }
+}
+
+/// Metrics of the canonical chain, from its Bor metadata.
+#[derive(Metrics)]
+#[metrics(scope = "bor.canon")]
+struct CanonMetrics {
+    /// Span of the canonical tip.
+    span_id: Gauge,
+    /// Last state sync ID committed on the canonical chain.
+    last_state_sync_id: Gauge,
+    /// State sync events committed by canonical blocks.
+    state_syncs: Counter,
+    /// Reorgs of the canonical chain.
+    reorgs: Counter,
+}
+
+/// Export metrics of the canonical chain from `updates` until the broadcast closes.
+pub async fn export_canon_metrics(mut updates: broadcast::Receiver<Arc<BorCanonUpdate>>) {
+    let metrics = CanonMetrics::default();
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(target: "bor::canon", skipped, "canonical metrics missed updates");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if update.reverted_from.is_some() {
+            metrics.reorgs.increment(1);
+        }
+        for block in &update.committed {
+            if let Some(ids) = &block.state_sync_ids {
+                metrics.state_syncs.increment(ids.end() - ids.start() + 1);
+                metrics.last_state_sync_id.set(*ids.end() as f64);
+            }
+        }
+        if let Some(tip) = update.tip() {
+            metrics.span_id.set(tip.span_id as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+    use bor_chainspec::MainnetBorHardforks;
+
+    fn event(id: u64) -> CommittedStateSync {
+        CommittedStateSync { id, contract: None, data: Bytes::new() }
+    }
+
+    #[test]
+    fn test_meta_state_sync_range() {
+        let spans = MainnetBorHardforks;
+        let hash = B256::with_last_byte(1);
+        let meta = BorBlockMeta::new(&spans, 6_656, hash, None, &[event(7), event(8), event(9)]);
+        assert_eq!(meta.state_sync_ids, Some(7..=9));
+        assert_eq!(meta.span_id, 2);
+
+        let meta = BorBlockMeta::new(&spans, 100, hash, Some(Address::with_last_byte(1)), &[]);
+        assert_eq!(meta.state_sync_ids, None);
+        assert_eq!(meta.span_id, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_updates() {
+        let notifications = BorCanonNotifications::new();
+        // Published without subscribers: dropped.
+        notifications.publish(BorCanonUpdate::default());
+
+        let mut updates = notifications.subscribe();
+        let spans = MainnetBorHardforks;
+        let block = BorBlockMeta::new(&spans, 16, B256::with_last_byte(16), None, &[event(1)]);
+        notifications.clone().publish(BorCanonUpdate {
+            reverted_from: Some(15),
+            committed: vec![block.clone()],
+        });
+
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.reverted_from, Some(15));
+        assert_eq!(update.tip(), Some(&block));
+    }
+}
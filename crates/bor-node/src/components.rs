@@ -0,0 +1,509 @@
+//! Reth node components of a Bor node.
+//!
+//! Each builder swaps one of Ethereum's components for Bor's, or tunes it: PoA consensus,
+//! the Bor EVM config, a pool reloading reorged accounts, and networking with Bor's eth/69
+//! handshake and imports of announced blocks.
+
+use crate::handshake::BorRlpxHandshake;
+use crate::import::BorBlockImport;
+use crate::tasks::reload_reorged_accounts;
+use crate::txpool::BorTxPoolConfig;
+use alloy_primitives::Address;
+use bor_chainspec::{BorConfig, BorHardforks};
+use bor_consensus::{
+    BorConsensus, ContractValidatorSource, MilestoneTracker, SharedDeferredChecks,
+    SharedLastValidatorMismatch, VerificationSourceSelector,
+};
+use bor_evm::{
+    BorEvmConfig, BorEvmFactory, BorPostExecution, BorSystemCaller, CachedSprintContext,
+    ExecutionDiffRecorder, HistoricalValidatorReader, PendingStateOverlay, SprintPresimulator,
+    StateSyncProfiler, SystemCallWarmer,
+};
+use bor_storage::{SharedBadBlockStore, SharedSprintWal, SharedStateSyncStore};
+use heimdall_client::{CommittedSpanSource, SharedHeimdallJournal, SharedSpanCache};
+use reth_chainspec::{EthChainSpec, EthereumHardforks, Hardforks};
+use reth_evm::{eth::spec::EthExecutorSpec, EthEvmFactory};
+use reth_ethereum_primitives::EthPrimitives;
+use reth_network::{
+    primitives::BasicNetworkPrimitives,
+    protocol::{IntoRlpxSubProtocol, RlpxSubProtocol},
+    NetworkHandle, NetworkManager, PeersInfo,
+};
+use reth_node_api::{PrimitivesTy, TxTy};
+use reth_node_builder::{
+    components::{ConsensusBuilder, ExecutorBuilder, NetworkBuilder, PoolBuilder},
+    node::{FullNodeTypes, NodeTypes},
+    BuilderContext,
+};
+use reth_node_ethereum::node::EthereumPoolBuilder;
+use reth_provider::{
+    BlockNumReader, CanonStateSubscriptions, HeaderProvider, StateProviderFactory,
+};
+use reth_transaction_pool::{PoolTransaction, TransactionPool, TransactionPoolExt};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Bor PoA consensus builder that replaces Ethereum's Beacon consensus.
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct BorConsensusBuilder {
+    /// Where rejected blocks are recorded for `debug_borBadBlocks`.
+    bad_blocks: Option<SharedBadBlockStore>,
+    /// Where the executor records the state sync events of blocks, for bad block records.
+    state_syncs: Option<SharedStateSyncStore>,
+    /// Span cache shared with the admin resync methods.
+    span_cache: Option<SharedSpanCache>,
+    /// Tip and selector of contract-state verification, if enabled.
+    contract_verification: Option<(Arc<MilestoneTracker>, VerificationSourceSelector)>,
+    /// Where spans missing at validation are recorded.
+    journal: Option<SharedHeimdallJournal>,
+    /// Where the last sprint-end validator mismatch is kept for
+    /// `debug_borLastValidatorMismatch`.
+    validator_mismatch: Option<SharedLastValidatorMismatch>,
+    /// Blocks held back under [`STRICT_CONSENSUS`](bor_consensus::STRICT_CONSENSUS), shared
+    /// with the executor.
+    deferred: Option<SharedDeferredChecks>,
+    /// Whether executed blocks have their receipts root and logs bloom checked.
+    assert_roots: bool,
+}
+
+impl BorConsensusBuilder {
+    /// Record rejected blocks in `store`.
+    pub fn with_bad_block_store(mut self, store: SharedBadBlockStore) -> Self {
+        self.bad_blocks = Some(store);
+        self
+    }
+
+    /// Read the state sync events of rejected executed blocks from `store`.
+    pub fn with_state_sync_store(mut self, store: SharedStateSyncStore) -> Self {
+        self.state_syncs = Some(store);
+        self
+    }
+
+    /// Validate signers against spans in `cache`.
+    pub fn with_span_cache(mut self, cache: SharedSpanCache) -> Self {
+        self.span_cache = Some(cache);
+        self
+    }
+
+    /// Record spans missing when blocks are validated in `journal`.
+    pub fn with_heimdall_journal(mut self, journal: SharedHeimdallJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Keep the last sprint-end validator mismatch in `last`.
+    pub fn with_last_validator_mismatch(mut self, last: SharedLastValidatorMismatch) -> Self {
+        self.validator_mismatch = Some(last);
+        self
+    }
+
+    /// Hold back blocks in `deferred`, which the executor fails with a retryable error.
+    pub fn with_deferred_checks(mut self, deferred: SharedDeferredChecks) -> Self {
+        self.deferred = Some(deferred);
+        self
+    }
+
+    /// Check the receipts root and logs bloom of executed blocks, for `--bor.assert-roots`.
+    pub fn with_root_assertion(mut self, enabled: bool) -> Self {
+        self.assert_roots = enabled;
+        self
+    }
+
+    /// Check blocks far behind the latest milestone in `milestones` against the
+    /// ValidatorSet contract instead of Heimdall spans.
+    pub fn with_contract_state_verification(
+        mut self,
+        milestones: Arc<MilestoneTracker>,
+        selector: VerificationSourceSelector,
+    ) -> Self {
+        self.contract_verification = Some((milestones, selector));
+        self
+    }
+}
+
+/// Reads a block's validators from the ValidatorSet contract at its parent's state.
+struct ContractValidators<P, C>(HistoricalValidatorReader<P, C>);
+
+impl<P, C> std::fmt::Debug for ContractValidators<P, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContractValidators").finish_non_exhaustive()
+    }
+}
+
+impl<P, C> ContractValidatorSource for ContractValidators<P, C>
+where
+    P: StateProviderFactory + HeaderProvider<Header = alloy_consensus::Header> + Send + Sync,
+    C: EthExecutorSpec
+        + EthChainSpec<Header = alloy_consensus::Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    fn signers(&self, number: u64) -> Option<Vec<Address>> {
+        let parent = number.checked_sub(1)?;
+        match self.0.validators_at(parent.into(), number) {
+            Ok(validators) => Some(validators.into_iter().map(|v| v.signer).collect()),
+            Err(err) => {
+                debug!(target: "boreth", number, %err, "validators not readable from contract");
+                None
+            }
+        }
+    }
+}
+
+/// Reads the producers of committed spans from the ValidatorSet contract at the tip.
+pub struct CommittedSpans<P, C>(HistoricalValidatorReader<P, C>);
+
+impl<P, C> CommittedSpans<P, C> {
+    /// Read the committed spans with `reader`.
+    pub fn new(reader: HistoricalValidatorReader<P, C>) -> Self {
+        Self(reader)
+    }
+}
+
+impl<P, C> std::fmt::Debug for CommittedSpans<P, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommittedSpans").finish_non_exhaustive()
+    }
+}
+
+impl<P, C> CommittedSpanSource for CommittedSpans<P, C>
+where
+    P: StateProviderFactory
+        + HeaderProvider<Header = alloy_consensus::Header>
+        + BlockNumReader
+        + Send
+        + Sync,
+    C: EthExecutorSpec
+        + EthChainSpec<Header = alloy_consensus::Header>
+        + EthereumHardforks
+        + BorHardforks
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    fn committed_producers(&self, number: u64) -> Option<Vec<Address>> {
+        let tip = self.0.provider().best_block_number().ok()?;
+        match self.0.validators_at(tip.into(), number) {
+            Ok(validators) => Some(validators.into_iter().map(|v| v.signer).collect()),
+            Err(err) => {
+                debug!(target: "boreth", number, %err, "committed span not readable");
+                None
+            }
+        }
+    }
+}
+
+impl<Node> ConsensusBuilder<Node> for BorConsensusBuilder
+where
+    Node: FullNodeTypes<
+        Types: NodeTypes<
+            ChainSpec: EthExecutorSpec
+                + EthChainSpec<Header = alloy_consensus::Header>
+                + EthereumHardforks
+                + BorHardforks
+                + Clone,
+            Primitives = EthPrimitives,
+        >,
+    >,
+{
+    type Consensus = Arc<BorConsensus<<Node::Types as NodeTypes>::ChainSpec>>;
+
+    async fn build_consensus(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Consensus> {
+        let mut consensus = BorConsensus::new(ctx.chain_spec());
+        if let Some(store) = self.bad_blocks {
+            consensus = consensus.with_bad_block_store(store);
+        }
+        if let Some(store) = self.state_syncs {
+            consensus = consensus.with_state_sync_store(store);
+        }
+        if let Some(cache) = self.span_cache {
+            consensus = consensus.with_span_cache(cache);
+        }
+        if let Some(journal) = self.journal {
+            consensus = consensus.with_heimdall_journal(journal);
+        }
+        if let Some(last) = self.validator_mismatch {
+            consensus = consensus.with_last_validator_mismatch(last);
+        }
+        if let Some(deferred) = self.deferred {
+            consensus = consensus.with_deferred_checks(deferred);
+        }
+        if self.assert_roots {
+            consensus = consensus.with_root_assertion();
+        }
+        if let Some((milestones, selector)) = self.contract_verification {
+            let reader = HistoricalValidatorReader::new(
+                ctx.provider().clone(),
+                BorEvmConfig::new(ctx.chain_spec()),
+            );
+            consensus = consensus.with_contract_state_verification(
+                Arc::new(ContractValidators(reader)),
+                milestones,
+                selector,
+            );
+        }
+        Ok(Arc::new(consensus))
+    }
+}
+
+/// Bor network builder with custom eth/69 handshake.
+///
+/// Go-Bor's eth/69 Status message includes a TD field that standard eth/69
+/// omits. This builder wires in [`BorRlpxHandshake`] to handle both formats.
+///
+/// Polygon-specific p2p extensions are RLPx sub-protocols registered with
+/// [`with_sub_protocol`](Self::with_sub_protocol); none are by default.
+#[derive(Default)]
+#[non_exhaustive]
+pub struct BorNetworkBuilder {
+    /// Sub-protocols offered to peers next to eth.
+    sub_protocols: Vec<RlpxSubProtocol>,
+    /// Where the blocks peers announce are handed to, if anywhere.
+    block_import: Option<BorBlockImport>,
+}
+
+impl BorNetworkBuilder {
+    /// Hand the blocks peers announce to `import` instead of dropping them.
+    pub fn with_block_import(mut self, import: BorBlockImport) -> Self {
+        self.block_import = Some(import);
+        self
+    }
+
+    /// Offer `protocol` to peers next to eth.
+    pub fn with_sub_protocol(mut self, protocol: impl IntoRlpxSubProtocol) -> Self {
+        self.sub_protocols.push(protocol.into_rlpx_sub_protocol());
+        self
+    }
+}
+
+impl std::fmt::Debug for BorNetworkBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BorNetworkBuilder")
+            .field("sub_protocols", &self.sub_protocols.len())
+            .field("block_import", &self.block_import)
+            .finish()
+    }
+}
+
+impl<Node, Pool> NetworkBuilder<Node, Pool> for BorNetworkBuilder
+where
+    Node: FullNodeTypes<Types: NodeTypes<ChainSpec: Hardforks, Primitives = EthPrimitives>>,
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TxTy<Node::Types>>>
+        + Unpin
+        + 'static,
+{
+    type Network = NetworkHandle<
+        BasicNetworkPrimitives<PrimitivesTy<Node::Types>, reth_transaction_pool::PoolPooledTx<Pool>>,
+    >;
+
+    async fn build_network(
+        self,
+        ctx: &BuilderContext<Node>,
+        pool: Pool,
+    ) -> eyre::Result<Self::Network> {
+        let mut network_config_builder = ctx
+            .network_config_builder()?
+            .eth_rlpx_handshake(Arc::new(BorRlpxHandshake::default()));
+        for protocol in self.sub_protocols {
+            network_config_builder = network_config_builder.add_rlpx_sub_protocol(protocol);
+        }
+        if let Some(import) = self.block_import {
+            network_config_builder = network_config_builder.block_import(Box::new(import));
+        }
+
+        let network_config = ctx.build_network_config(network_config_builder);
+        let network = NetworkManager::builder(network_config).await?;
+        let handle = ctx.start_network(network, pool);
+        info!(target: "boreth", enode=%handle.local_node_record(), "P2P networking initialized with Bor handshake");
+        Ok(handle)
+    }
+}
+
+/// Bor EVM executor builder that wires in the custom [`BorEvmConfig`].
+///
+/// EVMs are created by `EvmF`, Ethereum's factory unless another is given with
+/// [`with_evm_factory`](Self::with_evm_factory).
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct BorExecutorBuilder<EvmF = EthEvmFactory> {
+    /// Factory of the EVMs blocks are executed in.
+    evm_factory: EvmF,
+    /// Whether executors reuse sprint-start system calls simulated ahead of the block.
+    presimulate_sprint: bool,
+    /// Where executors mark sprint-start blocks, if anywhere.
+    sprint_wal: Option<SharedSprintWal>,
+    /// Where executors record the state sync events of blocks, if anywhere.
+    state_syncs: Option<SharedStateSyncStore>,
+    /// Gas above which executed state sync events are logged, if they are profiled.
+    profile_state_syncs: Option<u64>,
+    /// Where executors record the execution diff of blocks, if anywhere.
+    execution_diffs: Option<ExecutionDiffRecorder>,
+    /// Blocks consensus held back, which executors fail, if shared with it.
+    deferred_checks: Option<SharedDeferredChecks>,
+    /// Spans and pending state sync events the system calls of blocks are read from.
+    sprint_context: Option<(SharedSpanCache, PendingStateOverlay)>,
+    /// Gas limit built blocks move toward, the chain's block gas limit if unset.
+    gas_limit_target: Option<u64>,
+}
+
+impl BorExecutorBuilder {
+    /// A builder of Ethereum EVMs with nothing recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<EvmF> BorExecutorBuilder<EvmF> {
+    /// Create EVMs with `evm_factory`, e.g. one adding the precompiles of a private chain.
+    pub fn with_evm_factory<F: BorEvmFactory>(self, evm_factory: F) -> BorExecutorBuilder<F> {
+        BorExecutorBuilder {
+            evm_factory,
+            presimulate_sprint: self.presimulate_sprint,
+            sprint_wal: self.sprint_wal,
+            state_syncs: self.state_syncs,
+            profile_state_syncs: self.profile_state_syncs,
+            execution_diffs: self.execution_diffs,
+            deferred_checks: self.deferred_checks,
+            sprint_context: self.sprint_context,
+            gas_limit_target: self.gas_limit_target,
+        }
+    }
+
+    /// Let executors reuse sprint-start system calls simulated ahead of the block.
+    pub fn with_sprint_presimulation(mut self, enabled: bool) -> Self {
+        self.presimulate_sprint = enabled;
+        self
+    }
+
+    /// Let executors mark sprint-start blocks in `sprint_wal`.
+    pub fn with_sprint_wal(mut self, sprint_wal: SharedSprintWal) -> Self {
+        self.sprint_wal = Some(sprint_wal);
+        self
+    }
+
+    /// Let executors record the state sync events of blocks in `state_syncs`.
+    pub fn with_state_sync_store(mut self, state_syncs: SharedStateSyncStore) -> Self {
+        self.state_syncs = Some(state_syncs);
+        self
+    }
+
+    /// Profile each state sync event executed, logging those using more than
+    /// `gas_threshold` gas. `None` leaves them unprofiled.
+    pub fn with_state_sync_profiling(mut self, gas_threshold: Option<u64>) -> Self {
+        self.profile_state_syncs = gas_threshold;
+        self
+    }
+
+    /// Record the execution diff of each block in `execution_diffs`. `None` records none.
+    pub fn with_execution_diffs(mut self, execution_diffs: Option<ExecutionDiffRecorder>) -> Self {
+        self.execution_diffs = execution_diffs;
+        self
+    }
+
+    /// Fail the blocks consensus held back in `deferred_checks`.
+    pub fn with_deferred_checks(mut self, deferred_checks: SharedDeferredChecks) -> Self {
+        self.deferred_checks = Some(deferred_checks);
+        self
+    }
+
+    /// Read the span commits of blocks from `spans` and the state sync events of sprint
+    /// starts from `pending`.
+    pub fn with_sprint_context(
+        mut self,
+        spans: SharedSpanCache,
+        pending: PendingStateOverlay,
+    ) -> Self {
+        self.sprint_context = Some((spans, pending));
+        self
+    }
+
+    /// Move the gas limit of built blocks toward `target` (`--miner.gaslimit`) instead of
+    /// the chain's block gas limit.
+    pub fn with_gas_limit_target(mut self, target: Option<u64>) -> Self {
+        self.gas_limit_target = target;
+        self
+    }
+}
+
+impl<Types, Node, EvmF> ExecutorBuilder<Node> for BorExecutorBuilder<EvmF>
+where
+    Types: NodeTypes<
+        ChainSpec: EthExecutorSpec + EthChainSpec + EthereumHardforks + BorHardforks + Clone,
+        Primitives = EthPrimitives,
+    >,
+    Node: FullNodeTypes<Types = Types>,
+    EvmF: BorEvmFactory,
+{
+    type EVM = BorEvmConfig<Types::ChainSpec, EvmF>;
+
+    async fn build_evm(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::EVM> {
+        let chain_id = ctx.chain_spec().chain().id();
+        let bor_config = BorConfig::for_chain(chain_id, ctx.chain_spec().genesis())?;
+        let mut system_caller =
+            BorSystemCaller::from_config(&bor_config).with_warmer(SystemCallWarmer::new());
+        if let Some(gas_threshold) = self.profile_state_syncs {
+            system_caller = system_caller.with_profiler(StateSyncProfiler::new(gas_threshold));
+        }
+        let mut config = BorEvmConfig::new_with_custom_factory(ctx.chain_spec(), self.evm_factory)
+            .with_system_caller(system_caller)
+            .with_post_execution(BorPostExecution::from_config(&bor_config)?)
+            .with_gas_limit_target(self.gas_limit_target);
+        if let Some(sprint_wal) = self.sprint_wal {
+            config = config.with_sprint_wal(sprint_wal);
+        }
+        if let Some(state_syncs) = self.state_syncs {
+            config = config.with_state_sync_store(state_syncs);
+        }
+        if let Some(execution_diffs) = self.execution_diffs {
+            config = config.with_execution_diffs(execution_diffs);
+        }
+        if let Some(deferred_checks) = self.deferred_checks {
+            config = config.with_deferred_checks(deferred_checks);
+        }
+        if let Some((spans, pending)) = self.sprint_context {
+            let source = CachedSprintContext::new(ctx.chain_spec(), spans, pending);
+            config = config.with_sprint_context(Arc::new(source));
+        }
+        Ok(if self.presimulate_sprint {
+            config.with_presimulator(SprintPresimulator::new())
+        } else {
+            config
+        })
+    }
+}
+
+/// Ethereum's transaction pool, tuned for reorgs of 2 second blocks: the accounts changed
+/// by a reorg too deep for reth's pool maintenance are reloaded at once, see
+/// [`BorTxPoolConfig::reloads_after_reorg`].
+#[derive(Debug, Clone)]
+pub struct BorPoolBuilder {
+    /// Bor's pool settings.
+    pub config: BorTxPoolConfig,
+}
+
+impl<Node> PoolBuilder<Node> for BorPoolBuilder
+where
+    Node: FullNodeTypes<Types: NodeTypes<Primitives = EthPrimitives>>,
+    Node::Provider: StateProviderFactory,
+    EthereumPoolBuilder: PoolBuilder<Node>,
+    <EthereumPoolBuilder as PoolBuilder<Node>>::Pool: TransactionPoolExt + Clone + 'static,
+{
+    type Pool = <EthereumPoolBuilder as PoolBuilder<Node>>::Pool;
+
+    async fn build_pool(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Pool> {
+        let pool = EthereumPoolBuilder::default().build_pool(ctx).await?;
+        ctx.task_executor().spawn(reload_reorged_accounts(
+            pool.clone(),
+            ctx.provider().clone(),
+            ctx.provider().subscribe_to_canonical_state(),
+            self.config,
+        ));
+        Ok(pool)
+    }
+}
@@ -17,6 +17,20 @@ pub struct BorNodeConfig {
     pub rpc_port: u16,
     /// P2P listen port.
     pub p2p_port: u16,
+    /// Who drives forkchoice updates.
+    pub forkchoice: ForkchoiceMode,
+}
+
+/// Source of forkchoice (head/safe/finalized) updates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ForkchoiceMode {
+    /// The node drives forkchoice itself from the blocks peers announce and Heimdall
+    /// milestones.
+    #[default]
+    Internal,
+    /// An external client calls `engine_forkchoiceUpdated`, for hybrid setups pairing the
+    /// node with one.
+    External,
 }
 
 /// Which Bor network to connect to.
@@ -26,6 +40,30 @@ pub enum BorNetwork {
     Mainnet,
     /// Polygon Amoy testnet (chain ID 80002).
     Amoy,
+    /// Any other Bor chain, such as a devnet, by chain ID. Its chain spec comes from its
+    /// genesis; see [`BorNode::with_chain_spec`](crate::BorNode::with_chain_spec).
+    Custom(u64),
+}
+
+impl BorNetwork {
+    /// Returns the network for a chain ID, if it is a known Bor network.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            bor_chainspec::constants::MAINNET_CHAIN_ID => Some(Self::Mainnet),
+            bor_chainspec::constants::AMOY_CHAIN_ID => Some(Self::Amoy),
+            _ => None,
+        }
+    }
+
+    /// Returns the public Heimdall endpoint for this network, or a local Heimdall's for a
+    /// custom one.
+    pub fn default_heimdall_url(self) -> Url {
+        match self {
+            Self::Mainnet => BorNodeConfig::mainnet().heimdall_url,
+            Self::Amoy => BorNodeConfig::amoy().heimdall_url,
+            Self::Custom(chain_id) => BorNodeConfig::custom(chain_id).heimdall_url,
+        }
+    }
 }
 
 impl BorNodeConfig {
@@ -38,6 +76,7 @@ impl BorNodeConfig {
             rpc_addr: "127.0.0.1".to_string(),
             rpc_port: 8545,
             p2p_port: 30303,
+            forkchoice: ForkchoiceMode::Internal,
         }
     }
 
@@ -50,6 +89,30 @@ impl BorNodeConfig {
             rpc_addr: "127.0.0.1".to_string(),
             rpc_port: 8545,
             p2p_port: 30303,
+            forkchoice: ForkchoiceMode::Internal,
+        }
+    }
+
+    /// Create a default config for the custom network `chain_id`, with Heimdall on
+    /// localhost.
+    pub fn custom(chain_id: u64) -> Self {
+        Self {
+            network: BorNetwork::Custom(chain_id),
+            heimdall_url: Url::parse("http://localhost:1317").unwrap(),
+            data_dir: format!("~/.boreth-{chain_id}"),
+            rpc_addr: "127.0.0.1".to_string(),
+            rpc_port: 8545,
+            p2p_port: 30303,
+            forkchoice: ForkchoiceMode::Internal,
+        }
+    }
+
+    /// Create the default config for the network of `chain_id`.
+    pub fn for_chain(chain_id: u64) -> Self {
+        match BorNetwork::from_chain_id(chain_id) {
+            Some(BorNetwork::Mainnet) => Self::mainnet(),
+            Some(BorNetwork::Amoy) => Self::amoy(),
+            _ => Self::custom(chain_id),
         }
     }
 
@@ -58,6 +121,7 @@ impl BorNodeConfig {
         match self.network {
             BorNetwork::Mainnet => bor_chainspec::constants::MAINNET_CHAIN_ID,
             BorNetwork::Amoy => bor_chainspec::constants::AMOY_CHAIN_ID,
+            BorNetwork::Custom(chain_id) => chain_id,
         }
     }
 }
@@ -79,4 +143,29 @@ mod tests {
         assert_eq!(config.chain_id(), 80002);
         assert_eq!(config.network, BorNetwork::Amoy);
     }
+
+    #[test]
+    fn test_custom_config() {
+        let config = BorNodeConfig::custom(1337);
+        assert_eq!(config.chain_id(), 1337);
+        assert_eq!(config.data_dir, "~/.boreth-1337");
+        assert_eq!(BorNetwork::from_chain_id(1337), None);
+        assert_eq!(BorNetwork::Custom(1337).default_heimdall_url(), config.heimdall_url);
+    }
+
+    #[test]
+    fn test_forkchoice_defaults_to_internal() {
+        assert_eq!(BorNodeConfig::mainnet().forkchoice, ForkchoiceMode::Internal);
+        assert_eq!(BorNodeConfig::amoy().forkchoice, ForkchoiceMode::Internal);
+        assert_eq!(ForkchoiceMode::default(), ForkchoiceMode::Internal);
+    }
+
+    #[test]
+    fn test_network_from_chain_id() {
+        assert_eq!(BorNetwork::from_chain_id(137), Some(BorNetwork::Mainnet));
+        assert_eq!(BorNetwork::from_chain_id(80002), Some(BorNetwork::Amoy));
+        assert_eq!(BorNetwork::from_chain_id(1), None);
+        assert_eq!(BorNodeConfig::for_chain(80002).network, BorNetwork::Amoy);
+        assert_eq!(BorNodeConfig::for_chain(1).network, BorNetwork::Custom(1));
+    }
 }
@@ -0,0 +1,173 @@
+//! Bor settings read from a TOML file (`--bor.config`).
+//!
+//! Keys are the names of the command line flags, split on dots into tables:
+//!
+//! ```toml
+//! [bor]
+//! heimdall = "http://localhost:1317"
+//! heimdall-retries = 5
+//! signer = "0x00000000000000000000000000000000000000aa"
+//!
+//! [bor.txpool]
+//! pending = 65536
+//!
+//! [miner]
+//! gaslimit = 45000000
+//! ```
+//!
+//! A flag given on the command line wins over the file; see
+//! [`BorArgs::merge_config_file`](crate::BorArgs::merge_config_file). Errors name the
+//! offending key.
+
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Error reading a Bor configuration file.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigFileError {
+    /// The file could not be read.
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        /// Path of the file.
+        path: PathBuf,
+        /// Why reading failed.
+        #[source]
+        source: std::io::Error,
+    },
+    /// The file is not valid TOML.
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    /// A key names no Bor setting.
+    #[error("unknown key `{0}`")]
+    UnknownKey(String),
+    /// A key has a value of the wrong type or out of range.
+    #[error("`{key}`: {reason}")]
+    Invalid {
+        /// The offending key.
+        key: String,
+        /// What is wrong with its value.
+        reason: String,
+    },
+    /// A key names a bor-geth setting boreth does not implement.
+    #[error("`{0}` is not supported by boreth")]
+    Unsupported(String),
+}
+
+/// The settings of a Bor configuration file, by dotted key.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorConfigFile {
+    entries: Vec<(String, toml::Value)>,
+}
+
+impl BorConfigFile {
+    /// Read the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, ConfigFileError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|source| ConfigFileError::Read { path: path.to_path_buf(), source })?;
+        content.parse()
+    }
+
+    /// The settings of the file, sorted by key.
+    pub fn entries(&self) -> impl Iterator<Item = ConfigValue<'_>> {
+        self.entries.iter().map(|(key, value)| ConfigValue { key, value })
+    }
+}
+
+impl FromStr for BorConfigFile {
+    type Err = ConfigFileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table: toml::Table = s.parse()?;
+        let mut entries = Vec::new();
+        flatten(String::new(), table, &mut entries);
+        Ok(Self { entries })
+    }
+}
+
+/// Collect the leaves of `table` under their dotted keys.
+fn flatten(prefix: String, table: toml::Table, entries: &mut Vec<(String, toml::Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() { key } else { format!("{prefix}.{key}") };
+        match value {
+            toml::Value::Table(table) => flatten(key, table, entries),
+            value => entries.push((key, value)),
+        }
+    }
+}
+
+/// A setting of a [`BorConfigFile`].
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigValue<'a> {
+    /// Dotted key of the setting, e.g. `bor.heimdall-retries`.
+    pub key: &'a str,
+    /// Its value.
+    pub value: &'a toml::Value,
+}
+
+impl ConfigValue<'_> {
+    /// The value as a `T`.
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T, ConfigFileError> {
+        self.value.clone().try_into().map_err(|e: toml::de::Error| self.invalid(e.message()))
+    }
+
+    /// The value as a string parsed into a `T`.
+    pub fn parse<T>(&self) -> Result<T, ConfigFileError>
+    where
+        T: FromStr<Err: Display>,
+    {
+        self.get::<String>()?.parse().map_err(|e: T::Err| self.invalid(e))
+    }
+
+    /// The value as an array of strings, each parsed with `parse`.
+    pub fn parse_each<T, E: Display>(
+        &self,
+        parse: impl Fn(&str) -> Result<T, E>,
+    ) -> Result<Vec<T>, ConfigFileError> {
+        self.get::<Vec<String>>()?
+            .iter()
+            .map(|s| parse(s).map_err(|e| self.invalid(e)))
+            .collect()
+    }
+
+    /// An error naming this setting.
+    pub fn invalid(&self, reason: impl Display) -> ConfigFileError {
+        ConfigFileError::Invalid { key: self.key.to_string(), reason: reason.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_flattened() {
+        let file: BorConfigFile = r#"
+            [bor]
+            heimdall = "http://localhost:1317"
+            heimdall-header = ["X-Region: eu"]
+
+            [bor.txpool]
+            pending = 10
+        "#
+        .parse()
+        .unwrap();
+        let keys: Vec<_> = file.entries().map(|entry| entry.key.to_string()).collect();
+        assert_eq!(keys, ["bor.heimdall", "bor.heimdall-header", "bor.txpool.pending"]);
+    }
+
+    #[test]
+    fn test_errors_name_the_key() {
+        let file: BorConfigFile = "bor.heimdall-retries = -1\nbor.signer = 7".parse().unwrap();
+        let mut entries = file.entries();
+        let retries = entries.next().unwrap();
+        let err = retries.get::<u32>().unwrap_err();
+        assert!(err.to_string().starts_with("`bor.heimdall-retries`: "), "{err}");
+        let signer = entries.next().unwrap();
+        let err = signer.parse::<alloy_primitives::Address>().unwrap_err();
+        assert!(err.to_string().starts_with("`bor.signer`: "), "{err}");
+
+        assert!(matches!("[bor".parse::<BorConfigFile>(), Err(ConfigFileError::Toml(_))));
+    }
+}
@@ -0,0 +1,105 @@
+//! The Bor state a node keeps in its data directory.
+//!
+//! [`BorDataDir::open`] loads everything the node persists next to reth's database: the
+//! Heimdall cache and journal, the sprint WAL, the indexes of state syncs and total
+//! difficulty, and the bad block and snapshot stores.
+
+use bor_consensus::{MilestoneTracker, SPAN_CACHE_SIZE};
+use bor_storage::{
+    FileBadBlockStore, FileSnapshotStore, FileStateSyncStore, SharedBadBlockStore,
+    SharedSnapshotStore, SharedSprintWal, SharedStateSyncStore, SharedTotalDifficultyIndex,
+    SprintWal, TotalDifficultyIndex, BAD_BLOCKS_FILE, DEFAULT_TD_CHECKPOINT_INTERVAL,
+    MAX_BAD_BLOCKS, MAX_SNAPSHOTS, MAX_STATE_SYNC_BLOCKS, SNAPSHOTS_FILE, STATE_SYNCS_FILE,
+    TD_INDEX_FILE,
+};
+use heimdall_client::{
+    HeimdallCacheFile, HeimdallCacheSnapshot, HeimdallJournal, SharedHeimdallJournal,
+    SharedSpanCache, SpanCache, HEIMDALL_CACHE_FILE,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::info;
+
+/// Journal of the spans missed while Heimdall was unreachable, relative to the data
+/// directory.
+const HEIMDALL_JOURNAL_FILE: &str = "bor-heimdall-journal.json";
+
+/// Sprint WAL, relative to the data directory.
+const SPRINT_WAL_FILE: &str = "bor-sprint-wal.json";
+
+/// The Bor stores of a data directory, opened.
+#[derive(Debug)]
+pub struct BorDataDir {
+    /// Spans cached from Heimdall, loaded from the Heimdall cache.
+    pub span_cache: SharedSpanCache,
+    /// The Heimdall cache the spans and milestones are persisted to.
+    pub heimdall_cache: HeimdallCacheFile,
+    /// Milestones and checkpoints, starting from those of the Heimdall cache.
+    pub tracker: Arc<MilestoneTracker>,
+    /// Spans missed while Heimdall was unreachable.
+    pub journal: SharedHeimdallJournal,
+    /// Sprint starts whose system calls may not have completed.
+    pub sprint_wal: SharedSprintWal,
+    /// The index of canonical state syncs.
+    pub state_syncs: SharedStateSyncStore,
+    /// Total difficulty checkpoints of canonical blocks.
+    pub total_difficulty: SharedTotalDifficultyIndex,
+    /// Blocks rejected by Bor consensus.
+    pub bad_blocks: SharedBadBlockStore,
+    /// Snapshots stored by block hash.
+    pub snapshots: SharedSnapshotStore,
+}
+
+impl BorDataDir {
+    /// Open the Bor stores of `data_dir`, emptying the Heimdall cache first if
+    /// `clear_heimdall_cache` is set.
+    pub fn open(data_dir: &Path, clear_heimdall_cache: bool) -> eyre::Result<Self> {
+        let span_cache: SharedSpanCache = Arc::new(Mutex::new(SpanCache::new(SPAN_CACHE_SIZE)));
+        let heimdall_cache = HeimdallCacheFile::new(data_dir.join(HEIMDALL_CACHE_FILE));
+        let cached = if clear_heimdall_cache {
+            heimdall_cache.clear()?;
+            let path = heimdall_cache.path().display();
+            info!(target: "boreth", %path, "cleared Heimdall cache");
+            HeimdallCacheSnapshot::default()
+        } else {
+            heimdall_cache.load().unwrap_or_default()
+        };
+        if !cached.spans.is_empty() {
+            info!(target: "boreth", spans = cached.spans.len(), "loaded cached Heimdall spans");
+        }
+        let mut spans = span_cache.lock().expect("span cache lock poisoned");
+        for span in cached.spans {
+            spans.insert(span);
+        }
+        drop(spans);
+        let tracker = Arc::new(MilestoneTracker::new());
+        if let Some(milestone) = cached.milestone {
+            tracker.update(milestone);
+        }
+        if let Some(checkpoint) = cached.checkpoint {
+            tracker.update_checkpoint(checkpoint);
+        }
+
+        let journal = HeimdallJournal::open(data_dir.join(HEIMDALL_JOURNAL_FILE))?;
+        let sprint_wal = SprintWal::open(data_dir.join(SPRINT_WAL_FILE))?;
+        let state_syncs =
+            FileStateSyncStore::open(data_dir.join(STATE_SYNCS_FILE), MAX_STATE_SYNC_BLOCKS)?;
+        let td_index = TotalDifficultyIndex::load(
+            &data_dir.join(TD_INDEX_FILE),
+            DEFAULT_TD_CHECKPOINT_INTERVAL,
+        );
+        let bad_blocks = FileBadBlockStore::open(data_dir.join(BAD_BLOCKS_FILE), MAX_BAD_BLOCKS)?;
+        let snapshots = FileSnapshotStore::open(data_dir.join(SNAPSHOTS_FILE), MAX_SNAPSHOTS)?;
+        Ok(Self {
+            span_cache,
+            heimdall_cache,
+            tracker,
+            journal: Arc::new(journal),
+            sprint_wal: Arc::new(sprint_wal),
+            state_syncs: Arc::new(RwLock::new(state_syncs)),
+            total_difficulty: Arc::new(RwLock::new(td_index.unwrap_or_default())),
+            bad_blocks: Arc::new(RwLock::new(bad_blocks)),
+            snapshots: Arc::new(RwLock::new(snapshots)),
+        })
+    }
+}
@@ -0,0 +1,153 @@
+//! Errors of the node as a whole.
+//!
+//! Each crate reports failures in its own error type. [`BorError`] gathers them so the
+//! binary can propagate any of them with `?`, and gives each a JSON-RPC error code through
+//! [`RpcErrorCode`], so that the same failure reads the same whether it ends an RPC call or
+//! the node.
+
+use bor_chainspec::ScheduleError;
+use bor_consensus::{
+    DoubleSignError, ExtraDataError, ProposerError, SealError, SnapshotRebuildError,
+    ValidationError,
+};
+use bor_evm::ValidatorContractError;
+use bor_rpc::{BorRpcError, RpcErrorCode};
+use heimdall_client::{HeimdallError, JournalError};
+use reth_evm::block::BlockExecutionError;
+use reth_storage_errors::provider::ProviderError;
+
+/// JSON-RPC code of a request with invalid parameters.
+pub const INVALID_PARAMS_CODE: i32 = -32602;
+
+/// JSON-RPC code of an internal error.
+pub const INTERNAL_ERROR_CODE: i32 = -32603;
+
+/// EIP-1474 code of input that is well-formed but invalid, such as a badly sealed block.
+pub const INVALID_INPUT_CODE: i32 = -32000;
+
+/// EIP-1474 code of a block, milestone or other resource that does not exist.
+pub const RESOURCE_NOT_FOUND_CODE: i32 = -32001;
+
+/// EIP-1474 code of a resource that exists but cannot be reached, such as Heimdall.
+pub const RESOURCE_UNAVAILABLE_CODE: i32 = -32002;
+
+/// EIP-1474 code of a request refused because of a rate limit.
+pub const LIMIT_EXCEEDED_CODE: i32 = -32005;
+
+/// Any error of a Bor node.
+#[derive(Debug, thiserror::Error)]
+pub enum BorError {
+    /// Heimdall could not be reached or answered badly.
+    #[error("heimdall: {0}")]
+    Heimdall(#[from] HeimdallError),
+    /// The Heimdall journal could not be read or written.
+    #[error(transparent)]
+    HeimdallJournal(#[from] JournalError),
+    /// A header broke a consensus rule.
+    #[error("consensus: {0}")]
+    Validation(#[from] ValidationError),
+    /// A block's seal could not be recovered.
+    #[error("consensus: {0}")]
+    Seal(#[from] SealError),
+    /// A header's extra data is malformed.
+    #[error("consensus: {0}")]
+    ExtraData(#[from] ExtraDataError),
+    /// The proposer could not be determined.
+    #[error("consensus: {0}")]
+    Proposer(#[from] ProposerError),
+    /// Signing was refused to avoid a double sign.
+    #[error("consensus: {0}")]
+    DoubleSign(#[from] DoubleSignError),
+    /// A validator snapshot could not be rebuilt.
+    #[error("snapshot: {0}")]
+    Snapshot(#[from] SnapshotRebuildError),
+    /// A block failed to execute.
+    #[error("execution: {0}")]
+    Execution(#[from] BlockExecutionError),
+    /// The ValidatorSet contract could not be read.
+    #[error("execution: {0}")]
+    ValidatorContract(#[from] ValidatorContractError),
+    /// The database failed.
+    #[error("storage: {0}")]
+    Storage(#[from] ProviderError),
+    /// A block-keyed chain parameter is invalid.
+    #[error("config: {0}")]
+    Schedule(#[from] ScheduleError),
+    /// The node configuration is invalid.
+    #[error("config: {0}")]
+    Config(String),
+    /// An RPC response could not be serialized.
+    #[error("serialization: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A Bor RPC method failed.
+    #[error(transparent)]
+    Rpc(#[from] BorRpcError),
+}
+
+impl RpcErrorCode for BorError {
+    fn code(&self) -> i32 {
+        match self {
+            Self::Heimdall(HeimdallError::NotFound) => RESOURCE_NOT_FOUND_CODE,
+            Self::Heimdall(HeimdallError::RateLimited) => LIMIT_EXCEEDED_CODE,
+            Self::Heimdall(HeimdallError::InvalidConfig(_)) => INVALID_PARAMS_CODE,
+            Self::Heimdall(_) => RESOURCE_UNAVAILABLE_CODE,
+            Self::Validation(_)
+            | Self::Seal(_)
+            | Self::ExtraData(_)
+            | Self::Proposer(_)
+            | Self::DoubleSign(_) => INVALID_INPUT_CODE,
+            Self::HeimdallJournal(_)
+            | Self::Snapshot(_)
+            | Self::Execution(_)
+            | Self::ValidatorContract(_)
+            | Self::Storage(_)
+            | Self::Json(_) => INTERNAL_ERROR_CODE,
+            Self::Schedule(_) | Self::Config(_) => INVALID_PARAMS_CODE,
+            Self::Rpc(err) => match err {
+                BorRpcError::BlockNotFound(_) | BorRpcError::MilestoneNotFound(_) => {
+                    RESOURCE_NOT_FOUND_CODE
+                }
+                BorRpcError::SealError(_)
+                | BorRpcError::ExtraDataError(_)
+                | BorRpcError::VoteRefused(_) => INVALID_INPUT_CODE,
+                BorRpcError::InvalidBlockRange { .. } | BorRpcError::InvalidParams(_) => {
+                    INVALID_PARAMS_CODE
+                }
+                BorRpcError::Heimdall(_) => RESOURCE_UNAVAILABLE_CODE,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(BorError::from(HeimdallError::Timeout).code(), RESOURCE_UNAVAILABLE_CODE);
+        assert_eq!(BorError::from(HeimdallError::NotFound).code(), RESOURCE_NOT_FOUND_CODE);
+        assert_eq!(BorError::from(HeimdallError::RateLimited).code(), LIMIT_EXCEEDED_CODE);
+        assert_eq!(BorError::from(BorRpcError::BlockNotFound(7)).code(), RESOURCE_NOT_FOUND_CODE);
+        assert_eq!(
+            BorError::from(BorRpcError::InvalidBlockRange { start: 2, end: 1 }).code(),
+            INVALID_PARAMS_CODE
+        );
+        assert_eq!(
+            BorError::from(ScheduleError::Zero { name: "sprint", block: 0 }).code(),
+            INVALID_PARAMS_CODE
+        );
+        let storage = ProviderError::HeaderNotFound(7u64.into());
+        assert_eq!(BorError::from(storage).code(), INTERNAL_ERROR_CODE);
+    }
+
+    #[test]
+    fn test_messages_name_the_subsystem() {
+        assert_eq!(BorError::from(HeimdallError::Timeout).to_string(), "heimdall: request timeout");
+        assert_eq!(
+            BorError::from(ScheduleError::Zero { name: "sprint", block: 64 }).to_string(),
+            "config: sprint is zero from block 64"
+        );
+        assert_eq!(BorError::from(BorRpcError::BlockNotFound(7)).to_string(), "block not found: 7");
+    }
+}
@@ -0,0 +1,380 @@
+//! Internal forkchoice driver.
+//!
+//! Bor has no consensus-layer client calling `engine_forkchoiceUpdated`. The node takes
+//! `head` from the blocks its peers announce, recorded in [`NetworkHead`], so the engine
+//! downloads the chain up to it, and derives `safe` / `finalized` from Heimdall checkpoints
+//! and milestones tracked by [`MilestoneTracker`].
+
+use crate::canon::BorCanonUpdate;
+use alloy_primitives::{B256, U256};
+use alloy_rpc_types_engine::ForkchoiceState;
+use bor_consensus::MilestoneTracker;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Default interval between forkchoice evaluations (one Bor block period).
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long an announced head is kept without a heavier block announced after it: ten
+/// Bor block periods.
+pub const NETWORK_HEAD_TTL: Duration = Duration::from_secs(20);
+
+/// Source of the node's current best chain.
+pub trait HeadSource: Send + Sync {
+    /// Returns the number and hash of the current best block.
+    fn best_block(&self) -> Option<(u64, B256)>;
+
+    /// Returns the canonical block hash at `number`, if known.
+    fn canonical_hash(&self, number: u64) -> Option<B256>;
+}
+
+/// The heaviest block peers have announced, shared between the network and the
+/// [`HeadSource`] of the driver.
+///
+/// The head is forgotten when the local chain reorgs, see [`follow_reorgs`](Self::follow_reorgs),
+/// and once no heavier block has been announced for [`NETWORK_HEAD_TTL`], so that a head
+/// the network abandoned, or a total difficulty a peer inflated, does not pin it.
+#[derive(Debug, Clone)]
+pub struct NetworkHead {
+    head: Arc<Mutex<Option<AnnouncedHead>>>,
+    ttl: Duration,
+}
+
+/// A block announced by a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AnnouncedHead {
+    number: u64,
+    hash: B256,
+    total_difficulty: U256,
+    observed_at: Instant,
+}
+
+impl Default for NetworkHead {
+    fn default() -> Self {
+        Self { head: Arc::default(), ttl: NETWORK_HEAD_TTL }
+    }
+}
+
+impl NetworkHead {
+    /// Override how long a head is kept without a heavier block announced.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Record block `number` with `hash` announced at `total_difficulty`, if it is heavier
+    /// than the head recorded so far or that head expired. Returns whether it became the
+    /// head.
+    pub fn observe(&self, number: u64, hash: B256, total_difficulty: U256) -> bool {
+        let mut head = self.head.lock().expect("network head lock poisoned");
+        let now = Instant::now();
+        if head.is_some_and(|head| {
+            head.total_difficulty >= total_difficulty && now - head.observed_at < self.ttl
+        }) {
+            return false;
+        }
+        *head = Some(AnnouncedHead { number, hash, total_difficulty, observed_at: now });
+        true
+    }
+
+    /// Number and hash of the heaviest block announced, unless it expired.
+    pub fn best_block(&self) -> Option<(u64, B256)> {
+        let head = *self.head.lock().expect("network head lock poisoned");
+        head.filter(|head| head.observed_at.elapsed() < self.ttl)
+            .map(|head| (head.number, head.hash))
+    }
+
+    /// Forget the announced head.
+    pub fn clear(&self) {
+        self.head.lock().expect("network head lock poisoned").take();
+    }
+
+    /// Forget the announced head whenever `updates` reports a reorg, until the broadcast
+    /// closes. The blocks announced after it weigh the new chain from scratch.
+    pub async fn follow_reorgs(self, mut updates: broadcast::Receiver<Arc<BorCanonUpdate>>) {
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    let Some(reverted_from) = update.reverted_from else { continue };
+                    debug!(target: "bor::forkchoice", reverted_from, "reorg, network head cleared");
+                    self.clear();
+                }
+                // A missed update may have been a reorg.
+                Err(RecvError::Lagged(_)) => self.clear(),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Receiver of forkchoice updates, typically the engine handle of the blockchain tree.
+pub trait ForkchoiceSink: Send + Sync {
+    /// Apply a new forkchoice state.
+    fn update_forkchoice(
+        &self,
+        state: ForkchoiceState,
+    ) -> impl Future<Output = eyre::Result<()>> + Send;
+}
+
+/// Drives head/safe/finalized updates from the local chain and Heimdall finality data.
+pub struct ForkchoiceDriver<H, S> {
+    head: H,
+    sink: S,
+    tracker: Arc<MilestoneTracker>,
+    /// The last state successfully applied to the sink.
+    last_sent: Option<ForkchoiceState>,
+    poll_interval: Duration,
+}
+
+impl<H: HeadSource, S: ForkchoiceSink> ForkchoiceDriver<H, S> {
+    /// Create a new driver.
+    pub fn new(head: H, sink: S, tracker: Arc<MilestoneTracker>) -> Self {
+        Self {
+            head,
+            sink,
+            tracker,
+            last_sent: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Override the poll interval.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the last state applied to the sink.
+    pub fn last_sent(&self) -> Option<ForkchoiceState> {
+        self.last_sent
+    }
+
+    /// Compute the forkchoice state for the current chain and finality data.
+    ///
+    /// `finalized` is only advanced to a milestone whose hash matches the local canonical
+    /// chain; a mismatch keeps the previous value. Returns `None` if there is no head yet.
+    pub fn compute_state(&self) -> Option<ForkchoiceState> {
+        let (head_number, head_block_hash) = self.head.best_block()?;
+
+        let finalized = self
+            .tracker
+            .latest()
+            .filter(|milestone| milestone.end_block <= head_number)
+            .and_then(|milestone| match self.head.canonical_hash(milestone.end_block) {
+                Some(local) if local == milestone.hash => Some(local),
+                Some(local) => {
+                    warn!(
+                        target: "bor::forkchoice",
+                        milestone_id = %milestone.milestone_id,
+                        end_block = milestone.end_block,
+                        milestone_hash = %milestone.hash,
+                        local_hash = %local,
+                        "local chain disagrees with milestone"
+                    );
+                    None
+                }
+                None => None,
+            });
+
+        let safe = self
+            .tracker
+            .safe_tag_block()
+            .filter(|number| *number <= head_number)
+            .and_then(|number| self.head.canonical_hash(number));
+
+        let previous = self.last_sent.unwrap_or_default();
+        Some(ForkchoiceState {
+            head_block_hash,
+            safe_block_hash: safe.unwrap_or(previous.safe_block_hash),
+            finalized_block_hash: finalized.unwrap_or(previous.finalized_block_hash),
+        })
+    }
+
+    /// Evaluate the forkchoice once and send it if it changed.
+    ///
+    /// Returns `true` if an update was sent.
+    pub async fn step(&mut self) -> eyre::Result<bool> {
+        let Some(state) = self.compute_state() else { return Ok(false) };
+        if self.last_sent == Some(state) {
+            return Ok(false);
+        }
+
+        self.sink.update_forkchoice(state).await?;
+        debug!(
+            target: "bor::forkchoice",
+            head = %state.head_block_hash,
+            safe = %state.safe_block_hash,
+            finalized = %state.finalized_block_hash,
+            "forkchoice updated"
+        );
+        self.last_sent = Some(state);
+        Ok(true)
+    }
+
+    /// Run the driver as a background loop.
+    pub async fn run(mut self) {
+        info!(target: "bor::forkchoice", interval = ?self.poll_interval, "forkchoice driver started");
+
+        loop {
+            if let Err(e) = self.step().await {
+                warn!(target: "bor::forkchoice", error = %e, "failed to apply forkchoice update");
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+    use heimdall_client::{Checkpoint, Milestone};
+    use std::collections::BTreeMap;
+
+    struct TestChain(BTreeMap<u64, B256>);
+
+    impl TestChain {
+        fn with_blocks(n: u64) -> Self {
+            Self((0..=n).map(|i| (i, B256::with_last_byte(i as u8))).collect())
+        }
+    }
+
+    impl HeadSource for TestChain {
+        fn best_block(&self) -> Option<(u64, B256)> {
+            self.0.last_key_value().map(|(n, h)| (*n, *h))
+        }
+
+        fn canonical_hash(&self, number: u64) -> Option<B256> {
+            self.0.get(&number).copied()
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<ForkchoiceState>>);
+
+    impl ForkchoiceSink for &RecordingSink {
+        async fn update_forkchoice(&self, state: ForkchoiceState) -> eyre::Result<()> {
+            self.0.lock().unwrap().push(state);
+            Ok(())
+        }
+    }
+
+    fn milestone(end_block: u64, hash: B256) -> Milestone {
+        Milestone {
+            milestone_id: format!("ms-{end_block}"),
+            start_block: 0,
+            end_block,
+            hash,
+            proposer: Address::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_network_head_is_the_heaviest_announced() {
+        let head = NetworkHead::default();
+        assert_eq!(head.best_block(), None);
+        assert!(head.observe(10, B256::with_last_byte(10), U256::from(100)));
+        // A longer chain that is lighter does not win.
+        assert!(!head.observe(11, B256::with_last_byte(11), U256::from(90)));
+        assert!(!head.observe(10, B256::with_last_byte(12), U256::from(100)));
+        assert_eq!(head.best_block(), Some((10, B256::with_last_byte(10))));
+        assert!(head.observe(11, B256::with_last_byte(13), U256::from(110)));
+        assert_eq!(head.clone().best_block(), Some((11, B256::with_last_byte(13))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_network_head_expires() {
+        let head = NetworkHead::default().with_ttl(Duration::from_secs(20));
+        assert!(head.observe(10, B256::with_last_byte(10), U256::from(100)));
+        tokio::time::advance(Duration::from_secs(19)).await;
+        assert!(!head.observe(11, B256::with_last_byte(11), U256::from(90)));
+        assert_eq!(head.best_block(), Some((10, B256::with_last_byte(10))));
+
+        // Not superseded in time: dropped, and a lighter block can take over.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(head.best_block(), None);
+        assert!(head.observe(11, B256::with_last_byte(11), U256::from(90)));
+        assert_eq!(head.best_block(), Some((11, B256::with_last_byte(11))));
+    }
+
+    #[tokio::test]
+    async fn test_network_head_cleared_on_reorg() {
+        let head = NetworkHead::default();
+        let notifications = crate::BorCanonNotifications::new();
+        let follower = tokio::spawn(head.clone().follow_reorgs(notifications.subscribe()));
+        assert!(head.observe(10, B256::with_last_byte(10), U256::from(100)));
+
+        notifications.publish(BorCanonUpdate::default());
+        tokio::task::yield_now().await;
+        assert_eq!(head.best_block(), Some((10, B256::with_last_byte(10))));
+
+        notifications.publish(BorCanonUpdate { reverted_from: Some(8), committed: vec![] });
+        drop(notifications);
+        follower.await.unwrap();
+        assert_eq!(head.best_block(), None);
+        // The new chain starts from scratch, whatever it weighs.
+        assert!(head.observe(9, B256::with_last_byte(9), U256::from(50)));
+    }
+
+    #[tokio::test]
+    async fn test_head_only_without_finality_data() {
+        let sink = RecordingSink::default();
+        let mut driver =
+            ForkchoiceDriver::new(TestChain::with_blocks(10), &sink, Arc::default());
+
+        assert!(driver.step().await.unwrap());
+        let state = sink.0.lock().unwrap()[0];
+        assert_eq!(state.head_block_hash, B256::with_last_byte(10));
+        assert_eq!(state.finalized_block_hash, B256::ZERO);
+        assert_eq!(state.safe_block_hash, B256::ZERO);
+
+        // Unchanged state is not resent.
+        assert!(!driver.step().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_milestone_and_checkpoint_set_finalized_and_safe() {
+        let tracker = Arc::new(MilestoneTracker::new());
+        tracker.update(milestone(8, B256::with_last_byte(8)));
+        tracker.update_checkpoint(Checkpoint {
+            start_block: 0,
+            end_block: 5,
+            root_hash: B256::ZERO,
+            proposer: Address::ZERO,
+        });
+
+        let sink = RecordingSink::default();
+        let mut driver = ForkchoiceDriver::new(TestChain::with_blocks(10), &sink, tracker);
+        driver.step().await.unwrap();
+
+        let state = driver.last_sent().unwrap();
+        assert_eq!(state.finalized_block_hash, B256::with_last_byte(8));
+        assert_eq!(state.safe_block_hash, B256::with_last_byte(5));
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_milestone_is_not_finalized() {
+        let tracker = Arc::new(MilestoneTracker::new());
+        tracker.update(milestone(8, B256::repeat_byte(0xff)));
+
+        let sink = RecordingSink::default();
+        let driver = ForkchoiceDriver::new(TestChain::with_blocks(10), &sink, tracker);
+
+        assert_eq!(driver.compute_state().unwrap().finalized_block_hash, B256::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_milestone_ahead_of_head_is_deferred() {
+        let tracker = Arc::new(MilestoneTracker::new());
+        tracker.update(milestone(20, B256::with_last_byte(20)));
+
+        let sink = RecordingSink::default();
+        let driver = ForkchoiceDriver::new(TestChain::with_blocks(10), &sink, tracker);
+
+        assert_eq!(driver.compute_state().unwrap().finalized_block_hash, B256::ZERO);
+    }
+}
@@ -0,0 +1,309 @@
+//! Experimental milestone gossip over an RLPx sub-protocol (`bmg/1`).
+//!
+//! Peers that both speak `bmg` tell each other the end of the latest Heimdall
+//! milestone they have seen when they connect. The hints are untrusted: they
+//! never feed the [`MilestoneTracker`], which only Heimdall updates, but they
+//! tell the node that its peers are ahead and which of them to sync from.
+//! [`poll_on_milestone_hints`] turns a hint past the tracker's latest milestone into a
+//! milestone notification, so the milestone poller asks Heimdall right away instead of
+//! at its next interval. Peers without the protocol are kept; they simply send no hints.
+
+use crate::push::{HeimdallPush, HeimdallTopic};
+use alloy_primitives::B256;
+use alloy_rlp::{Decodable, Encodable, RlpDecodable, RlpEncodable};
+use bor_consensus::MilestoneTracker;
+use bytes::{BufMut, BytesMut};
+use futures::Stream;
+use reth_eth_wire::{
+    capability::{Capability, SharedCapabilities},
+    multiplex::ProtocolConnection,
+    protocol::Protocol,
+};
+use reth_network::protocol::{ConnectionHandler, OnNotSupported, ProtocolHandler};
+use reth_network_api::Direction;
+use reth_network_peers::PeerId;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use tokio::sync::Notify;
+use tracing::{debug, trace};
+
+/// Name of the sub-protocol.
+pub const MILESTONE_GOSSIP_NAME: &str = "bmg";
+
+/// Version of the sub-protocol.
+pub const MILESTONE_GOSSIP_VERSION: usize = 1;
+
+/// ID of [`MilestoneHint`] messages.
+const MILESTONE_HINT_ID: u8 = 0x00;
+
+/// End of a milestone, as a peer reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct MilestoneHint {
+    /// Last block the milestone covers.
+    pub end_block: u64,
+    /// Hash of that block.
+    pub hash: B256,
+}
+
+impl MilestoneHint {
+    /// The message carrying this hint: its ID followed by the RLP payload.
+    pub fn encode_message(&self) -> BytesMut {
+        let mut buf = BytesMut::new();
+        buf.put_u8(MILESTONE_HINT_ID);
+        self.encode(&mut buf);
+        buf
+    }
+
+    /// Decode a message produced by [`encode_message`](Self::encode_message).
+    pub fn decode_message(mut msg: &[u8]) -> Option<Self> {
+        let (&id, payload) = msg.split_first()?;
+        if id != MILESTONE_HINT_ID {
+            return None;
+        }
+        msg = payload;
+        Self::decode(&mut msg).ok()
+    }
+}
+
+/// Latest milestone hint received from each peer.
+#[derive(Debug, Default)]
+pub struct MilestoneHints {
+    by_peer: Mutex<HashMap<PeerId, MilestoneHint>>,
+    /// Woken whenever a hint is recorded.
+    received: Notify,
+}
+
+impl MilestoneHints {
+    /// Record `hint` from `peer`, replacing an older one.
+    pub fn insert(&self, peer: PeerId, hint: MilestoneHint) {
+        let mut by_peer = self.by_peer.lock().expect("milestone hints lock poisoned");
+        let entry = by_peer.entry(peer).or_insert(hint);
+        if hint.end_block >= entry.end_block {
+            *entry = hint;
+        }
+        drop(by_peer);
+        self.received.notify_one();
+    }
+
+    /// Wait for the next hint, or return at once if one arrived since the last wait.
+    pub async fn received(&self) {
+        self.received.notified().await
+    }
+
+    /// Forget `peer`, e.g. once it disconnected.
+    pub fn remove(&self, peer: &PeerId) {
+        self.by_peer.lock().expect("milestone hints lock poisoned").remove(peer);
+    }
+
+    /// The furthest milestone any peer reported, with that peer.
+    pub fn highest(&self) -> Option<(PeerId, MilestoneHint)> {
+        let by_peer = self.by_peer.lock().expect("milestone hints lock poisoned");
+        by_peer.iter().max_by_key(|(_, hint)| hint.end_block).map(|(peer, hint)| (*peer, *hint))
+    }
+
+    /// The furthest milestone any peer reported past `end_block`, with that peer.
+    pub fn ahead_of(&self, end_block: Option<u64>) -> Option<(PeerId, MilestoneHint)> {
+        self.highest().filter(|(_, hint)| end_block.is_none_or(|end| hint.end_block > end))
+    }
+}
+
+/// Wake the milestone poller through `push` whenever a peer hints at a milestone past
+/// the latest one of `tracker`, once per hinted milestone.
+pub async fn poll_on_milestone_hints(
+    hints: Arc<MilestoneHints>,
+    tracker: Arc<MilestoneTracker>,
+    push: HeimdallPush,
+) {
+    let mut woken_for = None;
+    loop {
+        hints.received().await;
+        let latest = tracker.latest().map(|milestone| milestone.end_block);
+        let Some((peer, hint)) = hints.ahead_of(latest.max(woken_for)) else { continue };
+        debug!(
+            target: "bor::gossip",
+            %peer,
+            end_block = hint.end_block,
+            ?latest,
+            "peer is past the latest milestone, polling Heimdall"
+        );
+        woken_for = Some(hint.end_block);
+        push.notify(HeimdallTopic::Milestone);
+    }
+}
+
+/// [`ProtocolHandler`] of `bmg/1`.
+#[derive(Debug, Clone)]
+pub struct MilestoneGossip {
+    tracker: Arc<MilestoneTracker>,
+    hints: Arc<MilestoneHints>,
+}
+
+impl MilestoneGossip {
+    /// Gossip the latest milestone of `tracker`, collecting peers' hints.
+    pub fn new(tracker: Arc<MilestoneTracker>) -> Self {
+        Self { tracker, hints: Arc::default() }
+    }
+
+    /// Hints received from peers.
+    pub fn hints(&self) -> Arc<MilestoneHints> {
+        self.hints.clone()
+    }
+
+    /// The sub-protocol's capability and message count.
+    pub fn protocol() -> Protocol {
+        Protocol::new(
+            Capability::new_static(MILESTONE_GOSSIP_NAME, MILESTONE_GOSSIP_VERSION),
+            1,
+        )
+    }
+}
+
+impl ProtocolHandler for MilestoneGossip {
+    type ConnectionHandler = Self;
+
+    fn on_incoming(&self, _socket_addr: SocketAddr) -> Option<Self::ConnectionHandler> {
+        Some(self.clone())
+    }
+
+    fn on_outgoing(
+        &self,
+        _socket_addr: SocketAddr,
+        _peer_id: PeerId,
+    ) -> Option<Self::ConnectionHandler> {
+        Some(self.clone())
+    }
+}
+
+impl ConnectionHandler for MilestoneGossip {
+    type Connection = MilestoneGossipConnection;
+
+    fn protocol(&self) -> Protocol {
+        Self::protocol()
+    }
+
+    fn on_unsupported_by_peer(
+        self,
+        _supported: &SharedCapabilities,
+        _direction: Direction,
+        _peer_id: PeerId,
+    ) -> OnNotSupported {
+        OnNotSupported::KeepAlive
+    }
+
+    fn into_connection(
+        self,
+        _direction: Direction,
+        peer_id: PeerId,
+        conn: ProtocolConnection,
+    ) -> Self::Connection {
+        let greeting = self
+            .tracker
+            .latest()
+            .map(|milestone| MilestoneHint { end_block: milestone.end_block, hash: milestone.hash });
+        MilestoneGossipConnection {
+            conn,
+            peer_id,
+            greeting: greeting.map(|hint| hint.encode_message()),
+            hints: self.hints,
+        }
+    }
+}
+
+/// One peer's `bmg` connection.
+///
+/// Yields the messages to send to the peer and records the hints it receives.
+#[derive(Debug)]
+pub struct MilestoneGossipConnection {
+    conn: ProtocolConnection,
+    peer_id: PeerId,
+    /// Our latest milestone, sent once when the connection opens.
+    greeting: Option<BytesMut>,
+    hints: Arc<MilestoneHints>,
+}
+
+impl Stream for MilestoneGossipConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(greeting) = this.greeting.take() {
+            return Poll::Ready(Some(greeting));
+        }
+        loop {
+            let Some(msg) = ready!(Pin::new(&mut this.conn).poll_next(cx)) else {
+                this.hints.remove(&this.peer_id);
+                return Poll::Ready(None);
+            };
+            match MilestoneHint::decode_message(&msg) {
+                Some(hint) => {
+                    trace!(target: "bor::gossip", peer = %this.peer_id, ?hint, "milestone hint");
+                    this.hints.insert(this.peer_id, hint);
+                }
+                None => {
+                    debug!(target: "bor::gossip", peer = %this.peer_id, "invalid milestone hint");
+                    this.hints.remove(&this.peer_id);
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_hint_message_roundtrip() {
+        let hint = MilestoneHint { end_block: 70_000_000, hash: B256::with_last_byte(7) };
+        let msg = hint.encode_message();
+        assert_eq!(msg[0], MILESTONE_HINT_ID);
+        assert_eq!(MilestoneHint::decode_message(&msg), Some(hint));
+
+        assert_eq!(MilestoneHint::decode_message(&[]), None);
+        let mut wrong_id = msg.to_vec();
+        wrong_id[0] = 0x01;
+        assert_eq!(MilestoneHint::decode_message(&wrong_id), None);
+    }
+
+    #[test]
+    fn test_hints_keep_furthest_per_peer() {
+        let hints = MilestoneHints::default();
+        let (a, b) = (PeerId::with_last_byte(1), PeerId::with_last_byte(2));
+        let hint = |end_block| MilestoneHint { end_block, hash: B256::ZERO };
+
+        hints.insert(a, hint(100));
+        hints.insert(a, hint(90));
+        hints.insert(b, hint(120));
+        assert_eq!(hints.highest(), Some((b, hint(120))));
+
+        hints.remove(&b);
+        assert_eq!(hints.highest(), Some((a, hint(100))));
+        assert_eq!(hints.ahead_of(None), Some((a, hint(100))));
+        assert_eq!(hints.ahead_of(Some(99)), Some((a, hint(100))));
+        assert_eq!(hints.ahead_of(Some(100)), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hint_past_latest_milestone_wakes_poller() {
+        let hints = Arc::new(MilestoneHints::default());
+        let push = HeimdallPush::new();
+        let tracker = Arc::new(MilestoneTracker::new());
+        let task = tokio::spawn(poll_on_milestone_hints(hints.clone(), tracker, push.clone()));
+        let hint = |end_block| MilestoneHint { end_block, hash: B256::ZERO };
+        let woken = || push.wait(&[HeimdallTopic::Milestone], Duration::from_millis(200));
+
+        hints.insert(PeerId::with_last_byte(1), hint(100));
+        assert_eq!(woken().await, Some(HeimdallTopic::Milestone));
+        // The same milestone from another peer does not poll again.
+        hints.insert(PeerId::with_last_byte(2), hint(100));
+        assert_eq!(woken().await, None);
+        hints.insert(PeerId::with_last_byte(2), hint(116));
+        assert_eq!(woken().await, Some(HeimdallTopic::Milestone));
+        task.abort();
+    }
+}
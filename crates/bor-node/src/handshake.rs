@@ -3,6 +3,10 @@
 //! Go-Bor's eth/69 Status message includes a `TD` (Total Difficulty) field
 //! that standard eth/69 (EIP-7642) removed. This custom handshake handles
 //! both Go-Bor's non-standard format and standard Reth/Geth format.
+//!
+//! Beyond the Status message, bor peers speak the eth protocol exactly as geth
+//! does, on the versions in [`BOR_ETH_VERSIONS`]. `tests/bor_peering.rs` dials a
+//! bor node in a container with this handshake to check that the two agree.
 
 use alloy_chains::Chain;
 use alloy_primitives::{B256, U256};
@@ -22,6 +26,13 @@ use tokio::time::timeout;
 use tokio_stream::StreamExt;
 use tracing::{debug, trace};
 
+/// eth protocol versions bor peers negotiate, newest first.
+pub const BOR_ETH_VERSIONS: [EthVersion; 4] =
+    [EthVersion::Eth69, EthVersion::Eth68, EthVersion::Eth67, EthVersion::Eth66];
+
+/// Largest eth message bor accepts, in bytes.
+pub const MAX_MESSAGE_SIZE: usize = 10 * 1024 * 1024;
+
 /// Go-Bor's eth/69 Status message (8 fields, includes TD).
 ///
 /// ```text
@@ -133,7 +144,7 @@ where
         }
     };
 
-    if their_msg.len() > 10 * 1024 * 1024 {
+    if their_msg.len() > MAX_MESSAGE_SIZE {
         unauth
             .disconnect(DisconnectReason::ProtocolBreach)
             .await
@@ -312,3 +323,31 @@ fn decode_bor_eth69_status(msg: &[u8]) -> Result<UnifiedStatus, EthStreamError>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_ethereum_forks::ForkHash;
+
+    #[test]
+    fn test_bor_eth69_status_roundtrip() {
+        let status = BorStatusEth69 {
+            version: EthVersion::Eth69,
+            chain: Chain::from_id(137),
+            total_difficulty: U256::from(1_000),
+            genesis: B256::with_last_byte(1),
+            forkid: ForkId { hash: ForkHash([0xde, 0xad, 0xbe, 0xef]), next: 0 },
+            earliest: 0,
+            latest: 100,
+            blockhash: B256::with_last_byte(2),
+        };
+        let mut buf = Vec::new();
+        EthMessageID::Status.encode(&mut buf);
+        status.encode(&mut buf);
+
+        let decoded = decode_bor_eth69_status(&buf).unwrap();
+        assert_eq!(decoded.total_difficulty, Some(U256::from(1_000)));
+        assert_eq!(decoded.latest_block, Some(100));
+        assert_eq!(decoded.blockhash, B256::with_last_byte(2));
+    }
+}
@@ -0,0 +1,129 @@
+//! Import of the blocks peers announce.
+//!
+//! The network hands announced blocks to [`BorBlockImport`], which records the heaviest in
+//! the [`NetworkHead`] the forkchoice driver follows and queues them for
+//! [`AnnouncedImports`], which imports them into the engine tree once it is up.
+
+use crate::forkchoice::NetworkHead;
+use crate::payload::BorEngineTypes;
+use alloy_consensus::BlockHeader;
+use alloy_primitives::U256;
+use alloy_rpc_types_engine::PayloadStatusEnum;
+use reth_eth_wire::NewBlock;
+use reth_network::{
+    import::{
+        BlockImport, BlockImportError, BlockImportEvent, BlockImportOutcome, BlockValidation,
+        NewBlockEvent,
+    },
+    message::NewBlockMessage,
+};
+use reth_network_peers::PeerId;
+use reth_node_api::{ConsensusEngineHandle, PayloadTypes};
+use reth_primitives_traits::SealedBlock;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Blocks announced by peers, as the network hands them to its [`BlockImport`].
+type AnnouncedBlock = NewBlockMessage<NewBlock<reth_ethereum_primitives::Block>>;
+
+/// Outcome of importing an [`AnnouncedBlock`].
+type AnnouncedOutcome = BlockImportOutcome<NewBlock<reth_ethereum_primitives::Block>>;
+
+/// Blocks announced and not yet imported at most; further ones are dropped, as peers
+/// announce every block to many nodes and the engine downloads what it misses.
+const ANNOUNCED_IMPORT_QUEUE: usize = 64;
+
+/// The network's [`BlockImport`]: records the heaviest block peers announce in a
+/// [`NetworkHead`] and queues it for [`AnnouncedImports::run`], which imports it into
+/// the engine tree. The outcome goes back to the network, which relays valid blocks and
+/// penalizes peers announcing invalid ones.
+#[derive(Debug)]
+pub struct BorBlockImport {
+    head: NetworkHead,
+    to_import: mpsc::Sender<(PeerId, AnnouncedBlock)>,
+    outcomes: mpsc::UnboundedReceiver<AnnouncedOutcome>,
+}
+
+impl BorBlockImport {
+    /// Create the import, recording announced blocks in `head`, and the queue the
+    /// blocks are imported from.
+    pub fn new(head: NetworkHead) -> (Self, AnnouncedImports) {
+        let (to_import, blocks) = mpsc::channel(ANNOUNCED_IMPORT_QUEUE);
+        let (outcomes_tx, outcomes) = mpsc::unbounded_channel();
+        (Self { head, to_import, outcomes }, AnnouncedImports { blocks, outcomes: outcomes_tx })
+    }
+}
+
+impl BlockImport<NewBlock<reth_ethereum_primitives::Block>> for BorBlockImport {
+    fn on_new_block(
+        &mut self,
+        peer_id: PeerId,
+        incoming_block: NewBlockEvent<NewBlock<reth_ethereum_primitives::Block>>,
+    ) {
+        // Announced hashes carry neither the block nor a total difficulty to weigh it by.
+        let NewBlockEvent::Block(message) = incoming_block else { return };
+        let number = message.block.block.header().number();
+        let td = U256::from(message.block.td);
+        if self.head.observe(number, message.hash, td) {
+            debug!(target: "boreth", %peer_id, number, hash = %message.hash, "new network head");
+        }
+        if self.to_import.try_send((peer_id, message)).is_err() {
+            debug!(target: "boreth", %peer_id, number, "announced block import queue full");
+        }
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<BlockImportEvent<NewBlock<reth_ethereum_primitives::Block>>> {
+        match self.outcomes.poll_recv(cx) {
+            Poll::Ready(Some(outcome)) => Poll::Ready(BlockImportEvent::Outcome(outcome)),
+            // Without the import task there is nothing left to report.
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The queue of blocks [`BorBlockImport`] received, imported once the engine is up.
+#[derive(Debug)]
+pub struct AnnouncedImports {
+    blocks: mpsc::Receiver<(PeerId, AnnouncedBlock)>,
+    outcomes: mpsc::UnboundedSender<AnnouncedOutcome>,
+}
+
+impl AnnouncedImports {
+    /// Import the queued blocks into the tree of `engine` as new payloads, and report
+    /// those found valid or invalid back to the network. Blocks whose parent the tree
+    /// lacks are left to the engine's download.
+    pub async fn run(mut self, engine: ConsensusEngineHandle<BorEngineTypes>) {
+        while let Some((peer, block)) = self.blocks.recv().await {
+            let (number, hash) = (block.block.block.header().number(), block.hash);
+            let sealed = SealedBlock::new_unchecked(block.block.block.clone(), hash);
+            let payload = BorEngineTypes::block_to_payload(sealed);
+            let status = match engine.new_payload(payload).await {
+                Ok(status) => status,
+                Err(err) => {
+                    warn!(target: "boreth", number, %hash, %err, "cannot import announced block");
+                    continue;
+                }
+            };
+            let result = match status.status {
+                PayloadStatusEnum::Valid => Ok(BlockValidation::ValidBlock { block }),
+                PayloadStatusEnum::Invalid { validation_error } => {
+                    debug!(
+                        target: "boreth",
+                        %peer,
+                        number,
+                        %hash,
+                        %validation_error,
+                        "invalid announced block"
+                    );
+                    Err(BlockImportError::Other(validation_error.into()))
+                }
+                PayloadStatusEnum::Syncing | PayloadStatusEnum::Accepted => continue,
+            };
+            let _ = self.outcomes.send(BlockImportOutcome { peer, result });
+        }
+    }
+}
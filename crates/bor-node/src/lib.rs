@@ -4,8 +4,69 @@
 //! RPC, storage, and Heimdall client into a complete node.
 
 pub mod node;
+pub mod adapters;
+pub mod args;
+pub mod canon;
+pub mod components;
 pub mod config;
+pub mod config_file;
+pub mod datadir;
+pub mod error;
+pub mod forkchoice;
+#[cfg(feature = "milestone-gossip")]
+pub mod gossip;
 pub mod handshake;
+pub mod import;
+pub mod milestone;
+pub mod milestone_peers;
+pub mod monitor;
+pub mod params;
+pub mod payload;
+pub mod producer;
+pub mod proposal;
+pub mod push;
+pub mod resync;
+pub mod sync;
+pub mod tasks;
+pub mod txpool;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 
 pub use node::BorNode;
-pub use config::BorNodeConfig;
+pub use adapters::{
+    proposal_candidates, AnnouncedHead, EngineForkchoiceSink, EnginePayloadTrigger,
+    PendingStateChanges, ProviderProduction, ProviderProposalExecutor, SprintPresimulation,
+};
+pub use args::BorArgs;
+pub use canon::{
+    export_canon_metrics, BorBlockMeta, BorCanonNotifications, BorCanonUpdate,
+    BOR_CANON_CHANNEL_SIZE,
+};
+pub use components::{
+    BorConsensusBuilder, BorExecutorBuilder, BorNetworkBuilder, BorPoolBuilder, CommittedSpans,
+};
+pub use config::{BorNodeConfig, ForkchoiceMode};
+pub use config_file::{BorConfigFile, ConfigFileError};
+pub use datadir::BorDataDir;
+pub use error::BorError;
+pub use forkchoice::{ForkchoiceDriver, ForkchoiceSink, HeadSource, NetworkHead};
+pub use import::{AnnouncedImports, BorBlockImport};
+pub use milestone::MilestoneService;
+pub use milestone_peers::{MilestonePeers, PeerConsistency, CONFLICTING_PEER_PENALTY};
+pub use monitor::{
+    MonitorSource, MonitoredBlock, ProducerHistory, ProducerMonitor, ProducerRecord,
+    DEFAULT_MONITOR_HISTORY,
+};
+pub use params::BorParams;
+pub use payload::{
+    BorEngineTypes, BorEngineValidator, BorEngineValidatorBuilder, BorNodeTypes,
+    BorPayloadBuilder, BorPayloadBuilderBuilder, BorPayloadError,
+};
+pub use producer::{
+    ParentBlock, PayloadTrigger, ProducerScheduler, ProductionHalt, ProductionSource, Slot,
+};
+pub use proposal::{ExecutedProposal, ProposalBlock, ProposalExecutor, ProposalSimulator};
+pub use push::{prefetch_pushed_spans, HeimdallPush, HeimdallTopic, PushListener};
+pub use resync::{BorResync, JOURNAL_REPLAY_INTERVAL};
+pub use sync::SyncTuning;
+pub use txpool::{BorTxPoolConfig, TxJournal};
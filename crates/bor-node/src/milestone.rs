@@ -0,0 +1,164 @@
+//! Milestone service: polls Heimdall for new milestones and checkpoints.
+//!
+//! Feeds the shared [`MilestoneTracker`] that the RPC layer reads to report milestone
+//! finality and to resolve the `finalized` / `safe` block tags.
+
+use crate::push::{HeimdallPush, HeimdallTopic};
+use bor_consensus::MilestoneTracker;
+use heimdall_client::{HeimdallClient, HeimdallError};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Default interval between Heimdall milestone polls.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Background service that keeps a [`MilestoneTracker`] in sync with Heimdall.
+pub struct MilestoneService<C> {
+    /// The Heimdall client to fetch milestones from.
+    client: C,
+    /// Tracker shared with consumers (RPC, forkchoice).
+    tracker: Arc<MilestoneTracker>,
+    /// Delay between polls.
+    poll_interval: Duration,
+    /// Notifications that cut the delay short, if Heimdall pushes them.
+    push: Option<HeimdallPush>,
+}
+
+impl<C: HeimdallClient> MilestoneService<C> {
+    /// Create a new milestone service writing into `tracker`.
+    pub fn new(client: C, tracker: Arc<MilestoneTracker>) -> Self {
+        Self {
+            client,
+            tracker,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            push: None,
+        }
+    }
+
+    /// Override the poll interval.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Poll as soon as Heimdall pushes a milestone or checkpoint notification.
+    pub fn with_push(mut self, push: HeimdallPush) -> Self {
+        self.push = Some(push);
+        self
+    }
+
+    /// Returns the tracker this service writes into.
+    pub fn tracker(&self) -> &Arc<MilestoneTracker> {
+        &self.tracker
+    }
+
+    /// Fetch the latest milestone once and record it.
+    ///
+    /// Returns `true` if the finalized height advanced.
+    pub async fn poll_once(&self) -> Result<bool, HeimdallError> {
+        let milestone = self.client.fetch_milestone_latest().await?;
+        let (id, end_block, hash) = (
+            milestone.milestone_id.clone(),
+            milestone.end_block,
+            milestone.hash,
+        );
+
+        let advanced = self.tracker.update(milestone);
+        if advanced {
+            debug!(target: "bor::milestone", %id, end_block, %hash, "new milestone");
+        }
+        Ok(advanced)
+    }
+
+    /// Fetch the latest checkpoint once and record it.
+    ///
+    /// Returns `true` if the checkpointed height advanced.
+    pub async fn poll_checkpoint_once(&self) -> Result<bool, HeimdallError> {
+        let checkpoint = self.client.fetch_checkpoint_latest().await?;
+        let end_block = checkpoint.end_block;
+
+        let advanced = self.tracker.update_checkpoint(checkpoint);
+        if advanced {
+            debug!(target: "bor::milestone", end_block, "new checkpoint");
+        }
+        Ok(advanced)
+    }
+
+    /// Run the service as a background loop.
+    pub async fn run(self) {
+        info!(target: "bor::milestone", interval = ?self.poll_interval, "milestone service started");
+
+        loop {
+            match self.poll_once().await {
+                Ok(_) | Err(HeimdallError::NotFound) => {}
+                Err(e) => warn!(target: "bor::milestone", error = %e, "failed to fetch milestone"),
+            }
+            match self.poll_checkpoint_once().await {
+                Ok(_) | Err(HeimdallError::NotFound) => {}
+                Err(e) => warn!(target: "bor::milestone", error = %e, "failed to fetch checkpoint"),
+            }
+
+            match &self.push {
+                Some(push) => {
+                    let topics = [HeimdallTopic::Milestone, HeimdallTopic::Checkpoint];
+                    push.wait(&topics, self.poll_interval).await;
+                }
+                None => tokio::time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256};
+    use heimdall_client::{Checkpoint, Milestone, MockHeimdallClient};
+
+    fn milestone(id: &str, end_block: u64) -> Milestone {
+        Milestone {
+            milestone_id: id.to_string(),
+            start_block: end_block.saturating_sub(15),
+            end_block,
+            hash: B256::from([0x11; 32]),
+            proposer: Address::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_updates_tracker() {
+        let mock = MockHeimdallClient::new().with_latest_milestone(milestone("ms-1", 1_000));
+        let service = MilestoneService::new(mock, Arc::new(MilestoneTracker::new()));
+
+        assert!(service.poll_once().await.unwrap());
+        assert_eq!(service.tracker().latest_id().as_deref(), Some("ms-1"));
+        assert!(service.tracker().is_finalized(1_000));
+
+        // Same milestone again does not advance.
+        assert!(!service.poll_once().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_poll_checkpoint_once() {
+        let mock = MockHeimdallClient::new().with_latest_checkpoint(Checkpoint {
+            start_block: 0,
+            end_block: 2_047,
+            root_hash: B256::ZERO,
+            proposer: Address::ZERO,
+        });
+        let service = MilestoneService::new(mock, Arc::new(MilestoneTracker::new()));
+
+        assert!(service.poll_checkpoint_once().await.unwrap());
+        assert_eq!(service.tracker().safe_tag_block(), Some(2_047));
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_propagates_errors() {
+        let service =
+            MilestoneService::new(MockHeimdallClient::new(), Arc::new(MilestoneTracker::new()));
+
+        assert!(matches!(service.poll_once().await, Err(HeimdallError::NotFound)));
+        assert!(service.tracker().latest().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! Peer preference by milestone consistency.
+//!
+//! After a reorg below the latest milestone is ruled out, a peer whose chain does not
+//! contain the milestone block is on a fork that can never become canonical. reth's
+//! header downloader does not know this: it keeps asking such peers for headers and
+//! the node keeps downloading a chain it will discard.
+//!
+//! [`MilestonePeers`] keeps, for the latest milestone, whether each peer's chain
+//! contains its block, as found by asking the peer for the header by hash. A peer
+//! whose head was at or past the milestone and that does not have the block is
+//! [`Conflicting`](PeerConsistency::Conflicting) and has its reputation lowered once
+//! per milestone, so that repeat offenders are dropped and downloads and head
+//! selection go to the peers left. A peer behind the milestone is not penalized.
+
+use alloy_primitives::{B256, B512};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Reputation change applied to a peer conflicting with a milestone.
+///
+/// reth bans a peer once its reputation drops below -51,200 (`BANNED_REPUTATION`), so
+/// a peer is dropped after conflicting with about ten consecutive milestones.
+pub const CONFLICTING_PEER_PENALTY: i32 = -5_120;
+
+/// How a peer's chain relates to the latest milestone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerConsistency {
+    /// The peer has the milestone block.
+    Consistent,
+    /// The peer was not asked yet, or is behind the milestone.
+    Unknown,
+    /// The peer's head was at or past the milestone, and it does not have its block.
+    Conflicting,
+}
+
+#[derive(Debug, Default)]
+struct Verdicts {
+    /// End block and hash of the milestone the verdicts are for.
+    milestone: Option<(u64, B256)>,
+    by_peer: HashMap<B512, PeerConsistency>,
+}
+
+/// Consistency of each peer with the latest milestone.
+#[derive(Debug, Default)]
+pub struct MilestonePeers {
+    verdicts: Mutex<Verdicts>,
+}
+
+impl MilestonePeers {
+    /// Judge peers against the milestone ending at `end_block` with `hash`, forgetting
+    /// the verdicts of an earlier milestone.
+    ///
+    /// Returns `true` if the milestone changed.
+    pub fn set_milestone(&self, end_block: u64, hash: B256) -> bool {
+        let mut verdicts = self.verdicts.lock().expect("milestone peers lock poisoned");
+        if verdicts.milestone == Some((end_block, hash)) {
+            return false;
+        }
+        verdicts.milestone = Some((end_block, hash));
+        verdicts.by_peer.clear();
+        true
+    }
+
+    /// The milestone to ask `peer` for, if its consistency is not known yet.
+    pub fn probe(&self, peer: &B512) -> Option<(u64, B256)> {
+        let verdicts = self.verdicts.lock().expect("milestone peers lock poisoned");
+        let judged = verdicts.by_peer.get(peer).is_some_and(|c| *c != PeerConsistency::Unknown);
+        verdicts.milestone.filter(|_| !judged)
+    }
+
+    /// Record that `peer`, whose head was at `head` if it said so, answered a request
+    /// for the block of milestone `hash` with a header of `header` hash, or none.
+    ///
+    /// Answers about an earlier milestone are ignored. Returns the peer's consistency.
+    pub fn record(
+        &self,
+        peer: B512,
+        hash: B256,
+        head: Option<u64>,
+        header: Option<B256>,
+    ) -> PeerConsistency {
+        let mut verdicts = self.verdicts.lock().expect("milestone peers lock poisoned");
+        let Some((end_block, current)) = verdicts.milestone else {
+            return PeerConsistency::Unknown;
+        };
+        if current != hash {
+            return PeerConsistency::Unknown;
+        }
+        let consistency = match header {
+            Some(header) if header == hash => PeerConsistency::Consistent,
+            // A header other than the one asked for is no better than none.
+            Some(_) => PeerConsistency::Conflicting,
+            None if head.is_some_and(|head| head >= end_block) => PeerConsistency::Conflicting,
+            None => PeerConsistency::Unknown,
+        };
+        verdicts.by_peer.insert(peer, consistency);
+        consistency
+    }
+
+    /// Consistency of `peer` with the latest milestone.
+    pub fn consistency(&self, peer: &B512) -> PeerConsistency {
+        let verdicts = self.verdicts.lock().expect("milestone peers lock poisoned");
+        verdicts.by_peer.get(peer).copied().unwrap_or(PeerConsistency::Unknown)
+    }
+
+    /// `peers` ordered by preference: consistent first, conflicting last, otherwise in
+    /// the order given.
+    pub fn ranked(&self, peers: impl IntoIterator<Item = B512>) -> Vec<B512> {
+        let mut peers: Vec<_> = peers.into_iter().collect();
+        peers.sort_by_key(|peer| match self.consistency(peer) {
+            PeerConsistency::Consistent => 0,
+            PeerConsistency::Unknown => 1,
+            PeerConsistency::Conflicting => 2,
+        });
+        peers
+    }
+
+    /// Forget `peer`, e.g. once it disconnected.
+    pub fn remove(&self, peer: &B512) {
+        self.verdicts.lock().expect("milestone peers lock poisoned").by_peer.remove(peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdicts_follow_the_milestone() {
+        let peers = MilestonePeers::default();
+        let (a, b, c) = (B512::with_last_byte(1), B512::with_last_byte(2), B512::with_last_byte(3));
+        let hash = B256::with_last_byte(7);
+        assert_eq!(peers.probe(&a), None);
+
+        assert!(peers.set_milestone(1_000, hash));
+        assert!(!peers.set_milestone(1_000, hash));
+        assert_eq!(peers.probe(&a), Some((1_000, hash)));
+        assert_eq!(peers.record(a, hash, Some(1_010), Some(hash)), PeerConsistency::Consistent);
+        assert_eq!(peers.record(b, hash, Some(1_010), None), PeerConsistency::Conflicting);
+        assert_eq!(peers.record(c, hash, Some(990), None), PeerConsistency::Unknown);
+        assert_eq!(peers.probe(&a), None);
+        assert_eq!(peers.probe(&c), Some((1_000, hash)));
+        assert_eq!(peers.ranked([b, c, a]), vec![a, c, b]);
+
+        // Late answers about an earlier milestone change nothing.
+        let next = B256::with_last_byte(8);
+        assert!(peers.set_milestone(1_016, next));
+        assert_eq!(peers.record(a, hash, Some(1_020), None), PeerConsistency::Unknown);
+        assert_eq!(peers.consistency(&a), PeerConsistency::Unknown);
+        assert_eq!(peers.consistency(&b), PeerConsistency::Unknown);
+
+        peers.record(b, next, None, Some(B256::ZERO));
+        assert_eq!(peers.consistency(&b), PeerConsistency::Conflicting);
+        peers.remove(&b);
+        assert_eq!(peers.consistency(&b), PeerConsistency::Unknown);
+    }
+}
@@ -0,0 +1,440 @@
+//! Producer monitoring.
+//!
+//! [`ProducerMonitor`] follows the canonical chain and records, for each new block, the
+//! sprint proposer expected to produce it and the validator that actually signed it. A
+//! block signed by someone else was produced out of turn, after its proposer missed the
+//! slot. Counts for this node's own blocks and missed slots, the out-of-turn rate and
+//! the block time are exported as metrics, and [`ProducerHistory::performance`] answers
+//! `bor_getProducerPerformance` over the blocks recorded since the node started.
+
+use crate::producer::ProductionSource;
+use alloy_primitives::{Address, B256};
+use bor_rpc::{BorRpcError, ProducerPerformanceResponse, ProducerStats};
+use reth_metrics::{
+    metrics::{Counter, Histogram},
+    Metrics,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Default number of blocks [`ProducerHistory`] keeps.
+pub const DEFAULT_MONITOR_HISTORY: usize = 10_000;
+
+/// Default interval between head evaluations.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A canonical block as the monitor sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitoredBlock {
+    /// Block hash.
+    pub hash: B256,
+    /// Block timestamp, in seconds.
+    pub timestamp: u64,
+    /// Validator that signed the block.
+    pub signer: Address,
+}
+
+/// Canonical blocks and their signers, on top of what the producer scheduler reads.
+pub trait MonitorSource: ProductionSource {
+    /// Returns canonical block `number` with its recovered signer.
+    fn block(&self, number: u64) -> Option<MonitoredBlock>;
+}
+
+/// Expected and actual producer of one block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProducerRecord {
+    /// Block number.
+    pub number: u64,
+    /// Block hash.
+    pub hash: B256,
+    /// Block timestamp, in seconds.
+    pub timestamp: u64,
+    /// Sprint proposer of the block, if its span was known.
+    pub expected: Option<Address>,
+    /// Validator that signed the block.
+    pub signer: Address,
+    /// Seconds since the parent, if the parent was seen.
+    pub block_time: Option<u64>,
+    /// Whether this node signed the block.
+    pub own_block: bool,
+    /// Whether this node was the proposer but someone else signed the block.
+    pub own_missed: bool,
+}
+
+impl ProducerRecord {
+    /// Whether the block was signed by someone other than its proposer.
+    pub fn is_out_of_turn(&self) -> bool {
+        self.expected.is_some_and(|expected| expected != self.signer)
+    }
+}
+
+/// The last blocks recorded by a [`ProducerMonitor`], shared with the RPC.
+#[derive(Debug, Clone)]
+pub struct ProducerHistory {
+    records: Arc<RwLock<VecDeque<ProducerRecord>>>,
+    capacity: usize,
+}
+
+impl Default for ProducerHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_MONITOR_HISTORY)
+    }
+}
+
+impl ProducerHistory {
+    /// Create a history keeping the last `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self { records: Default::default(), capacity: capacity.max(1) }
+    }
+
+    /// Returns the last block recorded.
+    pub fn last(&self) -> Option<ProducerRecord> {
+        self.records.read().expect("producer history lock poisoned").back().copied()
+    }
+
+    /// Returns the record of block `number`.
+    pub fn get(&self, number: u64) -> Option<ProducerRecord> {
+        let records = self.records.read().expect("producer history lock poisoned");
+        let first = records.front()?.number;
+        records.get(number.checked_sub(first)? as usize).filter(|r| r.number == number).copied()
+    }
+
+    /// Sum up the records of blocks `start..=end`.
+    ///
+    /// Fails if `start > end` or if none of the blocks were recorded.
+    pub fn performance(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<ProducerPerformanceResponse, BorRpcError> {
+        if start > end {
+            return Err(BorRpcError::InvalidBlockRange { start, end });
+        }
+        let records = self.records.read().expect("producer history lock poisoned");
+        let mut range = records.iter().filter(|r| (start..=end).contains(&r.number)).peekable();
+        let Some(first) = range.peek().map(|r| r.number) else {
+            return Err(BorRpcError::BlockNotFound(start));
+        };
+
+        let mut response = ProducerPerformanceResponse {
+            start_block: first,
+            end_block: first,
+            ..Default::default()
+        };
+        let mut producers = BTreeMap::<Address, ProducerStats>::new();
+        let (mut block_times, mut timed) = (0u64, 0u64);
+        for record in range {
+            response.end_block = record.number;
+            response.blocks += 1;
+            response.out_of_turn_blocks += record.is_out_of_turn() as u64;
+            response.unknown_proposer_blocks += record.expected.is_none() as u64;
+            response.own_blocks += record.own_block as u64;
+            response.own_missed_slots += record.own_missed as u64;
+            if let Some(block_time) = record.block_time {
+                block_times += block_time;
+                timed += 1;
+            }
+
+            let signer =
+                producers.entry(record.signer).or_insert_with(|| empty_stats(record.signer));
+            signer.blocks += 1;
+            signer.in_turn_blocks += (record.expected == Some(record.signer)) as u64;
+            if let Some(expected) = record.expected.filter(|_| record.is_out_of_turn()) {
+                let proposer = producers.entry(expected).or_insert_with(|| empty_stats(expected));
+                proposer.missed_slots += 1;
+            }
+        }
+        response.average_block_time = (timed > 0).then(|| block_times as f64 / timed as f64);
+        response.producers = producers.into_values().collect();
+        response.producers.sort_by(|a, b| b.blocks.cmp(&a.blocks));
+        Ok(response)
+    }
+
+    fn push(&self, record: ProducerRecord) {
+        let mut records = self.records.write().expect("producer history lock poisoned");
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Drop the records of blocks `number` and above.
+    fn truncate(&self, number: u64) {
+        let mut records = self.records.write().expect("producer history lock poisoned");
+        while records.back().is_some_and(|r| r.number >= number) {
+            records.pop_back();
+        }
+    }
+}
+
+fn empty_stats(address: Address) -> ProducerStats {
+    ProducerStats { address, blocks: 0, in_turn_blocks: 0, missed_slots: 0 }
+}
+
+/// Producer monitor metrics.
+#[derive(Metrics)]
+#[metrics(scope = "bor.producer_monitor")]
+struct MonitorMetrics {
+    /// Number of blocks recorded.
+    blocks: Counter,
+    /// Number of blocks signed by someone other than their sprint proposer.
+    out_of_turn_blocks: Counter,
+    /// Number of blocks signed by this node.
+    own_blocks: Counter,
+    /// Number of blocks this node was the proposer for but someone else signed.
+    own_missed_slots: Counter,
+    /// Seconds between a block and its parent.
+    block_time: Histogram,
+}
+
+/// Records the expected and actual producer of each new canonical block.
+pub struct ProducerMonitor<S> {
+    source: S,
+    history: ProducerHistory,
+    poll_interval: Duration,
+    metrics: MonitorMetrics,
+}
+
+impl<S: MonitorSource> ProducerMonitor<S> {
+    /// Create a monitor recording into `history`.
+    pub fn new(source: S, history: ProducerHistory) -> Self {
+        Self {
+            source,
+            history,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            metrics: MonitorMetrics::default(),
+        }
+    }
+
+    /// Override the poll interval.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the history this monitor records into.
+    pub fn history(&self) -> &ProducerHistory {
+        &self.history
+    }
+
+    /// Record the blocks added to the canonical chain since the last step.
+    ///
+    /// Blocks replaced by a reorg are dropped and recorded again. The first step only
+    /// records the head. Returns the number of blocks recorded.
+    pub fn step(&self) -> usize {
+        let Some(head) = self.source.parent() else { return 0 };
+
+        // Unwind to the last record still canonical.
+        while let Some(last) = self.history.last() {
+            let canonical = if last.number == head.number {
+                last.hash == head.hash
+            } else {
+                last.number < head.number
+                    && self.source.block(last.number).is_some_and(|b| b.hash == last.hash)
+            };
+            if canonical {
+                break;
+            }
+            debug!(target: "bor::monitor", number = last.number, "dropping reorged block");
+            self.history.truncate(last.number);
+        }
+
+        let from = self.history.last().map_or(head.number, |last| last.number + 1);
+        let from = from.max(head.number.saturating_sub(self.history.capacity as u64 - 1));
+        let mut recorded = 0;
+        for number in from..=head.number {
+            let Some(record) = self.record(number) else { break };
+            self.history.push(record);
+            recorded += 1;
+        }
+        recorded
+    }
+
+    fn record(&self, number: u64) -> Option<ProducerRecord> {
+        let block = self.source.block(number)?;
+        let expected = self
+            .source
+            .validator_set(number)
+            .and_then(|set| set.proposer)
+            .map(|proposer| proposer.signer);
+        let parent_timestamp = number.checked_sub(1).and_then(|parent| {
+            self.history
+                .get(parent)
+                .map(|r| r.timestamp)
+                .or_else(|| self.source.block(parent).map(|b| b.timestamp))
+        });
+        let block_time = parent_timestamp.map(|parent| block.timestamp.saturating_sub(parent));
+        let own = self.source.signer();
+        let record = ProducerRecord {
+            number,
+            hash: block.hash,
+            timestamp: block.timestamp,
+            expected,
+            signer: block.signer,
+            block_time,
+            own_block: own == Some(block.signer),
+            own_missed: own.is_some_and(|own| expected == Some(own) && block.signer != own),
+        };
+
+        self.metrics.blocks.increment(1);
+        if record.is_out_of_turn() {
+            self.metrics.out_of_turn_blocks.increment(1);
+        }
+        if record.own_block {
+            self.metrics.own_blocks.increment(1);
+        }
+        if record.own_missed {
+            self.metrics.own_missed_slots.increment(1);
+            warn!(
+                target: "bor::monitor",
+                number,
+                signer = %block.signer,
+                "missed our slot, block produced by a backup"
+            );
+        }
+        if let Some(block_time) = block_time {
+            self.metrics.block_time.record(block_time as f64);
+        }
+        Some(record)
+    }
+
+    /// Run the monitor as a background loop.
+    pub async fn run(self) {
+        info!(target: "bor::monitor", interval = ?self.poll_interval, "producer monitor started");
+        loop {
+            self.step();
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::producer::ParentBlock;
+    use bor_chainspec::{BorHardforks, MainnetBorHardforks};
+    use bor_primitives::{Validator, ValidatorSet};
+    use std::sync::Mutex;
+
+    fn addr(b: u8) -> Address {
+        Address::new([b; 20])
+    }
+
+    /// A chain whose blocks are signed by the given validators, proposed by validator 1.
+    struct TestChain {
+        signers: Mutex<Vec<u8>>,
+        fork: u8,
+    }
+
+    impl TestChain {
+        fn new(signers: &[u8]) -> Self {
+            Self { signers: Mutex::new(signers.to_vec()), fork: 0 }
+        }
+
+        fn hash(&self, number: u64) -> B256 {
+            B256::from([number as u8 ^ self.fork; 32])
+        }
+    }
+
+    impl ProductionSource for TestChain {
+        fn parent(&self) -> Option<ParentBlock> {
+            let number = self.signers.lock().unwrap().len() as u64 - 1;
+            Some(ParentBlock {
+                number,
+                hash: self.hash(number),
+                timestamp: number * 2,
+                gas_limit: 30_000_000,
+            })
+        }
+
+        fn validator_set(&self, _number: u64) -> Option<ValidatorSet> {
+            let proposer = Validator {
+                id: 1,
+                address: addr(1),
+                voting_power: 100,
+                signer: addr(1),
+                proposer_priority: 0,
+            };
+            Some(ValidatorSet { validators: vec![proposer.clone()], proposer: Some(proposer) })
+        }
+
+        fn signer(&self) -> Option<Address> {
+            Some(addr(1))
+        }
+
+        fn chain_spec(&self) -> &dyn BorHardforks {
+            &MainnetBorHardforks
+        }
+    }
+
+    impl MonitorSource for TestChain {
+        fn block(&self, number: u64) -> Option<MonitoredBlock> {
+            let signer = *self.signers.lock().unwrap().get(number as usize)?;
+            Some(MonitoredBlock {
+                hash: self.hash(number),
+                timestamp: number * 2 + (signer != 1) as u64 * 4,
+                signer: addr(signer),
+            })
+        }
+    }
+
+    #[test]
+    fn test_records_missed_slots() {
+        let chain = TestChain::new(&[1, 1, 1]);
+        let monitor = ProducerMonitor::new(chain, ProducerHistory::default());
+        assert_eq!(monitor.step(), 1);
+        assert_eq!(monitor.history().last().unwrap().number, 2);
+
+        monitor.source.signers.lock().unwrap().extend([2, 1, 3]);
+        assert_eq!(monitor.step(), 3);
+        assert_eq!(monitor.step(), 0);
+
+        let report = monitor.history().performance(0, 100).unwrap();
+        assert_eq!((report.start_block, report.end_block, report.blocks), (2, 5, 4));
+        assert_eq!(report.out_of_turn_blocks, 2);
+        assert_eq!((report.own_blocks, report.own_missed_slots), (2, 2));
+        assert_eq!(report.producers[0], ProducerStats {
+            address: addr(1),
+            blocks: 2,
+            in_turn_blocks: 2,
+            missed_slots: 2,
+        });
+        assert_eq!(report.producers.len(), 3);
+        assert!(report.average_block_time.is_some());
+
+        let report = monitor.history().performance(4, 4).unwrap();
+        assert_eq!((report.blocks, report.out_of_turn_blocks, report.own_blocks), (1, 0, 1));
+        assert!(matches!(monitor.history().performance(0, 1), Err(BorRpcError::BlockNotFound(0))));
+        assert!(matches!(
+            monitor.history().performance(5, 4),
+            Err(BorRpcError::InvalidBlockRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reorg_rerecords_blocks() {
+        let chain = TestChain::new(&[1, 1, 1, 2]);
+        let mut monitor = ProducerMonitor::new(chain, ProducerHistory::default());
+        monitor.step();
+        assert!(monitor.history().get(3).unwrap().own_missed);
+
+        // Block 3 replaced by our own.
+        monitor.source.fork = 0xff;
+        monitor.source.signers.lock().unwrap()[3] = 1;
+        assert_eq!(monitor.step(), 1);
+        let record = monitor.history().get(3).unwrap();
+        assert_eq!((record.hash, record.own_block), (B256::from([3 ^ 0xff; 32]), true));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let chain = TestChain::new(&[1; 8]);
+        let monitor = ProducerMonitor::new(chain, ProducerHistory::new(4));
+        monitor.step();
+        monitor.source.signers.lock().unwrap().extend([1; 8]);
+        assert_eq!(monitor.step(), 4);
+        assert_eq!(monitor.history().performance(0, 100).unwrap().start_block, 12);
+    }
+}
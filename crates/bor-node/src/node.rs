@@ -1,11 +1,20 @@
 //! BorNode: wires all components together.
+//!
+//! A node owns all of its state, and its [`BorParams`] if given any, so several nodes,
+//! even of different networks, can run in one process without seeing each other.
 
+use alloy_consensus::Header;
 use alloy_primitives::B256;
 use bor_chainspec::BorChainSpec;
-use bor_consensus::BorSnapshot;
-use bor_storage::persistence::{InMemorySpanStore, InMemorySnapshotStore, SnapshotStore};
+use bor_consensus::{BorSnapshot, MilestoneTracker, SnapshotRebuilder};
+use bor_primitives::ValidatorSet;
+use bor_storage::persistence::{
+    InMemorySnapshotStore, InMemorySpanStore, SharedSnapshotStore, SnapshotStore,
+};
 use crate::config::{BorNodeConfig, BorNetwork};
+use crate::params::BorParams;
 use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
 
 /// The assembled Bor node with all components wired together.
 pub struct BorNode {
@@ -15,29 +24,68 @@ pub struct BorNode {
     pub chain_spec: Arc<BorChainSpec>,
     /// Span store.
     pub span_store: Arc<RwLock<InMemorySpanStore>>,
-    /// Snapshot store.
-    pub snapshot_store: Arc<RwLock<InMemorySnapshotStore>>,
+    /// Snapshot store, in memory unless [`with_snapshot_store`](Self::with_snapshot_store)
+    /// gives one.
+    pub snapshot_store: SharedSnapshotStore,
+    /// Latest milestones observed from Heimdall.
+    pub milestones: Arc<MilestoneTracker>,
+    /// Runtime-adjustable parameters, if the node talks to Heimdall.
+    pub params: Option<BorParams>,
 }
 
 impl BorNode {
     /// Create a new BorNode from configuration.
+    ///
+    /// Mainnet and Amoy use their embedded chain specs; a custom network needs
+    /// [`with_chain_spec`](Self::with_chain_spec).
     pub fn new(config: BorNodeConfig) -> eyre::Result<Self> {
         let chain_spec = match config.network {
             BorNetwork::Mainnet => Arc::new(bor_chainspec::bor_mainnet_genesis()),
             BorNetwork::Amoy => Arc::new(bor_chainspec::bor_amoy_genesis()),
+            BorNetwork::Custom(chain_id) => {
+                eyre::bail!("chain {chain_id} has no embedded chain spec, pass its genesis")
+            }
         };
+        Self::with_chain_spec(config, chain_spec)
+    }
 
+    /// Create a new BorNode running `chain_spec`, which must be of the configured network.
+    pub fn with_chain_spec(
+        config: BorNodeConfig,
+        chain_spec: Arc<BorChainSpec>,
+    ) -> eyre::Result<Self> {
+        let chain_id = chain_spec.inner().chain.id();
+        eyre::ensure!(
+            chain_id == config.chain_id(),
+            "chain spec of chain {chain_id} for a node of chain {}",
+            config.chain_id()
+        );
         let span_store = Arc::new(RwLock::new(InMemorySpanStore::new()));
-        let snapshot_store = Arc::new(RwLock::new(InMemorySnapshotStore::new()));
+        let snapshot_store: SharedSnapshotStore =
+            Arc::new(RwLock::new(InMemorySnapshotStore::new()));
 
         Ok(Self {
             config,
             chain_spec,
             span_store,
             snapshot_store,
+            milestones: Arc::new(MilestoneTracker::new()),
+            params: None,
         })
     }
 
+    /// Talk to Heimdall and produce blocks as `params` say.
+    pub fn with_params(mut self, params: BorParams) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Keep snapshots in `store`, e.g. one persisted in the datadir.
+    pub fn with_snapshot_store(mut self, store: SharedSnapshotStore) -> Self {
+        self.snapshot_store = store;
+        self
+    }
+
     /// Get the chain ID.
     pub fn chain_id(&self) -> u64 {
         self.config.chain_id()
@@ -62,6 +110,60 @@ impl BorNode {
             None => Ok(None),
         }
     }
+
+    /// The snapshot at `hash`, rebuilt if the store has none or cannot decode it.
+    ///
+    /// The rebuild applies `headers`, which must run from the block after `anchor`
+    /// up to `hash`, and stores the snapshot after every chunk, so an interrupted
+    /// rebuild resumes from the last one stored. See [`SnapshotRebuilder::rebuild`]
+    /// for `is_sprint_end` and `next_validators`.
+    pub fn snapshot_or_rebuild(
+        &self,
+        hash: B256,
+        anchor: BorSnapshot,
+        headers: impl IntoIterator<Item = Header>,
+        is_sprint_end: impl Fn(u64) -> bool,
+        next_validators: impl FnMut(u64, &BorSnapshot) -> Option<ValidatorSet>,
+    ) -> eyre::Result<BorSnapshot> {
+        match self.get_snapshot(&hash) {
+            Ok(Some(snapshot)) => return Ok(snapshot),
+            Ok(None) => {
+                let from = anchor.number;
+                info!(target: "bor::snapshot", %hash, from, "no snapshot, rebuilding");
+            }
+            Err(err) => {
+                warn!(target: "bor::snapshot", %hash, %err, "corrupt snapshot, rebuilding");
+            }
+        }
+
+        let mut stored = Ok(());
+        let snapshot = SnapshotRebuilder::default().rebuild(
+            anchor,
+            headers,
+            is_sprint_end,
+            next_validators,
+            |snapshot, progress| {
+                info!(
+                    target: "bor::snapshot",
+                    number = progress.number,
+                    applied = progress.applied,
+                    "rebuilding snapshot"
+                );
+                if stored.is_ok() {
+                    stored = self.put_snapshot(snapshot.hash, snapshot);
+                }
+            },
+        )?;
+        stored?;
+        eyre::ensure!(
+            snapshot.hash == hash,
+            "rebuilt snapshot ends at {} ({}), not {hash}",
+            snapshot.number,
+            snapshot.hash
+        );
+        self.put_snapshot(hash, &snapshot)?;
+        Ok(snapshot)
+    }
 }
 
 #[cfg(test)]
@@ -97,6 +199,13 @@ mod tests {
         assert_eq!(node.chain_id(), 137);
     }
 
+    #[test]
+    fn test_custom_network_needs_chain_spec() {
+        assert!(BorNode::new(BorNodeConfig::custom(1337)).is_err());
+        let amoy = Arc::new(bor_chainspec::bor_amoy_genesis());
+        assert!(BorNode::with_chain_spec(BorNodeConfig::mainnet(), amoy).is_err());
+    }
+
     #[test]
     fn test_snapshot_roundtrip() {
         let config = BorNodeConfig::amoy();
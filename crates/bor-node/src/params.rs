@@ -0,0 +1,146 @@
+//! Bor parameters that can be changed while the node runs.
+//!
+//! Operators switch Heimdall endpoints or rotate the validator key through the
+//! [`BorAdminApi`] methods (`bor_setHeimdallUrl`, `bor_setSigner`) instead of
+//! restarting the node. Heimdall timeouts and paging are fixed at startup and
+//! travel with the parameters as a [`HeimdallConfig`].
+
+use alloy_primitives::Address;
+use bor_rpc::{BorAdminApi, BorRpcError};
+use heimdall_client::{HeimdallConfig, HttpHeimdallClient};
+use std::sync::{Arc, RwLock};
+use tracing::info;
+use url::Url;
+
+/// Runtime-adjustable Bor parameters, shared by every component that reads them.
+///
+/// Cloning is cheap and clones observe each other's updates.
+#[derive(Debug, Clone)]
+pub struct BorParams {
+    /// Heimdall client handed to the node's services; endpoint switches apply to all clones.
+    heimdall: HttpHeimdallClient,
+    /// Address the node produces blocks as, if it is a validator.
+    signer: Arc<RwLock<Option<Address>>>,
+}
+
+impl BorParams {
+    /// Create the parameters from the startup Heimdall client and signer.
+    pub fn new(heimdall: HttpHeimdallClient, signer: Option<Address>) -> Self {
+        Self { heimdall, signer: Arc::new(RwLock::new(signer)) }
+    }
+
+    /// A Heimdall client that follows endpoint switches.
+    pub fn heimdall(&self) -> HttpHeimdallClient {
+        self.heimdall.clone()
+    }
+
+    /// Timeout, paging and retry settings of Heimdall requests.
+    pub fn heimdall_config(&self) -> &HeimdallConfig {
+        self.heimdall.config()
+    }
+
+    /// The current Heimdall endpoint.
+    pub fn heimdall_url(&self) -> String {
+        self.heimdall.base_url()
+    }
+
+    /// Switch the Heimdall endpoint.
+    pub fn set_heimdall_url(&self, url: &Url) {
+        let previous = self.heimdall.base_url();
+        self.heimdall.set_base_url(url.as_str());
+        info!(target: "bor::params", %previous, current = %url, "switched Heimdall endpoint");
+    }
+
+    /// The address the node currently produces blocks as.
+    pub fn signer(&self) -> Option<Address> {
+        *self.signer.read().expect("signer lock poisoned")
+    }
+
+    /// Replace the block producer address, returning the previous one.
+    pub fn set_signer(&self, signer: Address) -> Option<Address> {
+        let previous = self.signer.write().expect("signer lock poisoned").replace(signer);
+        info!(target: "bor::params", ?previous, current = %signer, "rotated signer");
+        previous
+    }
+}
+
+impl BorAdminApi for BorParams {
+    type Error = BorRpcError;
+
+    fn bor_set_heimdall_url(&self, url: String) -> Result<bool, Self::Error> {
+        let url = Url::parse(&url)
+            .map_err(|e| BorRpcError::InvalidParams(format!("invalid Heimdall URL {url:?}: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(BorRpcError::InvalidParams(format!(
+                "unsupported Heimdall URL scheme {:?}",
+                url.scheme()
+            )));
+        }
+        self.set_heimdall_url(&url);
+        Ok(true)
+    }
+
+    fn bor_set_signer(&self, signer: Address) -> Result<bool, Self::Error> {
+        if signer.is_zero() {
+            return Err(BorRpcError::InvalidParams("signer must not be the zero address".into()));
+        }
+        self.set_signer(signer);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> BorParams {
+        BorParams::new(HttpHeimdallClient::new("http://localhost:1317"), None)
+    }
+
+    #[test]
+    fn test_set_heimdall_url_reaches_existing_clients() {
+        let params = params();
+        let service_client = params.heimdall();
+
+        assert!(params.bor_set_heimdall_url("https://heimdall-backup:1317/".into()).unwrap());
+        assert_eq!(service_client.base_url(), "https://heimdall-backup:1317");
+        assert_eq!(params.clone().heimdall_url(), "https://heimdall-backup:1317");
+    }
+
+    #[test]
+    fn test_set_heimdall_url_rejects_invalid_urls() {
+        let params = params();
+        assert!(matches!(
+            params.bor_set_heimdall_url("not a url".into()),
+            Err(BorRpcError::InvalidParams(_))
+        ));
+        assert!(matches!(
+            params.bor_set_heimdall_url("ws://localhost:1317".into()),
+            Err(BorRpcError::InvalidParams(_))
+        ));
+        assert_eq!(params.heimdall_url(), "http://localhost:1317");
+    }
+
+    #[test]
+    fn test_heimdall_config_follows_client() {
+        let config = HeimdallConfig::default().with_max_state_sync_pages(4);
+        let params = BorParams::new(
+            HttpHeimdallClient::new("http://localhost:1317").with_config(config.clone()).unwrap(),
+            None,
+        );
+        assert_eq!(params.clone().heimdall_config(), &config);
+    }
+
+    #[test]
+    fn test_set_signer() {
+        let params = params();
+        let clone = params.clone();
+        assert_eq!(params.signer(), None);
+
+        assert!(params.bor_set_signer(Address::new([0xaa; 20])).unwrap());
+        assert_eq!(clone.signer(), Some(Address::new([0xaa; 20])));
+        assert_eq!(params.set_signer(Address::new([0xbb; 20])), Some(Address::new([0xaa; 20])));
+        assert!(params.bor_set_signer(Address::ZERO).is_err());
+        assert_eq!(clone.signer(), Some(Address::new([0xbb; 20])));
+    }
+}
@@ -0,0 +1,461 @@
+//! Engine types and payload builder of boreth.
+//!
+//! Builds are requested with [`BorPayloadAttributes`], which carry the Bor fields of
+//! the block next to Ethereum's attributes, so the payload builder receives them as
+//! [`BorPayloadBuilderAttributes`]. [`BorPayloadBuilder`] runs reth's Ethereum builder
+//! with the extra data they call for: the vanity, the next span's validators at the end
+//! of a sprint, and the seal left zeroed for the sealer. Transactions are taken from the
+//! pool by effective tip for as long as the [`BuildBudget`] of the block allows, see
+//! [`BudgetedTransactions`].
+
+use alloy_primitives::Bytes;
+use alloy_rpc_types_engine::ExecutionData;
+use bor_consensus::ExtraDataBuilder;
+use bor_payload::{
+    record_selection, BorPayloadAttributes, BorPayloadBuilderAttributes, BuildBudget,
+    SelectionClock, StopReason, DEFAULT_BUILD_MARGIN,
+};
+use heimdall_client::SharedSpanCache;
+use reth_basic_payload_builder::{
+    BuildArguments, BuildOutcome, MissingPayloadBehaviour, PayloadBuilder, PayloadConfig,
+};
+use reth_chainspec::{ChainSpec, EthChainSpec, EthereumHardforks};
+use reth_engine_primitives::{EngineApiValidator, EngineTypes, PayloadValidator};
+use reth_ethereum_engine_primitives::{
+    EthBuiltPayload, EthEngineTypes, EthPayloadBuilderAttributes,
+};
+use reth_ethereum_payload_builder::{
+    default_ethereum_payload, EthereumBuilderConfig, EthereumPayloadBuilder,
+};
+use reth_ethereum_primitives::{EthPrimitives, TransactionSigned};
+use reth_evm::{ConfigureEvm, NextBlockEnvAttributes};
+use reth_node_api::{AddOnsContext, FullNodeComponents, NodeTypes, PayloadTypes, TxTy};
+use reth_node_builder::{
+    components::PayloadBuilderBuilder, node::FullNodeTypes, rpc::PayloadValidatorBuilder,
+    BuilderContext,
+};
+use reth_node_ethereum::EthereumEngineValidator;
+use reth_payload_primitives::{
+    EngineApiMessageVersion, EngineObjectValidationError, InvalidPayloadAttributesError,
+    NewPayloadError, PayloadBuilderError, PayloadOrAttributes,
+};
+use reth_primitives_traits::SealedBlock;
+use reth_provider::{ChainSpecProvider, EthStorage, StateProviderFactory};
+use reth_transaction_pool::{
+    error::InvalidPoolTransactionError, BestTransactions, PoolTransaction, TransactionPool,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Node types of boreth: Ethereum's, with [`BorEngineTypes`].
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct BorNodeTypes;
+
+impl NodeTypes for BorNodeTypes {
+    type Primitives = EthPrimitives;
+    type ChainSpec = ChainSpec;
+    type Storage = EthStorage;
+    type Payload = BorEngineTypes;
+}
+
+/// Ethereum's engine types, with builds requested by [`BorPayloadAttributes`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[non_exhaustive]
+pub struct BorEngineTypes;
+
+impl PayloadTypes for BorEngineTypes {
+    type ExecutionData = ExecutionData;
+    type BuiltPayload = EthBuiltPayload;
+    type PayloadAttributes = BorPayloadAttributes;
+    type PayloadBuilderAttributes = BorPayloadBuilderAttributes;
+
+    fn block_to_payload(block: SealedBlock<reth_ethereum_primitives::Block>) -> ExecutionData {
+        <EthEngineTypes as PayloadTypes>::block_to_payload(block)
+    }
+}
+
+impl EngineTypes for BorEngineTypes {
+    type ExecutionPayloadEnvelopeV1 = <EthEngineTypes as EngineTypes>::ExecutionPayloadEnvelopeV1;
+    type ExecutionPayloadEnvelopeV2 = <EthEngineTypes as EngineTypes>::ExecutionPayloadEnvelopeV2;
+    type ExecutionPayloadEnvelopeV3 = <EthEngineTypes as EngineTypes>::ExecutionPayloadEnvelopeV3;
+    type ExecutionPayloadEnvelopeV4 = <EthEngineTypes as EngineTypes>::ExecutionPayloadEnvelopeV4;
+    type ExecutionPayloadEnvelopeV5 = <EthEngineTypes as EngineTypes>::ExecutionPayloadEnvelopeV5;
+}
+
+/// Validates payloads as Ethereum's engine does, and the Ethereum part of
+/// [`BorPayloadAttributes`].
+#[derive(Debug, Clone)]
+pub struct BorEngineValidator(EthereumEngineValidator<ChainSpec>);
+
+impl PayloadValidator<BorEngineTypes> for BorEngineValidator {
+    type Block = reth_ethereum_primitives::Block;
+
+    fn convert_payload_to_block(
+        &self,
+        payload: ExecutionData,
+    ) -> Result<SealedBlock<Self::Block>, NewPayloadError> {
+        PayloadValidator::<EthEngineTypes>::convert_payload_to_block(&self.0, payload)
+    }
+
+    fn validate_payload_attributes_against_header(
+        &self,
+        attributes: &BorPayloadAttributes,
+        header: &alloy_consensus::Header,
+    ) -> Result<(), InvalidPayloadAttributesError> {
+        if attributes.payload_attributes.timestamp <= header.timestamp
+            || attributes.block_number != header.number + 1
+        {
+            return Err(InvalidPayloadAttributesError::InvalidTimestamp);
+        }
+        Ok(())
+    }
+}
+
+impl EngineApiValidator<BorEngineTypes> for BorEngineValidator {
+    fn validate_version_specific_fields(
+        &self,
+        version: EngineApiMessageVersion,
+        payload_or_attrs: PayloadOrAttributes<'_, ExecutionData, BorPayloadAttributes>,
+    ) -> Result<(), EngineObjectValidationError> {
+        let payload_or_attrs = match payload_or_attrs {
+            PayloadOrAttributes::ExecutionPayload(payload) => {
+                PayloadOrAttributes::ExecutionPayload(payload)
+            }
+            PayloadOrAttributes::PayloadAttributes(attributes) => {
+                PayloadOrAttributes::PayloadAttributes(&attributes.payload_attributes)
+            }
+        };
+        EngineApiValidator::<EthEngineTypes>::validate_version_specific_fields(
+            &self.0,
+            version,
+            payload_or_attrs,
+        )
+    }
+
+    fn ensure_well_formed_attributes(
+        &self,
+        version: EngineApiMessageVersion,
+        attributes: &BorPayloadAttributes,
+    ) -> Result<(), EngineObjectValidationError> {
+        EngineApiValidator::<EthEngineTypes>::ensure_well_formed_attributes(
+            &self.0,
+            version,
+            &attributes.payload_attributes,
+        )
+    }
+}
+
+/// Builds the [`BorEngineValidator`].
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct BorEngineValidatorBuilder;
+
+impl<Node> PayloadValidatorBuilder<Node> for BorEngineValidatorBuilder
+where
+    Node: FullNodeComponents<Types = BorNodeTypes>,
+{
+    type Validator = BorEngineValidator;
+
+    async fn build(self, ctx: &AddOnsContext<'_, Node>) -> eyre::Result<Self::Validator> {
+        Ok(BorEngineValidator(EthereumEngineValidator::new(ctx.config.chain.clone())))
+    }
+}
+
+/// Why a Bor payload cannot be built.
+#[derive(Debug, thiserror::Error)]
+pub enum BorPayloadError {
+    /// The span whose validators a sprint-end block lists is not cached.
+    #[error("span of block {0} not cached, sprint-end validators unknown")]
+    NextSpanUnavailable(u64),
+    /// The extra data cannot be assembled.
+    #[error(transparent)]
+    ExtraData(#[from] bor_consensus::ExtraDataError),
+}
+
+/// Builds Bor payloads with reth's Ethereum builder, giving each block the extra data
+/// its [`BorPayloadBuilderAttributes`] call for.
+#[derive(Debug, Clone)]
+pub struct BorPayloadBuilder<Pool, Client, EvmConfig> {
+    client: Client,
+    pool: Pool,
+    evm_config: EvmConfig,
+    /// Configuration of the Ethereum builder; its extra data is the vanity.
+    builder_config: EthereumBuilderConfig,
+    /// Spans the validators of sprint-end blocks are read from.
+    spans: SharedSpanCache,
+}
+
+impl<Pool, Client, EvmConfig> BorPayloadBuilder<Pool, Client, EvmConfig>
+where
+    Pool: Clone,
+    Client: Clone,
+    EvmConfig: Clone,
+{
+    /// The extra data of the block `attributes` describe, with the seal zeroed.
+    fn extra_data(
+        &self,
+        attributes: &BorPayloadBuilderAttributes,
+    ) -> Result<Bytes, BorPayloadError> {
+        let mut extra = ExtraDataBuilder::new(attributes.block_number, attributes.sprint_size)
+            .with_vanity(&self.builder_config.extra_data);
+        if attributes.sprint_end {
+            let next = attributes.block_number + 1;
+            let mut spans = self.spans.lock().expect("span cache lock poisoned");
+            let span =
+                spans.span_for_block(next).ok_or(BorPayloadError::NextSpanUnavailable(next))?;
+            extra = extra.with_validators(&span.validator_set.validators);
+        }
+        Ok(extra.build()?)
+    }
+
+    /// The Ethereum builder configuration and attributes of the block `attributes`
+    /// describe.
+    fn ethereum_config(
+        &self,
+        attributes: &BorPayloadBuilderAttributes,
+    ) -> Result<(EthereumBuilderConfig, EthPayloadBuilderAttributes), PayloadBuilderError> {
+        let extra_data = self.extra_data(attributes).map_err(PayloadBuilderError::other)?;
+        let eth =
+            EthPayloadBuilderAttributes::new(attributes.parent, attributes.payload_attributes());
+        Ok((self.builder_config.clone().with_extra_data(extra_data), eth))
+    }
+
+    /// The Ethereum builder of the block `attributes` describe, and its attributes.
+    fn ethereum(
+        &self,
+        attributes: &BorPayloadBuilderAttributes,
+    ) -> Result<
+        (EthereumPayloadBuilder<Pool, Client, EvmConfig>, EthPayloadBuilderAttributes),
+        PayloadBuilderError,
+    > {
+        let (builder_config, eth) = self.ethereum_config(attributes)?;
+        let builder = EthereumPayloadBuilder::new(
+            self.client.clone(),
+            self.pool.clone(),
+            self.evm_config.clone(),
+            builder_config,
+        );
+        Ok((builder, eth))
+    }
+}
+
+impl<Pool, Client, EvmConfig> PayloadBuilder for BorPayloadBuilder<Pool, Client, EvmConfig>
+where
+    EvmConfig: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
+    Client: StateProviderFactory + ChainSpecProvider<ChainSpec: EthereumHardforks> + Clone,
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TransactionSigned>>,
+{
+    type Attributes = BorPayloadBuilderAttributes;
+    type BuiltPayload = EthBuiltPayload;
+
+    /// Runs reth's Ethereum build, which executes the pool's best transactions in order of
+    /// effective tip, for as long as the budget of the block allows.
+    fn try_build(
+        &self,
+        args: BuildArguments<BorPayloadBuilderAttributes, EthBuiltPayload>,
+    ) -> Result<BuildOutcome<EthBuiltPayload>, PayloadBuilderError> {
+        let BuildArguments { cached_reads, config, cancel, best_payload } = args;
+        let budget = BuildBudget::for_block_time(config.attributes.timestamp, DEFAULT_BUILD_MARGIN);
+        let (builder_config, attributes) = self.ethereum_config(&config.attributes)?;
+        let config = PayloadConfig::new(config.parent_header, attributes);
+        default_ethereum_payload(
+            self.evm_config.clone(),
+            self.client.clone(),
+            self.pool.clone(),
+            builder_config,
+            BuildArguments::new(cached_reads, config, cancel, best_payload),
+            |attributes| {
+                let best = self.pool.best_transactions_with_attributes(attributes);
+                Box::new(BudgetedTransactions::new(best, budget))
+            },
+        )
+    }
+
+    /// A slot cannot wait: a block with what is built so far beats none.
+    fn on_missing_payload(
+        &self,
+        _args: BuildArguments<BorPayloadBuilderAttributes, EthBuiltPayload>,
+    ) -> MissingPayloadBehaviour<EthBuiltPayload> {
+        MissingPayloadBehaviour::RaceEmptyPayload
+    }
+
+    fn build_empty_payload(
+        &self,
+        config: PayloadConfig<BorPayloadBuilderAttributes>,
+    ) -> Result<EthBuiltPayload, PayloadBuilderError> {
+        let (builder, attributes) = self.ethereum(&config.attributes)?;
+        builder.build_empty_payload(PayloadConfig::new(config.parent_header, attributes))
+    }
+}
+
+/// The best transactions of the pool, which come in order of effective tip, until the
+/// [`BuildBudget`] of the block is too short for another one.
+///
+/// The builder executes each transaction before it takes the next, so the time between
+/// two is what the first took: the [`SelectionClock`] stops the build once the time left
+/// is shorter than the slowest. The selection is recorded in the builder metrics when
+/// the build is done with it.
+struct BudgetedTransactions<I> {
+    inner: I,
+    clock: SelectionClock,
+    /// Transactions taken and not rejected by the builder.
+    selected: usize,
+    stop: StopReason,
+}
+
+impl<I> BudgetedTransactions<I> {
+    /// Take transactions from `inner` within `budget`.
+    fn new(inner: I, budget: BuildBudget) -> Self {
+        Self { inner, clock: SelectionClock::new(budget), selected: 0, stop: StopReason::Exhausted }
+    }
+}
+
+impl<I: Iterator> Iterator for BudgetedTransactions<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stop == StopReason::Deadline {
+            return None;
+        }
+        if !self.clock.try_start(Instant::now()) {
+            self.stop = StopReason::Deadline;
+            return None;
+        }
+        let tx = self.inner.next()?;
+        self.selected += 1;
+        Some(tx)
+    }
+}
+
+impl<I: BestTransactions> BestTransactions for BudgetedTransactions<I> {
+    fn mark_invalid(&mut self, transaction: &Self::Item, kind: &InvalidPoolTransactionError) {
+        self.selected = self.selected.saturating_sub(1);
+        self.inner.mark_invalid(transaction, kind)
+    }
+
+    fn no_updates(&mut self) {
+        self.inner.no_updates()
+    }
+
+    fn set_skip_blobs(&mut self, skip_blobs: bool) {
+        self.inner.set_skip_blobs(skip_blobs)
+    }
+}
+
+impl<I> Drop for BudgetedTransactions<I> {
+    fn drop(&mut self) {
+        record_selection(self.selected, self.clock.utilization(), self.stop);
+    }
+}
+
+/// Builds the [`BorPayloadBuilder`], reading sprint-end validators from `spans`.
+#[derive(Debug, Clone)]
+pub struct BorPayloadBuilderBuilder {
+    /// Spans the validators of sprint-end blocks are read from.
+    pub spans: SharedSpanCache,
+}
+
+impl<Node, Pool, Evm> PayloadBuilderBuilder<Node, Pool, Evm> for BorPayloadBuilderBuilder
+where
+    Node: FullNodeTypes<Types = BorNodeTypes>,
+    Evm: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>
+        + 'static,
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus = TxTy<BorNodeTypes>>>
+        + Unpin
+        + 'static,
+{
+    type PayloadBuilder = BorPayloadBuilder<Pool, Node::Provider, Evm>;
+
+    async fn build_payload_builder(
+        self,
+        ctx: &BuilderContext<Node>,
+        pool: Pool,
+        evm_config: Evm,
+    ) -> eyre::Result<Self::PayloadBuilder> {
+        let conf = ctx.payload_builder_config();
+        // The gas limit is the Bor target's, applied by the EVM config at each block.
+        let gas_limit = conf.gas_limit_for(ctx.chain_spec().chain());
+        let builder_config = EthereumBuilderConfig::new()
+            .with_gas_limit(gas_limit)
+            .with_extra_data(conf.extra_data_bytes());
+        Ok(BorPayloadBuilder {
+            client: ctx.provider().clone(),
+            pool,
+            evm_config,
+            builder_config,
+            spans: self.spans,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256, U256};
+    use bor_chainspec::MainnetBorHardforks;
+    use bor_primitives::{
+        Span, Validator, ValidatorSet, EXTRADATA_SEAL_LEN, EXTRADATA_VANITY_LEN,
+    };
+    use heimdall_client::SpanCache;
+    use std::sync::Mutex;
+
+    fn builder(spans: SharedSpanCache) -> BorPayloadBuilder<(), (), ()> {
+        BorPayloadBuilder {
+            client: (),
+            pool: (),
+            evm_config: (),
+            builder_config: EthereumBuilderConfig::new()
+                .with_extra_data(Bytes::from_static(b"bor")),
+            spans,
+        }
+    }
+
+    fn attributes(number: u64) -> BorPayloadBuilderAttributes {
+        BorPayloadBuilderAttributes::new(
+            &MainnetBorHardforks,
+            B256::ZERO,
+            number,
+            0,
+            Address::ZERO,
+            U256::from(1),
+        )
+    }
+
+    #[test]
+    fn test_extra_data_lists_next_span_at_sprint_end() {
+        let validator = Validator {
+            id: 1,
+            address: Address::repeat_byte(0xaa),
+            voting_power: 100,
+            signer: Address::repeat_byte(0xaa),
+            proposer_priority: 0,
+        };
+        let spans: SharedSpanCache = Arc::new(Mutex::new(SpanCache::new(4)));
+        let builder = builder(spans.clone());
+
+        let extra = builder.extra_data(&attributes(100)).unwrap();
+        assert_eq!(extra.len(), EXTRADATA_VANITY_LEN + EXTRADATA_SEAL_LEN);
+        assert_eq!(&extra[..3], b"bor");
+
+        let sprint_end = attributes(255);
+        assert!(sprint_end.sprint_end);
+        let err = builder.extra_data(&sprint_end).unwrap_err();
+        assert!(matches!(err, BorPayloadError::NextSpanUnavailable(256)), "{err}");
+
+        spans.lock().unwrap().insert(Span {
+            id: 1,
+            start_block: 256,
+            end_block: 6655,
+            validator_set: ValidatorSet {
+                validators: vec![validator.clone()],
+                proposer: Some(validator.clone()),
+            },
+            selected_producers: vec![validator],
+            bor_chain_id: "137".to_string(),
+        });
+        let extra = builder.extra_data(&sprint_end).unwrap();
+        assert_eq!(extra.len(), EXTRADATA_VANITY_LEN + 20 + EXTRADATA_SEAL_LEN);
+        assert_eq!(&extra[EXTRADATA_VANITY_LEN..EXTRADATA_VANITY_LEN + 20], &[0xaa; 20]);
+    }
+}
@@ -0,0 +1,502 @@
+//! Block production scheduling.
+//!
+//! A validator may seal block `n` no earlier than
+//! `parent.timestamp + producer_delay(n, succession)`, where its succession
+//! number is its distance behind the sprint proposer. [`ProducerScheduler`]
+//! follows the chain head, works out when that moment comes for this node and
+//! asks the payload builder for a block then. Backups additionally wait a
+//! configurable *wiggle* per succession step, so that several backups do not
+//! race each other the instant the protocol allows them to.
+//!
+//! The local clock is not trusted blindly. A slot that is already due is built
+//! at once, however late. A parent timestamp so far ahead of the local clock
+//! that the wait exceeds the protocol delay by more than the allowed skew means
+//! the local clock lags; the scheduler then waits only the protocol delay
+//! rather than stalling production until the clock catches up.
+//!
+//! While it waits for a slot, the scheduler lets the trigger prepare it once
+//! (see [`PayloadTrigger::prepare`]), e.g. to run the sprint-start system calls
+//! ahead of the build.
+//!
+//! A [`ProductionHalt`] stops production altogether, e.g. when two Heimdall
+//! endpoints serve different state sync events, until an operator resumes it.
+
+use alloy_primitives::{Address, B256, U256};
+use bor_chainspec::BorHardforks;
+use bor_consensus::succession::{earliest_block_time, producer_delay, succession_number};
+use bor_primitives::ValidatorSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, warn};
+
+/// Default interval between head evaluations while no slot is due.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Default extra wait per succession step for backup producers.
+const DEFAULT_WIGGLE: Duration = Duration::from_millis(500);
+
+/// Default tolerated difference between the local clock and parent timestamps.
+const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(1);
+
+/// The block a new slot builds on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParentBlock {
+    /// Parent block number.
+    pub number: u64,
+    /// Parent block hash.
+    pub hash: B256,
+    /// Parent block timestamp, in seconds.
+    pub timestamp: u64,
+    /// Parent block gas limit.
+    pub gas_limit: u64,
+}
+
+/// Chain and validator data the scheduler plans slots from.
+pub trait ProductionSource: Send + Sync {
+    /// Returns the block the next slot builds on, normally the canonical head.
+    fn parent(&self) -> Option<ParentBlock>;
+
+    /// Returns the validator set, with its sprint proposer, that governs block `number`.
+    fn validator_set(&self, number: u64) -> Option<ValidatorSet>;
+
+    /// Returns the address this node signs with, if it is a validator.
+    fn signer(&self) -> Option<Address>;
+
+    /// The chain's sprints, block periods and producer delays.
+    fn chain_spec(&self) -> &dyn BorHardforks;
+}
+
+/// Starts building a block, typically through the engine's payload builder.
+pub trait PayloadTrigger: Send + Sync {
+    /// Build the block for `slot`.
+    fn build_payload(&self, slot: Slot) -> impl Future<Output = eyre::Result<()>> + Send;
+
+    /// Prepare the build of `slot`, which is not due yet.
+    ///
+    /// Called at most once per parent. Should return quickly and leave any long work to
+    /// a task of its own; does nothing by default.
+    fn prepare(&self, _slot: Slot) -> impl Future<Output = eyre::Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// A block this node is entitled to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    /// Number of the block to produce.
+    pub number: u64,
+    /// Hash of its parent.
+    pub parent_hash: B256,
+    /// Address the block is produced for.
+    pub signer: Address,
+    /// Distance of the signer behind the sprint proposer (0 = in-turn).
+    pub succession: usize,
+    /// Earliest timestamp the block may carry.
+    pub timestamp: u64,
+    /// Difficulty the block must carry.
+    pub difficulty: U256,
+}
+
+impl Slot {
+    /// Returns `true` if this node is the sprint proposer for the block.
+    pub fn is_inturn(&self) -> bool {
+        self.succession == 0
+    }
+}
+
+/// Shared switch that stops block production.
+///
+/// Clones share the same switch.
+#[derive(Debug, Clone, Default)]
+pub struct ProductionHalt {
+    reason: Arc<Mutex<Option<String>>>,
+}
+
+impl ProductionHalt {
+    /// Create a switch that lets production run.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop production for `reason`. A later halt replaces the reason.
+    pub fn halt(&self, reason: impl Into<String>) {
+        *self.reason.lock().expect("production halt lock poisoned") = Some(reason.into());
+    }
+
+    /// Let production run again, returning the reason it was halted for.
+    pub fn resume(&self) -> Option<String> {
+        self.reason.lock().expect("production halt lock poisoned").take()
+    }
+
+    /// Why production is halted, if it is.
+    pub fn reason(&self) -> Option<String> {
+        self.reason.lock().expect("production halt lock poisoned").clone()
+    }
+
+    /// Returns `true` while production is halted.
+    pub fn is_halted(&self) -> bool {
+        self.reason.lock().expect("production halt lock poisoned").is_some()
+    }
+}
+
+impl PartialEq for ProductionHalt {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.reason, &other.reason)
+    }
+}
+
+/// Wakes the payload builder when this node's production slot comes.
+pub struct ProducerScheduler<S, T> {
+    source: S,
+    trigger: T,
+    /// Stops builds while halted.
+    halt: Option<ProductionHalt>,
+    /// Extra wait per succession step for backup producers.
+    wiggle: Duration,
+    /// Tolerated lead of parent timestamps over the local clock.
+    max_clock_skew: Duration,
+    poll_interval: Duration,
+    /// Parent of the last slot a build was started for.
+    last_built: Option<B256>,
+    /// Parent of the last slot the trigger prepared.
+    last_prepared: Option<B256>,
+    /// Parent of the last slot skipped while halted.
+    last_halted: Option<B256>,
+}
+
+impl<S: ProductionSource, T: PayloadTrigger> ProducerScheduler<S, T> {
+    /// Create a new scheduler.
+    pub fn new(source: S, trigger: T) -> Self {
+        Self {
+            source,
+            trigger,
+            halt: None,
+            wiggle: DEFAULT_WIGGLE,
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            last_built: None,
+            last_prepared: None,
+            last_halted: None,
+        }
+    }
+
+    /// Override the extra wait per succession step for backup producers.
+    pub fn with_wiggle(mut self, wiggle: Duration) -> Self {
+        self.wiggle = wiggle;
+        self
+    }
+
+    /// Override the tolerated lead of parent timestamps over the local clock.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Skip every slot while `halt` is set.
+    pub fn with_halt(mut self, halt: ProductionHalt) -> Self {
+        self.halt = Some(halt);
+        self
+    }
+
+    /// Override the poll interval.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the slot this node may produce on top of the current parent.
+    ///
+    /// Returns `None` without a signer, parent or validator set, or if the signer is not
+    /// part of the validator set.
+    pub fn next_slot(&self) -> Option<Slot> {
+        let signer = self.source.signer()?;
+        let parent = self.source.parent()?;
+        let number = parent.number + 1;
+        let validator_set = self.source.validator_set(number)?;
+        let succession = match succession_number(&validator_set, &signer) {
+            Ok(succession) => succession,
+            Err(e) => {
+                debug!(target: "bor::producer", number, %signer, error = %e, "not a producer");
+                return None;
+            }
+        };
+        let chain_spec = self.source.chain_spec();
+        let timestamp = match earliest_block_time(chain_spec, parent.timestamp, number, succession)
+        {
+            Ok(timestamp) => timestamp,
+            Err(e) => {
+                warn!(target: "bor::producer", number, error = %e, "no production schedule");
+                return None;
+            }
+        };
+
+        Some(Slot {
+            number,
+            parent_hash: parent.hash,
+            signer,
+            succession,
+            timestamp,
+            difficulty: U256::from(validator_set.validators.len() - succession),
+        })
+    }
+
+    /// Returns how long to wait, from `now` (time since the Unix epoch), before building
+    /// `slot`. Zero means the slot is due.
+    pub fn wait_for(&self, slot: &Slot, now: Duration) -> Duration {
+        let wiggle = self.wiggle * slot.succession as u32;
+        let due = Duration::from_secs(slot.timestamp) + wiggle;
+        let wait = due.saturating_sub(now);
+
+        // `next_slot` already read the schedule of the slot.
+        let delay = producer_delay(self.source.chain_spec(), slot.number, slot.succession)
+            .unwrap_or_default();
+        let delay = Duration::from_secs(delay) + wiggle;
+        if wait > delay + self.max_clock_skew {
+            warn!(
+                target: "bor::producer",
+                number = slot.number,
+                ahead = ?(wait - delay),
+                "parent timestamp ahead of local clock, check clock synchronization"
+            );
+            return delay;
+        }
+        wait
+    }
+
+    /// Evaluate the head once, starting a build if this node's slot is due.
+    ///
+    /// `now` is the time since the Unix epoch. Returns how long to sleep before the next
+    /// evaluation.
+    pub async fn step(&mut self, now: Duration) -> eyre::Result<Duration> {
+        let Some(slot) = self.next_slot() else { return Ok(self.poll_interval) };
+        if self.last_built == Some(slot.parent_hash) {
+            return Ok(self.poll_interval);
+        }
+        if let Some(reason) = self.halt.as_ref().and_then(ProductionHalt::reason) {
+            // Alert once per slot rather than on every poll.
+            if self.last_halted != Some(slot.parent_hash) {
+                self.last_halted = Some(slot.parent_hash);
+                error!(
+                    target: "bor::producer",
+                    number = slot.number,
+                    %reason,
+                    "block production halted, skipping slot"
+                );
+            }
+            return Ok(self.poll_interval);
+        }
+
+        let wait = self.wait_for(&slot, now);
+        if !wait.is_zero() {
+            if self.last_prepared != Some(slot.parent_hash) {
+                self.last_prepared = Some(slot.parent_hash);
+                if let Err(e) = self.trigger.prepare(slot).await {
+                    let number = slot.number;
+                    debug!(target: "bor::producer", number, error = %e, "failed to prepare slot");
+                }
+            }
+            // Wake early enough to notice a competing block replacing the parent.
+            return Ok(wait.min(self.poll_interval));
+        }
+
+        debug!(
+            target: "bor::producer",
+            number = slot.number,
+            succession = slot.succession,
+            timestamp = slot.timestamp,
+            "slot due, building block"
+        );
+        self.trigger.build_payload(slot).await?;
+        self.last_built = Some(slot.parent_hash);
+        Ok(self.poll_interval)
+    }
+
+    /// Run the scheduler as a background loop.
+    pub async fn run(mut self) {
+        info!(target: "bor::producer", wiggle = ?self.wiggle, "producer scheduler started");
+
+        loop {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            let sleep = match self.step(now).await {
+                Ok(sleep) => sleep,
+                Err(e) => {
+                    warn!(target: "bor::producer", error = %e, "failed to start block production");
+                    self.poll_interval
+                }
+            };
+            tokio::time::sleep(sleep).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::MainnetBorHardforks;
+    use bor_primitives::Validator;
+    use std::sync::Mutex;
+
+    fn addr(b: u8) -> Address {
+        Address::new([b; 20])
+    }
+
+    fn validator_set(count: u8) -> ValidatorSet {
+        let validators: Vec<Validator> = (1..=count)
+            .map(|b| Validator {
+                id: b as u64,
+                address: addr(b),
+                voting_power: 100,
+                signer: addr(b),
+                proposer_priority: 0,
+            })
+            .collect();
+        ValidatorSet { proposer: validators.first().cloned(), validators }
+    }
+
+    struct TestSource {
+        parent: ParentBlock,
+        signer: Address,
+    }
+
+    impl ProductionSource for TestSource {
+        fn parent(&self) -> Option<ParentBlock> {
+            Some(self.parent)
+        }
+
+        fn validator_set(&self, _number: u64) -> Option<ValidatorSet> {
+            Some(validator_set(3))
+        }
+
+        fn signer(&self) -> Option<Address> {
+            Some(self.signer)
+        }
+
+        fn chain_spec(&self) -> &dyn BorHardforks {
+            &MainnetBorHardforks
+        }
+    }
+
+    /// Records the slots built, and those prepared.
+    #[derive(Default)]
+    struct RecordingTrigger(Mutex<Vec<Slot>>, Mutex<Vec<Slot>>);
+
+    impl PayloadTrigger for &RecordingTrigger {
+        async fn build_payload(&self, slot: Slot) -> eyre::Result<()> {
+            self.0.lock().unwrap().push(slot);
+            Ok(())
+        }
+
+        async fn prepare(&self, slot: Slot) -> eyre::Result<()> {
+            self.1.lock().unwrap().push(slot);
+            Ok(())
+        }
+    }
+
+    fn scheduler(
+        signer: u8,
+        trigger: &RecordingTrigger,
+    ) -> ProducerScheduler<TestSource, &RecordingTrigger> {
+        let parent = ParentBlock {
+            number: 64,
+            hash: B256::with_last_byte(64),
+            timestamp: 1_000,
+            gas_limit: 30_000_000,
+        };
+        ProducerScheduler::new(TestSource { parent, signer: addr(signer) }, trigger)
+    }
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_slot_for_proposer_and_backup() {
+        let trigger = RecordingTrigger::default();
+
+        let slot = scheduler(1, &trigger).next_slot().unwrap();
+        assert!(slot.is_inturn());
+        assert_eq!(slot.number, 65);
+        assert_eq!(slot.timestamp, 1_002);
+        assert_eq!(slot.difficulty, U256::from(3));
+
+        let backup = scheduler(3, &trigger).next_slot().unwrap();
+        assert_eq!(backup.succession, 2);
+        assert_eq!(backup.timestamp, 1_006);
+        assert_eq!(backup.difficulty, U256::from(1));
+
+        assert_eq!(scheduler(9, &trigger).next_slot(), None);
+    }
+
+    #[test]
+    fn test_wait_includes_wiggle_for_backups() {
+        let trigger = RecordingTrigger::default();
+        let scheduler = scheduler(3, &trigger).with_wiggle(Duration::from_millis(300));
+        let slot = scheduler.next_slot().unwrap();
+
+        assert_eq!(scheduler.wait_for(&slot, secs(1_000)), Duration::from_millis(6_600));
+        assert_eq!(scheduler.wait_for(&slot, secs(1_010)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_lagging_clock_waits_only_protocol_delay() {
+        let trigger = RecordingTrigger::default();
+        let scheduler = scheduler(1, &trigger);
+        let slot = scheduler.next_slot().unwrap();
+
+        // Within the tolerated skew the parent timestamp is trusted.
+        assert_eq!(scheduler.wait_for(&slot, secs(999)), secs(3));
+        // Far behind: wait the block period from now instead of stalling.
+        assert_eq!(scheduler.wait_for(&slot, secs(900)), secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_builds_once_per_parent_when_due() {
+        let trigger = RecordingTrigger::default();
+        let mut scheduler =
+            scheduler(1, &trigger).with_poll_interval(Duration::from_millis(100));
+
+        assert_eq!(scheduler.step(secs(1_000)).await.unwrap(), Duration::from_millis(100));
+        assert!(trigger.0.lock().unwrap().is_empty());
+
+        scheduler.step(secs(1_002)).await.unwrap();
+        scheduler.step(secs(1_003)).await.unwrap();
+        let built = trigger.0.lock().unwrap();
+        assert_eq!(built.len(), 1);
+        assert_eq!(built[0].parent_hash, B256::with_last_byte(64));
+    }
+
+    #[tokio::test]
+    async fn test_prepares_once_per_parent_before_due() {
+        let trigger = RecordingTrigger::default();
+        let mut scheduler = scheduler(1, &trigger);
+
+        scheduler.step(secs(1_000)).await.unwrap();
+        scheduler.step(secs(1_001)).await.unwrap();
+        assert_eq!(trigger.1.lock().unwrap().len(), 1);
+        assert!(trigger.0.lock().unwrap().is_empty());
+
+        // A slot due at once is built without being prepared first.
+        let trigger = RecordingTrigger::default();
+        scheduler(1, &trigger).step(secs(1_002)).await.unwrap();
+        assert!(trigger.1.lock().unwrap().is_empty());
+        assert_eq!(trigger.0.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_halt_skips_slots_until_resumed() {
+        let trigger = RecordingTrigger::default();
+        let halt = ProductionHalt::new();
+        let mut scheduler = scheduler(1, &trigger).with_halt(halt.clone());
+
+        halt.halt("state sync mismatch");
+        scheduler.step(secs(1_002)).await.unwrap();
+        assert!(trigger.0.lock().unwrap().is_empty());
+        assert!(trigger.1.lock().unwrap().is_empty());
+
+        assert_eq!(halt.resume().as_deref(), Some("state sync mismatch"));
+        assert!(!halt.is_halted());
+        scheduler.step(secs(1_002)).await.unwrap();
+        assert_eq!(trigger.0.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,400 @@
+//! Dry runs of block production.
+//!
+//! Before their sprint, validator operators want to know that the node would
+//! actually produce a valid block: that the signer is in the validator set,
+//! when its slot comes, and which extra data and system calls the block gets.
+//! [`ProposalSimulator`] backs `bor_simulateProposal` with the same sources the
+//! [`ProducerScheduler`](crate::ProducerScheduler) plans from, and has a
+//! [`ProposalExecutor`] execute the block on the state of the head, without sealing or
+//! broadcasting it.
+
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
+use bor_chainspec::{BorHardforks, ScheduleError};
+use bor_consensus::succession::{earliest_block_time, succession_number};
+use bor_evm::PendingStateOverlay;
+use bor_payload::{gas_limit_after, BorPayloadBuilder, PayloadConfig, PayloadTx};
+use bor_primitives::{encode_validator_bytes, ValidatorSet};
+use bor_rpc::{BorRpcError, SimulatedProposalResponse, SimulatedSystemCall};
+use heimdall_client::SharedSpanCache;
+
+use crate::error::BorError;
+use crate::producer::ProductionSource;
+
+/// The header fields of a simulated block, everything but what executing it gives.
+///
+/// The block executes on the head's state. If blocks come between, its parent is the head
+/// moved up to the height below it with `parent_timestamp` and `parent_gas_limit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalBlock {
+    /// Hash of the head the block is simulated on.
+    pub head: B256,
+    /// Timestamp of the block's parent.
+    pub parent_timestamp: u64,
+    /// Gas limit of the block's parent.
+    pub parent_gas_limit: u64,
+    /// Block number.
+    pub number: u64,
+    /// Block timestamp.
+    pub timestamp: u64,
+    /// Producer the block is sealed for; fees are paid to it.
+    pub producer: Address,
+    /// Difficulty of the block.
+    pub difficulty: U256,
+    /// Block gas limit.
+    pub gas_limit: u64,
+    /// Header extra data, with the seal left zeroed.
+    pub extra_data: Bytes,
+}
+
+/// What executing a simulated block gives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedProposal {
+    /// Gas used by the block, its system calls included.
+    pub gas_used: u64,
+    /// State root after the block.
+    pub state_root: B256,
+    /// Number of user transactions the block included.
+    pub transaction_count: usize,
+}
+
+/// Executes simulated blocks.
+pub trait ProposalExecutor: Send + Sync {
+    /// Execute `block` with its system calls on the state of its head, including the
+    /// `transactions` that fit its gas limit and execute, in order.
+    ///
+    /// Blocks between the head and `block` are taken to be empty.
+    fn execute(
+        &self,
+        block: &ProposalBlock,
+        transactions: Vec<PayloadTx>,
+    ) -> Result<ExecutedProposal, BorError>;
+}
+
+/// Builds the block this node would produce at a given height.
+#[derive(Debug, Clone)]
+pub struct ProposalSimulator<S, E> {
+    /// Chain head, validator sets and signer.
+    source: S,
+    /// Executes the simulated blocks.
+    executor: E,
+    /// Spans committed at span starts.
+    spans: SharedSpanCache,
+    /// State sync events expected at the next sprint start.
+    pending: PendingStateOverlay,
+    /// Gas limit produced blocks move toward, the chain's block gas limit if unset.
+    gas_limit_target: Option<u64>,
+}
+
+impl<S: ProductionSource, E: ProposalExecutor> ProposalSimulator<S, E> {
+    /// Create a simulator over the node's production source, span cache and pending events,
+    /// executing blocks with `executor`.
+    pub fn new(
+        source: S,
+        executor: E,
+        spans: SharedSpanCache,
+        pending: PendingStateOverlay,
+    ) -> Self {
+        Self { source, executor, spans, pending, gas_limit_target: None }
+    }
+
+    /// Move the gas limit of simulated blocks toward `target` (`--miner.gaslimit`) instead
+    /// of the chain's block gas limit.
+    pub fn with_gas_limit_target(mut self, target: Option<u64>) -> Self {
+        self.gas_limit_target = target;
+        self
+    }
+
+    /// Simulate block `number` with `user_txs`, which must come after the current head.
+    ///
+    /// Blocks between the head and `number` are assumed to be empty and to arrive one block
+    /// period apart.
+    pub fn simulate(
+        &self,
+        number: u64,
+        user_txs: Vec<PayloadTx>,
+    ) -> Result<SimulatedProposalResponse, BorError> {
+        let signer = self
+            .source
+            .signer()
+            .ok_or_else(|| BorRpcError::InvalidParams("no signer configured".into()))?;
+        let head = self
+            .source
+            .parent()
+            .ok_or_else(|| BorRpcError::InvalidParams("no chain head yet".into()))?;
+        if number <= head.number {
+            return Err(BorRpcError::InvalidParams(format!(
+                "block {number} is not after the head {}",
+                head.number
+            ))
+            .into());
+        }
+
+        let validator_set = self.validator_set(number)?;
+        let succession = succession_number(&validator_set, &signer).map_err(|e| {
+            BorRpcError::InvalidParams(format!("{signer} cannot produce block {number}: {e}"))
+        })?;
+        let chain_spec = self.source.chain_spec();
+        let parent_timestamp =
+            head.timestamp + (number - head.number - 1) * chain_spec.bor_period(number);
+        let timestamp = earliest_block_time(chain_spec, parent_timestamp, number, succession)
+            .map_err(|e| BorRpcError::InvalidParams(e.to_string()))?;
+
+        let schedule = |e: ScheduleError| BorRpcError::InvalidParams(e.to_string());
+        let sprint_size = chain_spec.try_bor_sprint_size(number).map_err(schedule)?;
+        let span_size = chain_spec.bor_span_size(number);
+        let gas_limit_at = |number| {
+            gas_limit_after(chain_spec, head.number, head.gas_limit, number, self.gas_limit_target)
+        };
+        let gas_limit = gas_limit_at(number);
+        let mut config = PayloadConfig {
+            block_number: number,
+            gas_limit,
+            sprint_size,
+            span_size,
+            producer: signer,
+            timestamp,
+            has_pending_span: false,
+            pending_span_id: None,
+            pending_validator_bytes: None,
+            pending_state_sync_events: self
+                .pending
+                .pending()
+                .filter(|pending| pending.block_number == number)
+                .map(|pending| pending.events())
+                .unwrap_or_default(),
+        };
+        let span_id = chain_spec.bor_span_id(number);
+        if chain_spec.bor_span_start(span_id) == number {
+            let mut spans = self.spans.lock().expect("span cache lock poisoned");
+            let span = spans.get(span_id).ok_or_else(|| {
+                BorRpcError::Heimdall(format!("span {span_id} committed at {number} not cached"))
+            })?;
+            config.has_pending_span = true;
+            config.pending_span_id = Some(U256::from(span.id));
+            config.pending_validator_bytes =
+                Some(encode_validator_bytes(&span.validator_set.validators).into());
+        }
+
+        // The last block of a sprint announces the validators of the next one.
+        let next_validators = if chain_spec.is_bor_sprint_end(number).map_err(schedule)? {
+            Some(encode_validator_bytes(&self.validator_set(number + 1)?.validators))
+        } else {
+            None
+        };
+        let extra_data =
+            BorPayloadBuilder::build_extra_data(&config, &[], next_validators.as_deref())
+                .map_err(|e| BorRpcError::ExtraDataError(e.to_string()))?;
+
+        let difficulty = U256::from(validator_set.validators.len() - succession);
+        let block = ProposalBlock {
+            head: head.hash,
+            parent_timestamp,
+            parent_gas_limit: gas_limit_at(number - 1),
+            number,
+            timestamp,
+            producer: signer,
+            difficulty,
+            gas_limit,
+            extra_data,
+        };
+        let executed = self.executor.execute(&block, user_txs)?;
+        let system_calls = BorPayloadBuilder::build(&config, Vec::new()).system_calls;
+        Ok(SimulatedProposalResponse {
+            number: U64::from(number),
+            parent_hash: head.hash,
+            timestamp: U64::from(timestamp),
+            difficulty,
+            gas_limit: U64::from(gas_limit),
+            gas_used: U64::from(executed.gas_used),
+            state_root: executed.state_root,
+            extra_data: block.extra_data,
+            signer,
+            succession: U64::from(succession),
+            transaction_count: U64::from(executed.transaction_count),
+            system_calls: system_calls
+                .into_iter()
+                .map(|call| SimulatedSystemCall { to: call.to, data: call.data })
+                .collect(),
+        })
+    }
+
+    fn validator_set(&self, number: u64) -> Result<ValidatorSet, BorRpcError> {
+        self.source.validator_set(number).ok_or_else(|| {
+            BorRpcError::Heimdall(format!("no span cached for block {number}"))
+        })
+    }
+}
+
+/// Selected user transaction, EIP-2718 `encoded`, with `gas_limit` reserved and
+/// `effective_tip` per gas.
+pub fn simulated_tx(encoded: Bytes, gas_limit: u64, effective_tip: u128) -> PayloadTx {
+    PayloadTx { data: encoded, gas_used: gas_limit, is_system_tx: false, effective_tip }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::producer::ParentBlock;
+    use bor_chainspec::MainnetBorHardforks;
+    use bor_evm::PendingStateSyncs;
+    use bor_primitives::Validator;
+    use heimdall_client::{SpanCache, StateSyncEvent};
+    use std::sync::{Arc, Mutex};
+
+    fn addr(b: u8) -> Address {
+        Address::new([b; 20])
+    }
+
+    fn validator_set(count: u8) -> ValidatorSet {
+        let validators: Vec<Validator> = (1..=count)
+            .map(|b| Validator {
+                id: b as u64,
+                address: addr(b),
+                voting_power: 100,
+                signer: addr(b),
+                proposer_priority: 0,
+            })
+            .collect();
+        ValidatorSet { proposer: validators.first().cloned(), validators }
+    }
+
+    struct TestSource {
+        signer: Option<Address>,
+    }
+
+    impl ProductionSource for TestSource {
+        fn parent(&self) -> Option<ParentBlock> {
+            Some(ParentBlock {
+                number: 100,
+                hash: B256::with_last_byte(100),
+                timestamp: 1_000,
+                gas_limit: 30_000_000,
+            })
+        }
+
+        fn validator_set(&self, _number: u64) -> Option<ValidatorSet> {
+            Some(validator_set(3))
+        }
+
+        fn signer(&self) -> Option<Address> {
+            self.signer
+        }
+
+        fn chain_spec(&self) -> &dyn BorHardforks {
+            &MainnetBorHardforks
+        }
+    }
+
+    /// Executes every transaction, each using the gas it reserves, and records the blocks.
+    #[derive(Default)]
+    struct TestExecutor {
+        blocks: Mutex<Vec<ProposalBlock>>,
+    }
+
+    impl ProposalExecutor for TestExecutor {
+        fn execute(
+            &self,
+            block: &ProposalBlock,
+            transactions: Vec<PayloadTx>,
+        ) -> Result<ExecutedProposal, BorError> {
+            self.blocks.lock().unwrap().push(block.clone());
+            Ok(ExecutedProposal {
+                gas_used: transactions.iter().map(|tx| tx.gas_used).sum(),
+                state_root: B256::with_last_byte(block.number as u8),
+                transaction_count: transactions.len(),
+            })
+        }
+    }
+
+    fn simulator(signer: Option<Address>) -> ProposalSimulator<TestSource, TestExecutor> {
+        ProposalSimulator::new(
+            TestSource { signer },
+            TestExecutor::default(),
+            Arc::new(Mutex::new(SpanCache::new(4))),
+            PendingStateOverlay::new(),
+        )
+    }
+
+    #[test]
+    fn test_simulates_next_block() {
+        let simulator = simulator(Some(addr(2)));
+        let txs =
+            vec![simulated_tx(Bytes::new(), 21_000, 1), simulated_tx(Bytes::new(), 50_000, 2)];
+
+        let proposal = simulator.simulate(101, txs).unwrap();
+        assert_eq!(proposal.parent_hash, B256::with_last_byte(100));
+        assert_eq!(proposal.succession, U64::from(1));
+        assert_eq!(proposal.difficulty, U256::from(2));
+        // Block period plus one backup multiplier.
+        assert_eq!(proposal.timestamp, U64::from(1_004));
+        assert_eq!(proposal.gas_used, U64::from(71_000));
+        assert_eq!(proposal.state_root, B256::with_last_byte(101));
+        assert_eq!(proposal.transaction_count, U64::from(2));
+        assert!(proposal.system_calls.is_empty());
+        assert_eq!(proposal.extra_data.len(), 32 + 65);
+
+        // The block is executed on the head with the fields the response reports.
+        let blocks = simulator.executor.blocks.lock().unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].head, B256::with_last_byte(100));
+        assert_eq!((blocks[0].parent_timestamp, blocks[0].parent_gas_limit), (1_000, 30_000_000));
+        assert_eq!(blocks[0].producer, addr(2));
+        assert_eq!(blocks[0].timestamp, 1_004);
+        assert_eq!(blocks[0].extra_data, proposal.extra_data);
+    }
+
+    #[test]
+    fn test_sprint_start_includes_state_syncs() {
+        let simulator = simulator(Some(addr(1)));
+        let sprint_start = 128;
+        simulator.pending.update(PendingStateSyncs {
+            block_number: sprint_start,
+            records: vec![StateSyncEvent {
+                id: 7,
+                contract: addr(9),
+                data: Bytes::from_static(b"event"),
+                tx_hash: B256::ZERO,
+                log_index: 0,
+                bor_chain_id: "137".to_string(),
+                time: 900,
+            }],
+        });
+
+        let proposal = simulator.simulate(sprint_start, Vec::new()).unwrap();
+        assert_eq!(proposal.system_calls.len(), 1);
+        // 27 blocks after the head, one block period apart, then the producer delay.
+        assert_eq!(proposal.timestamp, U64::from(1_000 + 27 * 2 + 6));
+
+        // Sprint ends announce the next validators.
+        let sprint_end = simulator.simulate(sprint_start - 1, Vec::new()).unwrap();
+        assert_eq!(sprint_end.extra_data.len(), 32 + 3 * 20 + 65);
+    }
+
+    #[test]
+    fn test_gas_limit_moves_toward_target() {
+        let simulator = simulator(Some(addr(2)));
+        let proposal = simulator.simulate(101, Vec::new()).unwrap();
+        assert_eq!(proposal.gas_limit, U64::from(30_000_000));
+
+        let simulator = simulator.with_gas_limit_target(Some(45_000_000));
+        let step = 30_000_000 / 1024 - 1;
+        let proposal = simulator.simulate(101, Vec::new()).unwrap();
+        assert_eq!(proposal.gas_limit, U64::from(30_000_000 + step));
+        // One step per block after the head.
+        let proposal = simulator.simulate(102, Vec::new()).unwrap();
+        assert!(proposal.gas_limit > U64::from(30_000_000 + step));
+    }
+
+    #[test]
+    fn test_rejects_missing_signer_and_past_blocks() {
+        assert!(matches!(
+            simulator(None).simulate(101, Vec::new()),
+            Err(BorError::Rpc(BorRpcError::InvalidParams(_)))
+        ));
+        let simulator = simulator(Some(addr(1)));
+        assert!(simulator.simulate(100, Vec::new()).is_err());
+        assert!(self::simulator(Some(addr(9))).simulate(101, Vec::new()).is_err());
+        // Nothing is executed for a block that cannot be produced.
+        assert!(simulator.executor.blocks.lock().unwrap().is_empty());
+    }
+}
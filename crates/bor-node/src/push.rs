@@ -0,0 +1,333 @@
+//! Push notifications from Heimdall.
+//!
+//! The node learns about new spans, milestones, checkpoints and state sync events by
+//! polling Heimdall, so a validator sees them up to a poll interval late. With
+//! `--bor.heimdall-push`, [`PushListener`] accepts HTTP `POST`s from Heimdall or a sidecar
+//! watching it, with a JSON body such as `{"type": "milestone"}` (or an array of them),
+//! and wakes the pollers of that kind of data right away.
+//!
+//! A notification carries no data: the woken poller fetches it from Heimdall as usual.
+//! A forged notification therefore costs one extra request and nothing more, and without
+//! the listener the pollers keep their intervals.
+
+use bor_primitives::Span;
+use heimdall_client::{HeimdallClient, SharedSpanCache};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+
+/// Largest notification body accepted.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Largest request line or header accepted.
+const MAX_LINE_SIZE: usize = 8 * 1024;
+
+/// Time a client gets to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kind of Heimdall data a notification announces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HeimdallTopic {
+    /// A new span was proposed.
+    Span,
+    /// A new milestone was agreed on.
+    Milestone,
+    /// A new checkpoint was submitted.
+    Checkpoint,
+    /// New state sync events were recorded.
+    StateSync,
+}
+
+/// Body of a push request: one notification or several.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PushBody {
+    One(HeimdallTopic),
+    Many(Vec<HeimdallTopic>),
+}
+
+#[derive(Debug, Default)]
+struct Topics {
+    span: Notify,
+    milestone: Notify,
+    checkpoint: Notify,
+    state_sync: Notify,
+}
+
+/// Wakes the pollers of the topics Heimdall pushed notifications for.
+///
+/// Clones share the same notifications. A notification nobody waits for is kept until
+/// the next wait on its topic, so none is lost between two polls.
+#[derive(Debug, Clone, Default)]
+pub struct HeimdallPush {
+    topics: Arc<Topics>,
+}
+
+impl HeimdallPush {
+    /// Create a handle with no notification pending.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Announce new data of `topic`.
+    pub fn notify(&self, topic: HeimdallTopic) {
+        self.topic(topic).notify_one();
+    }
+
+    /// Wait for the next notification of `topic`.
+    pub async fn notified(&self, topic: HeimdallTopic) {
+        self.topic(topic).notified().await
+    }
+
+    /// Wait up to `timeout` for a notification of any of `topics`.
+    ///
+    /// Returns the topic notified, or `None` once `timeout` passed. Pollers call it in
+    /// place of sleeping for their interval.
+    pub async fn wait(&self, topics: &[HeimdallTopic], timeout: Duration) -> Option<HeimdallTopic> {
+        if topics.is_empty() {
+            tokio::time::sleep(timeout).await;
+            return None;
+        }
+        let notified = topics.iter().map(|&topic| {
+            Box::pin(async move {
+                self.notified(topic).await;
+                topic
+            })
+        });
+        let (topic, _, _) =
+            tokio::time::timeout(timeout, futures::future::select_all(notified)).await.ok()?;
+        Some(topic)
+    }
+
+    fn topic(&self, topic: HeimdallTopic) -> &Notify {
+        match topic {
+            HeimdallTopic::Span => &self.topics.span,
+            HeimdallTopic::Milestone => &self.topics.milestone,
+            HeimdallTopic::Checkpoint => &self.topics.checkpoint,
+            HeimdallTopic::StateSync => &self.topics.state_sync,
+        }
+    }
+}
+
+/// HTTP endpoint Heimdall pushes notifications to.
+#[derive(Debug)]
+pub struct PushListener {
+    listener: TcpListener,
+    push: HeimdallPush,
+}
+
+impl PushListener {
+    /// Listen on `addr`, forwarding notifications to `push`.
+    pub async fn bind(addr: SocketAddr, push: HeimdallPush) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self { listener, push })
+    }
+
+    /// The address the listener is bound to.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept notifications until the task is dropped.
+    pub async fn run(self) {
+        let addr = self.listener.local_addr().ok();
+        info!(target: "bor::push", ?addr, "heimdall push listener started");
+        loop {
+            let (stream, peer) = match self.listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    warn!(target: "bor::push", %err, "failed to accept push connection");
+                    continue;
+                }
+            };
+            let push = self.push.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(REQUEST_TIMEOUT, handle(stream, &push)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => debug!(target: "bor::push", %peer, %err, "push request failed"),
+                    Err(_) => debug!(target: "bor::push", %peer, "push request timed out"),
+                }
+            });
+        }
+    }
+}
+
+/// Serve one request on `stream`.
+async fn handle(stream: TcpStream, push: &HeimdallPush) -> std::io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let status = match read_request(&mut stream).await? {
+        Ok(body) => match serde_json::from_slice::<PushBody>(&body) {
+            Ok(PushBody::One(topic)) => {
+                notify_all(push, &[topic]);
+                "204 No Content"
+            }
+            Ok(PushBody::Many(topics)) => {
+                notify_all(push, &topics);
+                "204 No Content"
+            }
+            Err(_) => "400 Bad Request",
+        },
+        Err(status) => status,
+    };
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    stream.get_mut().write_all(response.as_bytes()).await?;
+    stream.get_mut().shutdown().await
+}
+
+fn notify_all(push: &HeimdallPush, topics: &[HeimdallTopic]) {
+    for &topic in topics {
+        debug!(target: "bor::push", ?topic, "heimdall notification");
+        push.notify(topic);
+    }
+}
+
+/// Read a `POST` request and return its body, or the status to reject it with.
+async fn read_request(
+    stream: &mut BufReader<TcpStream>,
+) -> std::io::Result<Result<Vec<u8>, &'static str>> {
+    let mut line = String::new();
+    read_line(stream, &mut line).await?;
+    let Some(method) = line.split_whitespace().next() else {
+        return Ok(Err("400 Bad Request"));
+    };
+    let is_post = method == "POST";
+
+    let mut content_length = None;
+    loop {
+        line.clear();
+        read_line(stream, &mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    if !is_post {
+        return Ok(Err("405 Method Not Allowed"));
+    }
+    let Some(length) = content_length else { return Ok(Err("411 Length Required")) };
+    if length > MAX_BODY_SIZE {
+        return Ok(Err("413 Payload Too Large"));
+    }
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await?;
+    Ok(Ok(body))
+}
+
+/// Read one line of at most [`MAX_LINE_SIZE`] bytes into `line`.
+async fn read_line(stream: &mut BufReader<TcpStream>, line: &mut String) -> std::io::Result<()> {
+    let read = (&mut *stream).take(MAX_LINE_SIZE as u64).read_line(line).await?;
+    if read == 0 || !line.ends_with('\n') {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated request"));
+    }
+    Ok(())
+}
+
+/// Cache the latest span each time Heimdall announces one, so that consensus has it
+/// before the first block of the span arrives.
+pub async fn prefetch_pushed_spans<C: HeimdallClient>(
+    push: HeimdallPush,
+    heimdall: C,
+    spans: SharedSpanCache,
+) {
+    loop {
+        push.notified(HeimdallTopic::Span).await;
+        match heimdall.fetch_latest_span().await {
+            Ok(span) => cache_span(&spans, span),
+            Err(err) => warn!(target: "bor::push", %err, "failed to fetch pushed span"),
+        }
+    }
+}
+
+fn cache_span(spans: &SharedSpanCache, span: Span) {
+    let mut cache = spans.lock().expect("span cache lock poisoned");
+    if !cache.contains(span.id) {
+        debug!(target: "bor::push", span_id = span.id, "cached pushed span");
+        cache.insert(span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn post(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    fn request(body: &str) -> String {
+        format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}", body.len())
+    }
+
+    async fn listener() -> (SocketAddr, HeimdallPush) {
+        let push = HeimdallPush::new();
+        let listener = PushListener::bind("127.0.0.1:0".parse().unwrap(), push.clone())
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(listener.run());
+        (addr, push)
+    }
+
+    #[tokio::test]
+    async fn test_push_wakes_waiters() {
+        let (addr, push) = listener().await;
+
+        let response = post(addr, &request(r#"{"type":"milestone","id":"ms-9"}"#)).await;
+        assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+        let topics = [HeimdallTopic::Milestone, HeimdallTopic::Checkpoint];
+        let woken = push.wait(&topics, Duration::from_secs(5)).await;
+        assert_eq!(woken, Some(HeimdallTopic::Milestone));
+
+        let response = post(addr, &request(r#"[{"type":"span"},{"type":"stateSync"}]"#)).await;
+        assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+        assert_eq!(
+            push.wait(&[HeimdallTopic::StateSync], Duration::from_secs(5)).await,
+            Some(HeimdallTopic::StateSync)
+        );
+        assert_eq!(
+            push.wait(&[HeimdallTopic::Span], Duration::from_secs(5)).await,
+            Some(HeimdallTopic::Span)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_requests() {
+        let (addr, push) = listener().await;
+
+        let response = post(addr, &request(r#"{"type":"block"}"#)).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+        let response = post(addr, "GET / HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405"), "{response}");
+        let response = post(addr, "POST / HTTP/1.1\r\nContent-Length: 100000\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+        let topics = [HeimdallTopic::Span, HeimdallTopic::Milestone];
+        assert_eq!(push.wait(&topics, Duration::from_millis(50)).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_notification_waits_for_poller() {
+        let push = HeimdallPush::new();
+        push.notify(HeimdallTopic::Checkpoint);
+        // Sent before anyone waited, still seen once.
+        let topics = [HeimdallTopic::Checkpoint];
+        assert!(push.wait(&topics, Duration::from_millis(50)).await.is_some());
+        assert!(push.wait(&topics, Duration::from_millis(50)).await.is_none());
+    }
+}
@@ -0,0 +1,495 @@
+//! Manual recovery from bad Heimdall data.
+//!
+//! When Heimdall served a wrong span or wrong state sync events, the node keeps
+//! using its cached copies until they expire or the node restarts. The
+//! `bor_resyncSpan` and `bor_refetchStateSyncEvents` admin methods fetch them
+//! again through [`BorResync`] and replace those copies.
+//!
+//! The same handle replays the [`HeimdallJournal`]: spans that were missing while
+//! Heimdall was unreachable are fetched once it is back, and a warning is logged
+//! for as long as any remain missing.
+//!
+//! With a second Heimdall endpoint to cross-check against, refetched state sync
+//! events are only applied if both endpoints serve the same ones; otherwise block
+//! production is halted until an operator has looked into it.
+//!
+//! After a restart, the events of a sprint start whose system calls the previous run
+//! was killed in are fetched again from its [`SprintMarker`], since the block is
+//! executed again before the chain moves on to prefetch them.
+
+use crate::producer::ProductionHalt;
+use bor_evm::{
+    fetch_cross_checked_state_syncs, fetch_pending_state_syncs, PendingStateOverlay,
+    PendingStateSyncs, StateSyncCrossCheckError,
+};
+use bor_primitives::Span;
+use bor_rpc::{BorRecoveryApi, BorRpcError};
+use bor_storage::SprintMarker;
+use heimdall_client::{
+    HeimdallClient, HeimdallConfig, HeimdallJournal, SharedHeimdallJournal, SharedSpanCache,
+};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How often [`BorResync::run_journal_replay`] retries the spans in the journal.
+pub const JOURNAL_REPLAY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Invalidates Heimdall-derived data and refetches it.
+#[derive(Debug, Clone)]
+pub struct BorResync<C> {
+    /// Client the data is refetched from.
+    heimdall: C,
+    /// The span cache consensus validates signers against.
+    spans: SharedSpanCache,
+    /// State sync events pending for the next sprint start.
+    pending: PendingStateOverlay,
+    /// Paging of refetched state sync events.
+    config: HeimdallConfig,
+    /// Heimdall's ID of the chain, which refetched state sync events must target.
+    chain_id: String,
+    /// Spans missed while Heimdall was unreachable, if journaled.
+    journal: Option<SharedHeimdallJournal>,
+    /// Second endpoint state sync events must match, and the production halted if not.
+    cross_check: Option<(C, ProductionHalt)>,
+}
+
+impl<C: HeimdallClient> BorResync<C> {
+    /// Create a resync handle for chain `chain_id` over the node's span cache and pending
+    /// state overlay.
+    pub fn new(
+        heimdall: C,
+        chain_id: u64,
+        spans: SharedSpanCache,
+        pending: PendingStateOverlay,
+    ) -> Self {
+        Self {
+            heimdall,
+            spans,
+            pending,
+            config: HeimdallConfig::default(),
+            chain_id: chain_id.to_string(),
+            journal: None,
+            cross_check: None,
+        }
+    }
+
+    /// Page refetched state sync events as set by `config`.
+    pub fn with_config(mut self, config: HeimdallConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Resolve the spans of `journal` once they have been fetched again.
+    pub fn with_journal(mut self, journal: SharedHeimdallJournal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Only apply state sync events `secondary` serves as well, halting `halt` on a
+    /// mismatch.
+    pub fn with_cross_check(mut self, secondary: C, halt: ProductionHalt) -> Self {
+        self.cross_check = Some((secondary, halt));
+        self
+    }
+
+    /// Let block production run again after a cross-check mismatch, returning the reason
+    /// it was halted for.
+    pub fn resume_production(&self) -> Option<String> {
+        let reason = self.cross_check.as_ref().and_then(|(_, halt)| halt.resume())?;
+        warn!(target: "bor::resync", %reason, "block production resumed by operator");
+        Some(reason)
+    }
+
+    /// Fetch span `span_id` again and replace the cached copy with it.
+    ///
+    /// The cached copy is kept until the fetch succeeds: without any span for its
+    /// blocks, consensus could not check their signers at all.
+    pub async fn resync_span(&self, span_id: u64) -> Result<Span, BorRpcError> {
+        let span = self
+            .heimdall
+            .fetch_span(span_id)
+            .await
+            .map_err(|e| BorRpcError::Heimdall(format!("span {span_id}: {e}")))?;
+        if span.id != span_id {
+            return Err(BorRpcError::Heimdall(format!(
+                "requested span {span_id}, Heimdall returned span {}",
+                span.id
+            )));
+        }
+
+        let previous = self
+            .spans
+            .lock()
+            .expect("span cache lock poisoned")
+            .get(span_id)
+            .cloned();
+        info!(
+            target: "bor::resync",
+            span_id,
+            cached = previous.is_some(),
+            changed = previous.as_ref() != Some(&span),
+            "resynced span"
+        );
+        self.spans.lock().expect("span cache lock poisoned").insert(span.clone());
+        if let Some(journal) = &self.journal {
+            journal.resolve_span(span_id);
+        }
+        Ok(span)
+    }
+
+    /// Fetch every span the journal records as missing.
+    ///
+    /// Returns the number of spans still missing afterwards.
+    pub async fn replay_journal(&self) -> usize {
+        let Some(journal) = &self.journal else { return 0 };
+        for (span_id, miss) in journal.missed_spans() {
+            match self.resync_span(span_id).await {
+                Ok(_) => info!(
+                    target: "bor::resync",
+                    span_id,
+                    block = miss.block,
+                    misses = miss.misses,
+                    "recovered missed span"
+                ),
+                Err(err) => {
+                    // Heimdall is most likely still unreachable; later spans would fail too.
+                    warn!(target: "bor::resync", span_id, %err, "missed span still unavailable");
+                    break;
+                }
+            }
+        }
+        journal.missed_spans().len()
+    }
+
+    /// Replay the journal every `interval`, warning while spans remain missing.
+    pub async fn run_journal_replay(self, interval: Duration) {
+        loop {
+            let missing = self.replay_journal().await;
+            if missing > 0 {
+                let oldest = self
+                    .journal
+                    .as_deref()
+                    .map(HeimdallJournal::missed_spans)
+                    .and_then(|spans| spans.into_values().map(|m| m.first_missed).min());
+                warn!(
+                    target: "bor::resync",
+                    missing,
+                    oldest_unix = ?oldest,
+                    "node is unhealthy: spans missed while Heimdall was unreachable"
+                );
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Refetch the pending state sync events from ID `from_id` on.
+    ///
+    /// Events below `from_id` are kept; the refetched range ends at the last event that was
+    /// pending, so the overlay does not grow beyond the sprint it was built for. Returns the
+    /// number of events refetched.
+    ///
+    /// When cross-checking, a mismatch between the endpoints leaves the pending events as
+    /// they are and halts block production.
+    pub async fn refetch_state_sync_events(&self, from_id: u64) -> Result<usize, BorRpcError> {
+        if from_id == 0 {
+            return Err(BorRpcError::InvalidParams("state sync event IDs start at 1".into()));
+        }
+        let Some(pending) = self.pending.pending() else { return Ok(0) };
+        let Some(last_id) = pending.last_state_id().map(|id| id.to::<u64>()) else { return Ok(0) };
+        if last_id < from_id {
+            return Ok(0);
+        }
+
+        let block_number = pending.block_number;
+        let refetched = self
+            .fetch_state_syncs(block_number, from_id - 1, u64::MAX)
+            .await
+            .map_err(|e| BorRpcError::Heimdall(format!("state sync events from {from_id}: {e}")))?;
+
+        let mut records: Vec<_> =
+            pending.records.into_iter().filter(|record| record.id < from_id).collect();
+        let count = refetched.records.iter().take_while(|record| record.id <= last_id).count();
+        records.extend(refetched.records.into_iter().take(count));
+
+        info!(
+            target: "bor::resync",
+            from_id,
+            last_id,
+            count,
+            block = block_number,
+            "refetched state sync events"
+        );
+        self.pending.update(PendingStateSyncs { block_number, records });
+        Ok(count)
+    }
+
+    /// Fetch the events sprint start `block_number` relays into the pending overlay.
+    ///
+    /// Events follow `last_state_id`, the ID the state receiver committed last, and were
+    /// recorded before `to_time`; the sprint start commits those its timestamp allows.
+    /// Returns the number of events fetched.
+    pub async fn prefetch_state_syncs(
+        &self,
+        block_number: u64,
+        last_state_id: u64,
+        to_time: u64,
+    ) -> Result<usize, BorRpcError> {
+        let fetched =
+            self.fetch_state_syncs(block_number, last_state_id, to_time).await.map_err(|e| {
+                BorRpcError::Heimdall(format!("state sync events after {last_state_id}: {e}"))
+            })?;
+        let count = fetched.records.len();
+        debug!(
+            target: "bor::resync",
+            block = block_number,
+            last_state_id,
+            count,
+            "prefetched state sync events"
+        );
+        self.pending.update(fetched);
+        Ok(count)
+    }
+
+    /// Fetch the events of the first of the `rolled_back` sprint starts that relayed any
+    /// into the pending overlay again, so its block executes when it is imported again.
+    /// Returns the number of events fetched.
+    ///
+    /// Later sprint starts are prefetched as the chain reaches them.
+    pub async fn prefetch_rolled_back(
+        &self,
+        rolled_back: &[SprintMarker],
+        to_time: u64,
+    ) -> Result<usize, BorRpcError> {
+        let first = rolled_back
+            .iter()
+            .filter_map(|marker| Some((marker.number, marker.first_state_id?)))
+            .min();
+        let Some((block_number, first_state_id)) = first else { return Ok(0) };
+        self.prefetch_state_syncs(block_number, first_state_id.saturating_sub(1), to_time).await
+    }
+
+    /// Fetch events after `last_state_id`, cross-checked when configured.
+    async fn fetch_state_syncs(
+        &self,
+        block_number: u64,
+        last_state_id: u64,
+        to_time: u64,
+    ) -> Result<PendingStateSyncs, String> {
+        match &self.cross_check {
+            None => fetch_pending_state_syncs(
+                &self.heimdall,
+                &self.config,
+                block_number,
+                last_state_id,
+                to_time,
+                &self.chain_id,
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            Some((secondary, halt)) => fetch_cross_checked_state_syncs(
+                &self.heimdall,
+                secondary,
+                &self.config,
+                block_number,
+                last_state_id,
+                to_time,
+                &self.chain_id,
+            )
+            .await
+            .map_err(|e| {
+                if matches!(e, StateSyncCrossCheckError::Mismatch { .. }) {
+                    error!(target: "bor::resync", %e, "halting block production");
+                    halt.halt(e.to_string());
+                }
+                e.to_string()
+            }),
+        }
+    }
+}
+
+impl<C: HeimdallClient> BorRecoveryApi for BorResync<C> {
+    type Error = BorRpcError;
+
+    async fn bor_resync_span(&self, span_id: u64) -> Result<bool, Self::Error> {
+        self.resync_span(span_id).await.map(|_| true)
+    }
+
+    async fn bor_refetch_state_sync_events(&self, from_id: u64) -> Result<u64, Self::Error> {
+        self.refetch_state_sync_events(from_id).await.map(|count| count as u64)
+    }
+
+    fn bor_resume_production(&self) -> Option<String> {
+        self.resume_production()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, Bytes, B256, U256};
+    use bor_primitives::ValidatorSet;
+    use bor_storage::{SprintOutcome, SprintStage, SprintWal};
+    use heimdall_client::{MockHeimdallClient, SpanCache, StateSyncEvent};
+    use std::sync::{Arc, Mutex};
+
+    fn span(id: u64, chain_id: &str) -> Span {
+        Span {
+            id,
+            start_block: id * 6400,
+            end_block: (id + 1) * 6400 - 1,
+            validator_set: ValidatorSet { validators: vec![], proposer: None },
+            selected_producers: vec![],
+            bor_chain_id: chain_id.to_string(),
+        }
+    }
+
+    fn event(id: u64, data: u8) -> StateSyncEvent {
+        StateSyncEvent {
+            id,
+            contract: Address::ZERO,
+            data: Bytes::from(vec![data]),
+            tx_hash: B256::ZERO,
+            log_index: 0,
+            bor_chain_id: "137".to_string(),
+            time: id,
+        }
+    }
+
+    fn resync(client: MockHeimdallClient) -> BorResync<MockHeimdallClient> {
+        let spans = Arc::new(Mutex::new(SpanCache::new(4)));
+        BorResync::new(client, 137, spans, PendingStateOverlay::new())
+    }
+
+    #[tokio::test]
+    async fn test_resync_span_replaces_cached_copy() {
+        let resync = resync(MockHeimdallClient::new().with_span(3, span(3, "137")));
+        resync.spans.lock().unwrap().insert(span(3, "bad"));
+
+        let span = resync.resync_span(3).await.unwrap();
+        assert_eq!(span.bor_chain_id, "137");
+        assert_eq!(resync.spans.lock().unwrap().get(3).unwrap().bor_chain_id, "137");
+    }
+
+    #[tokio::test]
+    async fn test_resync_span_failure_keeps_cached_copy() {
+        let resync = resync(MockHeimdallClient::new());
+        resync.spans.lock().unwrap().insert(span(3, "stale"));
+
+        assert!(matches!(resync.resync_span(3).await, Err(BorRpcError::Heimdall(_))));
+        assert_eq!(resync.spans.lock().unwrap().get(3).unwrap().bor_chain_id, "stale");
+    }
+
+    #[tokio::test]
+    async fn test_replay_journal_resolves_fetched_spans() {
+        let journal = Arc::new(HeimdallJournal::new());
+        journal.record_span_miss(3, 19_200);
+        journal.record_span_miss(5, 32_000);
+        let resync = resync(MockHeimdallClient::new().with_span(3, span(3, "137")))
+            .with_journal(journal.clone());
+
+        // Span 5 is still unavailable.
+        assert_eq!(resync.replay_journal().await, 1);
+        assert!(resync.spans.lock().unwrap().contains(3));
+        assert_eq!(journal.missed_spans().keys().copied().collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[tokio::test]
+    async fn test_refetch_replaces_events_from_id() {
+        let client =
+            MockHeimdallClient::new().with_events((1..=6).map(|id| event(id, 0xaa)).collect());
+        let resync = resync(client);
+        resync.pending.update(PendingStateSyncs {
+            block_number: 32,
+            records: (1..=4).map(|id| event(id, 0xbb)).collect(),
+        });
+
+        assert_eq!(resync.refetch_state_sync_events(3).await.unwrap(), 2);
+        let pending = resync.pending.pending().unwrap();
+        let data: Vec<u8> = pending.records.iter().map(|record| record.data[0]).collect();
+        assert_eq!(data, vec![0xbb, 0xbb, 0xaa, 0xaa]);
+        assert_eq!(pending.block_number, 32);
+
+        // Nothing pending at or after the requested ID.
+        assert_eq!(resync.refetch_state_sync_events(5).await.unwrap(), 0);
+        assert!(resync.refetch_state_sync_events(0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_selects_events_of_chain() {
+        let mut events: Vec<_> = (3..=6).map(|id| event(id, 0xaa)).collect();
+        events[2].bor_chain_id = "80002".to_string();
+        let resync = resync(MockHeimdallClient::new().with_events(events));
+
+        // Event 5 targets another chain, so it and everything after it wait.
+        assert_eq!(resync.prefetch_state_syncs(48, 2, 100).await.unwrap(), 2);
+        let pending = resync.pending.pending().unwrap();
+        assert_eq!(pending.block_number, 48);
+        assert_eq!(pending.records.iter().map(|record| record.id).collect::<Vec<_>>(), [3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_kill_during_sprint_refetches_its_events() {
+        let path = std::env::temp_dir()
+            .join(format!("bor-resync-sprint-wal-{}.json", std::process::id()));
+        let marker = |number, first_state_id| SprintMarker {
+            number,
+            parent_hash: B256::with_last_byte(number as u8 - 1),
+            span_id: None,
+            first_state_id,
+            last_state_id: first_state_id,
+            stage: SprintStage::Executing,
+        };
+        let wal = SprintWal::open(&path).unwrap();
+        wal.begin(marker(64, Some(5))).unwrap();
+        wal.begin(marker(48, Some(3))).unwrap();
+        // Killed before either block's system calls finished.
+        drop(wal);
+
+        let restarted = SprintWal::open(&path).unwrap();
+        let rolled_back: Vec<_> = restarted
+            .recover(|_| None)
+            .into_iter()
+            .filter(|(_, outcome)| *outcome == SprintOutcome::RolledBack)
+            .map(|(marker, _)| marker)
+            .collect();
+        assert_eq!(rolled_back.len(), 2);
+
+        let events = (3..=6).map(|id| event(id, 0xaa)).collect();
+        let resync = resync(MockHeimdallClient::new().with_events(events));
+        assert_eq!(resync.prefetch_rolled_back(&rolled_back, 100).await.unwrap(), 4);
+        let pending = resync.pending.pending().unwrap();
+        assert_eq!(pending.block_number, 48);
+        assert_eq!(pending.records.first().map(|record| record.id), Some(3));
+
+        // Sprint starts without events need nothing fetched.
+        let resync = self::resync(MockHeimdallClient::new());
+        assert_eq!(resync.prefetch_rolled_back(&[marker(80, None)], 100).await.unwrap(), 0);
+        assert!(resync.pending.pending().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_mismatch_halts_production() {
+        let client =
+            MockHeimdallClient::new().with_events((1..=4).map(|id| event(id, 0xaa)).collect());
+        let forged = (1..=4).map(|id| event(id, if id == 4 { 0xff } else { 0xaa })).collect();
+        let halt = ProductionHalt::new();
+        let resync = resync(client.clone())
+            .with_cross_check(MockHeimdallClient::new().with_events(forged), halt.clone());
+        let before = PendingStateSyncs {
+            block_number: 32,
+            records: (1..=4).map(|id| event(id, 0xbb)).collect(),
+        };
+        resync.pending.update(before.clone());
+
+        assert!(matches!(resync.refetch_state_sync_events(1).await, Err(BorRpcError::Heimdall(_))));
+        assert!(halt.reason().unwrap().contains("state sync event 4"));
+        assert_eq!(resync.pending.pending(), Some(before));
+
+        // Agreeing endpoints refetch as usual.
+        assert!(resync.resume_production().is_some());
+        let resync = resync.with_cross_check(client, halt.clone());
+        assert_eq!(resync.refetch_state_sync_events(1).await.unwrap(), 4);
+        assert!(!halt.is_halted());
+    }
+}
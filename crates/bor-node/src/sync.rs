@@ -0,0 +1,111 @@
+//! Block download tuning for Polygon's block rate.
+//!
+//! reth sizes body and header requests for Ethereum's 12-second blocks. Polygon
+//! produces a block every two seconds, so a node catching up requests six times as
+//! many blocks for the same span of time, each of them small. [`SyncTuning`] holds
+//! larger batch sizes and writes them into the `reth.toml` of a fresh data
+//! directory; an existing file is left as the operator wrote it.
+//!
+//! The sizes are static and the same for every peer. reth's downloaders fix the size
+//! of a request before its network picks the peer that serves it, and neither exposes
+//! a hook in between, so a request size per peer would take a fork of reth's fetcher.
+
+use std::path::Path;
+use tracing::info;
+
+/// Blocks per body request; reth's default is 200.
+pub const DEFAULT_BODIES_REQUEST_LIMIT: u64 = 1_000;
+
+/// Blocks the bodies downloader hands to the stage at once; reth's default is 1,000.
+pub const DEFAULT_BODIES_STREAM_BATCH_SIZE: usize = 5_000;
+
+/// Concurrent body requests; reth's default is 100.
+pub const DEFAULT_BODIES_MAX_CONCURRENT_REQUESTS: usize = 200;
+
+/// Headers per header request, reth's default and the protocol's soft limit.
+pub const DEFAULT_HEADERS_REQUEST_LIMIT: u64 = 1_000;
+
+/// Download batch sizes applied to reth's stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncTuning {
+    /// Blocks per body request.
+    pub bodies_request_limit: u64,
+    /// Blocks the bodies downloader hands to the stage at once.
+    pub bodies_stream_batch_size: usize,
+    /// Concurrent body requests.
+    pub bodies_max_concurrent_requests: usize,
+    /// Headers per header request.
+    pub headers_request_limit: u64,
+}
+
+impl Default for SyncTuning {
+    fn default() -> Self {
+        Self {
+            bodies_request_limit: DEFAULT_BODIES_REQUEST_LIMIT,
+            bodies_stream_batch_size: DEFAULT_BODIES_STREAM_BATCH_SIZE,
+            bodies_max_concurrent_requests: DEFAULT_BODIES_MAX_CONCURRENT_REQUESTS,
+            headers_request_limit: DEFAULT_HEADERS_REQUEST_LIMIT,
+        }
+    }
+}
+
+impl SyncTuning {
+    /// The stage sections of a `reth.toml` applying these sizes; reth fills in the rest.
+    pub fn reth_toml(&self) -> String {
+        format!(
+            "# Download batch sizes for Polygon's 2-second blocks, written by boreth.\n\
+             [stages.headers]\n\
+             downloader_request_limit = {}\n\
+             \n\
+             [stages.bodies]\n\
+             downloader_request_limit = {}\n\
+             downloader_stream_batch_size = {}\n\
+             downloader_max_concurrent_requests = {}\n",
+            self.headers_request_limit,
+            self.bodies_request_limit,
+            self.bodies_stream_batch_size,
+            self.bodies_max_concurrent_requests,
+        )
+    }
+
+    /// Write [`reth_toml`](Self::reth_toml) to `path` unless a file is already there.
+    ///
+    /// Returns whether the file was written.
+    pub fn write_reth_toml(&self, path: &Path) -> std::io::Result<bool> {
+        if path.exists() {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.reth_toml())?;
+        info!(target: "bor::sync", path = %path.display(), "wrote Polygon download batch sizes");
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reth_toml_is_written_once() {
+        let dir = std::env::temp_dir().join(format!("bor-sync-{}", std::process::id()));
+        let path = dir.join("reth.toml");
+        let _ = std::fs::remove_file(&path);
+
+        let tuning = SyncTuning::default();
+        assert!(tuning.write_reth_toml(&path).unwrap());
+        let written = std::fs::read_to_string(&path).unwrap();
+        let parsed: toml::Table = toml::from_str(&written).unwrap();
+        assert_eq!(
+            parsed["stages"]["bodies"]["downloader_request_limit"].as_integer(),
+            Some(DEFAULT_BODIES_REQUEST_LIMIT as i64)
+        );
+
+        std::fs::write(&path, "# operator settings\n").unwrap();
+        assert!(!tuning.write_reth_toml(&path).unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# operator settings\n");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
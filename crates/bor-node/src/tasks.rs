@@ -0,0 +1,546 @@
+//! Background tasks of a Bor node.
+//!
+//! Each keeps Bor's own data in step with the chain reth imports: the indexes RPC reads,
+//! the Heimdall data execution needs, the markers and caches a restart starts from, and
+//! the pool's view of accounts reorgs and bridge deposits change.
+
+use crate::adapters::PendingStateChanges;
+use crate::canon::{BorBlockMeta, BorCanonNotifications, BorCanonUpdate};
+use crate::milestone_peers::{MilestonePeers, PeerConsistency, CONFLICTING_PEER_PENALTY};
+use crate::node::BorNode;
+use crate::resync::BorResync;
+use crate::txpool::BorTxPoolConfig;
+use alloy_eips::BlockHashOrNumber;
+use alloy_primitives::{Address, B256, U256};
+use bor_chainspec::{constants::STATE_RECEIVER_ADDRESS, BorHardforks};
+use bor_consensus::{compute_seal_hash, BorSnapshot, MilestoneTracker};
+use bor_evm::{next_sprint_start, ExecutionDiffRecorder};
+use bor_rpc::get_author;
+use bor_storage::{
+    SharedBadBlockStore, SharedSprintWal, SharedStateSyncStore, SharedTotalDifficultyIndex,
+    SprintMarker, SprintOutcome, SprintWal,
+};
+use heimdall_client::{
+    HeimdallCacheFile, HeimdallCacheSnapshot, HttpHeimdallClient, SharedSpanCache,
+};
+use reth_chainspec::ChainSpec;
+use reth_engine_primitives::ConsensusEngineEvent;
+use reth_eth_wire::{GetBlockHeaders, HeadersDirection};
+use reth_ethereum_primitives::EthPrimitives;
+use reth_network::{primitives::NetworkPrimitives, NetworkHandle};
+use reth_network_api::{PeerRequest, Peers, ReputationChangeKind};
+use reth_provider::{
+    BlockIdReader, BlockNumReader, CanonStateNotification, CanonStateNotifications,
+    ChainSpecProvider, DatabaseProviderFactory, HeaderProvider, StateProvider,
+    StateProviderFactory,
+};
+use reth_transaction_pool::{ChangedAccount, TransactionPoolExt};
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, error, info, warn};
+
+/// Interval at which the pending state sync events are shown to the transaction pool.
+const PENDING_STATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time the node waits on shutdown for sprint-start system calls in flight.
+const SPRINT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often markers of persisted sprint-start blocks are dropped.
+const SPRINT_WAL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the spans, milestone and checkpoint cached from Heimdall are written to disk.
+const HEIMDALL_CACHE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often finalized blocks are added to the total difficulty index.
+const TD_INDEX_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Checkpoints the total difficulty index adds at most per round, so that indexing a
+/// chain from genesis reads headers in batches rather than all at once.
+const TD_INDEX_BATCH: usize = 64;
+
+/// How often peers are asked for the block of the latest milestone.
+const MILESTONE_PEERS_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Time peers have to answer a request for the block of the latest milestone.
+const MILESTONE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Report what became of the sprint-start blocks the previous run left in flight.
+///
+/// Returns the markers of those rolled back, whose Heimdall data is fetched again.
+pub fn report_sprint_recovery<P: HeaderProvider<Header = alloy_consensus::Header>>(
+    wal: &SprintWal,
+    provider: &P,
+) -> Vec<SprintMarker> {
+    let canonical_parent =
+        |number| provider.header_by_number(number).ok().flatten().map(|h| h.parent_hash);
+    let mut rolled_back = Vec::new();
+    for (marker, outcome) in wal.recover(canonical_parent) {
+        match outcome {
+            SprintOutcome::Committed => info!(
+                target: "boreth",
+                number = marker.number,
+                "sprint-start block was committed before shutdown"
+            ),
+            SprintOutcome::RolledBack => warn!(
+                target: "boreth",
+                number = marker.number,
+                stage = ?marker.stage,
+                first_state_id = ?marker.first_state_id,
+                last_state_id = ?marker.last_state_id,
+                "sprint-start block was rolled back at shutdown, it is executed again"
+            ),
+        }
+        if outcome == SprintOutcome::RolledBack {
+            rolled_back.push(marker);
+        }
+    }
+    rolled_back
+}
+
+/// Rebuild the snapshot at the canonical head if `node` has none stored, or a corrupt one.
+///
+/// The rebuild starts at the block before the head's span, with the span's validators,
+/// and needs the span and the next one, if the head's sprint ends it, in `spans`.
+pub fn rebuild_head_snapshot<P>(
+    node: &BorNode,
+    provider: &P,
+    spans: &SharedSpanCache,
+) -> eyre::Result<()>
+where
+    P: BlockNumReader + HeaderProvider<Header = alloy_consensus::Header>,
+{
+    let head = provider.best_block_number()?;
+    let Some(hash) = provider.block_hash(head)? else { return Ok(()) };
+    let span = spans.lock().expect("span cache lock poisoned").span_for_block(head).cloned();
+    let Some(span) = span.filter(|_| head > 0) else {
+        debug!(target: "boreth", head, "no span cached for the head, snapshot not rebuilt");
+        return Ok(());
+    };
+    let from = span.start_block.saturating_sub(1);
+    let anchor_hash =
+        provider.block_hash(from)?.ok_or_else(|| eyre::eyre!("block {from} not found"))?;
+    let anchor = BorSnapshot::new(from, anchor_hash, span.validator_set);
+    let headers = (from + 1..=head).map_while(|n| provider.header_by_number(n).ok().flatten());
+    let chain_spec = node.chain_spec.clone();
+    let snapshot = node.snapshot_or_rebuild(
+        hash,
+        anchor,
+        headers,
+        |number| chain_spec.is_bor_sprint_end(number).unwrap_or(false),
+        |number, _| {
+            let mut spans = spans.lock().expect("span cache lock poisoned");
+            spans.span_for_block(number + 1).map(|span| span.validator_set.clone())
+        },
+    )?;
+    info!(target: "boreth", number = snapshot.number, %hash, "head snapshot ready");
+    Ok(())
+}
+
+/// Drop the markers of sprint-start blocks once persisted and, on shutdown, wait for
+/// the system calls in flight before letting the node stop.
+pub async fn complete_sprint_markers<P, G>(
+    wal: SharedSprintWal,
+    provider: P,
+    shutdown: impl Future<Output = G>,
+) where
+    P: DatabaseProviderFactory<Provider: BlockNumReader>,
+{
+    let mut shutdown = std::pin::pin!(shutdown);
+    let mut interval = tokio::time::interval(SPRINT_WAL_INTERVAL);
+    loop {
+        tokio::select! {
+            guard = &mut shutdown => {
+                let waiting = wal.clone();
+                let idle = tokio::task::spawn_blocking(move || {
+                    waiting.wait_idle(SPRINT_SHUTDOWN_TIMEOUT)
+                })
+                .await
+                .unwrap_or(false);
+                if !idle {
+                    warn!(
+                        target: "boreth",
+                        in_flight = wal.in_flight(),
+                        "stopping with sprint-start system calls in flight, \
+                         their blocks are rolled back"
+                    );
+                }
+                drop(guard);
+                return;
+            }
+            _ = interval.tick() => {
+                let persisted =
+                    provider.database_provider_ro().and_then(|db| db.best_block_number());
+                let Ok(persisted) = persisted else { continue };
+                if let Err(err) = wal.complete_through(persisted) {
+                    warn!(target: "boreth", %err, "failed to write sprint markers");
+                }
+            }
+        }
+    }
+}
+
+/// Write the spans in `spans` and the latest milestone and checkpoint of `tracker` to
+/// `file` periodically and on shutdown, for the next run to start with.
+pub async fn persist_heimdall_cache<G>(
+    file: HeimdallCacheFile,
+    spans: SharedSpanCache,
+    tracker: Arc<MilestoneTracker>,
+    shutdown: impl Future<Output = G>,
+) {
+    let save = || {
+        let snapshot = HeimdallCacheSnapshot {
+            spans: spans.lock().expect("span cache lock poisoned").spans().cloned().collect(),
+            milestone: tracker.latest(),
+            checkpoint: tracker.latest_checkpoint(),
+        };
+        if let Err(err) = file.save(&snapshot) {
+            let path = file.path().display();
+            warn!(target: "boreth", %path, %err, "failed to write Heimdall cache");
+        }
+    };
+    let mut shutdown = std::pin::pin!(shutdown);
+    let mut interval = tokio::time::interval(HEIMDALL_CACHE_INTERVAL);
+    // The first tick completes immediately, before anything new was fetched.
+    interval.tick().await;
+    loop {
+        tokio::select! {
+            guard = &mut shutdown => {
+                save();
+                drop(guard);
+                return;
+            }
+            _ = interval.tick() => save(),
+        }
+    }
+}
+
+/// Add a checkpoint to `index` for every finalized block due one, from genesis on, and
+/// write the index to `path` when it grew and on shutdown.
+pub async fn index_total_difficulty<P, G>(
+    provider: P,
+    index: SharedTotalDifficultyIndex,
+    path: PathBuf,
+    shutdown: impl Future<Output = G>,
+) where
+    P: HeaderProvider<Header = alloy_consensus::Header> + BlockNumReader + BlockIdReader,
+{
+    let save = || {
+        let index = index.read().expect("total difficulty lock poisoned");
+        if let Err(err) = index.save(&path) {
+            let path = path.display();
+            warn!(target: "boreth", %path, %err, "failed to write total difficulty index");
+        }
+    };
+    // Checkpoints above the head were left by a chain since unwound.
+    if let Ok(best) = provider.best_block_number() {
+        index.write().expect("total difficulty lock poisoned").truncate_above(best);
+    }
+    let mut shutdown = std::pin::pin!(shutdown);
+    let mut interval = tokio::time::interval(TD_INDEX_INTERVAL);
+    loop {
+        tokio::select! {
+            guard = &mut shutdown => {
+                save();
+                drop(guard);
+                return;
+            }
+            _ = interval.tick() => {}
+        }
+        let Ok(Some(finalized)) = provider.finalized_block_number() else { continue };
+        let mut added = 0;
+        while added < TD_INDEX_BATCH {
+            let range = index.read().expect("total difficulty lock poisoned").next_range();
+            if *range.end() > finalized {
+                break;
+            }
+            let headers = match provider.headers_range(range.clone()) {
+                Ok(headers) => headers,
+                Err(err) => {
+                    warn!(target: "boreth", %err, "failed to read headers for total difficulty");
+                    break;
+                }
+            };
+            if headers.len() as u64 != range.end() - range.start() + 1 {
+                break;
+            }
+            let difficulty = headers.iter().fold(U256::ZERO, |total, h| total + h.difficulty);
+            index.write().expect("total difficulty lock poisoned").push(difficulty);
+            added += 1;
+        }
+        if added > 0 {
+            save();
+        }
+    }
+}
+
+/// Ask each peer not yet judged for the block of the latest milestone of `tracker`, and
+/// lower the reputation of those on a fork that conflicts with it, so that headers are
+/// downloaded from the others.
+pub async fn prefer_milestone_peers<N>(
+    network: NetworkHandle<N>,
+    tracker: Arc<MilestoneTracker>,
+    peers: Arc<MilestonePeers>,
+) where
+    N: NetworkPrimitives<BlockHeader = alloy_consensus::Header>,
+{
+    let mut interval = tokio::time::interval(MILESTONE_PEERS_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(milestone) = tracker.latest() else { continue };
+        peers.set_milestone(milestone.end_block, milestone.hash);
+        let connected = match network.get_all_peers().await {
+            Ok(connected) => connected,
+            Err(err) => {
+                debug!(target: "boreth", %err, "failed to list peers");
+                continue;
+            }
+        };
+
+        let mut probes = Vec::new();
+        for info in connected {
+            let peer = info.remote_id;
+            let Some((_, hash)) = peers.probe(&peer) else { continue };
+            let (response, answer) = tokio::sync::oneshot::channel();
+            let request = GetBlockHeaders {
+                start_block: BlockHashOrNumber::Hash(hash),
+                limit: 1,
+                skip: 0,
+                direction: HeadersDirection::Rising,
+            };
+            network.send_request(peer, PeerRequest::GetBlockHeaders { request, response });
+            probes.push((peer, hash, info.status.latest_block, answer));
+        }
+        let deadline = tokio::time::Instant::now() + MILESTONE_PROBE_TIMEOUT;
+        for (peer, hash, head, answer) in probes {
+            // A peer that does not answer says nothing about its chain.
+            let Ok(Ok(Ok(headers))) = tokio::time::timeout_at(deadline, answer).await else {
+                continue;
+            };
+            let header = headers.0.first().map(|header| header.hash_slow());
+            if peers.record(peer, hash, head, header) == PeerConsistency::Conflicting {
+                let end_block = milestone.end_block;
+                debug!(target: "boreth", %peer, end_block, "peer conflicts with milestone");
+                let penalty = ReputationChangeKind::Other(CONFLICTING_PEER_PENALTY);
+                network.reputation_change(peer, penalty);
+            }
+        }
+    }
+}
+
+/// Index the state sync events of blocks as they become canonical, forget those of
+/// blocks reorged out, and publish each change with its Bor metadata on `bor_canon`.
+pub async fn index_state_syncs(
+    chain_spec: Arc<ChainSpec>,
+    store: SharedStateSyncStore,
+    bor_canon: BorCanonNotifications,
+    mut notifications: CanonStateNotifications<EthPrimitives>,
+) {
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(target: "boreth", skipped, "state sync index missed canonical updates");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let mut store = store.write().expect("state sync store lock poisoned");
+        let mut update = BorCanonUpdate::default();
+        if let CanonStateNotification::Reorg { old, .. } = &notification {
+            let reverted_from = old.first().header().number;
+            store.unwind_from(reverted_from);
+            update.reverted_from = Some(reverted_from);
+        }
+        for block in notification.committed().blocks_iter() {
+            let header = block.header();
+            store.canonicalize(header.number, header.parent_hash, block.hash());
+            let producer = get_author(&compute_seal_hash(header), &header.extra_data).ok();
+            let events = store.block(header.number).map(|b| b.events).unwrap_or_default();
+            let meta =
+                BorBlockMeta::new(&*chain_spec, header.number, block.hash(), producer, &events);
+            update.committed.push(meta);
+        }
+        drop(store);
+        bor_canon.publish(update);
+    }
+}
+
+/// Fetch the state sync events of the next sprint start whenever the canonical head moves,
+/// so that [`CachedSprintContext`] can execute it.
+///
+/// Events follow the last ID the state receiver committed at the head, and are those
+/// Heimdall has recorded so far; the sprint start commits the ones its timestamp allows.
+pub async fn prefetch_state_syncs<P>(
+    provider: P,
+    resync: BorResync<HttpHeimdallClient>,
+    mut notifications: CanonStateNotifications<EthPrimitives>,
+) where
+    P: StateProviderFactory + ChainSpecProvider<ChainSpec = ChainSpec>,
+{
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        let tip = notification.tip();
+        let head = tip.header().number;
+        let Ok(sprint_size) = provider.chain_spec().try_bor_sprint_size(head + 1) else {
+            continue;
+        };
+        let Some(sprint_start) = next_sprint_start(head, sprint_size) else { continue };
+        // `lastStateId` is the first storage slot of the state receiver.
+        let last_state_id = match provider
+            .history_by_block_hash(tip.hash())
+            .and_then(|state| state.storage(STATE_RECEIVER_ADDRESS, B256::ZERO))
+        {
+            Ok(last_state_id) => last_state_id.unwrap_or_default().saturating_to::<u64>(),
+            Err(err) => {
+                warn!(target: "boreth", head, %err, "cannot read the last state sync ID");
+                continue;
+            }
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if let Err(err) = resync.prefetch_state_syncs(sprint_start, last_state_id, now).await {
+            warn!(target: "boreth", sprint_start, %err, "state sync events not prefetched");
+        }
+    }
+}
+
+/// Under `--bor.assert-roots`, write the execution diff of the first executed block the
+/// engine rejects to `dir`, then stop the node.
+///
+/// Blocks rejected before execution, e.g. on their seal, have no diff and are only logged.
+pub async fn assert_roots(
+    mut events: impl Stream<Item = ConsensusEngineEvent<EthPrimitives>>
+        + Unpin,
+    diffs: ExecutionDiffRecorder,
+    bad_blocks: SharedBadBlockStore,
+    dir: PathBuf,
+) {
+    while let Some(event) = events.next().await {
+        let ConsensusEngineEvent::InvalidBlock(block) = event else { continue };
+        let (header, hash) = (block.header(), block.hash());
+        let Some(diff) = diffs.get(header.number, header.parent_hash) else {
+            debug!(target: "boreth", number = header.number, %hash, "rejected before execution");
+            continue;
+        };
+        let reason = bad_blocks
+            .read()
+            .expect("bad block store lock poisoned")
+            .get_bad_block(&hash)
+            .map_or_else(|| "rejected by the engine".to_string(), |record| record.error);
+        let dumped = diff.dump(&dir);
+        error!(
+            target: "boreth",
+            number = header.number,
+            %hash,
+            state_root = %header.state_root,
+            receipts_root = %header.receipts_root,
+            gas_used = diff.gas_used,
+            transactions = diff.transactions.len(),
+            touched = diff.touched.len(),
+            path = ?dumped.as_ref().map(|path| path.display().to_string()),
+            %reason,
+            "executed block failed root assertion"
+        );
+        if let Err(err) = dumped {
+            warn!(target: "boreth", %err, "failed to write execution diff");
+        }
+        panic!("block {} ({hash}) failed root assertion: {reason}", header.number);
+    }
+}
+
+/// Reload into `pool` the accounts changed on either side of each reorg `config` tunes.
+pub async fn reload_reorged_accounts<Pool, P>(
+    pool: Pool,
+    provider: P,
+    mut notifications: CanonStateNotifications<EthPrimitives>,
+    config: BorTxPoolConfig,
+) where
+    Pool: TransactionPoolExt,
+    P: StateProviderFactory,
+{
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+        let CanonStateNotification::Reorg { old, new } = &notification else { continue };
+        let depth = old.len() as u64;
+        if !config.reloads_after_reorg(depth) {
+            continue;
+        }
+        let addresses: HashSet<Address> = old
+            .execution_outcome()
+            .bundle_accounts_iter()
+            .chain(new.execution_outcome().bundle_accounts_iter())
+            .map(|(address, _)| address)
+            .collect();
+        let state = match provider.latest() {
+            Ok(state) => state,
+            Err(err) => {
+                warn!(target: "bor::txpool", depth, %err, "cannot reload reorged accounts");
+                continue;
+            }
+        };
+        let changed: Vec<_> = addresses
+            .into_iter()
+            .filter_map(|address| {
+                let account = state.basic_account(&address).ok()?.unwrap_or_default();
+                Some(ChangedAccount { address, nonce: account.nonce, balance: account.balance })
+            })
+            .collect();
+        debug!(target: "bor::txpool", depth, accounts = changed.len(), "reloaded reorged accounts");
+        pool.update_accounts(changed);
+    }
+}
+
+/// Show `pool` the balances and nonces the pending state sync events of `changes` leave,
+/// so that transactions spending a bridge deposit are not held back until its sprint start.
+///
+/// Reapplied every block: reth's pool maintenance reloads accounts from the canonical state.
+pub async fn overlay_pending_state_syncs<Pool, P>(pool: Pool, changes: PendingStateChanges<P>)
+where
+    Pool: TransactionPoolExt,
+    P: StateProviderFactory + BlockNumReader + HeaderProvider<Header = alloy_consensus::Header>,
+{
+    let mut interval = tokio::time::interval(PENDING_STATE_INTERVAL);
+    let mut last = None;
+    loop {
+        interval.tick().await;
+        let (head, changes) = match changes.changes() {
+            Ok(Some(changes)) => changes,
+            Ok(None) => continue,
+            Err(err) => {
+                debug!(target: "bor::txpool", %err, "cannot apply pending state syncs");
+                continue;
+            }
+        };
+        let mut changed: Vec<_> = changes
+            .state
+            .into_iter()
+            .filter_map(|(address, account)| {
+                let info = account.info?;
+                Some(ChangedAccount { address, nonce: info.nonce, balance: info.balance })
+            })
+            .collect();
+        changed.sort_by_key(|account| account.address);
+        if last.as_ref() == Some(&(head, changed.clone())) {
+            continue;
+        }
+        let accounts = changed.len();
+        debug!(target: "bor::txpool", head, accounts, "applied pending state syncs");
+        pool.update_accounts(changed.clone());
+        last = Some((head, changed));
+    }
+}
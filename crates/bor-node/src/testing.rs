@@ -0,0 +1,559 @@
+//! In-process multi-validator devnet for integration tests.
+//!
+//! [`Devnet`] runs N validators, each with its own [`BorNode`] and signing key,
+//! against a shared [`MockHeimdallClient`]. Every block is sealed by the first
+//! online validator in succession order and then imported by every node, which
+//! checks its header with [`BorConsensus`] against the spans it fetched (parent link,
+//! timing, seal, succession and difficulty) and the validator bytes carried at sprint
+//! end.
+//!
+//! Sprint and span lengths are configurable so tests can cross several of each
+//! quickly. The nodes run on the devnet's genesis schedule, which activates Jaipur and
+//! Delhi at genesis, so block timing follows the mainnet producer delay parameters.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use alloy_consensus::Header;
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use bor_consensus::extra_data::{ExtraData, ExtraDataBuilder};
+use bor_consensus::proposer::select_proposer;
+use bor_consensus::seal::{compute_seal_hash, ecrecover_seal};
+use bor_consensus::succession::{earliest_block_time, sprint_validator_set};
+use bor_consensus::{BorConsensus, BorSnapshot};
+use bor_chainspec::{bor_genesis_chainspec, BorChainSpec, BorHardforks};
+use bor_chainspec::constants::EXTRADATA_SEAL_LEN;
+use bor_primitives::{Span, Validator, ValidatorSet};
+use bor_storage::persistence::SpanStore;
+use heimdall_client::{HeimdallClient, MockHeimdallClient};
+use k256::ecdsa::SigningKey;
+use reth_consensus::HeaderValidator;
+use reth_primitives_traits::SealedHeader;
+
+use crate::{BorNode, BorNodeConfig};
+
+/// Vanity written into every devnet header.
+const DEVNET_VANITY: &[u8] = b"boreth-devnet";
+
+/// Chain ID of every devnet.
+pub const DEVNET_CHAIN_ID: u64 = 1337;
+
+/// Gas limit of every devnet block.
+const DEVNET_GAS_LIMIT: u64 = 30_000_000;
+
+/// Shape of a [`Devnet`].
+#[derive(Debug, Clone)]
+pub struct DevnetConfig {
+    /// Number of validators (and nodes).
+    pub validators: usize,
+    /// Blocks per sprint.
+    pub sprint_size: u64,
+    /// Blocks per span; must be a multiple of the sprint size.
+    pub span_size: u64,
+    /// Number of spans registered with the mock Heimdall.
+    pub spans: u64,
+    /// Timestamp of the genesis header.
+    pub genesis_timestamp: u64,
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            sprint_size: 4,
+            span_size: 16,
+            spans: 8,
+            genesis_timestamp: 1_700_000_000,
+        }
+    }
+}
+
+impl DevnetConfig {
+    /// Set the number of validators.
+    pub fn with_validators(mut self, validators: usize) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Set the sprint size.
+    pub fn with_sprint_size(mut self, sprint_size: u64) -> Self {
+        self.sprint_size = sprint_size;
+        self
+    }
+
+    /// Set the span size.
+    pub fn with_span_size(mut self, span_size: u64) -> Self {
+        self.span_size = span_size;
+        self
+    }
+
+    /// Set the number of spans known to Heimdall.
+    pub fn with_spans(mut self, spans: u64) -> Self {
+        self.spans = spans;
+        self
+    }
+}
+
+/// Deterministic signing key and address of devnet validator `index`.
+pub fn devnet_signer(index: usize) -> (SigningKey, Address) {
+    let secret = keccak256(format!("boreth-devnet-validator-{index}"));
+    let key = SigningKey::from_bytes((&secret.0).into()).expect("keccak output is a valid key");
+    let address = Address::from_raw_public_key(
+        &key.verifying_key().to_encoded_point(false).as_bytes()[1..],
+    );
+    (key, address)
+}
+
+/// Genesis of a devnet with `sprint_size` blocks per sprint.
+fn devnet_genesis(sprint_size: u64) -> serde_json::Value {
+    serde_json::json!({
+        "config": {
+            "chainId": DEVNET_CHAIN_ID,
+            "londonBlock": 0,
+            "bor": { "jaipurBlock": 0, "delhiBlock": 0, "sprint": { "0": sprint_size } }
+        },
+        "alloc": {}
+    })
+}
+
+/// Validator set of span `id` over the given validators, before Heimdall selects the
+/// proposer of its first sprint.
+///
+/// Voting powers differ per validator and per span so proposer rotation is
+/// weighted and changes at every span boundary.
+fn devnet_validator_set(id: u64, signers: &[Address]) -> ValidatorSet {
+    let validators: Vec<Validator> = signers
+        .iter()
+        .enumerate()
+        .map(|(i, signer)| Validator {
+            id: i as u64 + 1,
+            address: *signer,
+            voting_power: 100 * (1 + ((i as u64 + id) % signers.len() as u64) as i64),
+            signer: *signer,
+            proposer_priority: 0,
+        })
+        .collect();
+    ValidatorSet { validators, proposer: None }
+}
+
+/// Span `id` over the given validators, with the proposer of its first sprint selected.
+fn devnet_span(id: u64, span_size: u64, signers: &[Address]) -> Span {
+    let mut validator_set = devnet_validator_set(id, signers);
+    let selected_producers = validator_set.validators.clone();
+    select_proposer(&mut validator_set);
+    Span {
+        id,
+        start_block: id * span_size,
+        end_block: (id + 1) * span_size - 1,
+        validator_set,
+        selected_producers,
+        bor_chain_id: DEVNET_CHAIN_ID.to_string(),
+    }
+}
+
+/// One validator of a [`Devnet`] and its local view of the chain.
+pub struct DevnetNode {
+    /// The node's components.
+    pub node: BorNode,
+    /// The validator's signer address.
+    pub address: Address,
+    key: SigningKey,
+    chain_spec: Arc<BorChainSpec>,
+    consensus: BorConsensus<BorChainSpec>,
+    sprint_size: u64,
+    span_size: u64,
+    headers: Vec<Header>,
+    snapshot: BorSnapshot,
+}
+
+impl DevnetNode {
+    /// All imported headers, starting at genesis.
+    pub fn headers(&self) -> &[Header] {
+        &self.headers
+    }
+
+    /// The current head header.
+    pub fn head(&self) -> &Header {
+        self.headers.last().expect("genesis is always present")
+    }
+
+    /// Consensus snapshot at the head.
+    pub fn snapshot(&self) -> &BorSnapshot {
+        &self.snapshot
+    }
+
+    /// The span covering block `number`, from the span store or else Heimdall. A span
+    /// fetched is stored and handed to the consensus.
+    async fn span(&self, number: u64, heimdall: &MockHeimdallClient) -> eyre::Result<Span> {
+        let id = number / self.span_size;
+        let cached = self.node.span_store.read().map_err(|e| eyre::eyre!("{e}"))?.get_span(id);
+        if let Some(span) = cached {
+            return Ok(span);
+        }
+        let span = heimdall.fetch_span(id).await?;
+        self.node.span_store.write().map_err(|e| eyre::eyre!("{e}"))?.put_span(span.clone());
+        self.consensus.insert_span(span.clone());
+        Ok(span)
+    }
+
+    /// Validator set of the sprint containing block `number`, as the consensus derives
+    /// it from the span.
+    async fn sprint_validator_set(
+        &self,
+        number: u64,
+        heimdall: &MockHeimdallClient,
+    ) -> eyre::Result<ValidatorSet> {
+        let span = self.span(number, heimdall).await?;
+        sprint_validator_set(&*self.chain_spec, &span, number)?
+            .ok_or_else(|| eyre::eyre!("span {} does not cover block {number}", span.id))
+    }
+
+    /// Validate `header` against the local head and append it.
+    pub async fn import(&mut self, header: &Header, heimdall: &MockHeimdallClient) -> eyre::Result<()> {
+        // The consensus skips the succession checks of blocks whose span it lacks.
+        self.span(header.number, heimdall).await?;
+        let parent = SealedHeader::seal_slow(self.head().clone());
+        let sealed = SealedHeader::seal_slow(header.clone());
+        self.consensus.validate_header(&sealed)?;
+        self.consensus.validate_header_against_parent(&sealed, &parent)?;
+
+        let extra = ExtraData::parse(&header.extra_data)?;
+        let signer = ecrecover_seal(&compute_seal_hash(header), &extra.seal)?;
+        let next = if self.chain_spec.is_bor_sprint_end(header.number)? {
+            let next_span = self.span(header.number + 1, heimdall).await?;
+            let expected: Vec<Address> =
+                next_span.validator_set.validators.iter().map(|v| v.signer).collect();
+            eyre::ensure!(
+                extra.validators() == expected,
+                "block {} carries the wrong validator bytes",
+                header.number
+            );
+            Some(self.sprint_validator_set(header.number + 1, heimdall).await?)
+        } else {
+            eyre::ensure!(
+                extra.validator_bytes.is_empty(),
+                "block {} carries validator bytes outside sprint end",
+                header.number
+            );
+            None
+        };
+
+        let hash = sealed.hash();
+        self.snapshot.apply(header.number, signer);
+        self.snapshot.hash = hash;
+        if let Some(next) = next {
+            self.snapshot.validator_set = next;
+        }
+        self.node.put_snapshot(hash, &self.snapshot)?;
+        self.headers.push(header.clone());
+        Ok(())
+    }
+
+    /// Build and seal the next block on top of the local head.
+    async fn seal_next(&self, heimdall: &MockHeimdallClient) -> eyre::Result<Header> {
+        let parent = self.head();
+        let number = parent.number + 1;
+        let succession = self.snapshot.succession(&self.address)?;
+
+        let mut extra = ExtraDataBuilder::new(number, self.sprint_size).with_vanity(DEVNET_VANITY);
+        if self.chain_spec.is_bor_sprint_end(number)? {
+            let next_span = self.span(number + 1, heimdall).await?;
+            extra = extra.with_validators(&next_span.validator_set.validators);
+        }
+
+        let mut header = Header {
+            parent_hash: parent.hash_slow(),
+            beneficiary: self.address,
+            difficulty: self.snapshot.difficulty(&self.address)?,
+            number,
+            gas_limit: DEVNET_GAS_LIMIT,
+            timestamp: earliest_block_time(
+                &*self.chain_spec,
+                parent.timestamp,
+                number,
+                succession,
+            )?,
+            extra_data: extra.build()?,
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+
+        let (sig, recid) = self.key.sign_prehash_recoverable(compute_seal_hash(&header).as_ref())?;
+        let mut extra_data = header.extra_data.to_vec();
+        let seal_start = extra_data.len() - EXTRADATA_SEAL_LEN;
+        extra_data[seal_start..seal_start + 64].copy_from_slice(&sig.to_bytes());
+        extra_data[seal_start + 64] = recid.to_byte();
+        header.extra_data = Bytes::from(extra_data);
+        Ok(header)
+    }
+}
+
+/// N validators producing and importing blocks in-process.
+pub struct Devnet {
+    config: DevnetConfig,
+    heimdall: MockHeimdallClient,
+    nodes: Vec<DevnetNode>,
+    offline: HashSet<Address>,
+}
+
+impl Devnet {
+    /// Start a devnet at genesis.
+    pub async fn start(config: DevnetConfig) -> eyre::Result<Self> {
+        eyre::ensure!(config.validators > 0, "devnet needs at least one validator");
+        eyre::ensure!(config.sprint_size > 0, "sprint size must be positive");
+        eyre::ensure!(
+            config.span_size % config.sprint_size == 0 && config.span_size > 0,
+            "span size must be a positive multiple of the sprint size"
+        );
+
+        let signers: Vec<(SigningKey, Address)> = (0..config.validators).map(devnet_signer).collect();
+        let addresses: Vec<Address> = signers.iter().map(|(_, a)| *a).collect();
+
+        let mut heimdall = MockHeimdallClient::new();
+        for id in 0..config.spans {
+            heimdall = heimdall.with_span(id, devnet_span(id, config.span_size, &addresses));
+        }
+        let genesis_span = heimdall.fetch_span(0).await?;
+
+        // The first sprint of a span is led by the proposer the span names.
+        let validator_set = genesis_span.validator_set.clone();
+        let genesis = Header {
+            difficulty: U256::from(1),
+            gas_limit: DEVNET_GAS_LIMIT,
+            timestamp: config.genesis_timestamp,
+            extra_data: ExtraDataBuilder::new(0, config.sprint_size).build()?,
+            base_fee_per_gas: Some(7),
+            ..Default::default()
+        };
+        let genesis_hash = genesis.hash_slow();
+        let chain_spec = Arc::new(bor_genesis_chainspec(serde_json::from_value(devnet_genesis(
+            config.sprint_size,
+        ))?)?);
+
+        let mut nodes = Vec::with_capacity(config.validators);
+        for (key, address) in signers {
+            let config = BorNodeConfig::custom(DEVNET_CHAIN_ID);
+            let node = BorNode::with_chain_spec(config, chain_spec.clone())?;
+            node.span_store.write().map_err(|e| eyre::eyre!("{e}"))?.put_span(genesis_span.clone());
+            let snapshot = BorSnapshot::new(0, genesis_hash, validator_set.clone());
+            node.put_snapshot(genesis_hash, &snapshot)?;
+            let consensus = BorConsensus::new(chain_spec.clone());
+            consensus.insert_span(genesis_span.clone());
+            nodes.push(DevnetNode {
+                node,
+                address,
+                key,
+                chain_spec: chain_spec.clone(),
+                consensus,
+                sprint_size: config.sprint_size,
+                span_size: config.span_size,
+                headers: vec![genesis.clone()],
+                snapshot,
+            });
+        }
+
+        Ok(Self { config, heimdall, nodes, offline: HashSet::new() })
+    }
+
+    /// The devnet configuration.
+    pub fn config(&self) -> &DevnetConfig {
+        &self.config
+    }
+
+    /// The shared mock Heimdall.
+    pub fn heimdall(&self) -> &MockHeimdallClient {
+        &self.heimdall
+    }
+
+    /// All nodes, in validator order.
+    pub fn nodes(&self) -> &[DevnetNode] {
+        &self.nodes
+    }
+
+    /// Mutable access to one node, e.g. to import a crafted header.
+    pub fn node_mut(&mut self, index: usize) -> &mut DevnetNode {
+        &mut self.nodes[index]
+    }
+
+    /// Number of the current head.
+    pub fn head_number(&self) -> u64 {
+        self.nodes[0].head().number
+    }
+
+    /// Stop validator `index` from producing. It keeps importing blocks.
+    pub fn set_offline(&mut self, index: usize) {
+        self.offline.insert(self.nodes[index].address);
+    }
+
+    /// Let validator `index` produce again.
+    pub fn set_online(&mut self, index: usize) {
+        self.offline.remove(&self.nodes[index].address);
+    }
+
+    /// Produce one block with the first online validator in succession order
+    /// and import it on every node.
+    pub async fn produce_block(&mut self) -> eyre::Result<Header> {
+        let snapshot = self.nodes[0].snapshot();
+        let producer = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| !self.offline.contains(&n.address))
+            .filter_map(|(i, n)| snapshot.succession(&n.address).ok().map(|s| (s, i)))
+            .min()
+            .map(|(_, i)| i)
+            .ok_or_else(|| eyre::eyre!("no online validator can produce"))?;
+
+        let header = self.nodes[producer].seal_next(&self.heimdall).await?;
+        for node in &mut self.nodes {
+            node.import(&header, &self.heimdall).await?;
+        }
+        Ok(header)
+    }
+
+    /// Produce `count` blocks.
+    pub async fn produce_blocks(&mut self, count: usize) -> eyre::Result<Vec<Header>> {
+        let mut headers = Vec::with_capacity(count);
+        for _ in 0..count {
+            headers.push(self.produce_block().await?);
+        }
+        Ok(headers)
+    }
+
+    /// Produce blocks until the head is at `number`.
+    pub async fn advance_to(&mut self, number: u64) -> eyre::Result<()> {
+        while self.head_number() < number {
+            self.produce_block().await?;
+        }
+        Ok(())
+    }
+
+    /// Assert that every node has the same chain, snapshot and stored snapshot
+    /// at the head.
+    pub fn assert_consistent(&self) {
+        let reference = &self.nodes[0];
+        let hashes: Vec<B256> = reference.headers().iter().map(Header::hash_slow).collect();
+        let head_hash = *hashes.last().expect("genesis is always present");
+
+        for node in &self.nodes {
+            let node_hashes: Vec<B256> = node.headers().iter().map(Header::hash_slow).collect();
+            assert_eq!(node_hashes, hashes, "node {} diverged", node.address);
+            assert_eq!(
+                node.snapshot().validator_set,
+                reference.snapshot().validator_set,
+                "node {} has a different validator set",
+                node.address
+            );
+            let stored = node
+                .node
+                .get_snapshot(&head_hash)
+                .expect("snapshot store is readable")
+                .expect("head snapshot is stored");
+            assert_eq!(stored.number, node.head().number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_devnet_crosses_sprints_and_spans() {
+        let mut devnet = Devnet::start(DevnetConfig::default()).await.unwrap();
+        devnet.advance_to(3 * 16 + 5).await.unwrap();
+        devnet.assert_consistent();
+
+        // Spans 1..=3 were fetched from Heimdall and cached by every node.
+        for node in devnet.nodes() {
+            let store = node.node.span_store.read().unwrap();
+            assert_eq!(store.latest_span_id(), Some(3));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_validator_proposes() {
+        let mut devnet = Devnet::start(DevnetConfig::default()).await.unwrap();
+        let headers = devnet.produce_blocks(60).await.unwrap();
+
+        let producers: HashSet<Address> = headers.iter().map(|h| h.beneficiary).collect();
+        assert_eq!(producers.len(), 4);
+        assert!(headers.iter().all(|h| h.difficulty == U256::from(4)));
+    }
+
+    #[tokio::test]
+    async fn test_backup_takes_over_offline_validator() {
+        let mut devnet = Devnet::start(DevnetConfig::default()).await.unwrap();
+        let offline = devnet.nodes()[0].address;
+        devnet.set_offline(0);
+
+        let headers = devnet.produce_blocks(40).await.unwrap();
+        devnet.assert_consistent();
+        assert!(headers.iter().all(|h| h.beneficiary != offline));
+        assert!(headers.iter().any(|h| h.difficulty < U256::from(4)));
+
+        devnet.set_online(0);
+        let headers = devnet.produce_blocks(24).await.unwrap();
+        assert!(headers.iter().any(|h| h.beneficiary == offline));
+        devnet.assert_consistent();
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_tampered_block() {
+        let mut devnet = Devnet::start(DevnetConfig::default()).await.unwrap();
+        devnet.produce_blocks(2).await.unwrap();
+
+        let producer = devnet.nodes()[0].snapshot().validator_set.proposer.clone().unwrap().signer;
+        let index = devnet.nodes().iter().position(|n| n.address == producer).unwrap();
+        let mut header = devnet.nodes()[index].seal_next(devnet.heimdall()).await.unwrap();
+        header.difficulty = U256::from(1);
+
+        let heimdall = devnet.heimdall().clone();
+        // Changing the difficulty invalidates the seal, so the block no longer
+        // recovers to an authorized signer.
+        assert!(devnet.node_mut(1).import(&header, &heimdall).await.is_err());
+        assert_eq!(devnet.nodes()[1].head().number, 2);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_rebuilt_from_headers() {
+        let mut devnet = Devnet::start(DevnetConfig::default()).await.unwrap();
+        devnet.advance_to(3 * 16 + 5).await.unwrap();
+        let source = &devnet.nodes()[0];
+        let head = source.head().hash_slow();
+        let (sprint_size, span_size) = (source.sprint_size, source.span_size);
+        let signers: Vec<Address> = devnet.nodes().iter().map(|n| n.address).collect();
+        let genesis = source.node.get_snapshot(&source.headers()[0].hash_slow()).unwrap().unwrap();
+
+        // A fresh node with an empty store rebuilds the head snapshot from genesis.
+        let node = BorNode::new(BorNodeConfig::mainnet()).unwrap();
+        let rebuilt = node
+            .snapshot_or_rebuild(
+                head,
+                genesis,
+                source.headers()[1..].to_vec(),
+                |number| (number + 1) % sprint_size == 0,
+                |number, snapshot| {
+                    // The rebuilder selects the proposer of the next sprint itself.
+                    if (number + 1) % span_size == 0 {
+                        Some(devnet_validator_set((number + 1) / span_size, &signers))
+                    } else {
+                        Some(snapshot.validator_set.clone())
+                    }
+                },
+            )
+            .unwrap();
+
+        assert_eq!(rebuilt.number, source.snapshot().number);
+        assert_eq!(rebuilt.recents, source.snapshot().recents);
+        assert_eq!(rebuilt.validator_set, source.snapshot().validator_set);
+        assert!(node.get_snapshot(&head).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_devnet_rejects_bad_config() {
+        let config = DevnetConfig::default().with_sprint_size(5).with_span_size(16);
+        assert!(Devnet::start(config).await.is_err());
+        assert!(Devnet::start(DevnetConfig::default().with_validators(0)).await.is_err());
+    }
+}
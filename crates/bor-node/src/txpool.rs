@@ -0,0 +1,244 @@
+//! Transaction pool settings for Polygon and the local transaction journal.
+//!
+//! reth's pool defaults are sized for 12 second Ethereum blocks. Polygon blocks
+//! come every 2 seconds and carry more transactions, so Bor runs with larger
+//! pools, which [`BorTxPoolConfig`] mirrors.
+//!
+//! reth's pool maintenance applies reorgs of up to [`RETH_MAX_UPDATE_DEPTH`] blocks to
+//! the pool; after a deeper one it reloads the changed accounts a few per block. That
+//! depth spans 13 minutes of Ethereum but only 2 of Polygon, so Bor nodes reload the
+//! accounts changed by reorgs up to [`BorTxPoolConfig::max_reorg_depth`] blocks at once.
+//!
+//! Bor also journals local transactions to disk every hour and replays them at
+//! startup. reth saves its local transactions backup only on a clean shutdown;
+//! [`TxJournal`] rewrites the same file periodically so that a crash loses at
+//! most one interval. The file is an RLP list of transactions, the format reth
+//! reads back at startup.
+
+use alloy_rlp::{Decodable, Encodable};
+use reth_node_core::args::TxPoolArgs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default number of executable transactions kept in the pool, as in Bor.
+pub const DEFAULT_PENDING_MAX_COUNT: usize = 32_768;
+
+/// Default number of non-executable transactions kept in the pool, as in Bor.
+pub const DEFAULT_QUEUED_MAX_COUNT: usize = 32_768;
+
+/// Default number of executable transactions per account, as in Bor.
+pub const DEFAULT_MAX_ACCOUNT_SLOTS: usize = 16;
+
+/// Default time a non-executable transaction stays queued, as in Bor.
+pub const DEFAULT_QUEUED_LIFETIME: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// Default interval between journal rewrites, as in Bor.
+pub const DEFAULT_REJOURNAL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Deepest reorg reth's pool maintenance applies to the pool as it happens.
+pub const RETH_MAX_UPDATE_DEPTH: u64 = 64;
+
+/// Default depth of the deepest reorg whose changed accounts are reloaded at once: the
+/// 13 minutes reth's [`RETH_MAX_UPDATE_DEPTH`] spans on Ethereum, in 2 second blocks.
+pub const DEFAULT_MAX_REORG_DEPTH: u64 = 384;
+
+/// Default journal file name, relative to the data directory.
+pub const DEFAULT_JOURNAL_FILE: &str = "bor-transactions.rlp";
+
+/// Pool sizes and journal settings of a Bor node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorTxPoolConfig {
+    /// Maximum number of executable transactions.
+    pub pending_max_count: usize,
+    /// Maximum number of non-executable transactions.
+    pub queued_max_count: usize,
+    /// Maximum number of executable transactions per account.
+    pub max_account_slots: usize,
+    /// How long a non-executable transaction stays in the pool.
+    pub queued_lifetime: Duration,
+    /// Journal of local transactions, or `None` to keep them in memory only.
+    pub journal: Option<PathBuf>,
+    /// Interval between journal rewrites.
+    pub rejournal: Duration,
+    /// Depth of the deepest reorg whose changed accounts are reloaded at once.
+    pub max_reorg_depth: u64,
+}
+
+impl Default for BorTxPoolConfig {
+    fn default() -> Self {
+        Self {
+            pending_max_count: DEFAULT_PENDING_MAX_COUNT,
+            queued_max_count: DEFAULT_QUEUED_MAX_COUNT,
+            max_account_slots: DEFAULT_MAX_ACCOUNT_SLOTS,
+            queued_lifetime: DEFAULT_QUEUED_LIFETIME,
+            journal: None,
+            rejournal: DEFAULT_REJOURNAL_INTERVAL,
+            max_reorg_depth: DEFAULT_MAX_REORG_DEPTH,
+        }
+    }
+}
+
+impl BorTxPoolConfig {
+    /// Whether a reorg replacing `depth` blocks has its changed accounts reloaded at once:
+    /// reth's pool maintenance leaves it to a slow reload, and it is no deeper than
+    /// [`max_reorg_depth`](Self::max_reorg_depth).
+    pub fn reloads_after_reorg(&self, depth: u64) -> bool {
+        depth > RETH_MAX_UPDATE_DEPTH && depth <= self.max_reorg_depth
+    }
+
+    /// Replace reth's Ethereum pool settings in `args` with these, where reth's flags were
+    /// left at their defaults: a `--txpool.*` flag given explicitly wins over Bor's value.
+    pub fn apply_to(&self, args: &mut TxPoolArgs) {
+        let defaults = TxPoolArgs::default();
+        replace_default(
+            &mut args.pending_max_count,
+            defaults.pending_max_count,
+            self.pending_max_count,
+        );
+        replace_default(
+            &mut args.queued_max_count,
+            defaults.queued_max_count,
+            self.queued_max_count,
+        );
+        replace_default(
+            &mut args.max_account_slots,
+            defaults.max_account_slots,
+            self.max_account_slots,
+        );
+        replace_default(
+            &mut args.max_queued_lifetime,
+            defaults.max_queued_lifetime,
+            self.queued_lifetime,
+        );
+        // reth replays the journal at startup and rewrites it on shutdown; `TxJournal`
+        // rewrites it in between.
+        if args.transactions_backup_path == defaults.transactions_backup_path
+            && args.disable_transactions_backup == defaults.disable_transactions_backup
+        {
+            args.transactions_backup_path = self.journal.clone();
+            args.disable_transactions_backup = self.journal.is_none();
+        }
+    }
+}
+
+/// Set `arg` to `value` unless it was changed from reth's `default`.
+fn replace_default<T: PartialEq>(arg: &mut T, default: T, value: T) {
+    if *arg == default {
+        *arg = value;
+    }
+}
+
+/// Periodically writes the pool's local transactions to a file.
+#[derive(Debug, Clone)]
+pub struct TxJournal {
+    path: PathBuf,
+    interval: Duration,
+}
+
+impl TxJournal {
+    /// Create a journal at `path`, rewritten every `interval`.
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self { path: path.into(), interval }
+    }
+
+    /// Path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replace the journal with `txs`, atomically: to a temporary file first, then renamed.
+    pub fn write<T: Encodable>(&self, txs: &[T]) -> std::io::Result<()> {
+        let mut buf = Vec::new();
+        txs.encode(&mut buf);
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, buf)?;
+        std::fs::rename(&tmp, &self.path)
+    }
+
+    /// Read the journaled transactions. A missing journal holds none.
+    pub fn read<T: Decodable>(&self) -> std::io::Result<Vec<T>> {
+        let bytes = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Vec::<T>::decode(&mut bytes.as_slice())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Rewrite the journal with the output of `local_transactions` every interval.
+    pub async fn run<T, F>(self, local_transactions: F)
+    where
+        T: Encodable,
+        F: Fn() -> Vec<T>,
+    {
+        let mut interval = tokio::time::interval(self.interval);
+        // The first tick completes immediately; the pool was just restored from the journal.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let txs = local_transactions();
+            match self.write(&txs) {
+                Ok(()) => debug!(
+                    target: "bor::txpool",
+                    count = txs.len(),
+                    path = %self.path.display(),
+                    "rejournaled local transactions"
+                ),
+                Err(err) => warn!(
+                    target: "bor::txpool",
+                    %err,
+                    path = %self.path.display(),
+                    "failed to rejournal local transactions"
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    fn temp_journal(name: &str) -> TxJournal {
+        let dir = std::env::temp_dir().join(format!("bor-txpool-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TxJournal::new(dir.join(DEFAULT_JOURNAL_FILE), DEFAULT_REJOURNAL_INTERVAL)
+    }
+
+    #[test]
+    fn test_reloads_reorgs_reth_leaves_behind() {
+        let config = BorTxPoolConfig::default();
+        assert!(!config.reloads_after_reorg(1));
+        assert!(!config.reloads_after_reorg(RETH_MAX_UPDATE_DEPTH));
+        assert!(config.reloads_after_reorg(RETH_MAX_UPDATE_DEPTH + 1));
+        assert!(config.reloads_after_reorg(DEFAULT_MAX_REORG_DEPTH));
+        assert!(!config.reloads_after_reorg(DEFAULT_MAX_REORG_DEPTH + 1));
+    }
+
+    #[test]
+    fn test_journal_roundtrip() {
+        let journal = temp_journal("roundtrip");
+        let txs = vec![Bytes::from_static(b"first"), Bytes::from_static(b"second")];
+
+        journal.write(&txs).unwrap();
+        assert_eq!(journal.read::<Bytes>().unwrap(), txs);
+
+        journal.write::<Bytes>(&[]).unwrap();
+        assert!(journal.read::<Bytes>().unwrap().is_empty());
+        std::fs::remove_file(journal.path()).unwrap();
+    }
+
+    #[test]
+    fn test_missing_journal_is_empty_and_corrupt_journal_errors() {
+        let journal = temp_journal("missing");
+        let _ = std::fs::remove_file(journal.path());
+        assert!(journal.read::<Bytes>().unwrap().is_empty());
+
+        std::fs::write(journal.path(), [0xff, 0x00]).unwrap();
+        assert!(journal.read::<Bytes>().is_err());
+        std::fs::remove_file(journal.path()).unwrap();
+    }
+}
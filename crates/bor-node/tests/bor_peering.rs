@@ -0,0 +1,166 @@
+//! Integration test: peering with a bor node running in a container.
+//!
+//! Starts bor (`0xpolygon/bor`, or the image in `$BOR_IMAGE`) on Amoy with discovery off,
+//! so it stays at the Amoy genesis, and dials it from a reth network using the Bor
+//! handshake on boreth's Amoy chain spec. Bor must accept the Status message, agree on
+//! one of [`BOR_ETH_VERSIONS`], serve the genesis header and body over the session and
+//! keep it open.
+//!
+//! It needs docker, so it only runs when asked for:
+//! `cargo test -p bor-node --test bor_peering -- --ignored`.
+
+use bor_chainspec::bor_amoy_genesis;
+use bor_node::handshake::{BorRlpxHandshake, BOR_ETH_VERSIONS};
+use reth_chainspec::EthChainSpec;
+use reth_eth_wire::EthNetworkPrimitives;
+use reth_network::{config::rng_secret_key, NetworkConfigBuilder, NetworkManager};
+use reth_network_api::{
+    events::{NetworkEvent, PeerEvent},
+    BlockDownloaderProvider, NetworkEventListenerProvider, Peers, PeersInfo,
+};
+use reth_network_p2p::{
+    bodies::client::BodiesClient,
+    headers::client::{HeadersClient, HeadersDirection, HeadersRequest},
+};
+use reth_network_peers::{NodeRecord, PeerId};
+use std::net::SocketAddr;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+
+const BOR_IMAGE: &str = "0xpolygon/bor:latest";
+
+/// Port bor listens on for peers inside the container.
+const BOR_P2P_PORT: u16 = 30303;
+
+/// Time bor has to open its IPC endpoint after the container starts.
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Time bor has to complete the session handshake once dialed.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the session must stay open after the requests.
+const HOLD: Duration = Duration::from_secs(10);
+
+/// Run `docker` with `args` and return its output, failing the test if it fails.
+fn docker<'a>(args: impl IntoIterator<Item = &'a str>) -> String {
+    let output = Command::new("docker").args(args).output().expect("docker is not installed");
+    assert!(output.status.success(), "docker: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// A bor container, removed when dropped.
+struct BorContainer {
+    id: String,
+}
+
+impl BorContainer {
+    fn start() -> Self {
+        let image = std::env::var("BOR_IMAGE").unwrap_or_else(|_| BOR_IMAGE.to_string());
+        let publish = format!("127.0.0.1::{BOR_P2P_PORT}");
+        let port = format!("--port={BOR_P2P_PORT}");
+        let id = docker([
+            "run",
+            "--detach",
+            "--rm",
+            "--publish",
+            &publish,
+            &image,
+            "server",
+            "--chain=amoy",
+            "--datadir=/data",
+            "--nodiscover",
+            "--bor.withoutheimdall",
+            &port,
+        ]);
+        Self { id }
+    }
+
+    /// Host address the container's peer port is published on.
+    fn p2p_addr(&self) -> SocketAddr {
+        let port = format!("{BOR_P2P_PORT}/tcp");
+        let addrs = docker(["port", &self.id, &port]);
+        addrs.lines().next().expect("peer port not published").parse().unwrap()
+    }
+
+    /// The node ID bor reports once its IPC endpoint is up.
+    async fn peer_id(&self) -> PeerId {
+        let deadline = Instant::now() + READY_TIMEOUT;
+        loop {
+            let output = Command::new("docker")
+                .args(["exec", &self.id, "bor", "attach", "/data/bor.ipc"])
+                .args(["--exec", "admin.nodeInfo.enode"])
+                .output()
+                .unwrap();
+            let enode = String::from_utf8_lossy(&output.stdout);
+            if let Ok(record) = enode.trim().trim_matches('"').parse::<NodeRecord>() {
+                return record.id;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "bor did not come up: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+impl Drop for BorContainer {
+    fn drop(&mut self) {
+        let _ = Command::new("docker").args(["rm", "--force", &self.id]).output();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "starts a bor container, needs docker"]
+async fn bor_accepts_the_bor_handshake() -> eyre::Result<()> {
+    let bor = BorContainer::start();
+    let peer_id = bor.peer_id().await;
+    let chain_spec = Arc::new(bor_amoy_genesis().into_inner());
+    let genesis_hash = chain_spec.genesis_hash();
+
+    let config = NetworkConfigBuilder::<EthNetworkPrimitives>::new(rng_secret_key())
+        .listener_port(0)
+        .disable_discovery()
+        .eth_rlpx_handshake(Arc::new(BorRlpxHandshake::default()))
+        .build_with_noop_provider(chain_spec);
+    let manager = NetworkManager::new(config).await?;
+    let network = manager.handle().clone();
+    let mut events = network.event_listener();
+    tokio::spawn(manager);
+
+    network.add_peer(peer_id, bor.p2p_addr());
+    let session = tokio::time::timeout(SESSION_TIMEOUT, async {
+        loop {
+            match events.next().await {
+                Some(NetworkEvent::ActivePeer(session)) => return session,
+                Some(NetworkEvent::Peer(PeerEvent::SessionClosed { reason, .. })) => {
+                    panic!("bor closed the session: {reason:?}")
+                }
+                Some(_) => {}
+                None => panic!("network stopped"),
+            }
+        }
+    })
+    .await?;
+    assert_eq!(session.peer_id, peer_id);
+    assert!(BOR_ETH_VERSIONS.contains(&session.version), "negotiated {}", session.version);
+    assert_eq!(session.status.genesis, genesis_hash);
+
+    let fetch = network.fetch_client().await?;
+    let request =
+        HeadersRequest { start: 0u64.into(), limit: 1, direction: HeadersDirection::Rising };
+    let headers = fetch.get_headers(request).await?.into_data();
+    assert_eq!(headers.len(), 1);
+    assert_eq!(headers[0].hash_slow(), genesis_hash);
+    let bodies = fetch.get_block_bodies(vec![genesis_hash]).await?.into_data();
+    assert_eq!(bodies.len(), 1);
+    assert!(bodies[0].transactions.is_empty());
+
+    // Bor drops peers breaching the protocol; the session must outlive the requests.
+    tokio::time::sleep(HOLD).await;
+    assert_eq!(network.num_connected_peers(), 1);
+    Ok(())
+}
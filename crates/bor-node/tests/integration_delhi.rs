@@ -2,8 +2,7 @@
 //!
 //! Verifies sprint size transition from 64 to 16 at block 38,189,056.
 
-use bor_chainspec::params::sprint_size;
-use bor_chainspec::BorHardfork;
+use bor_chainspec::{BorHardfork, BorHardforks, MainnetBorHardforks};
 use bor_consensus::block_validation::validate_block_pre_execution;
 use bor_evm::plan_system_txs;
 use bor_node::{BorNode, BorNodeConfig};
@@ -13,11 +12,11 @@ const DELHI_BLOCK: u64 = 38_189_056;
 #[test]
 fn test_delhi_sprint_size_transition() {
     // Pre-Delhi: sprint size is 64
-    assert_eq!(sprint_size(DELHI_BLOCK - 1), 64);
+    assert_eq!(MainnetBorHardforks.bor_sprint_size(DELHI_BLOCK - 1), 64);
     // At Delhi: sprint size is 16
-    assert_eq!(sprint_size(DELHI_BLOCK), 16);
+    assert_eq!(MainnetBorHardforks.bor_sprint_size(DELHI_BLOCK), 16);
     // Post-Delhi: sprint size is 16
-    assert_eq!(sprint_size(DELHI_BLOCK + 100), 16);
+    assert_eq!(MainnetBorHardforks.bor_sprint_size(DELHI_BLOCK + 100), 16);
 }
 
 #[test]
@@ -26,7 +25,7 @@ fn test_delhi_boundary_blocks() {
 
     // Simulate blocks around Delhi boundary
     for block in (DELHI_BLOCK - 16)..=(DELHI_BLOCK + 16) {
-        let current_sprint_size = sprint_size(block);
+        let current_sprint_size = MainnetBorHardforks.bor_sprint_size(block);
 
         // Pre-execution validation should pass for normal blocks
         validate_block_pre_execution(
@@ -2,8 +2,7 @@
 //!
 //! Verifies span size transition from 6400 to 1600 at block 77,414,656.
 
-use bor_chainspec::params::{span_size, sprint_size};
-use bor_chainspec::BorHardfork;
+use bor_chainspec::{BorHardfork, BorHardforks, MainnetBorHardforks};
 use bor_consensus::block_validation::validate_block_pre_execution;
 use bor_evm::plan_system_txs;
 use bor_node::{BorNode, BorNodeConfig};
@@ -13,11 +12,11 @@ const RIO_BLOCK: u64 = 77_414_656;
 #[test]
 fn test_rio_span_size_transition() {
     // Pre-Rio: span size is 6400
-    assert_eq!(span_size(RIO_BLOCK - 1), 6400);
+    assert_eq!(MainnetBorHardforks.bor_span_size(RIO_BLOCK - 1), 6400);
     // At Rio: span size is 1600
-    assert_eq!(span_size(RIO_BLOCK), 1600);
+    assert_eq!(MainnetBorHardforks.bor_span_size(RIO_BLOCK), 1600);
     // Post-Rio: span size is 1600
-    assert_eq!(span_size(RIO_BLOCK + 1000), 1600);
+    assert_eq!(MainnetBorHardforks.bor_span_size(RIO_BLOCK + 1000), 1600);
 }
 
 #[test]
@@ -26,8 +25,8 @@ fn test_rio_boundary_blocks() {
 
     // Simulate blocks around Rio boundary
     for block in (RIO_BLOCK - 8)..=(RIO_BLOCK + 8) {
-        let current_sprint_size = sprint_size(block);
-        let current_span_size = span_size(block);
+        let current_sprint_size = MainnetBorHardforks.bor_sprint_size(block);
+        let current_span_size = MainnetBorHardforks.bor_span_size(block);
 
         // Pre-execution validation should pass
         // At span boundaries, we'd need validators in extra data,
@@ -6,7 +6,7 @@
 //! - Tests both pre and post Madhugiri paths
 
 use alloy_primitives::{Address, B256, Bytes, U256, keccak256};
-use bor_chainspec::params::{sprint_size, span_size};
+use bor_chainspec::{BorHardforks, MainnetBorHardforks};
 use bor_consensus::block_validation::{validate_block_pre_execution, validate_block_post_execution};
 use bor_consensus::difficulty::calculate_difficulty;
 use bor_evm::{plan_system_txs, execute_system_tx_plan};
@@ -31,8 +31,8 @@ fn test_1000_consecutive_blocks_post_lisovo() {
     let mut state_sync_count = 0u64;
 
     for block in start_block..end_block {
-        let current_sprint_size = sprint_size(block);
-        let current_span_size = span_size(block);
+        let current_sprint_size = MainnetBorHardforks.bor_sprint_size(block);
+        let current_span_size = MainnetBorHardforks.bor_span_size(block);
         let signer_idx = (block as usize) % validators.len();
 
         // 1. Calculate difficulty
@@ -0,0 +1,155 @@
+//! Integration test: an Amoy follower and a custom devnet node in one process.
+//!
+//! Each node is built from its own config, chain spec and [`BorParams`]. State shared
+//! behind their backs, through a static or the environment, shows up here as one node
+//! seeing the other's changes. [`launched_nodes_serve_their_own_chains`] goes further and
+//! launches a reth node for each in the same runtime.
+
+use alloy_primitives::{Address, B256, U256};
+use bor_chainspec::bor_genesis_chainspec;
+use bor_consensus::BorSnapshot;
+use bor_node::{BorNode, BorNodeConfig, BorParams};
+use bor_primitives::ValidatorSet;
+use heimdall_client::{HttpHeimdallClient, Milestone};
+use reth_chainspec::{ChainSpec, EthChainSpec};
+use reth_network::NetworkInfo;
+use reth_node_builder::NodeBuilder;
+use reth_node_core::node_config::NodeConfig;
+use reth_node_ethereum::EthereumNode;
+use reth_provider::{BlockHashReader, ChainSpecProvider};
+use reth_tasks::TaskManager;
+use std::sync::Arc;
+
+const DEVNET_CHAIN_ID: u64 = 1337;
+
+fn amoy_follower() -> BorNode {
+    let config = BorNodeConfig::amoy();
+    let heimdall = HttpHeimdallClient::new(config.heimdall_url.as_str());
+    BorNode::new(config).unwrap().with_params(BorParams::new(heimdall, None))
+}
+
+fn devnet_validator(signer: Address) -> BorNode {
+    let genesis = serde_json::from_value(serde_json::json!({
+        "config": { "chainId": DEVNET_CHAIN_ID, "bor": { "sprint": { "0": 4 } } },
+        "alloc": {}
+    }))
+    .unwrap();
+    let chain_spec = Arc::new(bor_genesis_chainspec(genesis).unwrap());
+    let config = BorNodeConfig::custom(DEVNET_CHAIN_ID);
+    let heimdall = HttpHeimdallClient::new(config.heimdall_url.as_str());
+    BorNode::with_chain_spec(config, chain_spec)
+        .unwrap()
+        .with_params(BorParams::new(heimdall, Some(signer)))
+}
+
+fn no_validators() -> ValidatorSet {
+    ValidatorSet { validators: vec![], proposer: None }
+}
+
+fn milestone(end_block: u64) -> Milestone {
+    Milestone {
+        milestone_id: format!("milestone-{end_block}"),
+        start_block: 1,
+        end_block,
+        hash: B256::with_last_byte(end_block as u8),
+        proposer: Address::ZERO,
+    }
+}
+
+#[test]
+fn nodes_run_their_own_chains() {
+    let amoy = amoy_follower();
+    let devnet = devnet_validator(Address::with_last_byte(1));
+
+    assert_eq!(amoy.chain_id(), 80002);
+    assert_eq!(devnet.chain_id(), DEVNET_CHAIN_ID);
+    assert_ne!(amoy.chain_spec.inner().genesis_hash(), devnet.chain_spec.inner().genesis_hash());
+}
+
+#[test]
+fn params_are_not_shared() {
+    let amoy = amoy_follower();
+    let devnet = devnet_validator(Address::with_last_byte(1));
+    let (amoy_params, devnet_params) = (amoy.params.unwrap(), devnet.params.unwrap());
+
+    devnet_params.set_signer(Address::with_last_byte(2));
+    assert_eq!(amoy_params.signer(), None);
+    assert_eq!(devnet_params.signer(), Some(Address::with_last_byte(2)));
+
+    let amoy_url = amoy_params.heimdall_url();
+    devnet_params.set_heimdall_url(&"http://localhost:26657".parse().unwrap());
+    assert_eq!(amoy_params.heimdall_url(), amoy_url);
+    assert_ne!(devnet_params.heimdall_url(), amoy_url);
+}
+
+#[test]
+fn stores_are_not_shared() {
+    let amoy = amoy_follower();
+    let devnet = devnet_validator(Address::with_last_byte(1));
+
+    let hash = B256::repeat_byte(0xab);
+    devnet.put_snapshot(hash, &BorSnapshot::new(4, hash, no_validators())).unwrap();
+    assert!(devnet.get_snapshot(&hash).unwrap().is_some());
+    assert!(amoy.get_snapshot(&hash).unwrap().is_none());
+
+    assert!(amoy.milestones.update(milestone(64)));
+    assert_eq!(amoy.milestones.finalized_block(), Some(64));
+    assert_eq!(devnet.milestones.finalized_block(), None);
+}
+
+#[test]
+fn nodes_run_side_by_side() {
+    let nodes = [amoy_follower(), devnet_validator(Address::with_last_byte(1))];
+    std::thread::scope(|scope| {
+        for (index, node) in nodes.iter().enumerate() {
+            scope.spawn(move || {
+                for number in 0..64u64 {
+                    let hash = B256::from(U256::from(number * 2 + index as u64));
+                    let snapshot = BorSnapshot::new(number, hash, no_validators());
+                    node.put_snapshot(hash, &snapshot).unwrap();
+                }
+            });
+        }
+    });
+
+    // Each node holds exactly the snapshots it wrote.
+    for (index, node) in nodes.iter().enumerate() {
+        for number in 0..64u64 {
+            let own = B256::from(U256::from(number * 2 + index as u64));
+            let other = B256::from(U256::from(number * 2 + 1 - index as u64));
+            assert_eq!(node.get_snapshot(&own).unwrap().unwrap().number, number);
+            assert!(node.get_snapshot(&other).unwrap().is_none());
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn launched_nodes_serve_their_own_chains() -> eyre::Result<()> {
+    let nodes = [amoy_follower(), devnet_validator(Address::with_last_byte(1))];
+    let tasks = TaskManager::current();
+    let launch = |chain_spec: ChainSpec| {
+        // Each node gets a fresh temporary datadir and its own ports.
+        let config = NodeConfig::new(Arc::new(chain_spec)).with_unused_ports();
+        let executor = tasks.executor();
+        async move {
+            NodeBuilder::new(config)
+                .testing_node(executor)
+                .node(EthereumNode::default())
+                .launch()
+                .await
+        }
+    };
+
+    let mut handles = Vec::new();
+    for node in &nodes {
+        handles.push(launch(node.chain_spec.inner().clone()).await?);
+    }
+
+    for (node, handle) in nodes.iter().zip(&handles) {
+        let provider = &handle.node.provider;
+        assert_eq!(provider.chain_spec().chain().id(), node.chain_id());
+        assert_eq!(provider.block_hash(0)?, Some(node.chain_spec.inner().genesis_hash()));
+    }
+    assert_ne!(handles[0].node.network.local_addr(), handles[1].node.network.local_addr());
+    Ok(())
+}
@@ -0,0 +1,335 @@
+//! Payload builder attributes carrying the Bor fields of the block being built.
+//!
+//! Ethereum payload attributes name the timestamp, fee recipient and randomness of
+//! a block. A Bor builder also needs the producer it seals for, whether the block
+//! starts or ends a sprint and the span it belongs to; [`BorPayloadBuilderAttributes`]
+//! resolves them once, from the fork schedule, when the build is requested.
+//!
+//! The engine receives them as [`BorPayloadAttributes`], Ethereum's attributes with the
+//! Bor fields added, and hands the payload builder the [`BorPayloadBuilderAttributes`]
+//! rebuilt from them through reth's [`PayloadBuilderAttributes`].
+
+use alloy_eips::eip4895::{Withdrawal, Withdrawals};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_rpc_types_engine::{PayloadAttributes, PayloadId};
+use bor_chainspec::BorHardforks;
+use bor_evm::difficulty_word;
+use reth_payload_primitives::PayloadBuilderAttributes;
+use serde::{Deserialize, Serialize};
+
+use crate::builder::PayloadConfig;
+
+/// Attributes of a Bor payload build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorPayloadBuilderAttributes {
+    /// Hash of the parent block.
+    pub parent: B256,
+    /// Number of the block to build.
+    pub block_number: u64,
+    /// Block timestamp.
+    pub timestamp: u64,
+    /// Producer the block is sealed for; fees are paid to it.
+    pub producer: Address,
+    /// Difficulty the block carries for the producer's succession.
+    pub difficulty: U256,
+    /// Sprint size at this block.
+    pub sprint_size: u64,
+    /// Length of the span the block belongs to.
+    pub span_size: u64,
+    /// Whether the block starts a sprint, so state sync events are committed in it.
+    pub sprint_start: bool,
+    /// Whether the block ends a sprint, so its extra data lists the next validators.
+    pub sprint_end: bool,
+    /// Heimdall span the block belongs to.
+    pub span_id: u64,
+    /// Always empty: Bor has no withdrawals.
+    withdrawals: Withdrawals,
+}
+
+impl BorPayloadBuilderAttributes {
+    /// Attributes of block `block_number` on top of `parent`, with the sprint and span
+    /// sizes `hardforks` sets there.
+    pub fn new(
+        hardforks: &impl BorHardforks,
+        parent: B256,
+        block_number: u64,
+        timestamp: u64,
+        producer: Address,
+        difficulty: U256,
+    ) -> Self {
+        let span_id = hardforks.bor_span_id(block_number);
+        // The span's own length: Rio shortens spans only from the first one starting
+        // after it, which the size at the block alone does not tell.
+        let span_size = hardforks.bor_span_start(span_id + 1) - hardforks.bor_span_start(span_id);
+        Self::with_sizes(
+            parent,
+            block_number,
+            timestamp,
+            producer,
+            difficulty,
+            hardforks.bor_sprint_size(block_number),
+            span_size,
+            span_id,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn with_sizes(
+        parent: B256,
+        block_number: u64,
+        timestamp: u64,
+        producer: Address,
+        difficulty: U256,
+        sprint_size: u64,
+        span_size: u64,
+        span_id: u64,
+    ) -> Self {
+        Self {
+            parent,
+            block_number,
+            timestamp,
+            producer,
+            difficulty,
+            sprint_size,
+            span_size,
+            sprint_start: block_number > 0 && block_number % sprint_size == 0,
+            sprint_end: (block_number + 1) % sprint_size == 0,
+            span_id,
+            withdrawals: Withdrawals::default(),
+        }
+    }
+
+    /// The Ethereum payload attributes the engine starts the build with.
+    ///
+    /// Bor headers carry a zero coinbase and mix hash; fees are paid to the producer,
+    /// and `prev_randao` carries the block's difficulty.
+    pub fn payload_attributes(&self) -> PayloadAttributes {
+        PayloadAttributes {
+            timestamp: self.timestamp,
+            prev_randao: difficulty_word(self.difficulty),
+            suggested_fee_recipient: self.producer,
+            withdrawals: None,
+            parent_beacon_block_root: None,
+        }
+    }
+
+    /// The attributes the engine is asked to build with, carrying the Bor fields.
+    pub fn engine_attributes(&self) -> BorPayloadAttributes {
+        BorPayloadAttributes {
+            payload_attributes: self.payload_attributes(),
+            block_number: self.block_number,
+            sprint_size: self.sprint_size,
+            span_size: self.span_size,
+            span_id: self.span_id,
+        }
+    }
+}
+
+/// Payload attributes of a Bor build as the engine receives them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BorPayloadAttributes {
+    /// The Ethereum attributes: the fee recipient is the producer, `prev_randao` the
+    /// difficulty.
+    #[serde(flatten)]
+    pub payload_attributes: PayloadAttributes,
+    /// Number of the block to build.
+    pub block_number: u64,
+    /// Sprint size at this block.
+    pub sprint_size: u64,
+    /// Length of the span the block belongs to.
+    pub span_size: u64,
+    /// Heimdall span the block belongs to.
+    pub span_id: u64,
+}
+
+impl reth_payload_primitives::PayloadAttributes for BorPayloadAttributes {
+    fn timestamp(&self) -> u64 {
+        self.payload_attributes.timestamp
+    }
+
+    fn withdrawals(&self) -> Option<&Vec<Withdrawal>> {
+        self.payload_attributes.withdrawals.as_ref()
+    }
+
+    fn parent_beacon_block_root(&self) -> Option<B256> {
+        self.payload_attributes.parent_beacon_block_root
+    }
+}
+
+/// Why engine attributes cannot start a Bor build.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BorAttributesError {
+    /// A sprint size of zero cannot place the block in a sprint.
+    #[error("zero sprint size for block {0}")]
+    ZeroSprintSize(u64),
+    /// Bor blocks carry no withdrawals.
+    #[error("withdrawals in the attributes of block {0}")]
+    Withdrawals(u64),
+}
+
+impl PayloadBuilderAttributes for BorPayloadBuilderAttributes {
+    type RpcPayloadAttributes = BorPayloadAttributes;
+    type Error = BorAttributesError;
+
+    fn try_new(
+        parent: B256,
+        attributes: BorPayloadAttributes,
+        _version: u8,
+    ) -> Result<Self, Self::Error> {
+        let number = attributes.block_number;
+        if attributes.sprint_size == 0 {
+            return Err(BorAttributesError::ZeroSprintSize(number));
+        }
+        let eth = attributes.payload_attributes;
+        if eth.withdrawals.as_ref().is_some_and(|w| !w.is_empty()) {
+            return Err(BorAttributesError::Withdrawals(number));
+        }
+        Ok(Self::with_sizes(
+            parent,
+            number,
+            eth.timestamp,
+            eth.suggested_fee_recipient,
+            eth.prev_randao.into(),
+            attributes.sprint_size,
+            attributes.span_size,
+            attributes.span_id,
+        ))
+    }
+
+    /// Derived from the parent and everything the built block depends on, so the same
+    /// build requested twice is one job.
+    fn payload_id(&self) -> PayloadId {
+        let mut preimage = Vec::with_capacity(32 + 8 + 8 + 20 + 32);
+        preimage.extend_from_slice(self.parent.as_slice());
+        preimage.extend_from_slice(&self.block_number.to_be_bytes());
+        preimage.extend_from_slice(&self.timestamp.to_be_bytes());
+        preimage.extend_from_slice(self.producer.as_slice());
+        preimage.extend_from_slice(&self.difficulty.to_be_bytes::<32>());
+        let hash = keccak256(preimage);
+        PayloadId::new(hash[..8].try_into().expect("8 bytes"))
+    }
+
+    fn parent(&self) -> B256 {
+        self.parent
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn parent_beacon_block_root(&self) -> Option<B256> {
+        None
+    }
+
+    fn suggested_fee_recipient(&self) -> Address {
+        self.producer
+    }
+
+    fn prev_randao(&self) -> B256 {
+        difficulty_word(self.difficulty)
+    }
+
+    fn withdrawals(&self) -> &Withdrawals {
+        &self.withdrawals
+    }
+}
+
+impl PayloadConfig {
+    /// Configuration for building the block described by `attributes` under `gas_limit`,
+    /// with no span or state sync events pending.
+    pub fn from_attributes(attributes: &BorPayloadBuilderAttributes, gas_limit: u64) -> Self {
+        Self {
+            block_number: attributes.block_number,
+            gas_limit,
+            sprint_size: attributes.sprint_size,
+            span_size: attributes.span_size,
+            producer: attributes.producer,
+            timestamp: attributes.timestamp,
+            has_pending_span: false,
+            pending_span_id: None,
+            pending_validator_bytes: None,
+            pending_state_sync_events: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::MainnetBorHardforks;
+
+    fn attributes(block_number: u64) -> BorPayloadBuilderAttributes {
+        BorPayloadBuilderAttributes::new(
+            &MainnetBorHardforks,
+            B256::with_last_byte(1),
+            block_number,
+            1_000,
+            Address::repeat_byte(0xaa),
+            U256::from(7),
+        )
+    }
+
+    #[test]
+    fn test_sprint_boundaries_follow_fork_schedule() {
+        // 64-block sprints before Delhi, 16-block sprints after it.
+        let start = attributes(64);
+        assert!(start.sprint_start && !start.sprint_end);
+        assert!(attributes(63).sprint_end);
+        assert!(!attributes(16).sprint_start);
+
+        let delhi = attributes(38_189_056);
+        assert_eq!(delhi.sprint_size, 16);
+        assert!(delhi.sprint_start);
+        assert!(attributes(38_189_071).sprint_end);
+        assert!(!attributes(0).sprint_start);
+    }
+
+    #[test]
+    fn test_span_id_and_size_of_the_same_span() {
+        assert_eq!((attributes(255).span_id, attributes(255).span_size), (0, 256));
+        assert_eq!((attributes(256).span_id, attributes(256).span_size), (1, 6400));
+        let rio = bor_chainspec::BorHardfork::Rio.mainnet_block();
+        assert_eq!(attributes(rio - 1).span_size, 6400);
+        assert_eq!(attributes(rio).span_size, 1600);
+    }
+
+    #[test]
+    fn test_payload_attributes_pay_the_producer() {
+        let attributes = attributes(100);
+        let payload = attributes.payload_attributes();
+        assert_eq!(payload.suggested_fee_recipient, Address::repeat_byte(0xaa));
+        assert_eq!(payload.prev_randao, B256::with_last_byte(7));
+        assert_eq!(payload.timestamp, 1_000);
+    }
+
+    #[test]
+    fn test_builder_attributes_rebuilt_from_engine_attributes() {
+        let attributes = attributes(38_189_071);
+        let engine = attributes.engine_attributes();
+        let json = serde_json::to_value(&engine).unwrap();
+        assert_eq!(json["spanId"], attributes.span_id);
+        assert_eq!(json["suggestedFeeRecipient"], "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+        let engine: BorPayloadAttributes = serde_json::from_value(json).unwrap();
+
+        let rebuilt = BorPayloadBuilderAttributes::try_new(attributes.parent, engine, 1).unwrap();
+        assert_eq!(rebuilt, attributes);
+        assert!(rebuilt.sprint_end);
+        assert_eq!(rebuilt.payload_id(), attributes.payload_id());
+        assert_ne!(rebuilt.payload_id(), self::attributes(38_189_072).payload_id());
+
+        let mut zero = attributes.engine_attributes();
+        zero.sprint_size = 0;
+        let err = BorPayloadBuilderAttributes::try_new(attributes.parent, zero, 1).unwrap_err();
+        assert_eq!(err, BorAttributesError::ZeroSprintSize(38_189_071));
+    }
+
+    #[test]
+    fn test_payload_config_from_attributes() {
+        let config = PayloadConfig::from_attributes(&attributes(64), 30_000_000);
+        assert_eq!(config.block_number, 64);
+        assert_eq!(config.sprint_size, 64);
+        assert_eq!(config.producer, Address::repeat_byte(0xaa));
+        assert!(config.pending_state_sync_events.is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+//! Time-budgeted transaction selection.
+//!
+//! A Bor producer must have its block sealed by the header timestamp, which is
+//! only one block period (2s) after the parent mid-sprint. Selection therefore
+//! runs against a [`BuildBudget`] that ends a safety margin before that moment:
+//! transactions are executed in order of effective tip, and the loop stops as
+//! soon as the remaining time is shorter than the slowest transaction seen so
+//! far, rather than overrunning the slot.
+//!
+//! [`select_transactions`] runs that loop over a list of candidates; a builder that
+//! pulls its candidates from elsewhere, such as the transaction pool, applies the
+//! same rule with a [`SelectionClock`].
+
+use crate::builder::PayloadTx;
+use reth_metrics::{
+    metrics::{Counter, Histogram},
+    Metrics,
+};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Default time reserved between the end of transaction selection and the block timestamp,
+/// for system transactions, state root computation and sealing.
+pub const DEFAULT_BUILD_MARGIN: Duration = Duration::from_millis(500);
+
+/// The time window available for selecting transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildBudget {
+    started: Instant,
+    deadline: Instant,
+}
+
+impl BuildBudget {
+    /// A budget running from `started` until `deadline`.
+    pub fn new(started: Instant, deadline: Instant) -> Self {
+        Self { started, deadline: deadline.max(started) }
+    }
+
+    /// A budget starting now and ending at `deadline`.
+    pub fn until(deadline: Instant) -> Self {
+        Self::new(Instant::now(), deadline)
+    }
+
+    /// A budget ending `margin` before the block timestamp (unix seconds).
+    ///
+    /// If that moment has already passed, the budget is empty.
+    pub fn for_block_time(block_timestamp: u64, margin: Duration) -> Self {
+        Self::for_block_time_at(block_timestamp, margin, SystemTime::now(), Instant::now())
+    }
+
+    /// [`for_block_time`](Self::for_block_time) with the clocks read at `now_unix` and
+    /// `now`.
+    fn for_block_time_at(
+        block_timestamp: u64,
+        margin: Duration,
+        now_unix: SystemTime,
+        now: Instant,
+    ) -> Self {
+        let now_unix = now_unix.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let stop_at = Duration::from_secs(block_timestamp).saturating_sub(margin);
+        Self::new(now, now + stop_at.saturating_sub(now_unix))
+    }
+
+    /// When the budget runs out.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Total length of the budget.
+    pub fn total(&self) -> Duration {
+        self.deadline - self.started
+    }
+
+    /// Time left at `now`.
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+}
+
+/// Why transaction selection ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every candidate was either included or skipped for gas.
+    Exhausted,
+    /// The deadline was too close to execute another transaction.
+    Deadline,
+}
+
+/// Times a selection loop against its [`BuildBudget`]: each transaction runs from the
+/// moment it is started until the next one is, and no transaction is started once the
+/// time left is shorter than the slowest so far.
+#[derive(Debug, Clone)]
+pub struct SelectionClock {
+    budget: BuildBudget,
+    started: Instant,
+    /// Start of the transaction running, if any.
+    running: Option<Instant>,
+    slowest: Duration,
+}
+
+impl SelectionClock {
+    /// Start timing a selection against `budget`.
+    pub fn new(budget: BuildBudget) -> Self {
+        Self { budget, started: Instant::now(), running: None, slowest: Duration::ZERO }
+    }
+
+    /// Start the next transaction at `now`, ending the one running, unless the deadline
+    /// is too close for it. Returns whether it may run.
+    pub fn try_start(&mut self, now: Instant) -> bool {
+        self.finish(now);
+        if self.budget.remaining(now) <= self.slowest {
+            return false;
+        }
+        self.running = Some(now);
+        true
+    }
+
+    /// End the transaction running at `now`, if any.
+    pub fn finish(&mut self, now: Instant) {
+        if let Some(started) = self.running.take() {
+            self.slowest = self.slowest.max(now.saturating_duration_since(started));
+        }
+    }
+
+    /// Time spent selecting so far.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Fraction of the budget spent selecting so far. Can exceed 1 if a transaction
+    /// overran the deadline.
+    pub fn utilization(&self) -> f64 {
+        utilization(self.elapsed(), &self.budget)
+    }
+}
+
+/// Outcome of [`select_transactions`].
+#[derive(Debug, Clone)]
+pub struct TxSelection {
+    /// Included transactions, in execution order.
+    pub selected: Vec<PayloadTx>,
+    /// Gas used by the included transactions.
+    pub gas_used: u64,
+    /// Candidates skipped because they did not fit in the remaining gas.
+    pub skipped: usize,
+    /// Why selection ended.
+    pub stop: StopReason,
+    /// Time spent selecting.
+    pub elapsed: Duration,
+}
+
+impl TxSelection {
+    /// Fraction of `budget` spent selecting. Can exceed 1 if a transaction overran the deadline.
+    pub fn utilization(&self, budget: &BuildBudget) -> f64 {
+        utilization(self.elapsed, budget)
+    }
+}
+
+/// Fraction of `budget` that `elapsed` spends. An empty budget is all spent.
+fn utilization(elapsed: Duration, budget: &BuildBudget) -> f64 {
+    let total = budget.total();
+    if total.is_zero() {
+        return 1.0;
+    }
+    elapsed.as_secs_f64() / total.as_secs_f64()
+}
+
+/// Payload builder metrics.
+#[derive(Metrics)]
+#[metrics(scope = "bor.payload_builder")]
+struct SelectionMetrics {
+    /// Fraction of the build budget spent selecting transactions.
+    budget_utilization: Histogram,
+    /// Number of transactions included per block.
+    selected_txs: Histogram,
+    /// Number of blocks whose selection was cut off by the deadline.
+    deadline_cutoffs: Counter,
+}
+
+/// Record a selection of `selected` transactions that spent `utilization` of its budget
+/// and ended for `stop`.
+pub fn record_selection(selected: usize, utilization: f64, stop: StopReason) {
+    let metrics = SelectionMetrics::default();
+    metrics.budget_utilization.record(utilization);
+    metrics.selected_txs.record(selected as f64);
+    if stop == StopReason::Deadline {
+        metrics.deadline_cutoffs.increment(1);
+    }
+}
+
+/// Select user transactions by descending effective tip within `gas_limit` and `budget`.
+///
+/// `execute` runs a transaction against the pending block state; its duration is what the
+/// budget is spent on. Transactions that do not fit in the remaining gas are skipped, and
+/// equal tips keep their original order.
+pub fn select_transactions(
+    mut txs: Vec<PayloadTx>,
+    gas_limit: u64,
+    budget: &BuildBudget,
+    mut execute: impl FnMut(&PayloadTx),
+) -> TxSelection {
+    let mut clock = SelectionClock::new(*budget);
+    txs.sort_by(|a, b| b.effective_tip.cmp(&a.effective_tip));
+
+    let mut selection = TxSelection {
+        selected: Vec::new(),
+        gas_used: 0,
+        skipped: 0,
+        stop: StopReason::Exhausted,
+        elapsed: Duration::ZERO,
+    };
+
+    for tx in txs {
+        if selection.gas_used + tx.gas_used > gas_limit {
+            selection.skipped += 1;
+            continue;
+        }
+        if !clock.try_start(Instant::now()) {
+            selection.stop = StopReason::Deadline;
+            break;
+        }
+
+        execute(&tx);
+        clock.finish(Instant::now());
+        selection.gas_used += tx.gas_used;
+        selection.selected.push(tx);
+    }
+
+    selection.elapsed = clock.elapsed();
+    record_selection(selection.selected.len(), selection.utilization(budget), selection.stop);
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    fn tx(gas: u64, tip: u128) -> PayloadTx {
+        PayloadTx {
+            data: Bytes::from(tip.to_be_bytes().to_vec()),
+            gas_used: gas,
+            is_system_tx: false,
+            effective_tip: tip,
+        }
+    }
+
+    fn generous() -> BuildBudget {
+        BuildBudget::until(Instant::now() + Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_orders_by_tip_and_skips_oversized() {
+        let txs = vec![tx(4_000, 1), tx(50_000, 5), tx(21_000, 3), tx(21_000, 3)];
+        let mut executed = 0;
+        let selection = select_transactions(txs, 75_000, &generous(), |_| executed += 1);
+
+        let tips: Vec<u128> = selection.selected.iter().map(|t| t.effective_tip).collect();
+        // The second tip-3 transaction no longer fits, but the cheaper tip-1 one still does.
+        assert_eq!(tips, vec![5, 3, 1]);
+        assert_eq!(executed, 3);
+        assert_eq!(selection.gas_used, 75_000);
+        assert_eq!(selection.skipped, 1);
+        assert_eq!(selection.stop, StopReason::Exhausted);
+    }
+
+    #[test]
+    fn test_expired_budget_selects_nothing() {
+        let now = Instant::now();
+        let budget = BuildBudget::new(now, now);
+        let selection = select_transactions(vec![tx(21_000, 1)], 30_000_000, &budget, |_| {
+            panic!("no transaction may run after the deadline")
+        });
+        assert!(selection.selected.is_empty());
+        assert_eq!(selection.stop, StopReason::Deadline);
+        assert_eq!(selection.utilization(&budget), 1.0);
+    }
+
+    #[test]
+    fn test_stops_before_deadline() {
+        let budget = BuildBudget::until(Instant::now() + Duration::from_millis(40));
+        let txs = (0..20).map(|i| tx(21_000, i)).collect();
+        let selection = select_transactions(txs, 30_000_000, &budget, |_| {
+            std::thread::sleep(Duration::from_millis(10))
+        });
+
+        assert_eq!(selection.stop, StopReason::Deadline);
+        assert!(!selection.selected.is_empty());
+        assert!(selection.selected.len() < 20);
+        // The loop never starts a transaction it expects to overrun.
+        assert!(selection.elapsed < Duration::from_millis(40) + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_clock_keeps_the_slowest_transaction_clear_of_the_deadline() {
+        let now = Instant::now();
+        let ms = Duration::from_millis;
+        let mut clock = SelectionClock::new(BuildBudget::new(now, now + ms(100)));
+
+        assert!(clock.try_start(now));
+        // The first transaction took 30ms: one more fits at 60ms, none at 70ms.
+        assert!(clock.try_start(now + ms(30)));
+        clock.finish(now + ms(40));
+        assert!(clock.try_start(now + ms(60)));
+        assert!(!clock.try_start(now + ms(70)));
+    }
+
+    #[test]
+    fn test_budget_ends_the_margin_before_block_time() {
+        let now = Instant::now();
+        let at = |millis: u64| UNIX_EPOCH + Duration::from_millis(millis);
+
+        let budget = BuildBudget::for_block_time_at(1_000, DEFAULT_BUILD_MARGIN, at(998_250), now);
+        assert_eq!(budget.total(), Duration::from_millis(1_250));
+        assert_eq!(budget.deadline(), now + Duration::from_millis(1_250));
+        assert_eq!(budget.remaining(now + Duration::from_secs(1)), Duration::from_millis(250));
+        assert_eq!(budget.remaining(now + Duration::from_secs(2)), Duration::ZERO);
+
+        // Past the margin, and past the block time, nothing is left.
+        let late = BuildBudget::for_block_time_at(1_000, DEFAULT_BUILD_MARGIN, at(999_600), now);
+        assert_eq!(late.total(), Duration::ZERO);
+        let past = BuildBudget::for_block_time_at(1, DEFAULT_BUILD_MARGIN, at(1_000_000), now);
+        assert_eq!(past.remaining(now), Duration::ZERO);
+    }
+}
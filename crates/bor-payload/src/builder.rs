@@ -4,8 +4,11 @@
 //! sprint/span boundaries, and constructs the complete block payload.
 
 use alloy_primitives::{Address, Bytes, U256};
+use bor_consensus::extra_data::{ExtraDataBuilder, ExtraDataError};
 use bor_evm::{plan_system_txs, execute_system_tx_plan, SystemCallRecord};
 
+use crate::budget::{select_transactions, BuildBudget, StopReason};
+
 /// Configuration for building a payload.
 #[derive(Debug, Clone)]
 pub struct PayloadConfig {
@@ -40,6 +43,8 @@ pub struct PayloadTx {
     pub gas_used: u64,
     /// Whether this is a system transaction.
     pub is_system_tx: bool,
+    /// Effective miner tip per gas, used to prioritize selection. Zero for system transactions.
+    pub effective_tip: u128,
 }
 
 /// A built payload ready for sealing.
@@ -57,6 +62,8 @@ pub struct BuiltPayload {
     pub commit_span_executed: bool,
     /// Number of state sync events included.
     pub state_sync_count: usize,
+    /// Whether user transaction selection was cut short by the build deadline.
+    pub deadline_cutoff: bool,
 }
 
 /// Bor payload builder.
@@ -83,6 +90,35 @@ impl BorPayloadBuilder {
             transactions.push(tx);
         }
 
+        Self::finish(config, transactions, total_gas_used, false)
+    }
+
+    /// Build a payload, selecting user transactions by effective tip within `budget`.
+    ///
+    /// `execute` runs each selected transaction against the pending state. Selection stops
+    /// early when the deadline approaches; system transactions are always appended.
+    pub fn build_with_budget(
+        config: &PayloadConfig,
+        user_txs: Vec<PayloadTx>,
+        budget: &BuildBudget,
+        execute: impl FnMut(&PayloadTx),
+    ) -> BuiltPayload {
+        let selection = select_transactions(user_txs, config.gas_limit, budget, execute);
+        Self::finish(
+            config,
+            selection.selected,
+            selection.gas_used,
+            selection.stop == StopReason::Deadline,
+        )
+    }
+
+    /// Append the system transactions due at this block to the user transactions.
+    fn finish(
+        config: &PayloadConfig,
+        mut transactions: Vec<PayloadTx>,
+        total_gas_used: u64,
+        deadline_cutoff: bool,
+    ) -> BuiltPayload {
         // 2. Plan system transactions
         let plan = plan_system_txs(
             config.block_number,
@@ -105,6 +141,7 @@ impl BorPayloadBuilder {
                 data: call.data.clone(),
                 gas_used: 0, // System txs use 0 gas
                 is_system_tx: true,
+                effective_tip: 0,
             });
         }
 
@@ -115,7 +152,25 @@ impl BorPayloadBuilder {
             system_calls: result.system_calls,
             commit_span_executed: result.commit_span_executed,
             state_sync_count: result.state_sync_count,
+            deadline_cutoff,
+        }
+    }
+
+    /// Build the header extra data for the block described by `config`.
+    ///
+    /// `validator_bytes` is only accepted when the block ends a sprint. The
+    /// seal is left zeroed for the sealer.
+    pub fn build_extra_data(
+        config: &PayloadConfig,
+        vanity: &[u8],
+        validator_bytes: Option<&[u8]>,
+    ) -> Result<Bytes, ExtraDataError> {
+        let mut builder =
+            ExtraDataBuilder::new(config.block_number, config.sprint_size).with_vanity(vanity);
+        if let Some(bytes) = validator_bytes {
+            builder = builder.with_validator_bytes(bytes);
         }
+        builder.build()
     }
 }
 
@@ -143,6 +198,7 @@ mod tests {
             data: Bytes::from_static(b"user_tx"),
             gas_used: gas,
             is_system_tx: false,
+            effective_tip: 0,
         }
     }
 
@@ -238,6 +294,40 @@ mod tests {
         assert!(payload.transactions.last().unwrap().is_system_tx);
     }
 
+    #[test]
+    fn test_build_extra_data() {
+        let extra = BorPayloadBuilder::build_extra_data(&make_config(15), b"boreth", Some(&[0xbb; 20]))
+            .unwrap();
+        assert_eq!(extra.len(), 32 + 20 + 65);
+        assert_eq!(&extra[..6], b"boreth");
+
+        let err = BorPayloadBuilder::build_extra_data(&make_config(5), b"", Some(&[0xbb; 20]))
+            .unwrap_err();
+        assert!(matches!(err, ExtraDataError::ValidatorsOutsideSprintEnd(5)));
+    }
+
+    #[test]
+    fn test_build_with_budget_keeps_system_txs_after_cutoff() {
+        use std::time::Instant;
+
+        let mut config = make_config(16);
+        config.pending_state_sync_events = vec![(U256::from(1), Bytes::from_static(b"sync"))];
+        let now = Instant::now();
+        let expired = BuildBudget::new(now, now);
+
+        let payload = BorPayloadBuilder::build_with_budget(
+            &config,
+            vec![make_user_tx(21000)],
+            &expired,
+            |_| {},
+        );
+        assert!(payload.deadline_cutoff);
+        assert_eq!(payload.total_gas_used, 0);
+        assert_eq!(payload.transactions.len(), 1);
+        assert!(payload.transactions[0].is_system_tx);
+        assert_eq!(payload.state_sync_count, 1);
+    }
+
     #[test]
     fn test_payload_empty_block() {
         let config = make_config(5);
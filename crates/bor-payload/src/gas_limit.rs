@@ -0,0 +1,69 @@
+//! Gas limit of produced blocks.
+//!
+//! Validators may configure a target gas limit (`--miner.gaslimit`); without one, blocks
+//! target the chain's block gas limit at their number. Each block they produce moves from
+//! its parent's limit toward the target by at most `parent / 1024 - 1`, one less than the
+//! bound [`validate_gas_limit`](bor_consensus::validate_gas_limit) enforces, so the
+//! network converges on a new limit gradually and every step verifies.
+
+use bor_chainspec::BorHardforks;
+pub use bor_consensus::{gas_limit_target, next_gas_limit};
+
+/// Gas limit of block `number`, `number - parent_number` blocks after a parent with
+/// `parent_gas_limit`, each block moving toward its [`gas_limit_target`].
+///
+/// Stops stepping once the target is reached and it is also the target of `number`, so
+/// far-off blocks cost no more than that.
+pub fn gas_limit_after<F: BorHardforks + ?Sized>(
+    forks: &F,
+    parent_number: u64,
+    parent_gas_limit: u64,
+    number: u64,
+    configured: Option<u64>,
+) -> u64 {
+    let final_target = gas_limit_target(forks, number, configured);
+    let mut gas_limit = parent_gas_limit;
+    for block in parent_number.saturating_add(1)..=number {
+        let target = gas_limit_target(forks, block, configured);
+        let next = next_gas_limit(gas_limit, target);
+        if next == gas_limit && target == final_target {
+            break;
+        }
+        gas_limit = next;
+    }
+    gas_limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::{BorHardfork, MainnetBorHardforks};
+    use bor_consensus::validate_gas_limit;
+
+    #[test]
+    fn test_converges_and_every_step_verifies() {
+        let mut gas_limit = 30_000_000;
+        let mut blocks = 0;
+        while gas_limit != 45_000_000 {
+            let next = next_gas_limit(gas_limit, 45_000_000);
+            assert!(validate_gas_limit(gas_limit, next).is_ok());
+            gas_limit = next;
+            blocks += 1;
+        }
+        let after =
+            |blocks| gas_limit_after(&MainnetBorHardforks, 0, 30_000_000, blocks, Some(45_000_000));
+        assert_eq!(after(blocks), 45_000_000);
+        assert_eq!(after(u64::MAX), 45_000_000);
+        assert!(after(blocks - 1) < 45_000_000);
+    }
+
+    #[test]
+    fn test_default_target_follows_chain() {
+        let bhilai = BorHardfork::Bhilai.mainnet_block();
+        let after =
+            |number| gas_limit_after(&MainnetBorHardforks, bhilai - 2, 30_000_000, number, None);
+        // Blocks before Bhilai keep the 30M limit; from Bhilai they move toward 45M.
+        assert_eq!(after(bhilai - 1), 30_000_000);
+        assert_eq!(after(bhilai), next_gas_limit(30_000_000, 45_000_000));
+    }
+}
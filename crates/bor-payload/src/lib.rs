@@ -3,5 +3,20 @@
 //! Constructs block payloads for Bor, including user transactions and
 //! system transactions (commitSpan, onStateReceive) at appropriate boundaries.
 
+pub mod budget;
+pub use budget::{
+    record_selection, select_transactions, BuildBudget, SelectionClock, StopReason, TxSelection,
+    DEFAULT_BUILD_MARGIN,
+};
+
+pub mod gas_limit;
+pub use gas_limit::{gas_limit_after, gas_limit_target, next_gas_limit};
+
+pub mod ordering;
+pub use ordering::{order_deterministically, PoolTx, TxOrdering};
+
 pub mod builder;
-pub use builder::{BorPayloadBuilder, PayloadConfig, BuiltPayload};
+pub use builder::{BorPayloadBuilder, PayloadConfig, PayloadTx, BuiltPayload};
+
+pub mod attributes;
+pub use attributes::{BorAttributesError, BorPayloadAttributes, BorPayloadBuilderAttributes};
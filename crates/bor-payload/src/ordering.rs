@@ -0,0 +1,130 @@
+//! Deterministic transaction ordering.
+//!
+//! The pool hands out transactions by effective tip, breaking ties by arrival, so
+//! two nodes with the same pending transactions can build different blocks, and
+//! the block a node would have built cannot be rebuilt later from a pool dump.
+//! [`order_deterministically`] depends on the transactions alone: it takes the
+//! highest effective tip first, then the lowest nonce, then the lowest hash, and
+//! never puts a transaction before a lower nonce of the same sender.
+
+use crate::builder::PayloadTx;
+use alloy_primitives::{Address, B256};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+
+/// How user transactions are ordered in a block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TxOrdering {
+    /// As the pool yields them: by effective tip, then arrival.
+    #[default]
+    Pool,
+    /// By effective tip, then nonce, then hash; see [`order_deterministically`].
+    Deterministic,
+}
+
+/// A pending transaction with what [`order_deterministically`] orders it by.
+#[derive(Debug, Clone)]
+pub struct PoolTx {
+    /// Sender of the transaction.
+    pub sender: Address,
+    /// Nonce of the transaction.
+    pub nonce: u64,
+    /// Hash of the transaction.
+    pub hash: B256,
+    /// The transaction as the payload builder sees it.
+    pub tx: PayloadTx,
+}
+
+/// Heads of the senders' queues, the best first.
+type Head = (u128, Reverse<u64>, Reverse<B256>, Address);
+
+fn head(tx: &PoolTx) -> Head {
+    (tx.tx.effective_tip, Reverse(tx.nonce), Reverse(tx.hash), tx.sender)
+}
+
+/// Order a snapshot of pending transactions independently of their arrival.
+///
+/// Among the lowest nonce left of each sender, the one with the highest effective
+/// tip goes first; equal tips go by nonce, then by hash.
+pub fn order_deterministically(txs: impl IntoIterator<Item = PoolTx>) -> Vec<PayloadTx> {
+    let mut senders: BTreeMap<Address, Vec<PoolTx>> = BTreeMap::new();
+    for tx in txs {
+        senders.entry(tx.sender).or_default().push(tx);
+    }
+    let mut queues: BTreeMap<Address, VecDeque<PoolTx>> = senders
+        .into_iter()
+        .map(|(sender, mut txs)| {
+            txs.sort_by_key(|tx| (tx.nonce, tx.hash));
+            (sender, txs.into())
+        })
+        .collect();
+
+    let mut heads: BinaryHeap<Head> =
+        queues.values().filter_map(|queue| queue.front().map(head)).collect();
+    let mut ordered = Vec::with_capacity(queues.values().map(VecDeque::len).sum());
+    while let Some((.., sender)) = heads.pop() {
+        let queue = queues.get_mut(&sender).expect("head of a known sender");
+        let tx = queue.pop_front().expect("head of a non-empty queue");
+        ordered.push(tx.tx);
+        if let Some(next) = queue.front() {
+            heads.push(head(next));
+        }
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Bytes;
+
+    fn pool_tx(sender: u8, nonce: u64, hash: u8, tip: u128) -> PoolTx {
+        PoolTx {
+            sender: Address::new([sender; 20]),
+            nonce,
+            hash: B256::repeat_byte(hash),
+            tx: PayloadTx {
+                data: Bytes::from(vec![sender, nonce as u8]),
+                gas_used: 21_000,
+                is_system_tx: false,
+                effective_tip: tip,
+            },
+        }
+    }
+
+    fn order(txs: Vec<PoolTx>) -> Vec<(u8, u8)> {
+        order_deterministically(txs).into_iter().map(|tx| (tx.data[0], tx.data[1])).collect()
+    }
+
+    #[test]
+    fn test_orders_by_tip_keeping_nonces() {
+        let txs = vec![
+            pool_tx(1, 0, 0x10, 2),
+            pool_tx(1, 1, 0x11, 9),
+            pool_tx(2, 5, 0x20, 5),
+            pool_tx(3, 0, 0x30, 1),
+        ];
+        // Sender 1's tip-9 transaction waits for its nonce 0, which pays less than sender 2.
+        assert_eq!(order(txs), vec![(2, 5), (1, 0), (1, 1), (3, 0)]);
+    }
+
+    #[test]
+    fn test_ties_break_by_nonce_then_hash() {
+        let txs = vec![pool_tx(1, 3, 0x01, 4), pool_tx(2, 1, 0x02, 4), pool_tx(3, 1, 0x00, 4)];
+        assert_eq!(order(txs), vec![(3, 1), (2, 1), (1, 3)]);
+    }
+
+    #[test]
+    fn test_arrival_order_does_not_matter() {
+        let txs = vec![
+            pool_tx(1, 0, 0x10, 3),
+            pool_tx(2, 0, 0x20, 3),
+            pool_tx(2, 1, 0x21, 7),
+            pool_tx(3, 0, 0x30, 3),
+            pool_tx(1, 1, 0x11, 1),
+        ];
+        let mut reversed = txs.clone();
+        reversed.reverse();
+        assert_eq!(order(txs), order(reversed));
+    }
+}
@@ -33,6 +33,7 @@ fn make_user_tx(gas: u64) -> PayloadTx {
         data: Bytes::from_static(b"user_tx"),
         gas_used: gas,
         is_system_tx: false,
+        effective_tip: 0,
     }
 }
 
@@ -0,0 +1,60 @@
+//! State sync event records, as Bor commits them.
+//!
+//! bor-geth hands each state sync event to the StateReceiver as the RLP encoding of its
+//! `clerk.EventRecord`: the event as Heimdall serves it, without the record time.
+
+use alloc::string::String;
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_rlp::{RlpDecodable, RlpEncodable};
+use serde::{Deserialize, Serialize};
+
+/// A state sync event, in the field order of bor-geth's `clerk.EventRecord`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, RlpEncodable, RlpDecodable)]
+pub struct EventRecord {
+    /// State ID of the event.
+    pub id: u64,
+    /// L1 contract that emitted the event.
+    pub contract: Address,
+    /// Data of the event.
+    pub data: Bytes,
+    /// L1 transaction that emitted the event.
+    pub tx_hash: B256,
+    /// Index of the event's log in that transaction.
+    pub log_index: u64,
+    /// Bor chain the event targets, as a decimal string.
+    #[serde(rename = "bor_chain_id")]
+    pub chain_id: String,
+}
+
+impl EventRecord {
+    /// The RLP encoding of the record, the `recordBytes` bor-geth commits.
+    pub fn record_bytes(&self) -> Bytes {
+        alloy_rlp::encode(self).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloy_rlp::Decodable;
+
+    #[test]
+    fn test_record_bytes_roundtrip() {
+        let record = EventRecord {
+            id: 1,
+            contract: Address::with_last_byte(0xaa),
+            data: Bytes::from_static(&[0xde, 0xad]),
+            tx_hash: B256::with_last_byte(0xbb),
+            log_index: 0,
+            chain_id: "137".to_string(),
+        };
+        let bytes = record.record_bytes();
+        // List of: 0x01, 20-byte address, 2-byte data, 32-byte hash, 0x80 for zero, "137".
+        assert_eq!(bytes[0], 0xf8);
+        assert_eq!(bytes[1] as usize, bytes.len() - 2);
+        assert_eq!(&bytes[2..4], &[0x01, 0x94]);
+        assert_eq!(&bytes[bytes.len() - 4..], &[0x83, b'1', b'3', b'7']);
+        assert_eq!(EventRecord::decode(&mut bytes.as_ref()).unwrap(), record);
+    }
+}
@@ -0,0 +1,338 @@
+//! ExtraData parsing for Bor consensus headers.
+//!
+//! Bor header extra data layout:
+//! `[vanity: 32 bytes] [validator_bytes: N * 20 bytes] [seal: 65 bytes]`
+//!
+//! When a block carries parallel-execution metadata, the middle section is
+//! instead the RLP encoding of [`BlockExtraData`].
+
+use crate::{sprint::is_sprint_end, Validator};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use alloy_primitives::{Address, Bytes};
+use alloy_rlp::{Decodable, Encodable, RlpDecodable, RlpEncodable};
+
+/// Length of the vanity portion of extra data (bytes).
+pub const EXTRADATA_VANITY_LEN: usize = 32;
+
+/// Length of the seal (signature) portion of extra data (bytes).
+pub const EXTRADATA_SEAL_LEN: usize = 65;
+
+/// Minimum extra data length: 32 bytes vanity + 65 bytes seal.
+const MIN_EXTRA_DATA_LEN: usize = EXTRADATA_VANITY_LEN + EXTRADATA_SEAL_LEN;
+
+/// Address size in bytes.
+const ADDRESS_LEN: usize = 20;
+
+/// Errors that can occur when parsing extra data.
+#[derive(Debug, thiserror::Error)]
+pub enum ExtraDataError {
+    /// Extra data is too short (must be at least 97 bytes).
+    #[error("extra data too short: {0} bytes, minimum is {MIN_EXTRA_DATA_LEN}")]
+    TooShort(usize),
+
+    /// Validator bytes length is not a multiple of 20.
+    #[error("validator bytes length {0} is not a multiple of {ADDRESS_LEN}")]
+    InvalidValidatorBytes(usize),
+
+    /// Validator bytes were supplied for a block that does not end a sprint.
+    #[error("validator bytes are only allowed at sprint end, block {0} is not one")]
+    ValidatorsOutsideSprintEnd(u64),
+
+    /// The RLP-encoded middle section could not be decoded.
+    #[error("invalid block extra data: {0}")]
+    InvalidBlockExtraData(String),
+}
+
+/// RLP payload of the extra data middle section when parallel-execution
+/// metadata is present.
+///
+/// `tx_dependency[i]` lists the indices of earlier transactions that
+/// transaction `i` depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, RlpEncodable, RlpDecodable)]
+pub struct BlockExtraData {
+    /// Validator bytes (N * 20 bytes, empty outside sprint end).
+    pub validator_bytes: Bytes,
+    /// Per-transaction dependency lists.
+    pub tx_dependency: Vec<Vec<u64>>,
+}
+
+impl BlockExtraData {
+    /// Decode the RLP middle section of raw header extra data.
+    pub fn from_extra(extra: &[u8]) -> Result<Self, ExtraDataError> {
+        if extra.len() < MIN_EXTRA_DATA_LEN {
+            return Err(ExtraDataError::TooShort(extra.len()));
+        }
+        let mut middle = &extra[EXTRADATA_VANITY_LEN..extra.len() - EXTRADATA_SEAL_LEN];
+        Self::decode(&mut middle).map_err(|e| ExtraDataError::InvalidBlockExtraData(e.to_string()))
+    }
+}
+
+/// Parsed extra data from a Bor consensus header.
+#[derive(Debug, Clone)]
+pub struct ExtraData {
+    /// 32-byte vanity field (arbitrary proposer data).
+    pub vanity: [u8; EXTRADATA_VANITY_LEN],
+    /// Raw validator bytes (N * 20 bytes, present only at sprint-end blocks).
+    pub validator_bytes: Vec<u8>,
+    /// 65-byte ECDSA signature (recovery-id ++ r ++ s).
+    pub seal: Vec<u8>,
+}
+
+impl ExtraData {
+    /// Parse extra data from raw header bytes.
+    pub fn parse(extra: &[u8]) -> Result<Self, ExtraDataError> {
+        if extra.len() < MIN_EXTRA_DATA_LEN {
+            return Err(ExtraDataError::TooShort(extra.len()));
+        }
+
+        let vanity: [u8; EXTRADATA_VANITY_LEN] =
+            extra[..EXTRADATA_VANITY_LEN].try_into().expect("vanity slice is exactly 32 bytes");
+
+        let validator_bytes_len = extra.len() - EXTRADATA_VANITY_LEN - EXTRADATA_SEAL_LEN;
+        if validator_bytes_len % ADDRESS_LEN != 0 {
+            return Err(ExtraDataError::InvalidValidatorBytes(validator_bytes_len));
+        }
+
+        let validator_bytes =
+            extra[EXTRADATA_VANITY_LEN..EXTRADATA_VANITY_LEN + validator_bytes_len].to_vec();
+
+        let seal = extra[extra.len() - EXTRADATA_SEAL_LEN..].to_vec();
+
+        Ok(Self { vanity, validator_bytes, seal })
+    }
+
+    /// Extract validator addresses from the validator bytes.
+    pub fn validators(&self) -> Vec<Address> {
+        self.validator_bytes
+            .chunks_exact(ADDRESS_LEN)
+            .map(|chunk| Address::from_slice(chunk))
+            .collect()
+    }
+}
+
+/// Builds header extra data for a block about to be sealed.
+///
+/// The vanity is padded with zeros or truncated to 32 bytes and the 65-byte
+/// seal is reserved as zeros for the sealer to fill in.
+#[derive(Debug, Clone)]
+pub struct ExtraDataBuilder {
+    block_number: u64,
+    sprint_size: u64,
+    vanity: [u8; EXTRADATA_VANITY_LEN],
+    validator_bytes: Option<Vec<u8>>,
+    tx_dependency: Option<Vec<Vec<u64>>>,
+}
+
+impl ExtraDataBuilder {
+    /// Create a builder for the given block with the sprint size in effect there.
+    pub fn new(block_number: u64, sprint_size: u64) -> Self {
+        Self {
+            block_number,
+            sprint_size,
+            vanity: [0u8; EXTRADATA_VANITY_LEN],
+            validator_bytes: None,
+            tx_dependency: None,
+        }
+    }
+
+    /// Returns `true` if the block is the last block of its sprint.
+    pub fn is_sprint_end(&self) -> bool {
+        is_sprint_end(self.block_number, self.sprint_size)
+    }
+
+    /// Set the vanity, zero-padded or truncated to 32 bytes.
+    pub fn with_vanity(mut self, vanity: impl AsRef<[u8]>) -> Self {
+        let vanity = vanity.as_ref();
+        let len = vanity.len().min(EXTRADATA_VANITY_LEN);
+        self.vanity = [0u8; EXTRADATA_VANITY_LEN];
+        self.vanity[..len].copy_from_slice(&vanity[..len]);
+        self
+    }
+
+    /// Set the raw validator bytes (sprint end only).
+    pub fn with_validator_bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.validator_bytes = Some(bytes.into());
+        self
+    }
+
+    /// Set the validator bytes from a validator list (sprint end only).
+    pub fn with_validators(self, validators: &[Validator]) -> Self {
+        self.with_validator_bytes(crate::encode_validator_bytes(validators))
+    }
+
+    /// Attach parallel-execution metadata, switching the middle section to the
+    /// RLP [`BlockExtraData`] form.
+    pub fn with_tx_dependency(mut self, tx_dependency: Vec<Vec<u64>>) -> Self {
+        self.tx_dependency = Some(tx_dependency);
+        self
+    }
+
+    /// Assemble the extra data.
+    pub fn build(self) -> Result<Bytes, ExtraDataError> {
+        let sprint_end = self.is_sprint_end();
+        let validator_bytes = self.validator_bytes.unwrap_or_default();
+        if !validator_bytes.is_empty() && !sprint_end {
+            return Err(ExtraDataError::ValidatorsOutsideSprintEnd(self.block_number));
+        }
+        if validator_bytes.len() % ADDRESS_LEN != 0 {
+            return Err(ExtraDataError::InvalidValidatorBytes(validator_bytes.len()));
+        }
+
+        let middle = match self.tx_dependency {
+            Some(tx_dependency) => {
+                let data = BlockExtraData { validator_bytes: validator_bytes.into(), tx_dependency };
+                let mut out = Vec::with_capacity(data.length());
+                data.encode(&mut out);
+                out
+            }
+            None => validator_bytes,
+        };
+
+        let mut out = Vec::with_capacity(MIN_EXTRA_DATA_LEN + middle.len());
+        out.extend_from_slice(&self.vanity);
+        out.extend_from_slice(&middle);
+        out.resize(out.len() + EXTRADATA_SEAL_LEN, 0);
+        Ok(out.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_minimal_extradata() {
+        // 32 vanity + 0 validator bytes + 65 seal = 97 bytes
+        let mut data = vec![0u8; MIN_EXTRA_DATA_LEN];
+        // Fill vanity with 0x01
+        data[..EXTRADATA_VANITY_LEN].fill(0x01);
+        // Fill seal with 0xff
+        data[EXTRADATA_VANITY_LEN..].fill(0xff);
+
+        let extra = ExtraData::parse(&data).unwrap();
+        assert_eq!(extra.vanity, [0x01; EXTRADATA_VANITY_LEN]);
+        assert!(extra.validator_bytes.is_empty());
+        assert_eq!(extra.seal.len(), EXTRADATA_SEAL_LEN);
+        assert!(extra.seal.iter().all(|&b| b == 0xff));
+        assert!(extra.validators().is_empty());
+    }
+
+    #[test]
+    fn test_parse_with_validators() {
+        // 32 vanity + 40 validator bytes (2 validators) + 65 seal = 137 bytes
+        let total_len = MIN_EXTRA_DATA_LEN + 2 * ADDRESS_LEN;
+        let mut data = vec![0u8; total_len];
+
+        // Vanity
+        data[..EXTRADATA_VANITY_LEN].fill(0x00);
+        // Validator 1: 0xaa repeated
+        data[EXTRADATA_VANITY_LEN..EXTRADATA_VANITY_LEN + ADDRESS_LEN].fill(0xaa);
+        // Validator 2: 0xbb repeated
+        data[EXTRADATA_VANITY_LEN + ADDRESS_LEN..EXTRADATA_VANITY_LEN + 2 * ADDRESS_LEN]
+            .fill(0xbb);
+        // Seal
+        data[total_len - EXTRADATA_SEAL_LEN..].fill(0xcc);
+
+        let extra = ExtraData::parse(&data).unwrap();
+        assert_eq!(extra.validator_bytes.len(), 40);
+        assert_eq!(extra.seal.len(), EXTRADATA_SEAL_LEN);
+
+        let validators = extra.validators();
+        assert_eq!(validators.len(), 2);
+        assert_eq!(validators[0], Address::new([0xaa; 20]));
+        assert_eq!(validators[1], Address::new([0xbb; 20]));
+    }
+
+    #[test]
+    fn test_reject_short_extradata() {
+        // Less than 97 bytes should fail
+        let data = vec![0u8; MIN_EXTRA_DATA_LEN - 1];
+        let result = ExtraData::parse(&data);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExtraDataError::TooShort(96)));
+    }
+
+    #[test]
+    fn test_builder_pads_and_truncates_vanity() {
+        let extra = ExtraDataBuilder::new(1, 16).with_vanity(b"bor").build().unwrap();
+        assert_eq!(extra.len(), MIN_EXTRA_DATA_LEN);
+        assert_eq!(&extra[..3], b"bor");
+        assert!(extra[3..].iter().all(|&b| b == 0));
+
+        let long = [0x11u8; 40];
+        let extra = ExtraDataBuilder::new(1, 16).with_vanity(long).build().unwrap();
+        assert_eq!(extra.len(), MIN_EXTRA_DATA_LEN);
+        assert_eq!(ExtraData::parse(&extra).unwrap().vanity, [0x11; EXTRADATA_VANITY_LEN]);
+    }
+
+    #[test]
+    fn test_builder_validators_at_sprint_end() {
+        let extra = ExtraDataBuilder::new(15, 16)
+            .with_validator_bytes([[0xaa; 20], [0xbb; 20]].concat())
+            .build()
+            .unwrap();
+        let parsed = ExtraData::parse(&extra).unwrap();
+        assert_eq!(parsed.validators(), vec![Address::new([0xaa; 20]), Address::new([0xbb; 20])]);
+        assert_eq!(parsed.seal, vec![0u8; EXTRADATA_SEAL_LEN]);
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_validator_bytes() {
+        let err = ExtraDataBuilder::new(14, 16)
+            .with_validator_bytes(vec![0xaa; 20])
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ExtraDataError::ValidatorsOutsideSprintEnd(14)));
+
+        let err = ExtraDataBuilder::new(15, 16)
+            .with_validator_bytes(vec![0xaa; 21])
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, ExtraDataError::InvalidValidatorBytes(21)));
+    }
+
+    #[test]
+    fn test_builder_tx_dependency_roundtrip() {
+        let deps = vec![vec![], vec![0], vec![0, 1]];
+        let extra = ExtraDataBuilder::new(31, 16)
+            .with_validator_bytes(vec![0xcc; 20])
+            .with_tx_dependency(deps.clone())
+            .build()
+            .unwrap();
+
+        let decoded = BlockExtraData::from_extra(&extra).unwrap();
+        assert_eq!(decoded.validator_bytes, Bytes::from(vec![0xcc; 20]));
+        assert_eq!(decoded.tx_dependency, deps);
+
+        assert!(matches!(
+            BlockExtraData::from_extra(&[0u8; MIN_EXTRA_DATA_LEN + 1]),
+            Err(ExtraDataError::InvalidBlockExtraData(_))
+        ));
+    }
+
+    #[test]
+    fn test_validator_extraction() {
+        // 3 validators
+        let total_len = MIN_EXTRA_DATA_LEN + 3 * ADDRESS_LEN;
+        let mut data = vec![0u8; total_len];
+
+        // Set distinct validator addresses
+        for i in 0..3 {
+            let offset = EXTRADATA_VANITY_LEN + i * ADDRESS_LEN;
+            data[offset..offset + ADDRESS_LEN].fill((i + 1) as u8);
+        }
+        // Seal
+        data[total_len - EXTRADATA_SEAL_LEN..].fill(0xff);
+
+        let extra = ExtraData::parse(&data).unwrap();
+        let validators = extra.validators();
+        assert_eq!(validators.len(), 3);
+        assert_eq!(validators[0], Address::new([0x01; 20]));
+        assert_eq!(validators[1], Address::new([0x02; 20]));
+        assert_eq!(validators[2], Address::new([0x03; 20]));
+    }
+}
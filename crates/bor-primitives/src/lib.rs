@@ -1,10 +1,34 @@
 //! Primitive types for the Bor chain.
+//!
+//! The types and encodings consensus depends on: validators and spans, header extra
+//! data, state sync event records, the Bor receipt key and sprint arithmetic. The crate
+//! is `no_std` without its default `std` feature and does not depend on reth.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod event_record;
+pub use event_record::EventRecord;
+
+pub mod extra_data;
+pub use extra_data::{
+    BlockExtraData, ExtraData, ExtraDataBuilder, ExtraDataError, EXTRADATA_SEAL_LEN,
+    EXTRADATA_VANITY_LEN,
+};
+
+pub mod receipt_key;
+pub use receipt_key::{bor_receipt_key, derived_bor_tx_hash};
+
+pub mod sprint;
+pub use sprint::{is_sprint_end, is_sprint_start, next_sprint_start};
+
+use alloc::{string::String, vec::Vec};
 use alloy_primitives::Address;
 use serde::{Deserialize, Serialize};
 
 /// A Bor validator.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Validator {
     pub id: u64,
     pub address: Address,
@@ -14,14 +38,14 @@ pub struct Validator {
 }
 
 /// A set of validators with an optional proposer.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidatorSet {
     pub validators: Vec<Validator>,
     pub proposer: Option<Validator>,
 }
 
 /// A Bor span defining a range of blocks and its validator set.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Span {
     pub id: u64,
     pub start_block: u64,
@@ -36,6 +60,76 @@ pub fn span_id_at(block: u64, span_size: u64) -> u64 {
     block / span_size
 }
 
+/// Number of blocks in the genesis span (span 0), which covers blocks 0 to 255.
+pub const GENESIS_SPAN_LENGTH: u64 = 256;
+
+/// Number of blocks in every span after the genesis span.
+pub const SPAN_LENGTH: u64 = 6400;
+
+/// Returns the Heimdall span ID covering `block`.
+///
+/// Span 0 covers blocks 0 to 255; span 1 starts at block 256 and every span after it
+/// covers 6400 blocks, so span `n` starts at `256 + (n - 1) * 6400`.
+pub fn span_id_for_block(block: u64) -> u64 {
+    span_id_for_block_with_length(block, SPAN_LENGTH)
+}
+
+/// Returns the span ID covering `block` when spans after the genesis span are
+/// `span_length` blocks long.
+pub fn span_id_for_block_with_length(block: u64, span_length: u64) -> u64 {
+    if block < GENESIS_SPAN_LENGTH {
+        0
+    } else {
+        1 + (block - GENESIS_SPAN_LENGTH) / span_length
+    }
+}
+
+/// Returns the first block of span `span_id`, the inverse of [`span_id_for_block`].
+pub fn span_start_block(span_id: u64) -> u64 {
+    match span_id {
+        0 => 0,
+        id => GENESIS_SPAN_LENGTH + (id - 1) * SPAN_LENGTH,
+    }
+}
+
+/// Number of blocks in every span from Rio on.
+pub const RIO_SPAN_LENGTH: u64 = 1600;
+
+/// The first span whose blocks are [`RIO_SPAN_LENGTH`] long on a chain activating Rio at
+/// `rio_block`: the first span starting at or after it.
+fn rio_span(rio_block: u64) -> u64 {
+    match rio_block {
+        0 => 0,
+        block => span_id_for_block(block - 1) + 1,
+    }
+}
+
+/// Returns the span ID covering `block` on a chain activating Rio at `rio_block`, if ever.
+///
+/// Spans are [`SPAN_LENGTH`] blocks long up to the first span starting at or after Rio,
+/// and [`RIO_SPAN_LENGTH`] blocks from it on. On mainnet and Amoy Rio activates at a span
+/// start.
+pub fn span_id_for_block_at(block: u64, rio_block: Option<u64>) -> u64 {
+    let Some(rio_span) = rio_block.map(rio_span) else { return span_id_for_block(block) };
+    let rio_start = span_start_block(rio_span);
+    if block < rio_start {
+        span_id_for_block(block)
+    } else {
+        rio_span + (block - rio_start) / RIO_SPAN_LENGTH
+    }
+}
+
+/// Returns the first block of span `span_id` on a chain activating Rio at `rio_block`, if
+/// ever, the inverse of [`span_id_for_block_at`].
+pub fn span_start_block_at(span_id: u64, rio_block: Option<u64>) -> u64 {
+    match rio_block.map(rio_span) {
+        Some(rio_span) if span_id >= rio_span => {
+            span_start_block(rio_span) + (span_id - rio_span) * RIO_SPAN_LENGTH
+        }
+        _ => span_start_block(span_id),
+    }
+}
+
 /// Encodes a slice of validators into raw bytes by concatenating their 20-byte signer addresses.
 pub fn encode_validator_bytes(validators: &[Validator]) -> Vec<u8> {
     let mut bytes = Vec::with_capacity(validators.len() * 20);
@@ -56,6 +150,7 @@ pub fn decode_validator_bytes(bytes: &[u8]) -> Vec<Address> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::{string::ToString, vec};
 
     fn sample_validator(id: u64, addr_byte: u8) -> Validator {
         Validator {
@@ -111,6 +206,38 @@ mod tests {
         assert_eq!(span_id_at(0, 6400), 0);
     }
 
+    #[test]
+    fn test_span_id_for_block_genesis_offset() {
+        assert_eq!(span_id_for_block(0), 0);
+        assert_eq!(span_id_for_block(255), 0);
+        assert_eq!(span_id_for_block(256), 1);
+        assert_eq!(span_id_for_block(6655), 1);
+        assert_eq!(span_id_for_block(6656), 2);
+        assert_eq!(span_start_block(2), 6656);
+        assert_eq!(span_id_for_block_with_length(1856, 1600), 2);
+    }
+
+    #[test]
+    fn test_span_id_from_rio() {
+        // Mainnet's Rio block starts span 12097.
+        let rio = 77_414_656;
+        assert_eq!(span_id_for_block_at(rio - 1, Some(rio)), 12_096);
+        assert_eq!(span_id_for_block_at(rio, Some(rio)), 12_097);
+        assert_eq!(span_id_for_block_at(rio + 1600, Some(rio)), 12_098);
+        assert_eq!(span_id_for_block_at(rio + 1600, None), 12_097);
+        assert_eq!(span_start_block_at(12_098, Some(rio)), rio + 1600);
+        assert_eq!(span_start_block_at(12_096, Some(rio)), span_start_block(12_096));
+
+        // Rio within a span takes effect from the next one.
+        assert_eq!(span_id_for_block_at(6656, Some(1000)), 2);
+        assert_eq!(span_id_for_block_at(6656 + 1600, Some(1000)), 3);
+        assert_eq!(span_id_for_block_at(1600, Some(0)), 1);
+        for id in 0..20 {
+            let start = span_start_block_at(id, Some(1000));
+            assert_eq!(span_id_for_block_at(start, Some(1000)), id);
+        }
+    }
+
     #[test]
     fn test_validator_bytes_encoding() {
         let validators = vec![sample_validator(1, 0xaa), sample_validator(2, 0xbb)];
@@ -10,6 +10,7 @@
 //!    `keccak256(BorReceiptKey(number, hash))`
 //!    This derives a deterministic tx hash for the Bor state sync transaction.
 
+use alloc::vec::Vec;
 use alloy_primitives::B256;
 
 /// The prefix used for all Bor receipt keys, matching Go Bor's `borReceiptPrefix`.
@@ -0,0 +1,42 @@
+//! Sprint arithmetic.
+//!
+//! The sprint size changes at forks (see `bor-chainspec`), so these take the size in
+//! effect at the block. None of them panic on a zero size: [`is_sprint_start`] and
+//! [`is_sprint_end`] find no boundaries in it and [`next_sprint_start`] finds no next one.
+
+/// Returns `true` if `block` is the first block of a sprint of `sprint_size` blocks.
+pub fn is_sprint_start(block: u64, sprint_size: u64) -> bool {
+    block.checked_rem(sprint_size) == Some(0)
+}
+
+/// Returns `true` if `block` is the last block of a sprint of `sprint_size` blocks, the
+/// one whose header carries the next validator set.
+pub fn is_sprint_end(block: u64, sprint_size: u64) -> bool {
+    (block + 1).checked_rem(sprint_size) == Some(0)
+}
+
+/// Returns the first sprint start after `head`, or `None` for a zero `sprint_size` or
+/// past `u64::MAX`.
+pub fn next_sprint_start(head: u64, sprint_size: u64) -> Option<u64> {
+    head.checked_div(sprint_size)?.checked_add(1)?.checked_mul(sprint_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sprint_boundaries() {
+        assert!(is_sprint_start(0, 16));
+        assert!(is_sprint_start(32, 16));
+        assert!(!is_sprint_start(33, 16));
+        assert!(is_sprint_end(15, 16));
+        assert!(!is_sprint_end(16, 16));
+        assert!(!is_sprint_start(0, 0));
+        assert!(!is_sprint_end(15, 0));
+        assert_eq!(next_sprint_start(15, 16), Some(16));
+        assert_eq!(next_sprint_start(16, 16), Some(32));
+        assert_eq!(next_sprint_start(16, 0), None);
+        assert_eq!(next_sprint_start(u64::MAX, 16), None);
+    }
+}
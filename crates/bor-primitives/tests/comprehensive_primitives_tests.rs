@@ -133,6 +133,21 @@ fn span_id_at_with_post_rio_span_size() {
     assert_eq!(span_id_at(16000, span_size), 10);
 }
 
+// ---------------------------------------------------------------------------
+// 8b. span_id_for_block at mainnet span boundaries (Heimdall spans 0..=3)
+// ---------------------------------------------------------------------------
+#[test]
+fn span_id_for_block_mainnet_boundaries() {
+    // (span id, start block, end block) as served by Heimdall for mainnet.
+    let spans = [(0, 0, 255), (1, 256, 6655), (2, 6656, 13055), (3, 13056, 19455)];
+    for (id, start, end) in spans {
+        assert_eq!(span_id_for_block(start), id, "start of span {id}");
+        assert_eq!(span_id_for_block(end), id, "end of span {id}");
+        assert_eq!(span_start_block(id), start);
+    }
+    assert_eq!(span_id_for_block(19456), 4);
+}
+
 // ---------------------------------------------------------------------------
 // 9. Span JSON roundtrip with all fields populated
 // ---------------------------------------------------------------------------
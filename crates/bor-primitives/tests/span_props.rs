@@ -0,0 +1,63 @@
+//! Property tests of span boundary math.
+//!
+//! Every node must agree on which span a block belongs to: a block assigned to the
+//! wrong span is checked against the wrong validator set.
+
+use bor_primitives::{
+    span_id_for_block, span_id_for_block_with_length, span_start_block, GENESIS_SPAN_LENGTH,
+    SPAN_LENGTH,
+};
+use proptest::prelude::*;
+
+/// The span of block `u64::MAX`, the last whose start block fits in a `u64`.
+const MAX_SPAN_ID: u64 = 1 + (u64::MAX - GENESIS_SPAN_LENGTH) / SPAN_LENGTH;
+
+/// Blocks near the genesis span boundary and the first span boundaries, or anywhere.
+fn block() -> impl Strategy<Value = u64> {
+    prop_oneof![0..20_000u64, any::<u64>()]
+}
+
+proptest! {
+    #[test]
+    fn start_block_is_in_its_span(id in prop_oneof![0..16u64, 0..=MAX_SPAN_ID]) {
+        let start = span_start_block(id);
+        prop_assert_eq!(span_id_for_block(start), id);
+        if start > 0 {
+            prop_assert_eq!(span_id_for_block(start - 1), id - 1);
+        }
+    }
+
+    #[test]
+    fn block_lies_between_span_starts(block in block()) {
+        let id = span_id_for_block(block);
+        prop_assert!(span_start_block(id) <= block);
+        if id < MAX_SPAN_ID {
+            prop_assert!(block < span_start_block(id + 1));
+        }
+    }
+
+    #[test]
+    fn span_id_is_monotonic(a in block(), b in block()) {
+        let (low, high) = (a.min(b), a.max(b));
+        prop_assert!(span_id_for_block(low) <= span_id_for_block(high));
+        // Consecutive blocks are in the same span or consecutive ones.
+        if low < u64::MAX {
+            let step = span_id_for_block(low + 1) - span_id_for_block(low);
+            prop_assert!(step <= 1);
+        }
+    }
+
+    #[test]
+    fn custom_span_lengths(block in block(), length in 1..10_000u64) {
+        let id = span_id_for_block_with_length(block, length);
+        if block < GENESIS_SPAN_LENGTH {
+            prop_assert_eq!(id, 0);
+        } else {
+            // Span `id` starts `id - 1` spans of `length` after the genesis span.
+            let start = GENESIS_SPAN_LENGTH + (id - 1) * length;
+            prop_assert!(start <= block && block - start < length);
+        }
+        let mainnet = span_id_for_block_with_length(block, SPAN_LENGTH);
+        prop_assert_eq!(mainnet, span_id_for_block(block));
+    }
+}
@@ -1,7 +1,11 @@
 //! Bor namespace RPC trait definition.
 
-use crate::types::{BorSnapshotResponse, CurrentValidatorsResponse, BorReceiptResponse};
+use crate::types::{
+    BorReceiptResponse, BorSnapshotResponse, CurrentValidatorsResponse, MilestoneResponse,
+    ProducerPerformanceResponse, SimulatedProposalResponse, ValidatorsHistoryResponse,
+};
 use alloy_primitives::{Address, B256};
+use std::future::Future;
 
 /// Bor namespace RPC methods.
 pub trait BorApi {
@@ -17,6 +21,15 @@ pub trait BorApi {
     /// Returns the current validator set.
     fn bor_get_current_validators(&self) -> Result<CurrentValidatorsResponse, Self::Error>;
 
+    /// Returns the changes of the validator set over blocks `from..=to`, one page at a
+    /// time; `page_token` is the token a previous page of the range ended with.
+    fn bor_get_validators_history(
+        &self,
+        from: u64,
+        to: u64,
+        page_token: Option<String>,
+    ) -> Result<ValidatorsHistoryResponse, Self::Error>;
+
     /// Returns the address of the current proposer.
     fn bor_get_current_proposer(&self) -> Result<Address, Self::Error>;
 
@@ -34,4 +47,76 @@ pub trait BorApi {
         &self,
         block_number: u64,
     ) -> Result<Vec<BorReceiptResponse>, Self::Error>;
+
+    /// Returns the latest milestone received from Heimdall.
+    fn bor_get_latest_milestone(&self) -> Result<MilestoneResponse, Self::Error>;
+
+    /// Returns a recently observed milestone by its Heimdall ID.
+    fn bor_get_milestone_by_id(&self, milestone_id: String)
+        -> Result<MilestoneResponse, Self::Error>;
+
+    /// Votes on a milestone proposal from Heimdall: `true` if blocks `start..=end` of the
+    /// canonical chain end with `hash`, locking the node on `end`.
+    fn bor_get_vote_on_hash(
+        &self,
+        start: u64,
+        end: u64,
+        hash: B256,
+        milestone_id: String,
+    ) -> Result<bool, Self::Error>;
+
+    /// Builds, without sealing or broadcasting, the block this node would produce at
+    /// `block_number`, so operators can check their validator setup before their sprint.
+    fn bor_simulate_proposal(
+        &self,
+        block_number: u64,
+    ) -> Result<SimulatedProposalResponse, Self::Error>;
+
+    /// Reports expected against actual producers of blocks `start..=end`, as recorded by
+    /// the node's producer monitor since it started.
+    fn bor_get_producer_performance(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<ProducerPerformanceResponse, Self::Error>;
+}
+
+/// Operator-only Bor methods that change node parameters at runtime.
+///
+/// These must only be exposed on authenticated or local transports. The Heimdall
+/// recovery methods are served next to them, from [`BorRecoveryApi`].
+pub trait BorAdminApi {
+    /// The error type returned by RPC methods.
+    type Error;
+
+    /// Switches the Heimdall endpoint used by every Heimdall consumer of the node.
+    fn bor_set_heimdall_url(&self, url: String) -> Result<bool, Self::Error>;
+
+    /// Sets the address the node produces blocks as, for validator key rotation.
+    fn bor_set_signer(&self, signer: Address) -> Result<bool, Self::Error>;
+}
+
+/// Operator-only Bor methods that recover from bad Heimdall data.
+///
+/// Like [`BorAdminApi`], these must only be exposed on authenticated or local transports.
+pub trait BorRecoveryApi {
+    /// The error type returned by RPC methods.
+    type Error;
+
+    /// Fetches span `span_id` from Heimdall again, replacing the cached copy.
+    fn bor_resync_span(
+        &self,
+        span_id: u64,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Fetches the pending state sync events from ID `from_id` on again, returning how many
+    /// were fetched.
+    fn bor_refetch_state_sync_events(
+        &self,
+        from_id: u64,
+    ) -> impl Future<Output = Result<u64, Self::Error>> + Send;
+
+    /// Lets block production halted by a Heimdall cross-check mismatch run again,
+    /// returning the reason it was halted for, if it was.
+    fn bor_resume_production(&self) -> Option<String>;
 }
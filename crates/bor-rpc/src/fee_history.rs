@@ -0,0 +1,205 @@
+//! `eth_feeHistory` with Bor's base fee rules.
+//!
+//! The base fee change denominator on Polygon rises from 8 to 16 at Delhi and
+//! to 64 at Bhilai, keyed on the parent block number as in bor-geth and taken
+//! from the chain's [`BorHardforks`] schedule. The
+//! Ethereum parameters reth uses by default would produce a different next
+//! base fee for every block after Delhi, so the history is recomputed here.
+//! Reward percentiles follow go-ethereum's gas-weighted selection over the
+//! block's transactions sorted by effective tip.
+
+use crate::methods::BorRpcError;
+use crate::types::FeeHistoryResponse;
+use alloy_eips::eip1559::{calc_next_block_base_fee, BaseFeeParams};
+use alloy_primitives::{U256, U64};
+use bor_chainspec::BorHardforks;
+
+/// Maximum number of blocks returned by one `eth_feeHistory` call, as in bor-geth.
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// Ratio of the gas limit to the gas target.
+pub const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// A block's inputs to `eth_feeHistory`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeHistoryBlock {
+    /// Block number.
+    pub number: u64,
+    /// Base fee per gas of the block.
+    pub base_fee_per_gas: u64,
+    /// Gas used by the block.
+    pub gas_used: u64,
+    /// Gas limit of the block.
+    pub gas_limit: u64,
+    /// `(gas used, effective tip per gas)` of each transaction, in block order.
+    pub txs: Vec<(u64, u128)>,
+}
+
+/// Base fee of the child of a block, using the denominator `forks` puts in force at
+/// `parent_number`.
+pub fn bor_next_base_fee(
+    forks: &impl BorHardforks,
+    parent_number: u64,
+    parent_base_fee: u64,
+    gas_used: u64,
+    gas_limit: u64,
+) -> u64 {
+    let params = BaseFeeParams::new(
+        forks.bor_base_fee_change_denominator(parent_number) as u128,
+        ELASTICITY_MULTIPLIER as u128,
+    );
+    calc_next_block_base_fee(gas_used, gas_limit, parent_base_fee, params)
+}
+
+/// Reject percentiles outside `0..=100` or not in ascending order.
+pub fn validate_reward_percentiles(percentiles: &[f64]) -> Result<(), BorRpcError> {
+    let mut previous = 0.0;
+    for &p in percentiles {
+        if !(0.0..=100.0).contains(&p) {
+            return Err(BorRpcError::InvalidParams(format!("invalid reward percentile: {p}")));
+        }
+        if p < previous {
+            return Err(BorRpcError::InvalidParams(format!(
+                "reward percentiles not in ascending order: {previous} > {p}"
+            )));
+        }
+        previous = p;
+    }
+    Ok(())
+}
+
+/// Effective tips at `percentiles` of the block's gas, lowest tip first.
+///
+/// For each percentile the reward is the tip of the first transaction at which the
+/// cumulative gas reaches that share of the block's gas used. An empty block rewards zero.
+pub fn block_rewards(block: &FeeHistoryBlock, percentiles: &[f64]) -> Vec<u128> {
+    if block.txs.is_empty() {
+        return vec![0; percentiles.len()];
+    }
+
+    let mut sorted = block.txs.clone();
+    sorted.sort_by_key(|&(_, tip)| tip);
+
+    let mut rewards = Vec::with_capacity(percentiles.len());
+    let mut index = 0;
+    let mut cumulative = sorted[0].0;
+    for &p in percentiles {
+        let threshold = (block.gas_used as f64 * p / 100.0) as u64;
+        while cumulative < threshold && index < sorted.len() - 1 {
+            index += 1;
+            cumulative += sorted[index].0;
+        }
+        rewards.push(sorted[index].1);
+    }
+    rewards
+}
+
+/// Build the `eth_feeHistory` response for consecutive `blocks`, oldest first.
+///
+/// `base_fee_per_gas` holds the first block's base fee followed by the computed next base
+/// fee of every block, so its last entry is the base fee of the block after the range.
+pub fn fee_history(
+    forks: &impl BorHardforks,
+    blocks: &[FeeHistoryBlock],
+    reward_percentiles: Option<&[f64]>,
+) -> Result<FeeHistoryResponse, BorRpcError> {
+    if let Some(percentiles) = reward_percentiles {
+        validate_reward_percentiles(percentiles)?;
+    }
+    for pair in blocks.windows(2) {
+        if pair[1].number != pair[0].number + 1 {
+            return Err(BorRpcError::InvalidBlockRange { start: pair[0].number, end: pair[1].number });
+        }
+    }
+    let Some(first) = blocks.first() else {
+        return Ok(FeeHistoryResponse {
+            oldest_block: U64::ZERO,
+            base_fee_per_gas: Vec::new(),
+            gas_used_ratio: Vec::new(),
+            reward: None,
+        });
+    };
+
+    let mut base_fee_per_gas = vec![U256::from(first.base_fee_per_gas)];
+    let mut gas_used_ratio = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        let next = bor_next_base_fee(
+            forks,
+            block.number,
+            block.base_fee_per_gas,
+            block.gas_used,
+            block.gas_limit,
+        );
+        base_fee_per_gas.push(U256::from(next));
+        gas_used_ratio.push(if block.gas_limit == 0 {
+            0.0
+        } else {
+            block.gas_used as f64 / block.gas_limit as f64
+        });
+    }
+
+    let reward = reward_percentiles.map(|percentiles| {
+        blocks
+            .iter()
+            .map(|block| block_rewards(block, percentiles).into_iter().map(U256::from).collect())
+            .collect()
+    });
+
+    Ok(FeeHistoryResponse {
+        oldest_block: U64::from(first.number),
+        base_fee_per_gas,
+        gas_used_ratio,
+        reward,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_chainspec::MainnetBorHardforks;
+
+    const GWEI: u128 = 1_000_000_000;
+
+    #[test]
+    fn test_next_base_fee_denominator_changes() {
+        // A full block raises the base fee by 1/8 before Delhi, 1/16 after and 1/64 after Bhilai.
+        let forks = MainnetBorHardforks;
+        assert_eq!(bor_next_base_fee(&forks, 1, 800, 30_000_000, 30_000_000), 900);
+        assert_eq!(bor_next_base_fee(&forks, 38_189_056, 1_600, 30_000_000, 30_000_000), 1_700);
+        assert_eq!(bor_next_base_fee(&forks, 76_000_000, 6_400, 45_000_000, 45_000_000), 6_500);
+        // At the gas target the base fee is unchanged.
+        assert_eq!(bor_next_base_fee(&forks, 1, 800, 15_000_000, 30_000_000), 800);
+    }
+
+    #[test]
+    fn test_rewards_are_gas_weighted() {
+        let block = FeeHistoryBlock {
+            number: 1,
+            base_fee_per_gas: 30 * GWEI as u64,
+            gas_used: 30_000_000,
+            gas_limit: 30_000_000,
+            txs: vec![(10_000_000, 30 * GWEI), (15_000_000, GWEI), (5_000_000, 50 * GWEI)],
+        };
+        assert_eq!(block_rewards(&block, &[10.0, 50.0, 90.0]), vec![GWEI, GWEI, 50 * GWEI]);
+        assert_eq!(block_rewards(&block, &[0.0, 100.0]), vec![GWEI, 50 * GWEI]);
+
+        let empty = FeeHistoryBlock { txs: Vec::new(), ..block };
+        assert_eq!(block_rewards(&empty, &[25.0, 75.0]), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_invalid_percentiles_and_ranges() {
+        assert!(validate_reward_percentiles(&[0.0, 50.0, 100.0]).is_ok());
+        assert!(validate_reward_percentiles(&[50.0, 10.0]).is_err());
+        assert!(validate_reward_percentiles(&[101.0]).is_err());
+
+        let gap = [
+            FeeHistoryBlock { number: 1, ..Default::default() },
+            FeeHistoryBlock { number: 3, ..Default::default() },
+        ];
+        assert!(matches!(
+            fee_history(&MainnetBorHardforks, &gap, None),
+            Err(BorRpcError::InvalidBlockRange { .. })
+        ));
+    }
+}
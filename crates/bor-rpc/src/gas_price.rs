@@ -0,0 +1,120 @@
+//! `eth_maxPriorityFeePerGas` as Polygon nodes suggest it.
+//!
+//! reth's oracle suggests tips from recent blocks with Ethereum's bounds. Most
+//! Polygon blocks are far from full and many transactions pay no more than the
+//! network's 25 gwei minimum tip, so the suggestion often lands below what
+//! validators accept. Like bor-geth's oracle, [`suggest_priority_fee`] samples
+//! the lowest tips of recent blocks, ignoring those below the minimum, takes a
+//! percentile and keeps the result between the minimum and a maximum.
+
+use crate::fee_history::FeeHistoryBlock;
+
+/// One gwei in wei.
+const GWEI: u128 = 1_000_000_000;
+
+/// Default number of recent blocks sampled.
+pub const DEFAULT_PRIORITY_FEE_BLOCKS: u64 = 20;
+
+/// Default number of lowest tips sampled per block.
+pub const DEFAULT_PRIORITY_FEE_SAMPLES: usize = 3;
+
+/// Default percentile of the sampled tips suggested.
+pub const DEFAULT_PRIORITY_FEE_PERCENTILE: usize = 60;
+
+/// Minimum tip accepted by Polygon validators (PIP-35).
+pub const DEFAULT_MIN_PRIORITY_FEE: u128 = 25 * GWEI;
+
+/// Default upper bound of the suggestion.
+pub const DEFAULT_MAX_PRIORITY_FEE: u128 = 500 * GWEI;
+
+/// How priority fees are suggested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeConfig {
+    /// Number of recent blocks sampled.
+    pub blocks: u64,
+    /// Number of lowest tips sampled per block.
+    pub samples_per_block: usize,
+    /// Percentile of the sampled tips suggested, `0..=100`.
+    pub percentile: usize,
+    /// Lowest suggestion; tips below it are not sampled.
+    pub min: u128,
+    /// Highest suggestion.
+    pub max: u128,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            blocks: DEFAULT_PRIORITY_FEE_BLOCKS,
+            samples_per_block: DEFAULT_PRIORITY_FEE_SAMPLES,
+            percentile: DEFAULT_PRIORITY_FEE_PERCENTILE,
+            min: DEFAULT_MIN_PRIORITY_FEE,
+            max: DEFAULT_MAX_PRIORITY_FEE,
+        }
+    }
+}
+
+/// Suggested priority fee per gas given the most recent `blocks`.
+///
+/// Blocks without a tip at or above the minimum contribute no samples; if no block does,
+/// the minimum is suggested.
+pub fn suggest_priority_fee(config: &PriorityFeeConfig, blocks: &[FeeHistoryBlock]) -> u128 {
+    let mut samples = Vec::new();
+    for block in blocks {
+        let mut tips: Vec<u128> =
+            block.txs.iter().map(|&(_, tip)| tip).filter(|&tip| tip >= config.min).collect();
+        tips.sort_unstable();
+        samples.extend(tips.into_iter().take(config.samples_per_block));
+    }
+    if samples.is_empty() {
+        return config.min;
+    }
+
+    samples.sort_unstable();
+    let index = (samples.len() - 1) * config.percentile.min(100) / 100;
+    samples[index].clamp(config.min, config.max.max(config.min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, tips: &[u128]) -> FeeHistoryBlock {
+        FeeHistoryBlock {
+            number,
+            base_fee_per_gas: 30,
+            gas_used: 21_000 * tips.len() as u64,
+            gas_limit: 30_000_000,
+            txs: tips.iter().map(|&tip| (21_000, tip)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_empty_chain_suggests_minimum() {
+        let config = PriorityFeeConfig::default();
+        assert_eq!(suggest_priority_fee(&config, &[]), DEFAULT_MIN_PRIORITY_FEE);
+        assert_eq!(suggest_priority_fee(&config, &[block(1, &[])]), DEFAULT_MIN_PRIORITY_FEE);
+        // Tips below the minimum are ignored rather than pulling the suggestion down.
+        assert_eq!(
+            suggest_priority_fee(&config, &[block(1, &[0, 1, GWEI])]),
+            DEFAULT_MIN_PRIORITY_FEE
+        );
+    }
+
+    #[test]
+    fn test_samples_lowest_tips_per_block() {
+        let config = PriorityFeeConfig::default();
+        // Only 30, 40 and 50 gwei are sampled from the first block; the outlier is not.
+        let blocks =
+            [block(1, &[30 * GWEI, 1_000 * GWEI, 40 * GWEI, 50 * GWEI]), block(2, &[60 * GWEI])];
+        // Of the samples 30, 40, 50 and 60 gwei the 60th percentile is at index 3 * 60 / 100.
+        assert_eq!(suggest_priority_fee(&config, &blocks), 40 * GWEI);
+    }
+
+    #[test]
+    fn test_suggestion_is_capped() {
+        let config = PriorityFeeConfig { max: 100 * GWEI, ..Default::default() };
+        let blocks = [block(1, &[1_000 * GWEI]), block(2, &[2_000 * GWEI])];
+        assert_eq!(suggest_priority_fee(&config, &blocks), 100 * GWEI);
+    }
+}
@@ -1,9 +1,39 @@
 //! Bor RPC extensions.
 
 pub mod api;
+pub mod fee_history;
+pub mod gas_price;
 pub mod methods;
+pub mod modules;
+pub mod root_hash;
 pub mod types;
 
-pub use api::BorApi;
-pub use methods::{BorRpcError, compute_root_hash, get_author};
-pub use types::{BorReceiptResponse, BorSnapshotResponse, CurrentValidatorsResponse, ValidatorInfo};
+pub use api::{BorAdminApi, BorApi, BorRecoveryApi};
+pub use fee_history::{
+    block_rewards, bor_next_base_fee, fee_history, FeeHistoryBlock, MAX_FEE_HISTORY_BLOCKS,
+};
+pub use gas_price::{suggest_priority_fee, PriorityFeeConfig};
+pub use methods::{
+    BorRpcError, compute_root_hash, get_author, get_bad_blocks, get_bor_snapshot, get_bor_tx_hash,
+    get_latest_milestone, get_milestone_by_id, get_state_sync_events_by_block,
+    get_state_sync_events_by_contract,
+    get_validators_history, get_vote_on_hash, resolve_block_tag, state_sync_transaction,
+    with_milestone_finality, MAX_STATE_SYNC_EVENTS, MAX_VALIDATOR_HISTORY_CHANGES,
+    MAX_VALIDATOR_HISTORY_SPRINTS, VOTE_CONFIRMATION_BLOCKS,
+};
+pub use modules::{
+    bor_admin_module, bor_block_module, bor_call_module, bor_debug_module, bor_fee_module,
+    bor_milestone_module, bor_monitor_module, bor_proposal_module, bor_resync_module,
+    bor_root_hash_module, bor_state_sync_module, bor_transaction_module, bor_validators_module,
+    bor_vote_module, fee_history_blocks, rpc_error, DebugContext, RpcErrorCode,
+};
+pub use root_hash::{
+    checkpoint_leaf, validate_checkpoint_range, RootHashBuilder, RootHashCache,
+    DEFAULT_ROOT_HASH_CACHE_SIZE, MAX_CHECKPOINT_LENGTH, ROOT_HASH_HEADER_BATCH,
+};
+pub use types::{
+    BlockStateSyncEventResponse, BorReceiptResponse, BorSnapshotResponse, CurrentValidatorsResponse, FeeHistoryResponse,
+    MilestoneResponse, ProducerPerformanceResponse, ProducerStats, SimulatedProposalResponse,
+    SimulatedSystemCall, StateSyncEventResponse, StateSyncTransactionResponse, ValidatorInfo,
+    ValidatorSetChange, ValidatorsHistoryResponse, WithMilestoneFinality,
+};
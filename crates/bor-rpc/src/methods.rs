@@ -4,9 +4,41 @@
 //! - `get_author`: recovers block signer from seal
 //! - `get_root_hash`: computes Merkle root of block hashes in a range
 //! - `get_transaction_receipts_by_block`: merges Bor receipts with regular ones
+//! - `get_latest_milestone` / `get_milestone_by_id`: milestone lookups
+//! - `get_vote_on_hash`: the validator's vote on a milestone proposal (`bor_getVoteOnHash`)
+//! - `resolve_block_tag`: maps `finalized` / `safe` onto milestone / checkpoint heights
+//! - `get_bad_blocks`: rejected blocks with their Bor context (`debug_borBadBlocks`)
+//! - `get_bor_tx_hash`: derived hash of a block's state sync transaction, if it has one
+//! - `state_sync_transaction`: that transaction as `eth_getTransactionByHash` returns it
+//! - `get_state_sync_events_by_contract`: events a bridge contract sent, from the index
+//!   (`bor_getStateSyncEventsByContract`)
+//! - `get_state_sync_events_by_block`: events a canonical block committed, from the index
+//!   (`bor_getStateSyncEventsByBlock`)
+//! - `get_bor_snapshot`: the snapshot at a block, rebuilt from its span and recent signers
+//!   (`debug_borSnapshot`)
+//! - `get_validators_history`: validator set changes over a block range, one page at a
+//!   time (`bor_getValidatorsHistory`)
 
-use alloy_primitives::{keccak256, Address, B256};
-use bor_consensus::{ecrecover_seal, ExtraData, SealError};
+use alloy_eips::BlockNumberOrTag;
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
+use crate::types::{
+    BlockStateSyncEventResponse, MilestoneResponse, StateSyncEventResponse, StateSyncTransactionResponse,
+    ValidatorInfo, ValidatorSetChange, ValidatorsHistoryResponse, WithMilestoneFinality,
+};
+use bor_chainspec::constants::STATE_RECEIVER_ADDRESS;
+use bor_primitives::{Validator, ValidatorSet};
+use bor_consensus::{
+    ecrecover_seal, BorSnapshot, ExtraData, MilestoneTracker, MilestoneVoteError, SealError,
+};
+use bor_storage::{
+    receipt_key::derived_bor_tx_hash, BadBlockRecord, BadBlockStore, StateSyncBlock,
+    StateSyncStore,
+};
+
+pub use heimdall_client::compute_root_hash;
+
+/// Most events `bor_getStateSyncEventsByContract` returns per call.
+pub const MAX_STATE_SYNC_EVENTS: usize = 1000;
 
 /// Errors from Bor RPC methods.
 #[derive(Debug, thiserror::Error)]
@@ -19,8 +51,19 @@ pub enum BorRpcError {
     ExtraDataError(String),
     #[error("invalid block range: start {start} > end {end}")]
     InvalidBlockRange { start: u64, end: u64 },
+    #[error("milestone not found: {0}")]
+    MilestoneNotFound(String),
+    #[error("invalid params: {0}")]
+    InvalidParams(String),
+    #[error("heimdall request failed: {0}")]
+    Heimdall(String),
+    #[error("milestone vote refused: {0}")]
+    VoteRefused(#[from] MilestoneVoteError),
 }
 
+/// Blocks that must follow a milestone's end block before the node votes for it.
+pub const VOTE_CONFIRMATION_BLOCKS: u64 = 16;
+
 /// Recover the block author (signer) from the header's extra data and seal hash.
 ///
 /// In Bor, `coinbase` is always `0x0`. The actual block producer must be recovered
@@ -32,43 +75,314 @@ pub fn get_author(seal_hash: &B256, extra_data: &[u8]) -> Result<Address, BorRpc
         .map_err(BorRpcError::SealError)
 }
 
-/// Compute the root hash for a range of block hashes.
+/// Returns the latest milestone known to the tracker.
+pub fn get_latest_milestone(tracker: &MilestoneTracker) -> Result<MilestoneResponse, BorRpcError> {
+    tracker
+        .latest()
+        .map(Into::into)
+        .ok_or_else(|| BorRpcError::MilestoneNotFound("latest".to_string()))
+}
+
+/// Returns a milestone by its Heimdall ID.
+pub fn get_milestone_by_id(
+    tracker: &MilestoneTracker,
+    milestone_id: &str,
+) -> Result<MilestoneResponse, BorRpcError> {
+    tracker
+        .by_id(milestone_id)
+        .map(Into::into)
+        .ok_or_else(|| BorRpcError::MilestoneNotFound(milestone_id.to_string()))
+}
+
+/// Vote on the milestone proposal `milestone_id` for blocks `start..=end` ending with
+/// `hash`, given the local chain's `head` and hash of `end`.
 ///
-/// This is a simple Merkle tree over the block hashes in the range [start, end].
-/// The hashes are repeatedly paired and hashed until a single root remains.
-pub fn compute_root_hash(block_hashes: &[B256]) -> B256 {
-    if block_hashes.is_empty() {
-        return B256::ZERO;
+/// As in Bor, the node only votes once `end` has [`VOTE_CONFIRMATION_BLOCKS`]
+/// descendants, and a vote locks `end` in the tracker (see [`MilestoneTracker::vote`]).
+pub fn get_vote_on_hash(
+    tracker: &MilestoneTracker,
+    start: u64,
+    end: u64,
+    hash: B256,
+    milestone_id: &str,
+    head: u64,
+    local_end_hash: Option<B256>,
+) -> Result<bool, BorRpcError> {
+    if start > end {
+        return Err(BorRpcError::InvalidBlockRange { start, end });
     }
-    if block_hashes.len() == 1 {
-        return block_hashes[0];
+    let confirmation = end.saturating_add(VOTE_CONFIRMATION_BLOCKS);
+    if head < confirmation {
+        return Err(BorRpcError::BlockNotFound(confirmation));
     }
+    let local_end_hash = local_end_hash.ok_or(BorRpcError::BlockNotFound(end))?;
+    tracker.vote(milestone_id, end, hash, local_end_hash)?;
+    Ok(true)
+}
 
-    let mut current_level: Vec<B256> = block_hashes.to_vec();
+/// Attach the milestone finality flag for `block_number` to a block response.
+pub fn with_milestone_finality<T>(
+    tracker: &MilestoneTracker,
+    block_number: u64,
+    block: T,
+) -> WithMilestoneFinality<T> {
+    WithMilestoneFinality {
+        inner: block,
+        milestone_finalized: tracker.is_finalized(block_number),
+    }
+}
 
-    // Pad to next power of 2
-    let next_pow2 = current_level.len().next_power_of_two();
-    while current_level.len() < next_pow2 {
-        current_level.push(B256::ZERO);
+/// Returns the derived hash of the state sync transaction of a block that committed
+/// `state_sync_count` events.
+///
+/// Blocks without state sync events have no Bor receipt and no state sync transaction, so
+/// this returns `None` and RPC responses carry `null` rather than a hash that resolves to
+/// nothing.
+pub fn get_bor_tx_hash(
+    block_number: u64,
+    block_hash: &B256,
+    state_sync_count: usize,
+) -> Option<B256> {
+    (state_sync_count > 0).then(|| derived_bor_tx_hash(block_number, block_hash))
+}
+
+/// The state sync transaction of `block`, which holds `transaction_count` transactions.
+///
+/// Returns `None` if the block committed no events, so has no such transaction.
+pub fn state_sync_transaction(
+    block: &StateSyncBlock,
+    transaction_count: u64,
+) -> Option<StateSyncTransactionResponse> {
+    let hash = get_bor_tx_hash(block.number, &block.hash, block.events.len())?;
+    let input: Vec<u8> = block.events.iter().flat_map(|event| event.data.iter().copied()).collect();
+    Some(StateSyncTransactionResponse {
+        hash,
+        block_hash: block.hash,
+        block_number: U64::from(block.number),
+        transaction_index: U64::from(transaction_count),
+        from: Address::ZERO,
+        to: STATE_RECEIVER_ADDRESS,
+        input: Bytes::from(input),
+        nonce: U64::ZERO,
+        gas: U64::ZERO,
+        gas_price: U256::ZERO,
+        value: U256::ZERO,
+        tx_type: U64::ZERO,
+        v: U64::ZERO,
+        r: U256::ZERO,
+        s: U256::ZERO,
+    })
+}
+
+/// Up to `limit` events sent by `contract`, from state ID `from_id` on, capped at
+/// [`MAX_STATE_SYNC_EVENTS`].
+///
+/// Only events of canonical blocks still in `store`, and whose contract the executor was
+/// told, are found.
+pub fn get_state_sync_events_by_contract(
+    store: &dyn StateSyncStore,
+    contract: Address,
+    from_id: u64,
+    limit: usize,
+) -> Vec<StateSyncEventResponse> {
+    store
+        .events_by_contract(&contract, from_id, limit.min(MAX_STATE_SYNC_EVENTS))
+        .into_iter()
+        .map(|(block_number, event)| StateSyncEventResponse {
+            id: U64::from(event.id),
+            contract,
+            data: event.data,
+            block_number: U64::from(block_number),
+        })
+        .collect()
+}
+
+/// The state sync events canonical block `number` committed, in order.
+///
+/// Empty for blocks that committed none, and for blocks no longer in `store`.
+pub fn get_state_sync_events_by_block(
+    store: &dyn StateSyncStore,
+    number: u64,
+) -> Vec<BlockStateSyncEventResponse> {
+    let Some(block) = store.block(number) else { return Vec::new() };
+    block
+        .events
+        .into_iter()
+        .map(|event| BlockStateSyncEventResponse {
+            id: U64::from(event.id),
+            contract: event.contract,
+            data: event.data,
+        })
+        .collect()
+}
+
+/// The snapshot at block `number`, `hash`, whose sprint runs under `validator_set`.
+///
+/// Boreth keeps no snapshots, so its recents are rebuilt from the signers of the blocks
+/// in the window Bor keeps, which `signer_of` recovers.
+pub fn get_bor_snapshot(
+    number: u64,
+    hash: B256,
+    validator_set: ValidatorSet,
+    mut signer_of: impl FnMut(u64) -> Result<Address, BorRpcError>,
+) -> Result<BorSnapshot, BorRpcError> {
+    let window = (validator_set.validators.len() / 2 + 1) as u64;
+    let mut snapshot = BorSnapshot::new(number, hash, validator_set);
+    // The genesis block carries no seal.
+    for block in number.saturating_sub(window).max(1)..=number {
+        snapshot.apply(block, signer_of(block)?);
     }
+    Ok(snapshot)
+}
 
-    while current_level.len() > 1 {
-        let mut next_level = Vec::with_capacity(current_level.len() / 2);
-        for pair in current_level.chunks(2) {
-            let mut combined = [0u8; 64];
-            combined[..32].copy_from_slice(pair[0].as_slice());
-            combined[32..].copy_from_slice(pair[1].as_slice());
-            next_level.push(keccak256(combined));
+/// Most validator set changes `bor_getValidatorsHistory` returns per page.
+pub const MAX_VALIDATOR_HISTORY_CHANGES: usize = 100;
+
+/// Most sprints `bor_getValidatorsHistory` reads the validator set of per page.
+pub const MAX_VALIDATOR_HISTORY_SPRINTS: usize = 1_000;
+
+/// The changes of the validator set over blocks `from..=to`, one page at a time.
+///
+/// The set only changes at sprint starts, so only those are read, with `validators_at`;
+/// `sprint_size` gives the chain's sprint length at a block. The first page starts with
+/// the set at `from`. A page ends after [`MAX_VALIDATOR_HISTORY_CHANGES`] changes or
+/// [`MAX_VALIDATOR_HISTORY_SPRINTS`] sprints, with the token that resumes the range
+/// where it stopped.
+pub fn get_validators_history<E: From<BorRpcError>>(
+    from: u64,
+    to: u64,
+    page_token: Option<&str>,
+    sprint_size: impl Fn(u64) -> u64,
+    mut validators_at: impl FnMut(u64) -> Result<Vec<Validator>, E>,
+) -> Result<ValidatorsHistoryResponse, E> {
+    if from > to {
+        return Err(BorRpcError::InvalidBlockRange { start: from, end: to }.into());
+    }
+    let cursor = match page_token {
+        None => from,
+        Some(token) => token
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .filter(|cursor| (from + 1..=to).contains(cursor))
+            .ok_or_else(|| BorRpcError::InvalidParams(format!("invalid page token {token}")))?,
+    };
+    let mut previous = if cursor == from { None } else { Some(validators_at(cursor - 1)?) };
+
+    let mut changes = Vec::new();
+    let mut block = cursor;
+    for sprint in 1.. {
+        let validators = validators_at(block)?;
+        if let Some(change) = ValidatorSetChange::between(block, previous.as_deref(), &validators) {
+            changes.push(change);
+        }
+        previous = Some(validators);
+
+        let size = sprint_size(block).max(1);
+        let next = (block / size).saturating_add(1).saturating_mul(size);
+        if next > to || next <= block {
+            break;
         }
-        current_level = next_level;
+        if changes.len() >= MAX_VALIDATOR_HISTORY_CHANGES || sprint >= MAX_VALIDATOR_HISTORY_SPRINTS
+        {
+            let next_page_token = Some(format!("{next:#x}"));
+            return Ok(ValidatorsHistoryResponse { changes, next_page_token });
+        }
+        block = next;
     }
+    Ok(ValidatorsHistoryResponse { changes, next_page_token: None })
+}
 
-    current_level[0]
+impl ValidatorSetChange {
+    /// The change from `previous`, the set before block `number`, to `validators`, the
+    /// set from it on; `None` if no validator joined, left or changed its voting power.
+    ///
+    /// Without a previous set, every validator counts as joining.
+    pub fn between(
+        number: u64,
+        previous: Option<&[Validator]>,
+        validators: &[Validator],
+    ) -> Option<Self> {
+        let powers = |set: &[Validator]| -> Vec<(Address, i64)> {
+            set.iter().map(|v| (v.address, v.voting_power)).collect()
+        };
+        if previous.is_some_and(|previous| powers(previous) == powers(validators)) {
+            return None;
+        }
+        let previous = previous.unwrap_or_default();
+        let joined = |set: &[Validator], other: &[Validator]| -> Vec<Address> {
+            set.iter()
+                .map(|v| v.address)
+                .filter(|address| !other.iter().any(|v| v.address == *address))
+                .collect()
+        };
+        Some(Self {
+            block_number: U64::from(number),
+            added: joined(validators, previous),
+            removed: joined(previous, validators),
+            validators: validators
+                .iter()
+                .map(|v| ValidatorInfo {
+                    address: v.address,
+                    voting_power: v.voting_power,
+                    proposer_priority: v.proposer_priority,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Returns the recorded bad blocks, most recent first.
+pub fn get_bad_blocks(store: &dyn BadBlockStore) -> Vec<BadBlockRecord> {
+    store.bad_blocks()
+}
+
+/// Resolve a block tag to a concrete block number using Bor finality semantics.
+///
+/// Bor has no beacon chain, so `finalized` maps to the latest Heimdall milestone and `safe`
+/// to the latest checkpoint. `pending` resolves to the head, matching bor-geth. Returns
+/// `None` when the tag cannot be resolved yet (no milestone or checkpoint observed).
+pub fn resolve_block_tag(
+    tag: BlockNumberOrTag,
+    head: u64,
+    tracker: &MilestoneTracker,
+) -> Option<u64> {
+    match tag {
+        BlockNumberOrTag::Latest | BlockNumberOrTag::Pending => Some(head),
+        BlockNumberOrTag::Earliest => Some(0),
+        BlockNumberOrTag::Number(number) => Some(number),
+        // Never report a finality height the local chain has not reached yet.
+        BlockNumberOrTag::Finalized => tracker.finalized_tag_block().map(|n| n.min(head)),
+        BlockNumberOrTag::Safe => tracker.safe_tag_block().map(|n| n.min(head)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_primitives::keccak256;
+    use bor_storage::{CommittedStateSync, InMemoryStateSyncStore};
+    use heimdall_client::Milestone;
+
+    #[test]
+    fn test_bor_tx_hash_null_without_state_syncs() {
+        let hash = B256::from([0xab; 32]);
+        let none = get_bor_tx_hash(100, &hash, 0);
+        assert_eq!(none, None);
+        assert_eq!(serde_json::to_value(none).unwrap(), serde_json::Value::Null);
+        assert_eq!(get_bor_tx_hash(100, &hash, 3), Some(derived_bor_tx_hash(100, &hash)));
+    }
+
+    fn tracker_with_milestone(id: &str, end_block: u64) -> MilestoneTracker {
+        let tracker = MilestoneTracker::new();
+        tracker.update(Milestone {
+            milestone_id: id.to_string(),
+            start_block: 0,
+            end_block,
+            hash: B256::from([0x42; 32]),
+            proposer: Address::ZERO,
+        });
+        tracker
+    }
 
     #[test]
     fn test_root_hash_empty() {
@@ -126,6 +440,285 @@ mod tests {
         assert_ne!(compute_root_hash(&hashes_a), compute_root_hash(&hashes_b));
     }
 
+    #[test]
+    fn test_latest_milestone() {
+        let tracker = tracker_with_milestone("ms-7", 500);
+        let resp = get_latest_milestone(&tracker).unwrap();
+        assert_eq!(resp.milestone_id, "ms-7");
+        assert_eq!(resp.end_block, 500);
+
+        let empty = MilestoneTracker::new();
+        assert!(matches!(
+            get_latest_milestone(&empty),
+            Err(BorRpcError::MilestoneNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_milestone_by_id() {
+        let tracker = tracker_with_milestone("ms-7", 500);
+        assert_eq!(get_milestone_by_id(&tracker, "ms-7").unwrap().end_block, 500);
+        assert!(get_milestone_by_id(&tracker, "ms-8").is_err());
+    }
+
+    #[test]
+    fn test_vote_on_hash() {
+        let tracker = tracker_with_milestone("ms-7", 500);
+        let hash = B256::from([0x99; 32]);
+        let head = 600 + VOTE_CONFIRMATION_BLOCKS;
+
+        assert!(matches!(
+            get_vote_on_hash(&tracker, 601, 600, hash, "ms-8", head, Some(hash)),
+            Err(BorRpcError::InvalidBlockRange { start: 601, end: 600 })
+        ));
+        assert!(matches!(
+            get_vote_on_hash(&tracker, 501, 600, hash, "ms-8", head - 1, Some(hash)),
+            Err(BorRpcError::BlockNotFound(616))
+        ));
+        assert!(matches!(
+            get_vote_on_hash(&tracker, 501, 600, hash, "ms-8", head, None),
+            Err(BorRpcError::BlockNotFound(600))
+        ));
+        assert!(matches!(
+            get_vote_on_hash(&tracker, 501, 600, hash, "ms-8", head, Some(B256::ZERO)),
+            Err(BorRpcError::VoteRefused(MilestoneVoteError::HashMismatch { .. }))
+        ));
+        assert!(matches!(
+            get_vote_on_hash(&tracker, 401, 500, hash, "ms-8", head, Some(hash)),
+            Err(BorRpcError::VoteRefused(MilestoneVoteError::Finalized { .. }))
+        ));
+
+        assert!(get_vote_on_hash(&tracker, 501, 600, hash, "ms-8", head, Some(hash)).unwrap());
+        assert_eq!(tracker.locked().unwrap().end_block, 600);
+    }
+
+    #[test]
+    fn test_block_milestone_finality_flag() {
+        let tracker = tracker_with_milestone("ms-7", 500);
+        let block = serde_json::json!({ "number": "0x1f4" });
+
+        let finalized = with_milestone_finality(&tracker, 500, block.clone());
+        let json = serde_json::to_value(&finalized).unwrap();
+        assert_eq!(json["number"], "0x1f4");
+        assert_eq!(json["milestoneFinalized"], true);
+
+        let pending = with_milestone_finality(&tracker, 501, block);
+        assert!(!pending.milestone_finalized);
+    }
+
+    #[test]
+    fn test_resolve_finalized_and_safe_tags() {
+        let tracker = tracker_with_milestone("ms-7", 500);
+        tracker.update_checkpoint(heimdall_client::Checkpoint {
+            start_block: 0,
+            end_block: 255,
+            root_hash: B256::ZERO,
+            proposer: Address::ZERO,
+        });
+
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Finalized, 1_000, &tracker), Some(500));
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Safe, 1_000, &tracker), Some(255));
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Latest, 1_000, &tracker), Some(1_000));
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Number(7), 1_000, &tracker), Some(7));
+        // Finality never runs ahead of the local head.
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Finalized, 400, &tracker), Some(400));
+    }
+
+    #[test]
+    fn test_resolve_tags_before_finality_data() {
+        let tracker = MilestoneTracker::new();
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Finalized, 10, &tracker), None);
+        assert_eq!(resolve_block_tag(BlockNumberOrTag::Safe, 10, &tracker), None);
+    }
+
+    #[test]
+    fn test_bad_blocks_serialize_with_context() {
+        let mut store = bor_storage::InMemoryBadBlockStore::default();
+        store.put_bad_block(BadBlockRecord {
+            number: 10,
+            hash: B256::with_last_byte(10),
+            parent_hash: B256::with_last_byte(9),
+            signer: None,
+            check: "gas_used".to_string(),
+            error: "block gas used mismatch".to_string(),
+            snapshot: None,
+            span_id: Some(0),
+            state_sync_ids: vec![1, 2],
+        });
+
+        let json = serde_json::to_value(get_bad_blocks(&store)).unwrap();
+        assert_eq!(json[0]["number"], 10);
+        assert_eq!(json[0]["check"], "gas_used");
+        assert_eq!(json[0]["spanId"], 0);
+        assert_eq!(json[0]["stateSyncIds"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_state_sync_transaction() {
+        let block = StateSyncBlock {
+            number: 6400,
+            hash: B256::with_last_byte(0x64),
+            events: vec![
+                CommittedStateSync { id: 7, contract: None, data: Bytes::from_static(&[0xaa]) },
+                CommittedStateSync {
+                    id: 8,
+                    contract: None,
+                    data: Bytes::from_static(&[0xbb, 0xcc]),
+                },
+            ],
+        };
+        let tx = state_sync_transaction(&block, 3).unwrap();
+        assert_eq!(tx.hash, derived_bor_tx_hash(6400, &block.hash));
+        assert_eq!(tx.input, Bytes::from_static(&[0xaa, 0xbb, 0xcc]));
+
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["blockNumber"], "0x1900");
+        assert_eq!(json["transactionIndex"], "0x3");
+        assert_eq!(json["from"], "0x0000000000000000000000000000000000000000");
+        assert_eq!(json["to"], "0x0000000000000000000000000000000000001001");
+        assert_eq!(json["type"], "0x0");
+        assert_eq!(json["gasPrice"], "0x0");
+
+        let empty = StateSyncBlock { events: Vec::new(), ..block };
+        assert!(state_sync_transaction(&empty, 3).is_none());
+    }
+
+    #[test]
+    fn test_state_sync_events_by_contract() {
+        let bridge = Address::repeat_byte(0xb1);
+        let event = |id, contract| CommittedStateSync {
+            id,
+            contract: Some(contract),
+            data: Bytes::from(vec![id as u8]),
+        };
+        let mut store = InMemoryStateSyncStore::default();
+        let events = vec![event(1, bridge), event(2, Address::ZERO), event(3, bridge)];
+        store.record_executed(16, B256::ZERO, events);
+        store.canonicalize(16, B256::ZERO, B256::with_last_byte(16));
+
+        let found = get_state_sync_events_by_contract(&store, bridge, 2, usize::MAX);
+        assert_eq!(found.len(), 1);
+        let json = serde_json::to_value(&found[0]).unwrap();
+        assert_eq!(json["id"], "0x3");
+        assert_eq!(json["blockNumber"], "0x10");
+        assert_eq!(get_state_sync_events_by_contract(&store, bridge, 0, 10).len(), 2);
+    }
+
+    #[test]
+    fn test_state_sync_events_by_block() {
+        let bridge = Address::repeat_byte(0xb1);
+        let mut store = InMemoryStateSyncStore::default();
+        let events = vec![
+            CommittedStateSync { id: 4, contract: Some(bridge), data: Bytes::from_static(&[4]) },
+            CommittedStateSync { id: 5, contract: None, data: Bytes::from_static(&[5]) },
+        ];
+        store.record_executed(32, B256::ZERO, events);
+        store.canonicalize(32, B256::ZERO, B256::with_last_byte(32));
+
+        let found = get_state_sync_events_by_block(&store, 32);
+        assert_eq!(found.iter().map(|event| event.id.to::<u64>()).collect::<Vec<_>>(), [4, 5]);
+        let json = serde_json::to_value(&found).unwrap();
+        assert_eq!(json[0]["contract"], serde_json::to_value(bridge).unwrap());
+        assert_eq!(json[1]["data"], "0x05");
+        assert!(json[1].get("contract").is_none());
+        assert!(get_state_sync_events_by_block(&store, 16).is_empty());
+    }
+
+    #[test]
+    fn test_bor_snapshot_recents_window() {
+        let validator = |byte| bor_primitives::Validator {
+            id: byte as u64,
+            address: Address::repeat_byte(byte),
+            voting_power: 10,
+            signer: Address::repeat_byte(byte),
+            proposer_priority: 0,
+        };
+        let set = ValidatorSet {
+            validators: vec![validator(1), validator(2), validator(3)],
+            proposer: None,
+        };
+        let signer = |block: u64| Address::repeat_byte(block as u8 % 3 + 1);
+
+        let mut asked = Vec::new();
+        let snapshot = get_bor_snapshot(100, B256::with_last_byte(100), set.clone(), |block| {
+            asked.push(block);
+            Ok(signer(block))
+        })
+        .unwrap();
+        assert_eq!(asked, vec![98, 99, 100]);
+        assert_eq!(snapshot.number, 100);
+        assert_eq!(snapshot.recents.get(&99), Some(&signer(99)));
+
+        let snapshot = get_bor_snapshot(1, B256::ZERO, set, |block| Ok(signer(block))).unwrap();
+        assert_eq!(snapshot.recents.keys().copied().collect::<Vec<_>>(), vec![1]);
+        let empty = ValidatorSet { validators: vec![], proposer: None };
+        let err = get_bor_snapshot(5, B256::ZERO, empty, |block| {
+            Err(BorRpcError::BlockNotFound(block))
+        });
+        assert!(matches!(err, Err(BorRpcError::BlockNotFound(4))));
+    }
+
+    fn validators(powers: &[(u8, i64)]) -> Vec<Validator> {
+        powers
+            .iter()
+            .map(|&(byte, voting_power)| Validator {
+                id: byte as u64,
+                address: Address::repeat_byte(byte),
+                voting_power,
+                signer: Address::repeat_byte(byte),
+                proposer_priority: 0,
+            })
+            .collect()
+    }
+
+    /// Validators of block `number` in the history tests: 1 and 2 until 640, then 2 with
+    /// more power until 1_600, then 2 and 3.
+    fn history(number: u64) -> Result<Vec<Validator>, BorRpcError> {
+        Ok(match number {
+            ..640 => validators(&[(1, 10), (2, 10)]),
+            640..1_600 => validators(&[(2, 30)]),
+            _ => validators(&[(2, 30), (3, 5)]),
+        })
+    }
+
+    #[test]
+    fn test_validators_history_reports_changes() {
+        let page = get_validators_history(100, 2_000, None, |_| 64, history).unwrap();
+        assert_eq!(page.next_page_token, None);
+        let blocks: Vec<_> = page.changes.iter().map(|c| c.block_number.to::<u64>()).collect();
+        assert_eq!(blocks, vec![100, 640, 1_600]);
+        assert_eq!(page.changes[0].added.len(), 2);
+        assert_eq!(page.changes[1].removed, vec![Address::repeat_byte(1)]);
+        assert!(page.changes[1].added.is_empty());
+        assert_eq!(page.changes[1].validators[0].voting_power, 30);
+        assert_eq!(page.changes[2].added, vec![Address::repeat_byte(3)]);
+
+        let err = get_validators_history(10, 5, None, |_| 64, history);
+        assert!(matches!(err, Err(BorRpcError::InvalidBlockRange { start: 10, end: 5 })));
+    }
+
+    #[test]
+    fn test_validators_history_pages_resume_where_they_stopped() {
+        let to = 64 * (MAX_VALIDATOR_HISTORY_SPRINTS as u64 + 500);
+        let mut reads = 0;
+        let mut counted = |number| {
+            reads += 1;
+            history(number)
+        };
+        let first = get_validators_history(0, to, None, |_| 64, &mut counted).unwrap();
+        assert_eq!(reads, MAX_VALIDATOR_HISTORY_SPRINTS);
+        assert_eq!(first.changes.len(), 3);
+        let token = first.next_page_token.unwrap();
+        assert_eq!(token, format!("{:#x}", 64 * MAX_VALIDATOR_HISTORY_SPRINTS as u64));
+
+        let second = get_validators_history(0, to, Some(&token), |_| 64, history).unwrap();
+        assert!(second.changes.is_empty());
+        assert_eq!(second.next_page_token, None);
+
+        let err = get_validators_history(0, to, Some("0x0"), |_| 64, history);
+        assert!(matches!(err, Err(BorRpcError::InvalidParams(_))));
+    }
+
     #[test]
     fn test_invalid_block_range_error() {
         let err = BorRpcError::InvalidBlockRange { start: 100, end: 50 };
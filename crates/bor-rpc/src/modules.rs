@@ -0,0 +1,747 @@
+//! The JSON-RPC modules serving the Bor methods.
+//!
+//! Each function builds the [`RpcModule`] of a group of methods over the chain provider and
+//! the Bor state the node keeps, for the node to merge into its RPC server. The node's own
+//! types stay behind the [`BorAdminApi`] and [`BorRecoveryApi`] traits, or behind the
+//! closures a module is built with.
+//!
+//! Methods fail with the node's error type `E`, which gives each failure its JSON-RPC code
+//! through [`RpcErrorCode`].
+
+use crate::api::{BorAdminApi, BorRecoveryApi};
+use crate::fee_history::{fee_history, FeeHistoryBlock, MAX_FEE_HISTORY_BLOCKS};
+use crate::gas_price::{suggest_priority_fee, PriorityFeeConfig};
+use crate::methods::{
+    get_author, get_bad_blocks, get_bor_snapshot, get_latest_milestone, get_milestone_by_id,
+    get_state_sync_events_by_block, get_state_sync_events_by_contract, get_validators_history,
+    get_vote_on_hash, resolve_block_tag, state_sync_transaction, with_milestone_finality,
+    BorRpcError, MAX_STATE_SYNC_EVENTS,
+};
+use crate::root_hash::{
+    validate_checkpoint_range, RootHashBuilder, RootHashCache, ROOT_HASH_HEADER_BATCH,
+};
+use crate::types::{
+    CurrentValidatorsResponse, ProducerPerformanceResponse, SimulatedProposalResponse,
+    ValidatorInfo,
+};
+use alloy_consensus::Transaction;
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
+use alloy_rpc_types_eth::{state::StateOverride, BlockOverrides};
+use bor_chainspec::{BorHardforks, ScheduleError};
+use bor_consensus::{
+    compute_seal_hash, sprint_validator_set, BorSnapshot, MilestoneTracker,
+    SharedLastValidatorMismatch,
+};
+use bor_evm::{HistoricalValidatorReader, ValidatorContractError};
+use bor_storage::{
+    SharedBadBlockStore, SharedSnapshotStore, SharedStateSyncStore, SharedTotalDifficultyIndex,
+};
+use heimdall_client::SharedSpanCache;
+use jsonrpsee::{core::RegisterMethodError, types::ErrorObjectOwned, RpcModule};
+use reth_chainspec::ChainSpec;
+use reth_provider::{
+    BlockBodyIndicesProvider, BlockHashReader, BlockNumReader, BlockReader, ChainSpecProvider,
+    HeaderProvider, ProviderError, StateProviderFactory,
+};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::debug;
+
+/// An error a Bor RPC method fails with.
+pub trait RpcErrorCode: std::fmt::Display {
+    /// The JSON-RPC error code the error is reported with.
+    fn code(&self) -> i32;
+}
+
+/// Report `err` to an RPC caller as an `E`, with the code and message of `E`.
+pub fn rpc_error<E: RpcErrorCode>(err: impl Into<E>) -> ErrorObjectOwned {
+    let err = err.into();
+    ErrorObjectOwned::owned(err.code(), err.to_string(), None::<()>)
+}
+
+/// The `bor_setHeimdallUrl` / `bor_setSigner` admin methods.
+pub fn bor_admin_module<A, E>(api: A) -> Result<RpcModule<A>, RegisterMethodError>
+where
+    A: BorAdminApi<Error: Into<E>> + Send + Sync + 'static,
+    E: RpcErrorCode + 'static,
+{
+    let mut module = RpcModule::new(api);
+    module.register_method("bor_setHeimdallUrl", |rpc_params, ctx, _| {
+        let url: String = rpc_params.one()?;
+        ctx.bor_set_heimdall_url(url).map_err(rpc_error::<E>)
+    })?;
+    module.register_method("bor_setSigner", |rpc_params, ctx, _| {
+        let signer: Address = rpc_params.one()?;
+        ctx.bor_set_signer(signer).map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// The `bor_resyncSpan` / `bor_refetchStateSyncEvents` / `bor_resumeProduction` admin
+/// methods.
+pub fn bor_resync_module<R, E>(resync: R) -> Result<RpcModule<R>, RegisterMethodError>
+where
+    R: BorRecoveryApi<Error: Into<E>> + Send + Sync + 'static,
+    E: RpcErrorCode + 'static,
+{
+    let mut module = RpcModule::new(resync);
+    module.register_async_method("bor_resyncSpan", |rpc_params, resync, _| async move {
+        let span_id: u64 = rpc_params.one()?;
+        resync.bor_resync_span(span_id).await.map_err(rpc_error::<E>)
+    })?;
+    module.register_async_method(
+        "bor_refetchStateSyncEvents",
+        |rpc_params, resync, _| async move {
+            let from_id: u64 = rpc_params.one()?;
+            let count =
+                resync.bor_refetch_state_sync_events(from_id).await.map_err(rpc_error::<E>)?;
+            Ok::<_, ErrorObjectOwned>(U64::from(count))
+        },
+    )?;
+    module.register_method("bor_resumeProduction", |_, resync, _| {
+        Ok::<_, ErrorObjectOwned>(resync.bor_resume_production())
+    })?;
+    Ok(module)
+}
+
+/// Inputs of `bor_simulateProposal`.
+pub struct ProposalContext<P, F> {
+    provider: P,
+    /// Builds the block this node would produce at a height.
+    simulate: F,
+}
+
+/// `bor_simulateProposal`, the block this node would produce at a height.
+///
+/// A block number or tag names the height to simulate; a hash names the block to build on.
+pub fn bor_proposal_module<P, F, E>(
+    provider: P,
+    simulate: F,
+) -> Result<RpcModule<ProposalContext<P, F>>, RegisterMethodError>
+where
+    P: BlockNumReader + Send + Sync + 'static,
+    F: Fn(u64) -> Result<SimulatedProposalResponse, E> + Send + Sync + 'static,
+    E: RpcErrorCode + From<ProviderError> + From<BorRpcError> + 'static,
+{
+    let mut module = RpcModule::new(ProposalContext { provider, simulate });
+    module.register_blocking_method("bor_simulateProposal", |rpc_params, ctx, _| {
+        let block: BlockId = rpc_params.one()?;
+        let number = match block {
+            BlockId::Number(BlockNumberOrTag::Number(number)) => number,
+            BlockId::Number(_) => ctx.provider.best_block_number().map_err(rpc_error::<E>)? + 1,
+            BlockId::Hash(hash) => {
+                let hash = hash.block_hash;
+                let parent = ctx.provider.block_number(hash).map_err(rpc_error::<E>)?;
+                let unknown = BorRpcError::InvalidParams(format!("unknown block {hash}"));
+                parent.ok_or_else(|| rpc_error::<E>(unknown))? + 1
+            }
+        };
+        (ctx.simulate)(number).map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// `bor_getCurrentValidators` and `bor_getValidatorsHistory`, read from the ValidatorSet
+/// contract: at the head's state, and at the state before each sprint start of a range.
+pub fn bor_validators_module<P, E>(
+    reader: HistoricalValidatorReader<P, ChainSpec>,
+) -> Result<RpcModule<HistoricalValidatorReader<P, ChainSpec>>, RegisterMethodError>
+where
+    P: StateProviderFactory
+        + BlockNumReader
+        + HeaderProvider<Header = alloy_consensus::Header>
+        + ChainSpecProvider<ChainSpec: BorHardforks>
+        + Send
+        + Sync
+        + 'static,
+    E: RpcErrorCode
+        + From<ProviderError>
+        + From<ValidatorContractError>
+        + From<BorRpcError>
+        + 'static,
+{
+    let mut module = RpcModule::new(reader);
+    module.register_blocking_method("bor_getCurrentValidators", |_, reader, _| {
+        let head = reader.provider().best_block_number().map_err(rpc_error::<E>)?;
+        let validators = reader.validators_at(head.into(), head + 1).map_err(rpc_error::<E>)?;
+        Ok::<_, ErrorObjectOwned>(CurrentValidatorsResponse {
+            validators: validators
+                .into_iter()
+                .map(|v| ValidatorInfo {
+                    address: v.address,
+                    voting_power: v.voting_power,
+                    proposer_priority: v.proposer_priority,
+                })
+                .collect(),
+        })
+    })?;
+    module.register_blocking_method("bor_getValidatorsHistory", |rpc_params, reader, _| {
+        let mut seq = rpc_params.sequence();
+        let from: u64 = seq.next()?;
+        let to: u64 = seq.next()?;
+        let page_token: Option<String> = seq.optional_next()?;
+
+        let head = reader.provider().best_block_number().map_err(rpc_error::<E>)?;
+        if to > head {
+            return Err(rpc_error::<E>(BorRpcError::BlockNotFound(to)));
+        }
+        let chain_spec = reader.provider().chain_spec();
+        get_validators_history::<E>(
+            from,
+            to,
+            page_token.as_deref(),
+            |number| chain_spec.bor_sprint_size(number),
+            |number| Ok(reader.validators_at(number.saturating_sub(1).into(), number)?),
+        )
+        .map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// Inputs of `bor_getRootHash`.
+pub struct RootHashContext<P> {
+    provider: P,
+    cache: RootHashCache,
+}
+
+/// `bor_getRootHash`, the checkpoint root of a block range, built from headers read in
+/// batches and cached like Bor's.
+pub fn bor_root_hash_module<P, E>(
+    provider: P,
+) -> Result<RpcModule<RootHashContext<P>>, RegisterMethodError>
+where
+    P: BlockNumReader + HeaderProvider<Header = alloy_consensus::Header> + Send + Sync + 'static,
+    E: RpcErrorCode + From<ProviderError> + From<BorRpcError> + 'static,
+{
+    let mut module = RpcModule::new(RootHashContext { provider, cache: RootHashCache::default() });
+    module.register_blocking_method("bor_getRootHash", |rpc_params, ctx, _| {
+        let mut seq = rpc_params.sequence();
+        let start: u64 = seq.next()?;
+        let end: u64 = seq.next()?;
+
+        let head = ctx.provider.best_block_number().map_err(rpc_error::<E>)?;
+        validate_checkpoint_range(start, end, head).map_err(rpc_error::<E>)?;
+        ctx.cache.get_or_compute(start, end, || {
+            let mut builder = RootHashBuilder::new(start);
+            while builder.next_block() <= end {
+                let from = builder.next_block();
+                let to = end.min(from + ROOT_HASH_HEADER_BATCH - 1);
+                let headers = ctx.provider.headers_range(from..=to).map_err(rpc_error::<E>)?;
+                if headers.is_empty() {
+                    return Err(rpc_error::<E>(BorRpcError::BlockNotFound(from)));
+                }
+                for header in headers {
+                    builder
+                        .push(
+                            header.number,
+                            header.timestamp,
+                            header.transactions_root,
+                            header.receipts_root,
+                        )
+                        .map_err(rpc_error::<E>)?;
+                }
+            }
+            Ok(builder.root())
+        })
+    })?;
+    Ok(module)
+}
+
+/// Inputs of `bor_getVoteOnHash`.
+pub struct VoteContext<P> {
+    provider: P,
+    tracker: Arc<MilestoneTracker>,
+}
+
+/// `bor_getVoteOnHash`, which Heimdall calls on validators to confirm a milestone proposal
+/// against their canonical chain.
+pub fn bor_vote_module<P, E>(
+    provider: P,
+    tracker: Arc<MilestoneTracker>,
+) -> Result<RpcModule<VoteContext<P>>, RegisterMethodError>
+where
+    P: BlockNumReader + BlockHashReader + Send + Sync + 'static,
+    E: RpcErrorCode + From<ProviderError> + From<BorRpcError> + 'static,
+{
+    let mut module = RpcModule::new(VoteContext { provider, tracker });
+    module.register_blocking_method("bor_getVoteOnHash", |rpc_params, ctx, _| {
+        let mut seq = rpc_params.sequence();
+        let start: u64 = seq.next()?;
+        let end: u64 = seq.next()?;
+        let hash: B256 = seq.next()?;
+        let milestone_id: String = seq.next()?;
+
+        let head = ctx.provider.best_block_number().map_err(rpc_error::<E>)?;
+        let local_end_hash = ctx.provider.block_hash(end).map_err(rpc_error::<E>)?;
+        let vote =
+            get_vote_on_hash(&ctx.tracker, start, end, hash, &milestone_id, head, local_end_hash)
+                .map_err(rpc_error::<E>)?;
+        debug!(target: "bor::rpc", start, end, %hash, milestone_id, "voted on milestone");
+        Ok::<_, ErrorObjectOwned>(vote)
+    })?;
+    Ok(module)
+}
+
+/// The Heimdall milestones the node tracks:
+/// - `bor_getLatestMilestone`, the latest milestone
+/// - `bor_getMilestoneByID`, a recent milestone by its Heimdall ID
+pub fn bor_milestone_module<E>(
+    tracker: Arc<MilestoneTracker>,
+) -> Result<RpcModule<Arc<MilestoneTracker>>, RegisterMethodError>
+where
+    E: RpcErrorCode + From<BorRpcError> + 'static,
+{
+    let mut module = RpcModule::new(tracker);
+    module.register_method("bor_getLatestMilestone", |_, tracker, _| {
+        get_latest_milestone(tracker).map_err(rpc_error::<E>)
+    })?;
+    module.register_method("bor_getMilestoneByID", |rpc_params, tracker, _| {
+        let milestone_id: String = rpc_params.one()?;
+        get_milestone_by_id(tracker, &milestone_id).map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// `bor_getProducerPerformance`, expected against actual producers of a block range, as
+/// `performance` reports them from the node's producer monitor.
+pub fn bor_monitor_module<F, E>(performance: F) -> Result<RpcModule<F>, RegisterMethodError>
+where
+    F: Fn(u64, u64) -> Result<ProducerPerformanceResponse, E> + Send + Sync + 'static,
+    E: RpcErrorCode + 'static,
+{
+    let mut module = RpcModule::new(performance);
+    module.register_method("bor_getProducerPerformance", |rpc_params, performance, _| {
+        let mut seq = rpc_params.sequence();
+        let start: u64 = seq.next()?;
+        let end: u64 = seq.next()?;
+        performance(start, end).map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// The state sync events of canonical blocks, from the index of canonical state syncs:
+/// - `bor_getStateSyncEventsByContract`, the events a bridge contract sent
+/// - `bor_getStateSyncEventsByBlock`, the event IDs and payloads a block committed
+pub fn bor_state_sync_module(
+    store: SharedStateSyncStore,
+) -> Result<RpcModule<SharedStateSyncStore>, RegisterMethodError> {
+    let mut module = RpcModule::new(store);
+    module.register_method("bor_getStateSyncEventsByContract", |rpc_params, store, _| {
+        let mut seq = rpc_params.sequence();
+        let contract: Address = seq.next()?;
+        let from_id: Option<U64> = seq.optional_next()?;
+        let limit: Option<U64> = seq.optional_next()?;
+
+        let from_id = from_id.map_or(0, |id| id.to());
+        let limit = limit.map_or(MAX_STATE_SYNC_EVENTS, |limit| limit.saturating_to());
+        let store = store.read().expect("state sync store lock poisoned");
+        Ok::<_, ErrorObjectOwned>(get_state_sync_events_by_contract(
+            &*store, contract, from_id, limit,
+        ))
+    })?;
+    module.register_method("bor_getStateSyncEventsByBlock", |rpc_params, store, _| {
+        let number: U64 = rpc_params.one()?;
+        let store = store.read().expect("state sync store lock poisoned");
+        Ok::<_, ErrorObjectOwned>(get_state_sync_events_by_block(&*store, number.to()))
+    })?;
+    Ok(module)
+}
+
+/// Inputs of the `debug_` methods.
+pub struct DebugContext<P> {
+    /// Chain the blocks are read from.
+    pub provider: P,
+    /// Blocks rejected by Bor consensus.
+    pub bad_blocks: SharedBadBlockStore,
+    /// Spans cached from Heimdall.
+    pub spans: SharedSpanCache,
+    /// The index of canonical state syncs.
+    pub state_syncs: SharedStateSyncStore,
+    /// Snapshots stored by block hash.
+    pub snapshots: SharedSnapshotStore,
+    /// The last validator bytes mismatch consensus recorded.
+    pub validator_mismatch: SharedLastValidatorMismatch,
+}
+
+/// The `debug_` methods exposing Bor data:
+/// - `debug_borBadBlocks`, blocks rejected by Bor consensus with their Bor context
+/// - `debug_borSpan`, a span as cached from Heimdall
+/// - `debug_borSnapshot`, the snapshot at a block, as stored or from its span and signers
+/// - `debug_borStateSyncEvents`, the state sync events a canonical block committed
+/// - `debug_borLastValidatorMismatch`, the last sprint-end block whose validator bytes
+///   differed from the next span
+///
+/// They return what the node holds, unprocessed, to compare against a bor-geth node.
+pub fn bor_debug_module<P, E>(
+    context: DebugContext<P>,
+) -> Result<RpcModule<DebugContext<P>>, RegisterMethodError>
+where
+    P: BlockNumReader
+        + HeaderProvider<Header = alloy_consensus::Header>
+        + ChainSpecProvider<ChainSpec = ChainSpec>
+        + Send
+        + Sync
+        + 'static,
+    E: RpcErrorCode
+        + From<ProviderError>
+        + From<serde_json::Error>
+        + From<ScheduleError>
+        + From<BorRpcError>
+        + 'static,
+{
+    let mut module = RpcModule::new(context);
+    module.register_method("debug_borBadBlocks", |_, ctx, _| {
+        let store = ctx.bad_blocks.read().expect("bad block store lock poisoned");
+        Ok::<_, ErrorObjectOwned>(get_bad_blocks(&*store))
+    })?;
+    module.register_method("debug_borSpan", |rpc_params, ctx, _| {
+        let span_id: u64 = rpc_params.one()?;
+        let mut spans = ctx.spans.lock().expect("span cache lock poisoned");
+        Ok::<_, ErrorObjectOwned>(spans.get(span_id).cloned())
+    })?;
+    module.register_blocking_method("debug_borSnapshot", |rpc_params, ctx, _| {
+        let block: BlockNumberOrTag = rpc_params.one()?;
+        let number = debug_block_number::<_, E>(&ctx.provider, block)?;
+        let Some(header) = ctx.provider.sealed_header(number).map_err(rpc_error::<E>)? else {
+            return Ok(None);
+        };
+        let stored = ctx.snapshots.read().expect("snapshot store lock poisoned");
+        if let Some(snapshot) = stored.get_snapshot(&header.hash().0) {
+            return BorSnapshot::decode(&snapshot).map(Some).map_err(rpc_error::<E>);
+        }
+        drop(stored);
+        let span =
+            ctx.spans.lock().expect("span cache lock poisoned").span_for_block(number).cloned();
+        let chain_spec = ctx.provider.chain_spec();
+        let validator_set = span
+            .and_then(|span| sprint_validator_set(&*chain_spec, &span, number).transpose())
+            .transpose()
+            .map_err(rpc_error::<E>)?;
+        let Some(validator_set) = validator_set else { return Ok(None) };
+        let signer_of = |number| {
+            let header = ctx.provider.sealed_header(number).ok().flatten();
+            let header = header.ok_or(BorRpcError::BlockNotFound(number))?;
+            get_author(&compute_seal_hash(header.header()), &header.extra_data)
+        };
+        get_bor_snapshot(number, header.hash(), validator_set, signer_of)
+            .map(Some)
+            .map_err(rpc_error::<E>)
+    })?;
+    module.register_method("debug_borStateSyncEvents", |rpc_params, ctx, _| {
+        let block: BlockNumberOrTag = rpc_params.one()?;
+        let number = debug_block_number::<_, E>(&ctx.provider, block)?;
+        let store = ctx.state_syncs.read().expect("state sync store lock poisoned");
+        Ok::<_, ErrorObjectOwned>(store.block(number))
+    })?;
+    module.register_method("debug_borLastValidatorMismatch", |_, ctx, _| {
+        Ok::<_, ErrorObjectOwned>(ctx.validator_mismatch.get())
+    })?;
+    Ok(module)
+}
+
+/// The block a `debug_` method is asked about; tags other than `earliest` name the head.
+fn debug_block_number<P, E>(provider: &P, block: BlockNumberOrTag) -> Result<u64, ErrorObjectOwned>
+where
+    P: BlockNumReader,
+    E: RpcErrorCode + From<ProviderError>,
+{
+    match block {
+        BlockNumberOrTag::Number(number) => Ok(number),
+        BlockNumberOrTag::Earliest => Ok(0),
+        _ => provider.best_block_number().map_err(rpc_error::<E>),
+    }
+}
+
+/// Collect the `eth_feeHistory` inputs of blocks `oldest..=newest`.
+///
+/// Fails if any of them, or its receipts, is missing, rather than answering for fewer blocks
+/// than asked.
+pub fn fee_history_blocks<P, E>(
+    provider: &P,
+    oldest: u64,
+    newest: u64,
+) -> Result<Vec<FeeHistoryBlock>, E>
+where
+    P: BlockReader<
+        Block = reth_ethereum_primitives::Block,
+        Receipt = reth_ethereum_primitives::Receipt,
+    >,
+    E: From<ProviderError> + From<BorRpcError>,
+{
+    let mut blocks = Vec::new();
+    for number in oldest..=newest {
+        let missing = || BorRpcError::BlockNotFound(number);
+        let block = provider.block_by_number(number)?.ok_or_else(missing)?;
+        let receipts = provider.receipts_by_block(number.into())?.ok_or_else(missing)?;
+        let base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+        let mut previous_cumulative = 0;
+        let txs = block
+            .body
+            .transactions
+            .iter()
+            .zip(&receipts)
+            .map(|(tx, receipt)| {
+                let gas_used = receipt.cumulative_gas_used - previous_cumulative;
+                previous_cumulative = receipt.cumulative_gas_used;
+                (gas_used, tx.effective_tip_per_gas(base_fee).unwrap_or_default())
+            })
+            .collect();
+        blocks.push(FeeHistoryBlock {
+            number,
+            base_fee_per_gas: base_fee,
+            gas_used: block.header.gas_used,
+            gas_limit: block.header.gas_limit,
+            txs,
+        });
+    }
+    Ok(blocks)
+}
+
+/// Inputs of `eth_getTransactionByHash`.
+pub struct TransactionLookup<P, F> {
+    provider: P,
+    state_syncs: SharedStateSyncStore,
+    /// reth's lookup, for every transaction that is not a state sync.
+    eth: F,
+}
+
+/// `eth_getTransactionByHash`, also answering for the derived hashes of state sync
+/// transactions as bor-geth does.
+pub fn bor_transaction_module<P, F, Fut, E>(
+    provider: P,
+    state_syncs: SharedStateSyncStore,
+    eth: F,
+) -> Result<RpcModule<TransactionLookup<P, F>>, RegisterMethodError>
+where
+    P: BlockBodyIndicesProvider + Send + Sync + 'static,
+    F: Fn(B256) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Option<serde_json::Value>, ErrorObjectOwned>> + Send,
+    E: RpcErrorCode + From<ProviderError> + From<serde_json::Error> + 'static,
+{
+    let mut module = RpcModule::new(TransactionLookup { provider, state_syncs, eth });
+    module.register_async_method("eth_getTransactionByHash", |rpc_params, ctx, _| async move {
+        let hash: B256 = rpc_params.one()?;
+        if let Some(tx) = (ctx.eth)(hash).await? {
+            return Ok(Some(tx));
+        }
+        let block = ctx
+            .state_syncs
+            .read()
+            .expect("state sync store lock poisoned")
+            .block_by_bor_tx_hash(&hash);
+        let Some(block) = block else { return Ok(None) };
+        let indices = ctx.provider.block_body_indices(block.number).map_err(rpc_error::<E>)?;
+        let Some(indices) = indices else { return Ok(None) };
+        let tx = state_sync_transaction(&block, indices.tx_count);
+        let Some(tx) = tx else { return Ok(None) };
+        serde_json::to_value(tx).map(Some).map_err(rpc_error::<E>)
+    })?;
+    Ok(module)
+}
+
+/// Inputs of `eth_getBlockByNumber` and `eth_getBlockByHash`.
+pub struct BlockLookup<P, F> {
+    provider: P,
+    total_difficulty: SharedTotalDifficultyIndex,
+    /// Milestones the finality of blocks is reported from.
+    tracker: Arc<MilestoneTracker>,
+    /// reth's lookup, whose blocks get their Bor fields set.
+    eth: F,
+}
+
+impl<P, F> BlockLookup<P, F>
+where
+    P: HeaderProvider<Header = alloy_consensus::Header> + BlockNumReader,
+{
+    /// Resolve the `finalized` and `safe` tags to the heights of the latest milestone and
+    /// checkpoint, as bor-geth does. Tags Heimdall has no height for yet are left to reth.
+    fn resolve_tag<E>(&self, tag: BlockNumberOrTag) -> Result<BlockNumberOrTag, ErrorObjectOwned>
+    where
+        E: RpcErrorCode + From<ProviderError>,
+    {
+        if !matches!(tag, BlockNumberOrTag::Finalized | BlockNumberOrTag::Safe) {
+            return Ok(tag);
+        }
+        let head = self.provider.best_block_number().map_err(rpc_error::<E>)?;
+        Ok(resolve_block_tag(tag, head, &self.tracker).map_or(tag, BlockNumberOrTag::Number))
+    }
+
+    /// Set the Bor fields of RPC `block`: its `totalDifficulty` and whether a milestone
+    /// finalized it.
+    fn with_bor_fields<E>(
+        &self,
+        block: serde_json::Value,
+    ) -> Result<serde_json::Value, ErrorObjectOwned>
+    where
+        E: RpcErrorCode + From<ProviderError> + From<BorRpcError> + From<serde_json::Error>,
+    {
+        let number = block["number"].as_str().and_then(|n| n.strip_prefix("0x"));
+        let Some(number) = number.and_then(|n| u64::from_str_radix(n, 16).ok()) else {
+            return Ok(block);
+        };
+        let block = self.with_total_difficulty::<E>(number, block)?;
+        serde_json::to_value(with_milestone_finality(&self.tracker, number, block))
+            .map_err(rpc_error::<E>)
+    }
+
+    /// Set the `totalDifficulty` of RPC `block` `number`, if the index has a checkpoint
+    /// within [`MAX_TD_SUMMED_HEADERS`](bor_storage::MAX_TD_SUMMED_HEADERS) blocks below it.
+    fn with_total_difficulty<E>(
+        &self,
+        number: u64,
+        mut block: serde_json::Value,
+    ) -> Result<serde_json::Value, ErrorObjectOwned>
+    where
+        E: RpcErrorCode + From<ProviderError> + From<BorRpcError>,
+    {
+        let index = self.total_difficulty.read().expect("total difficulty lock poisoned");
+        let total = index.total_difficulty(number, |range| {
+            let expected = range.end() - range.start() + 1;
+            let headers = self.provider.headers_range(range).map_err(rpc_error::<E>)?;
+            if headers.len() as u64 != expected {
+                return Err(rpc_error::<E>(BorRpcError::BlockNotFound(number)));
+            }
+            Ok(headers.iter().map(|header| header.difficulty).collect())
+        })?;
+        if let Some(total) = total {
+            block["totalDifficulty"] = serde_json::json!(total);
+        }
+        Ok(block)
+    }
+}
+
+/// `eth_getBlockByNumber` and `eth_getBlockByHash` reporting the total difficulty of
+/// the block, the sum of the difficulties up to it, as bor-geth does, and whether the
+/// latest milestone covers it.
+pub fn bor_block_module<P, F, Fut, E>(
+    provider: P,
+    total_difficulty: SharedTotalDifficultyIndex,
+    tracker: Arc<MilestoneTracker>,
+    eth: F,
+) -> Result<RpcModule<BlockLookup<P, F>>, RegisterMethodError>
+where
+    P: HeaderProvider<Header = alloy_consensus::Header> + BlockNumReader + Send + Sync + 'static,
+    F: Fn(BlockId, bool) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Option<serde_json::Value>, ErrorObjectOwned>> + Send,
+    E: RpcErrorCode
+        + From<ProviderError>
+        + From<BorRpcError>
+        + From<serde_json::Error>
+        + 'static,
+{
+    let mut module = RpcModule::new(BlockLookup { provider, total_difficulty, tracker, eth });
+    module.register_async_method("eth_getBlockByNumber", |rpc_params, ctx, _| async move {
+        let mut seq = rpc_params.sequence();
+        let number = ctx.resolve_tag::<E>(seq.next()?)?;
+        let full: bool = seq.next()?;
+        let block = (ctx.eth)(BlockId::Number(number), full).await?;
+        block.map(|block| ctx.with_bor_fields::<E>(block)).transpose()
+    })?;
+    module.register_async_method("eth_getBlockByHash", |rpc_params, ctx, _| async move {
+        let mut seq = rpc_params.sequence();
+        let hash: B256 = seq.next()?;
+        let full: bool = seq.next()?;
+        let block = (ctx.eth)(BlockId::from(hash), full).await?;
+        block.map(|block| ctx.with_bor_fields::<E>(block)).transpose()
+    })?;
+    Ok(module)
+}
+
+/// Inputs of `eth_call`.
+pub struct PendingCall<O, F> {
+    /// The state the pending block's state syncs change, as state overrides.
+    pending: O,
+    /// reth's `eth_call`, run with the pending state syncs as state overrides.
+    call: F,
+}
+
+/// `eth_call` seeing on the pending block the state sync events its sprint start will
+/// commit, as bor-geth does.
+pub fn bor_call_module<O, F, Fut, E>(
+    pending: O,
+    call: F,
+) -> Result<RpcModule<PendingCall<O, F>>, RegisterMethodError>
+where
+    O: Fn() -> Result<Option<StateOverride>, E> + Send + Sync + 'static,
+    F: Fn(
+            serde_json::Value,
+            Option<BlockId>,
+            Option<StateOverride>,
+            Option<Box<BlockOverrides>>,
+        ) -> Fut
+        + Send
+        + Sync
+        + 'static,
+    Fut: Future<Output = Result<Bytes, ErrorObjectOwned>> + Send,
+    E: RpcErrorCode + 'static,
+{
+    let mut module = RpcModule::new(PendingCall { pending, call });
+    module.register_async_method("eth_call", |rpc_params, ctx, _| async move {
+        let mut seq = rpc_params.sequence();
+        let request: serde_json::Value = seq.next()?;
+        let block: Option<BlockId> = seq.optional_next()?;
+        let mut overrides: Option<StateOverride> = seq.optional_next()?;
+        let block_overrides: Option<Box<BlockOverrides>> = seq.optional_next()?;
+        if block == Some(BlockId::pending()) {
+            if let Some(pending) = (ctx.pending)().map_err(rpc_error::<E>)? {
+                let overrides = overrides.get_or_insert_default();
+                for (address, account) in pending {
+                    // The caller's own overrides win.
+                    overrides.entry(address).or_insert(account);
+                }
+            }
+        }
+        (ctx.call)(request, block, overrides, block_overrides).await
+    })?;
+    Ok(module)
+}
+
+/// `eth_feeHistory` with the base fee change denominators of the chain's Bor fork schedule.
+pub fn bor_fee_module<P, E>(provider: P) -> Result<RpcModule<P>, RegisterMethodError>
+where
+    P: BlockReader<
+            Block = reth_ethereum_primitives::Block,
+            Receipt = reth_ethereum_primitives::Receipt,
+        > + ChainSpecProvider<ChainSpec: BorHardforks>
+        + Clone
+        + 'static,
+    E: RpcErrorCode + From<ProviderError> + From<BorRpcError> + 'static,
+{
+    let mut module = RpcModule::new(provider);
+    module.register_blocking_method("eth_feeHistory", |rpc_params, provider, _| {
+        let mut seq = rpc_params.sequence();
+        let block_count: U64 = seq.next()?;
+        let newest: BlockNumberOrTag = seq.next()?;
+        let percentiles: Option<Vec<f64>> = seq.optional_next()?;
+
+        let head = provider.best_block_number().map_err(rpc_error::<E>)?;
+        let newest = match newest {
+            BlockNumberOrTag::Number(number) => number,
+            BlockNumberOrTag::Earliest => 0,
+            _ => head,
+        };
+        if newest > head {
+            return Err(rpc_error::<E>(BorRpcError::BlockNotFound(newest)));
+        }
+
+        let count = block_count.to::<u64>().min(MAX_FEE_HISTORY_BLOCKS).min(newest + 1);
+        let blocks = if count == 0 {
+            Vec::new()
+        } else {
+            fee_history_blocks::<_, E>(&*provider, newest + 1 - count, newest)
+                .map_err(rpc_error::<E>)?
+        };
+        fee_history(&*provider.chain_spec(), &blocks, percentiles.as_deref())
+            .map_err(rpc_error::<E>)
+    })?;
+    module.register_blocking_method("eth_maxPriorityFeePerGas", |_, provider, _| {
+        let config = PriorityFeeConfig::default();
+        let head = provider.best_block_number().map_err(rpc_error::<E>)?;
+        let oldest = head.saturating_sub(config.blocks.saturating_sub(1));
+        let blocks =
+            fee_history_blocks::<_, E>(&*provider, oldest, head).map_err(rpc_error::<E>)?;
+        Ok::<_, ErrorObjectOwned>(U256::from(suggest_priority_fee(&config, &blocks)))
+    })?;
+    Ok(module)
+}
@@ -0,0 +1,190 @@
+//! Checkpoint root hashes (`bor_getRootHash`).
+//!
+//! A Heimdall checkpoint commits to a block range by the Merkle root of one
+//! leaf per block, [`checkpoint_leaf`], built by [`compute_root_hash`]; both live
+//! in `heimdall-client`, which clients without a node can use. [`RootHashBuilder`]
+//! takes headers one at a time, so a caller can stream a range without holding
+//! more than its leaves.
+//!
+//! Proposers' roots are recomputed by every validator, so [`RootHashCache`]
+//! keeps the last few answers like Bor's `rootHashCache` does.
+
+use crate::methods::{compute_root_hash, BorRpcError};
+use alloy_primitives::B256;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+pub use heimdall_client::checkpoint_leaf;
+
+/// Most blocks a checkpoint may span.
+pub const MAX_CHECKPOINT_LENGTH: u64 = 1 << 15;
+
+/// Headers fetched at a time while building a root hash.
+pub const ROOT_HASH_HEADER_BATCH: u64 = 1024;
+
+/// Root hashes kept by [`RootHashCache`] by default.
+pub const DEFAULT_ROOT_HASH_CACHE_SIZE: usize = 10;
+
+/// Check that `start..=end` may be checkpointed with the chain at `head`.
+pub fn validate_checkpoint_range(start: u64, end: u64, head: u64) -> Result<(), BorRpcError> {
+    if start > end {
+        return Err(BorRpcError::InvalidBlockRange { start, end });
+    }
+    if end - start + 1 > MAX_CHECKPOINT_LENGTH {
+        return Err(BorRpcError::InvalidParams(format!(
+            "checkpoint of {} blocks exceeds {MAX_CHECKPOINT_LENGTH}",
+            end - start + 1
+        )));
+    }
+    if end > head {
+        return Err(BorRpcError::BlockNotFound(end));
+    }
+    Ok(())
+}
+
+/// Builds the root hash of a range from its headers, in block order.
+#[derive(Debug)]
+pub struct RootHashBuilder {
+    next: u64,
+    leaves: Vec<B256>,
+}
+
+impl RootHashBuilder {
+    /// A builder for the range starting at block `start`.
+    pub fn new(start: u64) -> Self {
+        Self { next: start, leaves: Vec::new() }
+    }
+
+    /// The block number the builder expects next.
+    pub fn next_block(&self) -> u64 {
+        self.next
+    }
+
+    /// Add the header of the next block.
+    ///
+    /// Headers out of order would give a different root, so they are rejected.
+    pub fn push(
+        &mut self,
+        number: u64,
+        timestamp: u64,
+        transactions_root: B256,
+        receipts_root: B256,
+    ) -> Result<(), BorRpcError> {
+        if number != self.next {
+            return Err(BorRpcError::InvalidParams(format!(
+                "expected header {}, got {number}",
+                self.next
+            )));
+        }
+        self.leaves.push(checkpoint_leaf(number, timestamp, transactions_root, receipts_root));
+        self.next += 1;
+        Ok(())
+    }
+
+    /// The root hash of the headers added so far.
+    pub fn root(&self) -> B256 {
+        compute_root_hash(&self.leaves)
+    }
+}
+
+/// Root hashes of recently requested ranges.
+#[derive(Debug)]
+pub struct RootHashCache {
+    capacity: usize,
+    entries: Mutex<VecDeque<((u64, u64), B256)>>,
+}
+
+impl Default for RootHashCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROOT_HASH_CACHE_SIZE)
+    }
+}
+
+impl RootHashCache {
+    /// A cache of the last `capacity` root hashes.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    /// The cached root hash of `start..=end`.
+    pub fn get(&self, start: u64, end: u64) -> Option<B256> {
+        let entries = self.entries.lock().expect("root hash cache lock poisoned");
+        entries.iter().find(|(range, _)| *range == (start, end)).map(|(_, root)| *root)
+    }
+
+    /// Cache the root hash of `start..=end`, evicting the oldest entry when full.
+    pub fn insert(&self, start: u64, end: u64, root: B256) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("root hash cache lock poisoned");
+        entries.retain(|(range, _)| *range != (start, end));
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(((start, end), root));
+    }
+
+    /// The root hash of `start..=end`, computing it with `compute` if not cached.
+    pub fn get_or_compute<E>(
+        &self,
+        start: u64,
+        end: u64,
+        compute: impl FnOnce() -> Result<B256, E>,
+    ) -> Result<B256, E> {
+        if let Some(root) = self.get(start, end) {
+            return Ok(root);
+        }
+        let root = compute()?;
+        self.insert(start, end, root);
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_matches_leaves() {
+        let mut builder = RootHashBuilder::new(10);
+        let mut leaves = Vec::new();
+        for number in 10..15 {
+            builder.push(number, number * 2, B256::ZERO, B256::with_last_byte(1)).unwrap();
+            leaves.push(checkpoint_leaf(number, number * 2, B256::ZERO, B256::with_last_byte(1)));
+        }
+        assert_eq!(builder.next_block(), 15);
+        assert_eq!(builder.root(), compute_root_hash(&leaves));
+        assert!(builder.push(20, 0, B256::ZERO, B256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_range_validation() {
+        assert!(validate_checkpoint_range(1, 1, 1).is_ok());
+        assert!(matches!(
+            validate_checkpoint_range(2, 1, 10),
+            Err(BorRpcError::InvalidBlockRange { start: 2, end: 1 })
+        ));
+        assert!(validate_checkpoint_range(0, MAX_CHECKPOINT_LENGTH - 1, u64::MAX).is_ok());
+        assert!(validate_checkpoint_range(0, MAX_CHECKPOINT_LENGTH, u64::MAX).is_err());
+        assert!(matches!(
+            validate_checkpoint_range(1, 11, 10),
+            Err(BorRpcError::BlockNotFound(11))
+        ));
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let cache = RootHashCache::new(2);
+        cache.insert(0, 1, B256::with_last_byte(1));
+        cache.insert(2, 3, B256::with_last_byte(2));
+        cache.insert(4, 5, B256::with_last_byte(3));
+        assert_eq!(cache.get(0, 1), None);
+        assert_eq!(cache.get(2, 3), Some(B256::with_last_byte(2)));
+
+        let computed = cache.get_or_compute(4, 5, || Err::<B256, ()>(()));
+        assert_eq!(computed, Ok(B256::with_last_byte(3)));
+        assert_eq!(cache.get_or_compute(6, 7, || Ok::<_, ()>(B256::ZERO)), Ok(B256::ZERO));
+        assert_eq!(cache.get(2, 3), None);
+    }
+}
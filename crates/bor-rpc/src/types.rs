@@ -1,6 +1,6 @@
 //! RPC response types for the `bor_*` namespace.
 
-use alloy_primitives::{Address, B256, U256};
+use alloy_primitives::{Address, Bytes, B256, U256, U64};
 use serde::{Deserialize, Serialize};
 
 /// Response type for `bor_getSnapshot` and `bor_getSnapshotAtHash`.
@@ -16,7 +16,7 @@ pub struct BorSnapshotResponse {
 }
 
 /// Validator information returned in RPC responses.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidatorInfo {
     /// The validator's address.
@@ -27,6 +27,30 @@ pub struct ValidatorInfo {
     pub proposer_priority: i64,
 }
 
+/// A change of the validator set, as `bor_getValidatorsHistory` returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorSetChange {
+    /// First block validated by the new set.
+    pub block_number: U64,
+    /// The new set.
+    pub validators: Vec<ValidatorInfo>,
+    /// Validators that joined.
+    pub added: Vec<Address>,
+    /// Validators that left.
+    pub removed: Vec<Address>,
+}
+
+/// Response type for `bor_getValidatorsHistory`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorsHistoryResponse {
+    /// Changes of the validator set in the page, in block order.
+    pub changes: Vec<ValidatorSetChange>,
+    /// Token fetching the next page of the range, if the page did not reach its end.
+    pub next_page_token: Option<String>,
+}
+
 /// Response type for `bor_getCurrentValidators`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -54,3 +78,213 @@ pub struct BorReceiptResponse {
     /// Status (1 = success, 0 = failure).
     pub status: u64,
 }
+
+/// Response type for `bor_getLatestMilestone` and `bor_getMilestoneByID`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MilestoneResponse {
+    /// The Heimdall milestone identifier.
+    pub milestone_id: String,
+    /// First block covered by the milestone.
+    pub start_block: u64,
+    /// Last block covered by the milestone.
+    pub end_block: u64,
+    /// Hash of the end block.
+    pub hash: B256,
+    /// The validator that proposed the milestone.
+    pub proposer: Address,
+}
+
+impl From<heimdall_client::Milestone> for MilestoneResponse {
+    fn from(milestone: heimdall_client::Milestone) -> Self {
+        Self {
+            milestone_id: milestone.milestone_id,
+            start_block: milestone.start_block,
+            end_block: milestone.end_block,
+            hash: milestone.hash,
+            proposer: milestone.proposer,
+        }
+    }
+}
+
+/// Wraps a block response with its milestone finality flag.
+///
+/// Serializes as the inner object with an extra `milestoneFinalized` field, so it can be
+/// layered on top of the standard `eth_getBlockByNumber` payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithMilestoneFinality<T> {
+    /// The wrapped block response.
+    #[serde(flatten)]
+    pub inner: T,
+    /// Whether the block is covered by the latest Heimdall milestone.
+    pub milestone_finalized: bool,
+}
+
+/// Response type for `eth_feeHistory`.
+///
+/// Quantities serialize as hex, matching bor-geth. Blob fee fields are omitted since Bor has
+/// no blob transactions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistoryResponse {
+    /// First block of the returned range.
+    pub oldest_block: U64,
+    /// Base fee of each block in the range, followed by the base fee of the next block.
+    pub base_fee_per_gas: Vec<U256>,
+    /// Gas used divided by gas limit, per block.
+    pub gas_used_ratio: Vec<f64>,
+    /// Effective tips at the requested percentiles, per block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<U256>>>,
+}
+
+/// Response type for `bor_simulateProposal`.
+///
+/// Describes the block this node would produce at a height, without sealing or
+/// broadcasting it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedProposalResponse {
+    /// Number of the simulated block.
+    pub number: U64,
+    /// Hash of the head the block was simulated on; its parent if it directly follows the head.
+    pub parent_hash: B256,
+    /// Earliest timestamp the block may carry.
+    pub timestamp: U64,
+    /// Difficulty of the block, from the signer's position behind the proposer.
+    pub difficulty: U256,
+    /// Block gas limit.
+    pub gas_limit: U64,
+    /// Gas the block used when executed on the head's state.
+    pub gas_used: U64,
+    /// State root after the block.
+    pub state_root: B256,
+    /// Header extra data, with the seal left zeroed.
+    pub extra_data: Bytes,
+    /// Address the block would be signed with.
+    pub signer: Address,
+    /// Distance of the signer behind the sprint proposer (0 = in-turn).
+    pub succession: U64,
+    /// Number of user transactions from the pool the block included.
+    pub transaction_count: U64,
+    /// System calls executed at the end of the block, in order.
+    pub system_calls: Vec<SimulatedSystemCall>,
+}
+
+/// A system call of a simulated block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedSystemCall {
+    /// Contract called.
+    pub to: Address,
+    /// ABI-encoded call data.
+    pub data: Bytes,
+}
+
+/// Response type for `bor_getProducerPerformance`: who produced the blocks of a range,
+/// against who was expected to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProducerPerformanceResponse {
+    /// First block of the range with a record.
+    pub start_block: u64,
+    /// Last block of the range with a record.
+    pub end_block: u64,
+    /// Blocks recorded in the range.
+    pub blocks: u64,
+    /// Blocks signed by someone other than the sprint proposer.
+    pub out_of_turn_blocks: u64,
+    /// Blocks whose sprint proposer was not known when they were recorded.
+    pub unknown_proposer_blocks: u64,
+    /// Blocks signed by this node.
+    pub own_blocks: u64,
+    /// Blocks this node was the sprint proposer for but someone else signed.
+    pub own_missed_slots: u64,
+    /// Average seconds between a block and its parent.
+    pub average_block_time: Option<f64>,
+    /// Blocks per signer, most productive first.
+    pub producers: Vec<ProducerStats>,
+}
+
+/// Blocks of one signer in a `bor_getProducerPerformance` range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProducerStats {
+    /// Signer address.
+    pub address: Address,
+    /// Blocks signed.
+    pub blocks: u64,
+    /// Blocks signed as the sprint proposer.
+    pub in_turn_blocks: u64,
+    /// Blocks the signer was the sprint proposer for but someone else signed.
+    pub missed_slots: u64,
+}
+
+/// The state sync transaction of a block, as `eth_getTransactionByHash` returns it.
+///
+/// Bor has no such transaction on chain. Like bor-geth, the node reports one under the
+/// block's derived hash, signed by nobody, sent from the zero address to the
+/// StateReceiver and placed after the block's transactions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSyncTransactionResponse {
+    /// Derived hash of the transaction.
+    pub hash: B256,
+    /// Hash of the block.
+    pub block_hash: B256,
+    /// Number of the block.
+    pub block_number: U64,
+    /// Index after the block's transactions.
+    pub transaction_index: U64,
+    /// Always the zero address.
+    pub from: Address,
+    /// The StateReceiver contract.
+    pub to: Address,
+    /// Record bytes of the events committed, concatenated in order.
+    pub input: Bytes,
+    /// Always zero.
+    pub nonce: U64,
+    /// Always zero.
+    pub gas: U64,
+    /// Always zero.
+    pub gas_price: U256,
+    /// Always zero.
+    pub value: U256,
+    /// Legacy transaction type.
+    #[serde(rename = "type")]
+    pub tx_type: U64,
+    /// Empty signature.
+    pub v: U64,
+    /// Empty signature.
+    pub r: U256,
+    /// Empty signature.
+    pub s: U256,
+}
+
+/// A state sync event, as `bor_getStateSyncEventsByContract` returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSyncEventResponse {
+    /// State sync ID.
+    pub id: U64,
+    /// L1 contract that emitted the event.
+    pub contract: Address,
+    /// Record bytes passed to the StateReceiver.
+    pub data: Bytes,
+    /// Canonical block that committed the event.
+    pub block_number: U64,
+}
+
+/// A state sync event a block committed, as `bor_getStateSyncEventsByBlock` returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateSyncEventResponse {
+    /// State sync ID.
+    pub id: U64,
+    /// L1 contract that emitted the event, if the executor was told.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<Address>,
+    /// Record bytes passed to the StateReceiver.
+    pub data: Bytes,
+}
@@ -0,0 +1,86 @@
+// eth_feeHistory over a range of blocks straddling the Delhi fork (38,189,056), where the
+// base fee change denominator goes from 8 to 16. The expected base fees are worked out
+// from the EIP-1559 rule with the denominator in force at each parent.
+
+use bor_chainspec::MainnetBorHardforks;
+use bor_rpc::{fee_history, FeeHistoryBlock};
+use serde_json::{json, Value};
+
+const GWEI: u128 = 1_000_000_000;
+
+/// Blocks 38,189,054..=38,189,057: three full blocks followed by an empty one.
+fn delhi_range() -> Vec<FeeHistoryBlock> {
+    let full = |number, base_fee_per_gas, txs| FeeHistoryBlock {
+        number,
+        base_fee_per_gas,
+        gas_used: 30_000_000,
+        gas_limit: 30_000_000,
+        txs,
+    };
+    vec![
+        full(
+            38_189_054,
+            100_000_000_000,
+            vec![(10_000_000, 30 * GWEI), (15_000_000, GWEI), (5_000_000, 50 * GWEI)],
+        ),
+        full(38_189_055, 112_500_000_000, vec![(30_000_000, 2 * GWEI)]),
+        full(38_189_056, 126_562_500_000, vec![(30_000_000, 2 * GWEI)]),
+        FeeHistoryBlock {
+            number: 38_189_057,
+            base_fee_per_gas: 134_472_656_250,
+            gas_used: 0,
+            gas_limit: 30_000_000,
+            txs: Vec::new(),
+        },
+    ]
+}
+
+/// Base fee after a parent with `base_fee` that was `full`, or else empty.
+fn next_base_fee(base_fee: u128, full: bool, denominator: u128) -> u128 {
+    if full { base_fee + base_fee / denominator } else { base_fee - base_fee / denominator }
+}
+
+#[test]
+fn fee_history_switches_denominator_at_delhi() {
+    let response =
+        fee_history(&MainnetBorHardforks, &delhi_range(), Some(&[10.0, 50.0, 90.0])).unwrap();
+    let ours = serde_json::to_value(&response).unwrap();
+
+    assert_eq!(ours["oldestBlock"], json!(format!("{:#x}", 38_189_054)));
+    // The children of 38,189,054 and 38,189,055 change by 1/8, those of Delhi blocks by 1/16.
+    let after_delhi = next_base_fee(126_562_500_000, true, 16);
+    assert_eq!(after_delhi, 134_472_656_250);
+    let base_fees = [
+        100_000_000_000,
+        next_base_fee(100_000_000_000, true, 8),
+        next_base_fee(112_500_000_000, true, 8),
+        after_delhi,
+        next_base_fee(after_delhi, false, 16),
+    ];
+    let base_fees: Vec<_> = base_fees.iter().map(|fee| format!("{fee:#x}")).collect();
+    assert_eq!(ours["baseFeePerGas"], json!(base_fees));
+
+    // Rewards are picked by cumulative gas over the transactions sorted by tip.
+    let gwei = |n: u128| format!("{:#x}", n * GWEI);
+    assert_eq!(
+        ours["reward"],
+        json!([
+            [gwei(1), gwei(1), gwei(50)],
+            [gwei(2), gwei(2), gwei(2)],
+            [gwei(2), gwei(2), gwei(2)],
+            ["0x0", "0x0", "0x0"]
+        ])
+    );
+
+    let ratios: Vec<f64> =
+        ours["gasUsedRatio"].as_array().unwrap().iter().map(|r| r.as_f64().unwrap()).collect();
+    assert_eq!(ratios, vec![1.0, 1.0, 1.0, 0.0]);
+}
+
+#[test]
+fn fee_history_without_percentiles_omits_reward() {
+    let response = fee_history(&MainnetBorHardforks, &delhi_range()[..1], None).unwrap();
+    let ours = serde_json::to_value(&response).unwrap();
+    assert!(ours.get("reward").is_none());
+    assert_eq!(ours["baseFeePerGas"], json!(["0x174876e800", "0x1a3185c500"]));
+}
@@ -0,0 +1,247 @@
+//! Blocks rejected by validation or execution, kept with their Bor context.
+//!
+//! Each record holds what is needed to reproduce a rejection after the fact:
+//! the failing check, the validator set and recent signers the block was
+//! judged against, its span and the state sync events applied to it.
+//!
+//! [`FileBadBlockStore`] keeps the records in a file of the datadir, so a rejection
+//! can still be inspected after the node is restarted.
+
+use alloy_primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Default number of bad blocks retained by [`InMemoryBadBlockStore`].
+pub const MAX_BAD_BLOCKS: usize = 128;
+
+/// File in the datadir holding the records of [`FileBadBlockStore`].
+pub const BAD_BLOCKS_FILE: &str = "bor-bad-blocks.json";
+
+/// The consensus view a bad block was checked against.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSummary {
+    /// Signers of the validator set.
+    pub validators: Vec<Address>,
+    /// The proposer of the validator set, if known.
+    pub proposer: Option<Address>,
+    /// Recent `(block, signer)` pairs used for the double-sign check.
+    pub recents: Vec<(u64, Address)>,
+}
+
+/// A block that failed validation or execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadBlockRecord {
+    /// Block number.
+    pub number: u64,
+    /// Block hash.
+    pub hash: B256,
+    /// Parent block hash.
+    pub parent_hash: B256,
+    /// Signer recovered from the seal, if recovery got that far.
+    pub signer: Option<Address>,
+    /// Short name of the failing check, e.g. `unauthorized_signer` or `gas_used`.
+    pub check: String,
+    /// The error message.
+    pub error: String,
+    /// Consensus view at the block, if one was available.
+    pub snapshot: Option<SnapshotSummary>,
+    /// Span covering the block.
+    pub span_id: Option<u64>,
+    /// IDs of the state sync events applied by the block.
+    pub state_sync_ids: Vec<u64>,
+}
+
+impl BadBlockRecord {
+    /// Serialize for storage in the bad blocks table.
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("bad block record serializes")
+    }
+
+    /// Deserialize a record read from the bad blocks table.
+    pub fn decode(data: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+}
+
+/// Trait for persisting bad blocks.
+pub trait BadBlockStore: Send + Sync + std::fmt::Debug {
+    /// Store a bad block, replacing an earlier record for the same hash.
+    fn put_bad_block(&mut self, record: BadBlockRecord);
+    /// Retrieve a bad block by hash.
+    fn get_bad_block(&self, hash: &B256) -> Option<BadBlockRecord>;
+    /// All stored bad blocks, most recent first.
+    fn bad_blocks(&self) -> Vec<BadBlockRecord>;
+}
+
+/// A bad block store shared between the components that reject blocks and the RPC layer.
+pub type SharedBadBlockStore = Arc<RwLock<dyn BadBlockStore>>;
+
+/// In-memory [`BadBlockStore`] keeping the most recent `capacity` records.
+#[derive(Debug)]
+pub struct InMemoryBadBlockStore {
+    records: VecDeque<BadBlockRecord>,
+    capacity: usize,
+}
+
+impl InMemoryBadBlockStore {
+    /// Create a store holding at most `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self { records: VecDeque::with_capacity(capacity), capacity }
+    }
+}
+
+impl Default for InMemoryBadBlockStore {
+    fn default() -> Self {
+        Self::new(MAX_BAD_BLOCKS)
+    }
+}
+
+impl BadBlockStore for InMemoryBadBlockStore {
+    fn put_bad_block(&mut self, record: BadBlockRecord) {
+        self.records.retain(|r| r.hash != record.hash);
+        if self.capacity == 0 {
+            return;
+        }
+        while self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    fn get_bad_block(&self, hash: &B256) -> Option<BadBlockRecord> {
+        self.records.iter().find(|r| &r.hash == hash).cloned()
+    }
+
+    fn bad_blocks(&self) -> Vec<BadBlockRecord> {
+        self.records.iter().rev().cloned().collect()
+    }
+}
+
+/// [`BadBlockStore`] persisted to a file, keeping the most recent `capacity` records.
+#[derive(Debug)]
+pub struct FileBadBlockStore {
+    records: InMemoryBadBlockStore,
+    path: PathBuf,
+}
+
+impl FileBadBlockStore {
+    /// Create a store persisted at `path`, loading the records left by a previous run.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> std::io::Result<Self> {
+        let path = path.into();
+        let saved: Vec<BadBlockRecord> = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut records = InMemoryBadBlockStore::new(capacity);
+        // Saved newest first; replay oldest first so the newest survive the capacity.
+        for record in saved.into_iter().rev() {
+            records.put_bad_block(record);
+        }
+        Ok(Self { records, path })
+    }
+}
+
+impl BadBlockStore for FileBadBlockStore {
+    /// A failed write is logged, not returned: the record stays in memory, and the
+    /// rejection it describes must not fail because of it.
+    fn put_bad_block(&mut self, record: BadBlockRecord) {
+        self.records.put_bad_block(record);
+        if let Err(err) = write_atomically(&self.path, &self.records.bad_blocks()) {
+            let path = self.path.display();
+            warn!(target: "bor::bad_blocks", %path, %err, "failed to persist bad block");
+        }
+    }
+
+    fn get_bad_block(&self, hash: &B256) -> Option<BadBlockRecord> {
+        self.records.get_bad_block(hash)
+    }
+
+    fn bad_blocks(&self) -> Vec<BadBlockRecord> {
+        self.records.bad_blocks()
+    }
+}
+
+/// Write `records` to `path` through a temporary file renamed over it.
+fn write_atomically(path: &Path, records: &[BadBlockRecord]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(records)?)?;
+    std::fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(number: u64) -> BadBlockRecord {
+        BadBlockRecord {
+            number,
+            hash: B256::with_last_byte(number as u8),
+            parent_hash: B256::with_last_byte(number as u8 - 1),
+            signer: Some(Address::new([0xaa; 20])),
+            check: "unauthorized_signer".to_string(),
+            error: format!("unauthorized signer at block {number}"),
+            snapshot: Some(SnapshotSummary {
+                validators: vec![Address::new([0xbb; 20])],
+                proposer: Some(Address::new([0xbb; 20])),
+                recents: vec![(number - 1, Address::new([0xbb; 20]))],
+            }),
+            span_id: Some(1),
+            state_sync_ids: vec![7, 8],
+        }
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let record = record(5);
+        assert_eq!(BadBlockRecord::decode(&record.encode()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_store_newest_first_and_bounded() {
+        let mut store = InMemoryBadBlockStore::new(2);
+        store.put_bad_block(record(1));
+        store.put_bad_block(record(2));
+        store.put_bad_block(record(3));
+
+        let numbers: Vec<u64> = store.bad_blocks().iter().map(|r| r.number).collect();
+        assert_eq!(numbers, vec![3, 2]);
+        assert!(store.get_bad_block(&record(1).hash).is_none());
+        assert_eq!(store.get_bad_block(&record(2).hash).unwrap().state_sync_ids, vec![7, 8]);
+    }
+
+    #[test]
+    fn test_store_replaces_same_hash() {
+        let mut store = InMemoryBadBlockStore::default();
+        store.put_bad_block(record(1));
+        let mut again = record(1);
+        again.check = "gas_used".to_string();
+        store.put_bad_block(again);
+
+        assert_eq!(store.bad_blocks().len(), 1);
+        assert_eq!(store.bad_blocks()[0].check, "gas_used");
+    }
+
+    #[test]
+    fn test_file_store_reloads_records() {
+        let path =
+            std::env::temp_dir().join(format!("bor-bad-blocks-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = FileBadBlockStore::open(&path, 2).unwrap();
+        assert!(store.bad_blocks().is_empty());
+        store.put_bad_block(record(1));
+        store.put_bad_block(record(2));
+        store.put_bad_block(record(3));
+
+        let reopened = FileBadBlockStore::open(&path, 2).unwrap();
+        let numbers: Vec<u64> = reopened.bad_blocks().iter().map(|r| r.number).collect();
+        assert_eq!(numbers, vec![3, 2]);
+        assert_eq!(reopened.get_bad_block(&record(3).hash), Some(record(3)));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,9 +2,36 @@
 
 pub mod tables;
 
-pub mod receipt_key;
+pub use bor_primitives::receipt_key;
 pub mod receipt;
 pub mod gas;
 pub mod persistence;
+pub mod bad_blocks;
+pub mod parity;
+pub mod sprint_wal;
+pub mod state_syncs;
+pub mod total_difficulty;
 
-pub use receipt::{BorReceiptStorage, compute_receipt_root, store_block_receipts, is_post_madhugiri};
+pub use bad_blocks::{
+    BadBlockRecord, BadBlockStore, FileBadBlockStore, InMemoryBadBlockStore, SharedBadBlockStore,
+    SnapshotSummary, BAD_BLOCKS_FILE, MAX_BAD_BLOCKS,
+};
+pub use persistence::{
+    FileSnapshotStore, SharedSnapshotStore, MAX_SNAPSHOTS, SNAPSHOTS_FILE,
+};
+pub use parity::{verify_receipt_parity, BlockReceipts, ParityMismatch, ParityReceipt};
+pub use state_syncs::{
+    CommittedStateSync, FileStateSyncStore, InMemoryStateSyncStore, SharedStateSyncStore,
+    StateSyncBlock, StateSyncStore, MAX_STATE_SYNC_BLOCKS, STATE_SYNCS_FILE,
+};
+pub use total_difficulty::{
+    SharedTotalDifficultyIndex, TotalDifficultyIndex, DEFAULT_TD_CHECKPOINT_INTERVAL,
+    MAX_TD_SUMMED_HEADERS, TD_INDEX_FILE, TD_INDEX_VERSION,
+};
+pub use sprint_wal::{
+    SharedSprintWal, SprintMarker, SprintOutcome, SprintStage, SprintWal,
+};
+pub use receipt::{
+    BorReceiptStorage, bor_receipt_storage, compute_receipt_root, store_block_receipts,
+    is_post_madhugiri,
+};
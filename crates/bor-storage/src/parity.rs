@@ -0,0 +1,266 @@
+//! Receipt root, logs bloom and log index parity with Bor.
+//!
+//! Bor serves the state sync receipt of a block alongside its regular
+//! receipts, but before Madhugiri that receipt is not part of the header: the
+//! receipts root and logs bloom cover regular transactions only. Its logs are
+//! still indexed, continuing the block's log index after the last regular log.
+//! [`verify_receipt_parity`] recomputes the header commitments from served
+//! receipts and checks the served log indices against those rules.
+
+use alloy_consensus::{proofs::ordered_trie_root_with_encoder, Eip658Value, Receipt, ReceiptWithBloom};
+use alloy_primitives::{logs_bloom, Bloom, Log, B256};
+use alloy_rlp::Encodable;
+use std::fmt;
+
+/// A receipt as needed to recompute header commitments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParityReceipt {
+    /// EIP-2718 transaction type.
+    pub tx_type: u8,
+    /// Whether the transaction succeeded.
+    pub success: bool,
+    /// Gas used in the block up to and including this transaction.
+    pub cumulative_gas_used: u64,
+    /// Logs emitted by the transaction.
+    pub logs: Vec<Log>,
+}
+
+impl ParityReceipt {
+    /// Bloom filter over the receipt's logs.
+    pub fn bloom(&self) -> Bloom {
+        logs_bloom(&self.logs)
+    }
+
+    /// Append the EIP-2718 encoding used as the receipt trie value.
+    pub fn encode_2718(&self, out: &mut dyn alloy_rlp::BufMut) {
+        if self.tx_type != 0 {
+            out.put_u8(self.tx_type);
+        }
+        let receipt = Receipt {
+            status: Eip658Value::Eip658(self.success),
+            cumulative_gas_used: self.cumulative_gas_used,
+            logs: self.logs.clone(),
+        };
+        ReceiptWithBloom::new(receipt, self.bloom()).encode(out);
+    }
+}
+
+/// The receipts served for one block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockReceipts {
+    /// Receipts of the block's regular transactions, in order.
+    pub receipts: Vec<ParityReceipt>,
+    /// The state sync receipt, if the block applied state sync events.
+    pub bor_receipt: Option<ParityReceipt>,
+    /// Whether the state sync receipt is part of the header (post-Madhugiri).
+    pub unified: bool,
+}
+
+impl BlockReceipts {
+    /// The receipts committed to by the header.
+    fn committed(&self) -> impl Iterator<Item = &ParityReceipt> {
+        self.receipts.iter().chain(self.bor_receipt.iter().filter(|_| self.unified))
+    }
+
+    /// The receipts root the header must carry.
+    pub fn receipts_root(&self) -> B256 {
+        let committed: Vec<&ParityReceipt> = self.committed().collect();
+        ordered_trie_root_with_encoder(&committed, |receipt, buf| receipt.encode_2718(buf))
+    }
+
+    /// The logs bloom the header must carry.
+    pub fn logs_bloom(&self) -> Bloom {
+        logs_bloom(self.committed().flat_map(|r| &r.logs))
+    }
+
+    /// Block-wide log indices Bor assigns, per receipt, with the state sync receipt last.
+    ///
+    /// The state sync receipt's logs continue after the regular logs whether or not the
+    /// receipt is committed to by the header.
+    pub fn log_indices(&self) -> Vec<Vec<u64>> {
+        let mut next = 0u64;
+        self.receipts
+            .iter()
+            .chain(self.bor_receipt.iter())
+            .map(|receipt| {
+                let start = next;
+                next += receipt.logs.len() as u64;
+                (start..next).collect()
+            })
+            .collect()
+    }
+}
+
+/// A difference between served receipts and the header or Bor's log indexing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParityMismatch {
+    /// The header's receipts root differs from the recomputed one.
+    ReceiptsRoot {
+        /// Root in the header.
+        header: B256,
+        /// Root recomputed from the served receipts.
+        computed: B256,
+    },
+    /// The header's logs bloom differs from the recomputed one.
+    LogsBloom {
+        /// Bloom in the header.
+        header: Bloom,
+        /// Bloom recomputed from the served receipts.
+        computed: Bloom,
+    },
+    /// A served log index differs from the index Bor assigns.
+    LogIndex {
+        /// Receipt position, with the state sync receipt last.
+        receipt: usize,
+        /// Log position within the receipt.
+        log: usize,
+        /// The index Bor assigns.
+        expected: u64,
+        /// The served index, or `None` if the log is missing.
+        served: Option<u64>,
+    },
+}
+
+impl fmt::Display for ParityMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReceiptsRoot { header, computed } => {
+                write!(f, "receiptsRoot: header {header}, computed {computed}")
+            }
+            Self::LogsBloom { header, computed } => {
+                write!(f, "logsBloom: header {header}, computed {computed}")
+            }
+            Self::LogIndex { receipt, log, expected, served } => {
+                write!(f, "receipts[{receipt}].logs[{log}].logIndex: expected {expected}, served {served:?}")
+            }
+        }
+    }
+}
+
+/// Check served receipts against the header commitments and Bor's log indexing.
+///
+/// `served_log_indices` holds the `logIndex` values served for each receipt, in the same
+/// order as [`BlockReceipts::log_indices`].
+pub fn verify_receipt_parity(
+    block: &BlockReceipts,
+    header_receipts_root: B256,
+    header_logs_bloom: Bloom,
+    served_log_indices: &[Vec<u64>],
+) -> Vec<ParityMismatch> {
+    let mut mismatches = Vec::new();
+
+    let computed = block.receipts_root();
+    if computed != header_receipts_root {
+        mismatches.push(ParityMismatch::ReceiptsRoot { header: header_receipts_root, computed });
+    }
+    let computed = block.logs_bloom();
+    if computed != header_logs_bloom {
+        mismatches.push(ParityMismatch::LogsBloom { header: header_logs_bloom, computed });
+    }
+
+    for (receipt, expected) in block.log_indices().iter().enumerate() {
+        let served = served_log_indices.get(receipt);
+        for (log, &expected) in expected.iter().enumerate() {
+            let served = served.and_then(|s| s.get(log)).copied();
+            if served != Some(expected) {
+                mismatches.push(ParityMismatch::LogIndex { receipt, log, expected, served });
+            }
+        }
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, BloomInput, Bytes};
+
+    fn log(address: u8, topic: u8) -> Log {
+        Log::new_unchecked(
+            Address::new([address; 20]),
+            vec![B256::new([topic; 32])],
+            Bytes::from_static(&[0x01]),
+        )
+    }
+
+    fn receipt(tx_type: u8, cumulative_gas_used: u64, logs: Vec<Log>) -> ParityReceipt {
+        ParityReceipt { tx_type, success: true, cumulative_gas_used, logs }
+    }
+
+    /// Two regular receipts and a state sync receipt, as served for a sprint start.
+    fn sprint_start(unified: bool) -> BlockReceipts {
+        BlockReceipts {
+            receipts: vec![
+                receipt(0, 21_000, vec![log(0x10, 1), log(0x10, 2)]),
+                receipt(2, 60_000, vec![log(0x20, 3)]),
+            ],
+            bor_receipt: Some(receipt(0, 60_000, vec![log(0x11, 4), log(0x11, 5)])),
+            unified,
+        }
+    }
+
+    #[test]
+    fn test_pre_madhugiri_excludes_bor_receipt_from_header() {
+        let block = sprint_start(false);
+        let regular_only = BlockReceipts { bor_receipt: None, ..block.clone() };
+
+        assert_eq!(block.receipts_root(), regular_only.receipts_root());
+        assert_eq!(block.logs_bloom(), regular_only.logs_bloom());
+        assert!(!block.logs_bloom().contains_input(BloomInput::Raw(&[0x11; 20])));
+
+        let served = vec![vec![0, 1], vec![2], vec![3, 4]];
+        let mismatches =
+            verify_receipt_parity(&block, regular_only.receipts_root(), regular_only.logs_bloom(), &served);
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+    }
+
+    #[test]
+    fn test_post_madhugiri_includes_bor_receipt_in_header() {
+        let unified = sprint_start(true);
+        let legacy = sprint_start(false);
+        assert_ne!(unified.receipts_root(), legacy.receipts_root());
+        assert!(unified.logs_bloom().contains_input(BloomInput::Raw(&[0x11; 20])));
+        assert_eq!(unified.log_indices(), legacy.log_indices());
+
+        // A header built the pre-Madhugiri way no longer matches.
+        let mismatches = verify_receipt_parity(
+            &unified,
+            legacy.receipts_root(),
+            legacy.logs_bloom(),
+            &unified.log_indices(),
+        );
+        assert!(matches!(mismatches[0], ParityMismatch::ReceiptsRoot { .. }));
+        assert!(matches!(mismatches[1], ParityMismatch::LogsBloom { .. }));
+    }
+
+    #[test]
+    fn test_bor_log_indices_continue_after_regular_logs() {
+        let block = sprint_start(false);
+        assert_eq!(block.log_indices(), vec![vec![0, 1], vec![2], vec![3, 4]]);
+
+        // Restarting the state sync receipt's indices at 0 is a divergence.
+        let served = vec![vec![0, 1], vec![2], vec![0, 1]];
+        let mismatches =
+            verify_receipt_parity(&block, block.receipts_root(), block.logs_bloom(), &served);
+        assert_eq!(
+            mismatches,
+            vec![
+                ParityMismatch::LogIndex { receipt: 2, log: 0, expected: 3, served: Some(0) },
+                ParityMismatch::LogIndex { receipt: 2, log: 1, expected: 4, served: Some(1) },
+            ]
+        );
+
+        // A missing state sync receipt shows up as missing logs.
+        let mismatches =
+            verify_receipt_parity(&block, block.receipts_root(), block.logs_bloom(), &served[..2]);
+        assert_eq!(mismatches.len(), 2);
+        assert!(matches!(mismatches[0], ParityMismatch::LogIndex { served: None, .. }));
+    }
+
+    #[test]
+    fn test_empty_block_root() {
+        let block = BlockReceipts::default();
+        assert_eq!(block.receipts_root(), alloy_consensus::EMPTY_ROOT_HASH);
+        assert_eq!(block.logs_bloom(), Bloom::ZERO);
+    }
+}
@@ -1,7 +1,21 @@
 //! Span and snapshot DB persistence traits and in-memory implementations.
+//!
+//! [`FileSnapshotStore`] keeps the latest snapshots in a file of the datadir, so a
+//! restarted node resumes a snapshot rebuild from the last one it stored.
 
+use alloy_primitives::{Bytes, B256};
 use bor_primitives::Span;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// File in the datadir holding the snapshots of [`FileSnapshotStore`].
+pub const SNAPSHOTS_FILE: &str = "bor-snapshots.json";
+
+/// Default number of snapshots retained by [`FileSnapshotStore`].
+pub const MAX_SNAPSHOTS: usize = 16;
 
 /// Trait for persisting Bor spans.
 pub trait SpanStore: Send + Sync {
@@ -21,6 +35,9 @@ pub trait SnapshotStore: Send + Sync {
     fn put_snapshot(&mut self, block_hash: [u8; 32], data: Vec<u8>);
 }
 
+/// A [`SnapshotStore`] shared between the node and its RPC.
+pub type SharedSnapshotStore = Arc<RwLock<dyn SnapshotStore>>;
+
 /// In-memory [`SpanStore`] implementation for testing.
 #[derive(Debug, Default)]
 pub struct InMemorySpanStore {
@@ -71,6 +88,72 @@ impl SnapshotStore for InMemorySnapshotStore {
     }
 }
 
+/// A snapshot as saved by [`FileSnapshotStore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedSnapshot {
+    hash: B256,
+    data: Bytes,
+}
+
+/// [`SnapshotStore`] persisted to a file, keeping the most recent `capacity` snapshots.
+#[derive(Debug)]
+pub struct FileSnapshotStore {
+    snapshots: VecDeque<SavedSnapshot>,
+    capacity: usize,
+    path: PathBuf,
+}
+
+impl FileSnapshotStore {
+    /// Create a store persisted at `path`, loading the snapshots left by a previous run.
+    ///
+    /// A file that cannot be parsed is logged and ignored: its snapshots are rebuilt.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> std::io::Result<Self> {
+        let path = path.into();
+        let snapshots = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                let file = path.display();
+                warn!(target: "bor::snapshot", path = %file, %err, "ignoring corrupt snapshots");
+                VecDeque::new()
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { snapshots, capacity, path })
+    }
+}
+
+impl SnapshotStore for FileSnapshotStore {
+    fn get_snapshot(&self, block_hash: &[u8; 32]) -> Option<Vec<u8>> {
+        let hash = B256::from(*block_hash);
+        self.snapshots.iter().find(|saved| saved.hash == hash).map(|saved| saved.data.to_vec())
+    }
+
+    /// A failed write is logged, not returned: the snapshot stays in memory, and a
+    /// lost one is rebuilt on the next start.
+    fn put_snapshot(&mut self, block_hash: [u8; 32], data: Vec<u8>) {
+        let hash = B256::from(block_hash);
+        self.snapshots.retain(|saved| saved.hash != hash);
+        if self.capacity == 0 {
+            return;
+        }
+        while self.snapshots.len() >= self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(SavedSnapshot { hash, data: data.into() });
+        if let Err(err) = write_atomically(&self.path, &self.snapshots) {
+            let path = self.path.display();
+            warn!(target: "bor::snapshot", %path, %err, "failed to persist snapshot");
+        }
+    }
+}
+
+/// Write `snapshots` to `path` through a temporary file renamed over it.
+fn write_atomically(path: &Path, snapshots: &VecDeque<SavedSnapshot>) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(snapshots)?)?;
+    std::fs::rename(tmp, path)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,4 +230,24 @@ mod tests {
         let retrieved = store.get_snapshot(&hash).expect("snapshot should exist");
         assert_eq!(retrieved, vec![4, 5, 6]);
     }
+
+    #[test]
+    fn file_snapshot_store_reloads_latest() {
+        let path = std::env::temp_dir()
+            .join(format!("bor-snapshots-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = FileSnapshotStore::open(&path, 2).unwrap();
+        for byte in 1..=3u8 {
+            store.put_snapshot([byte; 32], vec![byte]);
+        }
+        let store = FileSnapshotStore::open(&path, 2).unwrap();
+        assert!(store.get_snapshot(&[1; 32]).is_none());
+        assert_eq!(store.get_snapshot(&[3; 32]), Some(vec![3]));
+
+        std::fs::write(&path, b"{not json").unwrap();
+        let store = FileSnapshotStore::open(&path, 2).unwrap();
+        assert!(store.get_snapshot(&[3; 32]).is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
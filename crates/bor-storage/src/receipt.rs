@@ -55,6 +55,18 @@ pub fn store_block_receipts(
     }
 }
 
+/// Where to store the Bor receipt of a block that committed `state_sync_count` events.
+///
+/// Returns `None` when no events were committed: Bor writes no receipt for such a block,
+/// so nothing may be stored under its key and no derived transaction exists.
+pub fn bor_receipt_storage(
+    block_number: u64,
+    block_hash: &B256,
+    state_sync_count: usize,
+) -> Option<BorReceiptStorage> {
+    (state_sync_count > 0).then(|| store_block_receipts(block_number, block_hash))
+}
+
 /// Describes how a Bor receipt should be stored.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BorReceiptStorage {
@@ -120,6 +132,15 @@ mod tests {
         assert_ne!(storage_pre.key, storage_post.key);
     }
 
+    #[test]
+    fn test_bor_receipt_only_with_state_syncs() {
+        let block_hash = B256::from([0xab; 32]);
+        assert_eq!(bor_receipt_storage(1_000_000, &block_hash, 0), None);
+
+        let storage = bor_receipt_storage(1_000_000, &block_hash, 2).unwrap();
+        assert_eq!(storage, store_block_receipts(1_000_000, &block_hash));
+    }
+
     #[test]
     fn test_no_bor_receipt() {
         let regular = vec![B256::from([0x01; 32])];
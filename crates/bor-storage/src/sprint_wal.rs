@@ -0,0 +1,287 @@
+//! Write-ahead markers of sprint-start blocks.
+//!
+//! Executing a sprint-start block runs `commitSpan` and `onStateReceive` on the
+//! block's in-memory state, and reth later writes the block, its state and its
+//! receipts in one database transaction. A node stopped in between loses the whole
+//! block, never part of it. [`SprintWal`] makes that visible: a marker is written
+//! before the system calls run, updated once they are applied, and dropped when the
+//! block is persisted. Markers left by the previous run name the sprint-start blocks
+//! that were in flight, and [`SprintWal::recover`] tells which of them made it to the
+//! database and which were rolled back and must be executed again.
+//!
+//! On shutdown the node waits, within a timeout, for system calls in flight to finish
+//! (see [`SprintWal::wait_idle`]) rather than abandoning them half way.
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A [`SprintWal`] shared between the block executors and the node.
+pub type SharedSprintWal = Arc<SprintWal>;
+
+/// How far the system calls of a sprint-start block got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SprintStage {
+    /// The system calls were running.
+    Executing,
+    /// The system calls were applied; the block was not persisted yet.
+    Executed,
+}
+
+/// A sprint-start block whose system calls are in flight or not persisted yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SprintMarker {
+    /// Block number.
+    pub number: u64,
+    /// Hash of the block's parent.
+    pub parent_hash: B256,
+    /// Span committed by the block, if any.
+    pub span_id: Option<u64>,
+    /// First state sync event committed by the block, if any.
+    pub first_state_id: Option<u64>,
+    /// Last state sync event committed by the block, if any.
+    pub last_state_id: Option<u64>,
+    /// How far its system calls got.
+    pub stage: SprintStage,
+}
+
+/// What became of a sprint-start block left in flight by the previous run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprintOutcome {
+    /// The block was persisted with its system calls.
+    Committed,
+    /// Nothing of the block was persisted; it is executed again on sync.
+    RolledBack,
+}
+
+/// Markers of the sprint-start blocks in flight, optionally persisted to a file.
+#[derive(Debug, Default)]
+pub struct SprintWal {
+    markers: Mutex<Vec<SprintMarker>>,
+    /// Signalled whenever a marker leaves [`SprintStage::Executing`].
+    idle: Condvar,
+    /// Markers left by the previous run.
+    recovered: Vec<SprintMarker>,
+    path: Option<PathBuf>,
+}
+
+impl SprintWal {
+    /// Create markers kept only while the node runs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create markers persisted at `path`, loading those left by a previous run.
+    pub fn open(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let recovered = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { recovered, path: Some(path), ..Default::default() })
+    }
+
+    /// Markers left by the previous run.
+    pub fn recovered(&self) -> &[SprintMarker] {
+        &self.recovered
+    }
+
+    /// Classify the markers left by the previous run.
+    ///
+    /// `canonical_parent` returns the parent hash of canonical block `number` as
+    /// persisted, if there is one.
+    pub fn recover(
+        &self,
+        canonical_parent: impl Fn(u64) -> Option<B256>,
+    ) -> Vec<(SprintMarker, SprintOutcome)> {
+        self.recovered
+            .iter()
+            .map(|marker| {
+                let outcome = match marker.stage {
+                    SprintStage::Executed
+                        if canonical_parent(marker.number) == Some(marker.parent_hash) =>
+                    {
+                        SprintOutcome::Committed
+                    }
+                    _ => SprintOutcome::RolledBack,
+                };
+                (marker.clone(), outcome)
+            })
+            .collect()
+    }
+
+    /// Record that the system calls of `marker`'s block are about to run.
+    pub fn begin(&self, mut marker: SprintMarker) -> std::io::Result<()> {
+        marker.stage = SprintStage::Executing;
+        let mut markers = self.lock();
+        markers.retain(|m| (m.number, m.parent_hash) != (marker.number, marker.parent_hash));
+        markers.push(marker);
+        self.persist(&markers)
+    }
+
+    /// Record that the system calls of block `number` on `parent_hash` were applied.
+    pub fn executed(&self, number: u64, parent_hash: B256) -> std::io::Result<()> {
+        let mut markers = self.lock();
+        let marker =
+            markers.iter_mut().find(|m| (m.number, m.parent_hash) == (number, parent_hash));
+        let Some(marker) = marker else { return Ok(()) };
+        marker.stage = SprintStage::Executed;
+        self.idle.notify_all();
+        self.persist(&markers)
+    }
+
+    /// Drop the marker of block `number` on `parent_hash`, whose execution failed.
+    pub fn abort(&self, number: u64, parent_hash: B256) -> std::io::Result<()> {
+        let mut markers = self.lock();
+        markers.retain(|m| (m.number, m.parent_hash) != (number, parent_hash));
+        self.idle.notify_all();
+        self.persist(&markers)
+    }
+
+    /// Drop the markers of blocks up to `persisted`, the last block in the database.
+    ///
+    /// Returns the number of markers dropped.
+    pub fn complete_through(&self, persisted: u64) -> std::io::Result<usize> {
+        let mut markers = self.lock();
+        let before = markers.len();
+        markers.retain(|m| m.number > persisted);
+        let dropped = before - markers.len();
+        if dropped > 0 {
+            self.idle.notify_all();
+            self.persist(&markers)?;
+        }
+        Ok(dropped)
+    }
+
+    /// The markers of this run.
+    pub fn markers(&self) -> Vec<SprintMarker> {
+        self.lock().clone()
+    }
+
+    /// Number of blocks whose system calls are running.
+    pub fn in_flight(&self) -> usize {
+        self.lock().iter().filter(|m| m.stage == SprintStage::Executing).count()
+    }
+
+    /// Wait up to `timeout` for the system calls in flight to finish.
+    ///
+    /// Returns `true` if none are left running.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut markers = self.lock();
+        loop {
+            if !markers.iter().any(|m| m.stage == SprintStage::Executing) {
+                return true;
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            markers = self.idle.wait_timeout(markers, left).expect("sprint wal lock poisoned").0;
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<SprintMarker>> {
+        self.markers.lock().expect("sprint wal lock poisoned")
+    }
+
+    fn persist(&self, markers: &[SprintMarker]) -> std::io::Result<()> {
+        match &self.path {
+            Some(path) => write_atomically(path, markers),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Write `markers` to `path` through a temporary file renamed over it.
+fn write_atomically(path: &Path, markers: &[SprintMarker]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(markers)?)?;
+    std::fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(number: u64) -> SprintMarker {
+        SprintMarker {
+            number,
+            parent_hash: B256::with_last_byte(number as u8),
+            span_id: None,
+            first_state_id: Some(1),
+            last_state_id: Some(2),
+            stage: SprintStage::Executed,
+        }
+    }
+
+    #[test]
+    fn test_marker_lifecycle() {
+        let wal = SprintWal::new();
+        wal.begin(marker(16)).unwrap();
+        assert_eq!(wal.in_flight(), 1);
+        assert!(!wal.wait_idle(Duration::from_millis(10)));
+
+        wal.executed(16, marker(16).parent_hash).unwrap();
+        assert_eq!(wal.in_flight(), 0);
+        assert!(wal.wait_idle(Duration::ZERO));
+
+        wal.begin(marker(32)).unwrap();
+        wal.abort(32, marker(32).parent_hash).unwrap();
+        assert_eq!(wal.complete_through(15).unwrap(), 0);
+        assert_eq!(wal.complete_through(16).unwrap(), 1);
+        assert!(wal.markers().is_empty());
+    }
+
+    #[test]
+    fn test_wait_idle_wakes_on_executed() {
+        let wal = Arc::new(SprintWal::new());
+        wal.begin(marker(16)).unwrap();
+        let executor = {
+            let wal = wal.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                wal.executed(16, marker(16).parent_hash).unwrap();
+            })
+        };
+        assert!(wal.wait_idle(Duration::from_secs(5)));
+        executor.join().unwrap();
+    }
+
+    #[test]
+    fn test_recover_after_restart() {
+        let path = std::env::temp_dir().join(format!("sprint-wal-{}.json", std::process::id()));
+        let wal = SprintWal::open(&path).unwrap();
+        assert!(wal.recovered().is_empty());
+        wal.begin(marker(16)).unwrap();
+        wal.executed(16, marker(16).parent_hash).unwrap();
+        wal.begin(marker(32)).unwrap();
+        wal.begin(marker(48)).unwrap();
+        wal.executed(48, marker(48).parent_hash).unwrap();
+        drop(wal);
+
+        let reopened = SprintWal::open(&path).unwrap();
+        assert_eq!(reopened.recovered().len(), 3);
+        // 16 was persisted, 32 was cut off mid-way, 48 never made it to the database.
+        let outcomes: Vec<_> = reopened
+            .recover(|number| (number == 16).then(|| marker(16).parent_hash))
+            .into_iter()
+            .map(|(marker, outcome)| (marker.number, outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (16, SprintOutcome::Committed),
+                (32, SprintOutcome::RolledBack),
+                (48, SprintOutcome::RolledBack),
+            ]
+        );
+        assert!(reopened.markers().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,507 @@
+//! State sync events committed by canonical blocks.
+//!
+//! Bor has no transaction for the `onStateReceive` calls of a sprint start, yet
+//! bor-geth reports one under a hash derived from the block (see
+//! [`derived_bor_tx_hash`]), and explorers look it up like any other. Block
+//! executors record the events a block committed under its number and parent,
+//! since its hash is not known yet; once the block is canonical, the record is
+//! moved under the block and indexed by its derived transaction hash.
+//!
+//! Canonical events are also indexed by state sync ID and by the L1 contract that
+//! emitted them, so bridge-specific queries are ordered-map lookups rather than scans
+//! over blocks.
+//!
+//! [`FileStateSyncStore`] appends each change of the canonical blocks to a file of the
+//! datadir and replays it at startup, so the events of blocks imported by earlier runs
+//! are still found. Blocks imported before the file existed are not backfilled.
+
+use crate::receipt::{bor_receipt_storage, BorReceiptStorage};
+use crate::receipt_key::derived_bor_tx_hash;
+use alloy_primitives::{Address, Bytes, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+/// Default number of blocks kept by [`InMemoryStateSyncStore`].
+pub const MAX_STATE_SYNC_BLOCKS: usize = 100_000;
+
+/// File in the datadir holding the log of [`FileStateSyncStore`].
+pub const STATE_SYNCS_FILE: &str = "bor-state-syncs.jsonl";
+
+/// A state sync event committed through `onStateReceive`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommittedStateSync {
+    /// State sync ID.
+    pub id: u64,
+    /// The L1 contract that emitted the event, if the executor was told.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<Address>,
+    /// The record bytes passed to the StateReceiver.
+    pub data: Bytes,
+}
+
+/// A canonical block that committed state sync events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSyncBlock {
+    /// Block number.
+    pub number: u64,
+    /// Block hash.
+    pub hash: B256,
+    /// Events committed by the block, in order.
+    pub events: Vec<CommittedStateSync>,
+}
+
+impl StateSyncBlock {
+    /// Hash of the block's state sync transaction.
+    pub fn bor_tx_hash(&self) -> B256 {
+        derived_bor_tx_hash(self.number, &self.hash)
+    }
+
+    /// Where the block's Bor receipt is stored, `None` if it committed no events and so
+    /// has neither a receipt nor a state sync transaction.
+    pub fn bor_receipt(&self) -> Option<BorReceiptStorage> {
+        bor_receipt_storage(self.number, &self.hash, self.events.len())
+    }
+}
+
+/// Trait for keeping the state sync events of canonical blocks.
+pub trait StateSyncStore: Send + Sync + std::fmt::Debug {
+    /// Record that block `number` on `parent_hash` committed `events` when executed.
+    fn record_executed(&mut self, number: u64, parent_hash: B256, events: Vec<CommittedStateSync>);
+    /// The events block `number` on `parent_hash` committed when executed, while it is not
+    /// canonical yet.
+    fn executed(&self, number: u64, parent_hash: B256) -> Vec<CommittedStateSync>;
+    /// Make block `hash`, number `number` on `parent_hash`, canonical.
+    ///
+    /// Returns whether the block committed events.
+    fn canonicalize(&mut self, number: u64, parent_hash: B256, hash: B256) -> bool;
+    /// Forget the canonical blocks from `number` on, which were reorged out.
+    fn unwind_from(&mut self, number: u64);
+    /// The events of canonical block `number`, if it committed any.
+    fn block(&self, number: u64) -> Option<StateSyncBlock>;
+    /// The canonical block whose state sync transaction has hash `hash`.
+    fn block_by_bor_tx_hash(&self, hash: &B256) -> Option<StateSyncBlock>;
+    /// Number of the canonical block that committed state sync `id`.
+    fn block_of_state_sync(&self, id: u64) -> Option<u64>;
+    /// Up to `limit` canonical events emitted by `contract`, from ID `from_id` on, with the
+    /// number of the block that committed each.
+    fn events_by_contract(
+        &self,
+        contract: &Address,
+        from_id: u64,
+        limit: usize,
+    ) -> Vec<(u64, CommittedStateSync)>;
+}
+
+/// A state sync store shared between the block executors and the RPC layer.
+pub type SharedStateSyncStore = Arc<RwLock<dyn StateSyncStore>>;
+
+/// In-memory [`StateSyncStore`] keeping the most recent `capacity` blocks.
+#[derive(Debug)]
+pub struct InMemoryStateSyncStore {
+    /// Events of executed blocks not canonical yet, by number and parent.
+    executed: HashMap<(u64, B256), Vec<CommittedStateSync>>,
+    blocks: BTreeMap<u64, StateSyncBlock>,
+    by_tx_hash: HashMap<B256, u64>,
+    /// Block number of each canonical event, by ID.
+    by_id: BTreeMap<u64, u64>,
+    /// IDs of the canonical events of each contract.
+    by_contract: HashMap<Address, BTreeSet<u64>>,
+    capacity: usize,
+}
+
+impl InMemoryStateSyncStore {
+    /// Create a store holding at most `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            executed: HashMap::new(),
+            blocks: BTreeMap::new(),
+            by_tx_hash: HashMap::new(),
+            by_id: BTreeMap::new(),
+            by_contract: HashMap::new(),
+            capacity,
+        }
+    }
+
+    fn index(&mut self, block: &StateSyncBlock) {
+        if block.bor_receipt().is_some() {
+            self.by_tx_hash.insert(block.bor_tx_hash(), block.number);
+        }
+        for event in &block.events {
+            self.by_id.insert(event.id, block.number);
+            if let Some(contract) = event.contract {
+                self.by_contract.entry(contract).or_default().insert(event.id);
+            }
+        }
+    }
+
+    fn unindex(&mut self, block: &StateSyncBlock) {
+        self.by_tx_hash.remove(&block.bor_tx_hash());
+        for event in &block.events {
+            self.by_id.remove(&event.id);
+            let Some(contract) = event.contract else { continue };
+            if let Some(ids) = self.by_contract.get_mut(&contract) {
+                ids.remove(&event.id);
+                if ids.is_empty() {
+                    self.by_contract.remove(&contract);
+                }
+            }
+        }
+    }
+
+    /// Insert canonical `block`, dropping the oldest blocks beyond the capacity.
+    fn insert_canonical(&mut self, block: StateSyncBlock) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.unwind_from(block.number);
+        while self.blocks.len() >= self.capacity {
+            let Some((_, oldest)) = self.blocks.pop_first() else { break };
+            self.unindex(&oldest);
+        }
+        self.index(&block);
+        self.blocks.insert(block.number, block);
+    }
+
+    /// The canonical event with ID `id`.
+    fn event(&self, id: u64) -> Option<(u64, &CommittedStateSync)> {
+        let number = *self.by_id.get(&id)?;
+        let block = self.blocks.get(&number)?;
+        block.events.iter().find(|event| event.id == id).map(|event| (number, event))
+    }
+}
+
+impl Default for InMemoryStateSyncStore {
+    fn default() -> Self {
+        Self::new(MAX_STATE_SYNC_BLOCKS)
+    }
+}
+
+impl StateSyncStore for InMemoryStateSyncStore {
+    /// Blocks that committed no events are not recorded: Bor writes no receipt for them.
+    fn record_executed(&mut self, number: u64, parent_hash: B256, events: Vec<CommittedStateSync>) {
+        if !events.is_empty() {
+            self.executed.insert((number, parent_hash), events);
+        }
+    }
+
+    fn executed(&self, number: u64, parent_hash: B256) -> Vec<CommittedStateSync> {
+        self.executed.get(&(number, parent_hash)).cloned().unwrap_or_default()
+    }
+
+    fn canonicalize(&mut self, number: u64, parent_hash: B256, hash: B256) -> bool {
+        let events = self.executed.remove(&(number, parent_hash));
+        // Siblings of the block will not become canonical at this height any more.
+        self.executed.retain(|(n, _), _| *n > number);
+        // Whatever was canonical from this height on has been replaced.
+        self.unwind_from(number);
+        let Some(events) = events else { return false };
+        self.insert_canonical(StateSyncBlock { number, hash, events });
+        true
+    }
+
+    fn unwind_from(&mut self, number: u64) {
+        for (_, block) in self.blocks.split_off(&number) {
+            self.unindex(&block);
+        }
+    }
+
+    fn block(&self, number: u64) -> Option<StateSyncBlock> {
+        self.blocks.get(&number).cloned()
+    }
+
+    fn block_by_bor_tx_hash(&self, hash: &B256) -> Option<StateSyncBlock> {
+        self.by_tx_hash.get(hash).and_then(|number| self.block(*number))
+    }
+
+    fn block_of_state_sync(&self, id: u64) -> Option<u64> {
+        self.by_id.get(&id).copied()
+    }
+
+    fn events_by_contract(
+        &self,
+        contract: &Address,
+        from_id: u64,
+        limit: usize,
+    ) -> Vec<(u64, CommittedStateSync)> {
+        let Some(ids) = self.by_contract.get(contract) else { return Vec::new() };
+        ids.range(from_id..)
+            .filter_map(|id| self.event(*id))
+            .take(limit)
+            .map(|(number, event)| (number, event.clone()))
+            .collect()
+    }
+}
+
+/// A change of the canonical blocks, one JSON line of the [`FileStateSyncStore`] log.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum LogEntry {
+    /// A block that committed events became canonical.
+    Canonical(StateSyncBlock),
+    /// The canonical blocks from this number on were reorged out.
+    Unwind(u64),
+}
+
+/// [`StateSyncStore`] whose canonical blocks are logged to a file.
+///
+/// Events of executed blocks not canonical yet are only kept in memory: a block lost
+/// with them is executed again, and records them again, after a restart.
+#[derive(Debug)]
+pub struct FileStateSyncStore {
+    store: InMemoryStateSyncStore,
+    log: File,
+    path: PathBuf,
+}
+
+impl FileStateSyncStore {
+    /// Create a store logged at `path` holding at most `capacity` blocks, replaying the
+    /// log left by a previous run.
+    ///
+    /// The log is compacted to the blocks it leaves, so it does not outgrow `capacity`.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut store = InMemoryStateSyncStore::new(capacity);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        for line in bytes.split(|b| *b == b'\n').filter(|line| !line.is_empty()) {
+            match serde_json::from_slice(line) {
+                Ok(LogEntry::Canonical(block)) => store.insert_canonical(block),
+                Ok(LogEntry::Unwind(number)) => store.unwind_from(number),
+                // Only the last line can be torn, by a crash while it was written.
+                Err(err) => {
+                    let path = path.display();
+                    warn!(target: "bor::state_syncs", %path, %err, "dropping torn log entry");
+                    break;
+                }
+            }
+        }
+        let blocks = store.blocks.values().cloned().map(LogEntry::Canonical);
+        compact(&path, blocks)?;
+        let log = OpenOptions::new().append(true).open(&path)?;
+        Ok(Self { store, log, path })
+    }
+
+    /// Append `entry` to the log.
+    ///
+    /// A failed write is logged, not returned: the store stays correct in memory, and
+    /// the canonical updates it follows must not fail because of it.
+    fn append(&mut self, entry: &LogEntry) {
+        let mut line = serde_json::to_vec(entry).expect("state sync log entry serializes");
+        line.push(b'\n');
+        if let Err(err) = self.log.write_all(&line) {
+            let path = self.path.display();
+            warn!(target: "bor::state_syncs", %path, %err, "failed to log state sync events");
+        }
+    }
+}
+
+/// Rewrite the log at `path` with `entries`, through a temporary file renamed over it.
+fn compact(path: &Path, entries: impl Iterator<Item = LogEntry>) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp)?;
+    for entry in entries {
+        serde_json::to_writer(&mut file, &entry)?;
+        file.write_all(b"\n")?;
+    }
+    file.sync_all()?;
+    std::fs::rename(tmp, path)
+}
+
+impl StateSyncStore for FileStateSyncStore {
+    fn record_executed(&mut self, number: u64, parent_hash: B256, events: Vec<CommittedStateSync>) {
+        self.store.record_executed(number, parent_hash, events);
+    }
+
+    fn executed(&self, number: u64, parent_hash: B256) -> Vec<CommittedStateSync> {
+        self.store.executed(number, parent_hash)
+    }
+
+    fn canonicalize(&mut self, number: u64, parent_hash: B256, hash: B256) -> bool {
+        let replaced = self.store.blocks.range(number..).next().is_some();
+        if !self.store.canonicalize(number, parent_hash, hash) {
+            if replaced {
+                self.append(&LogEntry::Unwind(number));
+            }
+            return false;
+        }
+        if let Some(block) = self.store.block(number) {
+            self.append(&LogEntry::Canonical(block));
+        }
+        true
+    }
+
+    fn unwind_from(&mut self, number: u64) {
+        if self.store.blocks.range(number..).next().is_some() {
+            self.store.unwind_from(number);
+            self.append(&LogEntry::Unwind(number));
+        }
+    }
+
+    fn block(&self, number: u64) -> Option<StateSyncBlock> {
+        self.store.block(number)
+    }
+
+    fn block_by_bor_tx_hash(&self, hash: &B256) -> Option<StateSyncBlock> {
+        self.store.block_by_bor_tx_hash(hash)
+    }
+
+    fn block_of_state_sync(&self, id: u64) -> Option<u64> {
+        self.store.block_of_state_sync(id)
+    }
+
+    fn events_by_contract(
+        &self,
+        contract: &Address,
+        from_id: u64,
+        limit: usize,
+    ) -> Vec<(u64, CommittedStateSync)> {
+        self.store.events_by_contract(contract, from_id, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::receipt::store_block_receipts;
+
+    fn events(ids: &[u64]) -> Vec<CommittedStateSync> {
+        ids.iter()
+            .map(|&id| CommittedStateSync {
+                id,
+                contract: Some(Address::with_last_byte(id as u8 % 2)),
+                data: Bytes::from(vec![id as u8]),
+            })
+            .collect()
+    }
+
+    fn hash(number: u64, fork: u8) -> B256 {
+        let mut bytes = [fork; 32];
+        bytes[24..].copy_from_slice(&number.to_be_bytes());
+        B256::from(bytes)
+    }
+
+    #[test]
+    fn test_executed_block_indexed_once_canonical() {
+        let mut store = InMemoryStateSyncStore::default();
+        store.record_executed(16, hash(15, 0), events(&[1, 2]));
+        assert!(store.block(16).is_none());
+        assert_eq!(store.executed(16, hash(15, 0)), events(&[1, 2]));
+        assert!(store.executed(16, hash(15, 1)).is_empty());
+
+        assert!(!store.canonicalize(15, hash(14, 0), hash(15, 0)));
+        assert!(store.canonicalize(16, hash(15, 0), hash(16, 0)));
+        assert!(store.executed(16, hash(15, 0)).is_empty());
+        let block = store.block(16).unwrap();
+        assert_eq!(block.events, events(&[1, 2]));
+        let tx_hash = derived_bor_tx_hash(16, &hash(16, 0));
+        assert_eq!(store.block_by_bor_tx_hash(&tx_hash), Some(block));
+        assert!(store.block_by_bor_tx_hash(&derived_bor_tx_hash(16, &hash(16, 1))).is_none());
+    }
+
+    #[test]
+    fn test_sprint_start_without_events_has_no_bor_tx() {
+        let mut store = InMemoryStateSyncStore::default();
+        store.record_executed(32, hash(31, 0), Vec::new());
+        assert!(!store.canonicalize(32, hash(31, 0), hash(32, 0)));
+        assert!(store.block(32).is_none());
+        assert!(store.block_by_bor_tx_hash(&derived_bor_tx_hash(32, &hash(32, 0))).is_none());
+
+        let empty = StateSyncBlock { number: 32, hash: hash(32, 0), events: Vec::new() };
+        assert_eq!(empty.bor_receipt(), None);
+        let block = StateSyncBlock { events: events(&[3]), ..empty };
+        assert_eq!(block.bor_receipt(), Some(store_block_receipts(32, &hash(32, 0))));
+    }
+
+    #[test]
+    fn test_reorg_replaces_block() {
+        let mut store = InMemoryStateSyncStore::default();
+        store.record_executed(16, hash(15, 0), events(&[1, 2]));
+        store.record_executed(16, hash(15, 1), events(&[1]));
+        store.canonicalize(16, hash(15, 0), hash(16, 0));
+        let reorged = derived_bor_tx_hash(16, &hash(16, 0));
+
+        store.unwind_from(16);
+        assert!(store.block_by_bor_tx_hash(&reorged).is_none());
+        // The sibling executed on the other fork was dropped with the first canonical block.
+        assert!(!store.canonicalize(16, hash(15, 1), hash(16, 1)));
+
+        store.record_executed(16, hash(15, 2), events(&[1, 2, 3]));
+        assert!(store.canonicalize(16, hash(15, 2), hash(16, 2)));
+        assert_eq!(store.block(16).unwrap().events.len(), 3);
+    }
+
+    #[test]
+    fn test_store_bounded() {
+        let mut store = InMemoryStateSyncStore::new(2);
+        for number in [16, 32, 48] {
+            store.record_executed(number, hash(number - 1, 0), events(&[number]));
+            store.canonicalize(number, hash(number - 1, 0), hash(number, 0));
+        }
+        assert!(store.block(16).is_none());
+        assert!(store.block_by_bor_tx_hash(&derived_bor_tx_hash(16, &hash(16, 0))).is_none());
+        assert!(store.block(32).is_some() && store.block(48).is_some());
+        assert_eq!(store.block_of_state_sync(16), None);
+        assert_eq!(store.block_of_state_sync(32), Some(32));
+    }
+
+    #[test]
+    fn test_events_indexed_by_id_and_contract() {
+        let mut store = InMemoryStateSyncStore::default();
+        store.record_executed(16, hash(15, 0), events(&[1, 2, 3]));
+        store.canonicalize(16, hash(15, 0), hash(16, 0));
+        store.record_executed(32, hash(31, 0), events(&[4, 5]));
+        store.canonicalize(32, hash(31, 0), hash(32, 0));
+
+        assert_eq!(store.block_of_state_sync(2), Some(16));
+        assert_eq!(store.block_of_state_sync(5), Some(32));
+        assert_eq!(store.block_of_state_sync(6), None);
+
+        let odd = Address::with_last_byte(1);
+        let ids = |events: Vec<(u64, CommittedStateSync)>| {
+            events.into_iter().map(|(number, event)| (number, event.id)).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(store.events_by_contract(&odd, 0, 10)), vec![(16, 1), (16, 3), (32, 5)]);
+        assert_eq!(ids(store.events_by_contract(&odd, 2, 1)), vec![(16, 3)]);
+        assert!(store.events_by_contract(&Address::with_last_byte(9), 0, 10).is_empty());
+
+        store.unwind_from(32);
+        assert_eq!(store.block_of_state_sync(5), None);
+        assert_eq!(ids(store.events_by_contract(&odd, 0, 10)), vec![(16, 1), (16, 3)]);
+    }
+
+    #[test]
+    fn test_file_store_replays_canonical_blocks() {
+        let path =
+            std::env::temp_dir().join(format!("bor-state-syncs-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = FileStateSyncStore::open(&path, 2).unwrap();
+        for number in [16, 32, 48] {
+            store.record_executed(number, hash(number - 1, 0), events(&[number]));
+            store.canonicalize(number, hash(number - 1, 0), hash(number, 0));
+        }
+        store.unwind_from(48);
+        // Executed but not canonical yet: not logged.
+        store.record_executed(64, hash(63, 0), events(&[64]));
+        drop(store);
+
+        let reopened = FileStateSyncStore::open(&path, 2).unwrap();
+        assert!(reopened.block(16).is_none());
+        assert_eq!(reopened.block(32).unwrap().events, events(&[32]));
+        assert!(reopened.block(48).is_none());
+        assert_eq!(reopened.block_of_state_sync(32), Some(32));
+        let tx_hash = derived_bor_tx_hash(32, &hash(32, 0));
+        assert_eq!(reopened.block_by_bor_tx_hash(&tx_hash).map(|b| b.number), Some(32));
+        assert!(reopened.executed(64, hash(63, 0)).is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,17 @@
+//! Names reserved for Bor tables in reth's database.
+//!
+//! No Bor data is written to these tables yet. Receipts, state syncs, snapshots and sprint
+//! markers live in datadir files, each rewritten through a temporary file renamed over it
+//! and reconciled against the chain tip at startup, so they are not part of the block's
+//! database transaction.
+
 /// Table names for Bor custom tables
 pub const BOR_SPANS_TABLE: &str = "BorSpans";
 pub const BOR_SNAPSHOTS_TABLE: &str = "BorSnapshots";
 pub const BOR_RECEIPTS_TABLE: &str = "BorReceipts";
 pub const BOR_TX_LOOKUP_TABLE: &str = "BorTxLookup";
 pub const BOR_META_TABLE: &str = "BorMeta";
+pub const BOR_BAD_BLOCKS_TABLE: &str = "BorBadBlocks";
 
 /// All Bor custom table names
 pub const BOR_TABLES: &[&str] = &[
@@ -12,6 +20,7 @@ pub const BOR_TABLES: &[&str] = &[
     BOR_RECEIPTS_TABLE,
     BOR_TX_LOOKUP_TABLE,
     BOR_META_TABLE,
+    BOR_BAD_BLOCKS_TABLE,
 ];
 
 /// Key types for each table
@@ -20,6 +29,7 @@ pub const BOR_TABLES: &[&str] = &[
 /// BorReceipts: B256 (receipt_key) -> Vec<u8> (RLP bytes)
 /// BorTxLookup: B256 (tx_hash) -> (u64, u64) (block_number, tx_index)
 /// BorMeta: u64 (meta_key) -> u64 (value)
+/// BorBadBlocks: B256 (block_hash) -> BadBlockRecord (JSON)
 ///
 /// Meta keys
 pub const META_LAST_SPAN_ID: u64 = 0;
@@ -37,11 +47,12 @@ mod tests {
         assert_eq!(BOR_RECEIPTS_TABLE, "BorReceipts");
         assert_eq!(BOR_TX_LOOKUP_TABLE, "BorTxLookup");
         assert_eq!(BOR_META_TABLE, "BorMeta");
+        assert_eq!(BOR_BAD_BLOCKS_TABLE, "BorBadBlocks");
     }
 
     #[test]
     fn test_all_tables_count() {
-        assert_eq!(BOR_TABLES.len(), 5);
+        assert_eq!(BOR_TABLES.len(), 6);
     }
 
     #[test]
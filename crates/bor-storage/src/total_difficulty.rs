@@ -0,0 +1,205 @@
+//! Total difficulty of Bor blocks.
+//!
+//! Bor never merged: every block carries a difficulty, from the signer's position
+//! behind the proposer, and bor-geth reports the sum of the difficulties up to a block
+//! as its `totalDifficulty`. reth assumes a merged chain and keeps no total
+//! difficulty, so tooling comparing the two nodes sees it missing.
+//!
+//! Summing from genesis for every query would read every header of the chain. A
+//! [`TotalDifficultyIndex`] keeps the total difficulty of every
+//! [`DEFAULT_TD_CHECKPOINT_INTERVAL`]th block instead, so that a query sums at most the
+//! headers after the nearest checkpoint. Checkpoints are only added for finalized
+//! blocks, which reorgs cannot replace, and are persisted in the data directory.
+//!
+//! While the index catches up, e.g. during sync, blocks far past its last checkpoint
+//! would still need long header scans; their total difficulty is not reported until
+//! the index is within [`MAX_TD_SUMMED_HEADERS`] of them.
+
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Blocks between two checkpoints of a [`TotalDifficultyIndex`].
+pub const DEFAULT_TD_CHECKPOINT_INTERVAL: u64 = 1_024;
+
+/// Headers a total difficulty query sums at most after the nearest checkpoint.
+pub const MAX_TD_SUMMED_HEADERS: u64 = 4 * DEFAULT_TD_CHECKPOINT_INTERVAL;
+
+/// Format version of the index file; files of other versions are discarded.
+pub const TD_INDEX_VERSION: u32 = 1;
+
+/// Name of the index file in the node's data directory.
+pub const TD_INDEX_FILE: &str = "bor-total-difficulty.json";
+
+/// A [`TotalDifficultyIndex`] shared between the indexing task and the RPC.
+pub type SharedTotalDifficultyIndex = Arc<RwLock<TotalDifficultyIndex>>;
+
+/// Total difficulty of every `interval`th block, from genesis on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TotalDifficultyIndex {
+    /// Blocks between two checkpoints.
+    interval: u64,
+    /// Total difficulty through block `i * interval`, for each `i`.
+    checkpoints: Vec<U256>,
+}
+
+impl Default for TotalDifficultyIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_TD_CHECKPOINT_INTERVAL)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    #[serde(flatten)]
+    index: T,
+}
+
+impl TotalDifficultyIndex {
+    /// Create an empty index with a checkpoint every `interval` blocks.
+    pub fn new(interval: u64) -> Self {
+        Self { interval: interval.max(1), checkpoints: Vec::new() }
+    }
+
+    /// Blocks between two checkpoints.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// The block the next checkpoint is for.
+    pub fn next_checkpoint(&self) -> u64 {
+        self.checkpoints.len() as u64 * self.interval
+    }
+
+    /// The blocks whose difficulties the next checkpoint adds to the last one.
+    pub fn next_range(&self) -> RangeInclusive<u64> {
+        let next = self.next_checkpoint();
+        let start = if next == 0 { 0 } else { next - self.interval + 1 };
+        start..=next
+    }
+
+    /// Add the next checkpoint, given the sum of the difficulties of the blocks of
+    /// [`next_range`](Self::next_range).
+    pub fn push(&mut self, difficulty: U256) {
+        let last = self.checkpoints.last().copied().unwrap_or_default();
+        self.checkpoints.push(last + difficulty);
+    }
+
+    /// Forget the checkpoints after block `number`, e.g. once the chain was unwound to it.
+    pub fn truncate_above(&mut self, number: u64) {
+        self.checkpoints.truncate((number / self.interval + 1) as usize);
+    }
+
+    /// The last checkpoint at or below block `number`, with its total difficulty.
+    pub fn checkpoint_below(&self, number: u64) -> Option<(u64, U256)> {
+        let index = (number / self.interval).min(self.checkpoints.len().checked_sub(1)? as u64);
+        Some((index * self.interval, self.checkpoints[index as usize]))
+    }
+
+    /// Total difficulty of block `number`, adding to the nearest checkpoint the
+    /// difficulties `difficulties` returns for the blocks after it.
+    ///
+    /// Returns `None` if no checkpoint was indexed yet, or if the nearest one is more
+    /// than [`MAX_TD_SUMMED_HEADERS`] blocks below `number`.
+    pub fn total_difficulty<E>(
+        &self,
+        number: u64,
+        difficulties: impl FnOnce(RangeInclusive<u64>) -> Result<Vec<U256>, E>,
+    ) -> Result<Option<U256>, E> {
+        let Some((checkpoint, total)) = self.checkpoint_below(number) else { return Ok(None) };
+        if checkpoint == number {
+            return Ok(Some(total));
+        }
+        if number - checkpoint > MAX_TD_SUMMED_HEADERS {
+            return Ok(None);
+        }
+        let after = difficulties(checkpoint + 1..=number)?;
+        Ok(Some(after.into_iter().fold(total, |total, difficulty| total + difficulty)))
+    }
+
+    /// Load the index left by a previous run at `path`.
+    ///
+    /// Returns `None` if there is none, or if it was written by another format version,
+    /// with another interval, or cannot be read.
+    pub fn load(path: &Path, interval: u64) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        let versioned: Versioned<Self> = serde_json::from_slice(&bytes).ok()?;
+        let index = versioned.index;
+        (versioned.version == TD_INDEX_VERSION && index.interval == interval).then_some(index)
+    }
+
+    /// Write the index to `path`, replacing the previous one.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let versioned = Versioned { version: TD_INDEX_VERSION, index: self };
+        let tmp: PathBuf = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&versioned)?)?;
+        std::fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Difficulty of block `number` in the tests: 1 to 3, as with three validators.
+    fn difficulty(number: u64) -> U256 {
+        U256::from(number % 3 + 1)
+    }
+
+    fn sum(range: RangeInclusive<u64>) -> U256 {
+        range.map(difficulty).fold(U256::ZERO, |total, difficulty| total + difficulty)
+    }
+
+    fn indexed(interval: u64, checkpoints: usize) -> TotalDifficultyIndex {
+        let mut index = TotalDifficultyIndex::new(interval);
+        for _ in 0..checkpoints {
+            index.push(sum(index.next_range()));
+        }
+        index
+    }
+
+    #[test]
+    fn test_total_difficulty_matches_sum_from_genesis() {
+        let index = indexed(16, 4);
+        assert_eq!(index.next_checkpoint(), 64);
+        assert_eq!(index.next_range(), 49..=64);
+        for number in 0..100 {
+            let td = index
+                .total_difficulty(number, |range| Ok::<_, ()>(range.map(difficulty).collect()))
+                .unwrap();
+            assert_eq!(td, Some(sum(0..=number)), "block {number}");
+        }
+
+        let empty = TotalDifficultyIndex::new(16);
+        assert_eq!(empty.total_difficulty(5, |_| Err::<Vec<U256>, _>(())), Ok(None));
+    }
+
+    #[test]
+    fn test_no_total_difficulty_far_past_last_checkpoint() {
+        let index = indexed(16, 4);
+        let last = index.checkpoint_below(u64::MAX).unwrap().0;
+        let summed = |range: RangeInclusive<u64>| Ok::<_, ()>(range.map(difficulty).collect());
+        let reachable = last + MAX_TD_SUMMED_HEADERS;
+        assert_eq!(index.total_difficulty(reachable, summed), Ok(Some(sum(0..=reachable))));
+        let unreached = |_| Err::<Vec<U256>, _>(());
+        assert_eq!(index.total_difficulty(reachable + 1, unreached), Ok(None));
+    }
+
+    #[test]
+    fn test_truncate_and_reload() {
+        let mut index = indexed(16, 4);
+        index.truncate_above(40);
+        assert_eq!(index.checkpoint_below(100), Some((32, sum(0..=32))));
+        index.truncate_above(1_000);
+        assert_eq!(index.next_checkpoint(), 48);
+
+        let path = std::env::temp_dir().join(format!("bor-td-{}.json", std::process::id()));
+        index.save(&path).unwrap();
+        assert_eq!(TotalDifficultyIndex::load(&path, 16), Some(index));
+        assert_eq!(TotalDifficultyIndex::load(&path, 32), None);
+        std::fs::remove_file(path).unwrap();
+    }
+}
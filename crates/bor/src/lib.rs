@@ -0,0 +1,33 @@
+//! Bor as a library, with only as much of the node as its features ask for.
+//!
+//! Without features this is the light part, for indexers and bridge services that
+//! only handle Bor and Heimdall data: the chain's [`primitives`], and the Heimdall
+//! types with the decoding of both Heimdall API versions in [`heimdall`]. It pulls in
+//! neither reth nor an HTTP client.
+//!
+//! - `heimdall-http`: `heimdall::HttpHeimdallClient`, over reqwest and tokio.
+//! - `rpc`: Bor's RPC types and methods, as `rpc`.
+//! - `node`: everything the node is built from, as `chainspec`, `consensus`, `evm`,
+//!   `node`, `payload` and `storage`, with the two features above.
+//!
+//! There is no gRPC transport for Heimdall: its v2 API is read through the gRPC
+//! gateway's REST endpoints, which `heimdall-http` covers.
+
+pub use bor_primitives as primitives;
+pub use heimdall_client as heimdall;
+
+#[cfg(feature = "rpc")]
+pub use bor_rpc as rpc;
+
+#[cfg(feature = "node")]
+pub use bor_chainspec as chainspec;
+#[cfg(feature = "node")]
+pub use bor_consensus as consensus;
+#[cfg(feature = "node")]
+pub use bor_evm as evm;
+#[cfg(feature = "node")]
+pub use bor_node as node;
+#[cfg(feature = "node")]
+pub use bor_payload as payload;
+#[cfg(feature = "node")]
+pub use bor_storage as storage;
@@ -2,6 +2,10 @@
 
 use bor_primitives::Span;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A span cache shared between consensus and the components that refresh it.
+pub type SharedSpanCache = Arc<Mutex<SpanCache>>;
 
 /// A simple LRU span cache.
 ///
@@ -36,6 +40,19 @@ impl SpanCache {
         }
     }
 
+    /// Returns the cached span whose blocks include `block`, promoting it to
+    /// most-recently-used. Where respun spans overlap the latest one wins.
+    /// Returns `None` if no such span is cached.
+    pub fn span_for_block(&mut self, block: u64) -> Option<&Span> {
+        let span_id = self
+            .spans
+            .values()
+            .filter(|span| (span.start_block..=span.end_block).contains(&block))
+            .map(|span| span.id)
+            .max()?;
+        self.get(span_id)
+    }
+
     /// Inserts a span into the cache. If the cache is full, the
     /// least-recently-used entry is evicted first.
     pub fn insert(&mut self, span: Span) {
@@ -60,6 +77,12 @@ impl SpanCache {
         self.access_order.push(span_id);
     }
 
+    /// Removes the span with the given ID, returning it if it was cached.
+    pub fn remove(&mut self, span_id: u64) -> Option<Span> {
+        self.access_order.retain(|&id| id != span_id);
+        self.spans.remove(&span_id)
+    }
+
     /// Returns `true` if the cache contains a span with the given ID.
     pub fn contains(&self, span_id: u64) -> bool {
         self.spans.contains_key(&span_id)
@@ -75,6 +98,11 @@ impl SpanCache {
         self.spans.is_empty()
     }
 
+    /// Returns the cached spans, least-recently-used first, without promoting them.
+    pub fn spans(&self) -> impl Iterator<Item = &Span> {
+        self.access_order.iter().filter_map(|id| self.spans.get(id))
+    }
+
     /// Promote `span_id` to most-recently-used by moving it to the back of
     /// the access-order vector.
     fn touch(&mut self, span_id: u64) {
@@ -125,6 +153,21 @@ mod tests {
         assert_eq!(retrieved.start_block, 6400);
     }
 
+    #[test]
+    fn test_span_for_block() {
+        let mut cache = SpanCache::new(4);
+        cache.insert(make_span(1));
+        cache.insert(make_span(2));
+
+        assert_eq!(cache.span_for_block(6400).map(|span| span.id), Some(1));
+        assert_eq!(cache.span_for_block(19_199).map(|span| span.id), Some(2));
+        assert!(cache.span_for_block(19_200).is_none());
+        // The span found is promoted.
+        assert_eq!(cache.spans().last().map(|span| span.id), Some(2));
+        cache.span_for_block(6400);
+        assert_eq!(cache.spans().last().map(|span| span.id), Some(1));
+    }
+
     #[test]
     fn test_get_missing_returns_none() {
         let mut cache = SpanCache::new(4);
@@ -164,6 +207,33 @@ mod tests {
         assert!(cache.contains(3));
     }
 
+    #[test]
+    fn test_remove() {
+        let mut cache = SpanCache::new(2);
+        cache.insert(make_span(1));
+        cache.insert(make_span(2));
+
+        assert_eq!(cache.remove(1).map(|span| span.id), Some(1));
+        assert!(cache.remove(1).is_none());
+        assert!(!cache.contains(1));
+
+        // The removed span no longer takes a slot or an eviction turn.
+        cache.insert(make_span(3));
+        assert!(cache.contains(2));
+        assert!(cache.contains(3));
+    }
+
+    #[test]
+    fn test_spans_in_access_order() {
+        let mut cache = SpanCache::new(4);
+        cache.insert(make_span(1));
+        cache.insert(make_span(2));
+        cache.get(1);
+
+        let ids: Vec<u64> = cache.spans().map(|span| span.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
     #[test]
     fn test_insert_duplicate_updates_in_place() {
         let mut cache = SpanCache::new(4);
@@ -0,0 +1,189 @@
+//! On-disk copy of the Heimdall data the node keeps in memory.
+//!
+//! A restarted node starts with an empty span cache and no milestone or checkpoint,
+//! and fetches them all again from Heimdall as blocks are validated. With a
+//! [`HeimdallCacheFile`], the cached spans and the latest milestone and checkpoint are
+//! written periodically and on shutdown, and loaded back at startup.
+//!
+//! The file is a cache: a file written by another format version, or one that cannot
+//! be read, is discarded and the data fetched from Heimdall again.
+
+use crate::{Checkpoint, Milestone};
+use bor_primitives::Span;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Format version of the cache file; files of other versions are discarded.
+pub const HEIMDALL_CACHE_VERSION: u32 = 1;
+
+/// Name of the cache file in the node's data directory.
+pub const HEIMDALL_CACHE_FILE: &str = "bor-heimdall-cache.json";
+
+/// The Heimdall data persisted across restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeimdallCacheSnapshot {
+    /// Cached spans, least recently used first.
+    pub spans: Vec<Span>,
+    /// Latest milestone, if one was seen.
+    pub milestone: Option<Milestone>,
+    /// Latest checkpoint, if one was seen.
+    pub checkpoint: Option<Checkpoint>,
+}
+
+impl HeimdallCacheSnapshot {
+    /// Forget the milestone and checkpoint if they end after `block`, e.g. once the
+    /// chain was rewound to it. Spans do not depend on the chain and are kept.
+    ///
+    /// Returns `true` if anything was forgotten.
+    pub fn rewind_to(&mut self, block: u64) -> bool {
+        let milestone = self.milestone.take_if(|milestone| milestone.end_block > block);
+        let checkpoint = self.checkpoint.take_if(|checkpoint| checkpoint.end_block > block);
+        milestone.is_some() || checkpoint.is_some()
+    }
+}
+
+#[derive(Serialize)]
+struct Versioned<'a> {
+    version: u32,
+    #[serde(flatten)]
+    snapshot: &'a HeimdallCacheSnapshot,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// The file a [`HeimdallCacheSnapshot`] is persisted in.
+#[derive(Debug, Clone)]
+pub struct HeimdallCacheFile {
+    path: PathBuf,
+}
+
+impl HeimdallCacheFile {
+    /// Persist the cache at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The cache file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the snapshot left by a previous run.
+    ///
+    /// Returns `None` if there is none, or if it was written by another format version
+    /// or cannot be read.
+    pub fn load(&self) -> Option<HeimdallCacheSnapshot> {
+        let bytes = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                warn!(target: "heimdall::cache", path = %self.path.display(), %err, "unreadable");
+                return None;
+            }
+        };
+        let decoded = serde_json::from_slice::<Version>(&bytes).and_then(|Version { version }| {
+            if version != HEIMDALL_CACHE_VERSION {
+                info!(target: "heimdall::cache", version, "discarding cache of another version");
+                return Ok(None);
+            }
+            serde_json::from_slice(&bytes).map(Some)
+        });
+        decoded.unwrap_or_else(|err| {
+            warn!(target: "heimdall::cache", path = %self.path.display(), %err, "discarding");
+            None
+        })
+    }
+
+    /// Write `snapshot`, replacing the previous one.
+    pub fn save(&self, snapshot: &HeimdallCacheSnapshot) -> std::io::Result<()> {
+        let versioned = Versioned { version: HEIMDALL_CACHE_VERSION, snapshot };
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&versioned)?)?;
+        std::fs::rename(tmp, &self.path)
+    }
+
+    /// Delete the file. Returns `true` if there was one.
+    pub fn clear(&self) -> std::io::Result<bool> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256};
+    use bor_primitives::ValidatorSet;
+
+    fn cache_file(name: &str) -> HeimdallCacheFile {
+        let path = std::env::temp_dir()
+            .join(format!("heimdall-cache-{name}-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        HeimdallCacheFile::new(path)
+    }
+
+    fn snapshot() -> HeimdallCacheSnapshot {
+        let span = Span {
+            id: 3,
+            start_block: 12_800,
+            end_block: 19_199,
+            validator_set: ValidatorSet { validators: vec![], proposer: None },
+            selected_producers: vec![],
+            bor_chain_id: "137".to_string(),
+        };
+        let milestone = Milestone {
+            milestone_id: "m-1".to_string(),
+            start_block: 100,
+            end_block: 115,
+            hash: B256::with_last_byte(1),
+            proposer: Address::with_last_byte(2),
+        };
+        HeimdallCacheSnapshot { spans: vec![span], milestone: Some(milestone), checkpoint: None }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let file = cache_file("roundtrip");
+        assert_eq!(file.load(), None);
+
+        file.save(&snapshot()).unwrap();
+        assert_eq!(file.load(), Some(snapshot()));
+
+        assert!(file.clear().unwrap());
+        assert!(!file.clear().unwrap());
+        assert_eq!(file.load(), None);
+    }
+
+    #[test]
+    fn test_other_version_or_corrupt_file_is_discarded() {
+        let file = cache_file("version");
+        file.save(&snapshot()).unwrap();
+        let mut value: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(file.path()).unwrap()).unwrap();
+        value["version"] = (HEIMDALL_CACHE_VERSION + 1).into();
+        std::fs::write(file.path(), value.to_string()).unwrap();
+        assert_eq!(file.load(), None);
+
+        std::fs::write(file.path(), b"not json").unwrap();
+        assert_eq!(file.load(), None);
+        file.clear().unwrap();
+    }
+
+    #[test]
+    fn test_rewind_forgets_heads_above_block() {
+        let mut cached = snapshot();
+        assert!(!cached.rewind_to(115));
+        assert!(cached.milestone.is_some());
+
+        assert!(cached.rewind_to(114));
+        assert_eq!(cached.milestone, None);
+        assert_eq!(cached.spans.len(), 1);
+    }
+}
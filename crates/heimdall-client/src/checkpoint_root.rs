@@ -0,0 +1,105 @@
+//! Checkpoint root hashes.
+//!
+//! A Heimdall checkpoint commits to a block range by the Merkle root of one leaf per
+//! block, [`checkpoint_leaf`], built by [`compute_root_hash`]. Only hashing is needed,
+//! so a client holding the headers of a range, e.g. a header verifier in a browser,
+//! can check them against a [`Checkpoint`] without a node.
+
+use crate::Checkpoint;
+use alloy_primitives::{keccak256, B256, U256};
+
+/// The leaf of a block in the checkpoint tree:
+/// `keccak256(number ‖ time ‖ txHash ‖ receiptHash)`, each a 32-byte big-endian word.
+pub fn checkpoint_leaf(
+    number: u64,
+    timestamp: u64,
+    transactions_root: B256,
+    receipts_root: B256,
+) -> B256 {
+    let mut preimage = [0u8; 128];
+    preimage[..32].copy_from_slice(&U256::from(number).to_be_bytes::<32>());
+    preimage[32..64].copy_from_slice(&U256::from(timestamp).to_be_bytes::<32>());
+    preimage[64..96].copy_from_slice(transactions_root.as_slice());
+    preimage[96..].copy_from_slice(receipts_root.as_slice());
+    keccak256(preimage)
+}
+
+/// Compute the root hash for a range of block hashes.
+///
+/// This is a simple Merkle tree over the block hashes in the range [start, end].
+/// The hashes are repeatedly paired and hashed until a single root remains.
+/// Checkpoints use it over [`checkpoint_leaf`]s.
+pub fn compute_root_hash(block_hashes: &[B256]) -> B256 {
+    if block_hashes.is_empty() {
+        return B256::ZERO;
+    }
+    if block_hashes.len() == 1 {
+        return block_hashes[0];
+    }
+
+    let mut current_level: Vec<B256> = block_hashes.to_vec();
+
+    // Pad to next power of 2
+    let next_pow2 = current_level.len().next_power_of_two();
+    while current_level.len() < next_pow2 {
+        current_level.push(B256::ZERO);
+    }
+
+    while current_level.len() > 1 {
+        let mut next_level = Vec::with_capacity(current_level.len() / 2);
+        for pair in current_level.chunks(2) {
+            let mut combined = [0u8; 64];
+            combined[..32].copy_from_slice(pair[0].as_slice());
+            combined[32..].copy_from_slice(pair[1].as_slice());
+            next_level.push(keccak256(combined));
+        }
+        current_level = next_level;
+    }
+
+    current_level[0]
+}
+
+impl Checkpoint {
+    /// Returns `true` if `leaves`, the [`checkpoint_leaf`]s of the checkpoint's blocks
+    /// in order, are the ones it commits to.
+    pub fn matches_leaves(&self, leaves: &[B256]) -> bool {
+        let blocks = self.end_block.checked_sub(self.start_block).map(|len| len + 1);
+        blocks == Some(leaves.len() as u64) && compute_root_hash(leaves) == self.root_hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    #[test]
+    fn test_leaf_words() {
+        let leaf = checkpoint_leaf(1, 2, B256::with_last_byte(3), B256::with_last_byte(4));
+        let mut preimage = [0u8; 128];
+        preimage[31] = 1;
+        preimage[63] = 2;
+        preimage[95] = 3;
+        preimage[127] = 4;
+        assert_eq!(leaf, keccak256(preimage));
+    }
+
+    #[test]
+    fn test_checkpoint_matches_leaves() {
+        let leaves: Vec<_> = (10..13)
+            .map(|number| checkpoint_leaf(number, number * 2, B256::ZERO, B256::ZERO))
+            .collect();
+        let checkpoint = Checkpoint {
+            start_block: 10,
+            end_block: 12,
+            root_hash: compute_root_hash(&leaves),
+            proposer: Address::ZERO,
+        };
+        assert!(checkpoint.matches_leaves(&leaves));
+        assert!(!checkpoint.matches_leaves(&leaves[..2]));
+
+        let mut reordered = leaves.clone();
+        reordered.swap(0, 1);
+        assert!(!checkpoint.matches_leaves(&reordered));
+    }
+}
@@ -0,0 +1,216 @@
+//! Timeouts, paging and retries of Heimdall requests.
+//!
+//! Bor hardcodes a 5 second request timeout and pages state sync events 50 at a
+//! time. [`HeimdallConfig`] keeps those as defaults but lets operators tune
+//! them for slow or heavily loaded Heimdall nodes.
+//!
+//! Validators often put Heimdall behind an authenticating load balancer, so the
+//! config also carries credentials ([`HeimdallAuth`]), extra request headers and
+//! root certificates to trust besides the system ones, and how far spans it serves
+//! are trusted ([`SpanTrust`]).
+
+use crate::span_trust::SpanTrust;
+use std::time::Duration;
+
+/// Default timeout of a single Heimdall request, as in Bor.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of state sync events fetched per request, as in Bor.
+pub const DEFAULT_STATE_SYNC_PAGE_SIZE: usize = 50;
+
+/// How failed requests are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of attempts per request, including the first. At least 1.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on each subsequent retry.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay: Duration::from_millis(500) }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before attempt `attempt` (0-based), or `None` for the first attempt.
+    pub fn delay_before(&self, attempt: u32) -> Option<Duration> {
+        (attempt > 0).then(|| self.base_delay.saturating_mul(2u32.saturating_pow(attempt - 1)))
+    }
+}
+
+/// Credentials sent with every Heimdall request.
+#[derive(Clone, PartialEq, Eq)]
+pub enum HeimdallAuth {
+    /// HTTP basic authentication.
+    Basic {
+        /// User name.
+        username: String,
+        /// Password, if any.
+        password: Option<String>,
+    },
+    /// A bearer token.
+    Bearer(String),
+}
+
+impl std::fmt::Debug for HeimdallAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Keep secrets out of logs.
+        match self {
+            Self::Basic { username, .. } => {
+                f.debug_struct("Basic").field("username", username).finish_non_exhaustive()
+            }
+            Self::Bearer(_) => f.write_str("Bearer(..)"),
+        }
+    }
+}
+
+impl std::str::FromStr for HeimdallAuth {
+    type Err = String;
+
+    /// Parse `user:password` (or just `user`) as basic authentication.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err("empty credentials".into());
+        }
+        let (username, password) = match s.split_once(':') {
+            Some((username, password)) => (username, Some(password.to_string())),
+            None => (s, None),
+        };
+        Ok(Self::Basic { username: username.to_string(), password })
+    }
+}
+
+/// Request settings shared by every Heimdall consumer of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeimdallConfig {
+    /// Timeout of a single HTTP request. [`Duration::ZERO`] disables the timeout.
+    pub timeout: Duration,
+    /// Number of state sync events requested per page.
+    pub state_sync_page_size: usize,
+    /// Maximum number of state sync pages fetched for one sprint, or `None` for no limit.
+    pub max_state_sync_pages: Option<usize>,
+    /// Retry policy for failed requests.
+    pub retry: RetryPolicy,
+    /// Credentials sent with every request, if any.
+    pub auth: Option<HeimdallAuth>,
+    /// Extra headers sent with every request, as name and value.
+    pub headers: Vec<(String, String)>,
+    /// PEM-encoded root certificates trusted in addition to the system ones.
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Whether fetched spans are checked against the spans committed on chain.
+    pub span_trust: SpanTrust,
+}
+
+impl Default for HeimdallConfig {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            state_sync_page_size: DEFAULT_STATE_SYNC_PAGE_SIZE,
+            max_state_sync_pages: None,
+            retry: RetryPolicy::default(),
+            auth: None,
+            headers: Vec::new(),
+            root_certificates: Vec::new(),
+            span_trust: SpanTrust::Trusted,
+        }
+    }
+}
+
+impl HeimdallConfig {
+    /// Set the request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the state sync page size. Values below 1 are raised to 1.
+    pub fn with_state_sync_page_size(mut self, page_size: usize) -> Self {
+        self.state_sync_page_size = page_size.max(1);
+        self
+    }
+
+    /// Set the maximum number of state sync pages per sprint. `0` removes the limit.
+    pub fn with_max_state_sync_pages(mut self, max_pages: usize) -> Self {
+        self.max_state_sync_pages = (max_pages > 0).then_some(max_pages);
+        self
+    }
+
+    /// Set the retry policy. An attempt count below 1 is raised to 1.
+    pub fn with_retry(mut self, mut retry: RetryPolicy) -> Self {
+        retry.max_attempts = retry.max_attempts.max(1);
+        self.retry = retry;
+        self
+    }
+
+    /// Send `auth` with every request.
+    pub fn with_auth(mut self, auth: HeimdallAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Send header `name` with `value` with every request.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trust the PEM-encoded root certificate `pem` in addition to the system ones.
+    pub fn with_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Check fetched spans as `span_trust` says.
+    pub fn with_span_trust(mut self, span_trust: SpanTrust) -> Self {
+        self.span_trust = span_trust;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_bor() {
+        let config = HeimdallConfig::default();
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        assert_eq!(config.state_sync_page_size, 50);
+        assert_eq!(config.max_state_sync_pages, None);
+        assert_eq!(config.retry.max_attempts, 3);
+        assert_eq!(config.span_trust, SpanTrust::Trusted);
+    }
+
+    #[test]
+    fn test_builders_clamp() {
+        let config = HeimdallConfig::default()
+            .with_state_sync_page_size(0)
+            .with_max_state_sync_pages(0)
+            .with_retry(RetryPolicy { max_attempts: 0, base_delay: Duration::ZERO });
+        assert_eq!(config.state_sync_page_size, 1);
+        assert_eq!(config.max_state_sync_pages, None);
+        assert_eq!(config.retry.max_attempts, 1);
+    }
+
+    #[test]
+    fn test_auth_parsing_and_redaction() {
+        let auth: HeimdallAuth = "validator:s3cret".parse().unwrap();
+        assert_eq!(
+            auth,
+            HeimdallAuth::Basic { username: "validator".into(), password: Some("s3cret".into()) }
+        );
+        assert!(!format!("{auth:?}").contains("s3cret"));
+        assert!(!format!("{:?}", HeimdallAuth::Bearer("t0ken".into())).contains("t0ken"));
+        assert!("".parse::<HeimdallAuth>().is_err());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let retry = RetryPolicy::default();
+        assert_eq!(retry.delay_before(0), None);
+        assert_eq!(retry.delay_before(1), Some(Duration::from_millis(500)));
+        assert_eq!(retry.delay_before(3), Some(Duration::from_secs(2)));
+    }
+}
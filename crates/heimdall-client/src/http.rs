@@ -1,65 +1,268 @@
 //! HTTP client for interacting with the Heimdall REST API.
+//!
+//! Supports both the v1 and v2 API shapes. The version is auto-detected on first use unless
+//! pinned with [`HttpHeimdallClient::with_api_version`].
+//!
+//! The endpoint can be switched at runtime with [`HttpHeimdallClient::set_base_url`]; all
+//! clones of the client follow the switch. Timeouts, retries, credentials, extra headers
+//! and trusted root certificates follow the client's [`HeimdallConfig`].
+//!
+//! Unless the config trusts spans outright, fetched spans are checked against the
+//! chain once a [`CommittedSpanSource`](crate::CommittedSpanSource) is set with
+//! [`HttpHeimdallClient::set_committed_spans`]; see [`span_trust`](crate::span_trust).
 
-use crate::{Checkpoint, HeimdallClient, HeimdallError, Milestone, StateSyncEvent};
+use crate::{
+    verify_span, Checkpoint, HeimdallApiVersion, HeimdallAuth, HeimdallClient, HeimdallConfig,
+    HeimdallError, Milestone, RequestLimiter, RequestLimits, SharedCommittedSpans, SpanCheck,
+    SpanTrust, StateSyncEvent,
+};
+use base64::Engine;
 use bor_primitives::Span;
-use reqwest::Client;
-use serde::Deserialize;
-use std::time::Duration;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Certificate, Client};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, warn};
 
-/// Maximum number of retry attempts for HTTP requests.
-const MAX_RETRIES: u32 = 3;
+/// Marker stored before the API version has been detected.
+const VERSION_UNKNOWN: u8 = 0;
 
-/// Base delay between retries (doubled on each subsequent attempt).
-const BASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+fn encode_version(version: HeimdallApiVersion) -> u8 {
+    match version {
+        HeimdallApiVersion::V1 => 1,
+        HeimdallApiVersion::V2 => 2,
+    }
+}
 
-/// Wrapper for Heimdall API responses that nest the actual payload under a `"result"` key.
-#[derive(Debug, Deserialize)]
-struct HeimdallResponse<T> {
-    result: T,
+fn normalize_base_url(base_url: String) -> String {
+    base_url.trim_end_matches('/').to_string()
+}
+
+fn build_client(config: &HeimdallConfig) -> Result<Client, HeimdallError> {
+    let invalid = |what: &str, e: &dyn std::fmt::Display| {
+        HeimdallError::InvalidConfig(format!("{what}: {e}"))
+    };
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.headers {
+        let name = HeaderName::try_from(name.as_str()).map_err(|e| invalid("header name", &e))?;
+        let value = HeaderValue::try_from(value.as_str()).map_err(|e| invalid("header value", &e))?;
+        headers.append(name, value);
+    }
+    if let Some(auth) = &config.auth {
+        let credentials = match auth {
+            HeimdallAuth::Basic { username, password } => {
+                let plain = format!("{username}:{}", password.as_deref().unwrap_or_default());
+                format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(plain))
+            }
+            HeimdallAuth::Bearer(token) => format!("Bearer {token}"),
+        };
+        let mut value =
+            HeaderValue::try_from(credentials).map_err(|e| invalid("credentials", &e))?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+
+    let mut builder = Client::builder().default_headers(headers);
+    if !config.timeout.is_zero() {
+        builder = builder.timeout(config.timeout);
+    }
+    for pem in &config.root_certificates {
+        let certificate = Certificate::from_pem(pem).map_err(|e| invalid("root certificate", &e))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+    builder.build().map_err(|e| invalid("http client", &e))
+}
+
+fn decode_version(value: u8) -> Option<HeimdallApiVersion> {
+    match value {
+        1 => Some(HeimdallApiVersion::V1),
+        2 => Some(HeimdallApiVersion::V2),
+        _ => None,
+    }
 }
 
 /// An HTTP-based Heimdall client that communicates with the Heimdall REST API.
 #[derive(Debug, Clone)]
 pub struct HttpHeimdallClient {
-    /// The base URL of the Heimdall API (e.g. `http://localhost:1317`).
-    base_url: String,
+    /// The base URL of the Heimdall API (e.g. `http://localhost:1317`), shared between clones.
+    base_url: Arc<RwLock<String>>,
     /// The inner reqwest HTTP client.
     client: Client,
+    /// Pinned or detected API version, shared between clones.
+    api_version: Arc<AtomicU8>,
+    /// API version pinned by the operator, kept across endpoint switches.
+    pinned_version: Option<HeimdallApiVersion>,
+    /// Concurrency and rate limits, shared between clones.
+    limiter: Arc<RequestLimiter>,
+    /// Timeout, paging and retry settings.
+    config: HeimdallConfig,
+    /// Spans committed on chain that fetched spans are checked against, shared between
+    /// clones.
+    committed_spans: Arc<RwLock<Option<SharedCommittedSpans>>>,
 }
 
 impl HttpHeimdallClient {
     /// Create a new [`HttpHeimdallClient`] with the given base URL.
     pub fn new(base_url: impl Into<String>) -> Self {
-        let base_url = base_url.into().trim_end_matches('/').to_string();
+        let config = HeimdallConfig::default();
         Self {
-            base_url,
-            client: Client::new(),
+            base_url: Arc::new(RwLock::new(normalize_base_url(base_url.into()))),
+            client: build_client(&config).expect("default reqwest client configuration"),
+            api_version: Arc::new(AtomicU8::new(VERSION_UNKNOWN)),
+            pinned_version: None,
+            limiter: Arc::new(RequestLimiter::default()),
+            config,
+            committed_spans: Arc::new(RwLock::new(None)),
         }
     }
 
-    /// Execute a GET request with retry logic (exponential backoff, up to [`MAX_RETRIES`]
-    /// attempts).
-    async fn get_with_retry<T: serde::de::DeserializeOwned>(
-        &self,
-        path: &str,
-    ) -> Result<T, HeimdallError> {
-        let url = format!("{}{}", self.base_url, path);
+    /// Replace the request settings.
+    ///
+    /// Fails if a header, the credentials or a root certificate cannot be used.
+    pub fn with_config(mut self, config: HeimdallConfig) -> Result<Self, HeimdallError> {
+        self.client = build_client(&config)?;
+        self.config = config;
+        Ok(self)
+    }
+
+    /// The request settings of this client.
+    pub fn config(&self) -> &HeimdallConfig {
+        &self.config
+    }
+
+    /// Replace the request limits. Clones made afterwards share the new limiter.
+    pub fn with_limits(mut self, limits: RequestLimits) -> Self {
+        self.limiter = Arc::new(RequestLimiter::new(limits));
+        self
+    }
+
+    /// The request limiter shared by this client and its clones.
+    pub fn limiter(&self) -> &RequestLimiter {
+        &self.limiter
+    }
+
+    /// Pin the API version instead of auto-detecting it.
+    pub fn with_api_version(mut self, version: HeimdallApiVersion) -> Self {
+        self.api_version.store(encode_version(version), Ordering::Relaxed);
+        self.pinned_version = Some(version);
+        self
+    }
+
+    /// Returns the current base URL.
+    pub fn base_url(&self) -> String {
+        self.base_url.read().expect("base url lock poisoned").clone()
+    }
+
+    /// Point this client and all of its clones at a different Heimdall endpoint.
+    ///
+    /// Requests already in flight finish against the old endpoint. Unless the API version is
+    /// pinned, it is detected again on the next request, since the new node may run a
+    /// different Heimdall release.
+    pub fn set_base_url(&self, base_url: impl Into<String>) {
+        *self.base_url.write().expect("base url lock poisoned") = normalize_base_url(base_url.into());
+        let version = self.pinned_version.map_or(VERSION_UNKNOWN, encode_version);
+        self.api_version.store(version, Ordering::Relaxed);
+    }
+
+    /// Check the spans this client and its clones fetch against `committed`, as the
+    /// [`SpanTrust`] of the config says.
+    ///
+    /// Set once the node's state can be read, which is after the client is created.
+    pub fn set_committed_spans(&self, committed: SharedCommittedSpans) {
+        *self.committed_spans.write().expect("committed spans lock poisoned") = Some(committed);
+    }
+
+    /// Accept `span` as its [`SpanTrust`] says, fetching the span before it to check
+    /// against the chain.
+    async fn accept_span(&self, span: Span) -> Result<Span, HeimdallError> {
+        if self.config.span_trust == SpanTrust::Trusted {
+            return Ok(span);
+        }
+        let committed = self.committed_spans.read().expect("committed spans lock poisoned").clone();
+        let Some(committed) = committed else {
+            debug!(target: "heimdall::span_trust", span_id = span.id, "no chain to check span");
+            return Ok(span);
+        };
+        let previous = match span.id.checked_sub(1) {
+            Some(previous) => Some(self.fetch_span_unchecked(previous).await?),
+            None => None,
+        };
+        match verify_span(&span, previous.as_ref(), committed.as_ref()) {
+            Ok(SpanCheck::Verified) => Ok(span),
+            Ok(SpanCheck::Unverifiable) => {
+                debug!(target: "heimdall::span_trust", span_id = span.id, "span not checkable");
+                Ok(span)
+            }
+            Err(err) if self.config.span_trust == SpanTrust::Warn => {
+                warn!(
+                    target: "heimdall::span_trust",
+                    span_id = span.id,
+                    %err,
+                    "span disagrees with chain"
+                );
+                Ok(span)
+            }
+            Err(err) => Err(HeimdallError::InvalidResponse(format!("untrusted span: {err}"))),
+        }
+    }
+
+    async fn fetch_span_unchecked(&self, span_id: u64) -> Result<Span, HeimdallError> {
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(&version.span_path(span_id)).await?;
+        version.decode_span(&body)
+    }
+
+    /// Returns the API version, detecting it on first use.
+    pub async fn api_version(&self) -> Result<HeimdallApiVersion, HeimdallError> {
+        match decode_version(self.api_version.load(Ordering::Relaxed)) {
+            Some(version) => Ok(version),
+            None => self.detect_api_version().await,
+        }
+    }
+
+    /// Probe the server for the latest span using each API shape and remember the first that
+    /// parses.
+    pub async fn detect_api_version(&self) -> Result<HeimdallApiVersion, HeimdallError> {
+        for version in [HeimdallApiVersion::V2, HeimdallApiVersion::V1] {
+            let probe = self
+                .get_with_retry(version.latest_span_path())
+                .await
+                .and_then(|body| version.decode_span(&body));
+            match probe {
+                Ok(_) => {
+                    self.api_version.store(encode_version(version), Ordering::Relaxed);
+                    return Ok(version);
+                }
+                Err(HeimdallError::NotFound | HeimdallError::InvalidResponse(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(HeimdallError::InvalidResponse(
+            "unable to detect Heimdall API version".to_string(),
+        ))
+    }
+
+    /// Execute a GET request with retry logic (exponential backoff, as configured by the
+    /// client's [`RetryPolicy`](crate::RetryPolicy)), returning the raw response body.
+    async fn get_with_retry(&self, path: &str) -> Result<Vec<u8>, HeimdallError> {
+        let url = format!("{}{}", self.base_url(), path);
         let mut last_err = HeimdallError::NetworkError("no attempts made".into());
 
-        for attempt in 0..MAX_RETRIES {
-            if attempt > 0 {
-                let delay = BASE_RETRY_DELAY * 2u32.pow(attempt - 1);
+        for attempt in 0..self.config.retry.max_attempts {
+            if let Some(delay) = self.config.retry.delay_before(attempt) {
                 tokio::time::sleep(delay).await;
             }
 
+            // Retry backoff happens without holding a slot.
+            let _permit = self.limiter.acquire().await;
             match self.client.get(&url).send().await {
                 Ok(resp) => {
                     let status = resp.status();
                     if status.is_success() {
-                        return resp.json::<T>().await.map_err(|e| {
-                            HeimdallError::InvalidResponse(format!(
-                                "failed to parse response: {e}"
-                            ))
+                        return resp.bytes().await.map(|body| body.to_vec()).map_err(|e| {
+                            HeimdallError::NetworkError(format!("failed to read response: {e}"))
                         });
                     } else if status.as_u16() == 404 {
                         return Err(HeimdallError::NotFound);
@@ -90,14 +293,14 @@ impl HttpHeimdallClient {
 
 impl HeimdallClient for HttpHeimdallClient {
     async fn fetch_span(&self, span_id: u64) -> Result<Span, HeimdallError> {
-        let resp: HeimdallResponse<Span> =
-            self.get_with_retry(&format!("/bor/span/{span_id}")).await?;
-        Ok(resp.result)
+        let span = self.fetch_span_unchecked(span_id).await?;
+        self.accept_span(span).await
     }
 
     async fn fetch_latest_span(&self) -> Result<Span, HeimdallError> {
-        let resp: HeimdallResponse<Span> = self.get_with_retry("/bor/latest-span").await?;
-        Ok(resp.result)
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(version.latest_span_path()).await?;
+        self.accept_span(version.decode_span(&body)?).await
     }
 
     async fn fetch_state_sync_events(
@@ -106,22 +309,122 @@ impl HeimdallClient for HttpHeimdallClient {
         to_time: u64,
         limit: usize,
     ) -> Result<Vec<StateSyncEvent>, HeimdallError> {
-        let path = format!(
-            "/clerk/event-record/list?from-id={from_id}&to-time={to_time}&limit={limit}"
-        );
-        let resp: HeimdallResponse<Vec<StateSyncEvent>> = self.get_with_retry(&path).await?;
-        Ok(resp.result)
+        let version = self.api_version().await?;
+        let path = version.state_sync_events_path(from_id, to_time, limit);
+        let body = self.get_with_retry(&path).await?;
+        version.decode_state_sync_events(&body)
     }
 
     async fn fetch_checkpoint(&self, number: u64) -> Result<Checkpoint, HeimdallError> {
-        let resp: HeimdallResponse<Checkpoint> =
-            self.get_with_retry(&format!("/checkpoints/{number}")).await?;
-        Ok(resp.result)
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(&version.checkpoint_path(number)).await?;
+        version.decode_checkpoint(&body)
+    }
+
+    async fn fetch_checkpoint_latest(&self) -> Result<Checkpoint, HeimdallError> {
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(version.latest_checkpoint_path()).await?;
+        version.decode_checkpoint(&body)
     }
 
     async fn fetch_milestone_latest(&self) -> Result<Milestone, HeimdallError> {
-        let resp: HeimdallResponse<Milestone> =
-            self.get_with_retry("/milestones/latest").await?;
-        Ok(resp.result)
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(version.latest_milestone_path()).await?;
+        version.decode_milestone(&body)
+    }
+
+    async fn fetch_milestone(&self, number: u64) -> Result<Milestone, HeimdallError> {
+        let version = self.api_version().await?;
+        let body = self.get_with_retry(&version.milestone_path(number)).await?;
+        version.decode_milestone(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_encoding_roundtrip() {
+        for version in [HeimdallApiVersion::V1, HeimdallApiVersion::V2] {
+            assert_eq!(decode_version(encode_version(version)), Some(version));
+        }
+        assert_eq!(decode_version(VERSION_UNKNOWN), None);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_version_skips_detection() {
+        // Port 9 (discard) is never a Heimdall node; a pinned version must not probe it.
+        let client = HttpHeimdallClient::new("http://127.0.0.1:9")
+            .with_api_version(HeimdallApiVersion::V1);
+        assert_eq!(client.api_version().await.unwrap(), HeimdallApiVersion::V1);
+    }
+
+    #[test]
+    fn test_clones_share_detected_version() {
+        let client = HttpHeimdallClient::new("http://localhost:1317/");
+        let clone = client.clone();
+        client
+            .api_version
+            .store(encode_version(HeimdallApiVersion::V2), Ordering::Relaxed);
+        assert_eq!(
+            decode_version(clone.api_version.load(Ordering::Relaxed)),
+            Some(HeimdallApiVersion::V2)
+        );
+        assert_eq!(client.base_url(), "http://localhost:1317");
+    }
+
+    #[test]
+    fn test_set_base_url_is_shared_and_resets_detection() {
+        let client = HttpHeimdallClient::new("http://localhost:1317");
+        let clone = client.clone();
+        client
+            .api_version
+            .store(encode_version(HeimdallApiVersion::V2), Ordering::Relaxed);
+
+        clone.set_base_url("http://heimdall-backup:1317/");
+        assert_eq!(client.base_url(), "http://heimdall-backup:1317");
+        assert_eq!(decode_version(client.api_version.load(Ordering::Relaxed)), None);
+
+        let pinned = HttpHeimdallClient::new("http://localhost:1317")
+            .with_api_version(HeimdallApiVersion::V1);
+        pinned.set_base_url("http://heimdall-backup:1317");
+        assert_eq!(
+            decode_version(pinned.api_version.load(Ordering::Relaxed)),
+            Some(HeimdallApiVersion::V1)
+        );
+    }
+
+    #[test]
+    fn test_config_is_kept_by_clones() {
+        let config = HeimdallConfig::default().with_state_sync_page_size(10);
+        let client = HttpHeimdallClient::new("http://localhost:1317").with_config(config).unwrap();
+        assert_eq!(client.clone().config().state_sync_page_size, 10);
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        let client = || HttpHeimdallClient::new("http://localhost:1317");
+        let auth = HeimdallAuth::Bearer("t0ken".into());
+        let config = HeimdallConfig::default().with_auth(auth).with_header("X-Validator", "v1");
+        assert!(client().with_config(config).is_ok());
+
+        for config in [
+            HeimdallConfig::default().with_header("bad header", "v"),
+            HeimdallConfig::default().with_header("X-Ok", "line\nbreak"),
+            HeimdallConfig::default().with_root_certificate(b"not a certificate".to_vec()),
+        ] {
+            assert!(matches!(client().with_config(config), Err(HeimdallError::InvalidConfig(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_limiter() {
+        let client = HttpHeimdallClient::new("http://localhost:1317")
+            .with_limits(RequestLimits::default().with_max_in_flight(4));
+        let clone = client.clone();
+        let _permit = client.limiter().acquire().await;
+        assert_eq!(clone.limiter().in_flight(), 1);
+        assert_eq!(clone.limiter().saturation(), 0.25);
     }
 }
@@ -0,0 +1,180 @@
+//! Journal of Heimdall data the node had to go without.
+//!
+//! When a span cannot be fetched, consensus lets blocks of that span through
+//! without a signer check and the producer sits out its slots. Both are right
+//! while Heimdall is down, but must not go unnoticed. [`HeimdallJournal`]
+//! records every span that was missing when it was needed, persists the record
+//! so it survives restarts, and keeps it until the span has been fetched again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// A [`HeimdallJournal`] shared between the components that fetch and use spans.
+pub type SharedHeimdallJournal = Arc<HeimdallJournal>;
+
+/// Error reading or writing the journal file.
+#[derive(Debug, thiserror::Error)]
+#[error("heimdall journal {path}: {reason}")]
+pub struct JournalError {
+    /// The journal file.
+    pub path: PathBuf,
+    /// What went wrong.
+    pub reason: String,
+}
+
+/// A span that was needed but not available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanMiss {
+    /// First block the span was needed for.
+    pub block: u64,
+    /// When it was first missed, in seconds since the Unix epoch.
+    pub first_missed: u64,
+    /// How often it was missed.
+    pub misses: u64,
+}
+
+/// Spans missed while Heimdall was unreachable, optionally persisted to a file.
+#[derive(Debug, Default)]
+pub struct HeimdallJournal {
+    spans: Mutex<BTreeMap<u64, SpanMiss>>,
+    path: Option<PathBuf>,
+}
+
+impl HeimdallJournal {
+    /// Create a journal kept only while the node runs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a journal persisted at `path`, loading the misses left by a previous run.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, JournalError> {
+        let path = path.into();
+        let error = |reason: String| JournalError { path: path.clone(), reason };
+        let spans = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| error(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(error(e.to_string())),
+        };
+        Ok(Self { spans: Mutex::new(spans), path: Some(path) })
+    }
+
+    /// Record that span `span_id` was needed for `block` but not available.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn record_span_miss(&self, span_id: u64, block: u64) {
+        let now =
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        self.record_span_miss_at(span_id, block, now);
+    }
+
+    /// Record that span `span_id` was needed for `block` but not available, at `now`
+    /// seconds since the Unix epoch.
+    ///
+    /// `wasm32-unknown-unknown` has no system clock, so there the caller gives the time.
+    pub fn record_span_miss_at(&self, span_id: u64, block: u64, now: u64) {
+        let mut spans = self.spans.lock().expect("heimdall journal lock poisoned");
+        let first = !spans.contains_key(&span_id);
+        let miss = spans
+            .entry(span_id)
+            .or_insert_with(|| SpanMiss { block, first_missed: now, misses: 0 });
+        miss.misses += 1;
+        if first {
+            warn!(target: "heimdall::journal", span_id, block, "span unavailable, recorded");
+            self.persist(&spans);
+        }
+    }
+
+    /// Forget span `span_id`, now that it has been fetched.
+    ///
+    /// Returns `true` if the span had been missed.
+    pub fn resolve_span(&self, span_id: u64) -> bool {
+        let mut spans = self.spans.lock().expect("heimdall journal lock poisoned");
+        let resolved = spans.remove(&span_id).is_some();
+        if resolved {
+            self.persist(&spans);
+        }
+        resolved
+    }
+
+    /// The missed spans not resolved yet, by ID.
+    pub fn missed_spans(&self) -> BTreeMap<u64, SpanMiss> {
+        self.spans.lock().expect("heimdall journal lock poisoned").clone()
+    }
+
+    /// Returns `true` if nothing missed is outstanding.
+    pub fn is_healthy(&self) -> bool {
+        self.spans.lock().expect("heimdall journal lock poisoned").is_empty()
+    }
+
+    /// Write the journal, if it is persisted.
+    ///
+    /// A failed write is logged, not returned: the in-memory journal stays correct, and
+    /// the callers are on paths that must not fail because of it.
+    fn persist(&self, spans: &BTreeMap<u64, SpanMiss>) {
+        let Some(path) = &self.path else { return };
+        if let Err(err) = write(path, spans) {
+            warn!(target: "heimdall::journal", path = %path.display(), %err, "failed to persist");
+        }
+    }
+}
+
+fn write(path: &Path, spans: &BTreeMap<u64, SpanMiss>) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(spans)?)?;
+    std::fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_resolve() {
+        let journal = HeimdallJournal::new();
+        assert!(journal.is_healthy());
+
+        journal.record_span_miss(3, 19_300);
+        journal.record_span_miss(3, 19_301);
+        let missed = journal.missed_spans();
+        assert_eq!(missed[&3].block, 19_300);
+        assert_eq!(missed[&3].misses, 2);
+        assert!(!journal.is_healthy());
+
+        journal.record_span_miss_at(4, 25_600, 1_700_000_000);
+        assert_eq!(journal.missed_spans()[&4].first_missed, 1_700_000_000);
+        assert!(journal.resolve_span(4));
+
+        assert!(journal.resolve_span(3));
+        assert!(!journal.resolve_span(3));
+        assert!(journal.is_healthy());
+    }
+
+    #[test]
+    fn test_persists_across_restarts() {
+        let path = std::env::temp_dir()
+            .join(format!("heimdall-journal-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let journal = HeimdallJournal::open(&path).unwrap();
+        journal.record_span_miss(7, 44_900);
+        journal.record_span_miss(8, 51_300);
+        journal.resolve_span(7);
+
+        let reopened = HeimdallJournal::open(&path).unwrap();
+        assert_eq!(reopened.missed_spans().keys().copied().collect::<Vec<_>>(), vec![8]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_file_is_an_error() {
+        let path = std::env::temp_dir()
+            .join(format!("heimdall-journal-corrupt-{}.json", std::process::id()));
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(HeimdallJournal::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
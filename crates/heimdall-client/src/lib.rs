@@ -1,16 +1,57 @@
 //! Heimdall client for interacting with the Heimdall layer.
+//!
+//! The HTTP client, `HttpHeimdallClient`, and its `RequestLimiter` are behind the
+//! default `http` feature; the types and the decoding of Heimdall's responses are not.
+//! Without it the crate builds for `wasm32-unknown-unknown`, so that a browser can
+//! decode Heimdall's responses and check checkpoint roots: times are kept as Unix
+//! seconds, and nothing outside `http` reads the system clock on that target.
 
 mod cache;
-pub use cache::SpanCache;
+pub use cache::{SharedSpanCache, SpanCache};
 
+pub mod cache_file;
+pub use cache_file::{
+    HeimdallCacheFile, HeimdallCacheSnapshot, HEIMDALL_CACHE_FILE, HEIMDALL_CACHE_VERSION,
+};
+
+pub mod checkpoint_root;
+pub use checkpoint_root::{checkpoint_leaf, compute_root_hash};
+
+pub mod config;
+pub use config::{HeimdallAuth, HeimdallConfig, RetryPolicy};
+
+#[cfg(feature = "http")]
 pub mod http;
+#[cfg(feature = "http")]
 pub use http::HttpHeimdallClient;
 
+pub mod journal;
+pub use journal::{HeimdallJournal, JournalError, SharedHeimdallJournal, SpanMiss};
+
+#[cfg(feature = "http")]
+pub mod limit;
+#[cfg(feature = "http")]
+pub use limit::{RequestLimiter, RequestLimits, RequestPermit};
+
 pub mod mock;
 pub use mock::MockHeimdallClient;
 
+pub mod span_trust;
+pub use span_trust::{
+    verify_span, CommittedSpanSource, SharedCommittedSpans, SpanCheck, SpanTrust, SpanTrustError,
+};
+
+mod serde_helpers;
+pub use serde_helpers::{format_rfc3339, parse_rfc3339};
+
+mod v1;
+mod v2;
+
+mod version;
+pub use version::HeimdallApiVersion;
+
 use alloy_primitives::{Address, Bytes, B256};
-use bor_primitives::Span;
+use bor_primitives::{EventRecord, Span};
 use serde::{Deserialize, Serialize};
 
 /// Errors that can occur when communicating with the Heimdall service.
@@ -35,10 +76,14 @@ pub enum HeimdallError {
     /// The client has been rate-limited by the Heimdall server.
     #[error("rate limited")]
     RateLimited,
+
+    /// The client configuration is invalid (e.g. a malformed header or certificate).
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
 }
 
 /// A state-sync event relayed from Ethereum L1 to Bor via Heimdall.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StateSyncEvent {
     /// Unique monotonically increasing identifier for this event.
     pub id: u64,
@@ -52,12 +97,29 @@ pub struct StateSyncEvent {
     pub log_index: u64,
     /// The Bor chain ID this event targets.
     pub bor_chain_id: String,
-    /// Unix timestamp of the event.
+    /// When Heimdall recorded the event, in seconds since the Unix epoch.
+    ///
+    /// Only selects which events a sprint commits; it is not part of the committed
+    /// [`record`](Self::record).
     pub time: u64,
 }
 
+impl StateSyncEvent {
+    /// The record of the event Bor commits, without its time.
+    pub fn record(&self) -> EventRecord {
+        EventRecord {
+            id: self.id,
+            contract: self.contract,
+            data: self.data.clone(),
+            tx_hash: self.tx_hash,
+            log_index: self.log_index,
+            chain_id: self.bor_chain_id.clone(),
+        }
+    }
+}
+
 /// A Heimdall checkpoint covering a range of Bor blocks.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Checkpoint {
     /// The first Bor block included in this checkpoint.
     pub start_block: u64,
@@ -70,8 +132,11 @@ pub struct Checkpoint {
 }
 
 /// A Heimdall milestone covering a range of Bor blocks.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Milestone {
+    /// The Heimdall-assigned milestone identifier.
+    #[serde(default)]
+    pub milestone_id: String,
     /// The first Bor block included in this milestone.
     pub start_block: u64,
     /// The last Bor block included in this milestone.
@@ -110,8 +175,19 @@ pub trait HeimdallClient: Send + Sync {
         number: u64,
     ) -> impl Future<Output = Result<Checkpoint, HeimdallError>> + Send;
 
+    /// Fetch the latest checkpoint.
+    fn fetch_checkpoint_latest(
+        &self,
+    ) -> impl Future<Output = Result<Checkpoint, HeimdallError>> + Send;
+
     /// Fetch the latest milestone.
     fn fetch_milestone_latest(
         &self,
     ) -> impl Future<Output = Result<Milestone, HeimdallError>> + Send;
+
+    /// Fetch a specific milestone by its sequence number.
+    fn fetch_milestone(
+        &self,
+        number: u64,
+    ) -> impl Future<Output = Result<Milestone, HeimdallError>> + Send;
 }
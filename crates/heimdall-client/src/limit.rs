@@ -0,0 +1,260 @@
+//! Client-side request limits for a shared Heimdall node.
+//!
+//! Span prefetchers, RPC handlers and the executor all talk to the same
+//! Heimdall node through clones of one client. [`RequestLimiter`] caps how many
+//! of those requests are in flight at once and, optionally, how many may start
+//! per second, so a burst of work on the Bor side does not turn into a burst of
+//! requests against Heimdall.
+
+use reth_metrics::{
+    metrics::{Counter, Gauge, Histogram},
+    Metrics,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+/// Default maximum number of concurrent Heimdall requests.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 16;
+
+/// Limits applied to requests sent by one Heimdall client and its clones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimits {
+    /// Maximum number of requests in flight at once.
+    pub max_in_flight: usize,
+    /// Maximum number of requests started per second, or `None` for no rate limit.
+    ///
+    /// Up to this many requests may start back to back after an idle second.
+    pub requests_per_second: Option<u32>,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self { max_in_flight: DEFAULT_MAX_IN_FLIGHT, requests_per_second: None }
+    }
+}
+
+impl RequestLimits {
+    /// Set the maximum number of requests in flight. Values below 1 are raised to 1.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Set the maximum number of requests started per second. `0` disables the rate limit.
+    pub fn with_requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = (requests_per_second > 0).then_some(requests_per_second);
+        self
+    }
+}
+
+/// Metrics of the Heimdall request limiter.
+#[derive(Metrics)]
+#[metrics(scope = "heimdall.client")]
+struct LimiterMetrics {
+    /// Number of Heimdall requests currently in flight.
+    in_flight: Gauge,
+    /// Fraction of the in-flight limit currently in use, from 0 to 1.
+    saturation: Gauge,
+    /// Number of requests that had to wait for a free slot or for the rate limit.
+    throttled: Counter,
+    /// Time requests spent waiting for the limiter, in seconds.
+    wait_seconds: Histogram,
+}
+
+impl std::fmt::Debug for LimiterMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LimiterMetrics").finish_non_exhaustive()
+    }
+}
+
+/// Token bucket refilled at `rate` tokens per second, holding at most `rate` tokens.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32) -> Self {
+        let rate = rate as f64;
+        Self { rate, tokens: rate, last_refill: Instant::now() }
+    }
+
+    /// Take a token, or return how long to wait until one is available.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// Enforces [`RequestLimits`] across all clones of a Heimdall client.
+#[derive(Debug)]
+pub struct RequestLimiter {
+    limits: RequestLimits,
+    slots: Semaphore,
+    bucket: Option<Mutex<TokenBucket>>,
+    in_flight: AtomicUsize,
+    metrics: LimiterMetrics,
+}
+
+impl RequestLimiter {
+    /// Create a limiter enforcing `limits`.
+    pub fn new(limits: RequestLimits) -> Self {
+        let limits = limits.with_max_in_flight(limits.max_in_flight);
+        Self {
+            limits,
+            slots: Semaphore::new(limits.max_in_flight),
+            bucket: limits.requests_per_second.map(|rate| Mutex::new(TokenBucket::new(rate))),
+            in_flight: AtomicUsize::new(0),
+            metrics: LimiterMetrics::default(),
+        }
+    }
+
+    /// The enforced limits.
+    pub fn limits(&self) -> RequestLimits {
+        self.limits
+    }
+
+    /// Number of requests currently holding a permit.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of the in-flight limit in use, from 0 to 1.
+    pub fn saturation(&self) -> f64 {
+        self.in_flight() as f64 / self.limits.max_in_flight as f64
+    }
+
+    /// Wait until a request may be sent. The request counts as in flight until the returned
+    /// permit is dropped.
+    pub async fn acquire(&self) -> RequestPermit<'_> {
+        let started = Instant::now();
+        let mut throttled = false;
+
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().expect("token bucket lock poisoned").try_take(Instant::now());
+                match wait {
+                    Ok(()) => break,
+                    Err(wait) => {
+                        throttled = true;
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            }
+        }
+
+        let slot = match self.slots.try_acquire() {
+            Ok(slot) => slot,
+            Err(_) => {
+                throttled = true;
+                self.slots.acquire().await.expect("request semaphore is never closed")
+            }
+        };
+
+        if throttled {
+            self.metrics.throttled.increment(1);
+        }
+        self.metrics.wait_seconds.record(started.elapsed().as_secs_f64());
+
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.record_in_flight(in_flight);
+        RequestPermit { _slot: slot, limiter: self }
+    }
+
+    fn record_in_flight(&self, in_flight: usize) {
+        self.metrics.in_flight.set(in_flight as f64);
+        self.metrics.saturation.set(in_flight as f64 / self.limits.max_in_flight as f64);
+    }
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        Self::new(RequestLimits::default())
+    }
+}
+
+/// A request slot handed out by [`RequestLimiter::acquire`].
+#[derive(Debug)]
+pub struct RequestPermit<'a> {
+    _slot: SemaphorePermit<'a>,
+    limiter: &'a RequestLimiter,
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        let in_flight = self.limiter.in_flight.fetch_sub(1, Ordering::Relaxed) - 1;
+        self.limiter.record_in_flight(in_flight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_builders() {
+        let limits = RequestLimits::default().with_max_in_flight(0).with_requests_per_second(0);
+        assert_eq!(limits, RequestLimits { max_in_flight: 1, requests_per_second: None });
+        assert_eq!(RequestLimits::default().with_requests_per_second(5).requests_per_second, Some(5));
+    }
+
+    #[test]
+    fn test_token_bucket_bursts_then_waits() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2);
+        bucket.last_refill = start;
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start).is_ok());
+        let wait = bucket.try_take(start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        assert!(bucket.try_take(start + Duration::from_millis(500)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_cap_and_saturation() {
+        let limiter = RequestLimiter::new(RequestLimits::default().with_max_in_flight(2));
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+        assert_eq!(limiter.in_flight(), 2);
+        assert_eq!(limiter.saturation(), 1.0);
+
+        // A third request waits until a slot frees up.
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.is_err());
+
+        drop(first);
+        assert_eq!(limiter.saturation(), 0.5);
+        let _third = limiter.acquire().await;
+        drop(second);
+        assert_eq!(limiter.in_flight(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_spaces_requests() {
+        let limiter = RequestLimiter::new(RequestLimits::default().with_requests_per_second(10));
+        let start = Instant::now();
+        for _ in 0..10 {
+            drop(limiter.acquire().await);
+        }
+        // The first second's burst is free.
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // The next request waits for a token, a tenth of a second.
+        drop(limiter.acquire().await);
+        let waited = start.elapsed();
+        assert!(waited >= Duration::from_millis(100) && waited < Duration::from_millis(200));
+    }
+}
@@ -19,7 +19,9 @@ pub struct MockHeimdallClient {
     latest_span: Option<Arc<Span>>,
     events: Arc<Vec<StateSyncEvent>>,
     checkpoints: Arc<HashMap<u64, Checkpoint>>,
+    latest_checkpoint: Option<Arc<Checkpoint>>,
     latest_milestone: Option<Arc<Milestone>>,
+    milestones: Arc<HashMap<u64, Milestone>>,
 }
 
 impl MockHeimdallClient {
@@ -52,11 +54,23 @@ impl MockHeimdallClient {
         self
     }
 
+    /// Set the latest checkpoint.
+    pub fn with_latest_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.latest_checkpoint = Some(Arc::new(checkpoint));
+        self
+    }
+
     /// Set the latest milestone.
     pub fn with_latest_milestone(mut self, milestone: Milestone) -> Self {
         self.latest_milestone = Some(Arc::new(milestone));
         self
     }
+
+    /// Register a milestone for a given sequence number.
+    pub fn with_milestone(mut self, number: u64, milestone: Milestone) -> Self {
+        Arc::make_mut(&mut self.milestones).insert(number, milestone);
+        self
+    }
 }
 
 impl HeimdallClient for MockHeimdallClient {
@@ -97,12 +111,26 @@ impl HeimdallClient for MockHeimdallClient {
             .ok_or(HeimdallError::NotFound)
     }
 
+    async fn fetch_checkpoint_latest(&self) -> Result<Checkpoint, HeimdallError> {
+        self.latest_checkpoint
+            .as_ref()
+            .map(|c| c.as_ref().clone())
+            .ok_or(HeimdallError::NotFound)
+    }
+
     async fn fetch_milestone_latest(&self) -> Result<Milestone, HeimdallError> {
         self.latest_milestone
             .as_ref()
             .map(|m| m.as_ref().clone())
             .ok_or(HeimdallError::NotFound)
     }
+
+    async fn fetch_milestone(&self, number: u64) -> Result<Milestone, HeimdallError> {
+        self.milestones
+            .get(&number)
+            .cloned()
+            .ok_or(HeimdallError::NotFound)
+    }
 }
 
 #[cfg(test)]
@@ -158,6 +186,7 @@ mod tests {
 
     fn sample_milestone() -> Milestone {
         Milestone {
+            milestone_id: "ms-1".to_string(),
             start_block: 0,
             end_block: 255,
             hash: B256::ZERO,
@@ -212,6 +241,14 @@ mod tests {
         assert!(client.fetch_checkpoint(99).await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_mock_fetch_checkpoint_latest() {
+        let client = MockHeimdallClient::new().with_latest_checkpoint(sample_checkpoint(3));
+
+        assert_eq!(client.fetch_checkpoint_latest().await.unwrap().end_block, 3999);
+        assert!(MockHeimdallClient::new().fetch_checkpoint_latest().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_mock_fetch_milestone_latest() {
         let client = MockHeimdallClient::new().with_latest_milestone(sample_milestone());
@@ -223,6 +260,19 @@ mod tests {
         assert!(empty.fetch_milestone_latest().await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_mock_fetch_milestone_by_number() {
+        let client = MockHeimdallClient::new().with_milestone(7, sample_milestone());
+
+        let ms = client.fetch_milestone(7).await.unwrap();
+        assert_eq!(ms.milestone_id, "ms-1");
+
+        assert!(matches!(
+            client.fetch_milestone(8).await,
+            Err(HeimdallError::NotFound)
+        ));
+    }
+
     #[tokio::test]
     async fn test_builder_chaining() {
         let client = MockHeimdallClient::new()
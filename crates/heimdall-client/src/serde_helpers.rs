@@ -0,0 +1,181 @@
+//! Serde and parsing helpers for the Heimdall wire formats.
+//!
+//! Heimdall v1 (Amino JSON) encodes integers as JSON numbers and byte fields as `0x` hex.
+//! Heimdall v2 (Cosmos SDK gRPC gateway) encodes 64-bit integers as strings and byte fields
+//! as base64. These helpers accept both.
+
+use base64::Engine;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString<T> {
+    Number(T),
+    String(String),
+}
+
+/// Deserialize a `u64` from either a JSON number or a decimal string.
+pub(crate) fn u64_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match NumberOrString::<u64>::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserialize an `i64` from either a JSON number or a decimal string.
+pub(crate) fn i64_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    match NumberOrString::<i64>::deserialize(deserializer)? {
+        NumberOrString::Number(n) => Ok(n),
+        NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Decode a byte field that is either `0x`-prefixed hex or standard base64.
+pub(crate) fn decode_bytes(value: &str) -> Result<Vec<u8>, String> {
+    if let Some(hex) = value.strip_prefix("0x") {
+        return alloy_primitives::hex::decode(hex).map_err(|e| format!("invalid hex: {e}"));
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| format!("invalid base64: {e}"))
+}
+
+/// Decode a 32-byte hash field that is either `0x`-prefixed hex or base64.
+pub(crate) fn decode_b256(value: &str) -> Result<alloy_primitives::B256, String> {
+    let bytes = decode_bytes(value)?;
+    if bytes.len() != 32 {
+        return Err(format!("expected 32 bytes, got {}", bytes.len()));
+    }
+    Ok(alloy_primitives::B256::from_slice(&bytes))
+}
+
+/// Parse an RFC 3339 timestamp (e.g. `2020-05-30T17:44:02.843856101Z`) into Unix seconds.
+///
+/// Fractional seconds are truncated. Returns `None` for malformed input or times before
+/// the Unix epoch.
+pub fn parse_rfc3339(value: &str) -> Option<u64> {
+    let (date, time) = value.split_once(['T', 't', ' '])?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Split off the UTC offset.
+    let (clock, offset_secs) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0i64)
+    } else {
+        let idx = time.rfind(['+', '-'])?;
+        let (clock, offset) = time.split_at(idx);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (h, m) = offset[1..].split_once(':')?;
+        let offset = h.parse::<i64>().ok()? * 3600 + m.parse::<i64>().ok()? * 60;
+        (clock, sign * offset)
+    };
+
+    let clock = clock.split('.').next()?;
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let second: i64 = clock_parts.next()?.parse().ok()?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second
+        - offset_secs;
+    u64::try_from(secs).ok()
+}
+
+/// Format Unix seconds as an RFC 3339 UTC timestamp (e.g. `2020-05-30T17:44:02Z`).
+pub fn format_rfc3339(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = (year - era * 400) as u64;
+    let mp = u64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + u64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe as i64 - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = (if z >= 0 { z } else { z - 146_096 }) / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe as i64 + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(parse_rfc3339("2020-05-30T17:44:02.843856101Z"), Some(1_590_860_642));
+        assert_eq!(parse_rfc3339("2020-05-30T17:44:02Z"), Some(1_590_860_642));
+        assert_eq!(parse_rfc3339("2020-05-30T19:44:02+02:00"), Some(1_590_860_642));
+        assert_eq!(parse_rfc3339("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_rfc3339_rejects_garbage() {
+        assert_eq!(parse_rfc3339(""), None);
+        assert_eq!(parse_rfc3339("2020-13-01T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2020-05-30"), None);
+        assert_eq!(parse_rfc3339("1969-12-31T23:59:59Z"), None);
+    }
+
+    #[test]
+    fn test_format_rfc3339_roundtrip() {
+        for secs in [0, 1_590_860_642, 1_709_164_800, 4_102_444_800] {
+            assert_eq!(parse_rfc3339(&format_rfc3339(secs)), Some(secs));
+        }
+        assert_eq!(format_rfc3339(1_590_860_642), "2020-05-30T17:44:02Z");
+    }
+
+    #[test]
+    fn test_decode_bytes_hex_and_base64() {
+        assert_eq!(decode_bytes("0x0102ff").unwrap(), vec![0x01, 0x02, 0xff]);
+        assert_eq!(decode_bytes("AQL/").unwrap(), vec![0x01, 0x02, 0xff]);
+        assert!(decode_bytes("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_number_or_string() {
+        #[derive(Deserialize)]
+        struct T {
+            #[serde(deserialize_with = "u64_or_string")]
+            a: u64,
+            #[serde(deserialize_with = "i64_or_string")]
+            b: i64,
+        }
+        let t: T = serde_json::from_str(r#"{"a":"42","b":-7}"#).unwrap();
+        assert_eq!((t.a, t.b), (42, -7));
+        let t: T = serde_json::from_str(r#"{"a":42,"b":"-7"}"#).unwrap();
+        assert_eq!((t.a, t.b), (42, -7));
+    }
+}
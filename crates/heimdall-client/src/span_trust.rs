@@ -0,0 +1,187 @@
+//! Checking spans served by Heimdall against the spans committed on chain.
+//!
+//! Every span is committed to the ValidatorSet contract by `commitSpan` during the
+//! span before it, so by the time a node accepts span `n` from Heimdall, the chain
+//! already holds the producers of span `n - 1`. A compromised or buggy endpoint that
+//! serves wrong spans will most likely also disagree with the chain about that one,
+//! so the client fetches span `n - 1` from the same endpoint and [`verify_span`]
+//! compares its producers with what the contract holds for its first block. How a
+//! disagreement is handled is the [`SpanTrust`] of the client's
+//! [`HeimdallConfig`](crate::HeimdallConfig).
+
+use alloy_primitives::Address;
+use bor_primitives::Span;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// How far spans served by Heimdall are trusted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpanTrust {
+    /// Accept spans as served, as Bor does.
+    #[default]
+    Trusted,
+    /// Check spans against the chain, and log those that fail.
+    Warn,
+    /// Check spans against the chain, and reject those that fail.
+    Verify,
+}
+
+impl std::str::FromStr for SpanTrust {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trusted" => Ok(Self::Trusted),
+            "warn" => Ok(Self::Warn),
+            "verify" => Ok(Self::Verify),
+            _ => Err(format!("unknown span trust mode `{s}`, expected trusted, warn or verify")),
+        }
+    }
+}
+
+/// Producers of the spans committed to the ValidatorSet contract.
+pub trait CommittedSpanSource: Debug + Send + Sync {
+    /// Signers of the producers the contract holds for block `number`, at the latest
+    /// state.
+    ///
+    /// Returns `None` if the state cannot be read, e.g. while the node is syncing.
+    fn committed_producers(&self, number: u64) -> Option<Vec<Address>>;
+}
+
+/// A [`CommittedSpanSource`] shared between clients.
+pub type SharedCommittedSpans = Arc<dyn CommittedSpanSource>;
+
+/// Why a span served by Heimdall is not trusted.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SpanTrustError {
+    /// A selected producer is not in the validator set of the span.
+    #[error("span {span_id}: producer {producer} is not a validator")]
+    UnknownProducer {
+        /// The span.
+        span_id: u64,
+        /// Signer of the producer.
+        producer: Address,
+    },
+    /// The previous span, as served by the same endpoint, disagrees with the chain.
+    #[error(
+        "span {span_id}: Heimdall reports producers {heimdall:?}, the chain committed {committed:?}"
+    )]
+    CommittedMismatch {
+        /// The previous span.
+        span_id: u64,
+        /// Its producers according to Heimdall.
+        heimdall: Vec<Address>,
+        /// Its producers according to the contract.
+        committed: Vec<Address>,
+    },
+}
+
+/// Outcome of a successful [`verify_span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanCheck {
+    /// The previous span matches the chain.
+    Verified,
+    /// There is no previous span, or the chain could not be read; only the span's own
+    /// consistency was checked.
+    Unverifiable,
+}
+
+/// Check `span` and `previous`, the span before it as served by the same endpoint,
+/// against the producers `committed` holds for `previous`.
+pub fn verify_span(
+    span: &Span,
+    previous: Option<&Span>,
+    committed: &dyn CommittedSpanSource,
+) -> Result<SpanCheck, SpanTrustError> {
+    let validators: BTreeSet<Address> =
+        span.validator_set.validators.iter().map(|v| v.signer).collect();
+    if let Some(producer) =
+        span.selected_producers.iter().map(|p| p.signer).find(|p| !validators.contains(p))
+    {
+        return Err(SpanTrustError::UnknownProducer { span_id: span.id, producer });
+    }
+
+    let Some(previous) = previous else { return Ok(SpanCheck::Unverifiable) };
+    let Some(committed) = committed.committed_producers(previous.start_block) else {
+        return Ok(SpanCheck::Unverifiable);
+    };
+    let heimdall: BTreeSet<Address> =
+        previous.selected_producers.iter().map(|p| p.signer).collect();
+    let committed: BTreeSet<Address> = committed.into_iter().collect();
+    if heimdall != committed {
+        return Err(SpanTrustError::CommittedMismatch {
+            span_id: previous.id,
+            heimdall: heimdall.into_iter().collect(),
+            committed: committed.into_iter().collect(),
+        });
+    }
+    Ok(SpanCheck::Verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bor_primitives::{Validator, ValidatorSet};
+
+    #[derive(Debug)]
+    struct Committed(Option<Vec<Address>>);
+
+    impl CommittedSpanSource for Committed {
+        fn committed_producers(&self, _number: u64) -> Option<Vec<Address>> {
+            self.0.clone()
+        }
+    }
+
+    fn validator(byte: u8) -> Validator {
+        Validator {
+            id: byte.into(),
+            address: Address::with_last_byte(byte),
+            voting_power: 100,
+            signer: Address::with_last_byte(byte),
+            proposer_priority: 0,
+        }
+    }
+
+    fn span(id: u64, producers: &[u8]) -> Span {
+        Span {
+            id,
+            start_block: id * 6400,
+            end_block: (id + 1) * 6400 - 1,
+            validator_set: ValidatorSet {
+                validators: (1..=3).map(validator).collect(),
+                proposer: None,
+            },
+            selected_producers: producers.iter().copied().map(validator).collect(),
+            bor_chain_id: "137".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_previous_span_matches_chain() {
+        let chain = Committed(Some(vec![Address::with_last_byte(2), Address::with_last_byte(1)]));
+        let previous = span(4, &[1, 2]);
+        assert_eq!(verify_span(&span(5, &[3]), Some(&previous), &chain), Ok(SpanCheck::Verified));
+
+        let forged = span(4, &[1, 3]);
+        let err = verify_span(&span(5, &[3]), Some(&forged), &chain).unwrap_err();
+        assert!(matches!(err, SpanTrustError::CommittedMismatch { span_id: 4, .. }), "{err}");
+    }
+
+    #[test]
+    fn test_unverifiable_and_inconsistent_spans() {
+        let unreadable = Committed(None);
+        let previous = span(4, &[1]);
+        assert_eq!(
+            verify_span(&span(5, &[1]), Some(&previous), &unreadable),
+            Ok(SpanCheck::Unverifiable)
+        );
+        assert_eq!(verify_span(&span(0, &[1]), None, &unreadable), Ok(SpanCheck::Unverifiable));
+
+        let err = verify_span(&span(5, &[9]), None, &unreadable).unwrap_err();
+        let producer = Address::with_last_byte(9);
+        assert_eq!(err, SpanTrustError::UnknownProducer { span_id: 5, producer });
+        assert_eq!("verify".parse::<SpanTrust>(), Ok(SpanTrust::Verify));
+        assert!("paranoid".parse::<SpanTrust>().is_err());
+    }
+}
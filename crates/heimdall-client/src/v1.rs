@@ -0,0 +1,147 @@
+//! Heimdall v1 (Amino JSON) wire models.
+//!
+//! Every response is wrapped as `{"height": "...", "result": ...}`. Validators use the legacy
+//! `ID` / `power` / `accum` field names.
+
+use crate::serde_helpers::{decode_bytes, parse_rfc3339};
+use crate::{Checkpoint, HeimdallError, Milestone, StateSyncEvent};
+use alloy_primitives::{Address, Bytes, B256};
+use bor_primitives::{Span, Validator, ValidatorSet};
+use serde::Deserialize;
+
+/// The v1 response envelope.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Envelope<T> {
+    pub(crate) result: T,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ValidatorV1 {
+    #[serde(rename = "ID")]
+    id: u64,
+    power: i64,
+    signer: Address,
+    #[serde(default)]
+    accum: i64,
+}
+
+impl From<ValidatorV1> for Validator {
+    fn from(v: ValidatorV1) -> Self {
+        Self {
+            id: v.id,
+            address: v.signer,
+            voting_power: v.power,
+            signer: v.signer,
+            proposer_priority: v.accum,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ValidatorSetV1 {
+    #[serde(default)]
+    validators: Vec<ValidatorV1>,
+    proposer: Option<ValidatorV1>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpanV1 {
+    span_id: u64,
+    start_block: u64,
+    end_block: u64,
+    validator_set: ValidatorSetV1,
+    #[serde(default)]
+    selected_producers: Vec<ValidatorV1>,
+    bor_chain_id: String,
+}
+
+impl From<SpanV1> for Span {
+    fn from(s: SpanV1) -> Self {
+        Self {
+            id: s.span_id,
+            start_block: s.start_block,
+            end_block: s.end_block,
+            validator_set: ValidatorSet {
+                validators: s.validator_set.validators.into_iter().map(Into::into).collect(),
+                proposer: s.validator_set.proposer.map(Into::into),
+            },
+            selected_producers: s.selected_producers.into_iter().map(Into::into).collect(),
+            bor_chain_id: s.bor_chain_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EventRecordV1 {
+    id: u64,
+    contract: Address,
+    data: String,
+    tx_hash: B256,
+    log_index: u64,
+    bor_chain_id: String,
+    record_time: String,
+}
+
+impl TryFrom<EventRecordV1> for StateSyncEvent {
+    type Error = HeimdallError;
+
+    fn try_from(e: EventRecordV1) -> Result<Self, Self::Error> {
+        let data = decode_bytes(&e.data).map_err(HeimdallError::InvalidResponse)?;
+        let time = parse_rfc3339(&e.record_time).ok_or_else(|| {
+            HeimdallError::InvalidResponse(format!("invalid record_time: {}", e.record_time))
+        })?;
+        Ok(Self {
+            id: e.id,
+            contract: e.contract,
+            data: Bytes::from(data),
+            tx_hash: e.tx_hash,
+            log_index: e.log_index,
+            bor_chain_id: e.bor_chain_id,
+            time,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CheckpointV1 {
+    proposer: Address,
+    start_block: u64,
+    end_block: u64,
+    root_hash: B256,
+}
+
+impl From<CheckpointV1> for Checkpoint {
+    fn from(c: CheckpointV1) -> Self {
+        Self {
+            start_block: c.start_block,
+            end_block: c.end_block,
+            root_hash: c.root_hash,
+            proposer: c.proposer,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MilestoneV1 {
+    proposer: Address,
+    start_block: u64,
+    end_block: u64,
+    hash: B256,
+    #[serde(default)]
+    milestone_id: String,
+}
+
+impl From<MilestoneV1> for Milestone {
+    fn from(m: MilestoneV1) -> Self {
+        Self {
+            milestone_id: m.milestone_id,
+            start_block: m.start_block,
+            end_block: m.end_block,
+            hash: m.hash,
+            proposer: m.proposer,
+        }
+    }
+}
+
+/// Event list responses carry `"result": null` when there are no records.
+pub(crate) type EventListV1 = Envelope<Option<Vec<EventRecordV1>>>;
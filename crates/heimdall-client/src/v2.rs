@@ -0,0 +1,175 @@
+//! Heimdall v2 (Cosmos SDK gRPC gateway) wire models.
+//!
+//! Responses name their payload (`{"span": ...}`, `{"event_records": [...]}`) instead of using
+//! a `result` envelope, 64-bit integers are JSON strings and byte fields are base64. List
+//! endpoints also carry a `pagination` object; it is ignored because event paging is driven
+//! by `from_id`, exactly as in v1.
+
+use crate::serde_helpers::{decode_b256, decode_bytes, i64_or_string, parse_rfc3339, u64_or_string};
+use crate::{Checkpoint, HeimdallError, Milestone, StateSyncEvent};
+use alloy_primitives::{Address, Bytes, B256};
+use bor_primitives::{Span, Validator, ValidatorSet};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ValidatorV2 {
+    #[serde(deserialize_with = "u64_or_string")]
+    val_id: u64,
+    #[serde(deserialize_with = "i64_or_string")]
+    voting_power: i64,
+    signer: Address,
+    #[serde(default, deserialize_with = "i64_or_string")]
+    proposer_priority: i64,
+}
+
+impl From<ValidatorV2> for Validator {
+    fn from(v: ValidatorV2) -> Self {
+        Self {
+            id: v.val_id,
+            address: v.signer,
+            voting_power: v.voting_power,
+            signer: v.signer,
+            proposer_priority: v.proposer_priority,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ValidatorSetV2 {
+    #[serde(default)]
+    validators: Vec<ValidatorV2>,
+    proposer: Option<ValidatorV2>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpanV2 {
+    #[serde(deserialize_with = "u64_or_string")]
+    id: u64,
+    #[serde(deserialize_with = "u64_or_string")]
+    start_block: u64,
+    #[serde(deserialize_with = "u64_or_string")]
+    end_block: u64,
+    validator_set: ValidatorSetV2,
+    #[serde(default)]
+    selected_producers: Vec<ValidatorV2>,
+    bor_chain_id: String,
+}
+
+impl From<SpanV2> for Span {
+    fn from(s: SpanV2) -> Self {
+        Self {
+            id: s.id,
+            start_block: s.start_block,
+            end_block: s.end_block,
+            validator_set: ValidatorSet {
+                validators: s.validator_set.validators.into_iter().map(Into::into).collect(),
+                proposer: s.validator_set.proposer.map(Into::into),
+            },
+            selected_producers: s.selected_producers.into_iter().map(Into::into).collect(),
+            bor_chain_id: s.bor_chain_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpanResponse {
+    pub(crate) span: SpanV2,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EventRecordV2 {
+    #[serde(deserialize_with = "u64_or_string")]
+    id: u64,
+    contract: Address,
+    data: String,
+    tx_hash: B256,
+    #[serde(deserialize_with = "u64_or_string")]
+    log_index: u64,
+    bor_chain_id: String,
+    record_time: String,
+}
+
+impl TryFrom<EventRecordV2> for StateSyncEvent {
+    type Error = HeimdallError;
+
+    fn try_from(e: EventRecordV2) -> Result<Self, Self::Error> {
+        let data = decode_bytes(&e.data).map_err(HeimdallError::InvalidResponse)?;
+        let time = parse_rfc3339(&e.record_time).ok_or_else(|| {
+            HeimdallError::InvalidResponse(format!("invalid record_time: {}", e.record_time))
+        })?;
+        Ok(Self {
+            id: e.id,
+            contract: e.contract,
+            data: Bytes::from(data),
+            tx_hash: e.tx_hash,
+            log_index: e.log_index,
+            bor_chain_id: e.bor_chain_id,
+            time,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EventListResponse {
+    #[serde(default)]
+    pub(crate) event_records: Vec<EventRecordV2>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CheckpointV2 {
+    proposer: Address,
+    #[serde(deserialize_with = "u64_or_string")]
+    start_block: u64,
+    #[serde(deserialize_with = "u64_or_string")]
+    end_block: u64,
+    root_hash: String,
+}
+
+impl TryFrom<CheckpointV2> for Checkpoint {
+    type Error = HeimdallError;
+
+    fn try_from(c: CheckpointV2) -> Result<Self, Self::Error> {
+        Ok(Self {
+            start_block: c.start_block,
+            end_block: c.end_block,
+            root_hash: decode_b256(&c.root_hash).map_err(HeimdallError::InvalidResponse)?,
+            proposer: c.proposer,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CheckpointResponse {
+    pub(crate) checkpoint: CheckpointV2,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MilestoneV2 {
+    proposer: Address,
+    #[serde(deserialize_with = "u64_or_string")]
+    start_block: u64,
+    #[serde(deserialize_with = "u64_or_string")]
+    end_block: u64,
+    hash: String,
+    #[serde(default)]
+    milestone_id: String,
+}
+
+impl TryFrom<MilestoneV2> for Milestone {
+    type Error = HeimdallError;
+
+    fn try_from(m: MilestoneV2) -> Result<Self, Self::Error> {
+        Ok(Self {
+            milestone_id: m.milestone_id,
+            start_block: m.start_block,
+            end_block: m.end_block,
+            hash: decode_b256(&m.hash).map_err(HeimdallError::InvalidResponse)?,
+            proposer: m.proposer,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MilestoneResponse {
+    pub(crate) milestone: MilestoneV2,
+}
@@ -0,0 +1,161 @@
+//! Heimdall REST API versions: endpoint paths and response decoding.
+
+use crate::serde_helpers::format_rfc3339;
+use crate::{v1, v2, Checkpoint, HeimdallError, Milestone, StateSyncEvent};
+use bor_primitives::Span;
+use serde::de::DeserializeOwned;
+
+/// The shape of the Heimdall REST API a client talks to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeimdallApiVersion {
+    /// Legacy Heimdall (Tendermint / Amino JSON).
+    V1,
+    /// Heimdall v2 (CometBFT / Cosmos SDK gRPC gateway).
+    V2,
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, HeimdallError> {
+    serde_json::from_slice(body)
+        .map_err(|e| HeimdallError::InvalidResponse(format!("failed to parse response: {e}")))
+}
+
+impl HeimdallApiVersion {
+    /// Path of the span with the given ID.
+    pub fn span_path(self, span_id: u64) -> String {
+        match self {
+            Self::V1 => format!("/bor/span/{span_id}"),
+            Self::V2 => format!("/bor/spans/{span_id}"),
+        }
+    }
+
+    /// Path of the latest span.
+    pub fn latest_span_path(self) -> &'static str {
+        match self {
+            Self::V1 => "/bor/latest-span",
+            Self::V2 => "/bor/spans/latest",
+        }
+    }
+
+    /// Path listing state-sync events from `from_id` recorded before `to_time` (Unix seconds).
+    pub fn state_sync_events_path(self, from_id: u64, to_time: u64, limit: usize) -> String {
+        match self {
+            Self::V1 => {
+                format!("/clerk/event-record/list?from-id={from_id}&to-time={to_time}&limit={limit}")
+            }
+            Self::V2 => format!(
+                "/clerk/time?from_id={from_id}&to_time={}&pagination.limit={limit}",
+                format_rfc3339(to_time)
+            ),
+        }
+    }
+
+    /// Path of the checkpoint with the given number.
+    pub fn checkpoint_path(self, number: u64) -> String {
+        format!("/checkpoints/{number}")
+    }
+
+    /// Path of the latest checkpoint.
+    pub fn latest_checkpoint_path(self) -> &'static str {
+        "/checkpoints/latest"
+    }
+
+    /// Path of the milestone with the given sequence number.
+    pub fn milestone_path(self, number: u64) -> String {
+        match self {
+            Self::V1 => format!("/milestone/{number}"),
+            Self::V2 => format!("/milestones/{number}"),
+        }
+    }
+
+    /// Path of the latest milestone.
+    pub fn latest_milestone_path(self) -> &'static str {
+        match self {
+            Self::V1 => "/milestone/latest",
+            Self::V2 => "/milestones/latest",
+        }
+    }
+
+    /// Decode a span response body.
+    pub fn decode_span(self, body: &[u8]) -> Result<Span, HeimdallError> {
+        match self {
+            Self::V1 => Ok(parse::<v1::Envelope<v1::SpanV1>>(body)?.result.into()),
+            Self::V2 => Ok(parse::<v2::SpanResponse>(body)?.span.into()),
+        }
+    }
+
+    /// Decode a state-sync event list response body.
+    pub fn decode_state_sync_events(
+        self,
+        body: &[u8],
+    ) -> Result<Vec<StateSyncEvent>, HeimdallError> {
+        match self {
+            Self::V1 => parse::<v1::EventListV1>(body)?
+                .result
+                .unwrap_or_default()
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect(),
+            Self::V2 => parse::<v2::EventListResponse>(body)?
+                .event_records
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect(),
+        }
+    }
+
+    /// Decode a checkpoint response body.
+    pub fn decode_checkpoint(self, body: &[u8]) -> Result<Checkpoint, HeimdallError> {
+        match self {
+            Self::V1 => Ok(parse::<v1::Envelope<v1::CheckpointV1>>(body)?.result.into()),
+            Self::V2 => parse::<v2::CheckpointResponse>(body)?.checkpoint.try_into(),
+        }
+    }
+
+    /// Decode a milestone response body.
+    pub fn decode_milestone(self, body: &[u8]) -> Result<Milestone, HeimdallError> {
+        match self {
+            Self::V1 => Ok(parse::<v1::Envelope<v1::MilestoneV1>>(body)?.result.into()),
+            Self::V2 => parse::<v2::MilestoneResponse>(body)?.milestone.try_into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_differ_by_version() {
+        assert_eq!(HeimdallApiVersion::V1.span_path(3), "/bor/span/3");
+        assert_eq!(HeimdallApiVersion::V2.span_path(3), "/bor/spans/3");
+        assert_eq!(HeimdallApiVersion::V1.latest_milestone_path(), "/milestone/latest");
+        assert_eq!(HeimdallApiVersion::V2.latest_milestone_path(), "/milestones/latest");
+    }
+
+    #[test]
+    fn test_v2_event_path_uses_rfc3339() {
+        let path = HeimdallApiVersion::V2.state_sync_events_path(5, 1_590_860_642, 50);
+        assert_eq!(
+            path,
+            "/clerk/time?from_id=5&to_time=2020-05-30T17:44:02Z&pagination.limit=50"
+        );
+    }
+
+    #[test]
+    fn test_v1_empty_event_list() {
+        let events = HeimdallApiVersion::V1
+            .decode_state_sync_events(br#"{"height":"1","result":null}"#)
+            .unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_wrong_version_is_invalid_response() {
+        let v2_body = br#"{"span":{"id":"1","start_block":"0","end_block":"1","validator_set":{"validators":[]},"bor_chain_id":"137"}}"#;
+        assert!(matches!(
+            HeimdallApiVersion::V1.decode_span(v2_body),
+            Err(HeimdallError::InvalidResponse(_))
+        ));
+        assert!(HeimdallApiVersion::V2.decode_span(v2_body).is_ok());
+    }
+}
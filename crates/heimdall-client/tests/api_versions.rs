@@ -0,0 +1,108 @@
+//! Decoding tests against v1 and v2 Heimdall response fixtures.
+//!
+//! Each fixture pair describes the same object in both API shapes; decoding either must yield
+//! identical domain types.
+
+use alloy_primitives::{address, b256};
+use heimdall_client::HeimdallApiVersion::{V1, V2};
+
+const V1_SPAN: &[u8] = include_bytes!("fixtures/v1/span.json");
+const V2_SPAN: &[u8] = include_bytes!("fixtures/v2/span.json");
+const V1_EVENTS: &[u8] = include_bytes!("fixtures/v1/event_records.json");
+const V2_EVENTS: &[u8] = include_bytes!("fixtures/v2/event_records.json");
+const V1_CHECKPOINT: &[u8] = include_bytes!("fixtures/v1/checkpoint.json");
+const V2_CHECKPOINT: &[u8] = include_bytes!("fixtures/v2/checkpoint.json");
+const V1_MILESTONE: &[u8] = include_bytes!("fixtures/v1/milestone.json");
+const V2_MILESTONE: &[u8] = include_bytes!("fixtures/v2/milestone.json");
+
+// ---------------------------------------------------------------------------
+// Spans
+// ---------------------------------------------------------------------------
+
+#[test]
+fn span_v1_and_v2_decode_identically() {
+    let v1 = V1.decode_span(V1_SPAN).unwrap();
+    let v2 = V2.decode_span(V2_SPAN).unwrap();
+
+    assert_eq!(v1, v2);
+    assert_eq!(v1.id, 1);
+    assert_eq!(v1.start_block, 256);
+    assert_eq!(v1.end_block, 6655);
+    assert_eq!(v1.bor_chain_id, "137");
+
+    let validators = &v1.validator_set.validators;
+    assert_eq!(validators.len(), 2);
+    assert_eq!(validators[0].signer, address!("5973918275c01f50555d44e92c9d9b353cadad54"));
+    assert_eq!(validators[0].voting_power, 10_000);
+    assert_eq!(validators[0].proposer_priority, -5_000);
+    assert_eq!(v1.selected_producers.len(), 2);
+    assert_eq!(v1.validator_set.proposer.as_ref().unwrap().id, 1);
+}
+
+#[test]
+fn span_shape_mismatch_is_rejected() {
+    assert!(V1.decode_span(V2_SPAN).is_err());
+    assert!(V2.decode_span(V1_SPAN).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// State-sync events
+// ---------------------------------------------------------------------------
+
+#[test]
+fn event_records_v1_and_v2_decode_identically() {
+    let v1 = V1.decode_state_sync_events(V1_EVENTS).unwrap();
+    let v2 = V2.decode_state_sync_events(V2_EVENTS).unwrap();
+
+    assert_eq!(v1.len(), 2);
+    assert_eq!(v2.len(), 2);
+    for (a, b) in v1.iter().zip(&v2) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.contract, b.contract);
+        assert_eq!(a.data, b.data);
+        assert_eq!(a.tx_hash, b.tx_hash);
+        assert_eq!(a.log_index, b.log_index);
+        assert_eq!(a.time, b.time);
+    }
+
+    // Fractional seconds are truncated.
+    assert_eq!(v1[0].time, 1_590_860_642);
+    assert_eq!(v1[1].time, 1_590_860_832);
+    assert_eq!(v1[0].data.len(), 64);
+}
+
+// ---------------------------------------------------------------------------
+// Checkpoints and milestones
+// ---------------------------------------------------------------------------
+
+#[test]
+fn checkpoint_v1_and_v2_decode_identically() {
+    let v1 = V1.decode_checkpoint(V1_CHECKPOINT).unwrap();
+    let v2 = V2.decode_checkpoint(V2_CHECKPOINT).unwrap();
+
+    assert_eq!(v1.start_block, v2.start_block);
+    assert_eq!(v1.end_block, 255);
+    assert_eq!(v2.end_block, 255);
+    assert_eq!(v1.proposer, v2.proposer);
+    assert_eq!(
+        v1.root_hash,
+        b256!("d6f2fd1b2a8ee3cbb2a1a3d8e6d6a4f4cb6b5b1f3e7c2a9d0e8f7a6b5c4d3e2f")
+    );
+    assert_eq!(v1.root_hash, v2.root_hash);
+}
+
+#[test]
+fn milestone_v1_and_v2_decode_identically() {
+    let v1 = V1.decode_milestone(V1_MILESTONE).unwrap();
+    let v2 = V2.decode_milestone(V2_MILESTONE).unwrap();
+
+    assert_eq!(v1.milestone_id, v2.milestone_id);
+    assert!(v1.milestone_id.starts_with("17ce48fe-0a18-41a8-ab7e-59d8002f027b"));
+    assert_eq!(v1.start_block, 48_000_000);
+    assert_eq!(v2.end_block, 48_000_015);
+    assert_eq!(
+        v1.hash,
+        b256!("3f8a8e1d1b2f0c6e9f5d4c3b2a1908f7e6d5c4b3a29180f7e6d5c4b3a2918070")
+    );
+    assert_eq!(v1.hash, v2.hash);
+}
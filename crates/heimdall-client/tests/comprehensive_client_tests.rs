@@ -238,6 +238,7 @@ async fn mock_builder_chaining_all_methods() {
         proposer: Address::new([0xcc; 20]),
     };
     let milestone = Milestone {
+        milestone_id: "ms-0".to_string(),
         start_block: 0,
         end_block: 255,
         hash: B256::ZERO,
@@ -330,6 +331,7 @@ fn serde_roundtrip_checkpoint() {
 #[test]
 fn serde_roundtrip_milestone() {
     let milestone = Milestone {
+        milestone_id: "ms-500".to_string(),
         start_block: 500,
         end_block: 750,
         hash: B256::new([0xfe; 32]),
@@ -338,8 +340,19 @@ fn serde_roundtrip_milestone() {
     let json = serde_json::to_string(&milestone).unwrap();
     let deserialized: Milestone = serde_json::from_str(&json).unwrap();
 
+    assert_eq!(deserialized.milestone_id, milestone.milestone_id);
     assert_eq!(deserialized.start_block, milestone.start_block);
     assert_eq!(deserialized.end_block, milestone.end_block);
     assert_eq!(deserialized.hash, milestone.hash);
     assert_eq!(deserialized.proposer, milestone.proposer);
 }
+
+/// 17. The committed record of an event does not depend on its time.
+#[test]
+fn state_sync_record_excludes_time() {
+    let event = make_event(42);
+    let later = StateSyncEvent { time: event.time + 3_600, ..event.clone() };
+    assert_eq!(event.record(), later.record());
+    assert_eq!(event.record().record_bytes(), later.record().record_bytes());
+    assert_eq!(event.record().chain_id, "137");
+}